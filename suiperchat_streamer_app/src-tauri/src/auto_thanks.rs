@@ -0,0 +1,140 @@
+//! スーパーチャットへの自動お礼メッセージモジュール
+//!
+//! 配信者が手動でお礼を言えない場合でも投げ銭に反応できるよう、スーパーチャットを配信した直後に
+//! テンプレートから作成したお礼メッセージをシステムメッセージとして全クライアントにブロードキャストします。
+//! テンプレートには `{name}`（表示名）・`{amount}`（金額）・`{coin}`（通貨シンボル）を埋め込めます。
+
+use crate::coin_registry;
+use crate::state::AppState;
+use crate::types::{MessageType, ServerResponse, SuperchatMessage};
+use crate::ws_server::connection_manager::global::get_manager;
+use tauri::Manager;
+
+/// 未登録のコインの金額を表示する際の小数点以下の桁数（SUIと同じ）
+const FALLBACK_DECIMALS: u8 = 9;
+
+/// 表示名のプレースホルダ
+pub const NAME_PLACEHOLDER: &str = "{name}";
+
+/// 金額のプレースホルダ
+pub const AMOUNT_PLACEHOLDER: &str = "{amount}";
+
+/// 通貨シンボルのプレースホルダ
+pub const COIN_PLACEHOLDER: &str = "{coin}";
+
+/// テンプレートの最大文字数
+pub const MAX_TEMPLATE_LEN: usize = 200;
+
+/// ## お礼メッセージのテンプレートを検証する
+///
+/// 前後の空白を除き、空でなく最大文字数以内であることを確認します。
+///
+/// ### Arguments
+/// - `template`: お礼メッセージのテンプレート
+///
+/// ### Returns
+/// - `Result<String, String>`: 前後の空白を除いたテンプレート、無効な場合はエラーメッセージ
+pub fn validate_template(template: &str) -> Result<String, String> {
+    let template = template.trim();
+    if template.is_empty() {
+        return Err("お礼メッセージのテンプレートを入力してください".to_string());
+    }
+    if template.chars().count() > MAX_TEMPLATE_LEN {
+        return Err(format!(
+            "お礼メッセージのテンプレートは{}文字以内で指定してください",
+            MAX_TEMPLATE_LEN
+        ));
+    }
+    Ok(template.to_string())
+}
+
+/// ## テンプレートのプレースホルダを実値に置換する
+///
+/// 表示名に含まれるプレースホルダを再度置換しないよう、表示名は最後に置換します。
+/// 金額はコインの小数点以下の桁数で丸めて表示します。
+///
+/// ### Arguments
+/// - `template`: お礼メッセージのテンプレート
+/// - `name`: 送信者の表示名
+/// - `amount`: 金額
+/// - `coin`: 通貨シンボル
+/// - `decimals`: コインの小数点以下の桁数
+///
+/// ### Returns
+/// - `String`: お礼メッセージ
+pub fn render_thanks(template: &str, name: &str, amount: f64, coin: &str, decimals: u8) -> String {
+    template
+        .replace(
+            AMOUNT_PLACEHOLDER,
+            &coin_registry::format_amount(amount, decimals),
+        )
+        .replace(COIN_PLACEHOLDER, coin)
+        .replace(NAME_PLACEHOLDER, name)
+}
+
+/// ## スーパーチャットへのお礼メッセージをブロードキャストする
+///
+/// テンプレートが設定されていない場合は何もしません。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `superchat_msg`: 配信したスーパーチャット
+pub fn broadcast_thanks(app_handle: &tauri::AppHandle, superchat_msg: &SuperchatMessage) {
+    let app_state = app_handle.state::<AppState>();
+    let template = app_state
+        .auto_thanks_template
+        .lock()
+        .ok()
+        .and_then(|template| template.clone());
+    let Some(template) = template else {
+        return;
+    };
+    let decimals = coin_registry::find_coin(&app_state, &superchat_msg.superchat.coin)
+        .map_or(FALLBACK_DECIMALS, |coin| coin.decimals);
+
+    let response = ServerResponse {
+        message_type: MessageType::System,
+        message: render_thanks(
+            &template,
+            &superchat_msg.display_name,
+            superchat_msg.superchat.amount,
+            &superchat_msg.superchat.coin,
+            decimals,
+        ),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+    match serde_json::to_string(&response) {
+        Ok(json) => get_manager().broadcast(&json),
+        Err(e) => eprintln!("お礼メッセージのシリアライズに失敗しました: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_thanks() {
+        assert_eq!(
+            render_thanks(
+                "{name}さん、{amount} {coin}ありがとう！",
+                "Alice",
+                1.5,
+                "SUI",
+                9
+            ),
+            "Aliceさん、1.5 SUIありがとう！"
+        );
+        assert_eq!(
+            render_thanks("{name}さん感謝！", "{amount}", 10.0, "SUI", 9),
+            "{amount}さん感謝！"
+        );
+        // 浮動小数点の誤差は表示しない
+        assert_eq!(
+            render_thanks("{amount} {coin}", "Bob", 0.1 + 1.1, "USDC", 6),
+            "1.2 USDC"
+        );
+        assert!(validate_template("   ").is_err());
+        assert!(validate_template(&"あ".repeat(MAX_TEMPLATE_LEN + 1)).is_err());
+    }
+}
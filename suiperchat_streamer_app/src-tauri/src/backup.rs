@@ -0,0 +1,206 @@
+//! メッセージ履歴の差分バックアップモジュール
+//!
+//! 差分バックアップは前回のバックアップ以降に受信したメッセージと、関連するセッションを
+//! JSONファイルに保存します。メッセージはシーケンス番号を基準に取得するため、同じ時刻の
+//! メッセージが複数あっても欠損・重複しません。前回のバックアップの `until` を次回の `since` とすることで
+//! 期間が途切れなく連続し、全てのファイルを時系列で適用すれば完全な履歴を復元できます。
+//! `since` がないファイルは最初のメッセージからの全件バックアップです。
+
+use crate::db_models::{Message, Session};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// バックアップファイルの形式バージョン
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// ## 差分バックアップファイル
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncrementalBackup {
+    /// ファイル形式のバージョン
+    pub format_version: u32,
+    /// 差分の開始時刻（全件のバックアップの場合はNone）
+    pub since: Option<DateTime<Utc>>,
+    /// 差分の終了時刻（次回の差分バックアップの開始時刻）
+    pub until: DateTime<Utc>,
+    /// 前回のバックアップの最大のシーケンス番号（全件のバックアップの場合はNone）
+    #[serde(default)]
+    pub since_sequence: Option<i64>,
+    /// 含まれるメッセージの最大のシーケンス番号（メッセージがない場合は `since_sequence`）
+    #[serde(default)]
+    pub until_sequence: Option<i64>,
+    /// バックアップの作成時刻
+    pub created_at: DateTime<Utc>,
+    /// 期間内のメッセージが属する、または期間内に更新されたセッション
+    pub sessions: Vec<Session>,
+    /// 前回のバックアップ以降に受信したメッセージ（受信順）
+    pub messages: Vec<Message>,
+}
+
+/// ## バックアップの欠損期間
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BackupGap {
+    /// 欠損の開始時刻（直前のファイルの終了時刻）
+    pub from: DateTime<Utc>,
+    /// 欠損の終了時刻（直後のファイルの開始時刻）
+    pub to: DateTime<Utc>,
+}
+
+/// ## バックアップファイル群の連続性の検証結果
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupChainReport {
+    /// 検証したファイル（適用順）
+    pub paths: Vec<String>,
+    /// 最初のファイルの開始時刻（全件のバックアップから始まる場合はNone）
+    pub since: Option<DateTime<Utc>>,
+    /// 最後のファイルの終了時刻
+    pub until: Option<DateTime<Utc>>,
+    /// 検出した欠損期間
+    pub gaps: Vec<BackupGap>,
+    /// 全件のバックアップから始まり、欠損なく連続しているかどうか
+    pub complete: bool,
+}
+
+/// ## バックアップファイルを書き出す
+///
+/// ### Arguments
+/// - `path`: 保存先のパス
+/// - `backup`: 保存するバックアップ
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功時は `Ok(())`、失敗時はエラーメッセージ
+pub fn write_backup(path: &Path, backup: &IncrementalBackup) -> Result<(), String> {
+    let json = serde_json::to_string(backup)
+        .map_err(|e| format!("バックアップのシリアライズに失敗しました: {}", e))?;
+    std::fs::write(path, json).map_err(|e| {
+        format!(
+            "バックアップファイルの書き込みに失敗しました ({}): {}",
+            path.display(),
+            e
+        )
+    })
+}
+
+/// ## バックアップファイルを読み込む
+///
+/// ### Arguments
+/// - `path`: バックアップファイルのパス
+///
+/// ### Returns
+/// - `Result<IncrementalBackup, String>`: 読み込んだバックアップ、またはエラーメッセージ
+pub fn read_backup(path: &Path) -> Result<IncrementalBackup, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        format!(
+            "バックアップファイルの読み込みに失敗しました ({}): {}",
+            path.display(),
+            e
+        )
+    })?;
+    let backup: IncrementalBackup = serde_json::from_str(&content).map_err(|e| {
+        format!(
+            "バックアップファイルの解析に失敗しました ({}): {}",
+            path.display(),
+            e
+        )
+    })?;
+
+    if backup.format_version > BACKUP_FORMAT_VERSION {
+        return Err(format!(
+            "未対応のバックアップ形式です ({}): バージョン{}",
+            path.display(),
+            backup.format_version
+        ));
+    }
+    Ok(backup)
+}
+
+/// ## バックアップを適用順に並べ、連続性を検証する
+///
+/// 開始時刻の順（全件のバックアップが先頭）に並べ替え、直前のファイルの終了時刻より
+/// 後から始まるファイルがあれば欠損として報告します。期間の重複は復元時に
+/// 同じメッセージがスキップされるため欠損として扱いません。
+///
+/// ### Arguments
+/// - `backups`: ファイルパスとバックアップの組
+///
+/// ### Returns
+/// - `(Vec<(String, IncrementalBackup)>, BackupChainReport)`: 適用順に並べたバックアップと検証結果
+pub fn order_backup_chain(
+    mut backups: Vec<(String, IncrementalBackup)>,
+) -> (Vec<(String, IncrementalBackup)>, BackupChainReport) {
+    // None（全件）が先頭になる
+    backups.sort_by(|(_, a), (_, b)| a.since.cmp(&b.since).then(a.until.cmp(&b.until)));
+
+    let mut gaps = Vec::new();
+    let mut covered_until: Option<DateTime<Utc>> = None;
+    for (_, backup) in &backups {
+        if let (Some(covered), Some(since)) = (covered_until, backup.since) {
+            if since > covered {
+                gaps.push(BackupGap {
+                    from: covered,
+                    to: since,
+                });
+            }
+        }
+        covered_until =
+            Some(covered_until.map_or(backup.until, |covered| covered.max(backup.until)));
+    }
+
+    let since = backups.first().and_then(|(_, backup)| backup.since);
+    let report = BackupChainReport {
+        paths: backups.iter().map(|(path, _)| path.clone()).collect(),
+        since,
+        until: covered_until,
+        complete: !backups.is_empty() && since.is_none() && gaps.is_empty(),
+        gaps,
+    };
+    (backups, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn backup(since: Option<u32>, until: u32) -> IncrementalBackup {
+        let at = |hour| Utc.with_ymd_and_hms(2024, 1, 1, hour, 0, 0).unwrap();
+        IncrementalBackup {
+            format_version: BACKUP_FORMAT_VERSION,
+            since: since.map(at),
+            until: at(until),
+            since_sequence: None,
+            until_sequence: None,
+            created_at: at(until),
+            sessions: Vec::new(),
+            messages: Vec::new(),
+        }
+    }
+
+    /// 時系列に並べ替えられ、期間の欠損が検出されることを確認
+    #[test]
+    fn test_order_backup_chain_detects_gaps() {
+        let (ordered, report) = order_backup_chain(vec![
+            ("c.json".to_string(), backup(Some(2), 3)),
+            ("full.json".to_string(), backup(None, 1)),
+            ("b.json".to_string(), backup(Some(1), 2)),
+        ]);
+        assert_eq!(ordered[0].0, "full.json");
+        assert_eq!(report.paths, vec!["full.json", "b.json", "c.json"]);
+        assert!(report.gaps.is_empty());
+        assert!(report.complete);
+
+        // 1時〜2時の差分が欠けている
+        let (_, report) = order_backup_chain(vec![
+            ("full.json".to_string(), backup(None, 1)),
+            ("c.json".to_string(), backup(Some(2), 3)),
+        ]);
+        assert_eq!(report.gaps.len(), 1);
+        assert_eq!(report.gaps[0].to, backup(Some(2), 3).since.unwrap());
+        assert!(!report.complete);
+
+        // 全件のバックアップから始まらない場合は不完全
+        let (_, report) = order_backup_chain(vec![("b.json".to_string(), backup(Some(1), 2))]);
+        assert!(report.gaps.is_empty());
+        assert!(!report.complete);
+    }
+}
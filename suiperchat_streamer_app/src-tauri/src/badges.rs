@@ -0,0 +1,296 @@
+//! 視聴者の称号バッジモジュール
+//!
+//! 視聴者プロフィール（累計スーパーチャット額・回数、配信参加回数）に基づいて、
+//! 配信者が設定した条件を満たす称号バッジを判定します。
+//! 判定結果はチャット・スーパーチャットのブロードキャストに含めて視聴者画面やOBSで表示されます。
+//! メッセージごとにデータベースへ問い合わせないよう、視聴者プロフィールは一定時間キャッシュします。
+
+use crate::database;
+use crate::db_models::ViewerProfile;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+use sqlx::Error as SqlxError;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+/// 視聴者プロフィールのキャッシュの有効期間
+pub const VIEWER_PROFILE_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// キャッシュする視聴者プロフィールの最大件数
+pub const MAX_VIEWER_PROFILE_CACHE_ENTRIES: usize = 10_000;
+
+/// 設定可能な称号の最大数
+pub const MAX_BADGE_RULES: usize = 20;
+
+/// 称号名の最大文字数
+pub const MAX_BADGE_NAME_LENGTH: usize = 20;
+
+/// ## 称号の付与条件
+///
+/// 全ての条件を満たした視聴者に称号を付与します。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BadgeRule {
+    /// 称号名
+    pub name: String,
+    /// 称号のグループ（同じグループの称号は、条件を満たすもののうち最後に定義されたものだけを付与）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// 必要なSUI建てのスーパーチャット累計額
+    #[serde(default)]
+    pub min_total_amount: f64,
+    /// 必要なスーパーチャットの累計回数
+    #[serde(default)]
+    pub min_superchat_count: i64,
+    /// 必要な配信参加回数
+    #[serde(default)]
+    pub min_session_count: i64,
+}
+
+impl BadgeRule {
+    /// ## 視聴者が付与条件を満たすか判定する
+    ///
+    /// ### Arguments
+    /// - `profile`: 視聴者プロフィール
+    ///
+    /// ### Returns
+    /// - `bool`: 全ての条件を満たす場合は `true`
+    pub fn matches(&self, profile: &ViewerProfile) -> bool {
+        profile.total_superchat_amount >= self.min_total_amount
+            && profile.superchat_count >= self.min_superchat_count
+            && profile.session_count >= self.min_session_count
+    }
+}
+
+/// ## 称号バッジの設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BadgeConfig {
+    /// 称号バッジを付与するかどうか
+    pub enabled: bool,
+    /// 称号の付与条件（定義順に判定）
+    pub rules: Vec<BadgeRule>,
+}
+
+impl Default for BadgeConfig {
+    fn default() -> Self {
+        let supporter = |name: &str, min_total_amount: f64| BadgeRule {
+            name: name.to_string(),
+            group: Some("supporter".to_string()),
+            min_total_amount,
+            min_superchat_count: 1,
+            min_session_count: 0,
+        };
+        Self {
+            enabled: true,
+            rules: vec![
+                supporter("ブロンズサポーター", 1.0),
+                supporter("シルバーサポーター", 10.0),
+                supporter("ゴールドサポーター", 100.0),
+                BadgeRule {
+                    name: "常連".to_string(),
+                    group: None,
+                    min_total_amount: 0.0,
+                    min_superchat_count: 0,
+                    min_session_count: 5,
+                },
+            ],
+        }
+    }
+}
+
+/// ## 視聴者に付与する称号を判定する
+///
+/// ### Arguments
+/// - `profile`: 視聴者プロフィール
+/// - `rules`: 称号の付与条件
+///
+/// ### Returns
+/// - `Vec<String>`: 付与する称号名（定義順）
+pub fn determine_viewer_badges(profile: &ViewerProfile, rules: &[BadgeRule]) -> Vec<String> {
+    let matched: Vec<&BadgeRule> = rules.iter().filter(|rule| rule.matches(profile)).collect();
+    matched
+        .iter()
+        .enumerate()
+        .filter(|(index, rule)| {
+            // 同じグループで後に条件を満たす称号がある場合は、上位の称号だけを残す
+            rule.group.as_ref().map_or(true, |group| {
+                !matched[index + 1..]
+                    .iter()
+                    .any(|later| later.group.as_ref() == Some(group))
+            })
+        })
+        .map(|(_, rule)| rule.name.clone())
+        .collect()
+}
+
+/// ## 視聴者プロフィールのキャッシュ
+///
+/// プロフィールが存在しない（未集計・オプトアウト済み）ことも記録し、再問い合わせを避けます。
+#[derive(Debug, Default)]
+pub struct ViewerProfileCache {
+    /// ウォレットアドレスごとの取得時刻とプロフィール
+    entries: HashMap<String, (Instant, Option<ViewerProfile>)>,
+}
+
+impl ViewerProfileCache {
+    /// 有効期間内のプロフィールを取得する（キャッシュされていない場合はNone）
+    fn get(&self, wallet_address: &str, now: Instant) -> Option<Option<&ViewerProfile>> {
+        self.entries
+            .get(wallet_address)
+            .filter(|(cached_at, _)| now.duration_since(*cached_at) < VIEWER_PROFILE_CACHE_TTL)
+            .map(|(_, profile)| profile.as_ref())
+    }
+
+    /// プロフィールをキャッシュする
+    fn insert(&mut self, wallet_address: &str, profile: Option<ViewerProfile>, now: Instant) {
+        if self.entries.len() >= MAX_VIEWER_PROFILE_CACHE_ENTRIES {
+            self.entries.retain(|_, (cached_at, _)| {
+                now.duration_since(*cached_at) < VIEWER_PROFILE_CACHE_TTL
+            });
+            if self.entries.len() >= MAX_VIEWER_PROFILE_CACHE_ENTRIES {
+                self.entries.clear();
+            }
+        }
+        self.entries
+            .insert(wallet_address.to_string(), (now, profile));
+    }
+
+    /// ## 視聴者のキャッシュを破棄する
+    ///
+    /// ### Arguments
+    /// - `wallet_address`: 対象のウォレットアドレス
+    pub fn invalidate(&mut self, wallet_address: &str) {
+        self.entries.remove(wallet_address);
+    }
+}
+
+/// 現在の設定で、キャッシュ済みのプロフィールから称号を判定する
+fn badges_from_cache(app_state: &AppState, wallet_address: &str) -> Option<Vec<String>> {
+    let config = app_state.badge_config.lock().ok()?;
+    if !config.enabled || config.rules.is_empty() {
+        return Some(Vec::new());
+    }
+    let cache = app_state.viewer_profile_cache.lock().ok()?;
+    let profile = cache.get(wallet_address, Instant::now())?;
+    Some(profile.map_or_else(Vec::new, |profile| {
+        determine_viewer_badges(profile, &config.rules)
+    }))
+}
+
+/// ## キャッシュ済みの視聴者プロフィールから称号を取得する
+///
+/// データベースへは問い合わせません。キャッシュされていない場合は、
+/// 次のメッセージに備えてバックグラウンドでプロフィールを読み込みます。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `pool`: SQLiteデータベース接続プール
+/// - `wallet_address`: 視聴者のウォレットアドレス
+///
+/// ### Returns
+/// - `Vec<String>`: 付与する称号名（キャッシュされていない場合は空）
+pub fn cached_viewer_badges(
+    app_handle: &tauri::AppHandle,
+    pool: &SqlitePool,
+    wallet_address: &str,
+) -> Vec<String> {
+    if let Some(badges) = badges_from_cache(&app_handle.state::<AppState>(), wallet_address) {
+        return badges;
+    }
+
+    let app_handle = app_handle.clone();
+    let pool = pool.clone();
+    let wallet_address = wallet_address.to_string();
+    tauri::async_runtime::spawn(async move {
+        viewer_badges(&app_handle, &pool, &wallet_address).await;
+    });
+    Vec::new()
+}
+
+/// ## 視聴者の称号を取得する
+///
+/// プロフィールがキャッシュされていない場合はデータベースから読み込んでキャッシュします。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `pool`: SQLiteデータベース接続プール
+/// - `wallet_address`: 視聴者のウォレットアドレス
+///
+/// ### Returns
+/// - `Vec<String>`: 付与する称号名（取得に失敗した場合は空）
+pub async fn viewer_badges(
+    app_handle: &tauri::AppHandle,
+    pool: &SqlitePool,
+    wallet_address: &str,
+) -> Vec<String> {
+    let app_state = app_handle.state::<AppState>();
+    if let Some(badges) = badges_from_cache(&app_state, wallet_address) {
+        return badges;
+    }
+
+    let profile = match database::get_viewer_profile(pool, wallet_address).await {
+        Ok(profile) => Some(profile),
+        Err(SqlxError::RowNotFound) => None,
+        Err(e) => {
+            eprintln!("称号判定用の視聴者プロフィールの取得に失敗: {}", e);
+            return Vec::new();
+        }
+    };
+    if let Ok(mut cache) = app_state.viewer_profile_cache.lock() {
+        cache.insert(wallet_address, profile, Instant::now());
+    }
+    badges_from_cache(&app_state, wallet_address).unwrap_or_default()
+}
+
+/// ## 視聴者プロフィールのキャッシュを破棄する
+///
+/// 視聴者の累計実績を更新した後に呼び出し、次のメッセージで最新の称号を判定させます。
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+/// - `wallet_address`: 対象のウォレットアドレス
+pub fn invalidate_viewer_profile(app_state: &AppState, wallet_address: &str) {
+    if let Ok(mut cache) = app_state.viewer_profile_cache.lock() {
+        cache.invalidate(wallet_address);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(
+        total_superchat_amount: f64,
+        superchat_count: i64,
+        session_count: i64,
+    ) -> ViewerProfile {
+        ViewerProfile {
+            wallet_address: "0xviewer".to_string(),
+            display_name: "視聴者".to_string(),
+            first_seen_at: "2024-01-01T00:00:00+00:00".to_string(),
+            last_seen_at: "2024-01-01T00:00:00+00:00".to_string(),
+            total_superchat_amount,
+            superchat_count,
+            message_count: superchat_count,
+            opted_out: false,
+            session_count,
+        }
+    }
+
+    #[test]
+    fn test_determine_viewer_badges() {
+        let rules = BadgeConfig::default().rules;
+
+        assert!(determine_viewer_badges(&profile(0.0, 0, 1), &rules).is_empty());
+        assert_eq!(
+            determine_viewer_badges(&profile(5.0, 2, 1), &rules),
+            vec!["ブロンズサポーター"]
+        );
+        // 同じグループでは最上位の称号だけを付与し、他のグループの称号は併せて付与する
+        assert_eq!(
+            determine_viewer_badges(&profile(150.0, 10, 8), &rules),
+            vec!["ゴールドサポーター", "常連"]
+        );
+    }
+}
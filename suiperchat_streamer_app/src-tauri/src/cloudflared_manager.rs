@@ -1,11 +1,70 @@
 use std::path::{Path, PathBuf};
 use std::fs;
 use std::io::{self, Write};
-use tauri::{AppHandle, Manager};
+use futures_util::StreamExt;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
 use thiserror::Error;
-use tracing::{info, error};
+use tracing::info;
 use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256};
 use tar::Archive;
+use crate::state::AppState;
+
+/// ダウンロードするcloudflaredのバージョンを指定する環境変数名（例: "2024.12.2"、未設定の場合は最新版）
+pub const CLOUDFLARED_VERSION_ENV: &str = "CLOUDFLARED_VERSION";
+
+/// ダウンロードしたファイルの期待するSHA256（16進数）を指定する環境変数名
+pub const CLOUDFLARED_SHA256_ENV: &str = "CLOUDFLARED_SHA256";
+
+/// チェックサムの検証をスキップする環境変数名（"1" または "true" でスキップ）
+pub const CLOUDFLARED_SKIP_CHECKSUM_ENV: &str = "CLOUDFLARED_SKIP_CHECKSUM";
+
+/// 既知のリリースファイルのSHA256（バージョン, ファイル名, SHA256）
+///
+/// バージョンを固定する場合は、リリースノートに記載されたチェックサムをここに追加するか、
+/// `set_cloudflared_version` コマンドまたは環境変数 `CLOUDFLARED_SHA256` で指定してください。
+const KNOWN_CHECKSUMS: &[(&str, &str, &str)] = &[];
+
+/// Content-Lengthが不明な場合に進捗イベントを発行するバイト数の間隔
+const PROGRESS_EMIT_INTERVAL_BYTES: u64 = 512 * 1024;
+
+/// ダウンロードするファイルの最大サイズ（cloudflaredのバイナリは数十MB程度）
+const MAX_DOWNLOAD_BYTES: u64 = 256 * 1024 * 1024;
+
+/// ## cloudflaredのダウンロード開始イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct CloudflaredDownloadStarted {
+    /// ダウンロード元のURL
+    pub url: String,
+    /// ダウンロードするファイルのサイズ（Content-Lengthが不明な場合はNone）
+    pub total_bytes: Option<u64>,
+}
+
+/// ## cloudflaredのダウンロード進捗イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct CloudflaredDownloadProgress {
+    /// ダウンロード済みのバイト数
+    pub downloaded_bytes: u64,
+    /// ダウンロードするファイルのサイズ（Content-Lengthが不明な場合はNone）
+    pub total_bytes: Option<u64>,
+    /// 進捗率（0-100、Content-Lengthが不明な場合はNone）
+    pub percent: Option<f64>,
+}
+
+/// ## cloudflaredのダウンロード完了イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct CloudflaredDownloadCompleted {
+    /// インストールしたバイナリのパス
+    pub path: PathBuf,
+}
+
+/// ## cloudflaredのダウンロード失敗イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct CloudflaredDownloadFailed {
+    /// エラーメッセージ
+    pub error: String,
+}
 
 #[derive(Error, Debug)]
 pub enum CloudflaredManagerError {
@@ -23,10 +82,21 @@ pub enum CloudflaredManagerError {
     
     #[error("Cloudflared binary not found at expected path")]
     BinaryNotFound,
+    
+    #[error("Invalid cloudflared version: {0}")]
+    InvalidVersion(String),
+    
+    #[error("Invalid cloudflared checksum: {0}")]
+    InvalidChecksum(String),
+    
+    #[error("Checksum mismatch for cloudflared (expected {expected}, actual {actual})")]
+    ChecksumMismatch { expected: String, actual: String },
+    
+    #[error("No known checksum for cloudflared {0}. Specify the sha256 with the version, or set {CLOUDFLARED_SHA256_ENV} or {CLOUDFLARED_SKIP_CHECKSUM_ENV}=1")]
+    ChecksumUnavailable(String),
 }
 
 pub struct CloudflaredManager {
-    #[allow(dead_code)]
     app_handle: AppHandle,
     binary_path: PathBuf,
 }
@@ -53,13 +123,34 @@ impl CloudflaredManager {
     }
     
     pub async fn ensure_cloudflared(&self) -> Result<PathBuf, CloudflaredManagerError> {
-        if self.binary_path.exists() {
+        let version = self.resolve_version()?;
+        
+        // 既存のバイナリは、指定したバージョン（最新版の場合は記録なし）と一致する場合のみ使用する
+        let installed_version = fs::read_to_string(self.version_marker_path()).ok();
+        let is_expected_version = installed_version.as_deref().map(str::trim) == version.as_deref();
+        if self.binary_path.exists() && is_expected_version {
             info!("Cloudflared binary found at: {:?}", self.binary_path);
             return Ok(self.binary_path.clone());
         }
         
-        info!("Cloudflared binary not found, downloading...");
-        self.download_cloudflared().await?;
+        info!("Cloudflared binary not found or version changed, downloading...");
+        match self.download_cloudflared(version.as_deref()).await {
+            Ok(()) => self.emit_event(
+                "cloudflared_download_completed",
+                CloudflaredDownloadCompleted {
+                    path: self.binary_path.clone(),
+                },
+            ),
+            Err(e) => {
+                self.emit_event(
+                    "cloudflared_download_failed",
+                    CloudflaredDownloadFailed {
+                        error: e.to_string(),
+                    },
+                );
+                return Err(e);
+            }
+        }
         
         if !self.binary_path.exists() {
             return Err(CloudflaredManagerError::BinaryNotFound);
@@ -67,9 +158,13 @@ impl CloudflaredManager {
         
         Ok(self.binary_path.clone())
     }
-    
-    async fn download_cloudflared(&self) -> Result<(), CloudflaredManagerError> {
-        let download_url = self.get_download_url()?;
+
+    async fn download_cloudflared(
+        &self,
+        version: Option<&str>,
+    ) -> Result<(), CloudflaredManagerError> {
+        let filename = Self::get_platform_filename()?;
+        let download_url = self.get_download_url(version)?;
         
         // バイナリディレクトリを作成
         if let Some(parent) = self.binary_path.parent() {
@@ -89,10 +184,55 @@ impl CloudflaredManager {
             ));
         }
         
-        let bytes = response
-            .bytes()
-            .await
-            .map_err(|e| CloudflaredManagerError::DownloadFailed(e.to_string()))?;
+        // フリーズしたと誤解されないよう、チャンク単位で読み込んで進捗を通知する
+        let total_bytes = response.content_length();
+        self.emit_event(
+            "cloudflared_download_started",
+            CloudflaredDownloadStarted {
+                url: download_url.clone(),
+                total_bytes,
+            },
+        );
+        
+        // Content-Lengthはサーバーの申告値のため、上限以下に抑えて確保する
+        let mut bytes =
+            Vec::with_capacity(total_bytes.unwrap_or(0).min(MAX_DOWNLOAD_BYTES) as usize);
+        let mut stream = response.bytes_stream();
+        let mut last_emitted_percent = None;
+        let mut last_emitted_bytes = 0;
+        while let Some(chunk) = stream.next().await {
+            let chunk =
+                chunk.map_err(|e| CloudflaredManagerError::DownloadFailed(e.to_string()))?;
+            if bytes.len() as u64 + chunk.len() as u64 > MAX_DOWNLOAD_BYTES {
+                return Err(CloudflaredManagerError::DownloadFailed(format!(
+                    "File exceeds {} bytes",
+                    MAX_DOWNLOAD_BYTES
+                )));
+            }
+            bytes.extend_from_slice(&chunk);
+            
+            let downloaded_bytes = bytes.len() as u64;
+            let percent = total_bytes
+                .filter(|total| *total > 0)
+                .map(|total| (downloaded_bytes as f64 / total as f64 * 100.0).min(100.0));
+            // イベントが大量に発行されないよう、進捗率が1%以上変化した場合のみ通知する
+            let should_emit = match percent {
+                Some(percent) => last_emitted_percent != Some(percent.floor()),
+                None => downloaded_bytes - last_emitted_bytes >= PROGRESS_EMIT_INTERVAL_BYTES,
+            };
+            if should_emit {
+                last_emitted_percent = percent.map(f64::floor);
+                last_emitted_bytes = downloaded_bytes;
+                self.emit_event(
+                    "cloudflared_download_progress",
+                    CloudflaredDownloadProgress {
+                        downloaded_bytes,
+                        total_bytes,
+                        percent,
+                    },
+                );
+            }
+        }
         
         // macOSの場合はtar.gzを展開、その他は直接保存
         if cfg!(target_os = "macos") && download_url.ends_with(".tgz") {
@@ -113,10 +253,77 @@ impl CloudflaredManager {
                 .map_err(|_| CloudflaredManagerError::PermissionsFailed)?;
         }
         
+        // 改ざん・破損したバイナリを実行しないよう、不一致の場合は削除する
+        if let Err(e) = self.verify_download(&bytes, version, filename) {
+            let _ = fs::remove_file(&self.binary_path);
+            let _ = fs::remove_file(self.version_marker_path());
+            return Err(e);
+        }
+        
+        // 固定したバージョンを記録（最新版の場合は記録を削除）
+        match version {
+            Some(version) => fs::write(self.version_marker_path(), version)?,
+            None => {
+                let _ = fs::remove_file(self.version_marker_path());
+            }
+        }
+        
         info!("Cloudflared downloaded successfully to: {:?}", self.binary_path);
         Ok(())
     }
     
+    /// ダウンロードの状態をフロントエンドに通知する
+    fn emit_event<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        if let Err(e) = self.app_handle.emit(event, payload) {
+            eprintln!("{} イベントの発行に失敗しました: {}", event, e);
+        }
+    }
+    
+    /// ダウンロードしたファイルのSHA256を期待値と照合する
+    ///
+    /// 期待値は `AppState` の設定、環境変数 `CLOUDFLARED_SHA256`、既知のチェックサムの順に解決します。
+    fn verify_download(
+        &self,
+        bytes: &[u8],
+        version: Option<&str>,
+        filename: &str,
+    ) -> Result<(), CloudflaredManagerError> {
+        if is_env_flag_set(CLOUDFLARED_SKIP_CHECKSUM_ENV) {
+            info!(
+                "Skipping cloudflared checksum verification (sha256: {:x})",
+                Sha256::digest(bytes)
+            );
+            return Ok(());
+        }
+        
+        let expected = self
+            .app_handle
+            .try_state::<AppState>()
+            .and_then(|state| state.cloudflared_sha256.lock().ok()?.clone())
+            .or_else(|| expected_checksum(version, filename));
+        verify_checksum(bytes, expected.as_deref(), version, filename)
+    }
+    
+    /// ダウンロードするバージョンを解決する（Noneの場合は最新版）
+    ///
+    /// `AppState` の設定、環境変数 `CLOUDFLARED_VERSION` の順に参照します。
+    fn resolve_version(&self) -> Result<Option<String>, CloudflaredManagerError> {
+        let from_state = self
+            .app_handle
+            .try_state::<AppState>()
+            .and_then(|state| state.cloudflared_version.lock().ok()?.clone());
+        let version = from_state.or_else(|| std::env::var(CLOUDFLARED_VERSION_ENV).ok());
+        match version.as_deref().map(str::trim) {
+            None | Some("") | Some("latest") => Ok(None),
+            Some(version) => validate_version(version).map(Some),
+        }
+    }
+    
+    /// インストール済みのバージョンを記録するファイルのパス
+    fn version_marker_path(&self) -> PathBuf {
+        self.binary_path.with_extension("version")
+    }
+    
     fn extract_tar_gz(&self, bytes: &[u8]) -> Result<(), CloudflaredManagerError> {
         info!("Extracting cloudflared tar.gz file...");
         
@@ -147,9 +354,22 @@ impl CloudflaredManager {
         ))
     }
     
-    fn get_download_url(&self) -> Result<String, CloudflaredManagerError> {
-        let base_url = "https://github.com/cloudflare/cloudflared/releases/latest/download";
+    fn get_download_url(&self, version: Option<&str>) -> Result<String, CloudflaredManagerError> {
+        let base_url = match version {
+            Some(version) => format!(
+                "https://github.com/cloudflare/cloudflared/releases/download/{}",
+                version
+            ),
+            None => {
+                "https://github.com/cloudflare/cloudflared/releases/latest/download".to_string()
+            }
+        };
         
+        Ok(format!("{}/{}", base_url, Self::get_platform_filename()?))
+    }
+    
+    /// 実行中のプラットフォーム向けのリリースファイル名を取得する
+    fn get_platform_filename() -> Result<&'static str, CloudflaredManagerError> {
         let filename = if cfg!(target_os = "windows") {
             if cfg!(target_arch = "x86_64") {
                 "cloudflared-windows-amd64.exe"
@@ -179,10 +399,141 @@ impl CloudflaredManager {
             return Err(CloudflaredManagerError::UnsupportedPlatform);
         };
         
-        Ok(format!("{}/{}", base_url, filename))
+        Ok(filename)
     }
     
     pub fn get_binary_path(&self) -> &Path {
         &self.binary_path
     }
-}
\ No newline at end of file
+}
+
+/// バージョン文字列を検証する（URLに埋め込むため、英数字と "." "-" のみを許可）
+pub fn validate_version(version: &str) -> Result<String, CloudflaredManagerError> {
+    let version = version.trim();
+    if version.is_empty()
+        || !version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+    {
+        return Err(CloudflaredManagerError::InvalidVersion(version.to_string()));
+    }
+    Ok(version.to_string())
+}
+
+/// SHA256の文字列を検証する（16進数64文字、小文字に正規化）
+pub fn validate_sha256(sha256: &str) -> Result<String, CloudflaredManagerError> {
+    let sha256 = sha256.trim().to_ascii_lowercase();
+    if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(CloudflaredManagerError::InvalidChecksum(sha256));
+    }
+    Ok(sha256)
+}
+
+/// 固定したバージョンの期待するSHA256が既知か（環境変数 `CLOUDFLARED_SHA256` または既知のチェックサム）
+pub fn has_known_checksum(version: &str) -> bool {
+    CloudflaredManager::get_platform_filename()
+        .is_ok_and(|filename| expected_checksum(Some(version), filename).is_some())
+}
+
+/// 環境変数 `CLOUDFLARED_SHA256`、既知のチェックサムの順に期待するSHA256を解決する
+fn expected_checksum(version: Option<&str>, filename: &str) -> Option<String> {
+    std::env::var(CLOUDFLARED_SHA256_ENV)
+        .ok()
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty())
+        .or_else(|| {
+            let version = version?;
+            KNOWN_CHECKSUMS
+                .iter()
+                .find(|(v, f, _)| *v == version && *f == filename)
+                .map(|(_, _, sha256)| sha256.to_string())
+        })
+}
+
+/// ダウンロードしたファイルのSHA256を期待値と照合する
+///
+/// 最新版をダウンロードした場合は期待値が定まらないため、期待値の指定がなければ検証しません。
+/// 固定したバージョンで期待値が不明な場合はエラーにします。
+fn verify_checksum(
+    bytes: &[u8],
+    expected: Option<&str>,
+    version: Option<&str>,
+    filename: &str,
+) -> Result<(), CloudflaredManagerError> {
+    let actual = format!("{:x}", Sha256::digest(bytes));
+    let Some(expected) = expected else {
+        return match version {
+            Some(version) => Err(CloudflaredManagerError::ChecksumUnavailable(format!(
+                "{} ({})",
+                version, filename
+            ))),
+            None => {
+                info!("No expected checksum for the latest cloudflared, skipping verification (sha256: {})", actual);
+                Ok(())
+            }
+        };
+    };
+    
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(CloudflaredManagerError::ChecksumMismatch {
+            expected: expected.to_string(),
+            actual,
+        });
+    }
+    info!("Cloudflared checksum verified: {}", actual);
+    Ok(())
+}
+
+/// 環境変数がフラグとして有効か（"1" または "true"）
+fn is_env_flag_set(name: &str) -> bool {
+    std::env::var(name)
+        .is_ok_and(|value| matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// バージョン文字列の検証のテスト
+    #[test]
+    fn test_validate_version() {
+        assert_eq!(validate_version(" 2024.12.2 ").unwrap(), "2024.12.2");
+        assert!(validate_version("2025.1.0-rc1").is_ok());
+        assert!(validate_version("").is_err());
+        assert!(validate_version("../latest").is_err());
+        assert!(validate_version("2024.12.2?x=1").is_err());
+    }
+
+    /// SHA256の文字列の検証のテスト
+    #[test]
+    fn test_validate_sha256() {
+        let sha256 = format!("{:x}", Sha256::digest(b"cloudflared"));
+        assert_eq!(
+            validate_sha256(&sha256.to_ascii_uppercase()).unwrap(),
+            sha256
+        );
+        assert!(validate_sha256(&sha256[1..]).is_err());
+        assert!(validate_sha256(&"g".repeat(64)).is_err());
+    }
+
+    /// チェックサムの照合のテスト
+    #[test]
+    fn test_verify_checksum() {
+        let bytes = b"cloudflared";
+        let sha256 = format!("{:x}", Sha256::digest(bytes));
+        let filename = "cloudflared-linux-amd64";
+
+        assert!(verify_checksum(bytes, Some(&sha256), Some("2024.12.2"), filename).is_ok());
+        assert!(verify_checksum(bytes, Some(&sha256.to_ascii_uppercase()), None, filename).is_ok());
+        assert!(matches!(
+            verify_checksum(b"tampered", Some(&sha256), Some("2024.12.2"), filename),
+            Err(CloudflaredManagerError::ChecksumMismatch { .. })
+        ));
+        // 固定したバージョンは期待値が必要、最新版は期待値がなければ検証しない
+        assert!(matches!(
+            verify_checksum(bytes, None, Some("2024.12.2"), filename),
+            Err(CloudflaredManagerError::ChecksumUnavailable(_))
+        ));
+        assert!(verify_checksum(bytes, None, None, filename).is_ok());
+    }
+}
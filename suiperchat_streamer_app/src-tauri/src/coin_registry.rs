@@ -0,0 +1,305 @@
+//! 対応コインのレジストリモジュール
+//!
+//! スーパーチャットに使用できるコインのシンボル・型引数・小数点以下の桁数を管理します。
+//! 視聴者フロントは `get_supported_coins` で一覧を取得するため、新しいコインへの対応は
+//! サーバー側のレジストリを更新するだけで済みます。
+
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+
+/// 設定可能な小数点以下の桁数の上限
+pub const MAX_COIN_DECIMALS: u8 = 18;
+
+/// ## 対応コインの情報
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoinInfo {
+    /// 通貨シンボル (例: "SUI")
+    pub symbol: String,
+    /// コインの型引数 (例: "0x2::sui::SUI")
+    pub type_arg: String,
+    /// 小数点以下の桁数
+    pub decimals: u8,
+}
+
+/// ## デフォルトの対応コイン（SUI・USDC）を取得する
+///
+/// ### Returns
+/// - `Vec<CoinInfo>`: メインネットのSUIとネイティブUSDC
+pub fn default_coins() -> Vec<CoinInfo> {
+    vec![
+        CoinInfo {
+            symbol: "SUI".to_string(),
+            type_arg: "0x2::sui::SUI".to_string(),
+            decimals: 9,
+        },
+        CoinInfo {
+            symbol: "USDC".to_string(),
+            type_arg:
+                "0xdba34672e30cb065b1f93e3ab55318768fd6fef66c15942c9f7cb846e2f900e7::usdc::USDC"
+                    .to_string(),
+            decimals: 6,
+        },
+    ]
+}
+
+/// ## シンボルから対応コインを検索する
+///
+/// シンボルの大文字・小文字は区別しません。
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+/// - `symbol`: 通貨シンボル
+///
+/// ### Returns
+/// - `Option<CoinInfo>`: 登録済みのコイン（未登録の場合はNone）
+pub fn find_coin(app_state: &AppState, symbol: &str) -> Option<CoinInfo> {
+    let coins = app_state.supported_coins.lock().ok()?;
+    coins
+        .iter()
+        .find(|coin| coin.symbol.eq_ignore_ascii_case(symbol.trim()))
+        .cloned()
+}
+
+/// ## 型引数から対応コインを検索する
+///
+/// 同じシンボルの偽コインを区別するため、シンボルではなく型引数全体で照合します。
+/// アドレス部分の大文字・小文字と先頭の0の有無は区別しません。
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+/// - `coin_type`: コインの型 (例: "0x2::sui::SUI")
+///
+/// ### Returns
+/// - `Option<CoinInfo>`: 登録済みのコイン（未登録の場合はNone）
+pub fn find_coin_by_type(app_state: &AppState, coin_type: &str) -> Option<CoinInfo> {
+    let coins = app_state.supported_coins.lock().ok()?;
+    coins
+        .iter()
+        .find(|coin| is_same_coin_type(&coin.type_arg, coin_type))
+        .cloned()
+}
+
+/// ## 2つのコインの型が同じか判定する
+///
+/// RPCは `0x2::sui::SUI` を `0x000…02::sui::SUI` の形式で返すことがあるため、
+/// アドレス部分は先頭の0を除いて大文字・小文字を区別せずに比較します。
+///
+/// ### Arguments
+/// - `a`: コインの型
+/// - `b`: コインの型
+///
+/// ### Returns
+/// - `bool`: 同じコインの型の場合はtrue
+pub fn is_same_coin_type(a: &str, b: &str) -> bool {
+    let split = |coin_type: &str| {
+        let coin_type = coin_type.trim();
+        let (address, rest) = coin_type.split_once("::").unwrap_or((coin_type, ""));
+        let address = address.strip_prefix("0x").unwrap_or(address);
+        let address = address.trim_start_matches('0').to_ascii_lowercase();
+        (address, rest.to_string())
+    };
+    split(a) == split(b)
+}
+
+/// ## スーパーチャットを受け付けるコインか判定する
+///
+/// 受け付けるコインが制限されていない場合は常に受け付けます。シンボルの大文字・小文字は区別しません。
+///
+/// ### Arguments
+/// - `accepted_coins`: 受け付けるコインのシンボル（制限しない場合はNone）
+/// - `symbol`: 通貨シンボル
+///
+/// ### Returns
+/// - `bool`: 受け付ける場合はtrue
+pub fn is_coin_accepted(accepted_coins: Option<&[String]>, symbol: &str) -> bool {
+    match accepted_coins {
+        Some(coins) => coins
+            .iter()
+            .any(|coin| coin.eq_ignore_ascii_case(symbol.trim())),
+        None => true,
+    }
+}
+
+/// ## 受信した金額を表示単位に変換する
+///
+/// 単位の指定がない場合、または単位が通貨シンボルと一致する場合は表示単位とみなしてそのまま返します。
+/// `"mist"`（SUIのみ）・`"base"` の場合は最小単位とみなし、小数点以下の桁数で割って変換します。
+///
+/// ### Arguments
+/// - `amount`: 受信した金額
+/// - `unit`: 金額の単位（未指定の場合はNone）
+/// - `coin`: 送金に使用されたコイン（未登録の場合はNone）
+///
+/// ### Returns
+/// - `Result<f64, String>`: 表示単位の金額、単位やコインが不明で変換できない場合はエラーメッセージ
+pub fn to_display_amount(
+    amount: f64,
+    unit: Option<&str>,
+    coin: Option<&CoinInfo>,
+) -> Result<f64, String> {
+    let Some(unit) = unit.map(str::trim).filter(|unit| !unit.is_empty()) else {
+        return Ok(amount);
+    };
+    let Some(coin) = coin else {
+        return Err(format!(
+            "未登録のコインのため金額の単位を変換できません: {}",
+            unit
+        ));
+    };
+
+    if unit.eq_ignore_ascii_case(&coin.symbol) {
+        return Ok(amount);
+    }
+    let is_base_unit = unit.eq_ignore_ascii_case("base")
+        || (unit.eq_ignore_ascii_case("mist") && coin.symbol.eq_ignore_ascii_case("SUI"));
+    if is_base_unit {
+        return Ok(amount / 10f64.powi(i32::from(coin.decimals)));
+    }
+    Err(format!("未知の金額の単位です ({}): {}", coin.symbol, unit))
+}
+
+/// ## 表示単位の金額を表示用の文字列に変換する
+///
+/// 浮動小数点の誤差（例: `1.2000000000000002`）を表示しないよう、コインの小数点以下の桁数で丸め、
+/// 末尾の0と小数点を取り除きます。
+///
+/// ### Arguments
+/// - `amount`: 表示単位の金額
+/// - `decimals`: コインの小数点以下の桁数
+///
+/// ### Returns
+/// - `String`: 表示用の金額 (例: "1.2")
+pub fn format_amount(amount: f64, decimals: u8) -> String {
+    let formatted = format!("{:.*}", usize::from(decimals), amount);
+    if !formatted.contains('.') {
+        return formatted;
+    }
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// ## 対応コインの一覧を検証する
+///
+/// ### Arguments
+/// - `coins`: 検証する対応コインの一覧
+///
+/// ### Returns
+/// - `Result<(), String>`: 不正な項目がある場合はエラーメッセージ
+pub fn validate_coins(coins: &[CoinInfo]) -> Result<(), String> {
+    if coins.is_empty() {
+        return Err("対応コインを1つ以上指定してください".to_string());
+    }
+
+    for (index, coin) in coins.iter().enumerate() {
+        if coin.symbol.trim().is_empty() {
+            return Err("通貨シンボルが空です".to_string());
+        }
+        if !is_valid_type_arg(&coin.type_arg) {
+            return Err(format!(
+                "コインの型引数の形式が不正です ({}): {}",
+                coin.symbol, coin.type_arg
+            ));
+        }
+        if coin.decimals > MAX_COIN_DECIMALS {
+            return Err(format!(
+                "小数点以下の桁数は{}以下で指定してください ({}): {}",
+                MAX_COIN_DECIMALS, coin.symbol, coin.decimals
+            ));
+        }
+        if coins[..index]
+            .iter()
+            .any(|other| other.symbol.eq_ignore_ascii_case(&coin.symbol))
+        {
+            return Err(format!("通貨シンボルが重複しています: {}", coin.symbol));
+        }
+    }
+    Ok(())
+}
+
+/// 型引数が `0x<アドレス>::<モジュール>::<型名>` の形式か判定する
+fn is_valid_type_arg(type_arg: &str) -> bool {
+    let mut parts = type_arg.split("::");
+    let (Some(address), Some(module), Some(name), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+    let is_identifier = |value: &str| {
+        !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    address
+        .strip_prefix("0x")
+        .is_some_and(|hex| !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        && is_identifier(module)
+        && is_identifier(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_coins() {
+        assert!(validate_coins(&default_coins()).is_ok());
+        assert!(validate_coins(&[]).is_err());
+
+        let mut duplicated = default_coins();
+        duplicated[1].symbol = "sui".to_string();
+        assert!(validate_coins(&duplicated).is_err());
+
+        let mut invalid_type = default_coins();
+        invalid_type[0].type_arg = "sui::SUI".to_string();
+        assert!(validate_coins(&invalid_type).is_err());
+    }
+
+    #[test]
+    fn test_is_same_coin_type() {
+        assert!(is_same_coin_type(
+            "0x2::sui::SUI",
+            &format!("0x{:0>64}::sui::SUI", "2")
+        ));
+        assert!(is_same_coin_type("0xABC::usdc::USDC", "0xabc::usdc::USDC"));
+        // 同じシンボルでもアドレスが異なる偽コインは一致しない
+        assert!(!is_same_coin_type("0x2::sui::SUI", "0xbad::sui::SUI"));
+        assert!(!is_same_coin_type("0x2::sui::SUI", "0x2::sui::Sui"));
+    }
+
+    #[test]
+    fn test_is_coin_accepted() {
+        assert!(is_coin_accepted(None, "SUI"));
+
+        let accepted = vec!["USDC".to_string()];
+        assert!(is_coin_accepted(Some(&accepted), "usdc"));
+        assert!(!is_coin_accepted(Some(&accepted), "SUI"));
+    }
+
+    #[test]
+    fn test_to_display_amount() {
+        let coins = default_coins();
+        let (sui, usdc) = (Some(&coins[0]), Some(&coins[1]));
+        assert_eq!(to_display_amount(1.5, None, sui), Ok(1.5));
+        assert_eq!(to_display_amount(1.5, Some("sui"), sui), Ok(1.5));
+        assert_eq!(
+            to_display_amount(1_500_000_000.0, Some("mist"), sui),
+            Ok(1.5)
+        );
+        assert_eq!(to_display_amount(2_500_000.0, Some("base"), usdc), Ok(2.5));
+
+        // 単位やコインが不明な場合は変換しない
+        assert!(to_display_amount(1.5, Some("mist"), usdc).is_err());
+        assert!(to_display_amount(1.5, Some("mist"), None).is_err());
+    }
+
+    /// 表示用の金額の変換のテスト
+    #[test]
+    fn test_format_amount() {
+        assert_eq!(format_amount(0.1 + 1.1, 9), "1.2");
+        assert_eq!(format_amount(2.5, 6), "2.5");
+        assert_eq!(format_amount(10.0, 9), "10");
+        assert_eq!(format_amount(100.0, 0), "100");
+        // 小数点以下の桁数より細かい端数は丸める
+        assert_eq!(format_amount(1.0000004, 6), "1");
+    }
+}
@@ -0,0 +1,58 @@
+//! スーパーチャットへの自動お礼メッセージ関連のコマンドモジュール
+//!
+//! スーパーチャットの配信直後にブロードキャストするお礼メッセージのテンプレートを
+//! 設定・取得するためのTauriコマンドを提供する
+
+use crate::auto_thanks;
+use crate::state::AppState;
+use tauri::State;
+
+/// 自動お礼メッセージのテンプレートを設定するTauriコマンド
+///
+/// テンプレートには `{name}`（表示名）・`{amount}`（金額）・`{coin}`（通貨シンボル）を埋め込めます。
+///
+/// # 引数
+/// * `template` - お礼メッセージのテンプレート（`None` の場合は自動お礼を無効にする）
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<(), String>` - 成功時は`()`、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - テンプレートが空、または最大文字数を超える場合
+#[tauri::command]
+pub fn set_auto_thanks(
+    template: Option<String>,
+    app_state: State<'_, AppState>,
+) -> Result<(), String> {
+    let template = template
+        .map(|template| auto_thanks::validate_template(&template))
+        .transpose()?;
+
+    let mut guard = app_state
+        .auto_thanks_template
+        .lock()
+        .map_err(|e| format!("お礼メッセージ設定のロックに失敗しました: {}", e))?;
+    match &template {
+        Some(template) => println!("自動お礼メッセージを設定しました: {}", template),
+        None => println!("自動お礼メッセージを無効にしました"),
+    }
+    *guard = template;
+    Ok(())
+}
+
+/// 自動お礼メッセージのテンプレートを取得するTauriコマンド
+///
+/// # 引数
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<Option<String>, String>` - 成功時はテンプレート（未設定の場合は`None`）、エラー時はエラーメッセージ
+#[tauri::command]
+pub fn get_auto_thanks(app_state: State<'_, AppState>) -> Result<Option<String>, String> {
+    app_state
+        .auto_thanks_template
+        .lock()
+        .map(|template| template.clone())
+        .map_err(|e| format!("お礼メッセージ設定のロックに失敗しました: {}", e))
+}
@@ -0,0 +1,217 @@
+//! メッセージ履歴のバックアップ関連のコマンドモジュール
+//!
+//! 前回のバックアップ以降に受信したメッセージのみを保存する差分バックアップと、
+//! 差分バックアップファイル群からの復元を行うTauriコマンドを提供する
+
+use crate::backup::{self, BackupChainReport, IncrementalBackup, BACKUP_FORMAT_VERSION};
+use crate::commands::history::get_db_pool;
+use crate::database;
+use crate::state::AppState;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::Path;
+use tauri::State;
+
+/// 差分バックアップの実行結果
+#[derive(Serialize, Debug)]
+pub struct BackupSummary {
+    /// バックアップファイルの保存先
+    pub dest_path: String,
+    /// 差分の開始時刻（全件のバックアップの場合はNone）
+    pub since: Option<DateTime<Utc>>,
+    /// 差分の終了時刻（次回の差分バックアップの開始時刻）
+    pub until: DateTime<Utc>,
+    /// バックアップしたメッセージ数
+    pub message_count: usize,
+    /// バックアップしたセッション数
+    pub session_count: usize,
+}
+
+/// 差分バックアップからの復元結果
+#[derive(Serialize, Debug)]
+pub struct RestoreSummary {
+    /// 適用したバックアップファイルの連続性の検証結果
+    pub chain: BackupChainReport,
+    /// 新たに追加したメッセージ数（既に存在したメッセージは含まない）
+    pub restored_messages: u64,
+}
+
+/// 差分バックアップを作成するTauriコマンド
+///
+/// `since` 以降に受信したメッセージを指定のファイルに保存します。
+/// `since` を省略した場合は前回のバックアップの終了時刻から、
+/// バックアップを一度も実行していない場合は全てのメッセージを保存します。
+///
+/// # 引数
+/// * `dest_path` - バックアップファイルの保存先
+/// * `since` - 差分の開始時刻（RFC 3339形式、省略時は前回のバックアップの終了時刻）
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<BackupSummary, String>` - 成功時はバックアップの概要、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - データベース接続が初期化されていない場合
+/// - 開始時刻の形式が不正な場合
+/// - ファイルの書き込みに失敗した場合
+#[tauri::command]
+pub async fn backup_incremental(
+    dest_path: String,
+    since: Option<String>,
+    app_state: State<'_, AppState>,
+) -> Result<BackupSummary, String> {
+    let db_pool = get_db_pool(&app_state)?;
+
+    let since = match since.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(since) => Some((
+            DateTime::parse_from_rfc3339(since)
+                .map(|since| since.with_timezone(&Utc))
+                .map_err(|e| format!("開始時刻の形式が不正です ({}): {}", since, e))?,
+            None,
+        )),
+        None => database::get_last_backup_until(&db_pool)
+            .await
+            .map_err(|e| format!("前回のバックアップ時刻の取得に失敗しました: {}", e))?,
+    };
+    // メッセージはシーケンス番号を基準に取得する
+    // （時刻で指定された場合や、シーケンス番号を記録する前のバックアップの場合は時刻から変換）
+    let since_sequence = match since {
+        Some((_, Some(since_sequence))) => Some(since_sequence),
+        Some((since, None)) => Some(
+            database::max_sequence_before(&db_pool, since)
+                .await
+                .map_err(|e| format!("開始位置の取得に失敗しました: {}", e))?,
+        ),
+        None => None,
+    };
+    let since = since.map(|(since, _)| since);
+    let until = Utc::now();
+
+    let messages = database::export_incremental(&db_pool, since_sequence)
+        .await
+        .map_err(|e| format!("メッセージの取得に失敗しました: {}", e))?;
+    let until_sequence = messages
+        .iter()
+        .filter_map(|msg| msg.sequence)
+        .max()
+        .or(since_sequence);
+    let sessions = database::get_sessions_for_backup(&db_pool, since, until, since_sequence)
+        .await
+        .map_err(|e| format!("セッションの取得に失敗しました: {}", e))?;
+
+    let summary = BackupSummary {
+        dest_path: dest_path.clone(),
+        since,
+        until,
+        message_count: messages.len(),
+        session_count: sessions.len(),
+    };
+    let backup = IncrementalBackup {
+        format_version: BACKUP_FORMAT_VERSION,
+        since,
+        until,
+        since_sequence,
+        until_sequence,
+        created_at: until,
+        sessions,
+        messages,
+    };
+    backup::write_backup(Path::new(&dest_path), &backup)?;
+
+    database::record_backup(
+        &db_pool,
+        &dest_path,
+        since,
+        until,
+        until_sequence,
+        summary.message_count as i64,
+    )
+    .await
+    .map_err(|e| format!("バックアップの記録に失敗しました: {}", e))?;
+
+    println!(
+        "差分バックアップを作成しました: {} ({}件, {:?} 〜 {})",
+        dest_path, summary.message_count, since, until
+    );
+    Ok(summary)
+}
+
+/// 差分バックアップファイル群の連続性を検証するTauriコマンド
+///
+/// # 引数
+/// * `paths` - 差分バックアップファイルのパス（順不同）
+///
+/// # 戻り値
+/// * `Result<BackupChainReport, String>` - 成功時は検証結果、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - ファイルの読み込みまたは解析に失敗した場合
+#[tauri::command]
+pub fn verify_backup_chain(paths: Vec<String>) -> Result<BackupChainReport, String> {
+    let (_, report) = backup::order_backup_chain(read_backups(&paths)?);
+    Ok(report)
+}
+
+/// 差分バックアップファイル群から履歴を復元するTauriコマンド
+///
+/// ファイルを時系列に並べ替えて連続性を検証し、欠損がなければ古い順に適用します。
+/// 既に存在するメッセージはスキップされます。
+///
+/// # 引数
+/// * `paths` - 差分バックアップファイルのパス（順不同）
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<RestoreSummary, String>` - 成功時は復元結果、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - データベース接続が初期化されていない場合
+/// - ファイルの読み込みまたは解析に失敗した場合
+/// - バックアップの期間に欠損がある場合
+#[tauri::command]
+pub async fn restore_from_incrementals(
+    paths: Vec<String>,
+    app_state: State<'_, AppState>,
+) -> Result<RestoreSummary, String> {
+    if paths.is_empty() {
+        return Err("復元するバックアップファイルを指定してください".to_string());
+    }
+    let db_pool = get_db_pool(&app_state)?;
+
+    let (backups, chain) = backup::order_backup_chain(read_backups(&paths)?);
+    if let Some(gap) = chain.gaps.first() {
+        return Err(format!(
+            "バックアップの期間に欠損があります（{} 〜 {}、他{}件）",
+            gap.from,
+            gap.to,
+            chain.gaps.len() - 1
+        ));
+    }
+
+    let mut restored_messages = 0;
+    for (path, backup) in &backups {
+        let inserted = database::import_backup(&db_pool, &backup.sessions, &backup.messages)
+            .await
+            .map_err(|e| format!("バックアップの適用に失敗しました ({}): {}", path, e))?;
+        println!(
+            "バックアップを適用しました: {} ({}/{}件を追加)",
+            path,
+            inserted,
+            backup.messages.len()
+        );
+        restored_messages += inserted;
+    }
+
+    Ok(RestoreSummary {
+        chain,
+        restored_messages,
+    })
+}
+
+/// バックアップファイルを全て読み込む
+fn read_backups(paths: &[String]) -> Result<Vec<(String, IncrementalBackup)>, String> {
+    paths
+        .iter()
+        .map(|path| backup::read_backup(Path::new(path)).map(|backup| (path.clone(), backup)))
+        .collect()
+}
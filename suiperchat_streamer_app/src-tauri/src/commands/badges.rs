@@ -0,0 +1,107 @@
+//! 視聴者の称号バッジ関連のコマンドモジュール
+//!
+//! 累計スーパーチャット額や配信参加回数に応じて視聴者に付与する称号の条件を
+//! 設定・取得するためのTauriコマンドを提供する
+
+use crate::badges::{BadgeConfig, BadgeRule, MAX_BADGE_NAME_LENGTH, MAX_BADGE_RULES};
+use crate::state::AppState;
+use tauri::State;
+
+/// 称号バッジの付与条件を設定するTauriコマンド
+///
+/// 条件は定義順に判定され、満たした全ての称号がメッセージに付与されます。
+/// 同じ `group` の称号は、条件を満たすもののうち最後に定義されたものだけが付与されます。
+///
+/// # 引数
+/// * `config` - 称号バッジの設定
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<BadgeConfig, String>` - 成功時は適用した設定、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - 称号の数が上限を超える場合
+/// - 称号名が空、または最大文字数を超える場合
+/// - 閾値が負の値、または有限でない場合
+#[tauri::command]
+pub fn set_badge_thresholds(
+    config: BadgeConfig,
+    app_state: State<'_, AppState>,
+) -> Result<BadgeConfig, String> {
+    if config.rules.len() > MAX_BADGE_RULES {
+        return Err(format!(
+            "称号は{}件まで設定できます: {}件",
+            MAX_BADGE_RULES,
+            config.rules.len()
+        ));
+    }
+
+    let rules = config
+        .rules
+        .into_iter()
+        .map(normalize_badge_rule)
+        .collect::<Result<Vec<_>, _>>()?;
+    let config = BadgeConfig {
+        enabled: config.enabled,
+        rules,
+    };
+
+    *app_state
+        .badge_config
+        .lock()
+        .map_err(|e| format!("称号バッジの設定のロックに失敗しました: {}", e))? = config.clone();
+
+    println!(
+        "称号バッジを設定しました: {}件 (有効: {})",
+        config.rules.len(),
+        config.enabled
+    );
+    Ok(config)
+}
+
+/// 現在の称号バッジの設定を取得するTauriコマンド
+///
+/// # 引数
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<BadgeConfig, String>` - 成功時は称号バッジの設定、エラー時はエラーメッセージ
+#[tauri::command]
+pub fn get_badge_thresholds(app_state: State<'_, AppState>) -> Result<BadgeConfig, String> {
+    app_state
+        .badge_config
+        .lock()
+        .map(|config| config.clone())
+        .map_err(|e| format!("称号バッジの設定のロックに失敗しました: {}", e))
+}
+
+/// 称号の付与条件を検証し、称号名とグループの前後の空白を取り除く
+fn normalize_badge_rule(rule: BadgeRule) -> Result<BadgeRule, String> {
+    let name = rule.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_BADGE_NAME_LENGTH {
+        return Err(format!(
+            "称号名は1〜{}文字で指定してください",
+            MAX_BADGE_NAME_LENGTH
+        ));
+    }
+    if !rule.min_total_amount.is_finite()
+        || rule.min_total_amount < 0.0
+        || rule.min_superchat_count < 0
+        || rule.min_session_count < 0
+    {
+        return Err(format!(
+            "称号の閾値には0以上の値を指定してください: {}",
+            name
+        ));
+    }
+
+    let group = rule
+        .group
+        .map(|group| group.trim().to_string())
+        .filter(|group| !group.is_empty());
+    Ok(BadgeRule {
+        name,
+        group,
+        ..rule
+    })
+}
@@ -0,0 +1,113 @@
+//! 対応コイン関連のコマンドモジュール
+//!
+//! スーパーチャットに使用できるコインの一覧を取得・更新するためのTauriコマンドを提供する
+//! 配信ごとに受け付けるコインを制限するためのTauriコマンドも提供する
+
+use crate::coin_registry::{self, CoinInfo};
+use crate::state::AppState;
+use tauri::State;
+
+/// 対応コインの一覧を取得するTauriコマンド
+///
+/// 視聴者フロントはこの一覧からコインのシンボル・型引数・小数点以下の桁数を取得します。
+///
+/// # 引数
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<Vec<CoinInfo>, String>` - 成功時は対応コインの一覧、エラー時はエラーメッセージ
+#[tauri::command]
+pub fn get_supported_coins(app_state: State<'_, AppState>) -> Result<Vec<CoinInfo>, String> {
+    app_state
+        .supported_coins
+        .lock()
+        .map(|coins| coins.clone())
+        .map_err(|e| format!("対応コインのロックに失敗しました: {}", e))
+}
+
+/// 対応コインの一覧を更新するTauriコマンド
+///
+/// 以降に受信したスーパーチャットは、更新後の一覧に登録されたコインのみ受け付けます。
+///
+/// # 引数
+/// * `coins` - 対応コインの一覧
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<Vec<CoinInfo>, String>` - 成功時は更新後の対応コインの一覧、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - 一覧が空の場合
+/// - 型引数の形式が不正、桁数が上限を超える、またはシンボルが重複している場合
+#[tauri::command]
+pub fn set_supported_coins(
+    coins: Vec<CoinInfo>,
+    app_state: State<'_, AppState>,
+) -> Result<Vec<CoinInfo>, String> {
+    let coins: Vec<CoinInfo> = coins
+        .into_iter()
+        .map(|coin| CoinInfo {
+            symbol: coin.symbol.trim().to_string(),
+            type_arg: coin.type_arg.trim().to_string(),
+            decimals: coin.decimals,
+        })
+        .collect();
+    coin_registry::validate_coins(&coins)?;
+
+    let mut supported_coins = app_state
+        .supported_coins
+        .lock()
+        .map_err(|e| format!("対応コインのロックに失敗しました: {}", e))?;
+    *supported_coins = coins;
+    println!("対応コインを更新しました: {:?}", *supported_coins);
+    Ok(supported_coins.clone())
+}
+
+/// スーパーチャットを受け付けるコインを設定するTauriコマンド
+///
+/// 一覧に含まれないコインのスーパーチャットは拒否され、DBに保存されず送信者にエラーが返されます。
+/// `None` を指定すると制限を解除し、対応コインをすべて受け付けます。
+///
+/// # 引数
+/// * `coins` - 受け付けるコインのシンボルの一覧（制限しない場合はNone）
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<Option<Vec<String>>, String>` - 成功時は適用した設定、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - 一覧が空の場合
+/// - 対応コインに登録されていないシンボルが含まれる場合
+#[tauri::command]
+pub fn set_accepted_coins(
+    coins: Option<Vec<String>>,
+    app_state: State<'_, AppState>,
+) -> Result<Option<Vec<String>>, String> {
+    let coins = match coins {
+        Some(coins) => {
+            let mut symbols: Vec<String> = Vec::new();
+            for coin in coins {
+                let Some(coin_info) = coin_registry::find_coin(&app_state, &coin) else {
+                    return Err(format!("対応していないコインです: {}", coin.trim()));
+                };
+                if !symbols.contains(&coin_info.symbol) {
+                    symbols.push(coin_info.symbol);
+                }
+            }
+            if symbols.is_empty() {
+                return Err(
+                    "受け付けるコインを1つ以上指定してください（制限しない場合はnull）".to_string(),
+                );
+            }
+            Some(symbols)
+        }
+        None => None,
+    };
+
+    *app_state
+        .accepted_coins
+        .lock()
+        .map_err(|e| format!("受け付けるコインのロックに失敗しました: {}", e))? = coins.clone();
+    println!("受け付けるコインを設定しました: {:?}", coins);
+    Ok(coins)
+}
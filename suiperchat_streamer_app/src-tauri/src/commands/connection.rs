@@ -2,13 +2,71 @@
 //!
 //! クライアント接続の管理・制限を行うコマンドを提供します。
 
+use crate::db_models::ViewerCountSample;
+use crate::settings::{self, MAX_CONNECTIONS_KEY};
 use crate::state::AppState;
-use crate::ws_server::ConnectionsInfo;
+use crate::types::MAX_GROUP_NAME_LENGTH;
+use crate::ws_server::access_token;
+use crate::ws_server::flow_control::{
+    DEFAULT_MAX_MESSAGES_PER_SECOND, MAX_MESSAGES_PER_SECOND_LIMIT,
+};
+use crate::ws_server::human_verification::MAX_POW_DIFFICULTY;
+use crate::ws_server::idle_monitor::{IdleDisconnectConfig, IdleTarget};
+use crate::ws_server::rate_limit::{
+    DEFAULT_RATE_LIMIT_MAX_MESSAGES, DEFAULT_RATE_LIMIT_WINDOW_SECS, MAX_RATE_LIMIT_WINDOW_SECS,
+};
+use crate::ws_server::tx_verification::{TxVerificationConfig, UnverifiedAction};
+use crate::ws_server::{
+    ConnectionStats, ConnectionsInfo, FlowControlConfig, HumanVerificationConfig, MessageRateLimit,
+    PaginatedConnectionsInfo,
+};
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 use tauri::{command, State};
 
+/// ページ単位取得時のデフォルト件数
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// ページ単位取得時の最大件数
+const MAX_PAGE_SIZE: usize = 500;
+
+/// ## 接続中クライアントの統計を取得するコマンド
+///
+/// クライアント一覧を含まないため、`get_connections_info` より軽量に取得できます。
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<ConnectionStats, String>`: 総接続数・総送信メッセージ数・最古の接続時刻・直近1分間のアクティブ数
+#[command]
+pub fn get_connection_stats(_app_state: State<'_, AppState>) -> Result<ConnectionStats, String> {
+    Ok(crate::ws_server::get_connection_stats())
+}
+
+/// ## 配信中の視聴者数の推移を取得するコマンド
+///
+/// 接続数が変化したときにサンプリングした視聴者数を、フロントエンドでグラフ化できるよう時刻順に返します。
+/// 直近1時間分（10秒間隔）のみを保持しています。
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<Vec<ViewerCountSample>, String>`: 視聴者数の推移（古い順）
+#[command]
+pub fn get_viewer_count_history(
+    app_state: State<'_, AppState>,
+) -> Result<Vec<ViewerCountSample>, String> {
+    let history = app_state
+        .viewer_count_history
+        .lock()
+        .map_err(|e| format!("視聴者数の推移のロックに失敗しました: {}", e))?;
+    Ok(history.iter().map(ViewerCountSample::from_sample).collect())
+}
+
 /// ## 接続情報を取得するコマンド
 ///
 /// 現在の接続状況に関する情報を取得します。
@@ -80,6 +138,37 @@ pub fn get_connections_info(_app_state: State<'_, AppState>) -> Result<Connectio
     )
 }
 
+/// ## 接続情報をページ単位で取得するコマンド
+///
+/// 数百の接続がある大規模配信向けに、接続時刻順に並べたクライアントの一部のみを取得します。
+/// 小規模配信では全件を返す `get_connections_info` を使用できます。
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `offset`: 取得開始位置（デフォルト0）
+/// - `limit`: 取得する最大件数（デフォルト50、最大500）
+///
+/// ### Returns
+/// - `Result<PaginatedConnectionsInfo, String>`: 成功した場合は指定範囲の接続情報、エラーの場合はエラーメッセージ
+#[command]
+pub fn get_connections_paginated(
+    _app_state: State<'_, AppState>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<PaginatedConnectionsInfo, String> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+
+    if limit < 1 {
+        return Err("取得件数は1以上である必要があります".to_string());
+    }
+
+    Ok(crate::ws_server::get_connections_paginated(
+        offset,
+        limit.min(MAX_PAGE_SIZE),
+    ))
+}
+
 /// ## クライアントを切断するコマンド
 ///
 /// 指定されたIDのクライアント接続を切断します。
@@ -100,19 +189,256 @@ pub fn disconnect_client(
     Ok(result)
 }
 
+/// ## 特定のクライアントにメッセージを送信するコマンド
+///
+/// 配信者のダッシュボードから特定の視聴者にシステム通知（例: 「まもなく配信終了します」）を送信します。
+/// 視聴者側では `type: "system"` のメッセージとして通常のチャットと区別されます。
+///
+/// ### Arguments
+/// - `client_id`: 送信先のクライアントのID
+/// - `message`: 送信するメッセージ
+///
+/// ### Returns
+/// - `Result<bool, String>`: 成功した場合は送信結果（クライアントが見つからない場合はfalse）、エラーの場合はエラーメッセージ
+#[command]
+pub fn send_message_to_client(client_id: String, message: String) -> Result<bool, String> {
+    let message = message.trim();
+    if message.is_empty() {
+        return Err("送信するメッセージを入力してください".to_string());
+    }
+    crate::ws_server::send_system_message(&client_id, message)
+}
+
+/// ## IPアドレスをブロックするコマンド
+///
+/// 指定したIPアドレスからの接続を拒否し、接続中のセッションを全て切断します。
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `ip`: ブロックするIPアドレス
+///
+/// ### Returns
+/// - `Result<usize, String>`: 成功した場合は切断したセッション数、エラーの場合はエラーメッセージ
+#[command]
+pub fn block_client_ip(_app_state: State<'_, AppState>, ip: String) -> Result<usize, String> {
+    let ip = ip
+        .trim()
+        .parse::<std::net::IpAddr>()
+        .map_err(|e| format!("無効なIPアドレスです ({}): {}", ip, e))?;
+    crate::ws_server::block_client_ip(&ip.to_string())
+}
+
+/// ## IPアドレスのブロックを解除するコマンド
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `ip`: ブロックを解除するIPアドレス
+///
+/// ### Returns
+/// - `Result<bool, String>`: 成功した場合はブロックされていたかどうか、エラーの場合はエラーメッセージ
+#[command]
+pub fn unblock_client_ip(_app_state: State<'_, AppState>, ip: String) -> Result<bool, String> {
+    let ip = ip
+        .trim()
+        .parse::<std::net::IpAddr>()
+        .map_err(|e| format!("無効なIPアドレスです ({}): {}", ip, e))?;
+    Ok(crate::ws_server::unblock_client_ip(&ip.to_string()))
+}
+
+/// ## ブロック中のIPアドレス一覧を取得するコマンド
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<Vec<String>, String>`: ブロック中のIPアドレス
+#[command]
+pub fn get_blocked_ips(_app_state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    Ok(crate::ws_server::get_blocked_ips())
+}
+
+/// グループ名を検証し、前後の空白を除去して返す
+fn normalize_group_name(group: &str) -> Result<String, String> {
+    let group = group.trim();
+    if group.is_empty() || group.chars().count() > MAX_GROUP_NAME_LENGTH {
+        return Err(format!(
+            "グループ名は1〜{}文字で指定してください",
+            MAX_GROUP_NAME_LENGTH
+        ));
+    }
+    Ok(group.to_string())
+}
+
+/// ## クライアントをグループに割り当てるコマンド
+///
+/// グループは割り当て時に作成されます。割り当てはクライアントの切断時に失われます。
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `client_id`: 割り当てるクライアントのID
+/// - `group`: グループ名
+///
+/// ### Returns
+/// - `Result<Vec<String>, String>`: 成功した場合は割り当て後の所属グループ、エラーの場合はエラーメッセージ
+#[command]
+pub fn assign_client_group(
+    _app_state: State<'_, AppState>,
+    client_id: String,
+    group: String,
+) -> Result<Vec<String>, String> {
+    let group = normalize_group_name(&group)?;
+    crate::ws_server::assign_client_group(&client_id, &group)
+        .ok_or_else(|| format!("クライアントが見つかりません: {}", client_id))
+}
+
+/// ## クライアントのグループ割り当てを解除するコマンド
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `client_id`: 解除するクライアントのID
+/// - `group`: グループ名
+///
+/// ### Returns
+/// - `Result<Vec<String>, String>`: 成功した場合は解除後の所属グループ、エラーの場合はエラーメッセージ
+#[command]
+pub fn unassign_client_group(
+    _app_state: State<'_, AppState>,
+    client_id: String,
+    group: String,
+) -> Result<Vec<String>, String> {
+    let group = normalize_group_name(&group)?;
+    crate::ws_server::unassign_client_group(&client_id, &group)
+        .ok_or_else(|| format!("クライアントが見つかりません: {}", client_id))
+}
+
+/// ## クライアントの所属グループを取得するコマンド
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `client_id`: クライアントのID
+///
+/// ### Returns
+/// - `Result<Vec<String>, String>`: 成功した場合は所属グループ、エラーの場合はエラーメッセージ
+#[command]
+pub fn get_client_groups(
+    _app_state: State<'_, AppState>,
+    client_id: String,
+) -> Result<Vec<String>, String> {
+    crate::ws_server::get_client_groups(&client_id)
+        .ok_or_else(|| format!("クライアントが見つかりません: {}", client_id))
+}
+
+/// ## グループ所属者にメッセージを送信するコマンド
+///
+/// VIPグループなど特定のグループの視聴者にだけシステム通知を送信します。
+/// グループ未割り当ての視聴者には、デフォルトグループ（"default"）を指定して送信します。
+///
+/// ### Arguments
+/// - `group`: 送信先のグループ名
+/// - `message`: 送信するメッセージ
+///
+/// ### Returns
+/// - `Result<usize, String>`: 成功した場合は送信したクライアントの数、エラーの場合はエラーメッセージ
+#[command]
+pub fn send_message_to_group(group: String, message: String) -> Result<usize, String> {
+    let group = normalize_group_name(&group)?;
+    let message = message.trim();
+    if message.is_empty() {
+        return Err("送信するメッセージを入力してください".to_string());
+    }
+    crate::ws_server::send_group_system_message(&group, message)
+}
+
+/// ## グループごとの接続数を取得するコマンド
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<BTreeMap<String, usize>, String>`: グループ名をキーにした接続数
+#[command]
+pub fn get_group_connection_counts(
+    _app_state: State<'_, AppState>,
+) -> Result<BTreeMap<String, usize>, String> {
+    Ok(crate::ws_server::get_group_connection_counts())
+}
+
+/// ## 配信統計をリセットするコマンド
+///
+/// 各クライアントの配信成功数・失敗数と警告フラグをリセットします。
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `client_id`: リセットするクライアントのID（省略時は全クライアント）
+///
+/// ### Returns
+/// - `Result<usize, String>`: 成功した場合はリセットしたクライアント数、エラーの場合はエラーメッセージ
+#[command]
+pub fn reset_delivery_stats(
+    _app_state: State<'_, AppState>,
+    client_id: Option<String>,
+) -> Result<usize, String> {
+    let reset_count = crate::ws_server::reset_delivery_stats(client_id.as_deref());
+
+    if let Some(client_id) = client_id {
+        if reset_count == 0 {
+            return Err(format!("クライアントが見つかりません: {}", client_id));
+        }
+    }
+    Ok(reset_count)
+}
+
+/// ## 満員時の代替URLを設定するコマンド
+///
+/// 最大接続数を超えて接続を拒否する際に、視聴者へ `type: "redirect"` で案内する
+/// 代替URL（録画アーカイブ、別プラットフォームなど）を設定します。
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `url`: 代替URL（http/https）。`None` または空文字の場合は設定を解除
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功した場合は`Ok(())`、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_overflow_redirect(
+    app_state: State<'_, AppState>,
+    url: Option<String>,
+) -> Result<(), String> {
+    let url = url
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+
+    if let Some(url) = &url {
+        let parsed = url::Url::parse(url).map_err(|e| format!("無効なURLです: {}", e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err("代替URLは http または https で指定してください".to_string());
+        }
+    }
+
+    let mut redirect_url = app_state
+        .overflow_redirect_url
+        .lock()
+        .map_err(|_| "Failed to lock overflow redirect URL mutex".to_string())?;
+    println!("満員時の代替URLを設定しました: {:?}", url);
+    *redirect_url = url;
+
+    Ok(())
+}
+
 /// ## 最大接続数を設定するコマンド
 ///
 /// WebSocketサーバーの最大同時接続数を設定します。
+/// 設定はDBに保存され、次回起動時に復元されます。
 ///
 /// ### Arguments
-/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
 /// - `max_connections`: 設定する最大接続数
 ///
 /// ### Returns
 /// - `Result<(), String>`: 成功した場合は`Ok(())`、エラーの場合はエラーメッセージ
 #[command]
 pub fn set_connection_limits(
-    _app_state: State<'_, AppState>,
+    app_state: State<'_, AppState>,
     max_connections: usize,
 ) -> Result<(), String> {
     if max_connections < 1 {
@@ -121,6 +447,395 @@ pub fn set_connection_limits(
 
     // グローバル接続マネージャを使用して最大接続数を設定
     crate::ws_server::set_max_connections(max_connections);
+    settings::save_setting(&app_state, MAX_CONNECTIONS_KEY, max_connections.to_string());
 
     Ok(())
 }
+
+/// ## 同一ウォレットからの接続数の上限を設定するコマンド
+///
+/// トランザクション検証で送金者を確認できた最初のスーパーチャットの受信時に接続とウォレットを紐づけ、
+/// 同一ウォレットに紐づく接続が上限を超えた場合は接続時刻の古いものから切断します。
+/// トランザクション検証が無効の場合は接続とウォレットが紐づかず上限が適用されないため、
+/// 上限の設定（1以上）は検証が有効な場合のみ受け付けます。
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `limit`: 同一ウォレットに紐づく接続数の上限（0の場合は無制限）
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功した場合は`Ok(())`、トランザクション検証が無効な場合などはエラーメッセージ
+#[command]
+pub fn set_per_wallet_limit(app_state: State<'_, AppState>, limit: usize) -> Result<(), String> {
+    validate_per_wallet_limit(limit, &TxVerificationConfig::from_app_state(&app_state))?;
+    crate::ws_server::set_per_wallet_limit(limit);
+    println!("同一ウォレットの接続数の上限を設定しました: {}", limit);
+
+    Ok(())
+}
+
+/// ## 同一ウォレットの接続数の上限を検証する
+///
+/// ### Arguments
+/// - `limit`: 同一ウォレットに紐づく接続数の上限（0の場合は無制限）
+/// - `tx_verification`: 現在のトランザクション検証の設定
+///
+/// ### Returns
+/// - `Result<(), String>`: 上限が適用される場合は `Ok(())`、検証が無効で適用されない場合はエラーメッセージ
+fn validate_per_wallet_limit(
+    limit: usize,
+    tx_verification: &TxVerificationConfig,
+) -> Result<(), String> {
+    if limit > 0 && !tx_verification.enabled {
+        return Err(
+            "同一ウォレットの接続数の上限はトランザクション検証が有効な場合のみ設定できます"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// ## 送信フロー制御を設定するコマンド
+///
+/// クライアントごとの送信レート上限を設定します。上限を超えたメッセージはバッファに溜められ、
+/// バッファが溢れた場合は古い通常チャットから間引かれます。
+/// 低頻度の配信では無効にすることで即時送信にできます。
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `enabled`: フロー制御を有効にするかどうか
+/// - `max_messages_per_second`: 1秒あたりの最大送信メッセージ数（省略時は20）
+/// - `prioritize_superchats`: 滞留時にスーパーチャットを優先して送信するかどうか（省略時は現在の設定を維持）
+///
+/// ### Returns
+/// - `Result<FlowControlConfig, String>`: 成功した場合は適用した設定、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_flow_control(
+    _app_state: State<'_, AppState>,
+    enabled: bool,
+    max_messages_per_second: Option<u32>,
+    prioritize_superchats: Option<bool>,
+) -> Result<FlowControlConfig, String> {
+    let max_messages_per_second =
+        max_messages_per_second.unwrap_or(DEFAULT_MAX_MESSAGES_PER_SECOND);
+    if !(1..=MAX_MESSAGES_PER_SECOND_LIMIT).contains(&max_messages_per_second) {
+        return Err(format!(
+            "送信レート上限は1〜{}の範囲で指定してください",
+            MAX_MESSAGES_PER_SECOND_LIMIT
+        ));
+    }
+
+    let config = FlowControlConfig {
+        enabled,
+        max_messages_per_second,
+        prioritize_superchats: prioritize_superchats
+            .unwrap_or_else(|| crate::ws_server::get_flow_control().prioritize_superchats),
+    };
+    crate::ws_server::set_flow_control(config);
+    println!("送信フロー制御を設定しました: {:?}", config);
+
+    Ok(config)
+}
+
+/// ## 送信フロー制御の設定を取得するコマンド
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<FlowControlConfig, String>`: 現在のフロー制御の設定
+#[command]
+pub fn get_flow_control(_app_state: State<'_, AppState>) -> Result<FlowControlConfig, String> {
+    Ok(crate::ws_server::get_flow_control())
+}
+
+/// ## メッセージ受信レート制限を設定するコマンド
+///
+/// 視聴者ごとに、直近の期間内に受け付ける通常チャットの件数に上限を設けます。
+/// 上限を超えたメッセージは保存・配信されず、送信者にエラーが返されます。
+/// スーパーチャットは制限の対象外です。
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `enabled`: レート制限を有効にするかどうか
+/// - `max_messages`: 期間内に受け付ける最大メッセージ数（省略時は5）
+/// - `window_secs`: レート制限の期間（秒、省略時は10）
+///
+/// ### Returns
+/// - `Result<MessageRateLimit, String>`: 成功した場合は適用した設定、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_message_rate_limit(
+    _app_state: State<'_, AppState>,
+    enabled: bool,
+    max_messages: Option<usize>,
+    window_secs: Option<u64>,
+) -> Result<MessageRateLimit, String> {
+    let max_messages = max_messages.unwrap_or(DEFAULT_RATE_LIMIT_MAX_MESSAGES);
+    if max_messages < 1 {
+        return Err("最大メッセージ数は1以上である必要があります".to_string());
+    }
+    let window_secs = window_secs.unwrap_or(DEFAULT_RATE_LIMIT_WINDOW_SECS);
+    if !(1..=MAX_RATE_LIMIT_WINDOW_SECS).contains(&window_secs) {
+        return Err(format!(
+            "レート制限の期間は1〜{}秒の範囲で指定してください",
+            MAX_RATE_LIMIT_WINDOW_SECS
+        ));
+    }
+
+    let limit = MessageRateLimit {
+        enabled,
+        max_messages,
+        window_secs,
+    };
+    crate::ws_server::set_message_rate_limit(limit);
+    println!("メッセージ受信レート制限を設定しました: {:?}", limit);
+
+    Ok(limit)
+}
+
+/// ## メッセージ受信レート制限の設定を取得するコマンド
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<MessageRateLimit, String>`: 現在のレート制限の設定
+#[command]
+pub fn get_message_rate_limit(_app_state: State<'_, AppState>) -> Result<MessageRateLimit, String> {
+    Ok(crate::ws_server::get_message_rate_limit())
+}
+
+/// ## アイドル接続の自動切断を設定するコマンド
+///
+/// 設定した時間活動のない接続を定期的に切断し、接続枠を解放します。
+/// 切断されたクライアントには理由が通知され、再接続は制限されません。
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `secs`: アイドルとみなすまでの時間（秒、60秒以上、0の場合は無効）
+/// - `target`: アイドル判定の対象（"unresponsive": pong応答もない接続のみ、"no_messages": メッセージを送信していない接続、省略時は現在の設定を維持）
+///
+/// ### Returns
+/// - `Result<IdleDisconnectConfig, String>`: 成功した場合は適用した設定、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_idle_disconnect_timeout(
+    _app_state: State<'_, AppState>,
+    secs: u64,
+    target: Option<IdleTarget>,
+) -> Result<IdleDisconnectConfig, String> {
+    IdleDisconnectConfig::validate_timeout_secs(secs)?;
+
+    let current = crate::ws_server::get_idle_disconnect();
+    let config = IdleDisconnectConfig {
+        timeout_secs: secs,
+        target: target.unwrap_or(current.target),
+    };
+    crate::ws_server::set_idle_disconnect(config);
+    println!("アイドル切断を設定しました: {:?}", config);
+
+    Ok(config)
+}
+
+/// ## アイドル接続の自動切断の設定を取得するコマンド
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<IdleDisconnectConfig, String>`: 現在のアイドル切断の設定
+#[command]
+pub fn get_idle_disconnect_timeout(
+    _app_state: State<'_, AppState>,
+) -> Result<IdleDisconnectConfig, String> {
+    Ok(crate::ws_server::get_idle_disconnect())
+}
+
+/// ## 人間検証を設定するコマンド
+///
+/// 有効にすると、視聴者はproof-of-workのチャレンジを解くまでメッセージを送信できません。
+/// 検証前に送信されたメッセージは保留され、検証完了後にまとめて配信されます。
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `enabled`: 人間検証を有効にするかどうか
+/// - `difficulty`: 難易度（先頭の0ビット数、省略時は現在の設定を維持）
+///
+/// ### Returns
+/// - `Result<HumanVerificationConfig, String>`: 成功した場合は適用した設定、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_human_verification(
+    app_state: State<'_, AppState>,
+    enabled: bool,
+    difficulty: Option<u8>,
+) -> Result<HumanVerificationConfig, String> {
+    if let Some(difficulty) = difficulty {
+        if !(1..=MAX_POW_DIFFICULTY).contains(&difficulty) {
+            return Err(format!(
+                "人間検証の難易度は1〜{}の範囲で指定してください",
+                MAX_POW_DIFFICULTY
+            ));
+        }
+        *app_state
+            .human_verification_difficulty
+            .lock()
+            .map_err(|_| "Failed to lock human verification difficulty mutex".to_string())? =
+            difficulty;
+    }
+    *app_state
+        .require_human_verification
+        .lock()
+        .map_err(|_| "Failed to lock human verification mutex".to_string())? = enabled;
+
+    let config = HumanVerificationConfig::from_app_state(&app_state);
+    println!("人間検証を設定しました: {:?}", config);
+    Ok(config)
+}
+
+/// ## 人間検証の設定を取得するコマンド
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<HumanVerificationConfig, String>`: 現在の人間検証の設定
+#[command]
+pub fn get_human_verification(
+    app_state: State<'_, AppState>,
+) -> Result<HumanVerificationConfig, String> {
+    Ok(HumanVerificationConfig::from_app_state(&app_state))
+}
+
+/// ## スーパーチャットのトランザクション検証を設定するコマンド
+///
+/// 有効にすると、視聴者が申告したスーパーチャットの `tx_hash` をSuiチェーン上で確認してから配信します。
+/// 同一ウォレットの接続数の上限は、検証で確認できたウォレットにのみ適用されます。
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `enabled`: トランザクション検証を有効にするかどうか
+/// - `on_mismatch`: 送金先・金額が一致しなかった場合の扱い（省略時は現在の設定を維持）
+/// - `on_unavailable`: RPCのタイムアウトなどで検証できなかった場合の扱い（省略時は現在の設定を維持）
+///
+/// ### Returns
+/// - `Result<TxVerificationConfig, String>`: 成功した場合は適用した設定、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_tx_verification(
+    app_state: State<'_, AppState>,
+    enabled: bool,
+    on_mismatch: Option<UnverifiedAction>,
+    on_unavailable: Option<UnverifiedAction>,
+) -> Result<TxVerificationConfig, String> {
+    let mut config = app_state
+        .tx_verification
+        .lock()
+        .map_err(|_| "Failed to lock tx verification mutex".to_string())?;
+    config.enabled = enabled;
+    if let Some(action) = on_mismatch {
+        config.on_mismatch = action;
+    }
+    if let Some(action) = on_unavailable {
+        config.on_unavailable = action;
+    }
+
+    println!("トランザクション検証を設定しました: {:?}", *config);
+    if !enabled && crate::ws_server::get_manager().per_wallet_limit() > 0 {
+        println!(
+            "警告: トランザクション検証が無効のため、同一ウォレットの接続数の上限は適用されません"
+        );
+    }
+    Ok(*config)
+}
+
+/// ## スーパーチャットのトランザクション検証の設定を取得するコマンド
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<TxVerificationConfig, String>`: 現在のトランザクション検証の設定
+#[command]
+pub fn get_tx_verification(app_state: State<'_, AppState>) -> Result<TxVerificationConfig, String> {
+    Ok(TxVerificationConfig::from_app_state(&app_state))
+}
+
+/// ## ASNデータベースを読み込むコマンド
+///
+/// MaxMindのGeoLite2-ASN（CSV形式）を読み込み、視聴者の接続元ネットワーク種別
+/// （モバイル/固定）の推定を有効にします。読み込み後に接続した視聴者から推定されます。
+///
+/// ### Arguments
+/// - `_app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `paths`: GeoLite2-ASN-Blocks-IPv4.csv / GeoLite2-ASN-Blocks-IPv6.csv のパス
+///
+/// ### Returns
+/// - `Result<usize, String>`: 成功した場合は読み込んだレコード数、エラーの場合はエラーメッセージ
+#[command]
+pub fn load_asn_database(
+    _app_state: State<'_, AppState>,
+    paths: Vec<String>,
+) -> Result<usize, String> {
+    if paths.is_empty() {
+        return Err("ASNデータベースのファイルを指定してください".to_string());
+    }
+    crate::ws_server::network_type::load_database(&paths)
+}
+
+/// ## WebSocket接続のアクセストークンを設定するコマンド
+///
+/// 設定すると、WebSocketハンドシェイク時にクエリパラメータ `?token=xxx` が一致しない、
+/// または欠落している接続を拒否します。`None` を指定すると誰でも接続できるようになります。
+/// 検査はハンドシェイク時のみ行うため、接続済みのクライアントには影響しません。
+/// 機微情報のためDBには保存せず、アプリの再起動で未設定に戻ります。
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `token`: アクセストークン（英数字と `-` `_` `.` `~` のみ、`None` で認証を無効化）
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功した場合は `Ok(())`、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_access_token(
+    app_state: State<'_, AppState>,
+    token: Option<String>,
+) -> Result<(), String> {
+    let token = token
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty());
+    if let Some(token) = &token {
+        access_token::validate_access_token(token)?;
+    }
+
+    let enabled = token.is_some();
+    *app_state
+        .access_token
+        .lock()
+        .map_err(|_| "Failed to lock access token mutex".to_string())? = token;
+
+    if enabled {
+        println!("WebSocket接続のアクセストークン認証を有効にしました");
+    } else {
+        println!("WebSocket接続のアクセストークン認証を無効にしました");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 同一ウォレットの接続数の上限はトランザクション検証が有効な場合のみ設定できることのテスト
+    #[test]
+    fn test_validate_per_wallet_limit() {
+        let enabled = TxVerificationConfig::default();
+        let disabled = TxVerificationConfig {
+            enabled: false,
+            ..enabled
+        };
+
+        assert!(validate_per_wallet_limit(2, &enabled).is_ok());
+        assert!(validate_per_wallet_limit(2, &disabled).is_err());
+        // 無制限（0）は検証の設定にかかわらず設定できる
+        assert!(validate_per_wallet_limit(0, &enabled).is_ok());
+        assert!(validate_per_wallet_limit(0, &disabled).is_ok());
+    }
+}
@@ -0,0 +1,59 @@
+//! クラッシュレポート関連のコマンドモジュール
+//!
+//! 保存済みのクラッシュレポートの一覧取得・削除・送信済み設定・未送信レポートの通知を行うTauriコマンドを提供する
+
+use crate::crash_report::{self, CrashReport};
+
+/// クラッシュレポートの一覧を取得するTauriコマンド
+///
+/// # 戻り値
+/// * `Result<Vec<CrashReport>, String>` - 成功時は新しい順のクラッシュレポート一覧、エラー時はエラーメッセージ
+#[tauri::command]
+pub fn list_crash_reports() -> Result<Vec<CrashReport>, String> {
+    crash_report::list_reports()
+}
+
+/// クラッシュレポートを削除するTauriコマンド
+///
+/// # 引数
+/// * `id` - 削除するレポートのID
+///
+/// # 戻り値
+/// * `Result<(), String>` - 成功時は `Ok(())`、エラー時はエラーメッセージ
+#[tauri::command]
+pub fn delete_crash_report(id: String) -> Result<(), String> {
+    crash_report::delete_report(&id)?;
+    println!("クラッシュレポートを削除しました: {}", id);
+    Ok(())
+}
+
+/// クラッシュレポートを送信済みにするTauriコマンド
+///
+/// 送信済みのレポートは次回起動時の通知対象から外れます。
+///
+/// # 引数
+/// * `id` - 送信済みにするレポートのID
+///
+/// # 戻り値
+/// * `Result<CrashReport, String>` - 成功時は更新後のレポート、エラー時はエラーメッセージ
+#[tauri::command]
+pub fn mark_crash_report_submitted(id: String) -> Result<CrashReport, String> {
+    crash_report::mark_submitted(&id)
+}
+
+/// 未送信のクラッシュレポートを通知するTauriコマンド
+///
+/// フロントエンドが `crash_reports_pending` イベントを購読した後に呼び出すと、
+/// 未送信のレポートがあればイベントで通知します。
+///
+/// # 引数
+/// * `app_handle` - Tauriアプリケーションハンドル
+///
+/// # 戻り値
+/// * `Result<Vec<CrashReport>, String>` - 成功時は未送信のクラッシュレポート、エラー時はエラーメッセージ
+#[tauri::command]
+pub fn notify_crash_reports_ready(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<CrashReport>, String> {
+    crash_report::notify_pending(&app_handle)
+}
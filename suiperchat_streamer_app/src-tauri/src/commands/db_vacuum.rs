@@ -0,0 +1,89 @@
+//! データベースVACUUM関連のコマンドモジュール
+//!
+//! データベースの空き領域を解放する `VACUUM` の手動実行と、実行状況の取得を行うTauriコマンドを提供する
+
+use crate::db_vacuum::{self, VacuumResult, VacuumState};
+use crate::state::AppState;
+use tauri::State;
+
+/// データベースのVACUUMを直ちに実行するTauriコマンド
+///
+/// 実行中はデータベースがロックされ、メッセージの保存が待たされるため、サーバーの停止中のみ実行できます。
+/// 実行前後のデータベースのサイズはログにも出力されます。
+///
+/// # 引数
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<VacuumResult, String>` - 成功時は実行前後のサイズを含む実行結果、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - サーバーが起動中の場合
+/// - データベース接続が初期化されていない場合
+/// - 既にVACUUMを実行中の場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn run_vacuum_now(app_state: State<'_, AppState>) -> Result<VacuumResult, String> {
+    ensure_server_stopped(&app_state)?;
+    db_vacuum::run_vacuum(&app_state).await
+}
+
+/// 設定画面からデータベースを最適化（VACUUM）するTauriコマンド
+///
+/// 配信中にデータベースがロックされるのを避けるため、サーバーの停止中のみ実行できます。
+/// 実行前後のデータベースのサイズはログにも出力されます。
+///
+/// # 引数
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<VacuumResult, String>` - 成功時は実行前後のサイズを含む実行結果、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - サーバーが起動中の場合
+/// - データベース接続が初期化されていない場合
+/// - 既にVACUUMを実行中の場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn optimize_database(app_state: State<'_, AppState>) -> Result<VacuumResult, String> {
+    ensure_server_stopped(&app_state)?;
+    db_vacuum::run_vacuum(&app_state).await
+}
+
+/// サーバーが停止中であることを確認する
+///
+/// # 引数
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<(), String>` - 停止中の場合は `Ok(())`、起動中の場合はエラーメッセージ
+fn ensure_server_stopped(app_state: &AppState) -> Result<(), String> {
+    let is_running = app_state
+        .server_handle
+        .lock()
+        .map_err(|e| format!("サーバーハンドルのロックに失敗しました: {}", e))?
+        .is_some();
+    if is_running {
+        return Err(
+            "配信中はデータベースを最適化できません。サーバーを停止してから再度お試しください。"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// VACUUMの実行状況を取得するTauriコマンド
+///
+/// # 引数
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<VacuumState, String>` - 最終実行時刻・前回以降の削除行数などの実行状況
+#[tauri::command]
+pub fn get_vacuum_status(app_state: State<'_, AppState>) -> Result<VacuumState, String> {
+    app_state
+        .vacuum_state
+        .lock()
+        .map(|state| state.clone())
+        .map_err(|e| format!("VACUUMの状態のロックに失敗しました: {}", e))
+}
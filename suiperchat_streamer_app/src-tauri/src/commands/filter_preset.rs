@@ -0,0 +1,307 @@
+//! フィルタプリセット関連のコマンドモジュール
+//!
+//! 配信者向けのメッセージ表示フィルタ条件を名前付きで保存・呼び出しするためのTauriコマンドを提供する
+
+use crate::commands::history::{get_db_pool, is_superchat};
+use crate::database;
+use crate::db_models::{FilterPreset, Message};
+use crate::state::AppState;
+use crate::types::SerializableMessageForStreamer;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+/// デフォルトプリセット（全表示）の名前
+pub const DEFAULT_FILTER_PRESET_NAME: &str = "全表示";
+
+/// プリセット名の最大文字数
+const MAX_PRESET_NAME_LENGTH: usize = 50;
+
+/// セッション未指定時に取得するメッセージの最大数
+const DEFAULT_APPLY_LIMIT: i64 = 1000;
+
+/// メッセージ表示フィルタの条件
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct FilterConditions {
+    /// スーパーチャットのみを表示するかどうか
+    #[serde(default)]
+    pub superchat_only: bool,
+    /// この金額以上のスーパーチャットのみを表示する（指定時は通常チャットを除外）
+    #[serde(default)]
+    pub min_amount: Option<f64>,
+    /// 表示名または本文に含まれるキーワード（大文字・小文字を区別しない）
+    #[serde(default)]
+    pub keyword: Option<String>,
+    /// 日本語（ひらがな・カタカナ・漢字）を含むメッセージのみを表示するかどうか
+    #[serde(default)]
+    pub japanese_only: bool,
+}
+
+impl FilterConditions {
+    /// メッセージが条件に一致するかどうかを判定する
+    fn matches(&self, msg: &Message) -> bool {
+        let superchat = is_superchat(msg);
+        if (self.superchat_only || self.min_amount.is_some()) && !superchat {
+            return false;
+        }
+        if let Some(min) = self.min_amount {
+            if msg.amount.unwrap_or(0.0) < min {
+                return false;
+            }
+        }
+        if let Some(keyword) = self.keyword.as_deref().map(str::trim) {
+            if !keyword.is_empty() {
+                let keyword = keyword.to_lowercase();
+                if !msg.content.to_lowercase().contains(&keyword)
+                    && !msg.display_name.to_lowercase().contains(&keyword)
+                {
+                    return false;
+                }
+            }
+        }
+        if self.japanese_only && !contains_japanese(&msg.content) {
+            return false;
+        }
+        true
+    }
+}
+
+impl From<FilterPreset> for FilterConditions {
+    fn from(preset: FilterPreset) -> Self {
+        Self {
+            superchat_only: preset.superchat_only,
+            min_amount: preset.min_amount,
+            keyword: preset.keyword,
+            japanese_only: preset.japanese_only,
+        }
+    }
+}
+
+/// フロントエンドに返すフィルタプリセット情報
+#[derive(Serialize, Debug, Clone)]
+pub struct FilterPresetInfo {
+    /// プリセット名
+    pub name: String,
+    /// フィルタ条件
+    pub conditions: FilterConditions,
+    /// デフォルトプリセット（全表示）かどうか
+    pub is_default: bool,
+}
+
+/// フィルタプリセットを保存するTauriコマンド
+///
+/// 同名のプリセットが既に存在する場合は条件を上書きします。
+/// デフォルトプリセット（全表示）は上書きできません。
+///
+/// # 引数
+/// * `name` - プリセット名
+/// * `conditions` - 保存するフィルタ条件
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<FilterPresetInfo, String>` - 成功時は保存したプリセット情報、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - プリセット名が空、長すぎる、またはデフォルトプリセット名と重複する場合
+/// - 最低金額が負の値の場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn save_filter_preset(
+    name: String,
+    conditions: FilterConditions,
+    app_state: State<'_, AppState>,
+) -> Result<FilterPresetInfo, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("プリセット名を入力してください".to_string());
+    }
+    if name.chars().count() > MAX_PRESET_NAME_LENGTH {
+        return Err(format!(
+            "プリセット名は{}文字以内で指定してください",
+            MAX_PRESET_NAME_LENGTH
+        ));
+    }
+    if name == DEFAULT_FILTER_PRESET_NAME {
+        return Err(format!(
+            "「{}」はデフォルトプリセットのため上書きできません",
+            DEFAULT_FILTER_PRESET_NAME
+        ));
+    }
+    if conditions.min_amount.is_some_and(|min| min < 0.0) {
+        return Err("最低金額には0以上の値を指定してください".to_string());
+    }
+
+    let conditions = FilterConditions {
+        keyword: conditions
+            .keyword
+            .map(|k| k.trim().to_string())
+            .filter(|k| !k.is_empty()),
+        ..conditions
+    };
+
+    let db_pool = get_db_pool(&app_state)?;
+
+    let preset = FilterPreset {
+        name: name.clone(),
+        superchat_only: conditions.superchat_only,
+        min_amount: conditions.min_amount,
+        keyword: conditions.keyword.clone(),
+        japanese_only: conditions.japanese_only,
+        created_at: String::new(),
+        updated_at: String::new(),
+    };
+
+    database::upsert_filter_preset(&db_pool, &preset)
+        .await
+        .map_err(|e| {
+            let error_msg = format!(
+                "フィルタプリセットの保存中にデータベースエラーが発生しました: {}",
+                e
+            );
+            eprintln!("エラー: {}", error_msg);
+            error_msg
+        })?;
+
+    println!("フィルタプリセットを保存しました: {}", name);
+
+    Ok(FilterPresetInfo {
+        name,
+        conditions,
+        is_default: false,
+    })
+}
+
+/// 保存されているフィルタプリセットの一覧を取得するTauriコマンド
+///
+/// 先頭には常にデフォルトプリセット（全表示）が含まれます。
+///
+/// # 引数
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<Vec<FilterPresetInfo>, String>` - 成功時はプリセット情報のベクター、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - データベース接続が初期化されていない場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn list_filter_presets(
+    app_state: State<'_, AppState>,
+) -> Result<Vec<FilterPresetInfo>, String> {
+    let db_pool = get_db_pool(&app_state)?;
+
+    let presets = database::get_all_filter_presets(&db_pool)
+        .await
+        .map_err(|e| {
+            let error_msg = format!(
+                "フィルタプリセットの取得中にデータベースエラーが発生しました: {}",
+                e
+            );
+            eprintln!("エラー: {}", error_msg);
+            error_msg
+        })?;
+
+    let mut result = vec![default_preset()];
+    result.extend(presets.into_iter().map(|preset| FilterPresetInfo {
+        name: preset.name.clone(),
+        conditions: FilterConditions::from(preset),
+        is_default: false,
+    }));
+
+    Ok(result)
+}
+
+/// フィルタプリセットを適用してメッセージ履歴を取得するTauriコマンド
+///
+/// 指定されたプリセットの条件に一致するメッセージを時系列順（昇順）で返します。
+/// セッションIDを指定しない場合は、全セッションの最新メッセージから絞り込みます。
+///
+/// # 引数
+/// * `name` - 適用するプリセット名
+/// * `session_id` - 取得対象のセッションID（指定しない場合は全セッション）
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<Vec<SerializableMessageForStreamer>, String>` - 成功時はメッセージのベクター、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - 指定されたプリセットが存在しない場合
+/// - データベース接続が初期化されていない場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn apply_filter_preset(
+    name: String,
+    session_id: Option<String>,
+    app_state: State<'_, AppState>,
+) -> Result<Vec<SerializableMessageForStreamer>, String> {
+    println!(
+        "フィルタプリセット適用: name={}, session_id={:?}",
+        name, session_id
+    );
+
+    let db_pool = get_db_pool(&app_state)?;
+
+    let conditions = if name == DEFAULT_FILTER_PRESET_NAME {
+        FilterConditions::default()
+    } else {
+        database::get_filter_preset_by_name(&db_pool, &name)
+            .await
+            .map_err(|e| {
+                let error_msg = format!(
+                    "フィルタプリセットの取得中にデータベースエラーが発生しました: {}",
+                    e
+                );
+                eprintln!("エラー: {}", error_msg);
+                error_msg
+            })?
+            .map(FilterConditions::from)
+            .ok_or_else(|| format!("フィルタプリセットが見つかりません: {}", name))?
+    };
+
+    let messages = match session_id {
+        Some(sid) => database::get_all_messages_by_session_id(&db_pool, &sid).await,
+        None => database::fetch_messages(&db_pool, DEFAULT_APPLY_LIMIT, 0)
+            .await
+            .map(|mut messages| {
+                // fetch_messagesは降順で返すため昇順に揃える
+                messages.reverse();
+                messages
+            }),
+    }
+    .map_err(|e| {
+        let error_msg = format!(
+            "メッセージ履歴の取得中にデータベースエラーが発生しました: {}",
+            e
+        );
+        eprintln!("エラー: {}", error_msg);
+        error_msg
+    })?;
+
+    Ok(messages
+        .into_iter()
+        .filter(|msg| conditions.matches(msg))
+        .map(SerializableMessageForStreamer::from)
+        .collect())
+}
+
+/// デフォルトプリセット（全表示）を作成する
+fn default_preset() -> FilterPresetInfo {
+    FilterPresetInfo {
+        name: DEFAULT_FILTER_PRESET_NAME.to_string(),
+        conditions: FilterConditions::default(),
+        is_default: true,
+    }
+}
+
+/// 文字列に日本語（ひらがな・カタカナ・漢字）が含まれるかどうかを判定する
+fn contains_japanese(text: &str) -> bool {
+    text.chars().any(|c| {
+        matches!(c,
+            '\u{3040}'..='\u{309F}' // ひらがな
+            | '\u{30A0}'..='\u{30FF}' // カタカナ
+            | '\u{31F0}'..='\u{31FF}' // カタカナ拡張
+            | '\u{FF66}'..='\u{FF9F}' // 半角カタカナ
+            | '\u{4E00}'..='\u{9FFF}' // CJK統合漢字
+            | '\u{3400}'..='\u{4DBF}' // CJK統合漢字拡張A
+        )
+    })
+}
@@ -2,10 +2,17 @@
 //!
 //! チャットメッセージとスーパーチャットの履歴を取得するためのTauriコマンドを提供する
 
-use crate::database;
+use crate::database::{self, MessageHistoryFilter, EDITOR_STREAMER};
+use crate::db_models::{
+    ConnectionLog, Message, MessageEdit, RejectedSuperchat, Session, ViewerCountSample,
+};
+use crate::language::normalize_language_filter;
 use crate::state::AppState;
-use crate::types::SerializableMessageForStreamer;
+use crate::stream_sessions;
+use crate::types::{OutgoingMessage, SerializableMessageForStreamer};
+use crate::ws_server::connection_manager::global::get_manager;
 use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
 use tauri::State;
 
 /// メッセージ履歴取得のパラメータ構造体
@@ -15,6 +22,10 @@ pub struct GetMessageHistoryParams {
     pub offset: Option<i64>,
     pub session_id: Option<String>,
     pub sort_asc: Option<bool>,
+    pub channel: Option<String>,
+    pub language: Option<String>,
+    pub superchat_only: Option<bool>,
+    pub highlighted_only: Option<bool>,
 }
 
 /// メッセージ履歴を取得するTauriコマンド
@@ -27,6 +38,10 @@ pub struct GetMessageHistoryParams {
 /// * `offset` - 結果セットのオフセット (ページネーション用、0以上)
 /// * `session_id` - 取得対象のセッションID（指定しない場合は全セッション）
 /// * `sort_asc` - ソート順（true: 昇順、false: 降順、デフォルトtrue）
+/// * `channel` - 取得対象のチャンネル（指定しない場合は全チャンネル）
+/// * `language` - 取得対象の言語（ISO 639-1、例: "ja", "en"。指定しない場合は全言語）
+/// * `superchat_only` - スーパーチャットのみを取得するかどうか（デフォルトfalse）
+/// * `highlighted_only` - ハイライトされたメッセージのみを取得するかどうか（デフォルトfalse）
 /// * `app_state` - アプリケーションの状態
 ///
 /// # 戻り値
@@ -45,6 +60,9 @@ pub async fn get_message_history(
     let limit_value = params.limit.unwrap_or(100);
     let offset_value = params.offset.unwrap_or(0);
     let sort_asc_value = params.sort_asc.unwrap_or(true);
+    let superchat_only = params.superchat_only.unwrap_or(false);
+    let highlighted_only = params.highlighted_only.unwrap_or(false);
+    let language = normalize_language_filter(params.language.as_deref());
 
     // パラメータログ
     if params.session_id.is_some() {
@@ -75,40 +93,25 @@ pub async fn get_message_history(
     };
 
     // データベースからメッセージを取得
-    let messages = match params.session_id {
-        Some(sid) => {
-            // セッションIDが指定されている場合、そのセッションのメッセージのみを取得
-            database::get_messages_by_session_id_with_options(
-                &db_pool,
-                &sid,
-                limit_value,
-                Some(offset_value),
-                sort_asc_value,
-            )
+    // ページングで件数が欠けないよう、全ての条件をSQLで絞り込む
+    let filter = MessageHistoryFilter {
+        session_id: params.session_id.as_deref(),
+        channel: params.channel.as_deref(),
+        language: language.as_deref(),
+        superchat_only,
+        highlighted_only,
+    };
+    let messages =
+        database::get_message_history(&db_pool, &filter, limit_value, offset_value, sort_asc_value)
             .await
             .map_err(|e| {
                 let error_msg = format!(
-                    "セッション別メッセージ取得中にデータベースエラーが発生しました: {}",
+                    "メッセージ履歴の取得中にデータベースエラーが発生しました: {}",
                     e
                 );
                 eprintln!("エラー: {}", error_msg);
                 error_msg
-            })?
-        }
-        None => {
-            // セッションIDが指定されていない場合、全メッセージを取得
-            database::fetch_messages(&db_pool, limit_value, offset_value)
-                .await
-                .map_err(|e| {
-                    let error_msg = format!(
-                        "メッセージ履歴の取得中にデータベースエラーが発生しました: {}",
-                        e
-                    );
-                    eprintln!("エラー: {}", error_msg);
-                    error_msg
-                })?
-        }
-    };
+            })?;
 
     // Message型からSerializableMessageForStreamer型に変換
     let serializable_messages: Vec<SerializableMessageForStreamer> = messages
@@ -121,16 +124,14 @@ pub async fn get_message_history(
 
 /// 現在アクティブなセッションIDを取得するTauriコマンド
 ///
+/// 同時配信でチャンネルごとのセッションがある場合は、サーバー起動時に作成したセッションを返します。
+///
 /// @return 現在のセッションID、またはサーバーが起動していない場合はNull
 #[tauri::command]
 pub async fn get_current_session_id(
     app_state: State<'_, AppState>,
 ) -> Result<Option<String>, String> {
-    let lock = app_state
-        .current_session_id
-        .lock()
-        .map_err(|e| e.to_string())?;
-    let result = lock.clone();
+    let result = stream_sessions::primary_session_id(&app_state);
     println!("get_current_session_id の戻り値: {:?}", result);
     Ok(result)
 }
@@ -146,8 +147,29 @@ pub struct SessionInfo {
     pub started_at: String,
     /// セッション終了日時（ISO 8601形式の文字列、終了していない場合はNone）
     pub ended_at: Option<String>,
+    /// 配信者が付けたタイトル・メモ（未設定の場合はNone）
+    pub title: Option<String>,
+    /// 配信時間（秒、終了していない場合や時刻を解釈できない場合はNone）
+    pub duration_seconds: Option<i64>,
 }
 
+impl From<Session> for SessionInfo {
+    fn from(session: Session) -> Self {
+        let duration_seconds = database::session_duration(&session)
+            .and_then(|duration| i64::try_from(duration.as_secs()).ok());
+        Self {
+            duration_seconds,
+            id: session.id,
+            started_at: session.started_at,
+            ended_at: session.ended_at,
+            title: session.title,
+        }
+    }
+}
+
+/// セッションのタイトル・メモの最大文字数
+pub const MAX_SESSION_TITLE_LENGTH: usize = 100;
+
 /// 全てのユニークなセッションIDを取得するTauriコマンド
 ///
 /// @return 過去のセッションIDのリスト
@@ -221,14 +243,8 @@ pub async fn get_all_sessions_info(
             println!("取得されたセッション数: {}", sessions.len());
 
             // Session型からSessionInfo型に変換
-            let session_infos: Vec<SessionInfo> = sessions
-                .into_iter()
-                .map(|session| SessionInfo {
-                    id: session.id,
-                    started_at: session.started_at,
-                    ended_at: session.ended_at,
-                })
-                .collect();
+            let session_infos: Vec<SessionInfo> =
+                sessions.into_iter().map(SessionInfo::from).collect();
 
             Ok(session_infos)
         }
@@ -242,3 +258,1285 @@ pub async fn get_all_sessions_info(
         }
     }
 }
+
+/// セッション時刻更新の結果を表す構造体
+#[derive(Serialize, Debug, Clone)]
+pub struct UpdateSessionTimesResult {
+    /// 更新後のセッション情報
+    pub session: SessionInfo,
+    /// 新しい期間外のタイムスタンプを持つメッセージ数
+    ///
+    /// 期間外のメッセージは削除・移動せずそのままセッションに残ります。
+    pub messages_outside_range: i64,
+    /// 期間外のメッセージがある場合の警告メッセージ
+    pub warning: Option<String>,
+}
+
+/// セッションの開始・終了時刻を修正するTauriコマンド
+///
+/// サーバー起動の遅れや停止忘れで記録時刻がずれたセッションの時刻を修正します。
+/// 修正後の期間外にタイムスタンプを持つメッセージは削除・移動せずにセッションに残し、
+/// その件数と警告を結果として返します。
+///
+/// # 引数
+/// * `session_id` - 修正対象のセッションID
+/// * `started_at` - 新しい開始時刻（ISO 8601形式の文字列）
+/// * `ended_at` - 新しい終了時刻（ISO 8601形式の文字列）
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<UpdateSessionTimesResult, String>` - 成功時は更新結果、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - 現在アクティブなセッションを指定した場合
+/// - 時刻の形式が不正、開始時刻が終了時刻以降、または終了時刻が未来の場合
+/// - 指定されたセッションが存在しない場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn update_session_times(
+    session_id: String,
+    started_at: String,
+    ended_at: String,
+    app_state: State<'_, AppState>,
+) -> Result<UpdateSessionTimesResult, String> {
+    // 配信中のセッションは終了時刻が確定していないため編集を拒否する
+    let is_active = stream_sessions::is_active_session(
+        &*app_state
+            .current_session_id
+            .lock()
+            .map_err(|e| format!("セッションIDのロックに失敗しました: {}", e))?,
+        &session_id,
+    );
+    if is_active {
+        return Err(
+            "配信中のセッションの時刻は編集できません。配信を終了してから修正してください。"
+                .to_string(),
+        );
+    }
+
+    let (started_at, ended_at) =
+        validate_session_times(&started_at, &ended_at, chrono::Utc::now())?;
+
+    let db_pool = get_db_pool(&app_state)?;
+
+    let updated = database::update_session_times(&db_pool, &session_id, &started_at, &ended_at)
+        .await
+        .map_err(|e| {
+            let error_msg = format!(
+                "セッション時刻の更新中にデータベースエラーが発生しました: {}",
+                e
+            );
+            eprintln!("エラー: {}", error_msg);
+            error_msg
+        })?;
+    if !updated {
+        return Err(format!("セッションが見つかりません: {}", session_id));
+    }
+
+    let messages_outside_range =
+        database::count_messages_outside_range(&db_pool, &session_id, &started_at, &ended_at)
+            .await
+            .map_err(|e| {
+                let error_msg = format!(
+                    "期間外メッセージの確認中にデータベースエラーが発生しました: {}",
+                    e
+                );
+                eprintln!("エラー: {}", error_msg);
+                error_msg
+            })?;
+
+    let warning = (messages_outside_range > 0).then(|| {
+        let warning = format!(
+            "{}件のメッセージが修正後のセッション期間外にあります。メッセージはセッションに残ります。",
+            messages_outside_range
+        );
+        println!("警告: {}", warning);
+        warning
+    });
+
+    let session = database::get_session(&db_pool, &session_id)
+        .await
+        .map_err(|e| {
+            let error_msg = format!(
+                "セッション情報の取得中にデータベースエラーが発生しました: {}",
+                e
+            );
+            eprintln!("エラー: {}", error_msg);
+            error_msg
+        })?
+        .ok_or_else(|| format!("セッションが見つかりません: {}", session_id))?;
+
+    Ok(UpdateSessionTimesResult {
+        session: session.into(),
+        messages_outside_range,
+        warning,
+    })
+}
+
+/// セッションにタイトル・メモを付けるTauriコマンド
+///
+/// 過去のセッションを開始日時以外でも区別できるよう、任意のセッションにタイトルを設定します。
+/// 配信中のセッションにも設定できます。
+///
+/// # 引数
+/// * `session_id` - 対象のセッションID
+/// * `title` - 新しいタイトル（空文字の場合はタイトルを削除）
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<SessionInfo, String>` - 成功時は更新後のセッション情報、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - タイトルが最大文字数を超える場合
+/// - 指定されたセッションが存在しない場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn set_session_title(
+    session_id: String,
+    title: String,
+    app_state: State<'_, AppState>,
+) -> Result<SessionInfo, String> {
+    let title = title.trim();
+    if title.chars().count() > MAX_SESSION_TITLE_LENGTH {
+        return Err(format!(
+            "タイトルは{}文字以内で指定してください",
+            MAX_SESSION_TITLE_LENGTH
+        ));
+    }
+    let title = (!title.is_empty()).then_some(title);
+
+    let db_pool = get_db_pool(&app_state)?;
+    let db_error = |e: sqlx::Error| {
+        let error_msg = format!(
+            "セッションタイトルの更新中にデータベースエラーが発生しました: {}",
+            e
+        );
+        eprintln!("エラー: {}", error_msg);
+        error_msg
+    };
+
+    if !database::set_session_title(&db_pool, &session_id, title)
+        .await
+        .map_err(db_error)?
+    {
+        return Err(format!("セッションが見つかりません: {}", session_id));
+    }
+    println!(
+        "セッション {} のタイトルを更新しました: {:?}",
+        session_id, title
+    );
+
+    database::get_session(&db_pool, &session_id)
+        .await
+        .map_err(db_error)?
+        .map(SessionInfo::from)
+        .ok_or_else(|| format!("セッションが見つかりません: {}", session_id))
+}
+
+/// セッション削除の結果を表す構造体
+#[derive(Serialize, Debug, Clone)]
+pub struct DeleteSessionResult {
+    /// 削除したセッションID
+    pub session_id: String,
+    /// セッションと共に削除したメッセージ数
+    pub deleted_messages: u64,
+}
+
+/// セッションを削除するTauriコマンド
+///
+/// テスト配信や誤って作成したセッションを履歴から削除します。
+/// セッションに紐づくメッセージも同時に削除されます。
+///
+/// # 引数
+/// * `session_id` - 削除するセッションID
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<DeleteSessionResult, String>` - 成功時は削除結果、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - 現在アクティブなセッションを指定した場合
+/// - 指定されたセッションが存在しない場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn delete_session(
+    session_id: String,
+    app_state: State<'_, AppState>,
+) -> Result<DeleteSessionResult, String> {
+    // 配信中のセッションはメッセージの保存先のため削除を拒否する
+    let is_active = stream_sessions::is_active_session(
+        &*app_state
+            .current_session_id
+            .lock()
+            .map_err(|e| format!("セッションIDのロックに失敗しました: {}", e))?,
+        &session_id,
+    );
+    if is_active {
+        return Err(
+            "配信中のセッションは削除できません。配信を終了してから削除してください。".to_string(),
+        );
+    }
+
+    let db_pool = get_db_pool(&app_state)?;
+
+    let deleted_messages = database::delete_session(&db_pool, &session_id)
+        .await
+        .map_err(|e| {
+            let error_msg = format!(
+                "セッションの削除中にデータベースエラーが発生しました: {}",
+                e
+            );
+            eprintln!("エラー: {}", error_msg);
+            error_msg
+        })?
+        .ok_or_else(|| format!("セッションが見つかりません: {}", session_id))?;
+
+    println!(
+        "セッションを削除しました: {} (メッセージ{}件)",
+        session_id, deleted_messages
+    );
+    // セッション行を含めた削除行数を自動VACUUMの判断に使用する
+    crate::db_vacuum::record_deleted_rows(&app_state, deleted_messages + 1);
+    Ok(DeleteSessionResult {
+        session_id,
+        deleted_messages,
+    })
+}
+
+/// 配信者がメッセージを編集するTauriコマンド
+///
+/// 誤字の修正や不適切な表現の差し替えに使用します。編集前後の内容を編集履歴に記録し、
+/// 接続中のクライアントに編集後の内容を通知します（視聴者フロントは「（編集済み）」を表示する）。
+///
+/// # 引数
+/// * `message_id` - 編集するメッセージのID
+/// * `content` - 編集後の内容
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<MessageEdit, String>` - 成功時は記録した編集履歴、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - 編集後の内容が空の場合
+/// - データベース接続が初期化されていない場合
+/// - メッセージが存在しない場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn edit_message(
+    message_id: String,
+    content: String,
+    app_state: State<'_, AppState>,
+) -> Result<MessageEdit, String> {
+    let content = content.trim();
+    if content.is_empty() {
+        return Err("編集後の内容を入力してください".to_string());
+    }
+    let db_pool = get_db_pool(&app_state)?;
+
+    let edit = database::record_message_edit(&db_pool, &message_id, content, EDITOR_STREAMER)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => format!("メッセージが見つかりません: {}", message_id),
+            e => format!(
+                "メッセージの編集中にデータベースエラーが発生しました: {}",
+                e
+            ),
+        })?;
+
+    let notice = OutgoingMessage::MessageEdited {
+        id: message_id.clone(),
+        message: edit.new_content.clone(),
+        is_edited: true,
+    };
+    match serde_json::to_string(&notice) {
+        Ok(json) => get_manager().broadcast(&json),
+        Err(e) => eprintln!("メッセージ編集通知のシリアライズに失敗: {}", e),
+    }
+
+    println!("メッセージを編集しました: {}", message_id);
+    Ok(edit)
+}
+
+/// メッセージの編集履歴を取得するTauriコマンド
+///
+/// モデレーション時に、編集されたメッセージの内容がどう変わったかを確認するために使用します。
+///
+/// # 引数
+/// * `message_id` - 対象メッセージのID
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<Vec<MessageEdit>, String>` - 成功時は編集履歴（古い順）、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - データベース接続が初期化されていない場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn get_message_edit_history(
+    message_id: String,
+    app_state: State<'_, AppState>,
+) -> Result<Vec<MessageEdit>, String> {
+    let db_pool = get_db_pool(&app_state)?;
+
+    database::get_message_edit_history(&db_pool, &message_id)
+        .await
+        .map_err(|e| format!("編集履歴の取得中にデータベースエラーが発生しました: {}", e))
+}
+
+/// メッセージのハイライト状態を反転するTauriコマンド
+///
+/// 配信中に後で見返したいメッセージへ印を付けるために使用します。
+///
+/// # 引数
+/// * `message_id` - 対象メッセージのID
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<bool, String>` - 成功時は反転後のハイライト状態、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - データベース接続が初期化されていない場合
+/// - メッセージが存在しない場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn toggle_message_highlight(
+    message_id: String,
+    app_state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let db_pool = get_db_pool(&app_state)?;
+    let db_error = |e: sqlx::Error| {
+        format!(
+            "ハイライトの更新中にデータベースエラーが発生しました: {}",
+            e
+        )
+    };
+
+    let highlighted = database::get_message_highlight(&db_pool, &message_id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| format!("メッセージが見つかりません: {}", message_id))?;
+    if !database::set_message_highlight(&db_pool, &message_id, !highlighted)
+        .await
+        .map_err(db_error)?
+    {
+        return Err(format!("メッセージが見つかりません: {}", message_id));
+    }
+
+    println!(
+        "メッセージのハイライトを{}しました: {}",
+        if highlighted { "解除" } else { "設定" },
+        message_id
+    );
+    Ok(!highlighted)
+}
+
+/// メッセージを全文検索するTauriコマンド
+///
+/// メッセージ本文と表示名を大文字小文字を区別せずに部分一致で検索し、新しい順に返します。
+///
+/// # 引数
+/// * `query` - 検索文字列
+/// * `limit` - 取得するメッセージの最大数（省略時は100、最大1000）
+/// * `session_id` - 指定された場合はそのセッションのメッセージのみを検索
+/// * `superchat_only` - `true` の場合はスーパーチャットのみを検索
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<Vec<SerializableMessageForStreamer>, String>` - 成功時は検索に一致したメッセージ、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - 検索文字列が空の場合
+/// - データベース接続が初期化されていない場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn search_messages(
+    query: String,
+    limit: Option<i64>,
+    session_id: Option<String>,
+    superchat_only: bool,
+    app_state: State<'_, AppState>,
+) -> Result<Vec<SerializableMessageForStreamer>, String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err("検索文字列を指定してください".to_string());
+    }
+    let db_pool = get_db_pool(&app_state)?;
+
+    database::search_messages_with_options(
+        &db_pool,
+        query,
+        limit.unwrap_or(100),
+        session_id.as_deref(),
+        superchat_only,
+    )
+    .await
+    .map(|messages| {
+        messages
+            .into_iter()
+            .map(SerializableMessageForStreamer::from)
+            .collect()
+    })
+    .map_err(|e| {
+        format!(
+            "メッセージの検索中にデータベースエラーが発生しました: {}",
+            e
+        )
+    })
+}
+
+/// 接続ログを取得するTauriコマンド
+///
+/// 視聴者の接続・切断の記録を接続時刻の新しい順に返します。
+///
+/// # 引数
+/// * `session_id` - 指定された場合はそのセッション中の接続のみを取得
+/// * `limit` - 取得する最大件数（省略時は100、最大1000）
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<Vec<ConnectionLog>, String>` - 成功時は接続ログ、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - データベース接続が初期化されていない場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn get_connection_logs(
+    session_id: Option<String>,
+    limit: Option<i64>,
+    app_state: State<'_, AppState>,
+) -> Result<Vec<ConnectionLog>, String> {
+    let db_pool = get_db_pool(&app_state)?;
+
+    database::get_connection_logs(&db_pool, session_id.as_deref(), limit.unwrap_or(100))
+        .await
+        .map_err(|e| format!("接続ログの取得中にデータベースエラーが発生しました: {}", e))
+}
+
+/// セッション中の各クライアントの送信メッセージ数ランキングを取得するTauriコマンド
+///
+/// 切断時に記録した送信メッセージ数の多い順に返します。
+/// 配信中のセッションの場合、接続中のクライアントは現在の送信メッセージ数で集計します。
+///
+/// # 引数
+/// * `session_id` - 配信セッションID
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<Vec<ConnectionLog>, String>` - 成功時は接続ログ（送信メッセージ数の多い順）、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - データベース接続が初期化されていない場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn get_client_activity_summary(
+    session_id: String,
+    app_state: State<'_, AppState>,
+) -> Result<Vec<ConnectionLog>, String> {
+    let db_pool = get_db_pool(&app_state)?;
+
+    let mut summary = database::get_client_activity_summary(&db_pool, &session_id)
+        .await
+        .map_err(|e| {
+            format!(
+                "クライアントの送信数の集計中にデータベースエラーが発生しました: {}",
+                e
+            )
+        })?;
+
+    // 接続中のクライアントは切断時まで送信数が記録されないため、現在の値で補う
+    let is_current_session = app_state
+        .current_session_id
+        .lock()
+        .is_ok_and(|sessions| stream_sessions::is_active_session(&sessions, &session_id));
+    if is_current_session {
+        let clients = crate::ws_server::get_manager().get_all_clients();
+        for log in summary
+            .iter_mut()
+            .filter(|log| log.disconnected_at.is_none())
+        {
+            if let Some(client) = clients.iter().find(|client| client.id == log.client_id) {
+                log.messages_sent = client.messages_sent as i64;
+            }
+        }
+        summary.sort_by_key(|log| std::cmp::Reverse(log.messages_sent));
+    }
+
+    Ok(summary)
+}
+
+/// 終了したセッションの視聴者数の推移を取得するTauriコマンド
+///
+/// セッション終了時に保存された視聴者数の推移を時刻順に返します。
+///
+/// # 引数
+/// * `session_id` - 配信セッションID
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<Vec<ViewerCountSample>, String>` - 成功時は視聴者数の推移（古い順）、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - データベース接続が初期化されていない場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn get_session_viewer_count_history(
+    session_id: String,
+    app_state: State<'_, AppState>,
+) -> Result<Vec<ViewerCountSample>, String> {
+    let db_pool = get_db_pool(&app_state)?;
+
+    database::get_viewer_count_history(&db_pool, &session_id)
+        .await
+        .map_err(|e| {
+            format!(
+                "視聴者数の推移の取得中にデータベースエラーが発生しました: {}",
+                e
+            )
+        })
+}
+
+/// 配信しなかったスーパーチャットを取得するTauriコマンド
+///
+/// 受け付けていないコインなどの理由で配信しなかったスーパーチャットを記録時刻の新しい順に返します。
+/// 視聴者が送金済みの場合に、返金などの対応のためトランザクションと照合できます。
+///
+/// # 引数
+/// * `session_id` - 指定された場合はそのセッション中の記録のみを取得
+/// * `limit` - 取得する最大件数（省略時は100、最大1000）
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<Vec<RejectedSuperchat>, String>` - 成功時は配信しなかったスーパーチャット、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - データベース接続が初期化されていない場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn get_rejected_superchats(
+    session_id: Option<String>,
+    limit: Option<i64>,
+    app_state: State<'_, AppState>,
+) -> Result<Vec<RejectedSuperchat>, String> {
+    let db_pool = get_db_pool(&app_state)?;
+
+    database::get_rejected_superchats(&db_pool, session_id.as_deref(), limit.unwrap_or(100))
+        .await
+        .map_err(|e| {
+            format!(
+                "配信しなかったスーパーチャットの取得中にデータベースエラーが発生しました: {}",
+                e
+            )
+        })
+}
+
+/// セッション時刻の文字列を検証し、UTCのRFC3339形式に正規化する
+///
+/// # 引数
+/// * `started_at` - 開始時刻（ISO 8601形式の文字列）
+/// * `ended_at` - 終了時刻（ISO 8601形式の文字列）
+/// * `now` - 現在時刻（未来の時刻の判定に使用）
+///
+/// # 戻り値
+/// * `Result<(String, String), String>` - 成功時は正規化した開始・終了時刻、エラー時はエラーメッセージ
+fn validate_session_times(
+    started_at: &str,
+    ended_at: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<(String, String), String> {
+    let parse = |label: &str, value: &str| {
+        chrono::DateTime::parse_from_rfc3339(value.trim())
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .map_err(|e| {
+                format!(
+                    "{}の形式が不正です（ISO 8601形式で指定してください）: {}",
+                    label, e
+                )
+            })
+    };
+
+    let started = parse("開始時刻", started_at)?;
+    let ended = parse("終了時刻", ended_at)?;
+
+    if started >= ended {
+        return Err("開始時刻は終了時刻より前である必要があります".to_string());
+    }
+    if ended > now {
+        return Err("終了時刻に未来の時刻は指定できません".to_string());
+    }
+
+    Ok((started.to_rfc3339(), ended.to_rfc3339()))
+}
+
+/// メッセージエクスポートのオプション構造体
+#[derive(Deserialize, Debug, Default)]
+pub struct ExportMessagesOptions {
+    /// 出力形式（"markdown" または "html"、デフォルトは "markdown"）
+    pub format: Option<String>,
+    /// スーパーチャットのみを出力するかどうか（デフォルトfalse）
+    pub superchat_only: Option<bool>,
+    /// この金額以上のスーパーチャットのみを出力する（指定時は通常チャットを除外）
+    pub min_amount: Option<f64>,
+}
+
+/// エクスポート出力形式
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExportFormat {
+    Markdown,
+    Html,
+}
+
+impl ExportFormat {
+    /// 文字列から出力形式を解決する
+    fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("") | Some("markdown") | Some("md") => Ok(ExportFormat::Markdown),
+            Some("html") => Ok(ExportFormat::Html),
+            Some(other) => Err(format!("サポートされていない出力形式です: {}", other)),
+        }
+    }
+}
+
+/// セッションのメッセージをマークダウン/HTML形式でエクスポートするTauriコマンド
+///
+/// 配信のハイライト共有用に、メッセージを読みやすい形式でファイルへ書き出します。
+/// スーパーチャットは金額を強調して出力し、各メッセージには時刻を付与します。
+///
+/// # 引数
+/// * `session_id` - エクスポート対象のセッションID
+/// * `path` - 出力先ファイルパス
+/// * `options` - 出力形式やフィルタのオプション
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<usize, String>` - 成功時は出力したメッセージ数、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - データベース接続が初期化されていない場合
+/// - 出力形式が不正な場合
+/// - ファイルの書き込みに失敗した場合
+#[tauri::command]
+pub async fn export_messages_markdown(
+    session_id: String,
+    path: String,
+    options: Option<ExportMessagesOptions>,
+    app_state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let options = options.unwrap_or_default();
+    let format = ExportFormat::parse(options.format.as_deref())?;
+    let superchat_only = options.superchat_only.unwrap_or(false);
+
+    println!(
+        "メッセージエクスポート開始: session_id={}, format={:?}, path={}",
+        session_id, format, path
+    );
+
+    let db_pool = get_db_pool(&app_state)?;
+
+    let messages = database::get_all_messages_by_session_id(&db_pool, &session_id)
+        .await
+        .map_err(|e| {
+            let error_msg = format!(
+                "エクスポート対象メッセージの取得中にデータベースエラーが発生しました: {}",
+                e
+            );
+            eprintln!("エラー: {}", error_msg);
+            error_msg
+        })?;
+
+    // フィルタを適用
+    let filtered: Vec<Message> = messages
+        .into_iter()
+        .filter(|msg| {
+            let superchat = is_superchat(msg);
+            if (superchat_only || options.min_amount.is_some()) && !superchat {
+                return false;
+            }
+            match options.min_amount {
+                Some(min) => msg.amount.unwrap_or(0.0) >= min,
+                None => true,
+            }
+        })
+        .collect();
+
+    let content = match format {
+        ExportFormat::Markdown => render_messages_markdown(&session_id, &filtered),
+        ExportFormat::Html => render_messages_html(&session_id, &filtered),
+    };
+
+    std::fs::write(&path, content).map_err(|e| {
+        let error_msg = format!(
+            "エクスポートファイルの書き込みに失敗しました ({}): {}",
+            path, e
+        );
+        eprintln!("エラー: {}", error_msg);
+        error_msg
+    })?;
+
+    println!(
+        "{}件のメッセージをエクスポートしました: {}",
+        filtered.len(),
+        path
+    );
+
+    Ok(filtered.len())
+}
+
+/// CSVエクスポートのヘッダー行
+const CSV_HEADER: &str = "id,timestamp,display_name,message,amount,coin,tx_hash,wallet_address";
+
+/// セッションのメッセージをCSV形式でエクスポートするTauriコマンド
+///
+/// 売上集計をスプレッドシートに取り込めるよう、セッションの全メッセージを
+/// 時系列順にヘッダー付きのCSV（RFC 4180準拠）でファイルへ書き出します。
+///
+/// # 引数
+/// * `session_id` - エクスポート対象のセッションID
+/// * `file_path` - 出力先ファイルパス
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<usize, String>` - 成功時は書き出したメッセージの行数（ヘッダーを除く）、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - データベース接続が初期化されていない場合
+/// - データベース操作中にエラーが発生した場合
+/// - ファイルの書き込みに失敗した場合
+#[tauri::command]
+pub async fn export_session_to_csv(
+    session_id: String,
+    file_path: String,
+    app_state: State<'_, AppState>,
+) -> Result<usize, String> {
+    println!(
+        "CSVエクスポート開始: session_id={}, file_path={}",
+        session_id, file_path
+    );
+
+    let db_pool = get_db_pool(&app_state)?;
+
+    // 先頭から全件を昇順で取得（SQLiteでは負のLIMITは件数制限なしを意味する）
+    let messages = database::get_messages_by_session_id_with_options(
+        &db_pool,
+        &session_id,
+        -1,
+        Some(0),
+        true,
+        None,
+        None,
+    )
+    .await
+    .map_err(|e| {
+        let error_msg = format!(
+            "エクスポート対象メッセージの取得中にデータベースエラーが発生しました: {}",
+            e
+        );
+        eprintln!("エラー: {}", error_msg);
+        error_msg
+    })?;
+
+    std::fs::write(&file_path, render_messages_csv(&messages)).map_err(|e| {
+        let error_msg = format!("CSVファイルの書き込みに失敗しました ({}): {}", file_path, e);
+        eprintln!("エラー: {}", error_msg);
+        error_msg
+    })?;
+
+    println!(
+        "{}件のメッセージをCSVにエクスポートしました: {}",
+        messages.len(),
+        file_path
+    );
+
+    Ok(messages.len())
+}
+
+/// セッションのJSONエクスポートの形式バージョン
+pub const SESSION_EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// セッションのJSONエクスポートを表す構造体
+///
+/// PCの移行などで過去ログを持ち運ぶため、セッション情報と全メッセージを1つのファイルにまとめます。
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SessionExport {
+    /// ファイル形式のバージョン
+    pub format_version: u32,
+    /// エクスポートした時刻（ISO 8601形式の文字列）
+    pub exported_at: String,
+    /// セッション情報
+    pub session: Session,
+    /// セッションの全メッセージ（時系列順）
+    pub messages: Vec<Message>,
+}
+
+/// インポート時に同じIDのセッション・メッセージが存在する場合の扱い
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportConflictMode {
+    /// 既存のものを残してスキップする
+    #[default]
+    Skip,
+    /// ファイルの内容で上書きする
+    Overwrite,
+}
+
+/// セッションのJSONインポートの結果を表す構造体
+#[derive(Serialize, Debug, Clone)]
+pub struct ImportSessionResult {
+    /// インポートしたセッションID
+    pub session_id: String,
+    /// セッション情報を作成・上書きしたかどうか（既存のセッションを流用した場合はfalse）
+    pub session_written: bool,
+    /// 復元したメッセージ数
+    pub imported_messages: u64,
+    /// IDの衝突によりスキップしたメッセージ数
+    pub skipped_messages: u64,
+}
+
+/// セッションをJSONファイルにエクスポートするTauriコマンド
+///
+/// セッション情報と全メッセージを1つのJSONファイルにまとめて書き出します。
+/// 書き出したファイルは `import_session_json` で復元できます。
+///
+/// # 引数
+/// * `session_id` - エクスポート対象のセッションID
+/// * `file_path` - 出力先ファイルパス
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<usize, String>` - 成功時は書き出したメッセージ数、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - データベース接続が初期化されていない場合
+/// - 指定されたセッションが存在しない場合
+/// - ファイルの書き込みに失敗した場合
+#[tauri::command]
+pub async fn export_session_json(
+    session_id: String,
+    file_path: String,
+    app_state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let db_pool = get_db_pool(&app_state)?;
+    let db_error = |e: sqlx::Error| {
+        let error_msg = format!(
+            "セッションのエクスポート中にデータベースエラーが発生しました: {}",
+            e
+        );
+        eprintln!("エラー: {}", error_msg);
+        error_msg
+    };
+
+    let session = database::get_session(&db_pool, &session_id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| format!("セッションが見つかりません: {}", session_id))?;
+    let messages = database::get_all_messages_by_session_id(&db_pool, &session_id)
+        .await
+        .map_err(db_error)?;
+    let message_count = messages.len();
+
+    let export = SessionExport {
+        format_version: SESSION_EXPORT_FORMAT_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        session,
+        messages,
+    };
+    let json = serde_json::to_string_pretty(&export)
+        .map_err(|e| format!("セッションのシリアライズに失敗しました: {}", e))?;
+    std::fs::write(&file_path, json).map_err(|e| {
+        let error_msg = format!(
+            "JSONファイルの書き込みに失敗しました ({}): {}",
+            file_path, e
+        );
+        eprintln!("エラー: {}", error_msg);
+        error_msg
+    })?;
+
+    println!(
+        "セッション {} をJSONにエクスポートしました: {} (メッセージ{}件)",
+        session_id, file_path, message_count
+    );
+    Ok(message_count)
+}
+
+/// JSONファイルからセッションをインポートするTauriコマンド
+///
+/// `export_session_json` で書き出したファイルを読み込み、セッションとメッセージを復元します。
+/// 同じIDのセッション・メッセージが既に存在する場合は `on_conflict` に従って上書きまたはスキップします。
+/// スキップの場合、既存のセッションはそのまま流用し、IDが衝突しないメッセージのみを追加します。
+///
+/// # 引数
+/// * `file_path` - 読み込むJSONファイルのパス
+/// * `on_conflict` - IDが衝突した場合の扱い（"skip" または "overwrite"、デフォルトは "skip"）
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<ImportSessionResult, String>` - 成功時はインポート結果、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - ファイルの読み込みに失敗した場合
+/// - JSONが壊れている、または形式が異なる場合
+/// - 配信中のセッションを上書きしようとした場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn import_session_json(
+    file_path: String,
+    on_conflict: Option<ImportConflictMode>,
+    app_state: State<'_, AppState>,
+) -> Result<ImportSessionResult, String> {
+    let on_conflict = on_conflict.unwrap_or_default();
+    let content = std::fs::read_to_string(&file_path).map_err(|e| {
+        format!(
+            "JSONファイルの読み込みに失敗しました ({}): {}",
+            file_path, e
+        )
+    })?;
+    let export = parse_session_export(&content)
+        .map_err(|e| format!("JSONファイルの解析に失敗しました ({}): {}", file_path, e))?;
+    let session_id = export.session.id.clone();
+
+    // 配信中のセッションはメッセージの保存先のため上書きを拒否する
+    let is_active = stream_sessions::is_active_session(
+        &*app_state
+            .current_session_id
+            .lock()
+            .map_err(|e| format!("セッションIDのロックに失敗しました: {}", e))?,
+        &session_id,
+    );
+    if is_active && on_conflict == ImportConflictMode::Overwrite {
+        return Err("配信中のセッションは上書きできません".to_string());
+    }
+
+    let db_pool = get_db_pool(&app_state)?;
+    let (session_written, imported_messages) = database::import_session(
+        &db_pool,
+        &export.session,
+        &export.messages,
+        on_conflict == ImportConflictMode::Overwrite,
+    )
+    .await
+    .map_err(|e| {
+        let error_msg = format!(
+            "セッションのインポート中にデータベースエラーが発生しました: {}",
+            e
+        );
+        eprintln!("エラー: {}", error_msg);
+        error_msg
+    })?;
+    let skipped_messages = export.messages.len() as u64 - imported_messages;
+
+    println!(
+        "セッション {} をインポートしました: 復元 {}件, スキップ {}件",
+        session_id, imported_messages, skipped_messages
+    );
+    Ok(ImportSessionResult {
+        session_id,
+        session_written,
+        imported_messages,
+        skipped_messages,
+    })
+}
+
+/// セッションのJSONエクスポートを解析し、形式を検証する
+fn parse_session_export(content: &str) -> Result<SessionExport, String> {
+    let export: SessionExport = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    if export.format_version > SESSION_EXPORT_FORMAT_VERSION {
+        return Err(format!(
+            "未対応のファイル形式です: バージョン{}",
+            export.format_version
+        ));
+    }
+    if export.session.id.trim().is_empty() {
+        return Err("セッションIDが空です".to_string());
+    }
+    Ok(export)
+}
+
+/// メッセージ一覧をヘッダー付きのCSV形式に変換する
+fn render_messages_csv(messages: &[Message]) -> String {
+    let mut output = String::new();
+    output.push_str(CSV_HEADER);
+    output.push_str("\r\n");
+
+    for msg in messages {
+        let fields = [
+            escape_csv(&msg.id),
+            escape_csv(&msg.timestamp.to_rfc3339()),
+            escape_csv(&msg.display_name),
+            escape_csv(&msg.content),
+            msg.amount
+                .map(|amount| amount.to_string())
+                .unwrap_or_default(),
+            escape_csv(msg.coin.as_deref().unwrap_or("")),
+            escape_csv(msg.tx_hash.as_deref().unwrap_or("")),
+            escape_csv(msg.wallet_address.as_deref().unwrap_or("")),
+        ];
+        output.push_str(&fields.join(","));
+        output.push_str("\r\n");
+    }
+
+    output
+}
+
+/// CSVのフィールドをRFC 4180に従ってエスケープする
+///
+/// 表計算ソフトで数式として実行されないよう、`=`・`+`・`-`・`@` で始まるフィールドには
+/// 先頭に `'` を付ける。
+/// カンマ・ダブルクオート・改行を含む場合はダブルクオートで囲み、
+/// フィールド内のダブルクオートは2つ重ねる。
+fn escape_csv(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", field)
+    } else {
+        field.to_string()
+    };
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+/// AppStateからデータベース接続プールを取得する
+pub(crate) fn get_db_pool(app_state: &AppState) -> Result<SqlitePool, String> {
+    let pool_guard = app_state.db_pool.lock().map_err(|e| {
+        let error_msg = format!("データベース接続プールのロックに失敗しました: {}", e);
+        eprintln!("エラー: {}", error_msg);
+        error_msg
+    })?;
+
+    match &*pool_guard {
+        Some(pool) => Ok(pool.clone()),
+        None => {
+            let error_msg =
+                "データベース接続が初期化されていません。アプリケーションを再起動してください。"
+                    .to_string();
+            eprintln!("エラー: {}", error_msg);
+            Err(error_msg)
+        }
+    }
+}
+
+/// メッセージがスーパーチャットかどうかを判定する
+pub(crate) fn is_superchat(msg: &Message) -> bool {
+    msg.amount.unwrap_or(0.0) > 0.0 && msg.coin.is_some()
+}
+
+/// メッセージの時刻を表示用の文字列に変換する（ローカルタイムゾーン）
+fn format_message_time(msg: &Message) -> String {
+    msg.timestamp
+        .with_timezone(&chrono::Local)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+/// マークダウンの特殊文字をエスケープする
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// HTMLの特殊文字をエスケープする
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// メッセージ一覧をマークダウン形式に変換する
+fn render_messages_markdown(session_id: &str, messages: &[Message]) -> String {
+    let mut output = String::new();
+    output.push_str("# SUIperCHAT メッセージログ\n\n");
+    output.push_str(&format!(
+        "- セッションID: `{}`\n",
+        escape_markdown(session_id)
+    ));
+    output.push_str(&format!("- メッセージ数: {}\n\n", messages.len()));
+
+    for msg in messages {
+        let time = format_message_time(msg);
+        let name = escape_markdown(&msg.display_name);
+        let content = escape_markdown(&msg.content);
+
+        if is_superchat(msg) {
+            output.push_str(&format!(
+                "- `{}` 💰**{} {}** **{}**: {}\n",
+                time,
+                msg.amount.unwrap_or(0.0),
+                escape_markdown(msg.coin.as_deref().unwrap_or("SUI")),
+                name,
+                content
+            ));
+        } else {
+            output.push_str(&format!("- `{}` **{}**: {}\n", time, name, content));
+        }
+    }
+
+    output
+}
+
+/// メッセージ一覧を自己完結したHTML形式に変換する
+fn render_messages_html(session_id: &str, messages: &[Message]) -> String {
+    let mut output = String::new();
+    output.push_str("<!DOCTYPE html>\n<html lang=\"ja\">\n<head>\n<meta charset=\"utf-8\">\n");
+    output.push_str("<title>SUIperCHAT メッセージログ</title>\n<style>\n");
+    output.push_str(
+        "body { font-family: sans-serif; background: #f5f7fa; color: #1f2933; margin: 2em; }\n\
+         .message { padding: 0.6em 0.9em; margin: 0.4em 0; border-radius: 6px; background: #ffffff; }\n\
+         .superchat { background: #fff4d6; border-left: 6px solid #f5a623; }\n\
+         .time { color: #7b8794; font-size: 0.85em; margin-right: 0.6em; }\n\
+         .name { font-weight: bold; margin-right: 0.4em; }\n\
+         .amount { font-weight: bold; color: #c05621; margin-right: 0.6em; }\n",
+    );
+    output.push_str("</style>\n</head>\n<body>\n");
+    output.push_str("<h1>SUIperCHAT メッセージログ</h1>\n");
+    output.push_str(&format!(
+        "<p>セッションID: <code>{}</code> / メッセージ数: {}</p>\n",
+        escape_html(session_id),
+        messages.len()
+    ));
+
+    for msg in messages {
+        let superchat = is_superchat(msg);
+        let class = if superchat {
+            "message superchat"
+        } else {
+            "message"
+        };
+        output.push_str(&format!("<div class=\"{}\">", class));
+        output.push_str(&format!(
+            "<span class=\"time\">{}</span>",
+            escape_html(&format_message_time(msg))
+        ));
+        if superchat {
+            output.push_str(&format!(
+                "<span class=\"amount\">💰{} {}</span>",
+                msg.amount.unwrap_or(0.0),
+                escape_html(msg.coin.as_deref().unwrap_or("SUI"))
+            ));
+        }
+        output.push_str(&format!(
+            "<span class=\"name\">{}</span><span class=\"content\">{}</span></div>\n",
+            escape_html(&msg.display_name),
+            escape_html(&msg.content)
+        ));
+    }
+
+    output.push_str("</body>\n</html>\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn test_message(display_name: &str, content: &str, amount: Option<f64>) -> Message {
+        Message {
+            id: "test-id".to_string(),
+            timestamp: Utc::now(),
+            display_name: display_name.to_string(),
+            content: content.to_string(),
+            amount,
+            coin: amount.map(|_| "SUI".to_string()),
+            tx_hash: None,
+            wallet_address: None,
+            session_id: Some("test-session".to_string()),
+            channel: None,
+            sequence: None,
+            language: None,
+            is_edited: false,
+            highlighted: false,
+        }
+    }
+
+    /// ## セッション時刻の検証をテスト
+    #[test]
+    fn test_validate_session_times() {
+        let now = Utc::now();
+
+        let (started, ended) =
+            validate_session_times("2024-01-01T19:00:00+09:00", "2024-01-01T12:00:00Z", now)
+                .unwrap();
+        assert_eq!(started, "2024-01-01T10:00:00+00:00");
+        assert_eq!(ended, "2024-01-01T12:00:00+00:00");
+
+        // 開始時刻が終了時刻以降
+        assert!(
+            validate_session_times("2024-01-01T12:00:00Z", "2024-01-01T12:00:00Z", now).is_err()
+        );
+        // 形式が不正
+        assert!(validate_session_times("2024/01/01 10:00", "2024-01-01T12:00:00Z", now).is_err());
+        // 終了時刻が未来
+        let future = (now + chrono::Duration::hours(1)).to_rfc3339();
+        assert!(validate_session_times("2024-01-01T10:00:00Z", &future, now).is_err());
+    }
+
+    /// ## HTML出力でメッセージ内容がエスケープされることをテスト
+    #[test]
+    fn test_render_messages_html_escapes_content() {
+        let messages = vec![test_message(
+            "<b>user</b>",
+            "<script>alert('x')</script>",
+            Some(10.0),
+        )];
+
+        let html = render_messages_html("session", &messages);
+
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;"));
+        assert!(html.contains("&lt;b&gt;user&lt;/b&gt;"));
+        assert!(html.contains("class=\"message superchat\""));
+    }
+
+    /// ## CSV出力のエスケープと金額の空欄をテスト
+    #[test]
+    fn test_render_messages_csv() {
+        let messages = vec![
+            test_message("視聴者A", "こんにちは", None),
+            test_message("視聴者,B", "改行\nと\"引用\"を含む", Some(1.5)),
+            test_message("=HYPERLINK(\"x\")", "@SUM(A1)", None),
+        ];
+
+        let csv = render_messages_csv(&messages);
+        let lines: Vec<&str> = csv.split("\r\n").collect();
+
+        assert_eq!(lines[0], CSV_HEADER);
+        assert!(lines[1].starts_with("test-id,"));
+        assert!(lines[1].ends_with(",視聴者A,こんにちは,,,,"));
+        assert!(lines[2].ends_with(",\"視聴者,B\",\"改行\nと\"\"引用\"\"を含む\",1.5,SUI,,"));
+        assert!(lines[3].ends_with(",\"'=HYPERLINK(\"\"x\"\")\",'@SUM(A1),,,,"));
+        assert_eq!(lines[4], "");
+    }
+
+    /// ## マークダウン出力の形式をテスト
+    #[test]
+    fn test_render_messages_markdown() {
+        let messages = vec![
+            test_message("視聴者A", "こんにちは", Some(0.0)),
+            test_message("視聴者B", "**応援**してます", Some(10.0)),
+        ];
+
+        let markdown = render_messages_markdown("session", &messages);
+
+        assert!(markdown.contains("**視聴者A**: こんにちは"));
+        assert!(markdown.contains("💰**10 SUI** **視聴者B**: \\*\\*応援\\*\\*してます"));
+    }
+}
@@ -0,0 +1,54 @@
+//! メンテナンス予告関連のコマンドモジュール
+//!
+//! サーバーの計画停止・再起動の前に、接続中の視聴者へカウントダウン付きの予告を
+//! 送るためのTauriコマンドを提供する
+
+use crate::maintenance::{self, MaintenanceAction};
+
+/// メンテナンスを予告するTauriコマンド
+///
+/// カウントダウン中は全クライアントに `type: "maintenance"` メッセージを定期的にブロードキャストします。
+/// 既に予告中の場合は新しい予告に置き換えます。
+///
+/// # 引数
+/// * `message` - 視聴者に表示する予告メッセージ
+/// * `countdown_secs` - メンテナンス開始までの秒数（1〜3600）
+/// * `action` - カウントダウン終了時の動作（"notify_only"・"stop_server"・"graceful_restart"、デフォルトは "notify_only"）
+/// * `app_handle` - Tauriアプリケーションハンドル
+///
+/// # 戻り値
+/// * `Result<(), String>` - 成功時は `Ok(())`、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - メッセージが空、または最大文字数を超える場合
+/// - カウントダウンの秒数が範囲外の場合
+/// - サーバーが起動していない場合
+#[tauri::command]
+pub fn schedule_maintenance(
+    message: String,
+    countdown_secs: u64,
+    action: Option<MaintenanceAction>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let message = maintenance::validate_request(&message, countdown_secs)?;
+    maintenance::schedule(
+        app_handle,
+        message,
+        countdown_secs,
+        action.unwrap_or_default(),
+    )
+}
+
+/// メンテナンス予告をキャンセルするTauriコマンド
+///
+/// 予告中だった場合は、全クライアントにキャンセル通知をブロードキャストします。
+///
+/// # 引数
+/// * `app_handle` - Tauriアプリケーションハンドル
+///
+/// # 戻り値
+/// * `Result<bool, String>` - 予告中だった場合は `true`
+#[tauri::command]
+pub fn cancel_maintenance(app_handle: tauri::AppHandle) -> Result<bool, String> {
+    Ok(maintenance::cancel(&app_handle))
+}
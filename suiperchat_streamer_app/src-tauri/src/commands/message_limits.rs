@@ -0,0 +1,71 @@
+//! 受信メッセージの文字数制限関連のコマンドモジュール
+//!
+//! 通常チャット・スーパーチャットの本文と表示名の最大文字数を設定・取得するためのTauriコマンドを提供する
+
+use crate::message_limits::{MessageLimits, OverLimitAction};
+use crate::state::AppState;
+use tauri::State;
+
+/// 受信メッセージの文字数制限を設定するTauriコマンド
+///
+/// 文字数はUTF-8のバイト数ではなく文字（`char`）単位で数えます。
+/// 省略した項目は現在の設定を維持します。
+///
+/// # 引数
+/// * `max_content_chars` - メッセージ本文の最大文字数（1以上）
+/// * `max_display_name_chars` - 表示名の最大文字数（1以上）
+/// * `action` - 上限を超えた場合の扱い（"truncate": 切り詰めて配信、"reject": 送信者にエラーを返す）
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<MessageLimits, String>` - 成功時は適用した設定、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - 最大文字数に0が指定された場合
+#[tauri::command]
+pub fn set_message_limits(
+    max_content_chars: Option<usize>,
+    max_display_name_chars: Option<usize>,
+    action: Option<OverLimitAction>,
+    app_state: State<'_, AppState>,
+) -> Result<MessageLimits, String> {
+    if max_content_chars == Some(0) || max_display_name_chars == Some(0) {
+        return Err("最大文字数は1以上で指定してください".to_string());
+    }
+
+    let mut limits = app_state
+        .message_limits
+        .lock()
+        .map_err(|e| format!("文字数制限の設定のロックに失敗しました: {}", e))?;
+    if let Some(max_content_chars) = max_content_chars {
+        limits.max_content_chars = max_content_chars;
+    }
+    if let Some(max_display_name_chars) = max_display_name_chars {
+        limits.max_display_name_chars = max_display_name_chars;
+    }
+    if let Some(action) = action {
+        limits.action = action;
+    }
+
+    println!(
+        "文字数制限を設定しました: 本文{}文字, 表示名{}文字 ({:?})",
+        limits.max_content_chars, limits.max_display_name_chars, limits.action
+    );
+    Ok(*limits)
+}
+
+/// 現在の受信メッセージの文字数制限を取得するTauriコマンド
+///
+/// # 引数
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<MessageLimits, String>` - 成功時は文字数制限の設定、エラー時はエラーメッセージ
+#[tauri::command]
+pub fn get_message_limits(app_state: State<'_, AppState>) -> Result<MessageLimits, String> {
+    app_state
+        .message_limits
+        .lock()
+        .map(|limits| *limits)
+        .map_err(|e| format!("文字数制限の設定のロックに失敗しました: {}", e))
+}
@@ -0,0 +1,85 @@
+//! マイルストーン関連のコマンドモジュール
+//!
+//! スーパーチャット総額のマイルストーンを設定・取得するためのTauriコマンドを提供する
+
+use crate::commands::history::get_db_pool;
+use crate::milestone::{self, MilestoneScope, MilestoneState};
+use crate::state::AppState;
+use crate::stream_sessions;
+use tauri::State;
+
+/// マイルストーンを設定するTauriコマンド
+///
+/// 総スパチャ額（SUI）が各マイルストーンに達したときに `milestone_reached` イベントを発行します。
+/// 設定時点の総額で既に超えているマイルストーンは達成済みとして扱われます。
+///
+/// # 引数
+/// * `amounts` - マイルストーン金額の一覧（例: `[50, 100]`）
+/// * `scope` - 集計範囲（"session": セッションごとにリセット、"cumulative": 累計、デフォルトは "session"）
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<MilestoneState, String>` - 成功時は設定後のマイルストーン状態、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - 0以下または数値でない金額が含まれる場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn set_milestones(
+    amounts: Vec<f64>,
+    scope: Option<MilestoneScope>,
+    app_state: State<'_, AppState>,
+) -> Result<MilestoneState, String> {
+    if let Some(invalid) = amounts.iter().find(|a| !a.is_finite() || **a <= 0.0) {
+        return Err(format!(
+            "マイルストーン金額には0より大きい値を指定してください: {}",
+            invalid
+        ));
+    }
+
+    let scope = scope.unwrap_or_default();
+    let session_id = stream_sessions::primary_session_id(&app_state);
+
+    // 現在の総額をDBから取得（既に超えているマイルストーンを発火させないため）
+    let total = match get_db_pool(&app_state) {
+        Ok(db_pool) => milestone::load_total(&db_pool, scope, session_id.as_deref())
+            .await
+            .map_err(|e| {
+                let error_msg = format!(
+                    "マイルストーン総額の取得中にデータベースエラーが発生しました: {}",
+                    e
+                );
+                eprintln!("エラー: {}", error_msg);
+                error_msg
+            })?,
+        Err(_) => 0.0,
+    };
+
+    let mut state = app_state
+        .milestones
+        .lock()
+        .map_err(|e| format!("マイルストーン状態のロックに失敗しました: {}", e))?;
+    state.configure(amounts, scope, total);
+    println!(
+        "マイルストーンを設定しました: {:?} (scope={:?}, total={})",
+        state.amounts, state.scope, state.total
+    );
+
+    Ok(state.clone())
+}
+
+/// 現在のマイルストーン設定と達成状況を取得するTauriコマンド
+///
+/// # 引数
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<MilestoneState, String>` - 成功時はマイルストーン状態、エラー時はエラーメッセージ
+#[tauri::command]
+pub async fn get_milestones(app_state: State<'_, AppState>) -> Result<MilestoneState, String> {
+    let state = app_state
+        .milestones
+        .lock()
+        .map_err(|e| format!("マイルストーン状態のロックに失敗しました: {}", e))?;
+    Ok(state.clone())
+}
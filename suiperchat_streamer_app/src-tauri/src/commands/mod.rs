@@ -2,15 +2,83 @@
 //!
 //! フロントエンドから呼び出されるTauriコマンドの定義を提供します。
 
+pub mod auto_thanks;
+pub mod backup;
+pub mod badges;
+pub mod coins;
 pub mod connection;
+pub mod crash_report;
+pub mod db_vacuum;
+pub mod filter_preset;
 pub mod history;
+pub mod maintenance;
+pub mod message_limits;
+pub mod milestone;
+pub mod moderation;
+pub mod obs_layout;
+pub mod obs_theme;
 pub mod server;
+pub mod signing;
+pub mod sui_watcher;
+pub mod superchat_alert;
+pub mod translation;
+pub mod viewer;
 pub mod wallet;
+pub mod webhook;
 pub mod youtube;
 
 // モジュールから関数をエクスポート
-pub use connection::{disconnect_client, get_connections_info, set_connection_limits};
-pub use history::{get_all_session_ids, get_current_session_id, get_message_history};
-pub use server::{start_websocket_server, stop_websocket_server};
-pub use wallet::{get_streamer_info, set_wallet_address};
+pub use auto_thanks::{get_auto_thanks, set_auto_thanks};
+pub use backup::{backup_incremental, restore_from_incrementals, verify_backup_chain};
+pub use badges::{get_badge_thresholds, set_badge_thresholds};
+pub use coins::{get_supported_coins, set_accepted_coins, set_supported_coins};
+pub use connection::{
+    assign_client_group, block_client_ip, disconnect_client, get_blocked_ips, get_client_groups,
+    get_connection_stats, get_connections_info, get_connections_paginated, get_flow_control,
+    get_group_connection_counts, get_human_verification, get_idle_disconnect_timeout,
+    get_message_rate_limit, get_tx_verification, get_viewer_count_history, load_asn_database,
+    reset_delivery_stats, send_message_to_client, send_message_to_group, set_access_token,
+    set_connection_limits, set_flow_control, set_human_verification, set_idle_disconnect_timeout,
+    set_message_rate_limit, set_overflow_redirect, set_per_wallet_limit, set_tx_verification,
+    unassign_client_group, unblock_client_ip,
+};
+pub use crash_report::{
+    delete_crash_report, list_crash_reports, mark_crash_report_submitted,
+    notify_crash_reports_ready,
+};
+pub use db_vacuum::{get_vacuum_status, optimize_database, run_vacuum_now};
+pub use filter_preset::{apply_filter_preset, list_filter_presets, save_filter_preset};
+pub use history::{
+    delete_session, edit_message, export_messages_markdown, export_session_json,
+    export_session_to_csv, get_all_session_ids, get_client_activity_summary, get_connection_logs,
+    get_current_session_id, get_message_edit_history, get_message_history, get_rejected_superchats,
+    get_session_viewer_count_history, import_session_json, search_messages, set_session_title,
+    toggle_message_highlight, update_session_times,
+};
+pub use maintenance::{cancel_maintenance, schedule_maintenance};
+pub use message_limits::{get_message_limits, set_message_limits};
+pub use milestone::{get_milestones, set_milestones};
+pub use moderation::{get_banned_words, set_banned_words};
+pub use obs_layout::{delete_obs_layout, list_obs_layouts, save_obs_layout, set_obs_layout};
+pub use obs_theme::{get_obs_theme, set_obs_theme};
+pub use server::{
+    disable_tls, end_channel_session, get_active_sessions, get_log_file_path,
+    get_tls_certificate_info, get_tunnel_protocol, get_tunnel_provider, graceful_restart,
+    regenerate_tunnel_url, set_auto_release_ports, set_bind_host, set_cloudflared_version,
+    set_compression_level, set_idle_shutdown, set_server_ports, set_tls_config,
+    set_tunnel_protocol, set_tunnel_provider, set_upnp_enabled, start_channel_session,
+    start_websocket_server, stop_websocket_server,
+};
+pub use signing::{get_message_signing, rotate_signing_key, set_message_signing};
+pub use sui_watcher::{
+    get_sui_watcher_status, set_sui_watcher_config, start_sui_watcher, stop_sui_watcher,
+};
+pub use superchat_alert::{get_superchat_alert_config, set_big_superchat_threshold};
+pub use translation::{get_translation_config, set_translation_api_key, set_translation_config};
+pub use viewer::{get_top_donors, get_viewer_profile, list_viewer_profiles, set_viewer_opt_out};
+pub use wallet::{
+    add_wallet, get_streamer_info, list_wallets, remove_wallet, set_active_wallet,
+    set_require_wallet, set_wallet_address,
+};
+pub use webhook::set_webhook_url;
 pub use youtube::{get_youtube_video_id, set_youtube_video_id};
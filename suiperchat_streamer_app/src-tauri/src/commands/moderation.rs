@@ -0,0 +1,88 @@
+//! NGワードによるモデレーション関連のコマンドモジュール
+//!
+//! NGワードとNGワードを含むスーパーチャットの扱いを設定・取得するためのTauriコマンドを提供する
+
+use crate::moderation::{self, SuperchatModeration};
+use crate::state::AppState;
+use serde::Serialize;
+use tauri::State;
+
+/// NGワードの設定
+#[derive(Serialize, Debug, Clone)]
+pub struct BannedWordsConfig {
+    /// NGワード（小文字に正規化済み）
+    pub words: Vec<String>,
+    /// NGワードを含むスーパーチャットの扱い
+    pub superchat_action: SuperchatModeration,
+}
+
+/// NGワードを設定するTauriコマンド
+///
+/// NGワードを含む通常チャットは配信・保存されず、送信者にエラーが返されます。
+/// 照合は大文字小文字を区別しません。空の一覧を指定するとフィルタは無効になります。
+///
+/// # 引数
+/// * `words` - NGワードの一覧
+/// * `superchat_action` - NGワードを含むスーパーチャットの扱い（"mask": 伏字にして配信、"block": 配信しない、省略時は現在の設定を維持）
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<BannedWordsConfig, String>` - 成功時は適用した設定、エラー時はエラーメッセージ
+#[tauri::command]
+pub fn set_banned_words(
+    words: Vec<String>,
+    superchat_action: Option<SuperchatModeration>,
+    app_state: State<'_, AppState>,
+) -> Result<BannedWordsConfig, String> {
+    let words = moderation::normalize_banned_words(words);
+
+    let superchat_action = {
+        let mut action = app_state
+            .superchat_moderation
+            .lock()
+            .map_err(|e| format!("スーパーチャットの扱いのロックに失敗しました: {}", e))?;
+        if let Some(superchat_action) = superchat_action {
+            *action = superchat_action;
+        }
+        *action
+    };
+    *app_state
+        .banned_words
+        .lock()
+        .map_err(|e| format!("NGワードのロックに失敗しました: {}", e))? = words.clone();
+
+    println!(
+        "NGワードを設定しました: {}件 (スーパーチャット: {:?})",
+        words.len(),
+        superchat_action
+    );
+    Ok(BannedWordsConfig {
+        words,
+        superchat_action,
+    })
+}
+
+/// 現在のNGワードの設定を取得するTauriコマンド
+///
+/// # 引数
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<BannedWordsConfig, String>` - 成功時はNGワードの設定、エラー時はエラーメッセージ
+#[tauri::command]
+pub fn get_banned_words(app_state: State<'_, AppState>) -> Result<BannedWordsConfig, String> {
+    let words = app_state
+        .banned_words
+        .lock()
+        .map_err(|e| format!("NGワードのロックに失敗しました: {}", e))?
+        .clone();
+    let superchat_action = *app_state
+        .superchat_moderation
+        .lock()
+        .map_err(|e| format!("スーパーチャットの扱いのロックに失敗しました: {}", e))?;
+
+    Ok(BannedWordsConfig {
+        words,
+        superchat_action,
+    })
+}
@@ -0,0 +1,133 @@
+//! OBSレイアウト関連のコマンドモジュール
+//!
+//! 配信の場面に応じたOBS表示のレイアウトを保存・一覧・削除し、
+//! アクティブなレイアウトを切り替えるためのTauriコマンドを提供する。変更はDBに保存する
+
+use crate::obs_layout::{self, ObsLayout, ObsLayoutState};
+use crate::settings;
+use crate::state::AppState;
+use tauri::State;
+
+/// 保存済みのOBSレイアウトを一覧するTauriコマンド
+///
+/// # 引数
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<ObsLayoutState, String>` - 成功時はアクティブなレイアウト名と名前順のレイアウト、エラー時はエラーメッセージ
+#[tauri::command]
+pub fn list_obs_layouts(app_state: State<'_, AppState>) -> Result<ObsLayoutState, String> {
+    app_state
+        .obs_layouts
+        .lock()
+        .map(|layouts| layouts.clone())
+        .map_err(|e| format!("OBSレイアウトのロックに失敗しました: {}", e))
+}
+
+/// OBSレイアウトを保存するTauriコマンド
+///
+/// 同名のレイアウトがある場合は上書きします。アクティブなレイアウトを上書きした場合は、
+/// 変更を即座にOBS表示へ反映します。
+///
+/// # 引数
+/// * `layout` - 保存するレイアウト
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<ObsLayout, String>` - 成功時は保存したレイアウト、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - レイアウト名が空、または最大文字数を超える場合
+/// - 位置・サイズ・表示倍率が範囲外の場合
+/// - 保存数の上限を超える場合
+#[tauri::command]
+pub fn save_obs_layout(
+    layout: ObsLayout,
+    app_state: State<'_, AppState>,
+) -> Result<ObsLayout, String> {
+    let layout = ObsLayout {
+        name: layout.name.trim().to_string(),
+        ..layout
+    };
+    layout.validate()?;
+
+    let is_active = app_state
+        .obs_layouts
+        .lock()
+        .map_err(|e| format!("OBSレイアウトのロックに失敗しました: {}", e))?
+        .save(layout.clone())?;
+    settings::save_obs_layouts(&app_state);
+    if is_active {
+        obs_layout::broadcast_layout(&layout);
+    }
+
+    println!("OBSレイアウトを保存しました: {}", layout.name);
+    Ok(layout)
+}
+
+/// OBSレイアウトを削除するTauriコマンド
+///
+/// アクティブなレイアウトを削除した場合は、デフォルトレイアウトに切り替えます。
+///
+/// # 引数
+/// * `layout_name` - 削除するレイアウトの名前
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<(), String>` - 成功時は `Ok(())`、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - デフォルトレイアウトを指定した場合
+/// - レイアウトが存在しない場合
+#[tauri::command]
+pub fn delete_obs_layout(
+    layout_name: String,
+    app_state: State<'_, AppState>,
+) -> Result<(), String> {
+    let active_layout = {
+        let mut layouts = app_state
+            .obs_layouts
+            .lock()
+            .map_err(|e| format!("OBSレイアウトのロックに失敗しました: {}", e))?;
+        layouts
+            .delete(layout_name.trim())?
+            .then(|| layouts.active_layout())
+    };
+    settings::save_obs_layouts(&app_state);
+    if let Some(layout) = active_layout {
+        obs_layout::broadcast_layout(&layout);
+    }
+
+    println!("OBSレイアウトを削除しました: {}", layout_name.trim());
+    Ok(())
+}
+
+/// アクティブなOBSレイアウトを切り替えるTauriコマンド
+///
+/// 接続中のOBS表示のみに `type: "obs_layout"` メッセージで通知し、表示を即座に切り替えます。
+///
+/// # 引数
+/// * `layout_name` - 切り替え先のレイアウトの名前
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<ObsLayout, String>` - 成功時は切り替え後のレイアウト、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - レイアウトが存在しない場合
+#[tauri::command]
+pub fn set_obs_layout(
+    layout_name: String,
+    app_state: State<'_, AppState>,
+) -> Result<ObsLayout, String> {
+    let layout = app_state
+        .obs_layouts
+        .lock()
+        .map_err(|e| format!("OBSレイアウトのロックに失敗しました: {}", e))?
+        .activate(layout_name.trim())?;
+    settings::save_obs_layouts(&app_state);
+    obs_layout::broadcast_layout(&layout);
+
+    println!("OBSレイアウトを切り替えました: {}", layout.name);
+    Ok(layout)
+}
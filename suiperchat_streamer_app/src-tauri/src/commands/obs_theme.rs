@@ -0,0 +1,57 @@
+//! OBSテーマ関連のコマンドモジュール
+//!
+//! OBS表示の色やフォントサイズを変更・取得するためのTauriコマンドを提供する
+
+use crate::obs_theme::{self, ObsTheme};
+use crate::state::AppState;
+use tauri::State;
+
+/// OBS表示のテーマを設定するTauriコマンド
+///
+/// 変更は接続中のOBS表示へ即座に反映されます。
+///
+/// # 引数
+/// * `theme` - 設定するテーマ
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<ObsTheme, String>` - 成功時は設定したテーマ、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - 色が16進数のカラーコードでない場合
+/// - フォントサイズが範囲外の場合
+#[tauri::command]
+pub fn set_obs_theme(theme: ObsTheme, app_state: State<'_, AppState>) -> Result<ObsTheme, String> {
+    let theme = ObsTheme {
+        background_color: theme.background_color.trim().to_string(),
+        text_color: theme.text_color.trim().to_string(),
+        superchat_color: theme.superchat_color.trim().to_string(),
+        ..theme
+    };
+    theme.validate()?;
+
+    *app_state
+        .obs_theme
+        .lock()
+        .map_err(|e| format!("OBSテーマのロックに失敗しました: {}", e))? = theme.clone();
+    obs_theme::broadcast_theme(&theme);
+
+    println!("OBSテーマを設定しました: {:?}", theme);
+    Ok(theme)
+}
+
+/// 現在のOBS表示のテーマを取得するTauriコマンド
+///
+/// # 引数
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<ObsTheme, String>` - 成功時は現在のテーマ、エラー時はエラーメッセージ
+#[tauri::command]
+pub fn get_obs_theme(app_state: State<'_, AppState>) -> Result<ObsTheme, String> {
+    app_state
+        .obs_theme
+        .lock()
+        .map(|theme| theme.clone())
+        .map_err(|e| format!("OBSテーマのロックに失敗しました: {}", e))
+}
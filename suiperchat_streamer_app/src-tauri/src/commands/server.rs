@@ -1,17 +1,34 @@
 //! WebSocketサーバー関連のコマンド
 //!
-//! サーバーの起動・停止のTauriコマンドを提供します。
+//! サーバーの起動・停止、TLS設定、トンネル設定のTauriコマンドを提供します。
 
+use crate::cloudflared_manager;
 use crate::state::AppState;
+use crate::stream_sessions::{self, SessionMap};
+use crate::wallet_registry;
+use crate::ws_server::compression::MAX_COMPRESSION_LEVEL;
+use crate::ws_server::event_logger;
+use crate::ws_server::server_manager::IdleShutdownConfig;
+use crate::ws_server::server_utils::{
+    is_lan_exposed_host, validate_bind_host, validate_server_ports,
+};
+use crate::ws_server::tls::{self, CertificateInfo, TlsConfig};
+use crate::ws_server::tunnel::{TunnelKind, TunnelProtocol, NGROK_AUTHTOKEN_ENV};
 use tauri::{command, State};
 
 /// ## WebSocket サーバーを起動する Tauri コマンド
 ///
 /// 指定されたホストとポートで WebSocket サーバーを非同期に起動します。
+/// `use_tunnel` に `false` を指定すると、トンネルを起動せずLAN内にのみ公開します。
+/// ウォレットアドレスが未設定の場合、`set_require_wallet` で強制モードが有効なら起動を拒否し、
+/// 無効なら警告をログに出力して起動します。
+/// `session_channels` を指定すると、同時配信用にチャンネルごとの配信セッションもあわせて作成します。
 ///
 /// ### Arguments
 /// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
 /// - `app_handle`: Tauri アプリケーションハンドル (`tauri::AppHandle`)
+/// - `use_tunnel`: トンネルを起動するかどうか（省略時は `true`）
+/// - `session_channels`: 追加で配信セッションを作成するチャンネル名（省略時は作成しない）
 ///
 /// ### Returns
 /// - `Result<(), String>`: 成功した場合は `Ok(())`、エラーの場合はエラーメッセージ
@@ -19,8 +36,93 @@ use tauri::{command, State};
 pub fn start_websocket_server(
     app_state: State<'_, AppState>,
     app_handle: tauri::AppHandle,
+    use_tunnel: Option<bool>,
+    session_channels: Option<Vec<String>>,
 ) -> Result<(), String> {
-    crate::ws_server::server_manager::start_server(&app_state, app_handle)
+    let mut channels: Vec<String> = Vec::new();
+    for channel in session_channels.unwrap_or_default() {
+        let channel = stream_sessions::validate_session_channel(&channel)?;
+        if channel == stream_sessions::PRIMARY_SESSION_CHANNEL {
+            return Err(format!("チャンネル名 {} は予約されています", channel));
+        }
+        if !channels.contains(&channel) {
+            channels.push(channel);
+        }
+    }
+
+    if wallet_registry::active_wallet_address(&app_state).is_none() {
+        let require_wallet = *app_state
+            .require_wallet
+            .lock()
+            .map_err(|_| "Failed to lock require wallet mutex".to_string())?;
+        if require_wallet {
+            return Err("ウォレットアドレスを設定してください".to_string());
+        }
+        eprintln!("警告: ウォレットアドレスが未設定のため、スーパーチャットを受け取れません");
+    }
+    *app_state
+        .session_channels
+        .lock()
+        .map_err(|_| "Failed to lock session channels mutex".to_string())? = channels;
+
+    crate::ws_server::server_manager::start_server(
+        &app_state,
+        app_handle,
+        use_tunnel.unwrap_or(true),
+    )
+}
+
+/// ## チャンネルの配信セッションを開始する Tauri コマンド
+///
+/// サーバーの起動中に、同時配信用のチャンネルの配信セッションを追加します。
+/// 視聴者はWebSocket接続のクエリパラメータ `?channel=xxx` でこのセッションに参加します。
+///
+/// ### Arguments
+/// - `channel`: チャンネル名（英数字・ハイフン・アンダースコア）
+/// - `app_handle`: Tauri アプリケーションハンドル (`tauri::AppHandle`)
+///
+/// ### Returns
+/// - `Result<String, String>`: 作成した配信セッションID、エラーの場合はエラーメッセージ
+#[command]
+pub async fn start_channel_session(
+    channel: String,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    stream_sessions::start_channel_session(&app_handle, &channel).await
+}
+
+/// ## チャンネルの配信セッションを終了する Tauri コマンド
+///
+/// サーバーや他のチャンネルのセッションは止めずに、指定したチャンネルのセッションだけを終了します。
+///
+/// ### Arguments
+/// - `channel`: チャンネル名
+/// - `app_handle`: Tauri アプリケーションハンドル (`tauri::AppHandle`)
+///
+/// ### Returns
+/// - `Result<String, String>`: 終了した配信セッションID、エラーの場合はエラーメッセージ
+#[command]
+pub async fn end_channel_session(
+    channel: String,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    stream_sessions::end_channel_session(&app_handle, &channel).await
+}
+
+/// ## 配信中のセッションの一覧を取得する Tauri コマンド
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<SessionMap, String>`: チャンネル名と配信セッションIDの対応、エラーの場合はエラーメッセージ
+#[command]
+pub fn get_active_sessions(app_state: State<'_, AppState>) -> Result<SessionMap, String> {
+    app_state
+        .current_session_id
+        .lock()
+        .map(|sessions| sessions.clone())
+        .map_err(|_| "Failed to lock current session id mutex".to_string())
 }
 
 /// ## WebSocket サーバーを停止する Tauri コマンド
@@ -38,5 +140,578 @@ pub fn stop_websocket_server(
     app_state: State<AppState>,
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
-    crate::ws_server::server_manager::stop_server(&app_state, app_handle)
+    crate::ws_server::server_manager::stop_server(
+        &app_state,
+        app_handle,
+        crate::ws_server::server_manager::STREAM_ENDED_REASON,
+    )
+}
+
+/// ## WebSocket サーバーをグレースフルリスタートする Tauri コマンド
+///
+/// 新しいサーバーを別ポートで起動し、既存接続を移行させてから旧サーバーを停止します。
+/// セッションIDとDB接続プールは引き継がれます。
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `app_handle`: Tauri アプリケーションハンドル (`tauri::AppHandle`)
+///
+/// ### Returns
+/// - `Result<(), String>`: 移行処理を開始できた場合は `Ok(())`、エラーの場合はエラーメッセージ
+#[command]
+pub fn graceful_restart(
+    app_state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    crate::ws_server::server_manager::graceful_restart(&app_state, app_handle)
+}
+
+/// ## トンネルURLを再生成する Tauri コマンド
+///
+/// WebSocketサーバーは止めずにトンネルプロセスだけを再起動し、新しいURLを取得します。
+/// 長時間の配信中にトンネルURLが無効になった場合に使用します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauri アプリケーションハンドル (`tauri::AppHandle`)
+///
+/// ### Returns
+/// - `Result<String, String>`: 成功した場合は新しいトンネルURL、エラーの場合はエラーメッセージ
+#[command]
+pub async fn regenerate_tunnel_url(app_handle: tauri::AppHandle) -> Result<String, String> {
+    crate::ws_server::server_manager::regenerate_tunnel_url(app_handle).await
+}
+
+/// ## 省電力モードを設定する Tauri コマンド
+///
+/// 有効にすると、視聴者ゼロの状態が `timeout` 秒続いた場合にトンネルを自動停止し、
+/// 新しい接続があった時点で再開します。
+/// トンネルの停止中はトンネルURLから新しい視聴者が接続できない点に注意してください。
+///
+/// ### Arguments
+/// - `enabled`: 省電力モードを有効にするかどうか
+/// - `timeout`: 接続数が0になってからトンネルを停止するまでの時間（秒）
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `app_handle`: Tauri アプリケーションハンドル (`tauri::AppHandle`)
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功した場合は `Ok(())`、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_idle_shutdown(
+    enabled: bool,
+    timeout: u64,
+    app_state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    IdleShutdownConfig::validate_timeout_secs(timeout)?;
+
+    *app_state
+        .idle_shutdown
+        .lock()
+        .map_err(|_| "Failed to lock idle shutdown mutex".to_string())? = IdleShutdownConfig {
+        enabled,
+        idle_timeout_secs: timeout,
+    };
+    println!(
+        "省電力モードを設定しました: enabled={}, timeout={}秒",
+        enabled, timeout
+    );
+
+    if enabled {
+        // 既に視聴者がいない場合はこの時点から待機を開始する
+        crate::ws_server::server_manager::schedule_idle_tunnel_shutdown(&app_handle);
+    } else {
+        // 待機中の停止を取り消し、停止中のトンネルは再開する
+        crate::ws_server::server_manager::cancel_idle_tunnel_shutdown(&app_handle);
+    }
+    Ok(())
+}
+
+/// ## WebSocketメッセージの圧縮レベルを設定する Tauri コマンド
+///
+/// permessage-deflate に対応したクライアントへの送信メッセージを指定したレベルで圧縮します。
+/// レベルが高いほど帯域を削減できますが、CPU負荷が増えます。0を指定すると圧縮を無効にします。
+/// 設定は新しく接続したクライアントから適用されます。
+///
+/// ### Arguments
+/// - `level`: 圧縮レベル（0〜9）
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功した場合は `Ok(())`、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_compression_level(level: u32, app_state: State<'_, AppState>) -> Result<(), String> {
+    if level > MAX_COMPRESSION_LEVEL {
+        return Err(format!(
+            "圧縮レベルは0〜{}で指定してください: {}",
+            MAX_COMPRESSION_LEVEL, level
+        ));
+    }
+
+    *app_state
+        .ws_compression_level
+        .lock()
+        .map_err(|_| "Failed to lock compression level mutex".to_string())? = level;
+    println!("WebSocketメッセージの圧縮レベルを設定しました: {}", level);
+    Ok(())
+}
+
+/// ## アプリ内TLS終端の設定を行う Tauri コマンド
+///
+/// 証明書と秘密鍵を読み込んで検証し、次回のサーバー起動から wss:// で直接待ち受けるよう設定します。
+/// TLS有効時は Cloudflared トンネルを使用しません。
+///
+/// ### Arguments
+/// - `cert_path`: 証明書（PEM形式、フルチェーン）のパス
+/// - `key_path`: 秘密鍵（PEM形式）のパス
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<CertificateInfo, String>`: 成功した場合は証明書情報、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_tls_config(
+    cert_path: String,
+    key_path: String,
+    app_state: State<'_, AppState>,
+) -> Result<CertificateInfo, String> {
+    ensure_server_stopped(&app_state, "TLS設定")?;
+
+    // 証明書と秘密鍵の組み合わせを検証
+    tls::load_server_config(&cert_path, &key_path)
+        .map_err(|e| format!("TLS証明書の読み込みに失敗しました: {}", e))?;
+    let certificate_info = tls::inspect_certificate(&cert_path)
+        .map_err(|e| format!("TLS証明書の解析に失敗しました: {}", e))?;
+
+    if certificate_info.is_expired {
+        return Err(format!(
+            "TLS証明書の有効期限が切れています（有効期限: {}）",
+            certificate_info.not_after
+        ));
+    }
+    if certificate_info.expires_soon {
+        println!(
+            "警告: TLS証明書の有効期限が近づいています（残り{}日）",
+            certificate_info.days_remaining
+        );
+    }
+
+    let mut tls_config = app_state
+        .tls_config
+        .lock()
+        .map_err(|_| "Failed to lock TLS config mutex".to_string())?;
+    *tls_config = TlsConfig {
+        enabled: true,
+        cert_path: Some(cert_path),
+        key_path: Some(key_path),
+        server_name: certificate_info
+            .dns_names
+            .iter()
+            .find(|name| !name.starts_with('*'))
+            .cloned(),
+    };
+    println!("TLS設定を有効にしました: {:?}", tls_config.server_name);
+
+    Ok(certificate_info)
+}
+
+/// ## アプリ内TLS終端を無効にする Tauri コマンド
+///
+/// 次回のサーバー起動から従来通り ws:// と Cloudflared トンネルで動作させます。
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功した場合は `Ok(())`、エラーの場合はエラーメッセージ
+#[command]
+pub fn disable_tls(app_state: State<'_, AppState>) -> Result<(), String> {
+    ensure_server_stopped(&app_state, "TLS設定")?;
+
+    let mut tls_config = app_state
+        .tls_config
+        .lock()
+        .map_err(|_| "Failed to lock TLS config mutex".to_string())?;
+    *tls_config = TlsConfig::default();
+    println!("TLS設定を無効にしました");
+
+    Ok(())
+}
+
+/// ## 設定中のTLS証明書の情報を取得する Tauri コマンド
+///
+/// 証明書の有効期限の確認に使用します。TLSが無効な場合は `None` を返します。
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<Option<CertificateInfo>, String>`: 証明書情報、エラーの場合はエラーメッセージ
+#[command]
+pub fn get_tls_certificate_info(
+    app_state: State<'_, AppState>,
+) -> Result<Option<CertificateInfo>, String> {
+    let cert_path = {
+        let tls_config = app_state
+            .tls_config
+            .lock()
+            .map_err(|_| "Failed to lock TLS config mutex".to_string())?;
+        match (&tls_config.enabled, &tls_config.cert_path) {
+            (true, Some(cert_path)) => cert_path.clone(),
+            _ => return Ok(None),
+        }
+    };
+
+    tls::inspect_certificate(&cert_path)
+        .map(Some)
+        .map_err(|e| format!("TLS証明書の解析に失敗しました: {}", e))
+}
+
+/// ## サーバーポートを設定する Tauri コマンド
+///
+/// 次回のサーバー起動時に使用するWebSocketサーバーとOBSサーバーのポートを設定します。
+/// 既定のポート（8082/8081）が他のアプリと競合する環境向けの設定です。
+///
+/// ### Arguments
+/// - `ws_port`: WebSocketサーバー（視聴者用）のポート
+/// - `obs_port`: OBSサーバーのポート
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功した場合は `Ok(())`、ポートが無効な場合などはエラーメッセージ
+#[command]
+pub fn set_server_ports(
+    ws_port: u16,
+    obs_port: u16,
+    app_state: State<'_, AppState>,
+) -> Result<(), String> {
+    ensure_server_stopped(&app_state, "ポート設定")?;
+    validate_server_ports(ws_port, obs_port)?;
+
+    *app_state
+        .configured_ws_port
+        .lock()
+        .map_err(|_| "Failed to lock configured ws port mutex".to_string())? = Some(ws_port);
+    *app_state
+        .configured_obs_port
+        .lock()
+        .map_err(|_| "Failed to lock configured obs port mutex".to_string())? = Some(obs_port);
+    println!(
+        "サーバーポートを設定しました: WebSocket={}, OBS={}",
+        ws_port, obs_port
+    );
+
+    Ok(())
+}
+
+/// ## サーバーのバインドホストを設定する Tauri コマンド
+///
+/// 次回のサーバー起動時に使用するバインドホストを設定します。
+/// `0.0.0.0` / `::` を設定すると、トンネルを経由せずにLAN内の他の端末から接続できます。
+/// トンネルは `127.0.0.1` に転送するため、それ以外のアドレスは指定できません。
+/// LANに公開する場合はアクセストークンの設定を推奨します。
+///
+/// ### Arguments
+/// - `host`: バインドするIPアドレス（例: "127.0.0.1", "0.0.0.0"）
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功した場合は `Ok(())`、IPアドレスが無効な場合などはエラーメッセージ
+#[command]
+pub fn set_bind_host(host: String, app_state: State<'_, AppState>) -> Result<(), String> {
+    ensure_server_stopped(&app_state, "バインドホスト設定")?;
+    let host = validate_bind_host(&host)?.to_string();
+
+    if is_lan_exposed_host(&host) {
+        let has_access_token = app_state
+            .access_token
+            .lock()
+            .is_ok_and(|token| token.is_some());
+        if !has_access_token {
+            eprintln!(
+                "警告: バインドホスト {} はLANに公開されます。アクセストークンの設定を推奨します。",
+                host
+            );
+        }
+    }
+    *app_state
+        .bind_host
+        .lock()
+        .map_err(|_| "Failed to lock bind host mutex".to_string())? = host.clone();
+    println!("バインドホストを設定しました: {}", host);
+
+    Ok(())
+}
+
+/// ## ポートの自動解放を設定する Tauri コマンド
+///
+/// 有効にすると、サーバー起動時にポートが前回起動したSUIperCHATのプロセスに
+/// 使用されていた場合、そのプロセスを終了してから起動します。
+/// 他のアプリケーションが使用しているポートは解放しません。
+///
+/// ### Arguments
+/// - `enabled`: ポートの自動解放を有効にするかどうか
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功した場合は `Ok(())`、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_auto_release_ports(enabled: bool, app_state: State<'_, AppState>) -> Result<(), String> {
+    *app_state
+        .auto_release_ports
+        .lock()
+        .map_err(|_| "Failed to lock auto release ports mutex".to_string())? = enabled;
+    println!("ポートの自動解放を設定しました: {}", enabled);
+
+    Ok(())
+}
+
+/// ## UPnPによるポート開放を設定する Tauri コマンド
+///
+/// 有効にすると、次回のサーバー起動時にルーターのUPnPでWebSocketポートを開放し、
+/// グローバルIPで直接接続できるようにします（`ServerStatus.upnp_ws_url`）。
+/// UPnP非対応のルーターなどで開放できなかった場合は Cloudflared トンネルにフォールバックします。
+/// 開放したポートはサーバー停止時に閉じます。
+///
+/// ### Arguments
+/// - `enabled`: UPnPによるポート開放を試みるかどうか
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功した場合は `Ok(())`、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_upnp_enabled(enabled: bool, app_state: State<'_, AppState>) -> Result<(), String> {
+    *app_state
+        .use_upnp
+        .lock()
+        .map_err(|_| "Failed to lock use upnp mutex".to_string())? = enabled;
+    println!("UPnPによるポート開放を設定しました: {}", enabled);
+
+    Ok(())
+}
+
+/// ## サーバーが停止していることを確認する
+///
+/// TLS設定やポート設定はサーバー起動時に読み込まれるため、起動中の変更を拒否します。
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+/// - `setting`: エラーメッセージに表示する設定名
+fn ensure_server_stopped(app_state: &AppState, setting: &str) -> Result<(), String> {
+    let is_running = app_state
+        .server_handle
+        .lock()
+        .map_err(|_| "Failed to lock server handle mutex".to_string())?
+        .is_some();
+
+    if is_running {
+        return Err(format!(
+            "サーバー起動中は{}を変更できません。サーバーを停止してから再度お試しください。",
+            setting
+        ));
+    }
+    Ok(())
+}
+
+/// ## Cloudflared トンネルの接続プロトコルを設定する Tauri コマンド
+///
+/// UDPがブロックされた環境向けに、QUICを無効化してHTTP/2で接続するよう設定できます。
+/// 設定は次回のトンネル起動時から反映されます。
+///
+/// ### Arguments
+/// - `protocol`: "auto"（Cloudflare任せ）、"http2"、"quic" のいずれか
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<String, String>`: 成功した場合は反映タイミングを示すメッセージ、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_tunnel_protocol(
+    protocol: String,
+    app_state: State<'_, AppState>,
+) -> Result<String, String> {
+    let protocol = TunnelProtocol::parse(&protocol).ok_or_else(|| {
+        format!(
+            "サポートされていないプロトコルです: {}（auto / http2 / quic のいずれかを指定してください）",
+            protocol
+        )
+    })?;
+
+    {
+        let mut protocol_guard = app_state
+            .tunnel_protocol
+            .lock()
+            .map_err(|_| "Failed to lock tunnel protocol mutex".to_string())?;
+        *protocol_guard = protocol;
+    }
+    println!("トンネルプロトコルを設定しました: {:?}", protocol);
+
+    let tunnel_running = matches!(
+        &*app_state
+            .tunnel_info
+            .lock()
+            .map_err(|_| "Failed to lock tunnel info mutex".to_string())?,
+        Some(Ok(_))
+    );
+
+    if tunnel_running {
+        Ok("トンネルプロトコルを変更しました。反映するにはサーバー（トンネル）を再起動してください。".to_string())
+    } else {
+        Ok("トンネルプロトコルを変更しました。次回のサーバー起動時から反映されます。".to_string())
+    }
+}
+
+/// ## Cloudflared トンネルの接続プロトコル設定を取得する Tauri コマンド
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<TunnelProtocol, String>`: 現在のプロトコル設定、エラーの場合はエラーメッセージ
+#[command]
+pub fn get_tunnel_protocol(app_state: State<'_, AppState>) -> Result<TunnelProtocol, String> {
+    app_state
+        .tunnel_protocol
+        .lock()
+        .map(|protocol| *protocol)
+        .map_err(|_| "Failed to lock tunnel protocol mutex".to_string())
+}
+
+/// ## トンネルのプロバイダを設定する Tauri コマンド
+///
+/// Cloudflare Quick Tunnelが不安定な環境向けに、代替としてngrokを選択できます。
+/// ngrokを使用する場合は環境変数 `NGROK_AUTHTOKEN` に認証トークンを設定してください。
+/// 設定は次回のトンネル起動時から反映されます。
+///
+/// ### Arguments
+/// - `provider`: "cloudflared" または "ngrok"
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<String, String>`: 成功した場合は反映タイミングを示すメッセージ、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_tunnel_provider(
+    provider: String,
+    app_state: State<'_, AppState>,
+) -> Result<String, String> {
+    let provider = TunnelKind::parse(&provider).ok_or_else(|| {
+        format!(
+            "サポートされていないトンネルプロバイダです: {}（cloudflared / ngrok のいずれかを指定してください）",
+            provider
+        )
+    })?;
+
+    if provider == TunnelKind::Ngrok
+        && !std::env::var(NGROK_AUTHTOKEN_ENV).is_ok_and(|token| !token.trim().is_empty())
+    {
+        return Err(format!(
+            "ngrokを使用するには環境変数 {} に認証トークンを設定してください",
+            NGROK_AUTHTOKEN_ENV
+        ));
+    }
+
+    {
+        let mut provider_guard = app_state
+            .tunnel_provider
+            .lock()
+            .map_err(|_| "Failed to lock tunnel provider mutex".to_string())?;
+        *provider_guard = provider;
+    }
+    println!("トンネルプロバイダを設定しました: {:?}", provider);
+
+    let tunnel_running = matches!(
+        &*app_state
+            .tunnel_info
+            .lock()
+            .map_err(|_| "Failed to lock tunnel info mutex".to_string())?,
+        Some(Ok(_))
+    );
+
+    if tunnel_running {
+        Ok("トンネルプロバイダを変更しました。反映するにはサーバー（トンネル）を再起動してください。".to_string())
+    } else {
+        Ok("トンネルプロバイダを変更しました。次回のサーバー起動時から反映されます。".to_string())
+    }
+}
+
+/// ## トンネルのプロバイダ設定を取得する Tauri コマンド
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<TunnelKind, String>`: 現在のプロバイダ設定、エラーの場合はエラーメッセージ
+#[command]
+pub fn get_tunnel_provider(app_state: State<'_, AppState>) -> Result<TunnelKind, String> {
+    app_state
+        .tunnel_provider
+        .lock()
+        .map(|provider| *provider)
+        .map_err(|_| "Failed to lock tunnel provider mutex".to_string())
+}
+
+/// ## cloudflaredのバージョンを固定する Tauri コマンド
+///
+/// Cloudflare側の変更でトンネルが突然動かなくなることを避けるため、ダウンロードするバージョンを固定します。
+/// 既存のバイナリと異なるバージョンを指定した場合は、次回のトンネル起動時に再ダウンロードします。
+/// 固定したバージョンのダウンロードには期待するチェックサムが必要なため、バージョンと一緒に指定します。
+/// チェックサムが既知であるか、環境変数 `CLOUDFLARED_SHA256` で指定されている場合は省略できます。
+///
+/// ### Arguments
+/// - `version`: 固定するバージョン（例: "2024.12.2"）、Noneの場合は環境変数または最新版を使用
+/// - `sha256`: 固定するバージョンのリリースファイルのSHA256（16進数）
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功した場合は `Ok(())`、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_cloudflared_version(
+    version: Option<String>,
+    sha256: Option<String>,
+    app_state: State<'_, AppState>,
+) -> Result<(), String> {
+    let version = version
+        .filter(|version| !version.trim().is_empty())
+        .map(|version| cloudflared_manager::validate_version(&version))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let sha256 = match &version {
+        Some(version) => match sha256.filter(|sha256| !sha256.trim().is_empty()) {
+            Some(sha256) => {
+                Some(cloudflared_manager::validate_sha256(&sha256).map_err(|e| e.to_string())?)
+            }
+            None if cloudflared_manager::has_known_checksum(version) => None,
+            None => {
+                return Err(format!(
+                    "cloudflared {} のチェックサムが不明です。SHA256を指定してください",
+                    version
+                ))
+            }
+        },
+        // 最新版を使用する場合はチェックサムを固定しない
+        None => None,
+    };
+
+    println!("cloudflaredのバージョンを設定しました: {:?}", version);
+    *app_state
+        .cloudflared_version
+        .lock()
+        .map_err(|_| "Failed to lock cloudflared version mutex".to_string())? = version;
+    *app_state
+        .cloudflared_sha256
+        .lock()
+        .map_err(|_| "Failed to lock cloudflared sha256 mutex".to_string())? = sha256;
+    Ok(())
+}
+
+/// ## サーバーイベントログのパスを取得する Tauri コマンド
+///
+/// サーバー状態の変化はJSON Lines形式で記録され、日付ごとにローテーションされます。
+/// 前日以前のログは同じディレクトリに `server_events-YYYY-MM-DD.log` として残ります。
+///
+/// ### Arguments
+/// - `app_handle`: Tauri アプリケーションハンドル (`tauri::AppHandle`)
+///
+/// ### Returns
+/// - `Result<String, String>`: 成功した場合は当日のログファイルのパス、エラーの場合はエラーメッセージ
+#[command]
+pub fn get_log_file_path(app_handle: tauri::AppHandle) -> Result<String, String> {
+    event_logger::log_file_path(&app_handle).map(|path| path.to_string_lossy().into_owned())
 }
@@ -0,0 +1,76 @@
+//! メッセージ署名関連のコマンドモジュール
+//!
+//! ブロードキャストメッセージへの署名の範囲設定と、署名鍵のローテーションを行うTauriコマンドを提供する
+
+use crate::signing::{self, MessageSigner, SigningInfo, SigningMode};
+use crate::ws_server::connection_manager::global;
+
+/// 署名するメッセージの範囲を設定するTauriコマンド
+///
+/// 署名はブロードキャストごとに1回行われます。負荷を抑えたい場合は
+/// スーパーチャットのみを署名する "important" を指定してください。
+///
+/// # 引数
+/// * `mode` - 署名の範囲（"off": 署名しない、"important": スーパーチャットのみ、"all": 全メッセージ）
+/// * `app_handle` - Tauriアプリケーションハンドル
+///
+/// # 戻り値
+/// * `Result<SigningInfo, String>` - 成功時は署名の設定と公開鍵、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - 署名鍵の読み込みまたは生成に失敗した場合
+#[tauri::command]
+pub fn set_message_signing(
+    mode: SigningMode,
+    app_handle: tauri::AppHandle,
+) -> Result<SigningInfo, String> {
+    let manager = global::get_manager();
+    // サーバー起動前でも公開鍵を確認できるよう、未読み込みの場合は読み込む
+    if mode != SigningMode::Off && manager.signer().is_none() {
+        let path = signing::key_path(&app_handle)?;
+        manager.set_signer(MessageSigner::load_or_generate(&path)?);
+    }
+    manager.set_signing_mode(mode);
+    println!("メッセージ署名の範囲を設定しました: {:?}", mode);
+
+    Ok(SigningInfo::new(mode, manager.signer().as_deref()))
+}
+
+/// 現在の署名の設定と公開鍵を取得するTauriコマンド
+///
+/// # 戻り値
+/// * `Result<SigningInfo, String>` - 署名の設定と公開鍵（鍵が未読み込みの場合は公開鍵なし）
+#[tauri::command]
+pub fn get_message_signing() -> Result<SigningInfo, String> {
+    let manager = global::get_manager();
+    Ok(SigningInfo::new(
+        manager.signing_mode(),
+        manager.signer().as_deref(),
+    ))
+}
+
+/// 署名鍵をローテーションするTauriコマンド
+///
+/// 新しい鍵ペアを生成して保存し、以降のメッセージは新しい鍵で署名します。
+/// 接続中の視聴者フロントは `GET /info` から公開鍵を再取得する必要があります。
+///
+/// # 引数
+/// * `app_handle` - Tauriアプリケーションハンドル
+///
+/// # 戻り値
+/// * `Result<SigningInfo, String>` - 成功時は新しい公開鍵を含む署名の設定、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - 鍵の生成または保存に失敗した場合
+#[tauri::command]
+pub fn rotate_signing_key(app_handle: tauri::AppHandle) -> Result<SigningInfo, String> {
+    let path = signing::key_path(&app_handle)?;
+    let signer = MessageSigner::rotate(&path)?;
+
+    let manager = global::get_manager();
+    manager.set_signer(signer);
+    Ok(SigningInfo::new(
+        manager.signing_mode(),
+        manager.signer().as_deref(),
+    ))
+}
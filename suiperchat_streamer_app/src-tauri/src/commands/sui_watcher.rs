@@ -0,0 +1,116 @@
+//! オンチェーン着金監視関連のコマンドモジュール
+//!
+//! 配信者のウォレットへの着金を監視してスーパーチャットとして扱う機能の
+//! 開始・停止と、監視対象の設定を行うTauriコマンドを提供する
+
+use crate::state::AppState;
+use crate::sui_watcher::{self, SuiWatcherStatus, POLL_INTERVAL_RANGE_SECS};
+use tauri::State;
+
+/// 着金監視の対象と接続先を設定するTauriコマンド
+///
+/// 監視中に変更した場合は、次回のポーリングから新しい設定で監視し直します。
+///
+/// # 引数
+/// * `wallet_address` - 監視対象のウォレットアドレス（省略時は配信者のウォレットアドレス）
+/// * `rpc_url` - Sui RPCエンドポイント（省略時は現在の設定を維持）
+/// * `poll_interval_secs` - ポーリング間隔（秒、省略時は現在の設定を維持）
+/// * `app_state` - アプリケーションの状態
+/// * `app_handle` - Tauriアプリケーションハンドル
+///
+/// # 戻り値
+/// * `Result<SuiWatcherStatus, String>` - 成功時は適用後の監視状態、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - ウォレットアドレスの形式が不正な場合
+/// - ポーリング間隔が範囲外の場合
+#[tauri::command]
+pub fn set_sui_watcher_config(
+    wallet_address: Option<String>,
+    rpc_url: Option<String>,
+    poll_interval_secs: Option<u64>,
+    app_state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<SuiWatcherStatus, String> {
+    let wallet_address = wallet_address
+        .map(|address| address.trim().to_string())
+        .filter(|address| !address.is_empty());
+    if let Some(address) = &wallet_address {
+        let valid = address.len() == 66
+            && address
+                .strip_prefix("0x")
+                .is_some_and(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()));
+        if !valid {
+            return Err(format!("ウォレットアドレスの形式が不正です: {}", address));
+        }
+    }
+    if let Some(secs) = poll_interval_secs {
+        if !POLL_INTERVAL_RANGE_SECS.contains(&secs) {
+            return Err(format!(
+                "ポーリング間隔は{}〜{}秒の範囲で指定してください",
+                POLL_INTERVAL_RANGE_SECS.start(),
+                POLL_INTERVAL_RANGE_SECS.end()
+            ));
+        }
+    }
+
+    {
+        let mut config = app_state
+            .sui_watcher
+            .lock()
+            .map_err(|e| format!("着金監視の設定のロックに失敗しました: {}", e))?;
+        config.wallet_address = wallet_address;
+        if let Some(rpc_url) = rpc_url.filter(|url| !url.trim().is_empty()) {
+            config.rpc_url = rpc_url.trim().to_string();
+        }
+        if let Some(secs) = poll_interval_secs {
+            config.poll_interval_secs = secs;
+        }
+        println!("着金監視の設定を変更しました: {:?}", *config);
+    }
+
+    Ok(sui_watcher::status(&app_handle))
+}
+
+/// 着金監視を開始するTauriコマンド
+///
+/// 開始時点以降の着金のみをスーパーチャットとして扱います。
+///
+/// # 引数
+/// * `app_handle` - Tauriアプリケーションハンドル
+///
+/// # 戻り値
+/// * `Result<SuiWatcherStatus, String>` - 成功時は監視状態、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - 監視対象のウォレットアドレスが設定されていない場合
+#[tauri::command]
+pub fn start_sui_watcher(app_handle: tauri::AppHandle) -> Result<SuiWatcherStatus, String> {
+    sui_watcher::start(app_handle.clone())?;
+    Ok(sui_watcher::status(&app_handle))
+}
+
+/// 着金監視を停止するTauriコマンド
+///
+/// # 引数
+/// * `app_handle` - Tauriアプリケーションハンドル
+///
+/// # 戻り値
+/// * `Result<SuiWatcherStatus, String>` - 停止後の監視状態
+#[tauri::command]
+pub fn stop_sui_watcher(app_handle: tauri::AppHandle) -> Result<SuiWatcherStatus, String> {
+    sui_watcher::stop(&app_handle);
+    Ok(sui_watcher::status(&app_handle))
+}
+
+/// 着金監視の状態を取得するTauriコマンド
+///
+/// # 引数
+/// * `app_handle` - Tauriアプリケーションハンドル
+///
+/// # 戻り値
+/// * `Result<SuiWatcherStatus, String>` - 現在の監視状態
+#[tauri::command]
+pub fn get_sui_watcher_status(app_handle: tauri::AppHandle) -> Result<SuiWatcherStatus, String> {
+    Ok(sui_watcher::status(&app_handle))
+}
@@ -0,0 +1,80 @@
+//! スーパーチャットのアラート関連のコマンドモジュール
+//!
+//! 大口のスーパーチャットとして `big_superchat_received` イベントを発行する
+//! 金額の閾値を設定・取得するためのTauriコマンドを提供する
+
+use crate::state::AppState;
+use crate::superchat_alert::SuperchatAlertConfig;
+use tauri::State;
+
+/// 大口スーパーチャットの閾値を設定するTauriコマンド
+///
+/// スーパーチャットの金額が通貨の閾値以上の場合に `big_superchat_received` イベントを発行します。
+///
+/// # 引数
+/// * `coin` - 通貨シンボル（例: "SUI"）
+/// * `threshold` - 閾値（`None` の場合はその通貨を大口として扱わない）
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<SuperchatAlertConfig, String>` - 成功時は設定後のアラート設定、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - 通貨シンボルが空の場合
+/// - 0以下または数値でない閾値が指定された場合
+#[tauri::command]
+pub fn set_big_superchat_threshold(
+    coin: String,
+    threshold: Option<f64>,
+    app_state: State<'_, AppState>,
+) -> Result<SuperchatAlertConfig, String> {
+    let coin = coin.trim().to_string();
+    if coin.is_empty() {
+        return Err("通貨シンボルを指定してください".to_string());
+    }
+    if let Some(invalid) = threshold.filter(|t| !t.is_finite() || *t <= 0.0) {
+        return Err(format!(
+            "閾値には0より大きい値を指定してください: {}",
+            invalid
+        ));
+    }
+
+    let mut config = app_state
+        .superchat_alert
+        .lock()
+        .map_err(|e| format!("アラート設定のロックに失敗しました: {}", e))?;
+    match threshold {
+        Some(threshold) => {
+            config
+                .big_superchat_thresholds
+                .insert(coin.clone(), threshold);
+            println!(
+                "大口スーパーチャットの閾値を設定しました: {} {}",
+                threshold, coin
+            );
+        }
+        None => {
+            config.big_superchat_thresholds.remove(&coin);
+            println!("大口スーパーチャットの閾値を解除しました: {}", coin);
+        }
+    }
+    Ok(config.clone())
+}
+
+/// 現在のスーパーチャットのアラート設定を取得するTauriコマンド
+///
+/// # 引数
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<SuperchatAlertConfig, String>` - 成功時はアラート設定、エラー時はエラーメッセージ
+#[tauri::command]
+pub fn get_superchat_alert_config(
+    app_state: State<'_, AppState>,
+) -> Result<SuperchatAlertConfig, String> {
+    app_state
+        .superchat_alert
+        .lock()
+        .map(|config| config.clone())
+        .map_err(|e| format!("アラート設定のロックに失敗しました: {}", e))
+}
@@ -0,0 +1,124 @@
+//! メッセージ翻訳関連のコマンドモジュール
+//!
+//! 翻訳の有効化・翻訳先言語・翻訳APIのキーを設定するためのTauriコマンドを提供する
+
+use crate::state::AppState;
+use crate::translation::{TranslationApiKey, TranslationConfig};
+use serde::Serialize;
+use tauri::State;
+
+/// ## 翻訳の設定（フロントエンドへの応答用）
+///
+/// APIキーの値は返さず、設定済みかどうかのみを返します。
+#[derive(Debug, Clone, Serialize)]
+pub struct TranslationSettings {
+    /// 翻訳の設定
+    #[serde(flatten)]
+    pub config: TranslationConfig,
+    /// 翻訳APIのキーが設定されているかどうか
+    pub api_key_configured: bool,
+}
+
+/// ## 現在の翻訳の設定を取得する
+fn translation_settings(app_state: &AppState) -> Result<TranslationSettings, String> {
+    let config = app_state
+        .translation
+        .lock()
+        .map_err(|e| format!("翻訳設定のロックに失敗しました: {}", e))?
+        .clone();
+    let api_key_configured = app_state
+        .translation_api_key
+        .lock()
+        .map_err(|e| format!("翻訳APIキーのロックに失敗しました: {}", e))?
+        .is_some();
+    Ok(TranslationSettings {
+        config,
+        api_key_configured,
+    })
+}
+
+/// ## 翻訳の設定を取得するTauriコマンド
+///
+/// ### Arguments
+/// - `app_state`: アプリケーションの状態
+///
+/// ### Returns
+/// - `Result<TranslationSettings, String>`: 現在の翻訳の設定とAPIキーの設定有無
+#[tauri::command]
+pub fn get_translation_config(
+    app_state: State<'_, AppState>,
+) -> Result<TranslationSettings, String> {
+    translation_settings(&app_state)
+}
+
+/// ## 翻訳の設定を更新するTauriコマンド
+///
+/// ### Arguments
+/// - `config`: 新しい翻訳の設定
+/// - `app_state`: アプリケーションの状態
+///
+/// ### Returns
+/// - `Result<TranslationSettings, String>`: 更新後の翻訳の設定、または翻訳先言語が不正な場合はエラーメッセージ
+#[tauri::command]
+pub fn set_translation_config(
+    config: TranslationConfig,
+    app_state: State<'_, AppState>,
+) -> Result<TranslationSettings, String> {
+    let target_language = config.target_language.trim().to_ascii_lowercase();
+    if target_language.is_empty()
+        || !target_language
+            .chars()
+            .all(|c| c.is_ascii_alphabetic() || c == '-')
+    {
+        return Err(format!(
+            "翻訳先の言語コードが不正です: {}",
+            config.target_language
+        ));
+    }
+
+    {
+        let mut translation = app_state
+            .translation
+            .lock()
+            .map_err(|e| format!("翻訳設定のロックに失敗しました: {}", e))?;
+        *translation = TranslationConfig {
+            target_language,
+            ..config
+        };
+        println!("翻訳設定を更新しました: {:?}", *translation);
+    }
+    translation_settings(&app_state)
+}
+
+/// ## 翻訳APIのキーを設定するTauriコマンド
+///
+/// キーはメモリ上にのみ保持し、アプリケーションの終了時に破棄されます。
+///
+/// ### Arguments
+/// - `api_key`: 翻訳APIのキー（Noneまたは空文字の場合は削除）
+/// - `app_state`: アプリケーションの状態
+///
+/// ### Returns
+/// - `Result<TranslationSettings, String>`: 更新後の翻訳の設定
+#[tauri::command]
+pub fn set_translation_api_key(
+    api_key: Option<String>,
+    app_state: State<'_, AppState>,
+) -> Result<TranslationSettings, String> {
+    {
+        let mut translation_api_key = app_state
+            .translation_api_key
+            .lock()
+            .map_err(|e| format!("翻訳APIキーのロックに失敗しました: {}", e))?;
+        *translation_api_key = api_key.as_deref().and_then(TranslationApiKey::new);
+        println!(
+            "翻訳APIキーを{}しました",
+            if translation_api_key.is_some() {
+                "設定"
+            } else {
+                "削除"
+            }
+        );
+    }
+    translation_settings(&app_state)
+}
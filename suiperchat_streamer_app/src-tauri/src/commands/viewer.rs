@@ -0,0 +1,177 @@
+//! 視聴者プロフィール関連のコマンドモジュール
+//!
+//! 複数の配信にわたる視聴者（ウォレットアドレスを持つ視聴者）の累計実績を
+//! 取得・一覧表示し、集計のオプトアウトを設定するためのTauriコマンドを提供する
+
+use crate::badges;
+use crate::commands::history::get_db_pool;
+use crate::database;
+use crate::db_models::{DonorRank, ViewerProfile};
+use crate::state::AppState;
+use sqlx::Error as SqlxError;
+use tauri::State;
+
+/// 一覧取得時のデフォルト件数
+const DEFAULT_VIEWER_LIST_LIMIT: i64 = 50;
+
+/// 一覧取得時の最大件数
+const MAX_VIEWER_LIST_LIMIT: i64 = 500;
+
+/// 視聴者プロフィールを取得するTauriコマンド
+///
+/// # 引数
+/// * `wallet_address` - 対象の視聴者のウォレットアドレス
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<Option<ViewerProfile>, String>` - 成功時は視聴者プロフィール（未集計・オプトアウト済みの場合はNone）、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - データベース接続が初期化されていない場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn get_viewer_profile(
+    wallet_address: String,
+    app_state: State<'_, AppState>,
+) -> Result<Option<ViewerProfile>, String> {
+    let db_pool = get_db_pool(&app_state)?;
+
+    match database::get_viewer_profile(&db_pool, wallet_address.trim()).await {
+        Ok(profile) => Ok(Some(profile)),
+        Err(SqlxError::RowNotFound) => Ok(None),
+        Err(e) => {
+            let error_msg = format!(
+                "視聴者プロフィールの取得中にデータベースエラーが発生しました: {}",
+                e
+            );
+            eprintln!("エラー: {}", error_msg);
+            Err(error_msg)
+        }
+    }
+}
+
+/// 視聴者プロフィールの一覧を累計スーパーチャット額の多い順に取得するTauriコマンド
+///
+/// # 引数
+/// * `limit` - 取得する最大件数（デフォルト50、最大500）
+/// * `offset` - 取得開始位置（デフォルト0）
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<Vec<ViewerProfile>, String>` - 成功時は視聴者プロフィールのベクター、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - データベース接続が初期化されていない場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn list_viewer_profiles(
+    limit: Option<i64>,
+    offset: Option<i64>,
+    app_state: State<'_, AppState>,
+) -> Result<Vec<ViewerProfile>, String> {
+    let db_pool = get_db_pool(&app_state)?;
+    let limit = limit
+        .unwrap_or(DEFAULT_VIEWER_LIST_LIMIT)
+        .clamp(1, MAX_VIEWER_LIST_LIMIT);
+    let offset = offset.unwrap_or(0).max(0);
+
+    database::list_viewer_profiles(&db_pool, limit, offset)
+        .await
+        .map_err(|e| {
+            let error_msg = format!(
+                "視聴者プロフィール一覧の取得中にデータベースエラーが発生しました: {}",
+                e
+            );
+            eprintln!("エラー: {}", error_msg);
+            error_msg
+        })
+}
+
+/// 全セッションを横断したスーパーチャット送金者のランキングを取得するTauriコマンド
+///
+/// 通貨ごとに累計スーパーチャット額の多い順に、最新の表示名・ウォレットアドレス・通貨・累計額・回数を返します。
+/// 異なる通貨の金額は合算しません。
+///
+/// # 引数
+/// * `limit` - 通貨ごとに取得する最大件数（デフォルト50、最大500）
+/// * `coin` - 集計対象の通貨シンボル（未指定の場合は全通貨）
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<Vec<DonorRank>, String>` - 成功時はランキングのベクター、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - データベース接続が初期化されていない場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn get_top_donors(
+    limit: Option<i64>,
+    coin: Option<String>,
+    app_state: State<'_, AppState>,
+) -> Result<Vec<DonorRank>, String> {
+    let db_pool = get_db_pool(&app_state)?;
+    let limit = limit
+        .unwrap_or(DEFAULT_VIEWER_LIST_LIMIT)
+        .clamp(1, MAX_VIEWER_LIST_LIMIT);
+    let coin = coin
+        .as_deref()
+        .map(str::trim)
+        .filter(|coin| !coin.is_empty());
+
+    database::get_top_donors(&db_pool, limit, coin)
+        .await
+        .map_err(|e| {
+            let error_msg = format!(
+                "送金者ランキングの取得中にデータベースエラーが発生しました: {}",
+                e
+            );
+            eprintln!("エラー: {}", error_msg);
+            error_msg
+        })
+}
+
+/// 視聴者の集計オプトアウトを設定するTauriコマンド
+///
+/// オプトアウトした視聴者の累計実績は破棄され、以降のメッセージも集計されません。
+///
+/// # 引数
+/// * `wallet_address` - 対象の視聴者のウォレットアドレス
+/// * `opted_out` - オプトアウトする場合は `true`、解除する場合は `false`
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<(), String>` - 成功時は `Ok(())`、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - ウォレットアドレスが空の場合
+/// - データベース操作中にエラーが発生した場合
+#[tauri::command]
+pub async fn set_viewer_opt_out(
+    wallet_address: String,
+    opted_out: bool,
+    app_state: State<'_, AppState>,
+) -> Result<(), String> {
+    let wallet_address = wallet_address.trim();
+    if wallet_address.is_empty() {
+        return Err("ウォレットアドレスを指定してください".to_string());
+    }
+
+    let db_pool = get_db_pool(&app_state)?;
+    database::set_viewer_opt_out(&db_pool, wallet_address, opted_out)
+        .await
+        .map_err(|e| {
+            let error_msg = format!(
+                "視聴者のオプトアウト設定中にデータベースエラーが発生しました: {}",
+                e
+            );
+            eprintln!("エラー: {}", error_msg);
+            error_msg
+        })?;
+    badges::invalidate_viewer_profile(&app_state, wallet_address);
+
+    println!(
+        "視聴者の集計オプトアウトを設定しました: {} (opted_out={})",
+        wallet_address, opted_out
+    );
+    Ok(())
+}
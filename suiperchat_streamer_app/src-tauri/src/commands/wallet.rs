@@ -1,8 +1,13 @@
 //! ウォレット関連のコマンド
 //!
-//! ウォレットアドレスの設定・取得を行うコマンドを提供します。
+//! ウォレットアドレスの設定・取得と、複数ウォレットの登録・切り替えを行うコマンドを提供します。
 
+use crate::settings::{self, REQUIRE_WALLET_KEY};
 use crate::state::AppState;
+use crate::validation;
+use crate::wallet_registry::{self, WalletEntry};
+use crate::ws_server::access_token;
+use crate::ws_server::server_utils::obs_page_url;
 use serde::Serialize;
 use tauri::{command, Emitter, State};
 
@@ -11,7 +16,7 @@ use tauri::{command, Emitter, State};
 /// WebSocketの接続URLと配信者のウォレットアドレスを含みます。
 #[derive(Serialize, Clone)]
 pub struct StreamerInfo {
-    /// WebSocketサーバーの完全なURL (例: "ws://127.0.0.1:8080")
+    /// WebSocketサーバーの完全なURL (例: "ws://127.0.0.1:8080/ws"、アクセストークン設定時は "?token=..." 付き)
     ws_url: String,
     /// OBSサーバーの完全なURL (例: "http://127.0.0.1:8081/obs/")
     obs_url: String,
@@ -19,11 +24,27 @@ pub struct StreamerInfo {
     wallet_address: String,
     /// YouTube動画ID (設定されている場合)
     youtube_video_id: Option<String>,
+    /// 受け付けるコインのシンボル (制限しない場合はNone)
+    accepted_coins: Option<Vec<String>>,
 }
 
+/// ## フロントエンドに渡すウォレットの一覧
+#[derive(Serialize, Clone)]
+pub struct WalletList {
+    /// 登録済みのウォレット
+    wallets: Vec<WalletEntry>,
+    /// アクティブなウォレットのアドレス（未登録の場合はNone）
+    active_address: Option<String>,
+}
+
+/// `set_wallet_address` で登録するウォレットのラベル
+const DEFAULT_WALLET_LABEL: &str = "メイン";
+
 /// ## ウォレットアドレスを設定する Tauri コマンド
 ///
-/// フロントエンドから受け取ったウォレットアドレスを `AppState` に保存します。
+/// フロントエンドから受け取ったウォレットアドレスをアクティブなウォレットにします。
+/// 未登録のアドレスの場合は "メイン" のラベルで登録します。
+/// 登録済みのウォレットはDBに保存され、次回起動時に復元されます。
 ///
 /// ### Arguments
 /// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
@@ -38,46 +59,231 @@ pub fn set_wallet_address(
     address: String,
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
-    let trimmed_address = address.trim();
-
     // --- SUIウォレットアドレス形式のバリデーション ---
-    if !trimmed_address.starts_with("0x") {
-        return Err("Invalid SUI wallet address: Must start with '0x'.".to_string());
+    let address = validation::validate_sui_address(&address)?;
+
+    // --- アドレスを登録してアクティブにする ---
+    {
+        let mut wallets = app_state
+            .wallets
+            .lock()
+            .map_err(|_| "Failed to lock wallets mutex".to_string())?;
+        let index = match wallet_registry::find_wallet(&wallets, &address) {
+            Some(index) => index,
+            None => {
+                wallets.push(WalletEntry {
+                    label: DEFAULT_WALLET_LABEL.to_string(),
+                    address,
+                });
+                wallets.len() - 1
+            }
+        };
+        *app_state
+            .active_wallet_index
+            .lock()
+            .map_err(|_| "Failed to lock active wallet index mutex".to_string())? = Some(index);
+    }
+    settings::save_wallets(&app_state);
+
+    notify_wallet_address_updated(&app_handle)
+}
+
+/// ## ウォレットを登録する Tauri コマンド
+///
+/// 最初に登録したウォレットはアクティブなウォレットになります。
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `label`: ウォレットのラベル (例: "サブ")
+/// - `address`: 登録するウォレットアドレス
+/// - `app_handle`: Tauri アプリケーションハンドル (`tauri::AppHandle`)
+///
+/// ### Returns
+/// - `Result<WalletList, String>`: 成功した場合は登録後のウォレットの一覧、エラーの場合はエラーメッセージ
+#[command]
+pub fn add_wallet(
+    app_state: State<'_, AppState>,
+    label: String,
+    address: String,
+    app_handle: tauri::AppHandle,
+) -> Result<WalletList, String> {
+    let label = label.trim().to_string();
+    if label.is_empty() {
+        return Err("ウォレットのラベルを指定してください".to_string());
+    }
+    let address = validation::validate_sui_address(&address)?;
+
+    let activated = {
+        let mut wallets = app_state
+            .wallets
+            .lock()
+            .map_err(|_| "Failed to lock wallets mutex".to_string())?;
+        if wallet_registry::find_wallet(&wallets, &address).is_some() {
+            return Err(format!("ウォレットは既に登録されています: {}", address));
+        }
+        wallets.push(WalletEntry { label, address });
+
+        let mut active_index = app_state
+            .active_wallet_index
+            .lock()
+            .map_err(|_| "Failed to lock active wallet index mutex".to_string())?;
+        let activated = active_index.is_none();
+        if activated {
+            *active_index = Some(wallets.len() - 1);
+        }
+        activated
+    };
+    settings::save_wallets(&app_state);
+
+    if activated {
+        notify_wallet_address_updated(&app_handle)?;
     }
-    if trimmed_address.len() != 66 {
-        // "0x" + 64 hex characters
-        return Err(format!(
-            "Invalid SUI wallet address: Expected length 66, got {}.",
-            trimmed_address.len()
-        ));
+    list_wallets(app_state)
+}
+
+/// ## ウォレットの登録を解除する Tauri コマンド
+///
+/// アクティブなウォレットを解除した場合は、残っている最初のウォレットがアクティブになります。
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `address`: 登録を解除するウォレットアドレス
+/// - `app_handle`: Tauri アプリケーションハンドル (`tauri::AppHandle`)
+///
+/// ### Returns
+/// - `Result<WalletList, String>`: 成功した場合は解除後のウォレットの一覧、エラーの場合はエラーメッセージ
+#[command]
+pub fn remove_wallet(
+    app_state: State<'_, AppState>,
+    address: String,
+    app_handle: tauri::AppHandle,
+) -> Result<WalletList, String> {
+    let active_changed = {
+        let mut wallets = app_state
+            .wallets
+            .lock()
+            .map_err(|_| "Failed to lock wallets mutex".to_string())?;
+        let index = wallet_registry::find_wallet(&wallets, &address)
+            .ok_or_else(|| format!("ウォレットが登録されていません: {}", address.trim()))?;
+        wallets.remove(index);
+
+        let mut active_index = app_state
+            .active_wallet_index
+            .lock()
+            .map_err(|_| "Failed to lock active wallet index mutex".to_string())?;
+        match *active_index {
+            Some(active) if active == index => {
+                *active_index = (!wallets.is_empty()).then_some(0);
+                true
+            }
+            Some(active) if active > index => {
+                *active_index = Some(active - 1);
+                false
+            }
+            _ => false,
+        }
+    };
+    settings::save_wallets(&app_state);
+
+    if active_changed {
+        notify_wallet_address_updated(&app_handle)?;
     }
-    if !trimmed_address[2..].chars().all(|c| c.is_ascii_hexdigit()) {
-        return Err(
-            "Invalid SUI wallet address: Contains non-hexadecimal characters after '0x'."
-                .to_string(),
+    list_wallets(app_state)
+}
+
+/// ## アクティブなウォレットを切り替える Tauri コマンド
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+/// - `address`: アクティブにする登録済みのウォレットアドレス
+/// - `app_handle`: Tauri アプリケーションハンドル (`tauri::AppHandle`)
+///
+/// ### Returns
+/// - `Result<WalletList, String>`: 成功した場合は切り替え後のウォレットの一覧、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_active_wallet(
+    app_state: State<'_, AppState>,
+    address: String,
+    app_handle: tauri::AppHandle,
+) -> Result<WalletList, String> {
+    {
+        let wallets = app_state
+            .wallets
+            .lock()
+            .map_err(|_| "Failed to lock wallets mutex".to_string())?;
+        let index = wallet_registry::find_wallet(&wallets, &address)
+            .ok_or_else(|| format!("ウォレットが登録されていません: {}", address.trim()))?;
+        *app_state
+            .active_wallet_index
+            .lock()
+            .map_err(|_| "Failed to lock active wallet index mutex".to_string())? = Some(index);
+        println!(
+            "アクティブなウォレットを切り替えました: {} ({})",
+            wallets[index].label, wallets[index].address
         );
     }
-    // --- バリデーションここまで ---
+    settings::save_wallets(&app_state);
+
+    notify_wallet_address_updated(&app_handle)?;
+    list_wallets(app_state)
+}
+
+/// ## 登録済みのウォレットの一覧を取得する Tauri コマンド
+///
+/// ### Arguments
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<WalletList, String>`: 成功した場合はウォレットの一覧とアクティブなアドレス
+#[command]
+pub fn list_wallets(app_state: State<'_, AppState>) -> Result<WalletList, String> {
+    let wallets = app_state
+        .wallets
+        .lock()
+        .map_err(|_| "Failed to lock wallets mutex".to_string())?
+        .clone();
+    Ok(WalletList {
+        wallets,
+        active_address: wallet_registry::active_wallet_address(&app_state),
+    })
+}
 
-    // --- アドレスを AppState に保存 ---
-    let mut wallet_addr = app_state
-        .wallet_address
+/// ## ウォレットアドレス未設定時の起動拒否を切り替える Tauri コマンド
+///
+/// 有効にすると、ウォレットアドレスが未設定のままサーバーを起動しようとした場合に
+/// 起動を拒否します。無効（デフォルト）の場合は警告をログに出力して起動を許可します。
+///
+/// ### Arguments
+/// - `required`: ウォレットアドレスの設定を必須にするかどうか
+/// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功した場合は `Ok(())`、エラーの場合はエラーメッセージ
+#[command]
+pub fn set_require_wallet(required: bool, app_state: State<'_, AppState>) -> Result<(), String> {
+    *app_state
+        .require_wallet
         .lock()
-        .map_err(|_| "Failed to lock wallet address mutex".to_string())?;
-    *wallet_addr = Some(trimmed_address.to_string());
+        .map_err(|_| "Failed to lock require wallet mutex".to_string())? = required;
+    settings::save_setting(&app_state, REQUIRE_WALLET_KEY, required.to_string());
+    println!(
+        "ウォレットアドレスの設定必須モードを設定しました: {}",
+        required
+    );
+    Ok(())
+}
 
-    // --- イベントを発行 ---
+/// アクティブなウォレットアドレスの変更をフロントエンドに通知する
+fn notify_wallet_address_updated(app_handle: &tauri::AppHandle) -> Result<(), String> {
     app_handle.emit("wallet_address_updated", ()).map_err(|e| {
         eprintln!("Failed to emit wallet_address_updated event: {}", e);
         "Failed to notify frontend about wallet address update".to_string()
-    })?;
-
-    Ok(())
+    })
 }
 
 /// ## 単純にウォレットアドレスを取得する Tauri コマンド
 ///
-/// 現在設定されているウォレットアドレスのみを返します。
+/// 現在アクティブなウォレットアドレスのみを返します。
 /// サーバーの状態に依存しないため、サーバー起動前にウォレットアドレスの
 /// 確認を行う場合に使用します。
 ///
@@ -88,15 +294,12 @@ pub fn set_wallet_address(
 /// - `Result<{ wallet_address: Option<String> }, String>`: 成功した場合はウォレットアドレスを含むオブジェクト
 #[command]
 pub fn get_wallet_address(app_state: State<'_, AppState>) -> Result<serde_json::Value, String> {
-    // ウォレットアドレスを取得
-    let wallet_addr_guard = app_state
-        .wallet_address
-        .lock()
-        .map_err(|_| "Failed to lock wallet address mutex".to_string())?;
+    // アクティブなウォレットアドレスを取得
+    let wallet_address = wallet_registry::active_wallet_address(&app_state);
 
     // JSONオブジェクトを作成
     // wallet_addressが存在する場合はそれを、存在しない場合はnullを返す
-    let json_result = if let Some(addr) = wallet_addr_guard.as_ref() {
+    let json_result = if let Some(addr) = wallet_address.as_ref() {
         serde_json::json!({ "wallet_address": addr })
     } else {
         serde_json::json!({ "wallet_address": null })
@@ -107,7 +310,7 @@ pub fn get_wallet_address(app_state: State<'_, AppState>) -> Result<serde_json::
 
 /// ## 配信者情報を取得する Tauri コマンド
 ///
-/// 現在設定されている配信者のウォレットアドレスと、
+/// 現在アクティブな配信者のウォレットアドレスと、
 /// 稼働中の（またはデフォルトの）WebSocketサーバーURLとOBSサーバーURLを取得して返します。
 ///
 /// ### Arguments
@@ -119,15 +322,9 @@ pub fn get_wallet_address(app_state: State<'_, AppState>) -> Result<serde_json::
 pub fn get_streamer_info(app_state: State<'_, AppState>) -> Result<StreamerInfo, String> {
     println!("Getting streamer info...");
 
-    // --- ウォレットアドレスを取得 ---
-    let wallet_addr_guard = app_state
-        .wallet_address
-        .lock()
-        .map_err(|_| "Failed to lock wallet address mutex".to_string())?;
-    let wallet_address = wallet_addr_guard
-        .as_ref()
-        .ok_or_else(|| "Wallet address is not set. Please configure it first.".to_string())?
-        .clone();
+    // --- アクティブなウォレットアドレスを取得 ---
+    let wallet_address = wallet_registry::active_wallet_address(&app_state)
+        .ok_or_else(|| "Wallet address is not set. Please configure it first.".to_string())?;
 
     // --- YouTube動画IDを取得 ---
     let youtube_id_guard = app_state
@@ -136,6 +333,14 @@ pub fn get_streamer_info(app_state: State<'_, AppState>) -> Result<StreamerInfo,
         .map_err(|_| "Failed to lock YouTube video ID mutex".to_string())?;
     let youtube_video_id = youtube_id_guard.clone();
 
+    // --- 受け付けるコインを取得 ---
+    // 視聴者フロントで送金前に選択できるコインを絞り込み、拒否されるコインでの送金を防ぐ
+    let accepted_coins = app_state
+        .accepted_coins
+        .lock()
+        .map_err(|_| "Failed to lock accepted coins mutex".to_string())?
+        .clone();
+
     // --- WebSocket URLをAppStateから構築 ---
     let host_guard = app_state
         .host
@@ -161,12 +366,17 @@ pub fn get_streamer_info(app_state: State<'_, AppState>) -> Result<StreamerInfo,
     let obs_port = obs_port_guard
         .ok_or_else(|| "OBS server port is not available (server not running?).".to_string())?;
 
+    // アクセストークン設定時は、視聴者・OBSに渡すURLにトークンを付与する
+    let token = access_token::current_access_token(&app_state);
+
     // WebSocket URL を構築
-    let ws_url = format!("ws://{}:{}/ws", host, port);
+    let ws_url =
+        access_token::with_access_token(format!("ws://{}:{}/ws", host, port), token.as_deref());
     println!("Constructed ws_url from AppState: {}", ws_url);
 
     // OBS URL を構築
-    let obs_url = format!("http://{}:{}/obs/", host, obs_port);
+    let obs_url =
+        access_token::with_access_token(obs_page_url(host, obs_port, Some(port)), token.as_deref());
     println!("Constructed obs_url from AppState: {}", obs_url);
 
     Ok(StreamerInfo {
@@ -174,5 +384,6 @@ pub fn get_streamer_info(app_state: State<'_, AppState>) -> Result<StreamerInfo,
         obs_url,
         wallet_address,
         youtube_video_id,
+        accepted_coins,
     })
 }
@@ -0,0 +1,57 @@
+//! Webhook関連のコマンドモジュール
+//!
+//! 受信メッセージを外部サービスへ転送するWebhookを設定するためのTauriコマンドを提供する
+
+use crate::state::AppState;
+use tauri::State;
+
+/// 受信メッセージを転送するWebhookを設定するTauriコマンド
+///
+/// スーパーチャット（`include_chat` が `true` の場合は通常チャットも）を受信するたびに、
+/// 設定したURLへJSONでPOSTします。
+///
+/// # 引数
+/// * `url` - WebhookのURL（http/https）。`None` または空文字の場合は転送を停止
+/// * `include_chat` - 通常チャットも転送するかどうか
+/// * `app_state` - アプリケーションの状態
+///
+/// # 戻り値
+/// * `Result<(), String>` - 成功時は `Ok(())`、エラー時はエラーメッセージ
+///
+/// # エラー
+/// - URLの形式が不正な場合
+/// - http/https 以外のURLが指定された場合
+#[tauri::command]
+pub fn set_webhook_url(
+    url: Option<String>,
+    include_chat: bool,
+    app_state: State<'_, AppState>,
+) -> Result<(), String> {
+    let url = url
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+
+    if let Some(url) = &url {
+        let parsed = url::Url::parse(url).map_err(|e| format!("無効なURLです: {}", e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err("WebhookのURLは http または https で指定してください".to_string());
+        }
+    }
+
+    *app_state
+        .webhook_include_chat
+        .lock()
+        .map_err(|e| format!("Webhook設定のロックに失敗しました: {}", e))? = include_chat;
+    *app_state
+        .webhook_url
+        .lock()
+        .map_err(|e| format!("Webhook設定のロックに失敗しました: {}", e))? = url.clone();
+
+    // URLにはトークンが含まれるため、マスクして出力する
+    println!(
+        "Webhookを設定しました: {:?} (通常チャットの転送: {})",
+        url.as_deref().map(crate::webhook::redact_url),
+        include_chat
+    );
+    Ok(())
+}
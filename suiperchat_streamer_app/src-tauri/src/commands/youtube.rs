@@ -2,6 +2,7 @@
 //!
 //! YouTube動画IDの設定を行うコマンドを提供します。
 
+use crate::settings::{self, YOUTUBE_VIDEO_ID_KEY};
 use crate::state::AppState;
 use serde_json::json;
 use tauri::{command, Emitter, State};
@@ -9,7 +10,7 @@ use tauri::{command, Emitter, State};
 /// ## YouTube動画IDを設定する Tauri コマンド
 ///
 /// フロントエンドから受け取ったYouTube動画IDを `AppState` に保存します。
-/// この設定はアプリ起動ごとにリセットされる一時的な設定です。
+/// 設定はDBに保存され、次回起動時に復元されます。
 ///
 /// ### Arguments
 /// - `app_state`: Tauri の管理するアプリケーション状態 (`State<AppState>`)
@@ -50,11 +51,16 @@ pub fn set_youtube_video_id(
     }
 
     // アドレスを AppState に保存
-    let mut youtube_id = app_state
+    *app_state
         .youtube_video_id
         .lock()
-        .map_err(|_| "Failed to lock YouTube video ID mutex".to_string())?;
-    *youtube_id = Some(trimmed_video_id.to_string());
+        .map_err(|_| "Failed to lock YouTube video ID mutex".to_string())? =
+        Some(trimmed_video_id.to_string());
+    settings::save_setting(
+        &app_state,
+        YOUTUBE_VIDEO_ID_KEY,
+        trimmed_video_id.to_string(),
+    );
 
     // イベントを発行
     app_handle
@@ -0,0 +1,406 @@
+//! クラッシュレポート管理モジュール
+//!
+//! パニック発生時にバックトレースやサーバー状態をアプリデータディレクトリの
+//! `crash_reports/` にJSONで保存し、次回起動時に未送信のレポートを配信者に通知します。
+//! 通知はフロントエンドがイベントの購読後に `notify_crash_reports_ready` を呼び出した時点で行います。
+//! ウォレットアドレス・IPアドレス・トークン・Webhook URL等の機密情報はレポートに含めません。
+
+use crate::state::AppState;
+use crate::types::get_connections_count;
+use chrono::Local;
+use once_cell::sync::{Lazy, OnceCell};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::net::Ipv6Addr;
+use std::path::{Path, PathBuf};
+use tauri::{Emitter, Manager};
+
+/// クラッシュレポートを保存するディレクトリ名（アプリデータディレクトリ配下）
+const CRASH_REPORTS_DIR: &str = "crash_reports";
+
+/// 未送信のクラッシュレポートがある場合に発行するTauriイベント名（フロントエンドの準備完了後に発行する）
+pub const CRASH_REPORTS_PENDING_EVENT: &str = "crash_reports_pending";
+
+/// クラッシュレポートの保存先ディレクトリ（セットアップ時に設定）
+static CRASH_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+/// サーバー状態の取得に使用するアプリケーションハンドル（セットアップ時に設定）
+static APP_HANDLE: OnceCell<tauri::AppHandle> = OnceCell::new();
+
+/// レポートから除外する16進数のアドレス・ハッシュ（ウォレットアドレス等）
+static HEX_ADDRESS_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"0x[0-9a-fA-F]{8,}").unwrap());
+
+/// レポートから除外するURLのパス・クエリ（Webhook URLのトークン等）
+static URL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)(https?|wss?)://([^/\s"'<>?#]+)[^\s"'<>]*"#).unwrap());
+
+/// レポートから除外するトークン・キー（`token=...`・`Bearer ...` 等）
+static TOKEN_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)\b((?:access_token|api_key|apikey|token|secret|password|authorization)["']?\s*[:=]\s*["']?(?:bearer\s+)?|bearer\s+)[^\s"'&,;]+"#,
+    )
+    .unwrap()
+});
+
+/// レポートから除外するIPv4アドレス
+static IPV4_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(?:\d{1,3}\.){3}\d{1,3}\b").unwrap());
+
+/// IPv6アドレスの候補（時刻等と区別するため、置換前にアドレスとして解析する）
+static IPV6_CANDIDATE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:[0-9a-fA-F]{0,4}:){2,7}[0-9a-fA-F]{0,4}").unwrap());
+
+/// 機密情報を置き換える文字列
+const REDACTED: &str = "[REDACTED]";
+
+/// ## クラッシュ時のサーバー状態
+///
+/// 状態のロックを取得できなかった項目は `None` になります。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CrashServerState {
+    /// WebSocketサーバーが起動していたかどうか
+    pub server_running: Option<bool>,
+    /// サーバー起動処理のフェーズ
+    pub startup_phase: Option<String>,
+    /// 接続中のクライアント数
+    pub active_connections: usize,
+    /// Cloudflaredトンネルが起動していたかどうか
+    pub tunnel_running: Option<bool>,
+    /// データベースに接続していたかどうか
+    pub database_connected: Option<bool>,
+    /// 配信セッションが進行中だったかどうか
+    pub session_active: Option<bool>,
+}
+
+/// ## クラッシュレポート
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    /// レポートID（ファイル名の拡張子を除いた部分）
+    pub id: String,
+    /// 発生時刻 (RFC3339)
+    pub occurred_at: String,
+    /// アプリバージョン
+    pub app_version: String,
+    /// パニックメッセージ（機密情報を除去済み）
+    pub message: String,
+    /// パニックの発生箇所 (ファイル:行:列)
+    pub location: Option<String>,
+    /// パニックが発生したスレッド名
+    pub thread: Option<String>,
+    /// バックトレース
+    pub backtrace: String,
+    /// クラッシュ時のサーバー状態
+    pub server_state: CrashServerState,
+    /// 配信者がレポートを送信済みかどうか
+    #[serde(default)]
+    pub submitted: bool,
+}
+
+/// ## パニックハンドラを設定する
+///
+/// 既存のハンドラ（標準エラー出力へのメッセージ表示）を維持したまま、
+/// クラッシュレポートをファイルに保存する処理を追加します。
+/// `run()` の冒頭で呼び出します。
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+
+        let message = if let Some(s) = info.payload().downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = info.payload().downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "不明なパニック".to_string()
+        };
+
+        let now = Local::now();
+        let report = CrashReport {
+            id: format!("crash-{}", now.format("%Y%m%d-%H%M%S%.3f")),
+            occurred_at: now.to_rfc3339(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            message: redact(&message),
+            location: info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            thread: std::thread::current().name().map(str::to_string),
+            backtrace: Backtrace::force_capture().to_string(),
+            server_state: capture_server_state(),
+            submitted: false,
+        };
+
+        match write_report(&report) {
+            Ok(path) => eprintln!("クラッシュレポートを保存しました: {}", path.display()),
+            Err(e) => eprintln!("クラッシュレポートの保存に失敗しました: {}", e),
+        }
+    }));
+}
+
+/// ## クラッシュレポートの保存先を初期化する
+///
+/// アプリデータディレクトリ配下に `crash_reports/` を作成します。
+/// フロントエンドがイベントを購読する前に発行すると通知が失われるため、
+/// 未送信のレポートの通知は `notify_pending` で行います。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功時は `Ok(())`、失敗時はエラーメッセージ
+pub fn init(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("アプリデータディレクトリの取得に失敗しました: {}", e))?
+        .join(CRASH_REPORTS_DIR);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("クラッシュレポートディレクトリの作成に失敗しました: {}", e))?;
+
+    let _ = CRASH_DIR.set(dir);
+    let _ = APP_HANDLE.set(app_handle.clone());
+
+    let pending = pending_reports()?.len();
+    if pending > 0 {
+        println!(
+            "前回以前のクラッシュレポートが {} 件見つかりました",
+            pending
+        );
+    }
+    Ok(())
+}
+
+/// ## 未送信のクラッシュレポートを通知する
+///
+/// フロントエンドが `crash_reports_pending` イベントを購読した後に呼び出します。
+/// 未送信のレポートがあればイベントで通知します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+///
+/// ### Returns
+/// - `Result<Vec<CrashReport>, String>`: 未送信のクラッシュレポート
+pub fn notify_pending(app_handle: &tauri::AppHandle) -> Result<Vec<CrashReport>, String> {
+    let pending = pending_reports()?;
+    if !pending.is_empty() {
+        if let Err(e) = app_handle.emit(CRASH_REPORTS_PENDING_EVENT, &pending) {
+            eprintln!(
+                "{} イベントの発火に失敗しました: {}",
+                CRASH_REPORTS_PENDING_EVENT, e
+            );
+        }
+    }
+    Ok(pending)
+}
+
+/// 未送信のクラッシュレポートを新しい順に取得する
+fn pending_reports() -> Result<Vec<CrashReport>, String> {
+    Ok(list_reports()?
+        .into_iter()
+        .filter(|report| !report.submitted)
+        .collect())
+}
+
+/// ## 保存済みのクラッシュレポートを新しい順に取得する
+///
+/// ### Returns
+/// - `Result<Vec<CrashReport>, String>`: クラッシュレポートの一覧
+pub fn list_reports() -> Result<Vec<CrashReport>, String> {
+    let dir = crash_dir()?;
+    let entries = std::fs::read_dir(dir).map_err(|e| {
+        format!(
+            "クラッシュレポートディレクトリの読み込みに失敗しました: {}",
+            e
+        )
+    })?;
+
+    let mut reports: Vec<CrashReport> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| match read_report(&path) {
+            Ok(report) => Some(report),
+            Err(e) => {
+                eprintln!("{}", e);
+                None
+            }
+        })
+        .collect();
+    reports.sort_by(|a, b| b.occurred_at.cmp(&a.occurred_at));
+    Ok(reports)
+}
+
+/// ## クラッシュレポートを削除する
+///
+/// ### Arguments
+/// - `id`: 削除するレポートのID
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功時は `Ok(())`、レポートが存在しない場合などはエラーメッセージ
+pub fn delete_report(id: &str) -> Result<(), String> {
+    let path = report_path(id)?;
+    std::fs::remove_file(&path)
+        .map_err(|e| format!("クラッシュレポートの削除に失敗しました ({}): {}", id, e))
+}
+
+/// ## クラッシュレポートを送信済みにする
+///
+/// ### Arguments
+/// - `id`: 送信済みにするレポートのID
+///
+/// ### Returns
+/// - `Result<CrashReport, String>`: 更新後のレポート
+pub fn mark_submitted(id: &str) -> Result<CrashReport, String> {
+    let path = report_path(id)?;
+    let mut report = read_report(&path)?;
+    report.submitted = true;
+    let json = serde_json::to_string_pretty(&report)
+        .map_err(|e| format!("クラッシュレポートのシリアライズに失敗しました: {}", e))?;
+    std::fs::write(&path, json)
+        .map_err(|e| format!("クラッシュレポートの更新に失敗しました ({}): {}", id, e))?;
+    Ok(report)
+}
+
+/// 保存先ディレクトリを取得する
+fn crash_dir() -> Result<&'static PathBuf, String> {
+    CRASH_DIR
+        .get()
+        .ok_or_else(|| "クラッシュレポートの保存先が初期化されていません".to_string())
+}
+
+/// レポートIDからファイルパスを取得する（ディレクトリ外を指すIDは拒否）
+fn report_path(id: &str) -> Result<PathBuf, String> {
+    if id.is_empty()
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-._".contains(c))
+    {
+        return Err(format!("無効なクラッシュレポートIDです: {}", id));
+    }
+    Ok(crash_dir()?.join(format!("{}.json", id)))
+}
+
+/// レポートファイルを読み込む
+fn read_report(path: &Path) -> Result<CrashReport, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        format!(
+            "クラッシュレポートの読み込みに失敗しました ({}): {}",
+            path.display(),
+            e
+        )
+    })?;
+    serde_json::from_str(&content).map_err(|e| {
+        format!(
+            "クラッシュレポートの解析に失敗しました ({}): {}",
+            path.display(),
+            e
+        )
+    })
+}
+
+/// レポートをファイルに書き出す
+fn write_report(report: &CrashReport) -> Result<PathBuf, String> {
+    let path = report_path(&report.id)?;
+    let json = serde_json::to_string_pretty(report)
+        .map_err(|e| format!("クラッシュレポートのシリアライズに失敗しました: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("{} ({})", e, path.display()))?;
+    Ok(path)
+}
+
+/// クラッシュ時のサーバー状態を取得する
+///
+/// パニック発生中のスレッドがロックを保持している可能性があるため、
+/// ロックを待たずに取得できた項目のみ記録します。
+fn capture_server_state() -> CrashServerState {
+    let mut state = CrashServerState {
+        active_connections: get_connections_count(),
+        ..Default::default()
+    };
+
+    let Some(app_handle) = APP_HANDLE.get() else {
+        return state;
+    };
+    let Some(app_state) = app_handle.try_state::<AppState>() else {
+        return state;
+    };
+
+    state.server_running = app_state.server_handle.try_lock().ok().map(|h| h.is_some());
+    state.startup_phase = app_state
+        .startup_progress
+        .try_lock()
+        .ok()
+        .map(|progress| progress.current_phase().as_str().to_string());
+    state.tunnel_running = app_state
+        .tunnel_info
+        .try_lock()
+        .ok()
+        .map(|info| matches!(*info, Some(Ok(_))));
+    state.database_connected = app_state.db_pool.try_lock().ok().map(|pool| pool.is_some());
+    state.session_active = app_state
+        .current_session_id
+        .try_lock()
+        .ok()
+        .map(|sessions| !sessions.is_empty());
+    state
+}
+
+/// ウォレットアドレス等の16進数の識別子・URLのパスとクエリ・トークン・IPアドレスをマスクする
+fn redact(text: &str) -> String {
+    let text = URL_REGEX.replace_all(text, format!("$1://$2/{}", REDACTED));
+    let text = TOKEN_REGEX.replace_all(&text, format!("${{1}}{}", REDACTED));
+    let text = IPV6_CANDIDATE_REGEX.replace_all(&text, |caps: &regex::Captures| {
+        let candidate = &caps[0];
+        if candidate.parse::<Ipv6Addr>().is_ok() {
+            REDACTED.to_string()
+        } else {
+            candidate.to_string()
+        }
+    });
+    let text = IPV4_REGEX.replace_all(&text, REDACTED);
+    HEX_ADDRESS_REGEX
+        .replace_all(&text, "0x[REDACTED]")
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_removes_wallet_address() {
+        let wallet = format!("0x{}", "ab".repeat(32));
+        let message = format!("failed to process superchat from {}", wallet);
+
+        let redacted = redact(&message);
+        assert!(!redacted.contains(&wallet));
+        assert_eq!(redacted, "failed to process superchat from 0x[REDACTED]");
+        // 短い16進数リテラルはそのまま残す
+        assert_eq!(redact("flags=0x1f"), "flags=0x1f");
+    }
+
+    /// IPアドレス・トークン・Webhook URLのマスクのテスト
+    #[test]
+    fn test_redact_removes_ip_token_and_url() {
+        assert_eq!(
+            redact("connection from 203.0.113.5 closed"),
+            "connection from [REDACTED] closed"
+        );
+        assert_eq!(
+            redact("connection from 2001:db8::1 closed"),
+            "connection from [REDACTED] closed"
+        );
+        assert_eq!(
+            redact("request failed: Authorization: Bearer abc.def-123"),
+            "request failed: Authorization: Bearer [REDACTED]"
+        );
+        assert_eq!(
+            redact("invalid query ?token=s3cr3t&seq=1"),
+            "invalid query ?token=[REDACTED]&seq=1"
+        );
+        assert_eq!(
+            redact("failed to post https://discord.com/api/webhooks/123/abcDEF"),
+            "failed to post https://discord.com/[REDACTED]"
+        );
+        // 時刻はIPv6アドレスとみなさない
+        assert_eq!(redact("at 12:34:56"), "at 12:34:56");
+    }
+}
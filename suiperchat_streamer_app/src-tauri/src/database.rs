@@ -2,9 +2,37 @@
 //!
 //! SQLiteデータベースへの接続管理、メッセージやセッションの保存・取得などの操作を提供する
 
-use crate::db_models::Message;
-use chrono::Utc;
+use crate::db_models::{
+    CoinTotal, ConnectionLog, DonorRank, FilterPreset, Message, MessageEdit, RejectedSuperchat,
+    Session, SessionTotals, ViewerCountPoint, ViewerCountSample, ViewerProfile,
+};
+use crate::types::DEFAULT_CHANNEL;
+use chrono::{DateTime, Local, NaiveDate, Utc};
 use sqlx::{sqlite::SqlitePool, Error as SqlxError};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// ヘルスチェッククエリの応答を待つ最大時間
+const POOL_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 1メッセージあたりに保持する編集履歴の最大件数（超えた分は古いものから削除）
+pub const MAX_EDITS_PER_MESSAGE: i64 = 50;
+
+/// 配信者による編集の編集者
+pub const EDITOR_STREAMER: &str = "streamer";
+
+/// 古いセッションの保持日数を指定する環境変数名（未設定の場合は削除しない）
+pub const DB_RETENTION_DAYS_ENV: &str = "DB_RETENTION_DAYS";
+
+/// 接続ログを保持する日数（視聴者のIPアドレスを含むため、セッションの保持期間とは別に削除する）
+pub const CONNECTION_LOG_RETENTION_DAYS: i64 = 30;
+
+/// 次のシーケンス番号を採番するSQL式
+///
+/// メッセージを保存するINSERT文は全てこの式で採番します。
+/// SQLiteは書き込みを行う文の実行中に書き込みロックを保持するため、同時に保存しても同じ番号にはならず、
+/// 万一重複した場合も `(session_id, sequence)` の一意インデックスで検出されます。
+const NEXT_SEQUENCE_SQL: &str = "(SELECT COALESCE(MAX(sequence), 0) + 1 FROM messages)";
 
 /// セッションをデータベースに作成する
 ///
@@ -81,46 +109,366 @@ pub async fn end_session(pool: &SqlitePool, session_id: &str) -> Result<(), Sqlx
     Ok(())
 }
 
+/// セッションの開始・終了時刻を更新する
+///
+/// 記録された時刻が実際の配信時間とずれている場合に、配信者が後から修正するために使用します。
+/// 時刻の妥当性（形式や前後関係）は呼び出し側で検証済みであることを前提とします。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session_id` - 更新するセッションID
+/// * `started_at` - 新しい開始時刻（RFC3339形式の文字列）
+/// * `ended_at` - 新しい終了時刻（RFC3339形式の文字列）
+///
+/// # 戻り値
+/// * `Result<bool, SqlxError>` - 更新した場合は `true`、セッションが存在しない場合は `false`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn update_session_times(
+    pool: &SqlitePool,
+    session_id: &str,
+    started_at: &str,
+    ended_at: &str,
+) -> Result<bool, SqlxError> {
+    println!(
+        "セッション時刻更新: {} ({} - {})",
+        session_id, started_at, ended_at
+    );
+
+    let result = sqlx::query(
+        r#"
+        UPDATE sessions
+        SET started_at = ?, ended_at = ?, updated_at = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(started_at)
+    .bind(ended_at)
+    .bind(Utc::now().to_rfc3339()) // updated_at
+    .bind(session_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// セッションのタイトル・メモを更新する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session_id` - 更新するセッションID
+/// * `title` - 新しいタイトル（Noneの場合はタイトルを削除）
+///
+/// # 戻り値
+/// * `Result<bool, SqlxError>` - 更新した場合は `true`、セッションが存在しない場合は `false`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn set_session_title(
+    pool: &SqlitePool,
+    session_id: &str,
+    title: Option<&str>,
+) -> Result<bool, SqlxError> {
+    let result = sqlx::query("UPDATE sessions SET title = ?, updated_at = ? WHERE id = ?")
+        .bind(title)
+        .bind(Utc::now().to_rfc3339()) // updated_at
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// セッション情報を取得する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session_id` - 取得するセッションID
+///
+/// # 戻り値
+/// * `Result<Option<Session>, SqlxError>` - 成功時はセッション情報（存在しない場合はNone）
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn get_session(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> Result<Option<Session>, SqlxError> {
+    sqlx::query_as::<_, Session>(
+        "SELECT id, started_at, ended_at, title, created_at, updated_at FROM sessions WHERE id = ?",
+    )
+    .bind(session_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// セッションの配信時間を計算する
+///
+/// 開始時刻と終了時刻をRFC3339形式としてパースし、その差を配信時間とします。
+///
+/// # 引数
+/// * `session` - 配信時間を計算するセッション
+///
+/// # 戻り値
+/// * `Option<Duration>` - 配信時間（未終了・時刻のパースに失敗・終了時刻が開始時刻より前の場合はNone）
+pub fn session_duration(session: &Session) -> Option<Duration> {
+    let started_at = DateTime::parse_from_rfc3339(&session.started_at).ok()?;
+    let ended_at = DateTime::parse_from_rfc3339(session.ended_at.as_deref()?).ok()?;
+    (ended_at - started_at).to_std().ok()
+}
+
+/// セッションの配信時間を取得する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session_id` - 対象のセッションID
+///
+/// # 戻り値
+/// * `Result<Option<Duration>, SqlxError>` - 成功時は配信時間（セッションが存在しない・未終了・時刻のパースに失敗した場合はNone）
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn get_session_duration(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> Result<Option<Duration>, SqlxError> {
+    Ok(get_session(pool, session_id)
+        .await?
+        .as_ref()
+        .and_then(session_duration))
+}
+
+/// 指定期間外のタイムスタンプを持つセッションのメッセージ数を取得する
+///
+/// セッション時刻の修正によってメッセージとの時系列関係が崩れないかを確認するために使用します。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session_id` - 対象のセッションID
+/// * `started_at` - 期間の開始時刻（RFC3339形式の文字列）
+/// * `ended_at` - 期間の終了時刻（RFC3339形式の文字列）
+///
+/// # 戻り値
+/// * `Result<i64, SqlxError>` - 成功時は期間外のメッセージ数、エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn count_messages_outside_range(
+    pool: &SqlitePool,
+    session_id: &str,
+    started_at: &str,
+    ended_at: &str,
+) -> Result<i64, SqlxError> {
+    // 文字列比較ではタイムゾーン表記の違いで誤判定するため julianday で比較する
+    let (count,) = sqlx::query_as::<_, (i64,)>(
+        r#"
+        SELECT COUNT(*)
+        FROM messages
+        WHERE session_id = ?
+          AND (julianday(timestamp) < julianday(?) OR julianday(timestamp) > julianday(?))
+        "#,
+    )
+    .bind(session_id)
+    .bind(started_at)
+    .bind(ended_at)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+/// セッションを削除する
+///
+/// `sessions` テーブルから該当行を削除します。紐づくメッセージは
+/// `ON DELETE CASCADE` により同時に削除されます。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session_id` - 削除するセッションID
+///
+/// # 戻り値
+/// * `Result<Option<u64>, SqlxError>` - 削除した場合は削除したメッセージ数、セッションが存在しない場合は `None`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn delete_session(pool: &SqlitePool, session_id: &str) -> Result<Option<u64>, SqlxError> {
+    println!("データベースセッション削除: {}", session_id);
+
+    let mut tx = pool.begin().await?;
+
+    let (message_count,) =
+        sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM messages WHERE session_id = ?")
+            .bind(session_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+    let result = sqlx::query("DELETE FROM sessions WHERE id = ?")
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        tx.rollback().await?;
+        return Ok(None);
+    }
+
+    tx.commit().await?;
+    Ok(Some(message_count as u64))
+}
+
+/// 保持期間を過ぎた古いセッションを削除する
+///
+/// `ended_at` が指定日数より前のセッションを削除します。紐づくメッセージは
+/// `ON DELETE CASCADE` により同時に削除されます。
+/// 進行中のセッションや正常に終了していないセッション（`ended_at` がNULL）は削除しません。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `retention_days` - セッションを保持する日数
+///
+/// # 戻り値
+/// * `Result<(u64, u64), SqlxError>` - 成功時は (削除したセッション数, 削除したメッセージ数)、エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn prune_old_sessions(
+    pool: &SqlitePool,
+    retention_days: i64,
+) -> Result<(u64, u64), SqlxError> {
+    let mut tx = pool.begin().await?;
+
+    let (message_count,) = sqlx::query_as::<_, (i64,)>(
+        r#"
+        SELECT COUNT(*) FROM messages
+        WHERE session_id IN (
+            SELECT id FROM sessions
+            WHERE ended_at IS NOT NULL
+              AND datetime(ended_at) < datetime('now', '-' || ? || ' days')
+        )
+        "#,
+    )
+    .bind(retention_days)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let result = sqlx::query(
+        r#"
+        DELETE FROM sessions
+        WHERE ended_at IS NOT NULL
+          AND datetime(ended_at) < datetime('now', '-' || ? || ' days')
+        "#,
+    )
+    .bind(retention_days)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok((result.rows_affected(), message_count as u64))
+}
+
+/// 前回正常に終了しなかったセッションを修復する
+///
+/// アプリのクラッシュや強制終了により `ended_at` がNULLのまま残ったセッションについて、
+/// 最後のメッセージのタイムスタンプを `ended_at` に設定します。
+/// メッセージが1件もないセッションは削除します。
+/// 起動直後（新しいセッションの開始前）に呼び出すことを前提としています。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+///
+/// # 戻り値
+/// * `Result<(u64, u64), SqlxError>` - 成功時は (修復したセッション数, 削除したセッション数)、エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn reconcile_orphaned_sessions(pool: &SqlitePool) -> Result<(u64, u64), SqlxError> {
+    let mut tx = pool.begin().await?;
+
+    let now = Utc::now().to_rfc3339();
+    let repaired = sqlx::query(
+        r#"
+        UPDATE sessions
+        SET ended_at = (SELECT MAX(timestamp) FROM messages WHERE messages.session_id = sessions.id),
+            updated_at = ?
+        WHERE ended_at IS NULL
+          AND EXISTS (SELECT 1 FROM messages WHERE messages.session_id = sessions.id)
+        "#,
+    )
+    .bind(&now)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let deleted = sqlx::query(
+        r#"
+        DELETE FROM sessions
+        WHERE ended_at IS NULL
+          AND NOT EXISTS (SELECT 1 FROM messages WHERE messages.session_id = sessions.id)
+        "#,
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    tx.commit().await?;
+    Ok((repaired, deleted))
+}
+
 /// メッセージをデータベースに保存する
 ///
 /// 受信したチャットメッセージまたはスーパーチャットをデータベースに記録します。
+/// 視聴者の再送などで同じIDのメッセージ、またはオンチェーンの着金監視とWebSocketの両方から届いた
+/// 同じトランザクションハッシュのスーパーチャットが既に保存されている場合は保存をスキップします。
 ///
 /// # 引数
 /// * `pool` - SQLiteデータベース接続プール
 /// * `message` - 保存するメッセージオブジェクト
 ///
 /// # 戻り値
-/// * `Result<(), SqlxError>` - 成功時は `Ok(())`, エラー時は `SqlxError`
+/// * `Result<bool, SqlxError>` - 成功時は保存した場合に `true`、同じIDまたはトランザクションハッシュのメッセージが保存済みの場合に `false`、エラー時は `SqlxError`
 ///
 /// # エラー
 /// - データベース接続エラー
 /// - SQLクエリ実行エラー
 /// - セッションIDが不足している場合
-pub async fn save_message_db(pool: &SqlitePool, message: &Message) -> Result<(), SqlxError> {
+pub async fn save_message_db(pool: &SqlitePool, message: &Message) -> Result<bool, SqlxError> {
     // セッションIDの存在確認（警告のみ表示）
     if message.session_id.is_none() {
         eprintln!("警告: メッセージにセッションIDが未設定");
     }
 
-    let _result = sqlx::query(
+    let query = format!(
         r#"
-        INSERT INTO messages (id, timestamp, display_name, message, amount, coin, tx_hash, wallet_address, session_id) 
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO messages (id, timestamp, display_name, message, amount, coin, tx_hash, wallet_address, session_id, channel, language, sequence) 
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, {})
+        ON CONFLICT DO NOTHING
         "#,
-    )
-    .bind(&message.id)
-    .bind(message.timestamp)
-    .bind(&message.display_name)
-    .bind(&message.content)
-    .bind(message.amount)
-    .bind(&message.coin)
-    .bind(&message.tx_hash)
-    .bind(&message.wallet_address)
-    .bind(&message.session_id)
-    .execute(pool)
-    .await?;
+        NEXT_SEQUENCE_SQL
+    );
+    let result = sqlx::query(&query)
+        .bind(&message.id)
+        .bind(message.timestamp)
+        .bind(&message.display_name)
+        .bind(&message.content)
+        .bind(message.amount)
+        .bind(&message.coin)
+        .bind(&message.tx_hash)
+        .bind(&message.wallet_address)
+        .bind(&message.session_id)
+        .bind(&message.channel)
+        .bind(&message.language)
+        .execute(pool)
+        .await?;
 
-    Ok(())
+    Ok(result.rows_affected() > 0)
 }
 
 /// メッセージの履歴をデータベースから取得する
@@ -167,9 +515,14 @@ pub async fn fetch_messages(
             coin,
             tx_hash, 
             wallet_address, 
-            session_id
+            session_id,
+            channel,
+            sequence,
+            language,
+            is_edited,
+            highlighted
         FROM messages
-        ORDER BY timestamp DESC
+        ORDER BY timestamp DESC, sequence DESC
         LIMIT ? OFFSET ?
         "#,
     )
@@ -192,6 +545,10 @@ pub async fn fetch_messages(
 /// * `session_id` - メッセージを取得する対象のセッションID
 /// * `limit` - 取得するメッセージの最大数（1-1000）
 /// * `before_timestamp` - このタイムスタンプより前のメッセージのみを取得（ミリ秒単位のUnixタイムスタンプ）
+/// * `before_sequence` - `before_timestamp` と同一時刻のメッセージのうち、このシーケンス番号より前のものも取得
+///   （ページの境界と同じミリ秒のメッセージを取りこぼさないよう、取得済みの最も古いメッセージの値を指定する）
+/// * `channel` - 指定された場合はそのチャンネルのメッセージのみを取得
+/// * `language` - 指定された場合はその言語（ISO 639-1）と判定されたメッセージのみを取得
 ///
 /// # 戻り値
 /// * `Result<Vec<Message>, SqlxError>` - 成功時はメッセージのベクター、エラー時は `SqlxError`
@@ -204,6 +561,9 @@ pub async fn get_messages_by_session_id(
     session_id: &str,
     limit: i64,
     before_timestamp: Option<i64>,
+    before_sequence: Option<i64>,
+    channel: Option<&str>,
+    language: Option<&str>,
 ) -> Result<Vec<Message>, SqlxError> {
     // パラメータの検証と調整
     let safe_limit = if limit <= 0 {
@@ -216,27 +576,48 @@ pub async fn get_messages_by_session_id(
 
     // クエリを構築
     let mut query_builder = sqlx::QueryBuilder::new(
-        "SELECT id, timestamp, display_name, message, amount, coin, tx_hash, wallet_address, session_id FROM messages WHERE session_id = ",
+        "SELECT id, timestamp, display_name, message, amount, coin, tx_hash, wallet_address, session_id, channel, sequence, language, is_edited, highlighted FROM messages WHERE session_id = ",
     );
 
     query_builder.push_bind(session_id);
 
     // before_timestampが指定されていれば条件を追加
-    if let Some(timestamp) = before_timestamp {
-        query_builder.push(" AND timestamp < ");
+    // (timestampは保存時と同じ形式の文字列で比較する。シーケンス番号がない場合は最も古いものとして扱う)
+    if let Some(timestamp) = before_timestamp.and_then(DateTime::from_timestamp_millis) {
+        query_builder.push(" AND (timestamp < ");
         query_builder.push_bind(timestamp);
+        if let Some(sequence) = before_sequence {
+            query_builder.push(" OR (timestamp = ");
+            query_builder.push_bind(timestamp);
+            query_builder.push(" AND COALESCE(sequence, 0) < ");
+            query_builder.push_bind(sequence);
+            query_builder.push(")");
+        }
+        query_builder.push(")");
+    }
+
+    // channelが指定されていれば条件を追加（NULLは "general" として扱う）
+    if let Some(channel) = channel {
+        query_builder.push(" AND COALESCE(channel, 'general') = ");
+        query_builder.push_bind(channel.to_string());
+    }
+
+    // languageが指定されていれば条件を追加
+    if let Some(language) = language {
+        query_builder.push(" AND language = ");
+        query_builder.push_bind(language.to_string());
     }
 
     // ORDER BY句を追加（最初は新しいものから取得）
-    query_builder.push(" ORDER BY timestamp DESC LIMIT ");
+    query_builder.push(" ORDER BY timestamp DESC, sequence DESC LIMIT ");
     query_builder.push_bind(safe_limit + 1); // +1することで、さらに古いログがあるかの判断材料にする
 
     // クエリを実行
     let query = query_builder.build_query_as::<Message>();
     let mut messages = query.fetch_all(pool).await?;
 
-    // timestampの昇順（古い順）にソート
-    messages.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    // (timestamp, sequence) の昇順（古い順）にソート
+    messages.sort_by_key(|msg| (msg.timestamp, msg.sequence));
 
     // メッセージインデックスの確認と作成
     ensure_message_index(pool).await?;
@@ -263,15 +644,20 @@ async fn ensure_message_index(pool: &SqlitePool) -> Result<(), SqlxError> {
 }
 
 /// 配信者用のセッションごとのメッセージ取得関数（既存の関数を拡張）
+///
+/// `channel` が指定された場合はそのチャンネルのメッセージのみを取得します。
+/// `language` が指定された場合はその言語（ISO 639-1）と判定されたメッセージのみを取得します。
 pub async fn get_messages_by_session_id_with_options(
     pool: &SqlitePool,
     session_id: &str,
     limit: i64,
     offset: Option<i64>,
     sort_asc: bool,
+    channel: Option<&str>,
+    language: Option<&str>,
 ) -> Result<Vec<Message>, sqlx::Error> {
-    println!("get_messages_by_session_id_with_options呼び出し: session_id={}, limit={}, offset={:?}, sort_asc={}", 
-        session_id, limit, offset, sort_asc);
+    println!("get_messages_by_session_id_with_options呼び出し: session_id={}, limit={}, offset={:?}, sort_asc={}, channel={:?}, language={:?}", 
+        session_id, limit, offset, sort_asc, channel, language);
 
     // ソート順の文字列を決定
     let order_by = if sort_asc { "ASC" } else { "DESC" };
@@ -281,7 +667,9 @@ pub async fn get_messages_by_session_id_with_options(
         let query = format!(
             "SELECT * FROM messages 
             WHERE session_id = $1 
-            ORDER BY timestamp {} 
+            AND ($4 IS NULL OR COALESCE(channel, 'general') = $4) 
+            AND ($5 IS NULL OR language = $5) 
+            ORDER BY timestamp {0}, sequence {0} 
             LIMIT $2 OFFSET $3",
             order_by
         );
@@ -296,6 +684,8 @@ pub async fn get_messages_by_session_id_with_options(
             .bind(session_id)
             .bind(limit)
             .bind(offset_value)
+            .bind(channel)
+            .bind(language)
             .fetch_all(pool)
             .await;
 
@@ -317,7 +707,11 @@ pub async fn get_messages_by_session_id_with_options(
                 .into_iter()
                 .filter(|msg| {
                     let msg_session_id = msg.session_id.as_deref().unwrap_or("");
-                    let matches = msg_session_id == session_id;
+                    let matches = msg_session_id == session_id
+                        && channel.map_or(true, |ch| {
+                            msg.channel.as_deref().unwrap_or(DEFAULT_CHANNEL) == ch
+                        })
+                        && language.map_or(true, |lang| msg.language.as_deref() == Some(lang));
                     if !matches {
                         println!("フィルタリングで除外: {} != {}", msg_session_id, session_id);
                     }
@@ -331,73 +725,2039 @@ pub async fn get_messages_by_session_id_with_options(
     }
 }
 
-/// 過去のコメント閲覧用に、データベースに存在する全てのユニークな `session_id` を取得する関数
-pub async fn get_distinct_session_ids(pool: &SqlitePool) -> Result<Vec<String>, sqlx::Error> {
-    let query = "SELECT DISTINCT session_id FROM messages WHERE session_id IS NOT NULL";
-
-    let rows = sqlx::query_as::<_, (String,)>(query)
-        .fetch_all(pool)
-        .await?;
-
-    // タプルの最初の要素を取り出してVec<String>に変換
-    let session_ids = rows.into_iter().map(|(id,)| id).collect();
-
-    Ok(session_ids)
+/// メッセージ履歴の絞り込み条件
+///
+/// # フィールド
+/// * `session_id` - 指定された場合はそのセッションのメッセージのみを取得
+/// * `channel` - 指定された場合はそのチャンネルのメッセージのみを取得（NULLは "general" として扱う）
+/// * `language` - 指定された場合はその言語（ISO 639-1）と判定されたメッセージのみを取得
+/// * `superchat_only` - `true` の場合はスーパーチャット（金額が0より大きいメッセージ）のみを取得
+/// * `highlighted_only` - `true` の場合は配信者がハイライトしたメッセージのみを取得
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessageHistoryFilter<'a> {
+    pub session_id: Option<&'a str>,
+    pub channel: Option<&'a str>,
+    pub language: Option<&'a str>,
+    pub superchat_only: bool,
+    pub highlighted_only: bool,
 }
 
-/// 全てのセッション情報を取得する関数
+/// 絞り込み条件に一致するメッセージ履歴を取得する
 ///
-/// セッション一覧を日時と共に表示するために使用されます。
-/// 結果は開始日時の降順（新しいものから古いものへ）でソートされます。
+/// 全ての条件をWHERE句で評価してからLIMIT/OFFSETを適用するため、
+/// 絞り込んだ結果をページングしても件数が欠けることはありません。
 ///
 /// # 引数
 /// * `pool` - SQLiteデータベース接続プール
+/// * `filter` - 絞り込み条件
+/// * `limit` - 取得するメッセージの最大数（1-1000、デフォルトは100）
+/// * `offset` - 結果セットのオフセット（ページネーション用、0以上）
+/// * `sort_asc` - ソート順（true: 昇順、false: 降順）
 ///
 /// # 戻り値
-/// * `Result<Vec<Session>, sqlx::Error>` - 成功時はセッション情報のベクター、エラー時はSQLエラー
+/// * `Result<Vec<Message>, SqlxError>` - 成功時はメッセージのベクター、エラー時は `SqlxError`
 ///
 /// # エラー
 /// - データベース接続エラー
 /// - SQLクエリ実行エラー
-pub async fn get_all_sessions(pool: &SqlitePool) -> Result<Vec<crate::db_models::Session>, sqlx::Error> {
-    println!("データベースから全セッション情報を取得中...");
-
-    let query = r#"
-        SELECT id, started_at, ended_at, created_at, updated_at 
-        FROM sessions 
-        ORDER BY started_at DESC
-    "#;
+pub async fn get_message_history(
+    pool: &SqlitePool,
+    filter: &MessageHistoryFilter<'_>,
+    limit: i64,
+    offset: i64,
+    sort_asc: bool,
+) -> Result<Vec<Message>, SqlxError> {
+    // パラメータの検証と調整
+    let safe_limit = if limit <= 0 {
+        100
+    } else if limit > 1000 {
+        1000
+    } else {
+        limit
+    };
 
-    let sessions = sqlx::query_as::<_, crate::db_models::Session>(query)
-        .fetch_all(pool)
-        .await?;
+    let mut query_builder = sqlx::QueryBuilder::new(
+        "SELECT id, timestamp, display_name, message, amount, coin, tx_hash, wallet_address, session_id, channel, sequence, language, is_edited, highlighted FROM messages WHERE 1 = 1",
+    );
 
-    println!("データベースから{}件のセッションを取得しました", sessions.len());
+    if let Some(session_id) = filter.session_id {
+        query_builder.push(" AND session_id = ");
+        query_builder.push_bind(session_id.to_string());
+    }
 
-    Ok(sessions)
-}
+    // channelが指定されていれば条件を追加（NULLは "general" として扱う）
+    if let Some(channel) = filter.channel {
+        query_builder.push(" AND COALESCE(channel, 'general') = ");
+        query_builder.push_bind(channel.to_string());
+    }
 
-#[cfg(test)]
-mod tests {
-    use crate::db_models::{Message, Session};
-    use crate::{CREATE_MESSAGES_TABLE_SQL, CREATE_SESSIONS_TABLE_SQL};
+    if let Some(language) = filter.language {
+        query_builder.push(" AND language = ");
+        query_builder.push_bind(language.to_string());
+    }
 
-    use super::*;
-    use uuid::Uuid;
+    if filter.superchat_only {
+        query_builder.push(" AND amount > 0");
+    }
 
-    /// `create_session`関数のテスト
-    #[sqlx::test]
-    async fn test_create_session(pool: SqlitePool) -> Result<(), SqlxError> {
-        // テスト用DBのセットアップ
-        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
-            .execute(&pool)
-            .await?;
+    if filter.highlighted_only {
+        query_builder.push(" AND highlighted = 1");
+    }
 
-        // テスト用のセッションIDを生成
-        let session_id = Uuid::new_v4().to_string();
+    let order_by = if sort_asc { "ASC" } else { "DESC" };
+    query_builder.push(format!(
+        " ORDER BY timestamp {0}, sequence {0} LIMIT ",
+        order_by
+    ));
+    query_builder.push_bind(safe_limit);
+    query_builder.push(" OFFSET ");
+    query_builder.push_bind(offset.max(0));
 
-        // セッション作成
-        create_session(&pool, &session_id).await?;
+    query_builder
+        .build_query_as::<Message>()
+        .fetch_all(pool)
+        .await
+}
+
+/// セッションに属する全メッセージを時系列順に取得する
+///
+/// エクスポート処理など、セッションのメッセージを件数制限なしで扱う用途に使用します。
+/// 結果はタイムスタンプの昇順（古い順）で返されます。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session_id` - メッセージを取得する対象のセッションID
+///
+/// # 戻り値
+/// * `Result<Vec<Message>, SqlxError>` - 成功時はメッセージのベクター、エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn get_all_messages_by_session_id(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> Result<Vec<Message>, SqlxError> {
+    let messages = sqlx::query_as::<_, Message>(
+        r#"
+        SELECT
+            id,
+            timestamp,
+            display_name,
+            message,
+            amount,
+            coin,
+            tx_hash,
+            wallet_address,
+            session_id,
+            channel,
+            sequence,
+            language,
+            is_edited,
+            highlighted
+        FROM messages
+        WHERE session_id = ?
+        ORDER BY timestamp ASC, sequence ASC
+        "#,
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(messages)
+}
+
+/// セッションに属するスーパーチャットのみを時系列順に取得する
+///
+/// 通常チャットは `amount` が0.0（古いデータではNULL）で保存されているため、
+/// `amount` が正の値のメッセージのみをスーパーチャットとして扱います。
+/// 結果はタイムスタンプの昇順（古い順）で返されます。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session_id` - メッセージを取得する対象のセッションID
+/// * `limit` - 取得する最大件数
+/// * `offset` - 取得開始位置（ページネーション用）
+///
+/// # 戻り値
+/// * `Result<Vec<Message>, SqlxError>` - 成功時はメッセージのベクター、エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn get_superchats_by_session(
+    pool: &SqlitePool,
+    session_id: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<Message>, SqlxError> {
+    sqlx::query_as::<_, Message>(
+        r#"
+        SELECT
+            id,
+            timestamp,
+            display_name,
+            message,
+            amount,
+            coin,
+            tx_hash,
+            wallet_address,
+            session_id,
+            channel,
+            sequence,
+            language,
+            is_edited,
+            highlighted
+        FROM messages
+        WHERE session_id = ? AND amount IS NOT NULL AND amount > 0
+        ORDER BY timestamp ASC, sequence ASC
+        LIMIT ? OFFSET ?
+        "#,
+    )
+    .bind(session_id)
+    .bind(limit)
+    .bind(offset.max(0))
+    .fetch_all(pool)
+    .await
+}
+
+/// メッセージ本文と表示名を部分一致で検索する
+///
+/// 大文字小文字を区別せずに検索し、結果はタイムスタンプの降順（新しい順）で返します。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `query` - 検索文字列
+/// * `limit` - 取得するメッセージの最大数（1-1000、デフォルトは100）
+///
+/// # 戻り値
+/// * `Result<Vec<Message>, SqlxError>` - 成功時は検索に一致したメッセージのベクター、エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn search_messages(
+    pool: &SqlitePool,
+    query: &str,
+    limit: i64,
+) -> Result<Vec<Message>, SqlxError> {
+    search_messages_with_options(pool, query, limit, None, false).await
+}
+
+/// セッションやスーパーチャットで絞り込んでメッセージを検索する
+///
+/// `search_messages` と同じ条件で検索し、さらに指定された条件で絞り込みます。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `query` - 検索文字列
+/// * `limit` - 取得するメッセージの最大数（1-1000、デフォルトは100）
+/// * `session_id` - 指定された場合はそのセッションのメッセージのみを検索
+/// * `superchat_only` - `true` の場合はスーパーチャット（金額が0より大きいメッセージ）のみを検索
+///
+/// # 戻り値
+/// * `Result<Vec<Message>, SqlxError>` - 成功時は検索に一致したメッセージのベクター、エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn search_messages_with_options(
+    pool: &SqlitePool,
+    query: &str,
+    limit: i64,
+    session_id: Option<&str>,
+    superchat_only: bool,
+) -> Result<Vec<Message>, SqlxError> {
+    // パラメータの検証と調整
+    let safe_limit = if limit <= 0 {
+        100
+    } else if limit > 1000 {
+        1000
+    } else {
+        limit
+    };
+
+    // 検索文字列中の `%` と `_` をワイルドカードとして扱わないようエスケープする
+    let escaped = query
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    let pattern = format!("%{}%", escaped);
+
+    // SQLiteのLIKEは英字の大文字小文字を区別しない
+    let mut query_builder = sqlx::QueryBuilder::new(
+        "SELECT id, timestamp, display_name, message, amount, coin, tx_hash, wallet_address, session_id, channel, sequence, language, is_edited, highlighted FROM messages WHERE (message LIKE ",
+    );
+    query_builder.push_bind(pattern.clone());
+    query_builder.push(" ESCAPE '\\' OR display_name LIKE ");
+    query_builder.push_bind(pattern);
+    query_builder.push(" ESCAPE '\\')");
+
+    // session_idが指定されていれば条件を追加
+    if let Some(session_id) = session_id {
+        query_builder.push(" AND session_id = ");
+        query_builder.push_bind(session_id.to_string());
+    }
+
+    if superchat_only {
+        query_builder.push(" AND amount > 0");
+    }
+
+    query_builder.push(" ORDER BY timestamp DESC, sequence DESC LIMIT ");
+    query_builder.push_bind(safe_limit);
+
+    query_builder
+        .build_query_as::<Message>()
+        .fetch_all(pool)
+        .await
+}
+
+/// テーブルにカラムが存在しない場合に追加する
+///
+/// 旧バージョンで作成されたデータベースに新しいカラムを追加するためのマイグレーション処理です。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `table` - 対象テーブル名
+/// * `column` - 追加するカラム名
+/// * `definition` - カラムの型・デフォルト値などの定義（例: "TEXT DEFAULT 'general'"）
+///
+/// # 戻り値
+/// * `Result<bool, SqlxError>` - カラムを追加した場合は `true`、既に存在した場合は `false`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn ensure_column(
+    pool: &SqlitePool,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<bool, SqlxError> {
+    let columns =
+        sqlx::query_as::<_, (String,)>(&format!("SELECT name FROM pragma_table_info('{}')", table))
+            .fetch_all(pool)
+            .await?;
+
+    if columns.iter().any(|(name,)| name == column) {
+        return Ok(false);
+    }
+
+    println!("{}テーブルに{}カラムを追加します", table, column);
+    sqlx::query(&format!(
+        "ALTER TABLE {} ADD COLUMN {} {}",
+        table, column, definition
+    ))
+    .execute(pool)
+    .await?;
+
+    Ok(true)
+}
+
+/// シーケンス番号が未設定のメッセージに受信順の番号を付与する
+///
+/// `sequence` カラム追加前に保存されたメッセージに対し、挿入順（rowid順）で
+/// 既存の最大値に続く番号を割り当てます。
+/// 一意インデックスの作成前にインポートされた、同じセッション内で番号が重複したメッセージも
+/// 最初の1件を除いて採番し直します。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+///
+/// # 戻り値
+/// * `Result<u64, SqlxError>` - 成功時は番号を付与したメッセージ数、エラー時は `SqlxError`
+pub async fn backfill_message_sequence(pool: &SqlitePool) -> Result<u64, SqlxError> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        UPDATE messages SET sequence = NULL
+        WHERE sequence IS NOT NULL
+            AND rowid NOT IN (SELECT MIN(rowid) FROM messages WHERE sequence IS NOT NULL GROUP BY session_id, sequence)
+        "#,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let (max_sequence,): (i64,) = sqlx::query_as("SELECT COALESCE(MAX(sequence), 0) FROM messages")
+        .fetch_one(&mut *tx)
+        .await?;
+    let row_ids: Vec<(i64,)> =
+        sqlx::query_as("SELECT rowid FROM messages WHERE sequence IS NULL ORDER BY rowid ASC")
+            .fetch_all(&mut *tx)
+            .await?;
+
+    for (index, (row_id,)) in row_ids.iter().enumerate() {
+        sqlx::query("UPDATE messages SET sequence = ? WHERE rowid = ?")
+            .bind(max_sequence + index as i64 + 1)
+            .bind(row_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(row_ids.len() as u64)
+}
+
+/// 過去のコメント閲覧用に、データベースに存在する全てのユニークな `session_id` を取得する関数
+pub async fn get_distinct_session_ids(pool: &SqlitePool) -> Result<Vec<String>, sqlx::Error> {
+    let query = "SELECT DISTINCT session_id FROM messages WHERE session_id IS NOT NULL";
+
+    let rows = sqlx::query_as::<_, (String,)>(query)
+        .fetch_all(pool)
+        .await?;
+
+    // タプルの最初の要素を取り出してVec<String>に変換
+    let session_ids = rows.into_iter().map(|(id,)| id).collect();
+
+    Ok(session_ids)
+}
+
+/// 全てのセッション情報を取得する関数
+///
+/// セッション一覧を日時と共に表示するために使用されます。
+/// 結果は開始日時の降順（新しいものから古いものへ）でソートされます。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+///
+/// # 戻り値
+/// * `Result<Vec<Session>, sqlx::Error>` - 成功時はセッション情報のベクター、エラー時はSQLエラー
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn get_all_sessions(pool: &SqlitePool) -> Result<Vec<crate::db_models::Session>, sqlx::Error> {
+    println!("データベースから全セッション情報を取得中...");
+
+    let query = r#"
+        SELECT id, started_at, ended_at, title, created_at, updated_at 
+        FROM sessions 
+        ORDER BY started_at DESC
+    "#;
+
+    let sessions = sqlx::query_as::<_, crate::db_models::Session>(query)
+        .fetch_all(pool)
+        .await?;
+
+    println!("データベースから{}件のセッションを取得しました", sessions.len());
+
+    Ok(sessions)
+}
+
+/// スーパーチャットの合計金額を取得する
+///
+/// 指定された通貨のスーパーチャット金額を合計します。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session_id` - 集計対象のセッションID（Noneの場合は全セッションの累計）
+/// * `coin` - 集計対象の通貨シンボル（例: "SUI"）
+///
+/// # 戻り値
+/// * `Result<f64, SqlxError>` - 成功時は合計金額、エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn get_total_superchat_amount(
+    pool: &SqlitePool,
+    session_id: Option<&str>,
+    coin: &str,
+) -> Result<f64, SqlxError> {
+    let (total,) = sqlx::query_as::<_, (f64,)>(
+        r#"
+        SELECT COALESCE(SUM(amount), 0.0)
+        FROM messages
+        WHERE coin = $1
+          AND amount > 0
+          AND ($2 IS NULL OR session_id = $2)
+        "#,
+    )
+    .bind(coin)
+    .bind(session_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(total)
+}
+
+/// セッション単位のスーパーチャット合計を集計する
+///
+/// 通貨ごとにスーパーチャットの合計金額と件数を集計し、通常チャット（通貨が未設定のメッセージ）は
+/// 金額の集計から除外して件数のみを数えます。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session_id` - 集計対象のセッションID
+///
+/// # 戻り値
+/// * `Result<SessionTotals, SqlxError>` - 成功時は集計結果（セッションが存在しない場合は全て0）、エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn get_session_totals(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> Result<SessionTotals, SqlxError> {
+    let rows = sqlx::query_as::<_, (Option<String>, Option<f64>, i64)>(
+        r#"
+        SELECT coin, SUM(amount), COUNT(*)
+        FROM messages
+        WHERE session_id = $1
+        GROUP BY coin
+        "#,
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut totals = SessionTotals::default();
+    for (coin, total_amount, count) in rows {
+        match coin {
+            Some(coin) => {
+                totals.coins.insert(
+                    coin,
+                    CoinTotal {
+                        total_amount: total_amount.unwrap_or(0.0),
+                        superchat_count: count,
+                    },
+                );
+            }
+            None => totals.chat_count += count,
+        }
+    }
+
+    Ok(totals)
+}
+
+/// ウォレットのスーパーチャット連続記録（ストリーク）を取得する
+///
+/// そのウォレットがスーパーチャットした配信セッションの日付を集め、
+/// 最新の日付から遡って連続している日数を返します。1日でも空くとストリークは途切れます。
+///
+/// セッションの日付は `started_at`（UTCのRFC 3339形式）をアプリを実行している
+/// PCのローカルタイムゾーン（配信者のタイムゾーン）に変換した日付で判定します。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `wallet_address` - 対象のウォレットアドレス
+///
+/// # 戻り値
+/// * `Result<u32, SqlxError>` - 成功時は連続日数（スーパーチャット履歴がない場合は0）、エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn get_donor_streak(pool: &SqlitePool, wallet_address: &str) -> Result<u32, SqlxError> {
+    get_donor_streak_for_session(pool, wallet_address, None).await
+}
+
+/// 受信中のスーパーチャットを含めたストリークを取得する
+///
+/// `current_session_id` のセッションを、保存前のスーパーチャットの配信日として
+/// ストリークの計算に含めます。スーパーチャット受信時にDB保存の完了を待たずに計算するために使用します。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `wallet_address` - 対象のウォレットアドレス
+/// * `current_session_id` - 受信中のスーパーチャットが属するセッションID
+///
+/// # 戻り値
+/// * `Result<u32, SqlxError>` - 成功時は連続日数、エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn get_donor_streak_for_session(
+    pool: &SqlitePool,
+    wallet_address: &str,
+    current_session_id: Option<&str>,
+) -> Result<u32, SqlxError> {
+    let started_ats = sqlx::query_as::<_, (String,)>(
+        r#"
+        SELECT started_at
+        FROM sessions
+        WHERE id IN (
+            SELECT DISTINCT session_id
+            FROM messages
+            WHERE wallet_address = $1
+              AND amount > 0
+        )
+        OR id = $2
+        "#,
+    )
+    .bind(wallet_address)
+    .bind(current_session_id)
+    .fetch_all(pool)
+    .await?;
+
+    let dates: Vec<NaiveDate> = started_ats
+        .iter()
+        .filter_map(
+            |(started_at,)| match DateTime::parse_from_rfc3339(started_at) {
+                Ok(started_at) => Some(started_at.with_timezone(&Local).date_naive()),
+                Err(e) => {
+                    eprintln!(
+                        "セッション開始時刻の解析に失敗しました: {} ({})",
+                        started_at, e
+                    );
+                    None
+                }
+            },
+        )
+        .collect();
+
+    Ok(calculate_streak(dates))
+}
+
+/// 指定したトランザクションハッシュのメッセージが保存済みか確認する
+///
+/// オンチェーンで検出した着金と、視聴者がWebSocket経由で申告したスーパーチャットの重複を防ぐために使用する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `tx_hash` - トランザクションハッシュ
+///
+/// # 戻り値
+/// * `Result<bool, SqlxError>` - 保存済みの場合は `true`
+pub async fn message_exists_with_tx_hash(
+    pool: &SqlitePool,
+    tx_hash: &str,
+) -> Result<bool, SqlxError> {
+    let exists: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM messages WHERE tx_hash = $1)")
+            .bind(tx_hash)
+            .fetch_one(pool)
+            .await?;
+
+    Ok(exists)
+}
+
+/// ウォレットアドレスによるスーパーチャット検索用のインデックスを作成する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+///
+/// # 戻り値
+/// * `Result<(), SqlxError>` - 成功時は `Ok(())`, エラー時は `SqlxError`
+pub async fn ensure_wallet_index(pool: &SqlitePool) -> Result<(), SqlxError> {
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_messages_wallet_session ON messages(wallet_address, session_id)",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// トランザクションハッシュの一意インデックスを作成する
+///
+/// 同じ送金が着金監視とWebSocketの両方から届いても1件のスーパーチャットとして保存されるようにする。
+/// 既存のデータベースに重複したトランザクションハッシュが残っている場合は作成に失敗する。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+///
+/// # 戻り値
+/// * `Result<(), SqlxError>` - 成功時は `Ok(())`, エラー時は `SqlxError`
+pub async fn ensure_tx_hash_unique_index(pool: &SqlitePool) -> Result<(), SqlxError> {
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_messages_tx_hash ON messages(tx_hash) WHERE tx_hash IS NOT NULL",
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// シーケンス番号のインデックスを作成する
+///
+/// `(session_id, sequence)` の一意インデックスで同じ番号の重複を防ぎ、
+/// `sequence` 単独のインデックスで採番時の `MAX(sequence)` を索引から取得できるようにする。
+/// 番号が重複した既存のメッセージは `backfill_message_sequence` で採番し直してから作成すること。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+///
+/// # 戻り値
+/// * `Result<(), SqlxError>` - 成功時は `Ok(())`, エラー時は `SqlxError`
+pub async fn ensure_sequence_indexes(pool: &SqlitePool) -> Result<(), SqlxError> {
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_messages_session_sequence ON messages(session_id, sequence)",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_messages_sequence ON messages(sequence)")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// 配信日の一覧から、最新の日付から遡った連続日数を計算する
+fn calculate_streak(mut dates: Vec<NaiveDate>) -> u32 {
+    dates.sort_unstable_by(|a, b| b.cmp(a));
+    dates.dedup();
+
+    let Some(mut previous) = dates.first().copied() else {
+        return 0;
+    };
+
+    let mut streak = 1;
+    for date in dates.into_iter().skip(1) {
+        if previous.pred_opt() != Some(date) {
+            break;
+        }
+        streak += 1;
+        previous = date;
+    }
+    streak
+}
+
+/// フィルタプリセットを保存する
+///
+/// 同名のプリセットが既に存在する場合は条件を上書きします。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `preset` - 保存するフィルタプリセット
+///
+/// # 戻り値
+/// * `Result<(), SqlxError>` - 成功時は `Ok(())`, エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn upsert_filter_preset(
+    pool: &SqlitePool,
+    preset: &FilterPreset,
+) -> Result<(), SqlxError> {
+    let now = Utc::now().to_rfc3339();
+
+    sqlx::query(
+        r#"
+        INSERT INTO filter_presets (name, superchat_only, min_amount, keyword, japanese_only, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(name) DO UPDATE SET
+            superchat_only = excluded.superchat_only,
+            min_amount = excluded.min_amount,
+            keyword = excluded.keyword,
+            japanese_only = excluded.japanese_only,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(&preset.name)
+    .bind(preset.superchat_only)
+    .bind(preset.min_amount)
+    .bind(&preset.keyword)
+    .bind(preset.japanese_only)
+    .bind(&now) // created_at
+    .bind(&now) // updated_at
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// 保存されている全てのフィルタプリセットを取得する
+///
+/// 結果は作成日時の昇順（古いものから新しいものへ）でソートされます。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+///
+/// # 戻り値
+/// * `Result<Vec<FilterPreset>, SqlxError>` - 成功時はプリセットのベクター、エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn get_all_filter_presets(pool: &SqlitePool) -> Result<Vec<FilterPreset>, SqlxError> {
+    let presets = sqlx::query_as::<_, FilterPreset>(
+        r#"
+        SELECT name, superchat_only, min_amount, keyword, japanese_only, created_at, updated_at
+        FROM filter_presets
+        ORDER BY created_at ASC
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(presets)
+}
+
+/// 名前を指定してフィルタプリセットを取得する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `name` - 取得するプリセット名
+///
+/// # 戻り値
+/// * `Result<Option<FilterPreset>, SqlxError>` - 見つかった場合は `Some(preset)`、存在しない場合は `None`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn get_filter_preset_by_name(
+    pool: &SqlitePool,
+    name: &str,
+) -> Result<Option<FilterPreset>, SqlxError> {
+    let preset = sqlx::query_as::<_, FilterPreset>(
+        r#"
+        SELECT name, superchat_only, min_amount, keyword, japanese_only, created_at, updated_at
+        FROM filter_presets
+        WHERE name = ?
+        "#,
+    )
+    .bind(name)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(preset)
+}
+
+/// 視聴者プロフィールの集計対象とする通貨
+const VIEWER_TOTAL_COIN: &str = "SUI";
+
+/// 受信したメッセージを視聴者プロフィールに反映する
+///
+/// ウォレットアドレスをキーに表示名・最終受信時刻・累計メッセージ数を更新し、
+/// スーパーチャットの場合はSUI建ての累計額と回数も加算します。
+/// ウォレットアドレスのないメッセージや、集計をオプトアウトした視聴者は更新しません。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `message` - 保存済みのメッセージ
+///
+/// # 戻り値
+/// * `Result<(), SqlxError>` - 成功時は `Ok(())`, エラー時は `SqlxError`
+pub async fn record_viewer_activity(pool: &SqlitePool, message: &Message) -> Result<(), SqlxError> {
+    let Some(wallet_address) = message.wallet_address.as_deref().filter(|w| !w.is_empty()) else {
+        return Ok(());
+    };
+
+    let amount = message.amount.unwrap_or(0.0);
+    let is_superchat = amount > 0.0;
+    let sui_amount = if is_superchat && message.coin.as_deref() == Some(VIEWER_TOTAL_COIN) {
+        amount
+    } else {
+        0.0
+    };
+    let seen_at = message.timestamp.to_rfc3339();
+
+    sqlx::query(
+        r#"
+        INSERT INTO viewers (
+            wallet_address, display_name, first_seen_at, last_seen_at,
+            total_superchat_amount, superchat_count, message_count, opted_out
+        )
+        VALUES (?1, ?2, ?3, ?3, ?4, ?5, 1, 0)
+        ON CONFLICT(wallet_address) DO UPDATE SET
+            display_name = excluded.display_name,
+            last_seen_at = excluded.last_seen_at,
+            total_superchat_amount = viewers.total_superchat_amount + excluded.total_superchat_amount,
+            superchat_count = viewers.superchat_count + excluded.superchat_count,
+            message_count = viewers.message_count + 1
+        WHERE viewers.opted_out = 0
+        "#,
+    )
+    .bind(wallet_address)
+    .bind(&message.display_name)
+    .bind(&seen_at)
+    .bind(sui_amount)
+    .bind(is_superchat as i64)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// ウォレットアドレスから視聴者の累計実績を取得する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `wallet_address` - 対象のウォレットアドレス
+///
+/// # 戻り値
+/// * `Result<ViewerProfile, SqlxError>` - 成功時は視聴者プロフィール、エラー時は `SqlxError`
+///
+/// # エラー
+/// - 視聴者が存在しない、または集計をオプトアウトしている場合は `SqlxError::RowNotFound`
+/// - SQLクエリ実行エラー
+pub async fn get_viewer_profile(
+    pool: &SqlitePool,
+    wallet_address: &str,
+) -> Result<ViewerProfile, SqlxError> {
+    sqlx::query_as::<_, ViewerProfile>(
+        r#"
+        SELECT viewers.*, (
+            SELECT COUNT(DISTINCT session_id) FROM messages
+            WHERE messages.wallet_address = viewers.wallet_address
+        ) AS session_count
+        FROM viewers
+        WHERE wallet_address = ? AND opted_out = 0
+        "#,
+    )
+    .bind(wallet_address)
+    .fetch_one(pool)
+    .await
+}
+
+/// 視聴者プロフィールを累計スーパーチャット額の多い順に取得する
+///
+/// 集計をオプトアウトしている視聴者は含まれません。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `limit` - 取得する最大件数
+/// * `offset` - 取得開始位置
+///
+/// # 戻り値
+/// * `Result<Vec<ViewerProfile>, SqlxError>` - 成功時は視聴者プロフィールのベクター、エラー時は `SqlxError`
+pub async fn list_viewer_profiles(
+    pool: &SqlitePool,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<ViewerProfile>, SqlxError> {
+    sqlx::query_as::<_, ViewerProfile>(
+        r#"
+        SELECT viewers.*, (
+            SELECT COUNT(DISTINCT session_id) FROM messages
+            WHERE messages.wallet_address = viewers.wallet_address
+        ) AS session_count
+        FROM viewers
+        WHERE opted_out = 0
+        ORDER BY total_superchat_amount DESC, message_count DESC, wallet_address ASC
+        LIMIT ? OFFSET ?
+        "#,
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await
+}
+
+/// 全セッションを横断したスーパーチャット送金者のランキングを取得する
+///
+/// ウォレットアドレスと通貨ごとにスーパーチャットの累計額を集計し、通貨ごとに多い順に返します。
+/// 異なる通貨の金額は合算しません。
+/// 同じウォレットで表示名が異なる場合は最新のメッセージの表示名を採用します。
+/// 集計をオプトアウトしている視聴者は含まれません。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `limit` - 通貨ごとに取得する最大件数
+/// * `coin` - 集計対象の通貨シンボル（Noneの場合は全通貨）
+///
+/// # 戻り値
+/// * `Result<Vec<DonorRank>, SqlxError>` - 成功時は通貨シンボル順・累計額の多い順のランキングのベクター、エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー
+pub async fn get_top_donors(
+    pool: &SqlitePool,
+    limit: i64,
+    coin: Option<&str>,
+) -> Result<Vec<DonorRank>, SqlxError> {
+    sqlx::query_as::<_, DonorRank>(
+        r#"
+        SELECT wallet_address, display_name, coin, total_amount, superchat_count
+        FROM (
+            SELECT
+                m.wallet_address AS wallet_address,
+                (
+                    SELECT latest.display_name
+                    FROM messages latest
+                    WHERE latest.wallet_address = m.wallet_address
+                    ORDER BY latest.timestamp DESC, latest.sequence DESC
+                    LIMIT 1
+                ) AS display_name,
+                UPPER(m.coin) AS coin,
+                SUM(m.amount) AS total_amount,
+                COUNT(*) AS superchat_count,
+                ROW_NUMBER() OVER (
+                    PARTITION BY UPPER(m.coin)
+                    ORDER BY SUM(m.amount) DESC, COUNT(*) DESC, m.wallet_address ASC
+                ) AS coin_rank
+            FROM messages m
+            WHERE m.wallet_address IS NOT NULL
+              AND m.coin IS NOT NULL
+              AND m.amount > 0
+              AND ($1 IS NULL OR m.coin = $1 COLLATE NOCASE)
+              AND m.wallet_address NOT IN (
+                  SELECT wallet_address FROM viewers WHERE opted_out = 1
+              )
+            GROUP BY m.wallet_address, UPPER(m.coin)
+        )
+        WHERE coin_rank <= $2
+        ORDER BY coin ASC, coin_rank ASC
+        "#,
+    )
+    .bind(coin)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// 視聴者の集計オプトアウトを設定する
+///
+/// オプトアウトした視聴者の累計実績は破棄され、以降のメッセージも集計されません。
+/// オプトアウトを解除した場合は、次回のメッセージから改めて集計されます。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `wallet_address` - 対象のウォレットアドレス
+/// * `opted_out` - オプトアウトする場合は `true`
+///
+/// # 戻り値
+/// * `Result<(), SqlxError>` - 成功時は `Ok(())`, エラー時は `SqlxError`
+pub async fn set_viewer_opt_out(
+    pool: &SqlitePool,
+    wallet_address: &str,
+    opted_out: bool,
+) -> Result<(), SqlxError> {
+    if !opted_out {
+        sqlx::query("DELETE FROM viewers WHERE wallet_address = ? AND opted_out = 1")
+            .bind(wallet_address)
+            .execute(pool)
+            .await?;
+        return Ok(());
+    }
+
+    // 集計済みの実績を破棄し、表示名などの個人情報も残さない
+    let now = Utc::now().to_rfc3339();
+    sqlx::query(
+        r#"
+        INSERT INTO viewers (
+            wallet_address, display_name, first_seen_at, last_seen_at,
+            total_superchat_amount, superchat_count, message_count, opted_out
+        )
+        VALUES (?1, '', ?2, ?2, 0, 0, 0, 1)
+        ON CONFLICT(wallet_address) DO UPDATE SET
+            display_name = '',
+            first_seen_at = excluded.first_seen_at,
+            last_seen_at = excluded.last_seen_at,
+            total_superchat_amount = 0,
+            superchat_count = 0,
+            message_count = 0,
+            opted_out = 1
+        "#,
+    )
+    .bind(wallet_address)
+    .bind(&now)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// 前回のバックアップ以降に受信したメッセージを差分バックアップ用に取得する
+///
+/// シーケンス番号が `since_sequence` より大きいメッセージを受信順に返します。
+/// 同じ時刻のメッセージが複数あっても、前回のバックアップで取得した最大のシーケンス番号を
+/// 次回の `since_sequence` に指定することで、欠損も重複もなく連続した差分を取得できます。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `since_sequence` - 前回のバックアップの最大のシーケンス番号（Noneの場合は最初のメッセージから）
+///
+/// # 戻り値
+/// * `Result<Vec<Message>, SqlxError>` - 成功時はメッセージのベクター、エラー時は `SqlxError`
+pub async fn export_incremental(
+    pool: &SqlitePool,
+    since_sequence: Option<i64>,
+) -> Result<Vec<Message>, SqlxError> {
+    sqlx::query_as::<_, Message>(
+        r#"
+        SELECT * FROM messages
+        WHERE ($1 IS NULL OR sequence > $1)
+        ORDER BY sequence ASC
+        "#,
+    )
+    .bind(since_sequence)
+    .fetch_all(pool)
+    .await
+}
+
+/// 指定時刻より前に受信したメッセージの最大のシーケンス番号を取得する
+///
+/// 時刻で指定された差分バックアップの開始位置をシーケンス番号に変換するために使用します。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `timestamp` - 基準の時刻
+///
+/// # 戻り値
+/// * `Result<i64, SqlxError>` - 成功時は最大のシーケンス番号（該当するメッセージがない場合は0）、エラー時は `SqlxError`
+pub async fn max_sequence_before(
+    pool: &SqlitePool,
+    timestamp: DateTime<Utc>,
+) -> Result<i64, SqlxError> {
+    sqlx::query_scalar("SELECT COALESCE(MAX(sequence), 0) FROM messages WHERE timestamp < ?")
+        .bind(timestamp)
+        .fetch_one(pool)
+        .await
+}
+
+/// 差分バックアップに含めるセッションを取得する
+///
+/// 差分のメッセージが属するセッションに加え、期間内に終了時刻などが更新されたセッションを返します。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `since_timestamp` - 取得開始時刻（Noneの場合は全期間）
+/// * `until_timestamp` - 取得終了時刻
+/// * `since_sequence` - 前回のバックアップの最大のシーケンス番号（Noneの場合は全てのメッセージ）
+///
+/// # 戻り値
+/// * `Result<Vec<Session>, SqlxError>` - 成功時はセッションのベクター、エラー時は `SqlxError`
+pub async fn get_sessions_for_backup(
+    pool: &SqlitePool,
+    since_timestamp: Option<DateTime<Utc>>,
+    until_timestamp: DateTime<Utc>,
+    since_sequence: Option<i64>,
+) -> Result<Vec<Session>, SqlxError> {
+    sqlx::query_as::<_, Session>(
+        r#"
+        SELECT * FROM sessions
+        WHERE (($1 IS NULL OR updated_at >= $1) AND updated_at < $2)
+           OR id IN (
+               SELECT session_id FROM messages
+               WHERE ($3 IS NULL OR sequence > $3)
+           )
+        ORDER BY started_at ASC
+        "#,
+    )
+    .bind(since_timestamp.map(|since| since.to_rfc3339()))
+    .bind(until_timestamp.to_rfc3339())
+    .bind(since_sequence)
+    .fetch_all(pool)
+    .await
+}
+
+/// バックアップのセッションとメッセージをデータベースに適用する
+///
+/// 既存のセッションは、バックアップの方が新しい場合のみ終了時刻などを更新します。
+/// 既に存在するメッセージ（同じID）はスキップするため、重複した期間のバックアップを適用しても安全です。
+/// メッセージの保存は `insert_imported_messages` で行います。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `sessions` - 適用するセッション
+/// * `messages` - 適用するメッセージ
+///
+/// # 戻り値
+/// * `Result<u64, SqlxError>` - 成功時は新たに追加したメッセージ数、エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー（エラー時は全ての変更がロールバックされます）
+pub async fn import_backup(
+    pool: &SqlitePool,
+    sessions: &[Session],
+    messages: &[Message],
+) -> Result<u64, SqlxError> {
+    // 採番してから保存するまでの間に他の接続が書き込まないよう、開始時に書き込みロックを取得する
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+
+    for session in sessions {
+        sqlx::query(
+            r#"
+            INSERT INTO sessions (id, started_at, ended_at, title, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                ended_at = excluded.ended_at,
+                title = excluded.title,
+                updated_at = excluded.updated_at
+            WHERE excluded.updated_at > sessions.updated_at
+            "#,
+        )
+        .bind(&session.id)
+        .bind(&session.started_at)
+        .bind(&session.ended_at)
+        .bind(&session.title)
+        .bind(&session.created_at)
+        .bind(&session.updated_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let inserted = insert_imported_messages(&mut tx, messages, None, false).await?;
+
+    tx.commit().await?;
+
+    Ok(inserted)
+}
+
+/// インポートするメッセージをトランザクション内で保存する
+///
+/// シーケンス番号は別のデータベースで採番されたものと重複しうるため、保存の開始時に一度だけ採番し、
+/// メッセージの順に連番で割り当てます。採番から保存までの間に他の接続が書き込まないよう、
+/// `BEGIN IMMEDIATE` で開始したトランザクションの接続を渡してください。
+///
+/// # 引数
+/// * `conn` - 書き込みロックを取得済みのトランザクションの接続
+/// * `messages` - 保存するメッセージ
+/// * `session_id` - 全てのメッセージを所属させるセッションID（Noneの場合は各メッセージのセッションIDを使用）
+/// * `overwrite` - 同じIDのメッセージを上書きするかどうか（別のセッションに属するメッセージは上書きしません）
+///
+/// # 戻り値
+/// * `Result<u64, SqlxError>` - 成功時は追加・上書きしたメッセージ数、エラー時は `SqlxError`
+async fn insert_imported_messages(
+    conn: &mut sqlx::SqliteConnection,
+    messages: &[Message],
+    session_id: Option<&str>,
+    overwrite: bool,
+) -> Result<u64, SqlxError> {
+    // 既存のメッセージを別のセッションへ移さないよう、セッションIDは上書きしない
+    let on_conflict = if overwrite {
+        r#"
+        ON CONFLICT(id) DO UPDATE SET
+            timestamp = excluded.timestamp,
+            display_name = excluded.display_name,
+            message = excluded.message,
+            amount = excluded.amount,
+            coin = excluded.coin,
+            tx_hash = excluded.tx_hash,
+            wallet_address = excluded.wallet_address,
+            channel = excluded.channel,
+            language = excluded.language,
+            is_edited = excluded.is_edited,
+            highlighted = excluded.highlighted
+        WHERE messages.session_id = excluded.session_id
+        "#
+    } else {
+        "ON CONFLICT DO NOTHING"
+    };
+    let message_query = format!(
+        r#"
+        INSERT INTO messages (id, timestamp, display_name, message, amount, coin, tx_hash, wallet_address, session_id, channel, language, is_edited, highlighted, sequence)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        {}
+        "#,
+        on_conflict
+    );
+
+    let first_sequence: i64 = sqlx::query_scalar(&format!("SELECT {}", NEXT_SEQUENCE_SQL))
+        .fetch_one(&mut *conn)
+        .await?;
+    let mut imported = 0;
+    for (index, message) in messages.iter().enumerate() {
+        let result = sqlx::query(&message_query)
+            .bind(&message.id)
+            .bind(message.timestamp)
+            .bind(&message.display_name)
+            .bind(&message.content)
+            .bind(message.amount)
+            .bind(&message.coin)
+            .bind(&message.tx_hash)
+            .bind(&message.wallet_address)
+            .bind(session_id.or(message.session_id.as_deref()))
+            .bind(&message.channel)
+            .bind(&message.language)
+            .bind(message.is_edited)
+            .bind(message.highlighted)
+            .bind(first_sequence + index as i64)
+            .execute(&mut *conn)
+            .await?;
+        imported += result.rows_affected();
+    }
+
+    Ok(imported)
+}
+
+/// エクスポートしたセッションとメッセージをデータベースに復元する
+///
+/// 同じIDのセッション・メッセージが既に存在する場合、`overwrite` が `true` なら
+/// ファイルの内容で上書きし、`false` なら既存のものを残してスキップします。
+/// メッセージは全て指定したセッションに属するものとして復元し、
+/// 別のセッションに属する同じIDのメッセージは上書きせずにスキップします。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session` - 復元するセッション
+/// * `messages` - 復元するメッセージ
+/// * `overwrite` - 既存のセッション・メッセージを上書きするかどうか
+///
+/// # 戻り値
+/// * `Result<(bool, u64), SqlxError>` - 成功時は (セッションを作成・上書きしたかどうか, 復元したメッセージ数)、エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - SQLクエリ実行エラー（エラー時は全ての変更がロールバックされます）
+pub async fn import_session(
+    pool: &SqlitePool,
+    session: &Session,
+    messages: &[Message],
+    overwrite: bool,
+) -> Result<(bool, u64), SqlxError> {
+    // 採番してから保存するまでの間に他の接続が書き込まないよう、開始時に書き込みロックを取得する
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+
+    let session_query = if overwrite {
+        r#"
+        INSERT INTO sessions (id, started_at, ended_at, title, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET
+            started_at = excluded.started_at,
+            ended_at = excluded.ended_at,
+            title = excluded.title,
+            created_at = excluded.created_at,
+            updated_at = excluded.updated_at
+        "#
+    } else {
+        r#"
+        INSERT OR IGNORE INTO sessions (id, started_at, ended_at, title, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#
+    };
+    let session_written = sqlx::query(session_query)
+        .bind(&session.id)
+        .bind(&session.started_at)
+        .bind(&session.ended_at)
+        .bind(&session.title)
+        .bind(&session.created_at)
+        .bind(&session.updated_at)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+        > 0;
+
+    let imported =
+        insert_imported_messages(&mut tx, messages, Some(&session.id), overwrite).await?;
+
+    tx.commit().await?;
+
+    Ok((session_written, imported))
+}
+
+/// 差分バックアップの実行記録を保存する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `dest_path` - バックアップファイルの保存先
+/// * `since_timestamp` - 差分の開始時刻（全件の場合はNone）
+/// * `until_timestamp` - 差分の終了時刻
+/// * `until_sequence` - バックアップしたメッセージの最大のシーケンス番号（メッセージがない場合はNone）
+/// * `message_count` - バックアップしたメッセージ数
+///
+/// # 戻り値
+/// * `Result<(), SqlxError>` - 成功時は `Ok(())`、エラー時は `SqlxError`
+pub async fn record_backup(
+    pool: &SqlitePool,
+    dest_path: &str,
+    since_timestamp: Option<DateTime<Utc>>,
+    until_timestamp: DateTime<Utc>,
+    until_sequence: Option<i64>,
+    message_count: i64,
+) -> Result<(), SqlxError> {
+    sqlx::query(
+        r#"
+        INSERT INTO backups (dest_path, since, until, until_sequence, message_count, created_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(dest_path)
+    .bind(since_timestamp.map(|since| since.to_rfc3339()))
+    .bind(until_timestamp.to_rfc3339())
+    .bind(until_sequence)
+    .bind(message_count)
+    .bind(Utc::now().to_rfc3339())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// 最後に実行したバックアップの終了時刻とシーケンス番号を取得する
+///
+/// 次回の差分バックアップの開始位置として使用します。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+///
+/// # 戻り値
+/// * `Result<Option<(DateTime<Utc>, Option<i64>)>, SqlxError>` - 成功時は (終了時刻, 最大のシーケンス番号)
+///   （バックアップ未実行の場合はNone、シーケンス番号を記録する前のバックアップの場合はシーケンス番号がNone）、エラー時は `SqlxError`
+pub async fn get_last_backup_until(
+    pool: &SqlitePool,
+) -> Result<Option<(DateTime<Utc>, Option<i64>)>, SqlxError> {
+    let last: Option<(String, Option<i64>)> = sqlx::query_as(
+        "SELECT until, until_sequence FROM backups ORDER BY until DESC, id DESC LIMIT 1",
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    last.map(|(until, until_sequence)| {
+        DateTime::parse_from_rfc3339(&until)
+            .map(|until| (until.with_timezone(&Utc), until_sequence))
+            .map_err(|e| SqlxError::Decode(Box::new(e)))
+    })
+    .transpose()
+}
+
+/// メッセージを編集し、編集履歴を記録する
+///
+/// メッセージ本文を更新して編集済みフラグを立て、編集前後の内容を `message_edits` に記録します。
+/// 1メッセージあたりの履歴が `MAX_EDITS_PER_MESSAGE` 件を超えた場合は古いものから削除します。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `message_id` - 編集するメッセージのID
+/// * `new_content` - 編集後の内容
+/// * `editor` - 編集者（"streamer"、視聴者のウォレットアドレスなど）
+///
+/// # 戻り値
+/// * `Result<MessageEdit, SqlxError>` - 成功時は記録した編集履歴、エラー時は `SqlxError`
+///
+/// # エラー
+/// - メッセージが存在しない場合は `SqlxError::RowNotFound`
+/// - SQLクエリ実行エラー
+pub async fn record_message_edit(
+    pool: &SqlitePool,
+    message_id: &str,
+    new_content: &str,
+    editor: &str,
+) -> Result<MessageEdit, SqlxError> {
+    let mut tx = pool.begin().await?;
+
+    let (old_content,): (String,) = sqlx::query_as("SELECT message FROM messages WHERE id = ?")
+        .bind(message_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(SqlxError::RowNotFound)?;
+
+    sqlx::query("UPDATE messages SET message = ?, is_edited = 1 WHERE id = ?")
+        .bind(new_content)
+        .bind(message_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let edit = sqlx::query_as::<_, MessageEdit>(
+        r#"
+        INSERT INTO message_edits (message_id, old_content, new_content, edited_at, editor)
+        VALUES (?, ?, ?, ?, ?)
+        RETURNING id, message_id, old_content, new_content, edited_at, editor
+        "#,
+    )
+    .bind(message_id)
+    .bind(&old_content)
+    .bind(new_content)
+    .bind(Utc::now().to_rfc3339())
+    .bind(editor)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // 保持件数の上限を超えた古い履歴を削除
+    sqlx::query(
+        r#"
+        DELETE FROM message_edits
+        WHERE message_id = ?
+          AND id NOT IN (
+            SELECT id FROM message_edits WHERE message_id = ? ORDER BY id DESC LIMIT ?
+          )
+        "#,
+    )
+    .bind(message_id)
+    .bind(message_id)
+    .bind(MAX_EDITS_PER_MESSAGE)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(edit)
+}
+
+/// メッセージの編集履歴を取得する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `message_id` - 対象メッセージのID
+///
+/// # 戻り値
+/// * `Result<Vec<MessageEdit>, SqlxError>` - 成功時は編集履歴（古い順）、エラー時は `SqlxError`
+pub async fn get_message_edit_history(
+    pool: &SqlitePool,
+    message_id: &str,
+) -> Result<Vec<MessageEdit>, SqlxError> {
+    sqlx::query_as::<_, MessageEdit>(
+        r#"
+        SELECT id, message_id, old_content, new_content, edited_at, editor
+        FROM message_edits
+        WHERE message_id = ?
+        ORDER BY id ASC
+        "#,
+    )
+    .bind(message_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// メッセージのハイライト状態を設定する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `message_id` - 対象メッセージのID
+/// * `highlighted` - ハイライトするかどうか
+///
+/// # 戻り値
+/// * `Result<bool, SqlxError>` - 成功時はメッセージが存在した場合 `true`、エラー時は `SqlxError`
+pub async fn set_message_highlight(
+    pool: &SqlitePool,
+    message_id: &str,
+    highlighted: bool,
+) -> Result<bool, SqlxError> {
+    let result = sqlx::query("UPDATE messages SET highlighted = ? WHERE id = ?")
+        .bind(highlighted)
+        .bind(message_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// メッセージのハイライト状態を取得する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `message_id` - 対象メッセージのID
+///
+/// # 戻り値
+/// * `Result<Option<bool>, SqlxError>` - 成功時はハイライト状態（メッセージが存在しない場合はNone）、エラー時は `SqlxError`
+pub async fn get_message_highlight(
+    pool: &SqlitePool,
+    message_id: &str,
+) -> Result<Option<bool>, SqlxError> {
+    let highlighted: Option<(bool,)> =
+        sqlx::query_as("SELECT highlighted FROM messages WHERE id = ?")
+            .bind(message_id)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(highlighted.map(|(highlighted,)| highlighted))
+}
+
+/// ハイライトされたメッセージを時系列順に取得する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session_id` - 指定された場合はそのセッションのメッセージのみを取得
+/// * `limit` - 取得するメッセージの最大数
+/// * `offset` - 結果セットのオフセット
+///
+/// # 戻り値
+/// * `Result<Vec<Message>, SqlxError>` - 成功時はハイライトされたメッセージ（古い順）、エラー時は `SqlxError`
+pub async fn get_highlighted_messages(
+    pool: &SqlitePool,
+    session_id: Option<&str>,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<Message>, SqlxError> {
+    sqlx::query_as::<_, Message>(
+        r#"
+        SELECT
+            id,
+            timestamp,
+            display_name,
+            message,
+            amount,
+            coin,
+            tx_hash,
+            wallet_address,
+            session_id,
+            channel,
+            sequence,
+            language,
+            is_edited,
+            highlighted
+        FROM messages
+        WHERE highlighted = 1 AND (? IS NULL OR session_id = ?)
+        ORDER BY timestamp ASC, sequence ASC
+        LIMIT ? OFFSET ?
+        "#,
+    )
+    .bind(session_id)
+    .bind(session_id)
+    .bind(limit)
+    .bind(offset.max(0))
+    .fetch_all(pool)
+    .await
+}
+
+/// WebSocket接続の開始を接続ログに記録する
+///
+/// 同じクライアントの記録が既にある場合（切断の記録が先に書き込まれた場合）は何もしません。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `client_id` - 接続クライアントのID
+/// * `ip` - 接続元のIPアドレス
+/// * `session_id` - 接続時の配信セッションID
+/// * `connected_at` - 接続時刻（ISO 8601形式の文字列）
+///
+/// # 戻り値
+/// * `Result<(), SqlxError>` - 成功時は `Ok(())`, エラー時は `SqlxError`
+pub async fn log_connection(
+    pool: &SqlitePool,
+    client_id: &str,
+    ip: &str,
+    session_id: Option<&str>,
+    connected_at: &str,
+) -> Result<(), SqlxError> {
+    sqlx::query(
+        r#"
+        INSERT INTO connections (client_id, ip, session_id, connected_at)
+        VALUES (?, ?, ?, ?)
+        ON CONFLICT(client_id) DO NOTHING
+        "#,
+    )
+    .bind(client_id)
+    .bind(ip)
+    .bind(session_id)
+    .bind(connected_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// WebSocket接続の切断を接続ログに記録する
+///
+/// 接続と切断の記録は非同期に書き込まれるため、接続の記録がまだない場合は
+/// 接続情報とあわせて記録します。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `client_id` - 切断したクライアントのID
+/// * `ip` - 接続元のIPアドレス
+/// * `session_id` - 接続時の配信セッションID
+/// * `connected_at` - 接続時刻（ISO 8601形式の文字列）
+/// * `disconnected_at` - 切断時刻
+/// * `messages_sent` - 切断時点までに送信したメッセージ数
+///
+/// # 戻り値
+/// * `Result<(), SqlxError>` - 成功時は `Ok(())`, エラー時は `SqlxError`
+pub async fn log_disconnection(
+    pool: &SqlitePool,
+    client_id: &str,
+    ip: &str,
+    session_id: Option<&str>,
+    connected_at: &str,
+    disconnected_at: DateTime<Utc>,
+    messages_sent: i64,
+) -> Result<(), SqlxError> {
+    sqlx::query(
+        r#"
+        INSERT INTO connections (client_id, ip, session_id, connected_at, disconnected_at, messages_sent)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT(client_id) DO UPDATE SET
+            disconnected_at = excluded.disconnected_at,
+            messages_sent = excluded.messages_sent
+        "#,
+    )
+    .bind(client_id)
+    .bind(ip)
+    .bind(session_id)
+    .bind(connected_at)
+    .bind(disconnected_at.to_rfc3339())
+    .bind(messages_sent)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// 接続ログを新しい順に取得する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session_id` - 指定された場合はそのセッション中の接続のみを取得
+/// * `limit` - 取得する最大件数（1-1000、デフォルトは100）
+///
+/// # 戻り値
+/// * `Result<Vec<ConnectionLog>, SqlxError>` - 成功時は接続ログ（接続時刻の新しい順）、エラー時は `SqlxError`
+pub async fn get_connection_logs(
+    pool: &SqlitePool,
+    session_id: Option<&str>,
+    limit: i64,
+) -> Result<Vec<ConnectionLog>, SqlxError> {
+    let safe_limit = if limit <= 0 {
+        100
+    } else if limit > 1000 {
+        1000
+    } else {
+        limit
+    };
+
+    sqlx::query_as::<_, ConnectionLog>(
+        r#"
+        SELECT id, client_id, ip, session_id, connected_at, disconnected_at, messages_sent
+        FROM connections
+        WHERE ? IS NULL OR session_id = ?
+        ORDER BY connected_at DESC, id DESC
+        LIMIT ?
+        "#,
+    )
+    .bind(session_id)
+    .bind(session_id)
+    .bind(safe_limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// 保持期間を過ぎた接続ログを削除する
+///
+/// 接続時刻が指定日数より前で、切断済みの接続ログを削除します。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `retention_days` - 接続ログを保持する日数
+///
+/// # 戻り値
+/// * `Result<u64, SqlxError>` - 成功時は削除した接続ログの数、エラー時は `SqlxError`
+pub async fn prune_connection_logs(
+    pool: &SqlitePool,
+    retention_days: i64,
+) -> Result<u64, SqlxError> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM connections
+        WHERE disconnected_at IS NOT NULL
+          AND datetime(connected_at) < datetime('now', '-' || ? || ' days')
+        "#,
+    )
+    .bind(retention_days)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// セッション中の各クライアントの接続ログを送信メッセージ数の多い順に取得する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session_id` - 配信セッションID
+///
+/// # 戻り値
+/// * `Result<Vec<ConnectionLog>, SqlxError>` - 成功時は接続ログ（送信メッセージ数の多い順）、エラー時は `SqlxError`
+pub async fn get_client_activity_summary(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> Result<Vec<ConnectionLog>, SqlxError> {
+    sqlx::query_as::<_, ConnectionLog>(
+        r#"
+        SELECT id, client_id, ip, session_id, connected_at, disconnected_at, messages_sent
+        FROM connections
+        WHERE session_id = ?
+        ORDER BY messages_sent DESC, connected_at ASC, id ASC
+        "#,
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// 視聴者数の推移をセッションに紐づけて保存する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session_id` - 配信セッションID
+/// * `history` - サンプリング時刻と視聴者数の時系列
+///
+/// # 戻り値
+/// * `Result<u64, SqlxError>` - 成功時は保存した件数、エラー時は `SqlxError`
+pub async fn save_viewer_count_history(
+    pool: &SqlitePool,
+    session_id: &str,
+    history: &[ViewerCountPoint],
+) -> Result<u64, SqlxError> {
+    let mut tx = pool.begin().await?;
+    for sample in history {
+        let sample = ViewerCountSample::from_sample(sample);
+        sqlx::query(
+            "INSERT INTO viewer_count_samples (session_id, sampled_at, viewer_count) VALUES (?, ?, ?)",
+        )
+        .bind(session_id)
+        .bind(&sample.sampled_at)
+        .bind(sample.viewer_count)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    Ok(history.len() as u64)
+}
+
+/// セッションの視聴者数の推移を時刻順に取得する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session_id` - 配信セッションID
+///
+/// # 戻り値
+/// * `Result<Vec<ViewerCountSample>, SqlxError>` - 成功時は視聴者数の推移（古い順）、エラー時は `SqlxError`
+pub async fn get_viewer_count_history(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> Result<Vec<ViewerCountSample>, SqlxError> {
+    sqlx::query_as::<_, ViewerCountSample>(
+        r#"
+        SELECT sampled_at, viewer_count
+        FROM viewer_count_samples
+        WHERE session_id = ?
+        ORDER BY sampled_at ASC, id ASC
+        "#,
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// 配信しなかったスーパーチャットを記録する
+///
+/// 同じトランザクションが記録済みの場合は記録しません。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `superchat` - 配信しなかったスーパーチャットの記録
+///
+/// # 戻り値
+/// * `Result<bool, SqlxError>` - 成功時は記録した場合にtrue、エラー時は `SqlxError`
+pub async fn record_rejected_superchat(
+    pool: &SqlitePool,
+    superchat: &RejectedSuperchat,
+) -> Result<bool, SqlxError> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO rejected_superchats (
+            id, session_id, tx_hash, wallet_address, display_name, amount, coin, content,
+            reason, verified, rejected_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(&superchat.id)
+    .bind(&superchat.session_id)
+    .bind(&superchat.tx_hash)
+    .bind(&superchat.wallet_address)
+    .bind(&superchat.display_name)
+    .bind(superchat.amount)
+    .bind(&superchat.coin)
+    .bind(&superchat.content)
+    .bind(&superchat.reason)
+    .bind(superchat.verified)
+    .bind(&superchat.rejected_at)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// 配信しなかったスーパーチャットを記録時刻の新しい順に取得する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `session_id` - 指定された場合はそのセッション中の記録のみを取得
+/// * `limit` - 取得する最大件数（1〜1000、範囲外の場合は100）
+///
+/// # 戻り値
+/// * `Result<Vec<RejectedSuperchat>, SqlxError>` - 成功時は配信しなかったスーパーチャット、エラー時は `SqlxError`
+pub async fn get_rejected_superchats(
+    pool: &SqlitePool,
+    session_id: Option<&str>,
+    limit: i64,
+) -> Result<Vec<RejectedSuperchat>, SqlxError> {
+    let safe_limit = if (1..=1000).contains(&limit) {
+        limit
+    } else {
+        100
+    };
+
+    sqlx::query_as::<_, RejectedSuperchat>(
+        r#"
+        SELECT id, session_id, tx_hash, wallet_address, display_name, amount, coin, content,
+               reason, verified, rejected_at
+        FROM rejected_superchats
+        WHERE ? IS NULL OR session_id = ?
+        ORDER BY rejected_at DESC, id DESC
+        LIMIT ?
+        "#,
+    )
+    .bind(session_id)
+    .bind(session_id)
+    .bind(safe_limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// 保存された設定値を取得する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `key` - 設定キー
+///
+/// # 戻り値
+/// * `Result<Option<String>, SqlxError>` - 成功時は設定値（未保存の場合はNone）、エラー時は `SqlxError`
+pub async fn get_setting(pool: &SqlitePool, key: &str) -> Result<Option<String>, SqlxError> {
+    let value: Option<(String,)> = sqlx::query_as("SELECT value FROM settings WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(value.map(|(value,)| value))
+}
+
+/// 設定値を保存する
+///
+/// 同じキーの設定値が既にある場合は上書きします。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `key` - 設定キー
+/// * `value` - 保存する値
+///
+/// # 戻り値
+/// * `Result<(), SqlxError>` - 成功時は `Ok(())`, エラー時は `SqlxError`
+pub async fn set_setting(pool: &SqlitePool, key: &str, value: &str) -> Result<(), SqlxError> {
+    sqlx::query(
+        r#"
+        INSERT INTO settings (key, value)
+        VALUES (?, ?)
+        ON CONFLICT(key) DO UPDATE SET value = excluded.value
+        "#,
+    )
+    .bind(key)
+    .bind(value)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// 複数の設定値を1つのトランザクションで保存する
+///
+/// 途中で失敗した場合は、いずれの設定値も保存しません。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+/// * `settings` - 設定キーと保存する値
+///
+/// # エラー
+/// * `SqlxError` - データベース操作に失敗した場合
+pub async fn set_settings(
+    pool: &SqlitePool,
+    settings: &BTreeMap<&'static str, String>,
+) -> Result<(), SqlxError> {
+    let mut tx = pool.begin().await?;
+    for (key, value) in settings {
+        sqlx::query(
+            r#"
+            INSERT INTO settings (key, value)
+            VALUES (?, ?)
+            ON CONFLICT(key) DO UPDATE SET value = excluded.value
+            "#,
+        )
+        .bind(key)
+        .bind(value)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// データベース接続プールが正常に応答するか確認する
+///
+/// `SELECT 1` を実行し、一定時間内に応答が得られるかを確認します。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+///
+/// # 戻り値
+/// * `bool` - 正常に応答した場合は `true`、エラーまたはタイムアウトの場合は `false`
+pub async fn check_pool_health(pool: &SqlitePool) -> bool {
+    let query = sqlx::query("SELECT 1").execute(pool);
+    match tokio::time::timeout(POOL_HEALTH_CHECK_TIMEOUT, query).await {
+        Ok(Ok(_)) => true,
+        Ok(Err(e)) => {
+            eprintln!("データベースのヘルスチェックに失敗しました: {}", e);
+            false
+        }
+        Err(_) => {
+            eprintln!("データベースのヘルスチェックがタイムアウトしました");
+            false
+        }
+    }
+}
+
+/// データベースの論理サイズ（ページ数 × ページサイズ）を取得する
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+///
+/// # 戻り値
+/// * `Result<i64, SqlxError>` - 成功時はデータベースのサイズ（バイト）、エラー時は `SqlxError`
+pub async fn get_database_size(pool: &SqlitePool) -> Result<i64, SqlxError> {
+    sqlx::query_scalar::<_, i64>(
+        "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// データベースに `VACUUM` を実行し、断片化した空き領域を解放する
+///
+/// 実行中はデータベース全体がロックされるため、配信に影響しないタイミングで呼び出してください。
+///
+/// # 引数
+/// * `pool` - SQLiteデータベース接続プール
+///
+/// # 戻り値
+/// * `Result<(i64, i64), SqlxError>` - 成功時は実行前・実行後のデータベースのサイズ（バイト）、エラー時は `SqlxError`
+///
+/// # エラー
+/// - データベース接続エラー
+/// - 他の接続がトランザクション中などで `VACUUM` を実行できない場合
+pub async fn vacuum(pool: &SqlitePool) -> Result<(i64, i64), SqlxError> {
+    let size_before = get_database_size(pool).await?;
+    sqlx::query("VACUUM").execute(pool).await?;
+    let size_after = get_database_size(pool).await?;
+    Ok((size_before, size_after))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db_models::{Message, Session};
+    use crate::{
+        CREATE_BACKUPS_TABLE_SQL, CREATE_CONNECTIONS_TABLE_SQL, CREATE_MESSAGES_TABLE_SQL,
+        CREATE_MESSAGE_EDITS_TABLE_SQL, CREATE_REJECTED_SUPERCHATS_TABLE_SQL,
+        CREATE_SESSIONS_TABLE_SQL, CREATE_SETTINGS_TABLE_SQL, CREATE_VIEWERS_TABLE_SQL,
+        CREATE_VIEWER_COUNT_SAMPLES_TABLE_SQL,
+    };
+
+    use super::*;
+    use uuid::Uuid;
+
+    /// `create_session`関数のテスト
+    #[sqlx::test]
+    async fn test_create_session(pool: SqlitePool) -> Result<(), SqlxError> {
+        // テスト用DBのセットアップ
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        // テスト用のセッションIDを生成
+        let session_id = Uuid::new_v4().to_string();
+
+        // セッション作成
+        create_session(&pool, &session_id).await?;
 
         // セッションがDBに正しく保存されたか確認
         let session: Session = sqlx::query_as::<_, Session>("SELECT * FROM sessions WHERE id = ?") // テーブル名を sessions に変更
@@ -405,45 +2765,1267 @@ mod tests {
             .fetch_one(&pool)
             .await?;
 
-        assert_eq!(session.id, session_id);
-        assert!(session.ended_at.is_none());
+        assert_eq!(session.id, session_id);
+        assert!(session.ended_at.is_none());
+
+        Ok(())
+    }
+
+    /// `end_session`関数のテスト
+    #[sqlx::test]
+    async fn test_end_session(pool: SqlitePool) -> Result<(), SqlxError> {
+        // テスト用DBのセットアップ
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        // テスト用のセッションIDを生成
+        let session_id = Uuid::new_v4().to_string();
+
+        // セッション作成
+        create_session(&pool, &session_id).await?;
+
+        // セッション終了
+        end_session(&pool, &session_id).await?;
+
+        // セッションが正しく更新されたか確認
+        let session: Session = sqlx::query_as::<_, Session>("SELECT * FROM sessions WHERE id = ?") // テーブル名を sessions に変更
+            .bind(&session_id)
+            .fetch_one(&pool)
+            .await?;
+
+        assert_eq!(session.id, session_id);
+        assert!(session.ended_at.is_some());
+
+        Ok(())
+    }
+
+    /// `get_session_duration`関数のテスト
+    #[sqlx::test]
+    async fn test_get_session_duration(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let session_id = Uuid::new_v4().to_string();
+        create_session(&pool, &session_id).await?;
+
+        // 未終了のセッションは配信時間なし
+        assert_eq!(get_session_duration(&pool, &session_id).await?, None);
+
+        update_session_times(
+            &pool,
+            &session_id,
+            "2024-05-01T20:00:00+09:00",
+            "2024-05-01T11:30:15Z",
+        )
+        .await?;
+        assert_eq!(
+            get_session_duration(&pool, &session_id).await?,
+            Some(Duration::from_secs(30 * 60 + 15))
+        );
+
+        Ok(())
+    }
+
+    /// `update_session_times`関数のテスト
+    #[sqlx::test]
+    async fn test_update_session_times(pool: SqlitePool) -> Result<(), SqlxError> {
+        // テスト用DBのセットアップ
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let session_id = Uuid::new_v4().to_string();
+        create_session(&pool, &session_id).await?;
+        end_session(&pool, &session_id).await?;
+
+        // 現在時刻のメッセージを保存
+        let message = Message {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            display_name: "テストユーザー".to_string(),
+            content: "テストメッセージ".to_string(),
+            amount: Some(0.0),
+            coin: None,
+            tx_hash: None,
+            wallet_address: None,
+            session_id: Some(session_id.clone()),
+            channel: None,
+            sequence: None,
+            language: None,
+            is_edited: false,
+            highlighted: false,
+        };
+        save_message_db(&pool, &message).await?;
+
+        // 過去の時刻に修正するとメッセージが期間外になる
+        let started_at = "2024-01-01T10:00:00+00:00";
+        let ended_at = "2024-01-01T12:00:00+00:00";
+        assert!(update_session_times(&pool, &session_id, started_at, ended_at).await?);
+        assert_eq!(
+            count_messages_outside_range(&pool, &session_id, started_at, ended_at).await?,
+            1
+        );
+
+        let session: Session = sqlx::query_as::<_, Session>("SELECT * FROM sessions WHERE id = ?")
+            .bind(&session_id)
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(session.started_at, started_at);
+        assert_eq!(session.ended_at.as_deref(), Some(ended_at));
+
+        // 存在しないセッションは更新されない
+        assert!(!update_session_times(&pool, "unknown", started_at, ended_at).await?);
+
+        Ok(())
+    }
+
+    /// `set_session_title`関数のテスト
+    #[sqlx::test]
+    async fn test_set_session_title(pool: SqlitePool) -> Result<(), SqlxError> {
+        // テスト用DBのセットアップ
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let session_id = Uuid::new_v4().to_string();
+        create_session(&pool, &session_id).await?;
+        assert_eq!(get_session(&pool, &session_id).await?.unwrap().title, None);
+
+        assert!(set_session_title(&pool, &session_id, Some("雑談配信")).await?);
+        let session = get_session(&pool, &session_id).await?.unwrap();
+        assert_eq!(session.title.as_deref(), Some("雑談配信"));
+
+        // Noneを指定するとタイトルが削除される
+        assert!(set_session_title(&pool, &session_id, None).await?);
+        assert_eq!(get_session(&pool, &session_id).await?.unwrap().title, None);
+
+        // 存在しないセッションは更新されない
+        assert!(!set_session_title(&pool, "unknown", Some("タイトル")).await?);
+
+        Ok(())
+    }
+
+    /// `save_message_db`関数のテスト
+    #[sqlx::test]
+    async fn test_save_message_db(pool: SqlitePool) -> Result<(), SqlxError> {
+        // テスト用DBのセットアップ
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        // テスト用のセッションを作成
+        let session_id = uuid::Uuid::new_v4().to_string();
+        create_session(&pool, &session_id).await?;
+
+        // テスト用のメッセージを作成
+        let message = Message {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            display_name: "テストユーザー".to_string(),
+            content: "これはテストメッセージです".to_string(),
+            amount: Some(10.5),
+            coin: Some("SUI".to_string()),
+            tx_hash: Some("0x123456789abcdef".to_string()),
+            wallet_address: Some("0xabcdef123456789".to_string()),
+            session_id: Some(session_id.clone()),
+            channel: None,
+            sequence: None,
+            language: None,
+            is_edited: false,
+            highlighted: false,
+        };
+
+        // メッセージを保存
+        assert!(save_message_db(&pool, &message).await?);
+
+        // 同じIDのメッセージの再送は保存をスキップする
+        let resent = Message {
+            content: "再送されたメッセージ".to_string(),
+            ..message.clone()
+        };
+        assert!(!save_message_db(&pool, &resent).await?);
+
+        // 同じトランザクションハッシュのスーパーチャットは別IDでも保存をスキップする
+        ensure_tx_hash_unique_index(&pool).await?;
+        let replayed = Message {
+            id: uuid::Uuid::new_v4().to_string(),
+            ..message.clone()
+        };
+        assert!(!save_message_db(&pool, &replayed).await?);
+        let chat = Message {
+            id: uuid::Uuid::new_v4().to_string(),
+            tx_hash: None,
+            ..message.clone()
+        };
+        let another_chat = Message {
+            id: uuid::Uuid::new_v4().to_string(),
+            ..chat.clone()
+        };
+        assert!(save_message_db(&pool, &chat).await?);
+        assert!(save_message_db(&pool, &another_chat).await?);
+
+        // メッセージがDBに正しく保存されたか確認
+        let saved_message: Message =
+            sqlx::query_as::<_, Message>("SELECT * FROM messages WHERE id = ?") // テーブル名を messages に変更
+                .bind(&message.id)
+                .fetch_one(&pool)
+                .await?;
+
+        assert_eq!(saved_message.id, message.id);
+        assert_eq!(saved_message.display_name, message.display_name);
+        assert_eq!(saved_message.content, message.content);
+        assert_eq!(saved_message.amount, message.amount);
+        assert_eq!(saved_message.tx_hash, message.tx_hash);
+        assert_eq!(saved_message.wallet_address, message.wallet_address);
+        assert_eq!(saved_message.session_id, message.session_id);
+
+        Ok(())
+    }
+
+    /// `fetch_messages`関数のテスト
+    #[sqlx::test]
+    async fn test_fetch_messages(pool: SqlitePool) -> Result<(), SqlxError> {
+        // テスト用DBのセットアップ
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        // テスト用のセッションIDを生成
+        let session_id = Uuid::new_v4().to_string();
+
+        // セッション作成
+        create_session(&pool, &session_id).await?;
+
+        // テスト用のメッセージを5件作成して保存
+        let mut test_messages = Vec::new();
+        for i in 1..=5 {
+            let message = Message {
+                id: Uuid::new_v4().to_string(),
+                timestamp: Utc::now(),
+                display_name: format!("テストユーザー{}", i),
+                content: format!("テストメッセージ本文{}", i),
+                amount: if i % 2 == 0 {
+                    Some(i as f64 * 10.0)
+                } else {
+                    None
+                },
+                coin: if i % 2 == 0 {
+                    Some("SUI".to_string())
+                } else {
+                    None
+                },
+                tx_hash: if i % 2 == 0 {
+                    Some(format!("tx_hash_{}", i))
+                } else {
+                    None
+                },
+                wallet_address: if i % 2 == 0 {
+                    Some(format!("wallet_{}", i))
+                } else {
+                    None
+                },
+                session_id: Some(session_id.clone()),
+                channel: None,
+                sequence: None,
+                language: None,
+                is_edited: false,
+                highlighted: false,
+            };
+            test_messages.push(message.clone());
+            save_message_db(&pool, &message).await?;
+        }
+
+        // メッセージを取得し、結果を検証
+
+        // 全件取得 (limit=10, offset=0)
+        let all_messages = fetch_messages(&pool, 10, 0).await?;
+        assert_eq!(
+            all_messages.len(),
+            5,
+            "全件取得で5件のメッセージが取得されるべき"
+        );
+
+        // 制限付き取得 (limit=3, offset=0)
+        let limited_messages = fetch_messages(&pool, 3, 0).await?;
+        assert_eq!(
+            limited_messages.len(),
+            3,
+            "制限付き取得で3件のメッセージが取得されるべき"
+        );
+
+        // オフセット付き取得 (limit=10, offset=2)
+        let offset_messages = fetch_messages(&pool, 10, 2).await?;
+        assert_eq!(
+            offset_messages.len(),
+            3,
+            "オフセット付き取得で3件のメッセージが取得されるべき"
+        );
+
+        // 範囲外のオフセット (limit=10, offset=10)
+        let out_of_range = fetch_messages(&pool, 10, 10).await?;
+        assert_eq!(
+            out_of_range.len(),
+            0,
+            "範囲外のオフセットで0件が取得されるべき"
+        );
+
+        // 負のlimitとoffsetの処理を確認 (安全な値に変換されるはず)
+        let with_negative = fetch_messages(&pool, -1, -5).await?;
+        assert!(!with_negative.is_empty(), "負の値が安全に処理されるべき");
+
+        println!("fetch_messagesのテスト完了");
+        Ok(())
+    }
+
+    /// 同一時刻のメッセージがページネーションの境界でも受信順に並ぶことのテスト
+    #[sqlx::test]
+    async fn test_message_order_with_same_timestamp(pool: SqlitePool) -> Result<(), SqlxError> {
+        // テスト用DBのセットアップ
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let session_id = Uuid::new_v4().to_string();
+        create_session(&pool, &session_id).await?;
+
+        // 同一時刻のメッセージを大量に保存（IDは受信順と無関係なUUID）
+        let timestamp = Utc::now();
+        let mut inserted_ids = Vec::new();
+        for i in 0..50 {
+            let message = Message {
+                id: Uuid::new_v4().to_string(),
+                timestamp,
+                display_name: "テストユーザー".to_string(),
+                content: format!("同一時刻メッセージ{}", i),
+                amount: Some(0.0),
+                coin: None,
+                tx_hash: None,
+                wallet_address: None,
+                session_id: Some(session_id.clone()),
+                channel: None,
+                sequence: None,
+                language: None,
+                is_edited: false,
+                highlighted: false,
+            };
+            save_message_db(&pool, &message).await?;
+            inserted_ids.push(message.id);
+        }
+
+        // 昇順・降順それぞれで境界をまたいでページングし、受信順と一致することを確認
+        for sort_asc in [true, false] {
+            let mut paged_ids = Vec::new();
+            let mut offset = 0;
+            loop {
+                let page = get_messages_by_session_id_with_options(
+                    &pool,
+                    &session_id,
+                    7,
+                    Some(offset),
+                    sort_asc,
+                    None,
+                    None,
+                )
+                .await?;
+                if page.is_empty() {
+                    break;
+                }
+                offset += page.len() as i64;
+                paged_ids.extend(page.into_iter().map(|msg| msg.id));
+            }
+
+            let mut expected = inserted_ids.clone();
+            if !sort_asc {
+                expected.reverse();
+            }
+            assert_eq!(
+                paged_ids, expected,
+                "sort_asc={} で受信順が保たれるべき",
+                sort_asc
+            );
+        }
+
+        // 最新N件の取得でも受信順（古い順）に並ぶことを確認
+        let latest =
+            get_messages_by_session_id(&pool, &session_id, 10, None, None, None, None).await?;
+        let latest_ids: Vec<String> = latest.into_iter().map(|msg| msg.id).collect();
+        assert_eq!(latest_ids, inserted_ids[39..].to_vec());
+
+        Ok(())
+    }
+
+    /// 過去ログのカーソル取得で、ページの境界と同一時刻のメッセージを取りこぼさないことのテスト
+    #[sqlx::test]
+    async fn test_history_cursor_with_same_timestamp(pool: SqlitePool) -> Result<(), SqlxError> {
+        // テスト用DBのセットアップ
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let session_id = Uuid::new_v4().to_string();
+        create_session(&pool, &session_id).await?;
+
+        // サーバーの受信時刻と同じミリ秒精度で、古いメッセージの後に同一時刻のメッセージを保存
+        let latest = DateTime::from_timestamp_millis(Utc::now().timestamp_millis()).unwrap();
+        let timestamps = (0..5)
+            .map(|i| latest - chrono::Duration::seconds(5 - i))
+            .chain(std::iter::repeat(latest).take(20));
+        let mut inserted_ids = Vec::new();
+        for (i, timestamp) in timestamps.enumerate() {
+            let message = Message {
+                id: Uuid::new_v4().to_string(),
+                timestamp,
+                display_name: "テストユーザー".to_string(),
+                content: format!("メッセージ{}", i),
+                amount: Some(0.0),
+                coin: None,
+                tx_hash: None,
+                wallet_address: None,
+                session_id: Some(session_id.clone()),
+                channel: None,
+                sequence: None,
+                language: None,
+                is_edited: false,
+                highlighted: false,
+            };
+            save_message_db(&pool, &message).await?;
+            inserted_ids.push(message.id);
+        }
+
+        // 取得済みの最も古いメッセージの時刻とシーケンス番号をカーソルにして、新しい順にページングする
+        let limit = 7;
+        let mut cursor: Option<(i64, Option<i64>)> = None;
+        let mut pages = Vec::new();
+        loop {
+            let page = get_messages_by_session_id(
+                &pool,
+                &session_id,
+                limit,
+                cursor.map(|(timestamp, _)| timestamp),
+                cursor.and_then(|(_, sequence)| sequence),
+                None,
+                None,
+            )
+            .await?;
+            let has_more = page.len() as i64 > limit;
+            // 最も古い1件は次のページがあるかの判定用のため返さない（WebSocketの履歴取得と同じ扱い）
+            let page: Vec<Message> = page.into_iter().skip(usize::from(has_more)).collect();
+            let oldest = page.first().expect("各ページにメッセージがあるべき");
+            cursor = Some((oldest.timestamp.timestamp_millis(), oldest.sequence));
+            pages.push(page);
+            if !has_more {
+                break;
+            }
+        }
+
+        let paged_ids: Vec<String> = pages
+            .into_iter()
+            .rev()
+            .flatten()
+            .map(|msg| msg.id)
+            .collect();
+        assert_eq!(
+            paged_ids, inserted_ids,
+            "境界のメッセージを取りこぼさず重複もしないべき"
+        );
+
+        Ok(())
+    }
+
+    /// 言語による履歴の絞り込みのテスト
+    #[sqlx::test]
+    async fn test_get_messages_filtered_by_language(pool: SqlitePool) -> Result<(), SqlxError> {
+        // テスト用DBのセットアップ
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let session_id = Uuid::new_v4().to_string();
+        create_session(&pool, &session_id).await?;
+
+        // 日本語・英語・判定不能のメッセージを保存
+        for language in [Some("ja"), Some("en"), Some("ja"), None] {
+            let message = Message {
+                id: Uuid::new_v4().to_string(),
+                timestamp: Utc::now(),
+                display_name: "テストユーザー".to_string(),
+                content: "テストメッセージ".to_string(),
+                amount: Some(0.0),
+                coin: None,
+                tx_hash: None,
+                wallet_address: None,
+                session_id: Some(session_id.clone()),
+                channel: None,
+                sequence: None,
+                language: language.map(str::to_string),
+                is_edited: false,
+                highlighted: false,
+            };
+            save_message_db(&pool, &message).await?;
+        }
+
+        let japanese =
+            get_messages_by_session_id(&pool, &session_id, 10, None, None, None, Some("ja"))
+                .await?;
+        assert_eq!(japanese.len(), 2);
+        assert!(japanese
+            .iter()
+            .all(|msg| msg.language.as_deref() == Some("ja")));
+
+        let english = get_messages_by_session_id_with_options(
+            &pool,
+            &session_id,
+            10,
+            Some(0),
+            true,
+            None,
+            Some("en"),
+        )
+        .await?;
+        assert_eq!(english.len(), 1);
+
+        // 言語未指定の場合は判定不能のメッセージも含めて取得
+        let all =
+            get_messages_by_session_id(&pool, &session_id, 10, None, None, None, None).await?;
+        assert_eq!(all.len(), 4);
+
+        Ok(())
+    }
+
+    /// `get_top_donors`関数のテスト
+    #[sqlx::test]
+    async fn test_get_top_donors(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_VIEWERS_TABLE_SQL).execute(&pool).await?;
+
+        let base = Utc::now();
+        let superchat =
+            |seconds: i64, display_name: &str, amount: f64, coin: &str, wallet: &str| Message {
+                id: Uuid::new_v4().to_string(),
+                timestamp: base + chrono::Duration::seconds(seconds),
+                display_name: display_name.to_string(),
+                content: "応援しています".to_string(),
+                amount: Some(amount),
+                coin: Some(coin.to_string()),
+                tx_hash: None,
+                wallet_address: Some(wallet.to_string()),
+                session_id: None,
+                channel: None,
+                sequence: None,
+                language: None,
+                is_edited: false,
+                highlighted: false,
+            };
+
+        save_message_db(&pool, &superchat(0, "初代", 1.0, "SUI", "0xa")).await?;
+        save_message_db(&pool, &superchat(1, "改名後", 2.0, "SUI", "0xa")).await?;
+        save_message_db(&pool, &superchat(2, "常連", 5.0, "USDC", "0xb")).await?;
+        save_message_db(&pool, &superchat(3, "非公開", 9.0, "SUI", "0xc")).await?;
+        save_message_db(&pool, &superchat(4, "改名後", 4.0, "usdc", "0xa")).await?;
+        set_viewer_opt_out(&pool, "0xc", true).await?;
+
+        // オプトアウトした視聴者は含まず、表示名は最新のものを採用する
+        // 異なる通貨の金額は合算せず、通貨ごとに多い順に並べる
+        let ranking = get_top_donors(&pool, 10, None).await?;
+        let summary: Vec<_> = ranking
+            .iter()
+            .map(|rank| {
+                (
+                    rank.coin.as_str(),
+                    rank.wallet_address.as_str(),
+                    rank.total_amount,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("SUI", "0xa", 3.0),
+                ("USDC", "0xb", 5.0),
+                ("USDC", "0xa", 4.0)
+            ]
+        );
+        assert_eq!(ranking[0].display_name, "改名後");
+        assert_eq!(ranking[0].superchat_count, 2);
+
+        // 件数の上限は通貨ごとに適用する
+        let ranking = get_top_donors(&pool, 1, None).await?;
+        assert_eq!(ranking.len(), 2);
+        assert_eq!(ranking[1].wallet_address, "0xb");
+
+        // 通貨を指定した場合はその通貨のみを集計する
+        let ranking = get_top_donors(&pool, 10, Some("sui")).await?;
+        assert_eq!(ranking.len(), 1);
+        assert_eq!(ranking[0].wallet_address, "0xa");
+
+        Ok(())
+    }
+
+    /// 視聴者プロフィールの集計とオプトアウトのテスト
+    #[sqlx::test]
+    async fn test_viewer_profile_aggregation(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_VIEWERS_TABLE_SQL).execute(&pool).await?;
+
+        let wallet = "0xviewer";
+        let message =
+            |display_name: &str, amount: f64, coin: Option<&str>, wallet: Option<&str>| Message {
+                id: Uuid::new_v4().to_string(),
+                timestamp: Utc::now(),
+                display_name: display_name.to_string(),
+                content: "応援しています".to_string(),
+                amount: Some(amount),
+                coin: coin.map(str::to_string),
+                tx_hash: None,
+                wallet_address: wallet.map(str::to_string),
+                session_id: None,
+                channel: None,
+                sequence: None,
+                language: None,
+                is_edited: false,
+                highlighted: false,
+            };
+
+        record_viewer_activity(&pool, &message("初代", 1.5, Some("SUI"), Some(wallet))).await?;
+        record_viewer_activity(&pool, &message("改名後", 2.0, Some("SUI"), Some(wallet))).await?;
+        // SUI以外の通貨は累計額に含めない
+        record_viewer_activity(&pool, &message("改名後", 10.0, Some("USDC"), Some(wallet))).await?;
+        // ウォレットのない匿名視聴者は識別対象外
+        record_viewer_activity(&pool, &message("匿名", 0.0, None, None)).await?;
+
+        let profile = get_viewer_profile(&pool, wallet).await?;
+        assert_eq!(profile.display_name, "改名後");
+        assert_eq!(profile.total_superchat_amount, 3.5);
+        assert_eq!(profile.superchat_count, 3);
+        assert_eq!(profile.message_count, 3);
+        assert_eq!(list_viewer_profiles(&pool, 10, 0).await?.len(), 1);
+
+        // オプトアウト後は実績が破棄され、以降のメッセージも集計されない
+        set_viewer_opt_out(&pool, wallet, true).await?;
+        record_viewer_activity(&pool, &message("改名後", 1.0, Some("SUI"), Some(wallet))).await?;
+        assert!(matches!(
+            get_viewer_profile(&pool, wallet).await,
+            Err(SqlxError::RowNotFound)
+        ));
+        assert!(list_viewer_profiles(&pool, 10, 0).await?.is_empty());
+
+        // オプトアウト解除後は改めて集計される
+        set_viewer_opt_out(&pool, wallet, false).await?;
+        record_viewer_activity(&pool, &message("再開", 1.0, Some("SUI"), Some(wallet))).await?;
+        let profile = get_viewer_profile(&pool, wallet).await?;
+        assert_eq!(profile.total_superchat_amount, 1.0);
+        assert_eq!(profile.message_count, 1);
+
+        Ok(())
+    }
+
+    /// 保持期間を過ぎたセッションの削除のテスト
+    #[sqlx::test]
+    async fn test_prune_old_sessions(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let old_ended = (Utc::now() - chrono::Duration::days(40)).to_rfc3339();
+        let recent_ended = (Utc::now() - chrono::Duration::days(5)).to_rfc3339();
+        for (session_id, ended_at) in [
+            ("old", Some(old_ended.as_str())),
+            ("recent", Some(recent_ended.as_str())),
+            ("unfinished", None),
+        ] {
+            sqlx::query(
+                "INSERT INTO sessions (id, started_at, ended_at, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?2, ?2)",
+            )
+            .bind(session_id)
+            .bind(&old_ended)
+            .bind(ended_at)
+            .execute(&pool)
+            .await?;
+        }
+        save_message_db(
+            &pool,
+            &Message {
+                id: Uuid::new_v4().to_string(),
+                timestamp: Utc::now(),
+                display_name: "視聴者".to_string(),
+                content: "古いメッセージ".to_string(),
+                amount: Some(0.0),
+                coin: None,
+                tx_hash: None,
+                wallet_address: None,
+                session_id: Some("old".to_string()),
+                channel: None,
+                sequence: None,
+                language: None,
+                is_edited: false,
+                highlighted: false,
+            },
+        )
+        .await?;
+
+        // 終了から30日を過ぎたセッションのみ削除し、未終了のセッションは残す
+        assert_eq!(prune_old_sessions(&pool, 30).await?, (1, 1));
+        let remaining: Vec<String> = sqlx::query_scalar("SELECT id FROM sessions ORDER BY id")
+            .fetch_all(&pool)
+            .await?;
+        assert_eq!(remaining, vec!["recent", "unfinished"]);
+        let (message_count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM messages")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(message_count, 0);
+
+        Ok(())
+    }
+
+    /// `reconcile_orphaned_sessions`関数のテスト
+    #[sqlx::test]
+    async fn test_reconcile_orphaned_sessions(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        create_session(&pool, "crashed").await?;
+        create_session(&pool, "empty").await?;
+        create_session(&pool, "finished").await?;
+        end_session(&pool, "finished").await?;
+
+        let last_timestamp = Utc::now() + chrono::Duration::minutes(30);
+        for timestamp in [Utc::now(), last_timestamp] {
+            save_message_db(
+                &pool,
+                &Message {
+                    id: Uuid::new_v4().to_string(),
+                    timestamp,
+                    display_name: "視聴者".to_string(),
+                    content: "こんにちは".to_string(),
+                    amount: Some(0.0),
+                    coin: None,
+                    tx_hash: None,
+                    wallet_address: None,
+                    session_id: Some("crashed".to_string()),
+                    channel: None,
+                    sequence: None,
+                    language: None,
+                    is_edited: false,
+                    highlighted: false,
+                },
+            )
+            .await?;
+        }
+
+        // 未終了のセッションは最後のメッセージ時刻で終了し、メッセージのないセッションは削除する
+        assert_eq!(reconcile_orphaned_sessions(&pool).await?, (1, 1));
+        let crashed = get_session(&pool, "crashed").await?.unwrap();
+        let ended_at = DateTime::parse_from_rfc3339(crashed.ended_at.as_deref().unwrap()).unwrap();
+        assert_eq!(ended_at.timestamp(), last_timestamp.timestamp());
+        assert!(get_session(&pool, "empty").await?.is_none());
+        assert!(get_session(&pool, "finished").await?.is_some());
+
+        Ok(())
+    }
+
+    /// `get_session_totals`関数のテスト
+    #[sqlx::test]
+    async fn test_get_session_totals(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let session_id = Uuid::new_v4().to_string();
+        let other_session_id = Uuid::new_v4().to_string();
+        create_session(&pool, &session_id).await?;
+        create_session(&pool, &other_session_id).await?;
+
+        let message = |amount: Option<f64>, coin: Option<&str>, session_id: &str| Message {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            display_name: "viewer".to_string(),
+            content: "こんにちは".to_string(),
+            amount,
+            coin: coin.map(str::to_string),
+            tx_hash: None,
+            wallet_address: None,
+            session_id: Some(session_id.to_string()),
+            channel: None,
+            sequence: None,
+            language: None,
+            is_edited: false,
+            highlighted: false,
+        };
+
+        save_message_db(&pool, &message(Some(1.5), Some("SUI"), &session_id)).await?;
+        save_message_db(&pool, &message(Some(2.0), Some("SUI"), &session_id)).await?;
+        save_message_db(&pool, &message(Some(10.0), Some("USDC"), &session_id)).await?;
+        save_message_db(&pool, &message(Some(0.0), None, &session_id)).await?;
+        // amountがNULLのチャットは合計に含めず件数のみ数える
+        save_message_db(&pool, &message(None, None, &session_id)).await?;
+        // 別セッションのメッセージは集計しない
+        save_message_db(&pool, &message(Some(5.0), Some("SUI"), &other_session_id)).await?;
+
+        let totals = get_session_totals(&pool, &session_id).await?;
+        assert_eq!(totals.chat_count, 2);
+        assert_eq!(totals.coins.len(), 2);
+        assert_eq!(
+            totals.coins["SUI"],
+            CoinTotal {
+                total_amount: 3.5,
+                superchat_count: 2
+            }
+        );
+        assert_eq!(totals.coins["USDC"].total_amount, 10.0);
+
+        // 存在しないセッションは全て0
+        assert_eq!(
+            get_session_totals(&pool, "missing").await?,
+            SessionTotals::default()
+        );
+
+        Ok(())
+    }
+
+    /// `delete_session`関数のテスト
+    #[sqlx::test]
+    async fn test_delete_session(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let session_id = Uuid::new_v4().to_string();
+        let other_session_id = Uuid::new_v4().to_string();
+        create_session(&pool, &session_id).await?;
+        create_session(&pool, &other_session_id).await?;
+
+        let message = |session_id: &str| Message {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            display_name: "viewer".to_string(),
+            content: "テスト配信".to_string(),
+            amount: Some(0.0),
+            coin: None,
+            tx_hash: None,
+            wallet_address: None,
+            session_id: Some(session_id.to_string()),
+            channel: None,
+            sequence: None,
+            language: None,
+            is_edited: false,
+            highlighted: false,
+        };
+        save_message_db(&pool, &message(&session_id)).await?;
+        save_message_db(&pool, &message(&session_id)).await?;
+        save_message_db(&pool, &message(&other_session_id)).await?;
+
+        // 紐づくメッセージも削除され、別セッションのメッセージは残る
+        assert_eq!(delete_session(&pool, &session_id).await?, Some(2));
+        assert!(get_all_messages_by_session_id(&pool, &session_id)
+            .await?
+            .is_empty());
+        assert_eq!(
+            get_all_messages_by_session_id(&pool, &other_session_id)
+                .await?
+                .len(),
+            1
+        );
+
+        // 存在しないセッションはNone
+        assert_eq!(delete_session(&pool, &session_id).await?, None);
+
+        Ok(())
+    }
+
+    /// `get_message_history`関数で絞り込んだ結果をページングするテスト
+    #[sqlx::test]
+    async fn test_get_message_history(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let session_id = Uuid::new_v4().to_string();
+        create_session(&pool, &session_id).await?;
+        let base = Utc::now();
+        for i in 0..10 {
+            let message = Message {
+                id: Uuid::new_v4().to_string(),
+                timestamp: base + chrono::Duration::seconds(i),
+                display_name: "viewer".to_string(),
+                content: format!("メッセージ{}", i),
+                amount: Some(if i % 3 == 0 { 1.0 } else { 0.0 }),
+                coin: None,
+                tx_hash: None,
+                wallet_address: None,
+                session_id: Some(session_id.clone()),
+                channel: (i % 2 == 1).then(|| "game".to_string()),
+                sequence: None,
+                language: Some(if i < 4 { "en" } else { "ja" }.to_string()),
+                is_edited: false,
+                highlighted: i >= 5,
+            };
+            save_message_db(&pool, &message).await?;
+        }
+
+        // 絞り込みはLIMIT/OFFSETより先に適用されるため、各ページが欠けない
+        let filter = MessageHistoryFilter {
+            channel: Some("game"),
+            ..Default::default()
+        };
+        let first = get_message_history(&pool, &filter, 3, 0, true).await?;
+        let second = get_message_history(&pool, &filter, 3, 3, true).await?;
+        assert_eq!(first.len(), 3);
+        assert_eq!(second.len(), 2);
+        assert!(first
+            .iter()
+            .chain(&second)
+            .all(|msg| msg.channel.as_deref() == Some("game")));
+
+        // チャンネル未設定のメッセージは "general" として扱う
+        let general = MessageHistoryFilter {
+            session_id: Some(&session_id),
+            channel: Some("general"),
+            ..Default::default()
+        };
+        let latest = get_message_history(&pool, &general, 2, 0, false).await?;
+        let contents: Vec<_> = latest.iter().map(|msg| msg.content.as_str()).collect();
+        assert_eq!(contents, vec!["メッセージ8", "メッセージ6"]);
+
+        let english = MessageHistoryFilter {
+            channel: Some("game"),
+            language: Some("en"),
+            ..Default::default()
+        };
+        let contents: Vec<_> = get_message_history(&pool, &english, 1, 1, true)
+            .await?
+            .into_iter()
+            .map(|msg| msg.content)
+            .collect();
+        assert_eq!(contents, vec!["メッセージ3"]);
+
+        // スーパーチャットのみの場合も他の条件と組み合わせて絞り込む
+        let superchats = MessageHistoryFilter {
+            session_id: Some(&session_id),
+            channel: Some("general"),
+            superchat_only: true,
+            ..Default::default()
+        };
+        let contents: Vec<_> = get_message_history(&pool, &superchats, 10, 0, false)
+            .await?
+            .into_iter()
+            .map(|msg| msg.content)
+            .collect();
+        assert_eq!(contents, vec!["メッセージ6", "メッセージ0"]);
+
+        let highlighted = MessageHistoryFilter {
+            channel: Some("game"),
+            highlighted_only: true,
+            ..Default::default()
+        };
+        let contents: Vec<_> = get_message_history(&pool, &highlighted, 2, 1, true)
+            .await?
+            .into_iter()
+            .map(|msg| msg.content)
+            .collect();
+        assert_eq!(contents, vec!["メッセージ7", "メッセージ9"]);
+
+        Ok(())
+    }
+
+    /// `get_superchats_by_session`関数のテスト
+    #[sqlx::test]
+    async fn test_get_superchats_by_session(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let session_id = Uuid::new_v4().to_string();
+        create_session(&pool, &session_id).await?;
+        for (i, amount) in [Some(0.0), Some(1.5), None, Some(3.0)]
+            .into_iter()
+            .enumerate()
+        {
+            save_message_db(
+                &pool,
+                &Message {
+                    id: Uuid::new_v4().to_string(),
+                    timestamp: Utc::now() + chrono::Duration::seconds(i as i64),
+                    display_name: "viewer".to_string(),
+                    content: format!("メッセージ{}", i),
+                    amount,
+                    coin: amount.map(|_| "SUI".to_string()),
+                    tx_hash: None,
+                    wallet_address: None,
+                    session_id: Some(session_id.clone()),
+                    channel: None,
+                    sequence: None,
+                    language: None,
+                    is_edited: false,
+                    highlighted: false,
+                },
+            )
+            .await?;
+        }
+
+        // 金額が0またはNULLの通常チャットは含まない
+        let superchats = get_superchats_by_session(&pool, &session_id, 10, 0).await?;
+        let amounts: Vec<_> = superchats.iter().map(|m| m.amount).collect();
+        assert_eq!(amounts, vec![Some(1.5), Some(3.0)]);
+        let page = get_superchats_by_session(&pool, &session_id, 1, 1).await?;
+        assert_eq!(page[0].amount, Some(3.0));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_message_highlight(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let session_id = Uuid::new_v4().to_string();
+        create_session(&pool, &session_id).await?;
+        let message = Message {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            display_name: "viewer".to_string(),
+            content: "後で見返したい".to_string(),
+            amount: None,
+            coin: None,
+            tx_hash: None,
+            wallet_address: None,
+            session_id: Some(session_id.clone()),
+            channel: None,
+            sequence: None,
+            language: None,
+            is_edited: false,
+            highlighted: false,
+        };
+        save_message_db(&pool, &message).await?;
+        assert_eq!(
+            get_message_highlight(&pool, &message.id).await?,
+            Some(false)
+        );
+
+        assert!(set_message_highlight(&pool, &message.id, true).await?);
+        let highlighted = get_highlighted_messages(&pool, Some(&session_id), 10, 0).await?;
+        assert_eq!(highlighted.len(), 1);
+        assert!(highlighted[0].highlighted);
+
+        // 存在しないメッセージは更新しない
+        assert!(!set_message_highlight(&pool, "unknown", true).await?);
+        assert_eq!(get_message_highlight(&pool, "unknown").await?, None);
 
         Ok(())
     }
 
-    /// `end_session`関数のテスト
+    /// `import_session`関数のテスト
     #[sqlx::test]
-    async fn test_end_session(pool: SqlitePool) -> Result<(), SqlxError> {
-        // テスト用DBのセットアップ
+    async fn test_import_session(pool: SqlitePool) -> Result<(), SqlxError> {
         sqlx::query(CREATE_SESSIONS_TABLE_SQL)
             .execute(&pool)
             .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
 
-        // テスト用のセッションIDを生成
         let session_id = Uuid::new_v4().to_string();
-
-        // セッション作成
         create_session(&pool, &session_id).await?;
+        let existing = Message {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            display_name: "viewer".to_string(),
+            content: "元のメッセージ".to_string(),
+            amount: Some(0.0),
+            coin: None,
+            tx_hash: None,
+            wallet_address: None,
+            session_id: Some(session_id.clone()),
+            channel: None,
+            sequence: None,
+            language: None,
+            is_edited: false,
+            highlighted: false,
+        };
+        save_message_db(&pool, &existing).await?;
+        let other_session_id = Uuid::new_v4().to_string();
+        create_session(&pool, &other_session_id).await?;
+        let other = Message {
+            id: Uuid::new_v4().to_string(),
+            content: "別の配信のメッセージ".to_string(),
+            session_id: Some(other_session_id.clone()),
+            ..existing.clone()
+        };
+        save_message_db(&pool, &other).await?;
 
-        // セッション終了
-        end_session(&pool, &session_id).await?;
+        let mut session = get_session(&pool, &session_id).await?.unwrap();
+        session.title = Some("移行した配信".to_string());
+        let messages = vec![
+            Message {
+                content: "移行したメッセージ".to_string(),
+                ..existing.clone()
+            },
+            Message {
+                id: Uuid::new_v4().to_string(),
+                ..existing.clone()
+            },
+            Message {
+                content: "移行したメッセージ".to_string(),
+                ..other.clone()
+            },
+        ];
 
-        // セッションが正しく更新されたか確認
-        let session: Session = sqlx::query_as::<_, Session>("SELECT * FROM sessions WHERE id = ?") // テーブル名を sessions に変更
-            .bind(&session_id)
-            .fetch_one(&pool)
+        // スキップの場合は既存のセッション・メッセージを残し、衝突しないメッセージのみ追加する
+        assert_eq!(
+            import_session(&pool, &session, &messages, false).await?,
+            (false, 1)
+        );
+        assert_eq!(get_session(&pool, &session_id).await?.unwrap().title, None);
+        let restored = get_all_messages_by_session_id(&pool, &session_id).await?;
+        assert_eq!(restored.len(), 2);
+        assert!(restored.iter().any(|m| m.content == "元のメッセージ"));
+
+        // 上書きの場合はファイルの内容で置き換える
+        assert_eq!(
+            import_session(&pool, &session, &messages, true).await?,
+            (true, 2)
+        );
+        assert_eq!(
+            get_session(&pool, &session_id)
+                .await?
+                .unwrap()
+                .title
+                .as_deref(),
+            Some("移行した配信")
+        );
+        let restored = get_all_messages_by_session_id(&pool, &session_id).await?;
+        assert_eq!(restored.len(), 2);
+        assert!(restored.iter().all(|m| m.content != "元のメッセージ"));
+        // 別のセッションに属するメッセージは移動・上書きしない
+        let others = get_all_messages_by_session_id(&pool, &other_session_id).await?;
+        assert_eq!(others.len(), 1);
+        assert_eq!(others[0].content, "別の配信のメッセージ");
+
+        Ok(())
+    }
+
+    /// 差分バックアップの取得と適用のテスト
+    #[sqlx::test]
+    async fn test_incremental_backup(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
             .await?;
+        sqlx::query(CREATE_BACKUPS_TABLE_SQL).execute(&pool).await?;
 
-        assert_eq!(session.id, session_id);
-        assert!(session.ended_at.is_some());
+        let session_id = Uuid::new_v4().to_string();
+        create_session(&pool, &session_id).await?;
+        // 全て同じ時刻のメッセージでも、シーケンス番号を基準に欠損・重複なく取得する
+        let timestamp = Utc::now();
+        let message = |content: &str| Message {
+            id: Uuid::new_v4().to_string(),
+            timestamp,
+            display_name: "viewer".to_string(),
+            content: content.to_string(),
+            amount: Some(0.0),
+            coin: None,
+            tx_hash: None,
+            wallet_address: None,
+            session_id: Some(session_id.clone()),
+            channel: None,
+            sequence: None,
+            language: None,
+            is_edited: false,
+            highlighted: false,
+        };
+        for content in ["1件目", "2件目"] {
+            save_message_db(&pool, &message(content)).await?;
+        }
+
+        assert_eq!(get_last_backup_until(&pool).await?, None);
+        let full = export_incremental(&pool, None).await?;
+        assert_eq!(full.len(), 2);
+        let until_sequence = full.iter().filter_map(|msg| msg.sequence).max();
+        record_backup(&pool, "full.json", None, timestamp, until_sequence, 2).await?;
+
+        save_message_db(&pool, &message("3件目")).await?;
+        let (_, since_sequence) = get_last_backup_until(&pool).await?.unwrap();
+        assert_eq!(since_sequence, until_sequence);
+        let diff = export_incremental(&pool, since_sequence).await?;
+        let contents: Vec<_> = diff.iter().map(|msg| msg.content.as_str()).collect();
+        assert_eq!(contents, vec!["3件目"]);
+        let sessions =
+            get_sessions_for_backup(&pool, Some(timestamp), Utc::now(), since_sequence).await?;
+        assert_eq!(sessions.len(), 1);
+        // 時刻で指定した場合は、その時刻より前のメッセージの最大のシーケンス番号から取得する
+        assert_eq!(max_sequence_before(&pool, timestamp).await?, 0);
+
+        // 適用時は既存のメッセージをスキップし、追加分に続きの番号を採番する
+        let new_message = message("4件目");
+        let applied =
+            import_backup(&pool, &sessions, &[full[0].clone(), new_message.clone()]).await?;
+        assert_eq!(applied, 1);
+        let restored = get_all_messages_by_session_id(&pool, &session_id).await?;
+        let sequences: Vec<_> = restored.iter().map(|msg| msg.sequence).collect();
+        assert_eq!(sequences, vec![Some(1), Some(2), Some(3), Some(5)]);
+        assert_eq!(restored[3].id, new_message.id);
 
         Ok(())
     }
 
-    /// `save_message_db`関数のテスト
+    /// シーケンス番号の重複の解消と採番のテスト
     #[sqlx::test]
-    async fn test_save_message_db(pool: SqlitePool) -> Result<(), SqlxError> {
-        // テスト用DBのセットアップ
+    async fn test_message_sequence(pool: SqlitePool) -> Result<(), SqlxError> {
         sqlx::query(CREATE_SESSIONS_TABLE_SQL)
             .execute(&pool)
             .await?;
@@ -451,48 +4033,126 @@ mod tests {
             .execute(&pool)
             .await?;
 
-        // テスト用のセッションを作成
-        let session_id = uuid::Uuid::new_v4().to_string();
+        let session_id = Uuid::new_v4().to_string();
         create_session(&pool, &session_id).await?;
+        let message = |content: &str| Message {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            display_name: "viewer".to_string(),
+            content: content.to_string(),
+            amount: Some(0.0),
+            coin: None,
+            tx_hash: None,
+            wallet_address: None,
+            session_id: Some(session_id.clone()),
+            channel: None,
+            sequence: None,
+            language: None,
+            is_edited: false,
+            highlighted: false,
+        };
 
-        // テスト用のメッセージを作成
+        // 一意インデックスの作成前に番号が重複したメッセージを用意する
+        for content in ["1件目", "2件目"] {
+            save_message_db(&pool, &message(content)).await?;
+        }
+        sqlx::query("UPDATE messages SET sequence = 1")
+            .execute(&pool)
+            .await?;
+
+        assert_eq!(backfill_message_sequence(&pool).await?, 1);
+        ensure_sequence_indexes(&pool).await?;
+        let sequences: Vec<(i64,)> =
+            sqlx::query_as("SELECT sequence FROM messages ORDER BY sequence")
+                .fetch_all(&pool)
+                .await?;
+        assert_eq!(sequences, vec![(1,), (2,)]);
+
+        // 別のデータベースで採番された番号を持つメッセージもインポートでき、続きの番号で採番される
+        let imported = Message {
+            sequence: Some(1),
+            ..message("インポート")
+        };
+        let session = get_session(&pool, &session_id).await?.unwrap();
+        assert_eq!(
+            import_session(&pool, &session, &[imported], false).await?,
+            (false, 1)
+        );
+        save_message_db(&pool, &message("3件目")).await?;
+        let sequences: Vec<Option<i64>> = get_all_messages_by_session_id(&pool, &session_id)
+            .await?
+            .into_iter()
+            .map(|msg| msg.sequence)
+            .collect();
+        assert_eq!(sequences, vec![Some(1), Some(2), Some(3), Some(4)]);
+
+        // 同じセッションで同じ番号は保存できない
+        assert!(
+            sqlx::query("UPDATE messages SET sequence = 1 WHERE sequence = 2")
+                .execute(&pool)
+                .await
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    /// メッセージの編集と編集履歴の記録のテスト
+    #[sqlx::test]
+    async fn test_record_message_edit(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::query(CREATE_MESSAGES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::raw_sql(CREATE_MESSAGE_EDITS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let session_id = Uuid::new_v4().to_string();
+        create_session(&pool, &session_id).await?;
         let message = Message {
-            id: uuid::Uuid::new_v4().to_string(),
+            id: Uuid::new_v4().to_string(),
             timestamp: Utc::now(),
-            display_name: "テストユーザー".to_string(),
-            content: "これはテストメッセージです".to_string(),
-            amount: Some(10.5),
-            coin: Some("SUI".to_string()),
-            tx_hash: Some("0x123456789abcdef".to_string()),
-            wallet_address: Some("0xabcdef123456789".to_string()),
+            display_name: "viewer".to_string(),
+            content: "こんにちわ".to_string(),
+            amount: Some(0.0),
+            coin: None,
+            tx_hash: None,
+            wallet_address: None,
             session_id: Some(session_id.clone()),
+            channel: None,
+            sequence: None,
+            language: None,
+            is_edited: false,
+            highlighted: false,
         };
-
-        // メッセージを保存
         save_message_db(&pool, &message).await?;
 
-        // メッセージがDBに正しく保存されたか確認
-        let saved_message: Message =
-            sqlx::query_as::<_, Message>("SELECT * FROM messages WHERE id = ?") // テーブル名を messages に変更
-                .bind(&message.id)
-                .fetch_one(&pool)
-                .await?;
+        record_message_edit(&pool, &message.id, "こんにちは", EDITOR_STREAMER).await?;
+        record_message_edit(&pool, &message.id, "こんにちは！", EDITOR_STREAMER).await?;
 
-        assert_eq!(saved_message.id, message.id);
-        assert_eq!(saved_message.display_name, message.display_name);
-        assert_eq!(saved_message.content, message.content);
-        assert_eq!(saved_message.amount, message.amount);
-        assert_eq!(saved_message.tx_hash, message.tx_hash);
-        assert_eq!(saved_message.wallet_address, message.wallet_address);
-        assert_eq!(saved_message.session_id, message.session_id);
+        // 編集前後の内容が古い順に記録され、メッセージは編集済みになる
+        let history = get_message_edit_history(&pool, &message.id).await?;
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].old_content, "こんにちわ");
+        assert_eq!(history[1].new_content, "こんにちは！");
+        let saved = get_all_messages_by_session_id(&pool, &session_id).await?;
+        assert!(saved[0].is_edited);
+        assert_eq!(saved[0].content, "こんにちは！");
+
+        // 存在しないメッセージは編集できない
+        assert!(matches!(
+            record_message_edit(&pool, "missing", "x", EDITOR_STREAMER).await,
+            Err(SqlxError::RowNotFound)
+        ));
 
         Ok(())
     }
 
-    /// `fetch_messages`関数のテスト
     #[sqlx::test]
-    async fn test_fetch_messages(pool: SqlitePool) -> Result<(), SqlxError> {
-        // テスト用DBのセットアップ
+    async fn test_search_messages(pool: SqlitePool) -> Result<(), SqlxError> {
         sqlx::query(CREATE_SESSIONS_TABLE_SQL)
             .execute(&pool)
             .await?;
@@ -500,85 +4160,246 @@ mod tests {
             .execute(&pool)
             .await?;
 
-        // テスト用のセッションIDを生成
         let session_id = Uuid::new_v4().to_string();
+        let other_session_id = Uuid::new_v4().to_string();
+        create_session(&pool, &session_id).await?;
+        create_session(&pool, &other_session_id).await?;
 
-        // セッション作成
+        let message = |display_name: &str, content: &str, amount: f64, session_id: &str| Message {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            display_name: display_name.to_string(),
+            content: content.to_string(),
+            amount: Some(amount),
+            coin: None,
+            tx_hash: None,
+            wallet_address: None,
+            session_id: Some(session_id.to_string()),
+            channel: None,
+            sequence: None,
+            language: None,
+            is_edited: false,
+            highlighted: false,
+        };
+        save_message_db(&pool, &message("Alice", "hello", 0.0, &session_id)).await?;
+        save_message_db(&pool, &message("bob", "Thanks ALICE!", 5.0, &session_id)).await?;
+        save_message_db(&pool, &message("carol", "alice?", 1.0, &other_session_id)).await?;
+        save_message_db(&pool, &message("dave", "100% ok", 0.0, &session_id)).await?;
+
+        // 本文と表示名の両方を大文字小文字を区別せずに検索する
+        assert_eq!(search_messages(&pool, "alice", 10).await?.len(), 3);
+        let filtered =
+            search_messages_with_options(&pool, "alice", 10, Some(&session_id), true).await?;
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].display_name, "bob");
+        // `%` はワイルドカードではなく文字として扱う
+        assert_eq!(search_messages(&pool, "0%", 10).await?.len(), 1);
+        assert!(search_messages(&pool, "%x", 10).await?.is_empty());
+
+        Ok(())
+    }
+
+    /// 接続ログの記録・取得のテスト
+    #[sqlx::test]
+    async fn test_connection_logs(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::raw_sql(CREATE_CONNECTIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let session_id = Uuid::new_v4().to_string();
         create_session(&pool, &session_id).await?;
 
-        // テスト用のメッセージを5件作成して保存
-        let mut test_messages = Vec::new();
-        for i in 1..=5 {
-            let message = Message {
-                id: Uuid::new_v4().to_string(),
-                timestamp: Utc::now(),
-                display_name: format!("テストユーザー{}", i),
-                content: format!("テストメッセージ本文{}", i),
-                amount: if i % 2 == 0 {
-                    Some(i as f64 * 10.0)
-                } else {
-                    None
-                },
-                coin: if i % 2 == 0 {
-                    Some("SUI".to_string())
-                } else {
-                    None
-                },
-                tx_hash: if i % 2 == 0 {
-                    Some(format!("tx_hash_{}", i))
-                } else {
-                    None
-                },
-                wallet_address: if i % 2 == 0 {
-                    Some(format!("wallet_{}", i))
-                } else {
-                    None
-                },
-                session_id: Some(session_id.clone()),
-            };
-            test_messages.push(message.clone());
-            save_message_db(&pool, &message).await?;
-        }
+        log_connection(
+            &pool,
+            "a",
+            "1.2.3.4",
+            Some(&session_id),
+            "2024-01-01T00:00:00+00:00",
+        )
+        .await?;
+        log_disconnection(
+            &pool,
+            "a",
+            "1.2.3.4",
+            Some(&session_id),
+            "2024-01-01T00:00:00+00:00",
+            Utc::now(),
+            3,
+        )
+        .await?;
+        // 切断の記録が先に書き込まれても、後からの接続の記録で上書きしない
+        log_disconnection(
+            &pool,
+            "b",
+            "5.6.7.8",
+            None,
+            "2024-01-01T00:01:00+00:00",
+            Utc::now(),
+            0,
+        )
+        .await?;
+        log_connection(&pool, "b", "5.6.7.8", None, "2024-01-01T00:01:00+00:00").await?;
 
-        // メッセージを取得し、結果を検証
+        let logs = get_connection_logs(&pool, None, 10).await?;
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].client_id, "b");
+        assert!(logs.iter().all(|log| log.disconnected_at.is_some()));
 
-        // 全件取得 (limit=10, offset=0)
-        let all_messages = fetch_messages(&pool, 10, 0).await?;
+        let logs = get_connection_logs(&pool, Some(&session_id), 10).await?;
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].ip, "1.2.3.4");
+
+        let summary = get_client_activity_summary(&pool, &session_id).await?;
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].messages_sent, 3);
+
+        Ok(())
+    }
+
+    /// 保持期間を過ぎた接続ログの削除のテスト
+    #[sqlx::test]
+    async fn test_prune_connection_logs(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::raw_sql(CREATE_CONNECTIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let old = (Utc::now() - chrono::Duration::days(40)).to_rfc3339();
+        let recent = (Utc::now() - chrono::Duration::days(5)).to_rfc3339();
+        log_disconnection(&pool, "old", "1.2.3.4", None, &old, Utc::now(), 0).await?;
+        log_disconnection(&pool, "recent", "1.2.3.4", None, &recent, Utc::now(), 0).await?;
+        // 接続中のクライアントは削除しない
+        log_connection(&pool, "connected", "5.6.7.8", None, &old).await?;
+
+        assert_eq!(prune_connection_logs(&pool, 30).await?, 1);
+        let mut remaining: Vec<String> = get_connection_logs(&pool, None, 10)
+            .await?
+            .into_iter()
+            .map(|log| log.client_id)
+            .collect();
+        remaining.sort();
+        assert_eq!(remaining, vec!["connected", "recent"]);
+
+        Ok(())
+    }
+
+    /// 設定値の保存・取得のテスト
+    #[sqlx::test]
+    async fn test_settings(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::query(CREATE_SETTINGS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        assert_eq!(get_setting(&pool, "max_connections").await?, None);
+        set_setting(&pool, "max_connections", "50").await?;
+        set_setting(&pool, "max_connections", "80").await?;
         assert_eq!(
-            all_messages.len(),
-            5,
-            "全件取得で5件のメッセージが取得されるべき"
+            get_setting(&pool, "max_connections").await?,
+            Some("80".to_string())
         );
 
-        // 制限付き取得 (limit=3, offset=0)
-        let limited_messages = fetch_messages(&pool, 3, 0).await?;
+        // 複数の設定値をまとめて保存する
+        let settings = BTreeMap::from([
+            ("max_connections", "100".to_string()),
+            ("require_wallet", "true".to_string()),
+        ]);
+        set_settings(&pool, &settings).await?;
         assert_eq!(
-            limited_messages.len(),
-            3,
-            "制限付き取得で3件のメッセージが取得されるべき"
+            get_setting(&pool, "max_connections").await?,
+            Some("100".to_string())
         );
-
-        // オフセット付き取得 (limit=10, offset=2)
-        let offset_messages = fetch_messages(&pool, 10, 2).await?;
         assert_eq!(
-            offset_messages.len(),
-            3,
-            "オフセット付き取得で3件のメッセージが取得されるべき"
+            get_setting(&pool, "require_wallet").await?,
+            Some("true".to_string())
         );
 
-        // 範囲外のオフセット (limit=10, offset=10)
-        let out_of_range = fetch_messages(&pool, 10, 10).await?;
+        Ok(())
+    }
+
+    /// 視聴者数の推移の保存・取得のテスト
+    #[sqlx::test]
+    async fn test_viewer_count_history(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::query(CREATE_SESSIONS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+        sqlx::raw_sql(CREATE_VIEWER_COUNT_SAMPLES_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let session_id = Uuid::new_v4().to_string();
+        create_session(&pool, &session_id).await?;
+
+        let start = Utc::now();
+        let history = vec![(start, 3), (start + chrono::Duration::seconds(10), 8)];
         assert_eq!(
-            out_of_range.len(),
-            0,
-            "範囲外のオフセットで0件が取得されるべき"
+            save_viewer_count_history(&pool, &session_id, &history).await?,
+            2
         );
 
-        // 負のlimitとoffsetの処理を確認 (安全な値に変換されるはず)
-        let with_negative = fetch_messages(&pool, -1, -5).await?;
-        assert!(!with_negative.is_empty(), "負の値が安全に処理されるべき");
+        let samples = get_viewer_count_history(&pool, &session_id).await?;
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[1].viewer_count, 8);
+        assert!(get_viewer_count_history(&pool, "unknown").await?.is_empty());
+
+        Ok(())
+    }
+
+    /// 配信しなかったスーパーチャットの記録のテスト
+    #[sqlx::test]
+    async fn test_rejected_superchats(pool: SqlitePool) -> Result<(), SqlxError> {
+        sqlx::raw_sql(CREATE_REJECTED_SUPERCHATS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let rejected = |id: &str, tx_hash: &str, session_id: Option<&str>| RejectedSuperchat {
+            id: id.to_string(),
+            session_id: session_id.map(str::to_string),
+            tx_hash: tx_hash.to_string(),
+            wallet_address: "0xabc".to_string(),
+            display_name: "viewer".to_string(),
+            amount: 1.5,
+            coin: "USDC".to_string(),
+            content: "hello".to_string(),
+            reason: crate::db_models::REJECT_REASON_COIN_NOT_ACCEPTED.to_string(),
+            verified: false,
+            rejected_at: Utc::now().to_rfc3339(),
+        };
+        assert!(record_rejected_superchat(&pool, &rejected("a", "tx1", Some("s1"))).await?);
+        // 同じトランザクションは重複して記録しない
+        assert!(!record_rejected_superchat(&pool, &rejected("b", "tx1", Some("s1"))).await?);
+        assert!(record_rejected_superchat(&pool, &rejected("c", "tx2", None)).await?);
+
+        let all = get_rejected_superchats(&pool, None, 100).await?;
+        assert_eq!(all.len(), 2);
+        let in_session = get_rejected_superchats(&pool, Some("s1"), 100).await?;
+        assert_eq!(in_session.len(), 1);
+        assert_eq!(in_session[0].id, "a");
+        assert_eq!(in_session[0].coin, "USDC");
 
-        println!("fetch_messagesのテスト完了");
         Ok(())
     }
+
+    /// ストリーク計算（日付の連続性判定）のテスト
+    #[test]
+    fn test_calculate_streak() {
+        let date = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+
+        assert_eq!(calculate_streak(vec![]), 0);
+        assert_eq!(calculate_streak(vec![date(5)]), 1);
+        // 同じ日の複数セッションは1日として数える
+        assert_eq!(
+            calculate_streak(vec![date(3), date(5), date(4), date(5)]),
+            3
+        );
+        // 1日空くとストリークはリセットされる
+        assert_eq!(
+            calculate_streak(vec![date(1), date(2), date(4), date(5)]),
+            2
+        );
+    }
 }
@@ -0,0 +1,273 @@
+//! データベース接続のヘルスチェック・自動再接続モジュール
+//!
+//! 配信中に定期的に接続プールの状態を確認し、応答がない場合は
+//! データベースを再初期化して `AppState` の接続プールを置き換えます。
+//! 再接続中に受信したメッセージは待機キューに退避し、復旧後に保存します。
+
+use crate::database;
+use crate::db_models::Message;
+use crate::state::AppState;
+use serde::Serialize;
+use sqlx::Error as SqlxError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+/// ヘルスチェックの実行間隔
+pub const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 接続状態の変化を通知するTauriイベント名
+pub const DB_CONNECTION_STATUS_EVENT: &str = "db_connection_status";
+
+/// ヘルスチェックタスクが起動済みかどうか（多重起動防止用）
+static HEALTH_CHECK_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// ## データベース接続状態イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct DbConnectionStatusPayload {
+    /// 接続状態 ("reconnecting" / "recovered" / "failed")
+    pub status: &'static str,
+    /// 再接続に失敗した場合のエラーメッセージ
+    pub error: Option<String>,
+    /// 復旧後に保存した待機メッセージの件数
+    pub flushed_messages: usize,
+}
+
+/// タスク終了時（ランタイム停止によるキャンセルを含む）に起動フラグを戻すガード
+struct RunningGuard;
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        HEALTH_CHECK_RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// ## 定期ヘルスチェックタスクを起動する
+///
+/// WebSocketサーバーが停止するまで `HEALTH_CHECK_INTERVAL` ごとに接続プールを確認し、
+/// 応答がない場合は再接続を試みます。既にタスクが起動している場合は何もしません。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+pub fn spawn_health_check(app_handle: tauri::AppHandle) {
+    if HEALTH_CHECK_RUNNING
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        println!("データベースのヘルスチェックは既に起動しています");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let _guard = RunningGuard;
+        println!(
+            "データベースのヘルスチェックを開始しました（間隔: {}秒）",
+            HEALTH_CHECK_INTERVAL.as_secs()
+        );
+
+        loop {
+            tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+
+            let app_state = app_handle.state::<AppState>();
+            let server_running = app_state
+                .server_handle
+                .lock()
+                .map(|guard| guard.is_some())
+                .unwrap_or(false);
+            if !server_running {
+                println!("サーバーが停止したため、データベースのヘルスチェックを終了します");
+                break;
+            }
+
+            let pool = match app_state.db_pool.lock() {
+                Ok(guard) => guard.clone(),
+                Err(e) => {
+                    eprintln!("データベースプールのロックに失敗しました: {}", e);
+                    continue;
+                }
+            };
+
+            let healthy = match &pool {
+                Some(pool) => database::check_pool_health(pool).await,
+                None => false,
+            };
+            if !healthy || is_reconnecting(&app_handle) {
+                reconnect(&app_handle).await;
+            }
+        }
+    });
+}
+
+/// ## データベースに再接続する
+///
+/// データベースを再初期化して接続プールを置き換え、古いプールを閉じたうえで
+/// 待機キューのメッセージを保存します。結果は `db_connection_status` イベントで通知します。
+/// 失敗した場合は再接続中の状態を維持し、次回のヘルスチェックで再試行します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+async fn reconnect(app_handle: &tauri::AppHandle) {
+    let app_state = app_handle.state::<AppState>();
+    set_reconnecting(app_handle, true);
+    eprintln!("データベース接続の異常を検出しました。再接続を試みます...");
+    emit_status(app_handle, "reconnecting", None, 0);
+
+    let new_pool = match crate::initialize_database(app_handle).await {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("データベースへの再接続に失敗しました: {}", e);
+            emit_status(app_handle, "failed", Some(e), 0);
+            return;
+        }
+    };
+
+    let old_pool = match app_state.db_pool.lock() {
+        Ok(mut guard) => guard.replace(new_pool.clone()),
+        Err(e) => {
+            let error = format!("データベースプールのロックに失敗しました: {}", e);
+            eprintln!("{}", error);
+            emit_status(app_handle, "failed", Some(error), 0);
+            return;
+        }
+    };
+    if let Some(old_pool) = old_pool {
+        old_pool.close().await;
+    }
+
+    set_reconnecting(app_handle, false);
+    crate::settings::flush_pending_settings(&app_state);
+    let flushed = flush_pending_messages(app_handle, &new_pool).await;
+    println!(
+        "データベースへの再接続に成功しました（待機メッセージ {} 件を保存）",
+        flushed
+    );
+    emit_status(app_handle, "recovered", None, flushed);
+}
+
+/// ## 待機キューのメッセージを保存する
+///
+/// 保存に失敗したメッセージはキューに戻します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `pool`: 再接続後の接続プール
+///
+/// ### Returns
+/// - `usize`: 保存に成功したメッセージの件数
+async fn flush_pending_messages(app_handle: &tauri::AppHandle, pool: &sqlx::SqlitePool) -> usize {
+    let app_state = app_handle.state::<AppState>();
+    let pending = match app_state.db_pending_messages.lock() {
+        Ok(mut queue) => std::mem::take(&mut *queue),
+        Err(e) => {
+            eprintln!("待機メッセージキューのロックに失敗しました: {}", e);
+            return 0;
+        }
+    };
+
+    let mut flushed = 0;
+    let mut failed = Vec::new();
+    for message in pending {
+        match database::save_message_db(pool, &message).await {
+            Ok(_) => flushed += 1,
+            Err(e) => {
+                eprintln!(
+                    "待機メッセージの保存に失敗しました: ID={}, エラー={}",
+                    message.id, e
+                );
+                failed.push(message);
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        if let Ok(mut queue) = app_state.db_pending_messages.lock() {
+            failed.append(&mut queue);
+            *queue = failed;
+        }
+    }
+    flushed
+}
+
+/// ## 再接続処理中かどうかを取得する
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+///
+/// ### Returns
+/// - `bool`: 再接続中の場合は `true`
+pub fn is_reconnecting(app_handle: &tauri::AppHandle) -> bool {
+    app_handle
+        .state::<AppState>()
+        .db_reconnecting
+        .lock()
+        .map(|guard| *guard)
+        .unwrap_or(false)
+}
+
+/// ## メッセージを再接続後の保存待ちキューに追加する
+///
+/// キューは再接続後にのみ保存されるため、再接続中フラグを立てて次回のヘルスチェックで再接続させます。
+/// 以降のメッセージも復旧するまでキューに退避されます。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `message`: 保存できなかったメッセージ
+pub fn queue_pending_message(app_handle: &tauri::AppHandle, message: Message) {
+    match app_handle.state::<AppState>().db_pending_messages.lock() {
+        Ok(mut queue) => {
+            println!(
+                "データベース再接続後に保存するためメッセージを退避しました: ID={}",
+                message.id
+            );
+            queue.push(message);
+        }
+        Err(e) => eprintln!("待機メッセージキューのロックに失敗しました: {}", e),
+    }
+    set_reconnecting(app_handle, true);
+}
+
+/// ## 接続断に起因するエラーかどうかを判定する
+///
+/// 再接続によって回復が見込めるエラーの場合に `true` を返します。
+///
+/// ### Arguments
+/// - `error`: 保存時に発生したエラー
+///
+/// ### Returns
+/// - `bool`: 接続断に起因するエラーの場合は `true`
+pub fn is_connection_error(error: &SqlxError) -> bool {
+    matches!(
+        error,
+        SqlxError::Io(_)
+            | SqlxError::PoolTimedOut
+            | SqlxError::PoolClosed
+            | SqlxError::WorkerCrashed
+    )
+}
+
+/// 再接続中フラグを更新する
+fn set_reconnecting(app_handle: &tauri::AppHandle, reconnecting: bool) {
+    if let Ok(mut guard) = app_handle.state::<AppState>().db_reconnecting.lock() {
+        *guard = reconnecting;
+    }
+}
+
+/// `db_connection_status` イベントを発行する
+fn emit_status(
+    app_handle: &tauri::AppHandle,
+    status: &'static str,
+    error: Option<String>,
+    flushed_messages: usize,
+) {
+    let payload = DbConnectionStatusPayload {
+        status,
+        error,
+        flushed_messages,
+    };
+    if let Err(e) = app_handle.emit(DB_CONNECTION_STATUS_EVENT, &payload) {
+        eprintln!(
+            "{} イベントの発火に失敗しました: {}",
+            DB_CONNECTION_STATUS_EVENT, e
+        );
+    }
+}
@@ -4,6 +4,7 @@
 
 use chrono::{DateTime, Utc};
 use sqlx::FromRow;
+use std::collections::BTreeMap;
 
 /// メッセージ情報を表す構造体
 ///
@@ -19,6 +20,11 @@ use sqlx::FromRow;
 /// * `tx_hash` - トランザクションハッシュ（スーパーチャット時）
 /// * `wallet_address` - 送信者のウォレットアドレス（スーパーチャット時）
 /// * `session_id` - 配信セッションの識別子
+/// * `channel` - 投稿先チャンネル（未設定の場合は "general" として扱う）
+/// * `sequence` - 受信順のシーケンス番号（保存時にDB側で採番、同一時刻のメッセージの順序付けに使用）
+/// * `language` - 判定されたメッセージの言語（ISO 639-1、短いメッセージなど判定できない場合はNone）
+/// * `is_edited` - 送信後に編集されたかどうか
+/// * `highlighted` - 配信者が後で見返すためにハイライトしたかどうか
 #[derive(FromRow, Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Message {
     pub id: String,
@@ -32,6 +38,170 @@ pub struct Message {
     pub tx_hash: Option<String>,
     pub wallet_address: Option<String>,
     pub session_id: Option<String>, // どの配信セッションのメッセージかを示すID
+    #[sqlx(default)]
+    #[serde(default)]
+    pub channel: Option<String>, // 投稿先チャンネル（カラムが無い古いクエリ結果ではNone）
+    #[sqlx(default)]
+    #[serde(default)]
+    pub sequence: Option<i64>, // 受信順のシーケンス番号（未保存のメッセージではNone）
+    #[sqlx(default)]
+    #[serde(default)]
+    pub language: Option<String>, // 判定されたメッセージの言語コード
+    #[sqlx(default)]
+    #[serde(default)]
+    pub is_edited: bool, // 送信後に編集されたかどうか（カラムが無い古いクエリ結果ではfalse）
+    #[sqlx(default)]
+    #[serde(default)]
+    pub highlighted: bool, // 配信者がハイライトしたかどうか（カラムが無い古いクエリ結果ではfalse）
+}
+
+/// メッセージの編集履歴を表す構造体
+///
+/// 編集のたびに編集前後の内容を記録し、モデレーション時の監査に使用する
+///
+/// # フィールド
+/// * `id` - 編集履歴の識別子（自動採番）
+/// * `message_id` - 編集されたメッセージのID
+/// * `old_content` - 編集前の内容
+/// * `new_content` - 編集後の内容
+/// * `edited_at` - 編集時刻（ISO 8601形式の文字列）
+/// * `editor` - 編集者（"streamer"、視聴者のウォレットアドレスなど）
+#[derive(FromRow, Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MessageEdit {
+    pub id: i64,
+    pub message_id: String,
+    pub old_content: String,
+    pub new_content: String,
+    pub edited_at: String, // ISO 8601形式の文字列
+    pub editor: String,
+}
+
+/// WebSocket接続の監査ログを表す構造体
+///
+/// 誰がいつ接続・切断したかを記録し、トラブル時の確認に使用する
+///
+/// # フィールド
+/// * `id` - 接続ログの識別子（自動採番）
+/// * `client_id` - 接続クライアントのID
+/// * `ip` - 接続元のIPアドレス
+/// * `session_id` - 接続時の配信セッションID（セッションがない場合はNone）
+/// * `connected_at` - 接続時刻（ISO 8601形式の文字列）
+/// * `disconnected_at` - 切断時刻（ISO 8601形式の文字列、接続中はNone）
+/// * `messages_sent` - 切断時点までに送信したメッセージ数（接続中は0）
+#[derive(FromRow, Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ConnectionLog {
+    pub id: i64,
+    pub client_id: String,
+    pub ip: String,
+    pub session_id: Option<String>,
+    pub connected_at: String,            // ISO 8601形式の文字列
+    pub disconnected_at: Option<String>, // ISO 8601形式の文字列
+    #[sqlx(default)]
+    #[serde(default)]
+    pub messages_sent: i64,
+}
+
+/// 受け付けていないコインのため配信しなかったスーパーチャットの理由
+pub const REJECT_REASON_COIN_NOT_ACCEPTED: &str = "coin_not_accepted";
+
+/// NGワードを含むため配信しなかったスーパーチャットの理由
+pub const REJECT_REASON_BANNED_WORD: &str = "banned_word";
+
+/// 文字数制限を超えたため配信しなかったスーパーチャットの理由
+pub const REJECT_REASON_OVER_LIMIT: &str = "over_limit";
+
+/// 配信しなかったスーパーチャットを表す構造体
+///
+/// 送金済みの可能性があるスーパーチャットを配信しなかった場合に、後から送金と照合できるよう記録する
+///
+/// # フィールド
+/// * `id` - メッセージID
+/// * `session_id` - 受信時の配信セッションID（セッションがない場合はNone）
+/// * `tx_hash` - トランザクションハッシュ
+/// * `wallet_address` - 送金者のウォレットアドレス
+/// * `display_name` - 表示名
+/// * `amount` - 金額（表示単位）
+/// * `coin` - 通貨シンボル
+/// * `content` - メッセージ内容
+/// * `reason` - 配信しなかった理由（`REJECT_REASON_*`）
+/// * `verified` - チェーン上で着金を確認済みかどうか
+/// * `rejected_at` - 記録時刻（ISO 8601形式の文字列）
+#[derive(FromRow, Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RejectedSuperchat {
+    pub id: String,
+    pub session_id: Option<String>,
+    pub tx_hash: String,
+    pub wallet_address: String,
+    pub display_name: String,
+    pub amount: f64,
+    pub coin: String,
+    pub content: String,
+    pub reason: String,
+    pub verified: bool,
+    pub rejected_at: String, // ISO 8601形式の文字列
+}
+
+impl RejectedSuperchat {
+    /// スーパーチャットから配信しなかった記録を作成する
+    ///
+    /// # 引数
+    /// * `superchat_msg` - 配信しなかったスーパーチャット
+    /// * `session_id` - 受信時の配信セッションID
+    /// * `reason` - 配信しなかった理由
+    ///
+    /// # 戻り値
+    /// * `Self` - 配信しなかったスーパーチャットの記録
+    pub fn from_superchat(
+        superchat_msg: &crate::types::SuperchatMessage,
+        session_id: Option<String>,
+        reason: &str,
+    ) -> Self {
+        Self {
+            id: superchat_msg.id.clone(),
+            session_id,
+            tx_hash: superchat_msg.superchat.tx_hash.clone(),
+            wallet_address: superchat_msg.superchat.wallet_address.clone(),
+            display_name: superchat_msg.display_name.clone(),
+            amount: superchat_msg.superchat.amount,
+            coin: superchat_msg.superchat.coin.clone(),
+            content: superchat_msg.content.clone(),
+            reason: reason.to_string(),
+            verified: superchat_msg.verified.unwrap_or(false),
+            rejected_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// 視聴者数のサンプル（サンプリング時刻と視聴者数）
+pub type ViewerCountPoint = (DateTime<Utc>, usize);
+
+/// 視聴者数の推移の1点を表す構造体
+///
+/// 配信中の視聴者数をサンプリングしたもので、盛り上がったタイミングの分析に使用する
+///
+/// # フィールド
+/// * `sampled_at` - サンプリング時刻（ISO 8601形式の文字列）
+/// * `viewer_count` - 視聴者数
+#[derive(FromRow, Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ViewerCountSample {
+    pub sampled_at: String, // ISO 8601形式の文字列
+    pub viewer_count: i64,
+}
+
+impl ViewerCountSample {
+    /// サンプリング時刻と視聴者数の組から作成する
+    ///
+    /// # 引数
+    /// * `sample` - サンプリング時刻と視聴者数
+    ///
+    /// # 戻り値
+    /// * `Self` - 視聴者数の推移の1点
+    pub fn from_sample(sample: &ViewerCountPoint) -> Self {
+        Self {
+            sampled_at: sample.0.to_rfc3339(),
+            viewer_count: sample.1 as i64,
+        }
+    }
 }
 
 /// 配信セッション情報を表す構造体
@@ -42,6 +212,7 @@ pub struct Message {
 /// * `id` - セッションの一意識別子（UUID）
 /// * `started_at` - セッション開始時刻（ISO 8601形式の文字列）
 /// * `ended_at` - セッション終了時刻（ISO 8601形式の文字列、セッション中はNone）
+/// * `title` - 配信者が付けたタイトル・メモ（未設定の場合はNone）
 /// * `created_at` - レコード作成時刻（ISO 8601形式の文字列）
 /// * `updated_at` - レコード更新時刻（ISO 8601形式の文字列）
 #[derive(FromRow, Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -49,6 +220,100 @@ pub struct Session {
     pub id: String,               // UUID
     pub started_at: String,       // ISO 8601形式の文字列
     pub ended_at: Option<String>, // ISO 8601形式の文字列
+    #[sqlx(default)]
+    #[serde(default)]
+    pub title: Option<String>, // 配信者が付けたタイトル・メモ
     pub created_at: String,       // ISO 8601形式の文字列
     pub updated_at: String,       // ISO 8601形式の文字列
 }
+
+/// メッセージ表示フィルタのプリセットを表す構造体
+///
+/// 配信者が名前を付けて保存した表示フィルタ条件を保持する
+///
+/// # フィールド
+/// * `name` - プリセット名（一意）
+/// * `superchat_only` - スーパーチャットのみを表示するかどうか
+/// * `min_amount` - この金額以上のスーパーチャットのみを表示する（未指定の場合はNone）
+/// * `keyword` - 表示名または本文に含まれるキーワード（未指定の場合はNone）
+/// * `japanese_only` - 日本語を含むメッセージのみを表示するかどうか
+/// * `created_at` - レコード作成時刻（ISO 8601形式の文字列）
+/// * `updated_at` - レコード更新時刻（ISO 8601形式の文字列）
+#[derive(FromRow, Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FilterPreset {
+    pub name: String,
+    pub superchat_only: bool,
+    pub min_amount: Option<f64>,
+    pub keyword: Option<String>,
+    pub japanese_only: bool,
+    pub created_at: String, // ISO 8601形式の文字列
+    pub updated_at: String, // ISO 8601形式の文字列
+}
+
+/// 複数の配信にわたる視聴者の累計実績を表す構造体
+///
+/// ウォレットアドレスを持つ視聴者のみが集計対象となる
+///
+/// # フィールド
+/// * `wallet_address` - 視聴者のウォレットアドレス（一意）
+/// * `display_name` - 最後に使用された表示名
+/// * `first_seen_at` - 初回のメッセージ受信時刻（ISO 8601形式の文字列）
+/// * `last_seen_at` - 最新のメッセージ受信時刻（ISO 8601形式の文字列）
+/// * `total_superchat_amount` - SUI建てのスーパーチャット累計額
+/// * `superchat_count` - スーパーチャットの累計回数
+/// * `message_count` - メッセージの累計数
+/// * `opted_out` - 集計をオプトアウトしているかどうか
+/// * `session_count` - メッセージを送信した配信（セッション）の数
+#[derive(FromRow, Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ViewerProfile {
+    pub wallet_address: String,
+    pub display_name: String,
+    pub first_seen_at: String, // ISO 8601形式の文字列
+    pub last_seen_at: String,  // ISO 8601形式の文字列
+    pub total_superchat_amount: f64,
+    pub superchat_count: i64,
+    pub message_count: i64,
+    pub opted_out: bool,
+    #[sqlx(default)]
+    #[serde(default)]
+    pub session_count: i64,
+}
+
+/// 全セッションを横断したスーパーチャット送金者のランキング項目を表す構造体
+///
+/// # フィールド
+/// * `wallet_address` - 送金者のウォレットアドレス
+/// * `display_name` - 最新のメッセージで使用された表示名
+/// * `coin` - 集計した通貨シンボル（大文字）
+/// * `total_amount` - スーパーチャットの累計額（`coin` の表示単位）
+/// * `superchat_count` - スーパーチャットの累計回数
+#[derive(FromRow, Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DonorRank {
+    pub wallet_address: String,
+    pub display_name: String,
+    pub coin: String,
+    pub total_amount: f64,
+    pub superchat_count: i64,
+}
+
+/// 通貨ごとのスーパーチャット集計を表す構造体
+///
+/// # フィールド
+/// * `total_amount` - スーパーチャットの合計金額
+/// * `superchat_count` - スーパーチャットの件数
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CoinTotal {
+    pub total_amount: f64,
+    pub superchat_count: i64,
+}
+
+/// 配信セッション単位のメッセージ集計を表す構造体
+///
+/// # フィールド
+/// * `coins` - 通貨シンボルをキーにしたスーパーチャットの集計
+/// * `chat_count` - 通常チャットの件数
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SessionTotals {
+    pub coins: BTreeMap<String, CoinTotal>,
+    pub chat_count: i64,
+}
@@ -0,0 +1,293 @@
+//! メッセージ保存のリトライモジュール
+//!
+//! 一時的なDBのロックやビジー状態でメッセージの保存に失敗した場合に、
+//! メッセージをリトライキューに積み、バックグラウンドタスクで指数バックオフしながら再保存します。
+//! `SQLITE_BUSY` などのロック競合は短い待機から指数バックオフし、長めのロックにも耐えられるよう
+//! 通常のエラーより多く再試行します。
+//! リトライ上限に達したメッセージは手動で復旧できるよう、全内容をエラーログに出力します。
+
+use crate::database;
+use crate::db_health;
+use crate::db_models::Message;
+use crate::state::AppState;
+use sqlx::Error as SqlxError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::Manager;
+
+/// 1件のメッセージあたりの最大リトライ回数（ロック競合以外のエラー）
+pub const MAX_SAVE_RETRIES: u32 = 3;
+
+/// 1件のメッセージあたりのロック競合時の最大リトライ回数（待機時間の合計は約13秒）
+pub const MAX_BUSY_RETRIES: u32 = 8;
+
+/// 指数バックオフの初回の待機時間
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// ロック競合（`SQLITE_BUSY` / `SQLITE_LOCKED`）時の指数バックオフの初回の待機時間
+const BUSY_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// SQLiteの `SQLITE_BUSY` のプライマリエラーコード
+const SQLITE_BUSY: i32 = 5;
+
+/// SQLiteの `SQLITE_LOCKED` のプライマリエラーコード
+const SQLITE_LOCKED: i32 = 6;
+
+/// リトライタスクが起動済みかどうか（多重起動防止用）
+static RETRY_WORKER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// タスク終了時（ランタイム停止によるキャンセルを含む）に起動フラグを戻すガード
+struct RunningGuard;
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        RETRY_WORKER_RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// ## 保存に失敗したメッセージをリトライキューに追加する
+///
+/// リトライタスクが起動していない場合は起動します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `message`: 保存に失敗したメッセージ
+pub fn enqueue(app_handle: &tauri::AppHandle, message: Message) {
+    match app_handle.state::<AppState>().db_retry_queue.lock() {
+        Ok(mut queue) => {
+            println!(
+                "保存に失敗したメッセージをリトライキューに追加しました: ID={}",
+                message.id
+            );
+            queue.push_back(message);
+        }
+        Err(e) => {
+            eprintln!("リトライキューのロックに失敗しました: {}", e);
+            log_unsaved_message(&message);
+            return;
+        }
+    }
+    spawn_retry_worker(app_handle.clone());
+}
+
+/// ## リトライタスクを起動する
+///
+/// キューが空になるまでメッセージを先頭から取り出して再保存します。
+/// 既にタスクが起動している場合は何もしません。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+fn spawn_retry_worker(app_handle: tauri::AppHandle) {
+    if RETRY_WORKER_RUNNING
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let _guard = RunningGuard;
+        loop {
+            let next = match app_handle.state::<AppState>().db_retry_queue.lock() {
+                Ok(mut queue) => queue.pop_front(),
+                Err(e) => {
+                    eprintln!("リトライキューのロックに失敗しました: {}", e);
+                    break;
+                }
+            };
+            let Some(message) = next else {
+                break;
+            };
+            retry_save(&app_handle, message).await;
+        }
+    });
+}
+
+/// ## メッセージの保存をリトライする
+///
+/// 接続断に起因するエラーの場合は、DB再接続後に保存するため待機キューに移します。
+/// ロック競合とそれ以外のエラーは、それぞれの上限回数まで再試行します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `message`: 保存するメッセージ
+async fn retry_save(app_handle: &tauri::AppHandle, message: Message) {
+    let mut failures = 0;
+    let mut busy_failures = 0;
+    let mut busy = false;
+    while failures < MAX_SAVE_RETRIES && busy_failures < MAX_BUSY_RETRIES {
+        let delay = if busy {
+            retry_delay(busy_failures - 1, true)
+        } else {
+            retry_delay(failures, false)
+        };
+        tokio::time::sleep(delay).await;
+
+        // 再接続で接続プールが置き換わる場合があるため、試行ごとに取得する
+        let pool = app_handle
+            .state::<AppState>()
+            .db_pool
+            .lock()
+            .ok()
+            .and_then(|pool| pool.clone());
+        let Some(pool) = pool else {
+            db_health::queue_pending_message(app_handle, message);
+            return;
+        };
+
+        match database::save_message_db(&pool, &message).await {
+            Ok(_) => {
+                println!(
+                    "メッセージの保存をリトライで完了しました: ID={}, 試行回数={}",
+                    message.id,
+                    failures + busy_failures + 1
+                );
+                return;
+            }
+            Err(e) if db_health::is_connection_error(&e) => {
+                eprintln!(
+                    "メッセージの保存のリトライ中に接続エラーが発生しました: ID={}, エラー={}",
+                    message.id, e
+                );
+                db_health::queue_pending_message(app_handle, message);
+                return;
+            }
+            Err(e) => {
+                busy = is_busy_error(&e);
+                if busy {
+                    busy_failures += 1;
+                } else {
+                    failures += 1;
+                }
+                eprintln!(
+                    "メッセージの保存のリトライに失敗しました (ロック競合 {}/{}, その他 {}/{}): ID={}, エラー={}",
+                    busy_failures,
+                    MAX_BUSY_RETRIES,
+                    failures,
+                    MAX_SAVE_RETRIES,
+                    message.id,
+                    e
+                );
+            }
+        }
+    }
+
+    log_unsaved_message(&message);
+}
+
+/// ## リトライ前の待機時間を計算する
+///
+/// ### Arguments
+/// - `attempt`: 同じ種類のエラーでのリトライの試行回数（0始まり）
+/// - `busy`: 直前の失敗がロック競合によるものかどうか
+///
+/// ### Returns
+/// - `Duration`: 指数バックオフの待機時間（ロック競合の場合は短い待機時間から始める）
+fn retry_delay(attempt: u32, busy: bool) -> Duration {
+    let base = if busy {
+        BUSY_RETRY_DELAY
+    } else {
+        RETRY_BASE_DELAY
+    };
+    base * 2u32.saturating_pow(attempt)
+}
+
+/// ## SQLiteのロック競合によるエラーかどうかを判定する
+///
+/// 拡張エラーコードの下位8ビットがプライマリエラーコードのため、
+/// `SQLITE_BUSY_SNAPSHOT` などの拡張コードも `SQLITE_BUSY` として扱います。
+///
+/// ### Arguments
+/// - `error`: 保存時に発生したエラー
+///
+/// ### Returns
+/// - `bool`: `SQLITE_BUSY` / `SQLITE_LOCKED` の場合は `true`
+pub fn is_busy_error(error: &SqlxError) -> bool {
+    let SqlxError::Database(db_error) = error else {
+        return false;
+    };
+    db_error
+        .code()
+        .and_then(|code| code.parse::<i32>().ok())
+        .is_some_and(|code| matches!(code & 0xff, SQLITE_BUSY | SQLITE_LOCKED))
+}
+
+/// リトライ上限に達したメッセージの全内容をエラーログに出力する
+fn log_unsaved_message(message: &Message) {
+    let content = serde_json::to_string(message).unwrap_or_else(|_| format!("{:?}", message));
+    eprintln!(
+        "メッセージを保存できませんでした。手動で復旧してください: ID={}, 内容={}",
+        message.id, content
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(0, false), Duration::from_millis(500));
+        assert_eq!(retry_delay(1, false), Duration::from_millis(1000));
+        assert_eq!(retry_delay(2, false), Duration::from_millis(2000));
+        assert_eq!(retry_delay(0, true), BUSY_RETRY_DELAY);
+        assert_eq!(retry_delay(2, true), Duration::from_millis(200));
+
+        // ロック競合の待機時間の合計は、数百ミリ秒程度のロックより十分長い
+        let total: Duration = (0..MAX_BUSY_RETRIES)
+            .map(|attempt| retry_delay(attempt, true))
+            .sum();
+        assert!(total >= Duration::from_secs(10));
+    }
+
+    /// テスト用のSQLiteのエラー
+    #[derive(Debug)]
+    struct TestDatabaseError(&'static str);
+
+    impl std::fmt::Display for TestDatabaseError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "database error {}", self.0)
+        }
+    }
+
+    impl std::error::Error for TestDatabaseError {}
+
+    impl sqlx::error::DatabaseError for TestDatabaseError {
+        fn message(&self) -> &str {
+            "database error"
+        }
+
+        fn code(&self) -> Option<std::borrow::Cow<'_, str>> {
+            Some(self.0.into())
+        }
+
+        fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> sqlx::error::ErrorKind {
+            sqlx::error::ErrorKind::Other
+        }
+    }
+
+    /// ロック競合によるエラーの判定のテスト
+    #[test]
+    fn test_is_busy_error() {
+        let error = |code| SqlxError::Database(Box::new(TestDatabaseError(code)));
+        assert!(is_busy_error(&error("5")));
+        assert!(is_busy_error(&error("6")));
+        // SQLITE_BUSY_SNAPSHOT (517) などの拡張コードもロック競合とみなす
+        assert!(is_busy_error(&error("517")));
+        // SQLITE_CONSTRAINT_UNIQUE (2067) はロック競合ではない
+        assert!(!is_busy_error(&error("2067")));
+        assert!(!is_busy_error(&SqlxError::RowNotFound));
+    }
+}
@@ -0,0 +1,358 @@
+//! データベースの自動VACUUMモジュール
+//!
+//! メッセージやセッションの削除で断片化した空き領域を `VACUUM` で解放します。
+//! `VACUUM` は時間がかかりデータベースをロックするため、サーバー停止中や接続数が0の
+//! アイドル時、セッション終了後など配信に影響しないタイミングでのみ自動実行します。
+//! 実行間隔がアプリの起動をまたいでも判定できるよう、実行状況はDBの `settings` テーブルに保存します。
+
+use crate::database;
+use crate::settings;
+use crate::state::AppState;
+use crate::types::get_connections_count;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::sqlite::SqlitePool;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+/// 自動VACUUMの実行間隔
+pub const VACUUM_INTERVAL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// 間隔に関わらず自動VACUUMを実行する削除行数
+pub const VACUUM_DELETED_ROWS_THRESHOLD: u64 = 10_000;
+
+/// 自動VACUUMの実行要否を確認する間隔
+pub const VACUUM_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// 最後にVACUUMを実行した時刻の設定キー
+pub const LAST_VACUUM_AT_KEY: &str = "last_vacuum_at";
+
+/// 実行間隔の起点の設定キー
+pub const VACUUM_TRACKING_SINCE_KEY: &str = "vacuum_tracking_since";
+
+/// 前回のVACUUM以降に削除した行数の設定キー
+pub const DELETED_ROWS_SINCE_VACUUM_KEY: &str = "deleted_rows_since_vacuum";
+
+/// ## VACUUMの実行状況
+#[derive(Debug, Clone, Serialize)]
+pub struct VacuumState {
+    /// 最後にVACUUMを実行した時刻（未実行の場合はNone）
+    pub last_vacuum_at: Option<DateTime<Utc>>,
+    /// 実行間隔の起点（未実行の場合はアプリケーションの起動時刻）
+    pub tracking_since: DateTime<Utc>,
+    /// 前回のVACUUM以降に削除した行数
+    pub deleted_rows_since_vacuum: u64,
+    /// VACUUMを実行中かどうか
+    pub running: bool,
+}
+
+impl Default for VacuumState {
+    fn default() -> Self {
+        Self {
+            last_vacuum_at: None,
+            tracking_since: Utc::now(),
+            deleted_rows_since_vacuum: 0,
+            running: false,
+        }
+    }
+}
+
+impl VacuumState {
+    /// ## 自動VACUUMを実行すべきか判定する
+    ///
+    /// ### Arguments
+    /// - `now`: 現在時刻
+    ///
+    /// ### Returns
+    /// - `bool`: 前回から `VACUUM_INTERVAL` 以上経過したか、削除行数が閾値を超えた場合はtrue
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        if self.running {
+            return false;
+        }
+        let since = self.last_vacuum_at.unwrap_or(self.tracking_since);
+        let elapsed = (now - since).to_std().unwrap_or_default();
+        elapsed >= VACUUM_INTERVAL
+            || self.deleted_rows_since_vacuum >= VACUUM_DELETED_ROWS_THRESHOLD
+    }
+}
+
+/// ## VACUUMの実行結果
+#[derive(Debug, Clone, Serialize)]
+pub struct VacuumResult {
+    /// 実行前のデータベースのサイズ（バイト）
+    pub size_before: i64,
+    /// 実行後のデータベースのサイズ（バイト）
+    pub size_after: i64,
+    /// 実行にかかった時間（ミリ秒）
+    pub duration_ms: u64,
+    /// 実行を完了した時刻
+    pub finished_at: DateTime<Utc>,
+}
+
+/// ## 削除した行数を記録する
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+/// - `rows`: 削除した行数
+pub fn record_deleted_rows(app_state: &AppState, rows: u64) {
+    if rows == 0 {
+        return;
+    }
+    if let Ok(mut state) = app_state.vacuum_state.lock() {
+        state.deleted_rows_since_vacuum = state.deleted_rows_since_vacuum.saturating_add(rows);
+    }
+    save_state(app_state);
+}
+
+/// ## 実行状況をDBに保存する
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+fn save_state(app_state: &AppState) {
+    let Ok(state) = app_state.vacuum_state.lock().map(|state| state.clone()) else {
+        return;
+    };
+    if let Some(last_vacuum_at) = state.last_vacuum_at {
+        settings::save_setting(app_state, LAST_VACUUM_AT_KEY, last_vacuum_at.to_rfc3339());
+    }
+    settings::save_setting(
+        app_state,
+        VACUUM_TRACKING_SINCE_KEY,
+        state.tracking_since.to_rfc3339(),
+    );
+    settings::save_setting(
+        app_state,
+        DELETED_ROWS_SINCE_VACUUM_KEY,
+        state.deleted_rows_since_vacuum.to_string(),
+    );
+}
+
+/// ## 保存した実行状況を復元する
+///
+/// 起動時、DBの初期化後に呼び出します。実行間隔の起点が保存されていない場合は、
+/// 次回の起動以降も同じ起点から判定できるよう現在の起点を保存します。
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+/// - `pool`: SQLiteデータベース接続プール
+pub async fn restore_state(app_state: &AppState, pool: &SqlitePool) {
+    let get = |key: &'static str| async move {
+        database::get_setting(pool, key).await.unwrap_or_else(|e| {
+            eprintln!("VACUUMの実行状況の読み込みに失敗しました ({}): {}", key, e);
+            None
+        })
+    };
+    let parse_time = |value: String| {
+        DateTime::parse_from_rfc3339(&value)
+            .ok()
+            .map(|time| time.with_timezone(&Utc))
+    };
+
+    let last_vacuum_at = get(LAST_VACUUM_AT_KEY).await.and_then(parse_time);
+    let tracking_since = get(VACUUM_TRACKING_SINCE_KEY).await.and_then(parse_time);
+    let deleted_rows = get(DELETED_ROWS_SINCE_VACUUM_KEY)
+        .await
+        .and_then(|rows| rows.parse::<u64>().ok());
+
+    let unsaved_tracking_since = {
+        let Ok(mut state) = app_state.vacuum_state.lock() else {
+            return;
+        };
+        state.last_vacuum_at = last_vacuum_at.or(state.last_vacuum_at);
+        state.deleted_rows_since_vacuum = state
+            .deleted_rows_since_vacuum
+            .saturating_add(deleted_rows.unwrap_or(0));
+        println!(
+            "VACUUMの実行状況を復元しました: 最終実行 {:?}, 削除行数 {}",
+            state.last_vacuum_at, state.deleted_rows_since_vacuum
+        );
+        match tracking_since {
+            Some(tracking_since) => {
+                state.tracking_since = tracking_since;
+                None
+            }
+            None => Some(state.tracking_since),
+        }
+    };
+
+    if let Some(tracking_since) = unsaved_tracking_since {
+        if let Err(e) = database::set_setting(
+            pool,
+            VACUUM_TRACKING_SINCE_KEY,
+            &tracking_since.to_rfc3339(),
+        )
+        .await
+        {
+            eprintln!("VACUUMの実行状況の保存に失敗しました: {}", e);
+        }
+    }
+}
+
+/// ## VACUUMを実行する
+///
+/// 実行前後のデータベースのサイズをログに出力し、最終実行時刻を記録します。
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+///
+/// ### Returns
+/// - `Result<VacuumResult, String>`: 実行結果、または既に実行中・DB未接続などの場合はエラーメッセージ
+pub async fn run_vacuum(app_state: &AppState) -> Result<VacuumResult, String> {
+    let pool = app_state
+        .db_pool
+        .lock()
+        .map_err(|e| format!("データベースプールのロックに失敗しました: {}", e))?
+        .clone()
+        .ok_or_else(|| "データベース接続が初期化されていません".to_string())?;
+
+    {
+        let mut state = app_state
+            .vacuum_state
+            .lock()
+            .map_err(|e| format!("VACUUMの状態のロックに失敗しました: {}", e))?;
+        if state.running {
+            return Err("VACUUMは既に実行中です".to_string());
+        }
+        state.running = true;
+    }
+
+    println!("データベースのVACUUMを開始します");
+    let started = Instant::now();
+    let result = database::vacuum(&pool).await;
+    let finished_at = Utc::now();
+
+    let mut state = app_state
+        .vacuum_state
+        .lock()
+        .map_err(|e| format!("VACUUMの状態のロックに失敗しました: {}", e))?;
+    state.running = false;
+    let (size_before, size_after) = result.map_err(|e| {
+        let error_msg = format!("VACUUMの実行中にデータベースエラーが発生しました: {}", e);
+        eprintln!("エラー: {}", error_msg);
+        error_msg
+    })?;
+    state.last_vacuum_at = Some(finished_at);
+    state.deleted_rows_since_vacuum = 0;
+    drop(state);
+    save_state(app_state);
+
+    let result = VacuumResult {
+        size_before,
+        size_after,
+        duration_ms: started.elapsed().as_millis() as u64,
+        finished_at,
+    };
+    println!(
+        "データベースのVACUUMが完了しました: {} bytes -> {} bytes ({} bytes 解放, {}ms)",
+        result.size_before,
+        result.size_after,
+        result.size_before - result.size_after,
+        result.duration_ms
+    );
+    Ok(result)
+}
+
+/// ## アイドル時であれば必要に応じてVACUUMを実行する
+///
+/// サーバーが停止中、または接続数が0の場合のみ実行します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+pub async fn run_if_idle(app_handle: &tauri::AppHandle) {
+    let app_state = app_handle.state::<AppState>();
+    let server_running = app_state
+        .server_handle
+        .lock()
+        .map(|guard| guard.is_some())
+        .unwrap_or(true);
+    if server_running && get_connections_count() > 0 {
+        return;
+    }
+
+    let due = app_state
+        .vacuum_state
+        .lock()
+        .map(|state| state.is_due(Utc::now()))
+        .unwrap_or(false);
+    if !due {
+        return;
+    }
+
+    println!("アイドル状態のため、自動VACUUMを実行します");
+    if let Err(e) = run_vacuum(&app_state).await {
+        eprintln!("自動VACUUMに失敗しました: {}", e);
+    }
+}
+
+/// ## 自動VACUUMのスケジューラーを起動する
+///
+/// `VACUUM_CHECK_INTERVAL` ごとに実行要否を確認し、アイドル時に実行します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+pub fn spawn_vacuum_scheduler(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(VACUUM_CHECK_INTERVAL).await;
+            run_if_idle(&app_handle).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CREATE_SETTINGS_TABLE_SQL;
+
+    #[test]
+    fn test_vacuum_is_due() {
+        let now = Utc::now();
+        let mut state = VacuumState {
+            tracking_since: now,
+            ..VacuumState::default()
+        };
+        assert!(!state.is_due(now));
+
+        // 実行間隔が経過した場合
+        let later = now + chrono::Duration::from_std(VACUUM_INTERVAL).unwrap();
+        assert!(state.is_due(later));
+
+        // 削除行数が閾値を超えた場合
+        state.deleted_rows_since_vacuum = VACUUM_DELETED_ROWS_THRESHOLD;
+        assert!(state.is_due(now));
+
+        // 実行中は重ねて実行しない
+        state.running = true;
+        assert!(!state.is_due(later));
+    }
+
+    /// 保存した実行状況の復元のテスト
+    #[sqlx::test]
+    async fn test_restore_state(pool: SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(CREATE_SETTINGS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        // 実行間隔の起点が保存されていない場合は、現在の起点を保存する
+        let app_state = AppState::new();
+        restore_state(&app_state, &pool).await;
+        let tracking_since = app_state.vacuum_state.lock().unwrap().tracking_since;
+        assert_eq!(
+            database::get_setting(&pool, VACUUM_TRACKING_SINCE_KEY).await?,
+            Some(tracking_since.to_rfc3339())
+        );
+
+        // 保存した最終実行時刻・削除行数を復元する
+        let last_vacuum_at = Utc::now() - chrono::Duration::days(3);
+        database::set_setting(&pool, LAST_VACUUM_AT_KEY, &last_vacuum_at.to_rfc3339()).await?;
+        database::set_setting(&pool, DELETED_ROWS_SINCE_VACUUM_KEY, "120").await?;
+        let app_state = AppState::new();
+        restore_state(&app_state, &pool).await;
+        let state = app_state.vacuum_state.lock().unwrap().clone();
+        assert_eq!(state.last_vacuum_at, Some(last_vacuum_at));
+        assert_eq!(state.tracking_since, tracking_since);
+        assert_eq!(state.deleted_rows_since_vacuum, 120);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+//! メッセージ言語判定モジュール
+//!
+//! チャットメッセージの言語を判定し、ISO 639-1 コードで返します。
+//! 判定には軽量な `whatlang` を使用するため、メッセージ処理中に同期的に実行しても
+//! 処理時間への影響はごくわずかです。
+
+use whatlang::Lang;
+
+/// 言語判定を行うメッセージの最小文字数
+///
+/// これより短いメッセージは判定の信頼性が低いため判定しません。
+pub const MIN_LANGUAGE_DETECTION_CHARS: usize = 10;
+
+/// ## メッセージの言語を判定する
+///
+/// ### Arguments
+/// - `text`: 判定対象のメッセージ
+///
+/// ### Returns
+/// - `Option<String>`: ISO 639-1 の言語コード（短すぎるメッセージや判定できない場合はNone）
+pub fn detect_language(text: &str) -> Option<String> {
+    let text = text.trim();
+    if text.chars().count() < MIN_LANGUAGE_DETECTION_CHARS {
+        return None;
+    }
+
+    whatlang::detect_lang(text).map(|lang| iso639_1(lang).to_string())
+}
+
+/// ## 言語絞り込み用のコードを正規化する
+///
+/// ### Arguments
+/// - `language`: 指定された言語コード（例: "ja", " EN "）
+///
+/// ### Returns
+/// - `Option<String>`: 小文字に正規化した言語コード（空文字の場合はNone）
+pub fn normalize_language_filter(language: Option<&str>) -> Option<String> {
+    language
+        .map(|code| code.trim().to_ascii_lowercase())
+        .filter(|code| !code.is_empty())
+}
+
+/// whatlangの言語（ISO 639-3）をISO 639-1 コードに変換する
+fn iso639_1(lang: Lang) -> &'static str {
+    match lang {
+        Lang::Epo => "eo",
+        Lang::Eng => "en",
+        Lang::Rus => "ru",
+        Lang::Cmn => "zh",
+        Lang::Spa => "es",
+        Lang::Por => "pt",
+        Lang::Ita => "it",
+        Lang::Ben => "bn",
+        Lang::Fra => "fr",
+        Lang::Deu => "de",
+        Lang::Ukr => "uk",
+        Lang::Kat => "ka",
+        Lang::Ara => "ar",
+        Lang::Hin => "hi",
+        Lang::Jpn => "ja",
+        Lang::Heb => "he",
+        Lang::Yid => "yi",
+        Lang::Pol => "pl",
+        Lang::Amh => "am",
+        Lang::Jav => "jv",
+        Lang::Kor => "ko",
+        Lang::Nob => "nb",
+        Lang::Dan => "da",
+        Lang::Swe => "sv",
+        Lang::Fin => "fi",
+        Lang::Tur => "tr",
+        Lang::Nld => "nl",
+        Lang::Hun => "hu",
+        Lang::Ces => "cs",
+        Lang::Ell => "el",
+        Lang::Bul => "bg",
+        Lang::Bel => "be",
+        Lang::Mar => "mr",
+        Lang::Kan => "kn",
+        Lang::Ron => "ro",
+        Lang::Slv => "sl",
+        Lang::Hrv => "hr",
+        Lang::Srp => "sr",
+        Lang::Mkd => "mk",
+        Lang::Lit => "lt",
+        Lang::Lav => "lv",
+        Lang::Est => "et",
+        Lang::Tam => "ta",
+        Lang::Vie => "vi",
+        Lang::Urd => "ur",
+        Lang::Tha => "th",
+        Lang::Guj => "gu",
+        Lang::Uzb => "uz",
+        Lang::Pan => "pa",
+        Lang::Aze => "az",
+        Lang::Ind => "id",
+        Lang::Tel => "te",
+        Lang::Pes => "fa",
+        Lang::Mal => "ml",
+        Lang::Ori => "or",
+        Lang::Mya => "my",
+        Lang::Nep => "ne",
+        Lang::Sin => "si",
+        Lang::Khm => "km",
+        Lang::Tuk => "tk",
+        Lang::Aka => "ak",
+        Lang::Zul => "zu",
+        Lang::Sna => "sn",
+        Lang::Afr => "af",
+        Lang::Lat => "la",
+        Lang::Slk => "sk",
+        Lang::Cat => "ca",
+        Lang::Tgl => "tl",
+        Lang::Hye => "hy",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ## 言語判定と短いメッセージの除外をテスト
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(
+            detect_language("今日の配信もとても楽しかったです、ありがとう！").as_deref(),
+            Some("ja")
+        );
+        assert_eq!(
+            detect_language("This stream is really fun, thanks for playing today!").as_deref(),
+            Some("en")
+        );
+
+        // 短すぎるメッセージは判定しない
+        assert_eq!(detect_language("草"), None);
+        assert_eq!(detect_language("   hello   "), None);
+    }
+}
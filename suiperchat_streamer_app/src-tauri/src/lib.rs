@@ -3,7 +3,7 @@
 //! このライブラリはStreamerアプリケーションの主要な機能を提供します。
 //! WebSocketサーバー、コマンド処理、状態管理などの機能が含まれています。
 
-use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
 use std::str::FromStr;
 use tauri::Manager;
 // --- プラグインの use 文を追加 ---
@@ -11,11 +11,35 @@ use tauri_plugin_log::{Target, TargetKind};
 use tauri_plugin_updater::Builder as UpdaterBuilder; // updater プラグインを追加
 
 // --- モジュール宣言 ---
+pub mod auto_thanks; // スパチャへの自動お礼メッセージモジュール
+pub mod backup; // メッセージ履歴の差分バックアップモジュール
+pub mod badges; // 視聴者の称号バッジモジュール
+pub mod coin_registry; // 対応コインのレジストリモジュール
 pub mod commands; // コマンドモジュール
+pub mod crash_report; // クラッシュレポート管理モジュール
 pub mod database; // データベース操作モジュール
+pub mod db_health; // データベース接続のヘルスチェック・自動再接続モジュール
 pub mod db_models; // データベースモデル定義モジュール
+pub mod db_retry; // メッセージ保存のリトライモジュール
+pub mod db_vacuum; // データベースの自動VACUUMモジュール
+pub mod language; // メッセージ言語判定モジュール
+pub mod maintenance; // メンテナンス予告モジュール
+pub mod message_limits; // 受信メッセージの文字数制限モジュール
+pub mod milestone; // スパチャ総額マイルストーン管理モジュール
+pub mod moderation; // NGワードによるメッセージモデレーションモジュール
+pub mod obs_layout; // OBSオーバーレイのレイアウト管理モジュール
+pub mod obs_theme; // OBSオーバーレイのテーマ管理モジュール
+pub mod settings; // アプリ設定の永続化モジュール
+pub mod signing; // ブロードキャストメッセージの署名モジュール
 pub mod state; // 状態管理モジュール
+pub mod stream_sessions; // 配信セッションの管理モジュール
+pub mod sui_watcher; // オンチェーン着金の監視モジュール
+pub mod superchat_alert; // スパチャ受信のアラート通知モジュール
+pub mod translation; // メッセージ翻訳モジュール
 pub mod types; // 型定義モジュール
+pub mod validation; // 入力値の形式検証モジュール
+pub mod wallet_registry; // 配信者ウォレットの管理モジュール
+pub mod webhook; // 受信メッセージのWebhook転送モジュール
 pub mod ws_server; // WebSocket サーバーロジック
 pub mod cloudflared_manager; // Cloudflaredダウンロード管理モジュール
 
@@ -23,7 +47,7 @@ pub mod cloudflared_manager; // Cloudflaredダウンロード管理モジュー
 pub use state::AppState;
 
 // Tauri コマンド関数の再エクスポート
-pub use commands::server::{start_websocket_server, stop_websocket_server};
+pub use commands::server::{graceful_restart, start_websocket_server, stop_websocket_server};
 pub use commands::wallet::{get_streamer_info, get_wallet_address, set_wallet_address};
 // 接続管理コマンドの再エクスポート
 pub use commands::connection::{disconnect_client, get_connections_info, set_connection_limits};
@@ -41,6 +65,7 @@ CREATE TABLE IF NOT EXISTS sessions (
     id TEXT PRIMARY KEY NOT NULL,
     started_at TEXT NOT NULL,
     ended_at TEXT,
+    title TEXT, -- 配信者が後から付けるタイトル・メモ（未設定の場合はNULL）
     created_at TEXT NOT NULL, -- DEFAULT削除 (Rust側で設定するため)
     updated_at TEXT NOT NULL  -- DEFAULT削除 (Rust側で設定するため)
 );
@@ -57,10 +82,130 @@ CREATE TABLE IF NOT EXISTS messages (
     tx_hash TEXT,
     wallet_address TEXT,
     session_id TEXT NOT NULL,
+    channel TEXT DEFAULT 'general',
+    sequence INTEGER, -- 受信順のシーケンス番号（同一時刻のメッセージの順序付けに使用）
+    language TEXT, -- 判定されたメッセージの言語（ISO 639-1、判定できない場合はNULL）
+    is_edited INTEGER NOT NULL DEFAULT 0, -- 送信後に編集されたかどうか
+    highlighted INTEGER NOT NULL DEFAULT 0, -- 配信者が後で見返すためにハイライトしたかどうか
     FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
 );
 "#;
 
+const CREATE_FILTER_PRESETS_TABLE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS filter_presets (
+    name TEXT PRIMARY KEY NOT NULL,
+    superchat_only INTEGER NOT NULL DEFAULT 0,
+    min_amount REAL,
+    keyword TEXT,
+    japanese_only INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+"#;
+
+const CREATE_VIEWERS_TABLE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS viewers (
+    wallet_address TEXT PRIMARY KEY NOT NULL,
+    display_name TEXT NOT NULL, -- 最後に使用された表示名
+    first_seen_at TEXT NOT NULL,
+    last_seen_at TEXT NOT NULL,
+    total_superchat_amount REAL NOT NULL DEFAULT 0, -- SUI建てのスーパーチャット累計額
+    superchat_count INTEGER NOT NULL DEFAULT 0,
+    message_count INTEGER NOT NULL DEFAULT 0,
+    opted_out INTEGER NOT NULL DEFAULT 0 -- 集計のオプトアウト（1の場合は集計しない）
+);
+"#;
+
+const CREATE_BACKUPS_TABLE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS backups (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    dest_path TEXT NOT NULL,
+    since TEXT, -- 差分の開始時刻（全件のバックアップの場合はNULL）
+    until TEXT NOT NULL, -- 差分の終了時刻（次回の差分バックアップの開始時刻）
+    until_sequence INTEGER, -- バックアップしたメッセージの最大のシーケンス番号（次回の差分バックアップの開始位置）
+    message_count INTEGER NOT NULL,
+    created_at TEXT NOT NULL
+);
+"#;
+
+const CREATE_MESSAGE_EDITS_TABLE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS message_edits (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    message_id TEXT NOT NULL,
+    old_content TEXT NOT NULL,
+    new_content TEXT NOT NULL,
+    edited_at TEXT NOT NULL,
+    editor TEXT NOT NULL, -- 編集者（"streamer"、視聴者のウォレットアドレスなど）
+    FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_message_edits_message_id ON message_edits (message_id);
+"#;
+
+const CREATE_CONNECTIONS_TABLE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS connections (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    client_id TEXT NOT NULL UNIQUE,
+    ip TEXT NOT NULL,
+    session_id TEXT, -- 接続時の配信セッションID（セッションがない場合はNULL）
+    connected_at TEXT NOT NULL,
+    disconnected_at TEXT, -- 接続中の場合はNULL
+    messages_sent INTEGER NOT NULL DEFAULT 0, -- 切断時点までに送信したメッセージ数
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_connections_session_id ON connections (session_id);
+"#;
+
+const CREATE_VIEWER_COUNT_SAMPLES_TABLE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS viewer_count_samples (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    sampled_at TEXT NOT NULL,
+    viewer_count INTEGER NOT NULL,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_viewer_count_samples_session_id ON viewer_count_samples (session_id);
+"#;
+
+const CREATE_SETTINGS_TABLE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS settings (
+    key TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL
+);
+"#;
+
+const CREATE_REJECTED_SUPERCHATS_TABLE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS rejected_superchats (
+    id TEXT PRIMARY KEY NOT NULL, -- メッセージID
+    session_id TEXT, -- 受信時の配信セッションID（送金記録のため、セッション削除後も残す）
+    tx_hash TEXT NOT NULL,
+    wallet_address TEXT NOT NULL,
+    display_name TEXT NOT NULL,
+    amount REAL NOT NULL,
+    coin TEXT NOT NULL,
+    content TEXT NOT NULL,
+    reason TEXT NOT NULL, -- 配信しなかった理由
+    verified INTEGER NOT NULL DEFAULT 0, -- チェーン上で着金を確認済みの場合は1
+    rejected_at TEXT NOT NULL
+);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_rejected_superchats_tx_hash ON rejected_superchats (tx_hash);
+"#;
+
+/// ## 既存テーブルへ追加するカラム定義
+///
+/// `CREATE TABLE IF NOT EXISTS` では既存テーブルにカラムが追加されないため、
+/// 旧バージョンで作成されたデータベースに対して起動時に不足カラムを追加します。
+/// 要素は `(テーブル名, カラム名, カラム定義)` の組です。
+const ADDITIONAL_COLUMNS: &[(&str, &str, &str)] = &[
+    ("messages", "channel", "TEXT DEFAULT 'general'"),
+    ("messages", "sequence", "INTEGER"),
+    ("messages", "language", "TEXT"),
+    ("messages", "is_edited", "INTEGER NOT NULL DEFAULT 0"),
+    ("messages", "highlighted", "INTEGER NOT NULL DEFAULT 0"),
+    ("sessions", "title", "TEXT"),
+    ("connections", "messages_sent", "INTEGER NOT NULL DEFAULT 0"),
+    ("backups", "until_sequence", "INTEGER"),
+];
+
 /// ## Tauriアプリケーションのエントリーポイント
 ///
 /// Tauriアプリケーションの実行に必要な設定と初期化を行います。
@@ -70,6 +215,9 @@ CREATE TABLE IF NOT EXISTS messages (
 /// - なし。エラーが発生した場合は、プログラムは終了します。
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // パニック発生時にクラッシュレポートを保存する
+    crash_report::install_panic_hook();
+
     tauri::Builder::default()
         // --- プラグインの登録 ---
         .plugin(tauri_plugin_shell::init())
@@ -106,137 +254,71 @@ pub fn run() {
             // アプリケーションハンドルのクローンを取得
             let app_handle = app.handle().clone();
 
+            // クラッシュレポートの保存先を初期化し、未送信のレポートがあれば通知
+            if let Err(e) = crash_report::init(&app_handle) {
+                eprintln!("クラッシュレポートの初期化に失敗しました: {}", e);
+            }
+
+            // アイドル時の自動VACUUMを定期的に確認
+            db_vacuum::spawn_vacuum_scheduler(app_handle.clone());
+
             // 非同期処理をspawn
             tauri::async_runtime::spawn(async move {
-                // 開発/リリースビルドに応じたDBパス解決と接続オプション生成
-                let connect_options_result = async {
-                    let db_path = if cfg!(debug_assertions) {
-                        // 開発ビルド時: プロジェクトルート（suiperchat_streamer_app）直下に dev.db を作成
-                        let path = std::path::PathBuf::from("../dev.db"); // パスを ../dev.db に変更（プロジェクトルートを指す）
-                        println!("開発モードのデータベースパス: {}", path.display());
-
-                        // 開発用DBが存在するか確認
-                        if !path.exists() {
-                            println!("警告: 開発用データベースファイル({})が存在しません。自動的に作成されます。", path.display());
-                        }
+                match initialize_database(&app_handle).await {
+                    Ok(pool) => {
+                        // 保持期間が設定されていれば、古いセッションを削除
+                        let mut deleted_rows = prune_sessions_on_startup(&pool).await;
 
-                        path
-                    } else {
-                        // リリースビルド時
-                        let app_data_dir = match app_handle.path().app_data_dir() {
-                            Ok(dir) => dir,
-                            Err(e) => {
-                                return Err(format!(
-                                    "アプリデータディレクトリの取得に失敗しました: {}",
-                                    e
-                                ));
+                        // 前回クラッシュ等で終了処理されなかったセッションを修復
+                        match database::reconcile_orphaned_sessions(&pool).await {
+                            Ok((repaired, deleted)) => {
+                                println!(
+                                    "未終了のセッションを修復しました: 修復 {}件, 削除 {}件",
+                                    repaired, deleted
+                                );
+                                deleted_rows += deleted;
                             }
-                        };
-                        let db_dir = app_data_dir.join("data");
-                        if let Err(e) = std::fs::create_dir_all(&db_dir) {
-                            return Err(format!(
-                                "データディレクトリ作成エラー ({}): {}",
-                                db_dir.display(),
-                                e
-                            ));
-                        }
-                        let path = db_dir.join("suiperchat_data.db");
-                        println!("本番モードのデータベースパス: {}", path.display());
-                        path
-                    };
-
-                    let db_url = format!("sqlite:{}", db_path.to_string_lossy());
-                    println!("データベースURL: {}", db_url);
-
-                    // SQLiteConnectOptionsを設定
-                    match SqliteConnectOptions::from_str(&db_url) {
-                        Ok(options) => {
-                            println!("SQLite接続オプションを設定しました");
-                            Ok(options
-                                .create_if_missing(true)
-                                .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal)
-                                .foreign_keys(true))
-                        }
-                        Err(e) => {
-                            let error_msg = format!("データベースURLのパースに失敗しました: {}", e);
-                            eprintln!("エラー: {}", error_msg);
-                            Err(error_msg)
+                            Err(e) => eprintln!("未終了のセッションの修復に失敗しました: {}", e),
                         }
-                    }
-                }
-                .await; // db_pathとconnect_options生成処理を完了
-
-                // 接続オプションの取得に成功した場合のみプール初期化に進む
-                match connect_options_result {
-                    Ok(connect_options) => {
-                        // SQLiteプールの初期化（接続オプションを使用）
-                        println!("データベース接続プールを初期化しています...");
-                        match sqlx::sqlite::SqlitePoolOptions::new()
-                            .max_connections(5)
-                            .connect_with(connect_options)
-                            .await
+
+                        // 保持期間を過ぎた接続ログを削除
+                        match database::prune_connection_logs(
+                            &pool,
+                            database::CONNECTION_LOG_RETENTION_DAYS,
+                        )
+                        .await
                         {
-                            Ok(pool) => {
-                                println!("データベース接続プールの初期化に成功しました");
-
-                                // データベースプールの設定
-                                // MutexGuardのスコープを制限するためブロックで囲む
-                                {
-                                    if let Ok(mut db_pool_guard) = app_handle.state::<AppState>().db_pool.lock() {
-                                        *db_pool_guard = Some(pool.clone());
-                                        println!("AppStateにデータベースプールを設定しました");
-                                    } else {
-                                        eprintln!("エラー: データベースプールのロックに失敗しました");
-                                    }
-                                } // ここでdb_pool_guardは解放される
-
-                                // テーブル作成処理の実行
-                                println!("必要なテーブルの作成を開始します...");
-
-                                // sessionsテーブルの作成
-                                match sqlx::query(CREATE_SESSIONS_TABLE_SQL)
-                                    .execute(&pool)
-                                    .await
-                                {
-                                    Ok(_) => println!("sessionsテーブルの作成に成功しました"),
-                                    Err(e) => {
-                                        eprintln!("sessionsテーブル作成中にエラーが発生しました: {}", e);
-                                        eprintln!("警告: sessionsテーブルが作成できなかったため、一部の機能が動作しない可能性があります");
-                                    }
-                                }
-
-                                // messagesテーブルの作成
-                                match sqlx::query(CREATE_MESSAGES_TABLE_SQL)
-                                    .execute(&pool)
-                                    .await
-                                {
-                                    Ok(_) => println!("messagesテーブルの作成に成功しました"),
-                                    Err(e) => {
-                                        eprintln!("messagesテーブル作成中にエラーが発生しました: {}", e);
-                                        eprintln!("警告: messagesテーブルが作成できなかったため、履歴機能が動作しない可能性があります");
-                                    }
-                                }
-
-                                println!("テーブル作成処理が完了しました");
-                            }
-                            Err(e) => {
-                                eprintln!("データベース接続エラー: {}", e);
-                                // 接続エラーの詳細情報を取得してログに出力
-                                let err_details = match e {
-                                    sqlx::Error::Database(db_err) => {
-                                        format!("データベースエラー: {}", db_err)
-                                    }
-                                    sqlx::Error::PoolTimedOut => "接続プールのタイムアウト".to_string(),
-                                    sqlx::Error::PoolClosed => "接続プールが閉じられています".to_string(),
-                                    sqlx::Error::WorkerCrashed => "ワーカーがクラッシュしました".to_string(),
-                                    _ => format!("その他のエラー: {}", e),
-                                };
-                                eprintln!("詳細: {}", err_details);
+                            Ok(pruned) => {
+                                println!(
+                                    "保持期間({}日)を過ぎた接続ログを削除しました: {}件",
+                                    database::CONNECTION_LOG_RETENTION_DAYS,
+                                    pruned
+                                );
+                                deleted_rows += pruned;
                             }
+                            Err(e) => eprintln!("古い接続ログの削除に失敗しました: {}", e),
+                        }
+
+                        // 前回保存した設定とVACUUMの実行状況を復元
+                        settings::restore_settings(&app_handle, &pool).await;
+                        db_vacuum::restore_state(&app_handle.state::<AppState>(), &pool).await;
+
+                        // データベースプールの設定
+                        if let Ok(mut db_pool_guard) = app_handle.state::<AppState>().db_pool.lock() {
+                            *db_pool_guard = Some(pool);
+                            println!("AppStateにデータベースプールを設定しました");
+                        } else {
+                            eprintln!("エラー: データベースプールのロックに失敗しました");
                         }
+
+                        // DBプールの設定前に変更された設定を保存
+                        settings::flush_pending_settings(&app_handle.state::<AppState>());
+
+                        // 起動時に削除した行数を自動VACUUMの判断に使用する（DBプールの設定後に保存する）
+                        db_vacuum::record_deleted_rows(&app_handle.state::<AppState>(), deleted_rows);
                     }
                     Err(e) => {
-                        eprintln!("DB接続オプション生成エラー: {}", e);
+                        eprintln!("{}", e);
                         eprintln!("データベース初期化をスキップします。この状態ではメッセージの保存と履歴機能は動作しません。");
                     }
                 }
@@ -249,19 +331,147 @@ pub fn run() {
             // サーバー関連コマンド
             commands::server::start_websocket_server,
             commands::server::stop_websocket_server,
+            commands::server::graceful_restart,
+            commands::server::regenerate_tunnel_url,
+            commands::server::set_idle_shutdown,
+            commands::server::set_compression_level,
+            commands::server::set_tls_config,
+            commands::server::disable_tls,
+            commands::server::get_tls_certificate_info,
+            commands::server::set_tunnel_protocol,
+            commands::server::get_tunnel_protocol,
+            commands::server::set_tunnel_provider,
+            commands::server::get_tunnel_provider,
+            commands::server::set_cloudflared_version,
+            commands::server::set_server_ports,
+            commands::server::start_channel_session,
+            commands::server::end_channel_session,
+            commands::server::get_active_sessions,
+            commands::server::set_bind_host,
+            commands::server::set_auto_release_ports,
+            commands::server::set_upnp_enabled,
+            commands::server::get_log_file_path,
             // ウォレット関連コマンド
             commands::wallet::set_wallet_address,
             commands::wallet::get_wallet_address,
+            commands::wallet::add_wallet,
+            commands::wallet::remove_wallet,
+            commands::wallet::set_active_wallet,
+            commands::wallet::list_wallets,
+            commands::wallet::set_require_wallet,
             commands::wallet::get_streamer_info,
             // 接続管理コマンド
             commands::connection::get_connections_info,
+            commands::connection::get_connections_paginated,
+            commands::connection::get_viewer_count_history,
             commands::connection::disconnect_client,
+            commands::connection::send_message_to_client,
+            commands::connection::block_client_ip,
+            commands::connection::unblock_client_ip,
+            commands::connection::get_blocked_ips,
+            commands::connection::assign_client_group,
+            commands::connection::unassign_client_group,
+            commands::connection::get_client_groups,
+            commands::connection::get_group_connection_counts,
+            commands::connection::send_message_to_group,
+            commands::connection::get_connection_stats,
+            commands::connection::reset_delivery_stats,
             commands::connection::set_connection_limits,
+            commands::connection::set_overflow_redirect,
+            commands::connection::set_flow_control,
+            commands::connection::get_flow_control,
+            commands::connection::set_message_rate_limit,
+            commands::connection::set_per_wallet_limit,
+            commands::connection::get_message_rate_limit,
+            commands::connection::set_idle_disconnect_timeout,
+            commands::connection::get_idle_disconnect_timeout,
+            commands::connection::set_human_verification,
+            commands::connection::get_human_verification,
+            commands::connection::set_tx_verification,
+            commands::connection::get_tx_verification,
+            commands::connection::load_asn_database,
+            commands::connection::set_access_token,
+            // 視聴者プロフィール関連コマンド
+            commands::viewer::get_viewer_profile,
+            commands::viewer::list_viewer_profiles,
+            commands::viewer::get_top_donors,
+            commands::badges::set_badge_thresholds,
+            commands::badges::get_badge_thresholds,
+            commands::obs_layout::list_obs_layouts,
+            commands::obs_layout::save_obs_layout,
+            commands::obs_layout::delete_obs_layout,
+            commands::obs_layout::set_obs_layout,
+            // OBSテーマ関連コマンド
+            commands::obs_theme::set_obs_theme,
+            commands::obs_theme::get_obs_theme,
+            commands::viewer::set_viewer_opt_out,
+            // バックアップ関連コマンド
+            commands::backup::backup_incremental,
+            commands::backup::verify_backup_chain,
+            commands::backup::restore_from_incrementals,
+            // クラッシュレポート関連コマンド
+            commands::crash_report::list_crash_reports,
+            commands::crash_report::delete_crash_report,
+            commands::crash_report::mark_crash_report_submitted,
+            commands::crash_report::notify_crash_reports_ready,
             // 履歴関連コマンド
             commands::history::get_message_history,
             commands::history::get_current_session_id,
             commands::history::get_all_session_ids,
             commands::history::get_all_sessions_info,
+            commands::history::export_messages_markdown,
+            commands::history::export_session_to_csv,
+            commands::history::export_session_json,
+            commands::history::import_session_json,
+            commands::history::update_session_times,
+            commands::history::delete_session,
+            commands::history::edit_message,
+            commands::history::get_message_edit_history,
+            commands::history::toggle_message_highlight,
+            commands::history::set_session_title,
+            commands::history::search_messages,
+            commands::history::get_connection_logs,
+            commands::history::get_client_activity_summary,
+            commands::history::get_session_viewer_count_history,
+            commands::history::get_rejected_superchats,
+            // フィルタプリセット関連コマンド
+            commands::filter_preset::save_filter_preset,
+            commands::filter_preset::list_filter_presets,
+            commands::filter_preset::apply_filter_preset,
+            // マイルストーン関連コマンド
+            commands::milestone::set_milestones,
+            commands::milestone::get_milestones,
+            // スパチャアラート関連コマンド
+            commands::superchat_alert::set_big_superchat_threshold,
+            commands::superchat_alert::get_superchat_alert_config,
+            commands::auto_thanks::set_auto_thanks,
+            commands::auto_thanks::get_auto_thanks,
+            // Webhook関連コマンド
+            commands::webhook::set_webhook_url,
+            // NGワード関連コマンド
+            commands::moderation::set_banned_words,
+            commands::moderation::get_banned_words,
+            commands::message_limits::set_message_limits,
+            commands::message_limits::get_message_limits,
+            // メッセージ署名関連コマンド
+            commands::signing::set_message_signing,
+            commands::signing::get_message_signing,
+            commands::signing::rotate_signing_key,
+            commands::sui_watcher::set_sui_watcher_config,
+            commands::sui_watcher::start_sui_watcher,
+            commands::sui_watcher::stop_sui_watcher,
+            commands::sui_watcher::get_sui_watcher_status,
+            commands::coins::get_supported_coins,
+            commands::coins::set_supported_coins,
+            commands::coins::set_accepted_coins,
+            commands::translation::get_translation_config,
+            commands::translation::set_translation_config,
+            commands::translation::set_translation_api_key,
+            commands::maintenance::schedule_maintenance,
+            commands::maintenance::cancel_maintenance,
+            commands::db_vacuum::run_vacuum_now,
+            commands::db_vacuum::optimize_database,
+            commands::db_vacuum::get_vacuum_status,
             // YouTube関連コマンド
             commands::youtube::set_youtube_video_id,
             commands::youtube::get_youtube_video_id
@@ -269,3 +479,300 @@ pub fn run() {
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+/// ## 保持期間を過ぎた古いセッションを削除する
+///
+/// 環境変数 `DB_RETENTION_DAYS` に1以上の日数が設定されている場合のみ、
+/// 終了からその日数を過ぎたセッションとメッセージを削除します。
+///
+/// ### Arguments
+/// - `pool`: SQLiteデータベース接続プール
+///
+/// ### Returns
+/// - `u64`: 削除したセッションとメッセージの行数
+async fn prune_sessions_on_startup(pool: &SqlitePool) -> u64 {
+    let Ok(value) = std::env::var(database::DB_RETENTION_DAYS_ENV) else {
+        return 0;
+    };
+    let retention_days = match value.trim().parse::<i64>() {
+        Ok(days) if days > 0 => days,
+        _ => {
+            eprintln!(
+                "{}の値が不正なため、古いセッションの削除をスキップします: {}",
+                database::DB_RETENTION_DAYS_ENV,
+                value
+            );
+            return 0;
+        }
+    };
+
+    match database::prune_old_sessions(pool, retention_days).await {
+        Ok((sessions, messages)) => {
+            println!(
+                "保持期間({}日)を過ぎたセッションを削除しました: {}件 (メッセージ{}件)",
+                retention_days, sessions, messages
+            );
+            sessions + messages
+        }
+        Err(e) => {
+            eprintln!("古いセッションの削除に失敗しました: {}", e);
+            0
+        }
+    }
+}
+
+/// ## データベースを初期化する
+///
+/// 開発/リリースビルドに応じたDBパスを解決して接続プールを作成し、
+/// 必要なテーブル・カラム・インデックスを準備します。
+/// 起動時に加え、接続プール異常時の自動再接続でも使用されます。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+///
+/// ### Returns
+/// - `Result<SqlitePool, String>`: 成功時は初期化済みの接続プール、失敗時はエラーメッセージ
+pub(crate) async fn initialize_database(
+    app_handle: &tauri::AppHandle,
+) -> Result<SqlitePool, String> {
+    // 開発/リリースビルドに応じたDBパス解決と接続オプション生成
+    let connect_options = async {
+        let db_path = if cfg!(debug_assertions) {
+            // 開発ビルド時: プロジェクトルート（suiperchat_streamer_app）直下に dev.db を作成
+            let path = std::path::PathBuf::from("../dev.db"); // パスを ../dev.db に変更（プロジェクトルートを指す）
+            println!("開発モードのデータベースパス: {}", path.display());
+
+            // 開発用DBが存在するか確認
+            if !path.exists() {
+                println!(
+                    "警告: 開発用データベースファイル({})が存在しません。自動的に作成されます。",
+                    path.display()
+                );
+            }
+
+            path
+        } else {
+            // リリースビルド時
+            let app_data_dir = match app_handle.path().app_data_dir() {
+                Ok(dir) => dir,
+                Err(e) => {
+                    return Err(format!(
+                        "アプリデータディレクトリの取得に失敗しました: {}",
+                        e
+                    ));
+                }
+            };
+            let db_dir = app_data_dir.join("data");
+            if let Err(e) = std::fs::create_dir_all(&db_dir) {
+                return Err(format!(
+                    "データディレクトリ作成エラー ({}): {}",
+                    db_dir.display(),
+                    e
+                ));
+            }
+            let path = db_dir.join("suiperchat_data.db");
+            println!("本番モードのデータベースパス: {}", path.display());
+            path
+        };
+
+        let db_url = format!("sqlite:{}", db_path.to_string_lossy());
+        println!("データベースURL: {}", db_url);
+
+        // SQLiteConnectOptionsを設定
+        match SqliteConnectOptions::from_str(&db_url) {
+            Ok(options) => {
+                println!("SQLite接続オプションを設定しました");
+                Ok(options
+                    .create_if_missing(true)
+                    .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal)
+                    .foreign_keys(true))
+            }
+            Err(e) => {
+                let error_msg = format!("データベースURLのパースに失敗しました: {}", e);
+                eprintln!("エラー: {}", error_msg);
+                Err(error_msg)
+            }
+        }
+    }
+    .await
+    .map_err(|e| format!("DB接続オプション生成エラー: {}", e))?;
+
+    // SQLiteプールの初期化（接続オプションを使用）
+    println!("データベース接続プールを初期化しています...");
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect_with(connect_options)
+        .await
+        .map_err(|e| {
+            // 接続エラーの詳細情報を取得
+            let err_details = match &e {
+                sqlx::Error::Database(db_err) => format!("データベースエラー: {}", db_err),
+                sqlx::Error::PoolTimedOut => "接続プールのタイムアウト".to_string(),
+                sqlx::Error::PoolClosed => "接続プールが閉じられています".to_string(),
+                sqlx::Error::WorkerCrashed => "ワーカーがクラッシュしました".to_string(),
+                _ => format!("その他のエラー: {}", e),
+            };
+            format!("データベース接続エラー: {} (詳細: {})", e, err_details)
+        })?;
+    println!("データベース接続プールの初期化に成功しました");
+
+    // テーブル作成処理の実行
+    println!("必要なテーブルの作成を開始します...");
+
+    // sessionsテーブルの作成
+    match sqlx::query(CREATE_SESSIONS_TABLE_SQL).execute(&pool).await {
+        Ok(_) => println!("sessionsテーブルの作成に成功しました"),
+        Err(e) => {
+            eprintln!("sessionsテーブル作成中にエラーが発生しました: {}", e);
+            eprintln!("警告: sessionsテーブルが作成できなかったため、一部の機能が動作しない可能性があります");
+        }
+    }
+
+    // messagesテーブルの作成
+    match sqlx::query(CREATE_MESSAGES_TABLE_SQL).execute(&pool).await {
+        Ok(_) => println!("messagesテーブルの作成に成功しました"),
+        Err(e) => {
+            eprintln!("messagesテーブル作成中にエラーが発生しました: {}", e);
+            eprintln!("警告: messagesテーブルが作成できなかったため、履歴機能が動作しない可能性があります");
+        }
+    }
+
+    // filter_presetsテーブルの作成
+    match sqlx::query(CREATE_FILTER_PRESETS_TABLE_SQL)
+        .execute(&pool)
+        .await
+    {
+        Ok(_) => println!("filter_presetsテーブルの作成に成功しました"),
+        Err(e) => {
+            eprintln!("filter_presetsテーブル作成中にエラーが発生しました: {}", e);
+            eprintln!("警告: filter_presetsテーブルが作成できなかったため、フィルタプリセットが保存されない可能性があります");
+        }
+    }
+
+    // viewersテーブルの作成
+    match sqlx::query(CREATE_VIEWERS_TABLE_SQL).execute(&pool).await {
+        Ok(_) => println!("viewersテーブルの作成に成功しました"),
+        Err(e) => {
+            eprintln!("viewersテーブル作成中にエラーが発生しました: {}", e);
+            eprintln!("警告: viewersテーブルが作成できなかったため、視聴者プロフィールが集計されない可能性があります");
+        }
+    }
+
+    // backupsテーブルの作成
+    match sqlx::query(CREATE_BACKUPS_TABLE_SQL).execute(&pool).await {
+        Ok(_) => println!("backupsテーブルの作成に成功しました"),
+        Err(e) => {
+            eprintln!("backupsテーブル作成中にエラーが発生しました: {}", e);
+            eprintln!("警告: backupsテーブルが作成できなかったため、差分バックアップの開始時刻が記録されない可能性があります");
+        }
+    }
+
+    // message_editsテーブルの作成
+    match sqlx::raw_sql(CREATE_MESSAGE_EDITS_TABLE_SQL)
+        .execute(&pool)
+        .await
+    {
+        Ok(_) => println!("message_editsテーブルの作成に成功しました"),
+        Err(e) => {
+            eprintln!("message_editsテーブル作成中にエラーが発生しました: {}", e);
+            eprintln!("警告: message_editsテーブルが作成できなかったため、メッセージの編集履歴が記録されない可能性があります");
+        }
+    }
+
+    // connectionsテーブルの作成
+    match sqlx::raw_sql(CREATE_CONNECTIONS_TABLE_SQL)
+        .execute(&pool)
+        .await
+    {
+        Ok(_) => println!("connectionsテーブルの作成に成功しました"),
+        Err(e) => {
+            eprintln!("connectionsテーブル作成中にエラーが発生しました: {}", e);
+            eprintln!("警告: connectionsテーブルが作成できなかったため、接続ログが記録されない可能性があります");
+        }
+    }
+
+    // viewer_count_samplesテーブルの作成
+    match sqlx::raw_sql(CREATE_VIEWER_COUNT_SAMPLES_TABLE_SQL)
+        .execute(&pool)
+        .await
+    {
+        Ok(_) => println!("viewer_count_samplesテーブルの作成に成功しました"),
+        Err(e) => {
+            eprintln!(
+                "viewer_count_samplesテーブル作成中にエラーが発生しました: {}",
+                e
+            );
+            eprintln!("警告: viewer_count_samplesテーブルが作成できなかったため、視聴者数の推移が保存されない可能性があります");
+        }
+    }
+
+    // settingsテーブルの作成
+    match sqlx::query(CREATE_SETTINGS_TABLE_SQL).execute(&pool).await {
+        Ok(_) => println!("settingsテーブルの作成に成功しました"),
+        Err(e) => {
+            eprintln!("settingsテーブル作成中にエラーが発生しました: {}", e);
+            eprintln!("警告: settingsテーブルが作成できなかったため、設定が再起動後に復元されない可能性があります");
+        }
+    }
+
+    // rejected_superchatsテーブルの作成
+    match sqlx::raw_sql(CREATE_REJECTED_SUPERCHATS_TABLE_SQL)
+        .execute(&pool)
+        .await
+    {
+        Ok(_) => println!("rejected_superchatsテーブルの作成に成功しました"),
+        Err(e) => {
+            eprintln!(
+                "rejected_superchatsテーブル作成中にエラーが発生しました: {}",
+                e
+            );
+            eprintln!("警告: rejected_superchatsテーブルが作成できなかったため、配信しなかったスーパーチャットが記録されない可能性があります");
+        }
+    }
+
+    // 既存データベースへの不足カラムの追加
+    for (table, column, definition) in ADDITIONAL_COLUMNS {
+        if let Err(e) = database::ensure_column(&pool, table, column, definition).await {
+            eprintln!(
+                "{}テーブルへの{}カラム追加中にエラーが発生しました: {}",
+                table, column, e
+            );
+        }
+    }
+
+    // スーパーチャットのストリーク計算用インデックスを作成
+    if let Err(e) = database::ensure_wallet_index(&pool).await {
+        eprintln!(
+            "ウォレットアドレスのインデックス作成中にエラーが発生しました: {}",
+            e
+        );
+    }
+
+    // 同じトランザクションのスーパーチャットを重複して保存しないための一意インデックスを作成
+    if let Err(e) = database::ensure_tx_hash_unique_index(&pool).await {
+        eprintln!(
+            "トランザクションハッシュのインデックス作成中にエラーが発生しました: {}",
+            e
+        );
+    }
+
+    // シーケンス番号が未設定・重複した既存メッセージに受信順の番号を付与
+    if let Err(e) = database::backfill_message_sequence(&pool).await {
+        eprintln!(
+            "メッセージのシーケンス番号付与中にエラーが発生しました: {}",
+            e
+        );
+    }
+
+    // 同じシーケンス番号を採番しないための一意インデックスを作成
+    if let Err(e) = database::ensure_sequence_indexes(&pool).await {
+        eprintln!(
+            "シーケンス番号のインデックス作成中にエラーが発生しました: {}",
+            e
+        );
+    }
+
+    println!("テーブル作成処理が完了しました");
+
+    Ok(pool)
+}
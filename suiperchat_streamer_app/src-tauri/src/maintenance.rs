@@ -0,0 +1,312 @@
+//! メンテナンス予告モジュール
+//!
+//! サーバーを計画的に停止・再起動する前に、接続中の全視聴者へカウントダウン付きの予告を
+//! 定期的にブロードキャストします。カウントダウン終了時にサーバーの停止または
+//! グレースフルリスタートを自動で実行することもできます。
+
+use crate::state::AppState;
+use crate::types::OutgoingMessage;
+use crate::ws_server::connection_manager::global::get_manager;
+use crate::ws_server::server_manager;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+/// 予告をブロードキャストする間隔
+pub const MAINTENANCE_NOTICE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 設定可能なカウントダウンの範囲（秒）
+pub const MAINTENANCE_COUNTDOWN_RANGE_SECS: std::ops::RangeInclusive<u64> = 1..=3600;
+
+/// 予告メッセージの最大文字数
+pub const MAX_MAINTENANCE_MESSAGE_LENGTH: usize = 200;
+
+/// ## カウントダウン終了時の動作
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceAction {
+    /// 通知のみ行い、サーバーはそのまま動作させる
+    #[default]
+    NotifyOnly,
+    /// サーバーを停止する
+    StopServer,
+    /// グレースフルリスタートを行う
+    GracefulRestart,
+}
+
+/// ## メンテナンス予告の段階
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenancePhase {
+    /// カウントダウン中
+    Scheduled,
+    /// 予告がキャンセルされた
+    Cancelled,
+    /// カウントダウンが終了し、メンテナンスを開始する
+    Started,
+}
+
+/// ## メンテナンス予告の内容を検証する
+///
+/// ### Arguments
+/// - `message`: 視聴者に表示する予告メッセージ
+/// - `countdown_secs`: メンテナンス開始までの秒数
+///
+/// ### Returns
+/// - `Result<String, String>`: 前後の空白を除いた予告メッセージ、メッセージが空・最大文字数を超える場合や
+///   カウントダウンの秒数が範囲外の場合はエラーメッセージ
+pub fn validate_request(message: &str, countdown_secs: u64) -> Result<String, String> {
+    let message = message.trim();
+    if message.is_empty() || message.chars().count() > MAX_MAINTENANCE_MESSAGE_LENGTH {
+        return Err(format!(
+            "予告メッセージは1〜{}文字で指定してください",
+            MAX_MAINTENANCE_MESSAGE_LENGTH
+        ));
+    }
+    if !MAINTENANCE_COUNTDOWN_RANGE_SECS.contains(&countdown_secs) {
+        return Err(format!(
+            "カウントダウンは{}〜{}秒の範囲で指定してください: {}",
+            MAINTENANCE_COUNTDOWN_RANGE_SECS.start(),
+            MAINTENANCE_COUNTDOWN_RANGE_SECS.end(),
+            countdown_secs
+        ));
+    }
+    Ok(message.to_string())
+}
+
+/// ## メンテナンス予告を開始する
+///
+/// 既に予告中の場合は、以前の予告を破棄して新しい予告に置き換えます。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `message`: 視聴者に表示する予告メッセージ
+/// - `countdown_secs`: メンテナンス開始までの秒数
+/// - `action`: カウントダウン終了時の動作
+///
+/// ### Returns
+/// - `Result<(), String>`: サーバーが起動していない場合などはエラーメッセージ
+pub fn schedule(
+    app_handle: tauri::AppHandle,
+    message: String,
+    countdown_secs: u64,
+    action: MaintenanceAction,
+) -> Result<(), String> {
+    if !is_server_running(&app_handle) {
+        return Err("サーバーが起動していないため、メンテナンスを予告できません".to_string());
+    }
+
+    let cancel_flag = {
+        let app_state = app_handle.state::<AppState>();
+        let mut cancel_guard = app_state
+            .maintenance_cancel
+            .lock()
+            .map_err(|e| format!("メンテナンス予告の状態のロックに失敗しました: {}", e))?;
+        if let Some(previous) = cancel_guard.take() {
+            previous.store(true, Ordering::SeqCst);
+            println!("以前のメンテナンス予告を新しい予告に置き換えます");
+        }
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        *cancel_guard = Some(Arc::clone(&cancel_flag));
+        cancel_flag
+    };
+
+    println!(
+        "メンテナンスを予告しました: {}秒後 ({:?}) - {}",
+        countdown_secs, action, message
+    );
+    let deadline = Instant::now() + Duration::from_secs(countdown_secs);
+    tauri::async_runtime::spawn(run(app_handle, message, deadline, action, cancel_flag));
+    Ok(())
+}
+
+/// ## メンテナンス予告をキャンセルする
+///
+/// 予告中だった場合は、全クライアントにキャンセル通知をブロードキャストします。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+///
+/// ### Returns
+/// - `bool`: 予告中だった場合は `true`
+pub fn cancel(app_handle: &tauri::AppHandle) -> bool {
+    let cancel_flag = app_handle
+        .state::<AppState>()
+        .maintenance_cancel
+        .lock()
+        .ok()
+        .and_then(|mut cancel| cancel.take());
+
+    match cancel_flag {
+        Some(cancel_flag) => {
+            cancel_flag.store(true, Ordering::SeqCst);
+            broadcast_notice(
+                MaintenancePhase::Cancelled,
+                "",
+                0,
+                MaintenanceAction::NotifyOnly,
+            );
+            println!("メンテナンス予告をキャンセルしました");
+            true
+        }
+        None => false,
+    }
+}
+
+/// キャンセルされるまでカウントダウンを通知し、終了時に指定された動作を実行する
+async fn run(
+    app_handle: tauri::AppHandle,
+    message: String,
+    deadline: Instant,
+    action: MaintenanceAction,
+    cancel_flag: Arc<AtomicBool>,
+) {
+    loop {
+        if cancel_flag.load(Ordering::SeqCst) {
+            return;
+        }
+        // 予告中に手動でサーバーが停止された場合は通知を終了する
+        if !is_server_running(&app_handle) {
+            release(&app_handle, &cancel_flag);
+            return;
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        broadcast_notice(
+            MaintenancePhase::Scheduled,
+            &message,
+            remaining_secs(remaining),
+            action,
+        );
+        tokio::time::sleep(remaining.min(MAINTENANCE_NOTICE_INTERVAL)).await;
+    }
+
+    // キャンセル・置き換えと競合した場合は何もしない
+    if !release(&app_handle, &cancel_flag) {
+        return;
+    }
+    broadcast_notice(MaintenancePhase::Started, &message, 0, action);
+    println!("メンテナンスを開始します ({:?})", action);
+
+    let app_state = app_handle.state::<AppState>();
+    let result = match action {
+        MaintenanceAction::NotifyOnly => Ok(()),
+        MaintenanceAction::StopServer => {
+            server_manager::stop_server(&app_state, app_handle.clone(), &message)
+        }
+        MaintenanceAction::GracefulRestart => {
+            server_manager::graceful_restart(&app_state, app_handle.clone())
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("メンテナンス開始時の処理に失敗しました: {}", e);
+    }
+}
+
+/// 予告中の状態が自身のものであれば解除し、解除できたかどうかを返す
+fn release(app_handle: &tauri::AppHandle, cancel_flag: &Arc<AtomicBool>) -> bool {
+    let app_state = app_handle.state::<AppState>();
+    let Ok(mut cancel_guard) = app_state.maintenance_cancel.lock() else {
+        return false;
+    };
+    if cancel_guard
+        .as_ref()
+        .is_some_and(|current| Arc::ptr_eq(current, cancel_flag))
+    {
+        *cancel_guard = None;
+        true
+    } else {
+        false
+    }
+}
+
+/// 残り時間を視聴者に表示する秒数に変換する（1秒未満は切り上げる）
+fn remaining_secs(remaining: Duration) -> u64 {
+    remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0)
+}
+
+/// サーバーが起動中かどうかを判定する
+fn is_server_running(app_handle: &tauri::AppHandle) -> bool {
+    app_handle
+        .state::<AppState>()
+        .server_handle
+        .lock()
+        .map(|handle| handle.is_some())
+        .unwrap_or(false)
+}
+
+/// 全クライアントにメンテナンス予告をブロードキャストする
+fn broadcast_notice(
+    phase: MaintenancePhase,
+    message: &str,
+    remaining_secs: u64,
+    action: MaintenanceAction,
+) {
+    let notice = OutgoingMessage::Maintenance {
+        phase,
+        message: message.to_string(),
+        remaining_secs,
+        action,
+    };
+    match serde_json::to_string(&notice) {
+        Ok(json) => get_manager().broadcast(&json),
+        Err(e) => eprintln!("メンテナンス予告のシリアライズに失敗: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 予告内容の検証のテスト
+    #[test]
+    fn test_validate_request() {
+        assert_eq!(
+            validate_request("  まもなく再起動します ", 60).as_deref(),
+            Ok("まもなく再起動します")
+        );
+        assert!(validate_request("   ", 60).is_err());
+        assert!(validate_request(&"あ".repeat(MAX_MAINTENANCE_MESSAGE_LENGTH + 1), 60).is_err());
+        assert!(validate_request("再起動します", 0).is_err());
+        assert!(validate_request("再起動します", 3601).is_err());
+    }
+
+    /// 残り秒数の切り上げのテスト
+    #[test]
+    fn test_remaining_secs() {
+        assert_eq!(remaining_secs(Duration::from_secs(10)), 10);
+        assert_eq!(remaining_secs(Duration::from_millis(9_001)), 10);
+        assert_eq!(remaining_secs(Duration::from_millis(1)), 1);
+        assert_eq!(remaining_secs(Duration::ZERO), 0);
+    }
+
+    /// 予告メッセージのシリアライズのテスト
+    #[test]
+    fn test_maintenance_notice_serialization() {
+        let notice = OutgoingMessage::Maintenance {
+            phase: MaintenancePhase::Scheduled,
+            message: "まもなく再起動します".to_string(),
+            remaining_secs: 30,
+            action: MaintenanceAction::GracefulRestart,
+        };
+        let json = serde_json::to_value(&notice).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "maintenance",
+                "phase": "scheduled",
+                "message": "まもなく再起動します",
+                "remaining_secs": 30,
+                "action": "graceful_restart",
+            })
+        );
+
+        let action: MaintenanceAction = serde_json::from_str("\"stop_server\"").unwrap();
+        assert_eq!(action, MaintenanceAction::StopServer);
+    }
+}
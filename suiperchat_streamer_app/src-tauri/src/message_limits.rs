@@ -0,0 +1,142 @@
+//! 受信メッセージの文字数制限モジュール
+//!
+//! 極端に長いメッセージや表示名によってOBSの表示が崩れたり、データベースが肥大化したりしないよう、
+//! 通常チャットとスーパーチャットの本文・表示名の文字数に上限を設けます。
+//! 文字数はバイト数ではなく `char` 単位で数えるため、日本語や絵文字を含む場合も文字の途中で切れません。
+
+use crate::types::ClientMessage;
+use serde::{Deserialize, Serialize};
+
+/// デフォルトのメッセージ本文の最大文字数
+pub const DEFAULT_MAX_CONTENT_CHARS: usize = 500;
+
+/// デフォルトの表示名の最大文字数
+pub const DEFAULT_MAX_DISPLAY_NAME_CHARS: usize = 50;
+
+/// ## 上限を超えたメッセージの扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverLimitAction {
+    /// 上限の文字数に切り詰めて配信・保存する
+    #[default]
+    Truncate,
+    /// 配信せず、送信者にエラーを返す（スーパーチャットは配信しなかった記録としてのみ保存する）
+    Reject,
+}
+
+/// ## 受信メッセージの文字数制限の設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageLimits {
+    /// メッセージ本文の最大文字数
+    pub max_content_chars: usize,
+    /// 表示名の最大文字数
+    pub max_display_name_chars: usize,
+    /// 上限を超えた場合の扱い
+    pub action: OverLimitAction,
+}
+
+impl Default for MessageLimits {
+    fn default() -> Self {
+        Self {
+            max_content_chars: DEFAULT_MAX_CONTENT_CHARS,
+            max_display_name_chars: DEFAULT_MAX_DISPLAY_NAME_CHARS,
+            action: OverLimitAction::default(),
+        }
+    }
+}
+
+impl MessageLimits {
+    /// ## メッセージに文字数制限を適用する
+    ///
+    /// 通常チャットとスーパーチャット以外のメッセージはそのまま受け付けます。
+    ///
+    /// ### Arguments
+    /// - `client_msg`: 制限を適用するメッセージ（切り詰める場合は内容を書き換える）
+    ///
+    /// ### Returns
+    /// - `Result<(), String>`: 受け付ける場合は `Ok(())`、拒否する場合は送信者に返すエラーメッセージ
+    pub fn apply(&self, client_msg: &mut ClientMessage) -> Result<(), String> {
+        let (display_name, content) = match client_msg {
+            ClientMessage::Chat(msg) => (&mut msg.display_name, &mut msg.content),
+            ClientMessage::Superchat(msg) => (&mut msg.display_name, &mut msg.content),
+            _ => return Ok(()),
+        };
+
+        match self.action {
+            OverLimitAction::Truncate => {
+                truncate_chars(display_name, self.max_display_name_chars);
+                truncate_chars(content, self.max_content_chars);
+                Ok(())
+            }
+            OverLimitAction::Reject => {
+                if display_name.chars().count() > self.max_display_name_chars {
+                    return Err(format!(
+                        "表示名は{}文字以内で入力してください",
+                        self.max_display_name_chars
+                    ));
+                }
+                if content.chars().count() > self.max_content_chars {
+                    return Err(format!(
+                        "メッセージは{}文字以内で入力してください",
+                        self.max_content_chars
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 文字列を指定した文字数に切り詰める
+fn truncate_chars(text: &mut String, max_chars: usize) {
+    if let Some((byte_index, _)) = text.char_indices().nth(max_chars) {
+        text.truncate(byte_index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ChatMessage, MessageType};
+
+    fn chat(display_name: &str, content: &str) -> ClientMessage {
+        ClientMessage::Chat(ChatMessage {
+            message_type: MessageType::Chat,
+            id: "test-id".to_string(),
+            display_name: display_name.to_string(),
+            content: content.to_string(),
+            timestamp: None,
+            channel: None,
+            detected_language: None,
+            translated_content: None,
+            badges: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_message_limits_count_chars() {
+        let limits = MessageLimits {
+            max_content_chars: 3,
+            max_display_name_chars: 2,
+            action: OverLimitAction::Truncate,
+        };
+
+        // 絵文字を含む日本語も文字単位で切り詰める
+        let mut msg = chat("山田太郎", "こん🎉にちは");
+        assert!(limits.apply(&mut msg).is_ok());
+        let ClientMessage::Chat(chat_msg) = &msg else {
+            unreachable!()
+        };
+        assert_eq!(chat_msg.display_name, "山田");
+        assert_eq!(chat_msg.content, "こん🎉");
+
+        // 上限ちょうどは拒否しない
+        let reject = MessageLimits {
+            action: OverLimitAction::Reject,
+            ..limits
+        };
+        assert!(reject.apply(&mut chat("山田", "🎉🎉🎉")).is_ok());
+        assert!(reject.apply(&mut chat("山田", "🎉🎉🎉🎉")).is_err());
+        assert!(reject.apply(&mut chat("山田太", "🎉")).is_err());
+    }
+}
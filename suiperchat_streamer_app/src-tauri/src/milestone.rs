@@ -0,0 +1,253 @@
+//! スーパーチャット総額のマイルストーン管理モジュール
+//!
+//! 総スパチャ額が設定したマイルストーンに達したときに、
+//! Tauriイベントの発行とOBS向けのWebSocket通知を行います。
+
+use crate::database;
+use crate::state::AppState;
+use crate::types::OutgoingMessage;
+use crate::ws_server::connection_manager::global::get_manager;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+use tauri::{Emitter, Manager};
+
+/// マイルストーンの集計対象となる通貨
+pub const MILESTONE_COIN: &str = "SUI";
+
+/// ## マイルストーンの集計範囲
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MilestoneScope {
+    /// 配信セッションごとに集計し、セッション開始時にリセットする
+    #[default]
+    Session,
+    /// 全セッションの累計で集計する
+    Cumulative,
+}
+
+/// ## マイルストーンの状態
+///
+/// 設定されたマイルストーン、達成済みのマイルストーン、現在の総額を保持します。
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MilestoneState {
+    /// マイルストーン金額の一覧（昇順）
+    pub amounts: Vec<f64>,
+    /// 達成済みのマイルストーン金額
+    pub reached: Vec<f64>,
+    /// 集計範囲
+    pub scope: MilestoneScope,
+    /// 現在の総額
+    pub total: f64,
+}
+
+impl MilestoneState {
+    /// ## マイルストーンを設定する
+    ///
+    /// 金額は昇順に並べ替えて重複を除きます。
+    /// 現在の総額で既に超えているマイルストーンは達成済みとして扱い、発火させません。
+    ///
+    /// ### Arguments
+    /// - `amounts`: マイルストーン金額の一覧
+    /// - `scope`: 集計範囲
+    /// - `total`: 現在の総額
+    pub fn configure(&mut self, mut amounts: Vec<f64>, scope: MilestoneScope, total: f64) {
+        amounts.sort_by(|a, b| a.total_cmp(b));
+        amounts.dedup();
+        self.amounts = amounts;
+        self.scope = scope;
+        self.reset(total);
+    }
+
+    /// ## 総額を再設定し、達成済みフラグを更新する
+    ///
+    /// ### Arguments
+    /// - `total`: 新しい総額
+    pub fn reset(&mut self, total: f64) {
+        self.total = total;
+        self.reached = self
+            .amounts
+            .iter()
+            .copied()
+            .filter(|amount| *amount <= total)
+            .collect();
+    }
+
+    /// ## 総額に金額を加算する
+    ///
+    /// ### Arguments
+    /// - `amount`: 加算する金額
+    ///
+    /// ### Returns
+    /// - `Vec<f64>`: 今回新たに達成したマイルストーン金額の一覧
+    pub fn add(&mut self, amount: f64) -> Vec<f64> {
+        self.total += amount;
+
+        let newly_reached: Vec<f64> = self
+            .amounts
+            .iter()
+            .copied()
+            .filter(|milestone| *milestone <= self.total && !self.reached.contains(milestone))
+            .collect();
+        self.reached.extend(newly_reached.iter().copied());
+        newly_reached
+    }
+}
+
+/// ## マイルストーン達成イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct MilestoneReachedPayload {
+    /// 達成したマイルストーン金額
+    pub milestone: f64,
+    /// 現在の総額
+    pub total: f64,
+    /// 通貨シンボル
+    pub coin: String,
+}
+
+/// ## 集計範囲に応じた現在の総額をDBから取得する
+///
+/// ### Arguments
+/// - `pool`: SQLiteデータベース接続プール
+/// - `scope`: 集計範囲
+/// - `session_id`: 現在のセッションID
+///
+/// ### Returns
+/// - `Result<f64, sqlx::Error>`: 現在の総額
+pub async fn load_total(
+    pool: &SqlitePool,
+    scope: MilestoneScope,
+    session_id: Option<&str>,
+) -> Result<f64, sqlx::Error> {
+    match (scope, session_id) {
+        (MilestoneScope::Cumulative, _) => {
+            database::get_total_superchat_amount(pool, None, MILESTONE_COIN).await
+        }
+        (MilestoneScope::Session, Some(session_id)) => {
+            database::get_total_superchat_amount(pool, Some(session_id), MILESTONE_COIN).await
+        }
+        // セッションが開始されていない場合はセッション総額を0とする
+        (MilestoneScope::Session, None) => Ok(0.0),
+    }
+}
+
+/// ## 新しいセッション開始時にマイルストーンの状態を更新する
+///
+/// セッション単位の場合は総額と達成済みフラグをリセットし、
+/// 累計の場合はDBから総額を再計算します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `pool`: SQLiteデータベース接続プール
+/// - `session_id`: 開始したセッションID
+pub async fn reset_for_session(app_handle: &tauri::AppHandle, pool: &SqlitePool, session_id: &str) {
+    let app_state = app_handle.state::<AppState>();
+    let scope = match app_state.milestones.lock() {
+        Ok(state) => state.scope,
+        Err(e) => {
+            eprintln!("マイルストーン状態のロックに失敗しました: {}", e);
+            return;
+        }
+    };
+
+    let total = match load_total(pool, scope, Some(session_id)).await {
+        Ok(total) => total,
+        Err(e) => {
+            eprintln!("マイルストーン総額の取得に失敗しました: {}", e);
+            return;
+        }
+    };
+
+    if let Ok(mut state) = app_state.milestones.lock() {
+        state.reset(total);
+        println!(
+            "マイルストーン状態をリセットしました: scope={:?}, total={}",
+            state.scope, total
+        );
+    };
+}
+
+/// ## スーパーチャットを総額に反映し、マイルストーン達成を通知する
+///
+/// 達成したマイルストーンごとに `milestone_reached` イベントを発行し、
+/// OBSを含む接続中のクライアントに `milestone_reached` メッセージを送信します。
+/// 集計対象外の通貨のスーパーチャットは無視します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `amount`: スーパーチャットの金額
+/// - `coin`: スーパーチャットの通貨シンボル
+pub fn record_superchat(app_handle: &tauri::AppHandle, amount: f64, coin: &str) {
+    if coin != MILESTONE_COIN || amount <= 0.0 {
+        return;
+    }
+
+    let (newly_reached, total) = match app_handle.state::<AppState>().milestones.lock() {
+        Ok(mut state) => {
+            let newly_reached = state.add(amount);
+            (newly_reached, state.total)
+        }
+        Err(e) => {
+            eprintln!("マイルストーン状態のロックに失敗しました: {}", e);
+            return;
+        }
+    };
+
+    for milestone in newly_reached {
+        println!(
+            "マイルストーン達成: {} {} (総額: {} {})",
+            milestone, MILESTONE_COIN, total, MILESTONE_COIN
+        );
+
+        let payload = MilestoneReachedPayload {
+            milestone,
+            total,
+            coin: MILESTONE_COIN.to_string(),
+        };
+        if let Err(e) = app_handle.emit("milestone_reached", &payload) {
+            eprintln!("milestone_reached イベントの発火に失敗しました: {}", e);
+        }
+
+        let message = OutgoingMessage::MilestoneReached {
+            milestone,
+            total,
+            coin: MILESTONE_COIN.to_string(),
+        };
+        match serde_json::to_string(&message) {
+            Ok(json) => get_manager().broadcast(&json),
+            Err(e) => eprintln!("マイルストーン通知のシリアライズに失敗: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ## 同一マイルストーンが複数回発火しないことをテスト
+    #[test]
+    fn test_milestone_fires_once() {
+        let mut state = MilestoneState::default();
+        state.configure(vec![100.0, 50.0, 50.0], MilestoneScope::Session, 0.0);
+        assert_eq!(state.amounts, vec![50.0, 100.0]);
+
+        assert!(state.add(30.0).is_empty());
+        assert_eq!(state.add(30.0), vec![50.0]);
+        assert!(state.add(10.0).is_empty());
+        assert_eq!(state.add(100.0), vec![100.0]);
+        assert!(state.add(100.0).is_empty());
+    }
+
+    /// ## 設定時点で超えているマイルストーンが発火しないことをテスト
+    #[test]
+    fn test_configure_marks_passed_milestones() {
+        let mut state = MilestoneState::default();
+        state.configure(vec![50.0, 100.0], MilestoneScope::Cumulative, 60.0);
+        assert_eq!(state.reached, vec![50.0]);
+
+        assert_eq!(state.add(50.0), vec![100.0]);
+
+        // リセット後は再び発火できる
+        state.reset(0.0);
+        assert_eq!(state.add(150.0), vec![50.0, 100.0]);
+    }
+}
@@ -0,0 +1,147 @@
+//! NGワードによるメッセージモデレーションモジュール
+//!
+//! 配信者が設定したNGワードを含むメッセージを検出します。照合は大文字小文字を区別しません。
+//! 通常チャットはNGワードを含む場合に配信・保存されません。
+//! スーパーチャットは送金済みのため、設定に応じてNGワードを伏字にして配信するか、配信しないかを選べます。
+//! 配信しないスーパーチャットも、返金などの対応のため配信しなかった記録として保存します。
+
+use serde::{Deserialize, Serialize};
+
+/// 伏字に使用する文字
+pub const MASK_CHAR: char = '*';
+
+/// ## NGワードを含むスーパーチャットの扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SuperchatModeration {
+    /// NGワードを伏字にして配信・保存する
+    #[default]
+    Mask,
+    /// 配信せず、配信しなかった記録としてのみ保存する
+    Block,
+}
+
+/// ## NGワードの一覧を正規化する
+///
+/// 前後の空白を除去し、空文字と重複（大文字小文字の違いのみのものを含む）を取り除きます。
+///
+/// ### Arguments
+/// - `words`: 設定されたNGワード
+///
+/// ### Returns
+/// - `Vec<String>`: 小文字に正規化したNGワード（設定順）
+pub fn normalize_banned_words(words: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for word in words {
+        let word = word.trim().to_lowercase();
+        if !word.is_empty() && !normalized.contains(&word) {
+            normalized.push(word);
+        }
+    }
+    normalized
+}
+
+/// ## メッセージにNGワードが含まれるか判定する
+///
+/// ### Arguments
+/// - `content`: 判定するメッセージ
+/// - `banned_words`: 正規化済みのNGワード
+///
+/// ### Returns
+/// - `bool`: NGワードを含む場合は `true`
+pub fn contains_banned_word(content: &str, banned_words: &[String]) -> bool {
+    let chars: Vec<char> = content.chars().collect();
+    let words = to_char_words(banned_words);
+    (0..chars.len()).any(|start| longest_match(&chars, start, &words).is_some())
+}
+
+/// ## メッセージ中のNGワードを伏字にする
+///
+/// ### Arguments
+/// - `content`: 伏字にするメッセージ
+/// - `banned_words`: 正規化済みのNGワード
+///
+/// ### Returns
+/// - `String`: NGワードを文字数分の `*` に置き換えたメッセージ
+pub fn mask_banned_words(content: &str, banned_words: &[String]) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let words = to_char_words(banned_words);
+
+    let mut masked = String::with_capacity(content.len());
+    let mut i = 0;
+    while i < chars.len() {
+        match longest_match(&chars, i, &words) {
+            Some(len) => {
+                masked.extend(std::iter::repeat(MASK_CHAR).take(len));
+                i += len;
+            }
+            None => {
+                masked.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+    masked
+}
+
+/// NGワードを文字単位で比較できる形に変換する
+fn to_char_words(banned_words: &[String]) -> Vec<Vec<char>> {
+    banned_words
+        .iter()
+        .map(|word| word.to_lowercase().chars().collect())
+        .collect()
+}
+
+/// `start` の位置から始まるNGワードのうち、最長のものの文字数を返す
+fn longest_match(chars: &[char], start: usize, words: &[Vec<char>]) -> Option<usize> {
+    words
+        .iter()
+        .filter_map(|word| match_len_at(chars, start, word))
+        .max()
+}
+
+/// `start` の位置からNGワードに一致する場合、一致した元のメッセージの文字数を返す
+///
+/// 小文字化で文字数が変わる文字にも対応するため、元の文字ごとに小文字化して比較します。
+fn match_len_at(chars: &[char], start: usize, word: &[char]) -> Option<usize> {
+    if word.is_empty() {
+        return None;
+    }
+
+    let mut matched = 0;
+    let mut i = start;
+    while matched < word.len() {
+        for lower in chars.get(i)?.to_lowercase() {
+            if word.get(matched) != Some(&lower) {
+                return None;
+            }
+            matched += 1;
+        }
+        i += 1;
+    }
+    Some(i - start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 大文字小文字を区別せずに検出・伏字化されることを確認
+    #[test]
+    fn test_banned_words_case_insensitive() {
+        let words = normalize_banned_words(vec![
+            " Spam ".to_string(),
+            "SPAM".to_string(),
+            "".to_string(),
+            "荒らし".to_string(),
+        ]);
+        assert_eq!(words, vec!["spam", "荒らし"]);
+
+        assert!(contains_banned_word("This is SpAm!", &words));
+        assert!(contains_banned_word("荒らしです", &words));
+        assert!(!contains_banned_word("こんにちは", &words));
+
+        assert_eq!(mask_banned_words("SPAM and 荒らし", &words), "**** and ***");
+        assert_eq!(mask_banned_words("no match", &words), "no match");
+    }
+}
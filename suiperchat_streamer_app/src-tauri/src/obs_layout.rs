@@ -0,0 +1,336 @@
+//! OBSオーバーレイのレイアウト管理モジュール
+//!
+//! 配信の場面（雑談・ゲーム・エンディングなど）に応じて切り替えられる、名前付きのOBS表示レイアウトを管理します。
+//! アクティブなレイアウトはOBSサーバーの `/obs/layout.css` としてCSSに変換して配信し、
+//! 切り替え時は接続中のOBS表示に `type: "obs_layout"` メッセージで通知します。
+//! 保存済みのレイアウトは設定としてDBに保存し、次回起動時に復元します。
+//! OBS側は新しいCSSの読み込みが完了してから古いCSSを外すため、切り替え時にちらつきません。
+
+use crate::types::OutgoingMessage;
+use crate::ws_server::connection_manager::global::get_manager;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// デフォルトレイアウトの名前（削除できない）
+pub const DEFAULT_OBS_LAYOUT_NAME: &str = "default";
+
+/// レイアウト名の最大文字数
+pub const MAX_OBS_LAYOUT_NAME_LENGTH: usize = 32;
+
+/// 保存できるレイアウトの最大数
+pub const MAX_OBS_LAYOUTS: usize = 20;
+
+/// 設定可能な表示倍率の範囲（%）
+pub const OBS_LAYOUT_SCALE_RANGE: std::ops::RangeInclusive<u32> = 50..=300;
+
+/// ## OBS表示のレイアウト
+///
+/// 位置とサイズはOBSブラウザソースの画面に対する割合（%）で指定します。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObsLayout {
+    /// レイアウト名
+    pub name: String,
+    /// 表示領域の左端の位置（%）
+    pub x_percent: f64,
+    /// 表示領域の上端の位置（%）
+    pub y_percent: f64,
+    /// 表示領域の幅（%）
+    pub width_percent: f64,
+    /// 表示領域の高さ（%）
+    pub height_percent: f64,
+    /// メッセージの表示倍率（%）
+    pub scale_percent: u32,
+    /// 通常チャットを表示するかどうか
+    pub show_chat: bool,
+    /// スーパーチャットを表示するかどうか
+    pub show_superchat: bool,
+    /// 受信時刻を表示するかどうか
+    pub show_timestamp: bool,
+}
+
+impl Default for ObsLayout {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_OBS_LAYOUT_NAME.to_string(),
+            x_percent: 0.0,
+            y_percent: 0.0,
+            width_percent: 100.0,
+            height_percent: 100.0,
+            scale_percent: 100,
+            show_chat: true,
+            show_superchat: true,
+            show_timestamp: true,
+        }
+    }
+}
+
+impl ObsLayout {
+    /// ## レイアウトの設定値を検証する
+    ///
+    /// ### Returns
+    /// - `Result<(), String>`: 不正な値が含まれる場合はエラーメッセージ
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() || self.name.chars().count() > MAX_OBS_LAYOUT_NAME_LENGTH {
+            return Err(format!(
+                "レイアウト名は1〜{}文字で指定してください",
+                MAX_OBS_LAYOUT_NAME_LENGTH
+            ));
+        }
+        let in_range = |value: f64, min: f64| value.is_finite() && (min..=100.0).contains(&value);
+        if !in_range(self.x_percent, 0.0) || !in_range(self.y_percent, 0.0) {
+            return Err("表示領域の位置は0〜100%で指定してください".to_string());
+        }
+        if !in_range(self.width_percent, 1.0) || !in_range(self.height_percent, 1.0) {
+            return Err("表示領域のサイズは1〜100%で指定してください".to_string());
+        }
+        if !OBS_LAYOUT_SCALE_RANGE.contains(&self.scale_percent) {
+            return Err(format!(
+                "表示倍率は{}〜{}%で指定してください",
+                OBS_LAYOUT_SCALE_RANGE.start(),
+                OBS_LAYOUT_SCALE_RANGE.end()
+            ));
+        }
+        Ok(())
+    }
+
+    /// ## レイアウトをOBS表示用のCSSに変換する
+    ///
+    /// OBSページの既定のスタイルより後に読み込まれ、表示領域と表示項目を上書きします。
+    ///
+    /// ### Returns
+    /// - `String`: CSS
+    pub fn to_css(&self) -> String {
+        let mut css = format!(
+            "/* SUIperCHAT OBSレイアウト: {} */\n\
+             .container {{\n\
+             \tposition: fixed;\n\
+             \tleft: {}%;\n\
+             \ttop: {}%;\n\
+             \twidth: {}%;\n\
+             \theight: {}%;\n\
+             \tbox-sizing: border-box;\n\
+             }}\n\
+             .superchat-container {{\n\
+             \tzoom: {};\n\
+             }}\n",
+            self.name.replace("*/", ""),
+            self.x_percent,
+            self.y_percent,
+            self.width_percent,
+            self.height_percent,
+            f64::from(self.scale_percent) / 100.0
+        );
+        let hidden = [
+            (self.show_chat, "yt-live-chat-text-message-renderer"),
+            (self.show_superchat, "yt-live-chat-paid-message-renderer"),
+            (self.show_timestamp, "#timestamp"),
+        ];
+        for (_, selector) in hidden.iter().filter(|(shown, _)| !shown) {
+            css.push_str(&format!(
+                "{} {{\n\tdisplay: none !important;\n}}\n",
+                selector
+            ));
+        }
+        css
+    }
+}
+
+/// ## 保存済みのレイアウトとアクティブなレイアウト
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObsLayoutState {
+    /// アクティブなレイアウトの名前
+    pub active: String,
+    /// 名前順のレイアウト
+    pub layouts: BTreeMap<String, ObsLayout>,
+}
+
+impl Default for ObsLayoutState {
+    fn default() -> Self {
+        let layout = ObsLayout::default();
+        Self {
+            active: layout.name.clone(),
+            layouts: BTreeMap::from([(layout.name.clone(), layout)]),
+        }
+    }
+}
+
+impl ObsLayoutState {
+    /// ## DBから復元したレイアウトを検証する
+    ///
+    /// 不正なレイアウトと保存数の上限を超えるレイアウトを除き、デフォルトレイアウトを補います。
+    /// アクティブなレイアウトが見つからない場合はデフォルトレイアウトに切り替えます。
+    ///
+    /// ### Returns
+    /// - `Self`: 検証後のレイアウト
+    pub fn sanitized(self) -> Self {
+        let mut layouts: BTreeMap<String, ObsLayout> = self
+            .layouts
+            .into_iter()
+            .filter(|(name, layout)| *name == layout.name && layout.validate().is_ok())
+            .collect();
+        layouts
+            .entry(DEFAULT_OBS_LAYOUT_NAME.to_string())
+            .or_default();
+        while layouts.len() > MAX_OBS_LAYOUTS {
+            let Some(name) = layouts
+                .keys()
+                .rev()
+                .find(|name| *name != DEFAULT_OBS_LAYOUT_NAME && **name != self.active)
+                .cloned()
+            else {
+                break;
+            };
+            layouts.remove(&name);
+        }
+        let active = if layouts.contains_key(&self.active) {
+            self.active
+        } else {
+            DEFAULT_OBS_LAYOUT_NAME.to_string()
+        };
+        Self { active, layouts }
+    }
+
+    /// ## アクティブなレイアウトを取得する
+    ///
+    /// ### Returns
+    /// - `ObsLayout`: アクティブなレイアウト（見つからない場合はデフォルトレイアウト）
+    pub fn active_layout(&self) -> ObsLayout {
+        self.layouts.get(&self.active).cloned().unwrap_or_default()
+    }
+
+    /// ## レイアウトを保存する
+    ///
+    /// 同名のレイアウトがある場合は上書きします。
+    ///
+    /// ### Arguments
+    /// - `layout`: 保存するレイアウト（検証済み）
+    ///
+    /// ### Returns
+    /// - `Result<bool, String>`: アクティブなレイアウトを更新した場合は `true`、保存数の上限を超える場合はエラーメッセージ
+    pub fn save(&mut self, layout: ObsLayout) -> Result<bool, String> {
+        if !self.layouts.contains_key(&layout.name) && self.layouts.len() >= MAX_OBS_LAYOUTS {
+            return Err(format!("レイアウトは{}件まで保存できます", MAX_OBS_LAYOUTS));
+        }
+        let is_active = layout.name == self.active;
+        self.layouts.insert(layout.name.clone(), layout);
+        Ok(is_active)
+    }
+
+    /// ## レイアウトを削除する
+    ///
+    /// アクティブなレイアウトを削除した場合は、デフォルトレイアウトに切り替えます。
+    ///
+    /// ### Arguments
+    /// - `name`: 削除するレイアウトの名前
+    ///
+    /// ### Returns
+    /// - `Result<bool, String>`: アクティブなレイアウトが切り替わった場合は `true`、削除できない場合はエラーメッセージ
+    pub fn delete(&mut self, name: &str) -> Result<bool, String> {
+        if name == DEFAULT_OBS_LAYOUT_NAME {
+            return Err("デフォルトレイアウトは削除できません".to_string());
+        }
+        if self.layouts.remove(name).is_none() {
+            return Err(format!("レイアウトが見つかりません: {}", name));
+        }
+        if self.active != name {
+            return Ok(false);
+        }
+        self.active = DEFAULT_OBS_LAYOUT_NAME.to_string();
+        self.layouts.entry(self.active.clone()).or_default();
+        Ok(true)
+    }
+
+    /// ## アクティブなレイアウトを切り替える
+    ///
+    /// ### Arguments
+    /// - `name`: 切り替え先のレイアウトの名前
+    ///
+    /// ### Returns
+    /// - `Result<ObsLayout, String>`: 切り替え後のレイアウト、見つからない場合はエラーメッセージ
+    pub fn activate(&mut self, name: &str) -> Result<ObsLayout, String> {
+        let layout = self
+            .layouts
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("レイアウトが見つかりません: {}", name))?;
+        self.active = layout.name.clone();
+        Ok(layout)
+    }
+}
+
+/// ## レイアウトの切り替えを接続中のOBS表示に通知する
+///
+/// OBS側は通知を受けると `/obs/layout.css` を読み込み直します。視聴者には送信しません。
+///
+/// ### Arguments
+/// - `layout`: 切り替え後のレイアウト
+pub fn broadcast_layout(layout: &ObsLayout) {
+    let notice = OutgoingMessage::ObsLayout {
+        layout: layout.clone(),
+    };
+    match serde_json::to_string(&notice) {
+        Ok(json) => get_manager().broadcast_to_obs(&json),
+        Err(e) => eprintln!("OBSレイアウト通知のシリアライズに失敗: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_obs_layout_state() {
+        let mut state = ObsLayoutState::default();
+        let game = ObsLayout {
+            name: "game".to_string(),
+            x_percent: 70.0,
+            width_percent: 30.0,
+            show_chat: false,
+            ..ObsLayout::default()
+        };
+        assert!(game.validate().is_ok());
+        assert!(!state.save(game).unwrap());
+
+        assert_eq!(state.activate("game").unwrap().x_percent, 70.0);
+        let css = state.active_layout().to_css();
+        assert!(css.contains("left: 70%;"));
+        assert!(css.contains("yt-live-chat-text-message-renderer {\n\tdisplay: none"));
+        assert!(!css.contains("yt-live-chat-paid-message-renderer"));
+
+        // アクティブなレイアウトを削除するとデフォルトに戻り、デフォルトは削除できない
+        assert!(state.delete("game").unwrap());
+        assert_eq!(state.active, DEFAULT_OBS_LAYOUT_NAME);
+        assert!(state.delete(DEFAULT_OBS_LAYOUT_NAME).is_err());
+        assert!(state.activate("game").is_err());
+    }
+
+    /// DBから復元したレイアウトの検証のテスト
+    #[test]
+    fn test_obs_layout_state_sanitized() {
+        let mut state = ObsLayoutState::default();
+        let talk = ObsLayout {
+            name: "talk".to_string(),
+            ..ObsLayout::default()
+        };
+        state.save(talk.clone()).unwrap();
+        state.activate("talk").unwrap();
+
+        // 保存済みのレイアウトはJSONを経由しても変わらない
+        let json = serde_json::to_string(&state).unwrap();
+        let restored: ObsLayoutState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.clone().sanitized(), state);
+
+        // 不正なレイアウトを除き、アクティブなレイアウトが無い場合はデフォルトに戻す
+        let mut broken = restored;
+        broken.layouts.remove(DEFAULT_OBS_LAYOUT_NAME);
+        broken.layouts.insert(
+            "talk".to_string(),
+            ObsLayout {
+                scale_percent: 0,
+                ..talk
+            },
+        );
+        let sanitized = broken.sanitized();
+        assert_eq!(sanitized, ObsLayoutState::default());
+    }
+}
@@ -0,0 +1,149 @@
+//! OBSオーバーレイのテーマ管理モジュール
+//!
+//! OBS表示の背景色・文字色・スーパーチャットの強調色・フォントサイズをアプリから変更できるようにします。
+//! テーマはOBSサーバーの `/obs/styles.css` に既定のスタイルの後ろへ埋め込んで配信し、
+//! 変更時は接続中のOBS表示に `type: "theme_updated"` メッセージで通知します。
+
+use crate::types::OutgoingMessage;
+use crate::ws_server::connection_manager::global::get_manager;
+use serde::{Deserialize, Serialize};
+
+/// 設定可能なフォントサイズの範囲（px）
+pub const OBS_THEME_FONT_SIZE_RANGE: std::ops::RangeInclusive<u32> = 8..=72;
+
+/// ## OBS表示のテーマ
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObsTheme {
+    /// 通常チャットの背景色（#RGB / #RRGGBB / #RRGGBBAA）
+    pub background_color: String,
+    /// 通常チャットの文字色（#RGB / #RRGGBB / #RRGGBBAA）
+    pub text_color: String,
+    /// スーパーチャットの強調色（#RGB / #RRGGBB / #RRGGBBAA）
+    pub superchat_color: String,
+    /// メッセージのフォントサイズ（px）
+    pub font_size: u32,
+}
+
+impl Default for ObsTheme {
+    /// `styles.css` の既定値と同じテーマ
+    fn default() -> Self {
+        Self {
+            background_color: "#ffffff".to_string(),
+            text_color: "#333333".to_string(),
+            superchat_color: "#62c1de".to_string(),
+            font_size: 16,
+        }
+    }
+}
+
+impl ObsTheme {
+    /// ## テーマの設定値を検証する
+    ///
+    /// CSSにそのまま埋め込むため、色は16進数のカラーコードのみを受け付けます。
+    ///
+    /// ### Returns
+    /// - `Result<(), String>`: 不正な値が含まれる場合はエラーメッセージ
+    pub fn validate(&self) -> Result<(), String> {
+        for (label, color) in [
+            ("背景色", &self.background_color),
+            ("文字色", &self.text_color),
+            ("スーパーチャットの強調色", &self.superchat_color),
+        ] {
+            if !is_hex_color(color) {
+                return Err(format!(
+                    "{}は #RRGGBB 形式のカラーコードで指定してください: {}",
+                    label, color
+                ));
+            }
+        }
+        if !OBS_THEME_FONT_SIZE_RANGE.contains(&self.font_size) {
+            return Err(format!(
+                "フォントサイズは{}〜{}pxで指定してください",
+                OBS_THEME_FONT_SIZE_RANGE.start(),
+                OBS_THEME_FONT_SIZE_RANGE.end()
+            ));
+        }
+        Ok(())
+    }
+
+    /// ## テーマをOBS表示用のCSSに変換する
+    ///
+    /// `styles.css` の後ろに連結され、カスタマイズ用の変数とフォントサイズを上書きします。
+    ///
+    /// ### Returns
+    /// - `String`: CSS
+    pub fn to_css(&self) -> String {
+        format!(
+            "/* SUIperCHAT OBSテーマ */\n\
+             :root {{\n\
+             \t--listener-comment-bg: {background};\n\
+             \t--listener-comment: {text};\n\
+             \t--superchat-name-bg: {superchat};\n\
+             \t--superchat-comment-bg: {superchat};\n\
+             }}\n\
+             yt-live-chat-paid-message-renderer *,\n\
+             yt-live-chat-text-message-renderer *,\n\
+             yt-live-chat-text-message-renderer #author-name,\n\
+             yt-live-chat-paid-message-renderer #content.yt-live-chat-paid-message-renderer {{\n\
+             \tfont-size: {font_size}px !important;\n\
+             }}\n",
+            background = self.background_color,
+            text = self.text_color,
+            superchat = self.superchat_color,
+            font_size = self.font_size
+        )
+    }
+}
+
+/// `#RGB` / `#RRGGBB` / `#RRGGBBAA` 形式のカラーコードか判定する
+fn is_hex_color(value: &str) -> bool {
+    value.strip_prefix('#').is_some_and(|hex| {
+        matches!(hex.len(), 3 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+    })
+}
+
+/// ## テーマの変更を接続中のOBS表示に通知する
+///
+/// OBS側は通知を受けて `/obs/styles.css` を読み込み直します。視聴者には送信しません。
+///
+/// ### Arguments
+/// - `theme`: 変更後のテーマ
+pub fn broadcast_theme(theme: &ObsTheme) {
+    let notice = OutgoingMessage::ThemeUpdated {
+        theme: theme.clone(),
+    };
+    match serde_json::to_string(&notice) {
+        Ok(json) => get_manager().broadcast_to_obs(&json),
+        Err(e) => eprintln!("OBSテーマ通知のシリアライズに失敗: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_obs_theme() {
+        let theme = ObsTheme {
+            superchat_color: "#FF0000".to_string(),
+            font_size: 24,
+            ..ObsTheme::default()
+        };
+        assert!(theme.validate().is_ok());
+        let css = theme.to_css();
+        assert!(css.contains("--superchat-comment-bg: #FF0000;"));
+        assert!(css.contains("font-size: 24px !important;"));
+
+        // CSSに埋め込めない値は拒否する
+        let invalid = ObsTheme {
+            text_color: "red; } body { display: none".to_string(),
+            ..ObsTheme::default()
+        };
+        assert!(invalid.validate().is_err());
+        let invalid = ObsTheme {
+            font_size: 100,
+            ..ObsTheme::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
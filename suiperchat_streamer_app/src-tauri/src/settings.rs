@@ -0,0 +1,422 @@
+//! アプリ設定の永続化モジュール
+//!
+//! ウォレットアドレスやYouTube動画ID、最大接続数、OBSレイアウトなどの設定をDBの `settings` テーブルに保存し、
+//! 起動時に `AppState` へ復元します。配信者がアプリを再起動するたびに設定し直す必要をなくします。
+//! アクセストークンや翻訳APIのキーなどの機微情報は保存しません。
+//!
+//! 書き込みは保存待ちの設定にまとめ、1つのタスクがトランザクションで順に書き込むため、
+//! 連続した変更が前後して保存されることはありません。DB接続の初期化前に変更された設定は、
+//! 初期化後に保存されます。
+
+use crate::database;
+use crate::obs_layout::ObsLayoutState;
+use crate::state::AppState;
+use crate::validation;
+use crate::wallet_registry::WalletEntry;
+use sqlx::sqlite::SqlitePool;
+use std::collections::BTreeMap;
+use tauri::{Emitter, Manager};
+
+/// 登録済みのウォレット（JSON配列）の設定キー
+pub const WALLETS_KEY: &str = "wallets";
+
+/// アクティブなウォレットのインデックスの設定キー
+pub const ACTIVE_WALLET_INDEX_KEY: &str = "active_wallet_index";
+
+/// ウォレットアドレス未設定時に起動を拒否するかどうかの設定キー
+pub const REQUIRE_WALLET_KEY: &str = "require_wallet";
+
+/// YouTube動画IDの設定キー
+pub const YOUTUBE_VIDEO_ID_KEY: &str = "youtube_video_id";
+
+/// 最大接続数の設定キー
+pub const MAX_CONNECTIONS_KEY: &str = "max_connections";
+
+/// 保存済みのOBSレイアウトとアクティブなレイアウト（JSON）の設定キー
+pub const OBS_LAYOUTS_KEY: &str = "obs_layouts";
+
+/// 保存しない機微情報の設定キー
+const SENSITIVE_KEYS: &[&str] = &["access_token", "translation_api_key"];
+
+/// ## 保存待ちの設定
+#[derive(Debug, Default)]
+pub struct PendingSettings {
+    /// 設定キーごとの保存待ちの値（同じキーは最後の値のみ保存する）
+    values: BTreeMap<&'static str, String>,
+    /// 保存待ちの設定を書き込むタスクが実行中かどうか
+    flushing: bool,
+}
+
+/// ## 設定をDBに保存する
+///
+/// 呼び出し元のコマンドをブロックしないよう、保存はバックグラウンドで行います。
+/// 機微情報のキーの場合は保存しません。
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+/// - `key`: 設定キー
+/// - `value`: 保存する値
+pub fn save_setting(app_state: &AppState, key: &'static str, value: String) {
+    save_settings(app_state, vec![(key, value)]);
+}
+
+/// ## 複数の設定をまとめてDBに保存する
+///
+/// 指定した設定は同じトランザクションで書き込みます。
+/// DB接続が初期化されていない場合は、初期化後に `flush_pending_settings` で保存します。
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+/// - `entries`: 設定キーと保存する値
+pub fn save_settings(app_state: &AppState, entries: Vec<(&'static str, String)>) {
+    let entries: Vec<_> = entries
+        .into_iter()
+        .filter(|(key, _)| {
+            let sensitive = SENSITIVE_KEYS.contains(key);
+            if sensitive {
+                eprintln!("機微情報のため設定を保存しません: {}", key);
+            }
+            !sensitive
+        })
+        .collect();
+    if entries.is_empty() {
+        return;
+    }
+    match app_state.pending_settings.lock() {
+        Ok(mut pending) => pending.values.extend(entries),
+        Err(e) => {
+            eprintln!("保存待ちの設定のロックに失敗しました: {}", e);
+            return;
+        }
+    }
+    flush_pending_settings(app_state);
+}
+
+/// ## 保存待ちの設定をDBに書き込む
+///
+/// 書き込みは1つのタスクで順に行い、実行中の場合は実行中のタスクがあわせて書き込みます。
+/// DB接続が初期化されていない場合は何もしません（DB接続の初期化後に呼び出す）。
+/// 書き込みに失敗した設定は、次回の保存時に再度書き込みます。
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+pub fn flush_pending_settings(app_state: &AppState) {
+    let Some(db_pool) = app_state.db_pool.lock().ok().and_then(|pool| pool.clone()) else {
+        println!("データベース接続が初期化されていないため、設定は初期化後に保存します");
+        return;
+    };
+    let pending_settings = app_state.pending_settings.clone();
+    match pending_settings.lock() {
+        Ok(mut pending) if !pending.flushing && !pending.values.is_empty() => {
+            pending.flushing = true;
+        }
+        _ => return,
+    }
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let values = match pending_settings.lock() {
+                Ok(mut pending) if pending.values.is_empty() => {
+                    pending.flushing = false;
+                    return;
+                }
+                Ok(mut pending) => std::mem::take(&mut pending.values),
+                Err(e) => {
+                    eprintln!("保存待ちの設定のロックに失敗しました: {}", e);
+                    return;
+                }
+            };
+
+            if let Err(e) = database::set_settings(&db_pool, &values).await {
+                eprintln!("設定の保存に失敗しました: {}", e);
+                // 書き込み中に変更された設定を優先し、それ以外は次回の保存時に再度書き込む
+                if let Ok(mut pending) = pending_settings.lock() {
+                    for (key, value) in values {
+                        pending.values.entry(key).or_insert(value);
+                    }
+                    pending.flushing = false;
+                }
+                return;
+            }
+        }
+    });
+}
+
+/// 保存待ちの設定キーか判定する（起動中に変更された設定を、保存済みの値で上書きしないために使用する）
+fn is_pending(app_state: &AppState, key: &str) -> bool {
+    app_state
+        .pending_settings
+        .lock()
+        .map(|pending| pending.values.contains_key(key))
+        .unwrap_or(false)
+}
+
+/// ## 登録済みのウォレットとアクティブなウォレットを保存する
+///
+/// 2つの設定は同じトランザクションで書き込みます。
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+pub fn save_wallets(app_state: &AppState) {
+    let Ok(wallets) = app_state.wallets.lock().map(|wallets| wallets.clone()) else {
+        return;
+    };
+    let Ok(active_index) = app_state.active_wallet_index.lock().map(|index| *index) else {
+        return;
+    };
+
+    let json = match serde_json::to_string(&wallets) {
+        Ok(json) => json,
+        Err(e) => {
+            eprintln!("ウォレットのシリアライズに失敗しました: {}", e);
+            return;
+        }
+    };
+    save_settings(
+        app_state,
+        vec![
+            (WALLETS_KEY, json),
+            (
+                ACTIVE_WALLET_INDEX_KEY,
+                active_index
+                    .map(|index| index.to_string())
+                    .unwrap_or_default(),
+            ),
+        ],
+    );
+}
+
+/// ## 保存済みのOBSレイアウトを保存する
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+pub fn save_obs_layouts(app_state: &AppState) {
+    let Ok(layouts) = app_state.obs_layouts.lock().map(|layouts| layouts.clone()) else {
+        return;
+    };
+    match serde_json::to_string(&layouts) {
+        Ok(json) => save_setting(app_state, OBS_LAYOUTS_KEY, json),
+        Err(e) => eprintln!("OBSレイアウトのシリアライズに失敗しました: {}", e),
+    }
+}
+
+/// ## 復元した設定のうち、`AppState` の外への反映が必要なもの
+#[derive(Debug, Default, PartialEq)]
+struct RestoredSettings {
+    /// ウォレットを復元したかどうか
+    wallets: bool,
+    /// YouTube動画IDを復元したかどうか
+    youtube_video_id: bool,
+    /// 復元した最大接続数
+    max_connections: Option<usize>,
+}
+
+/// ## 保存した設定を `AppState` に復元する
+///
+/// 起動時、DBの初期化後に呼び出します。形式が不正な値は無視します。
+/// 起動中に変更されて保存待ちの設定は、保存済みの値で上書きしません。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `pool`: SQLiteデータベース接続プール
+pub async fn restore_settings(app_handle: &tauri::AppHandle, pool: &SqlitePool) {
+    let restored = restore_into(&app_handle.state::<AppState>(), pool).await;
+
+    if restored.wallets {
+        if let Err(e) = app_handle.emit("wallet_address_updated", ()) {
+            eprintln!("Failed to emit wallet_address_updated event: {}", e);
+        }
+    }
+    if restored.youtube_video_id {
+        if let Err(e) = app_handle.emit("youtube_video_id_updated", ()) {
+            eprintln!("Failed to emit youtube_video_id_updated event: {}", e);
+        }
+    }
+    if let Some(max_connections) = restored.max_connections {
+        crate::ws_server::set_max_connections(max_connections);
+    }
+}
+
+/// ## 保存した設定を読み込み、`AppState` の設定を復元する
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+/// - `pool`: SQLiteデータベース接続プール
+///
+/// ### Returns
+/// - `RestoredSettings`: `AppState` の外への反映が必要な設定
+async fn restore_into(app_state: &AppState, pool: &SqlitePool) -> RestoredSettings {
+    let mut restored = RestoredSettings::default();
+    let get = |key: &'static str| async move {
+        if is_pending(app_state, key) {
+            println!("起動中に変更された設定のため復元しません: {}", key);
+            return None;
+        }
+        database::get_setting(pool, key).await.unwrap_or_else(|e| {
+            eprintln!("設定の読み込みに失敗しました ({}): {}", key, e);
+            None
+        })
+    };
+
+    // ウォレット
+    if let Some(json) = get(WALLETS_KEY).await {
+        let wallets: Vec<WalletEntry> = serde_json::from_str::<Vec<WalletEntry>>(&json)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|wallet| validation::validate_sui_address(&wallet.address).is_ok())
+            .collect();
+        let active_index = get(ACTIVE_WALLET_INDEX_KEY)
+            .await
+            .and_then(|index| index.parse::<usize>().ok())
+            .filter(|index| *index < wallets.len());
+        println!("保存されたウォレットを復元しました: {}件", wallets.len());
+        if let (Ok(mut wallets_guard), Ok(mut active_guard)) = (
+            app_state.wallets.lock(),
+            app_state.active_wallet_index.lock(),
+        ) {
+            *wallets_guard = wallets;
+            *active_guard = active_index;
+        }
+        restored.wallets = true;
+    }
+    if let Some(required) = get(REQUIRE_WALLET_KEY).await {
+        if let (Ok(required), Ok(mut guard)) =
+            (required.parse::<bool>(), app_state.require_wallet.lock())
+        {
+            *guard = required;
+        }
+    }
+
+    // YouTube動画ID
+    if let Some(video_id) = get(YOUTUBE_VIDEO_ID_KEY).await.filter(|id| !id.is_empty()) {
+        println!("保存されたYouTube動画IDを復元しました: {}", video_id);
+        if let Ok(mut guard) = app_state.youtube_video_id.lock() {
+            *guard = Some(video_id);
+        }
+        restored.youtube_video_id = true;
+    }
+
+    // OBSレイアウト
+    if let Some(layouts) = get(OBS_LAYOUTS_KEY)
+        .await
+        .and_then(|json| serde_json::from_str::<ObsLayoutState>(&json).ok())
+    {
+        let layouts = layouts.sanitized();
+        println!(
+            "保存されたOBSレイアウトを復元しました: {}件 (アクティブ: {})",
+            layouts.layouts.len(),
+            layouts.active
+        );
+        if let Ok(mut guard) = app_state.obs_layouts.lock() {
+            *guard = layouts;
+        }
+    }
+
+    // 最大接続数
+    restored.max_connections = get(MAX_CONNECTIONS_KEY)
+        .await
+        .and_then(|max| max.parse::<usize>().ok())
+        .filter(|max| *max >= 1);
+    if let Some(max_connections) = restored.max_connections {
+        println!("保存された最大接続数を復元しました: {}", max_connections);
+    }
+
+    restored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CREATE_SETTINGS_TABLE_SQL;
+
+    /// 保存した設定の復元のテスト
+    #[sqlx::test]
+    async fn test_restore_into(pool: SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query(CREATE_SETTINGS_TABLE_SQL)
+            .execute(&pool)
+            .await?;
+
+        let address = format!("0x{}", "a".repeat(64));
+        let wallets = serde_json::json!([
+            {"label": "メイン", "address": address},
+            {"label": "不正", "address": "0x123"},
+        ]);
+        let settings = BTreeMap::from([
+            (WALLETS_KEY, wallets.to_string()),
+            (ACTIVE_WALLET_INDEX_KEY, "0".to_string()),
+            (REQUIRE_WALLET_KEY, "true".to_string()),
+            (YOUTUBE_VIDEO_ID_KEY, "dQw4w9WgXcQ".to_string()),
+            (MAX_CONNECTIONS_KEY, "0".to_string()),
+            (
+                OBS_LAYOUTS_KEY,
+                serde_json::json!({
+                    "active": "game",
+                    "layouts": {"game": {"name": "game", "scale_percent": 0}},
+                })
+                .to_string(),
+            ),
+        ]);
+        database::set_settings(&pool, &settings).await?;
+
+        // 不正なウォレット・最大接続数は復元しない
+        let app_state = AppState::new();
+        let restored = restore_into(&app_state, &pool).await;
+        assert_eq!(
+            restored,
+            RestoredSettings {
+                wallets: true,
+                youtube_video_id: true,
+                max_connections: None,
+            }
+        );
+        let wallets = app_state.wallets.lock().unwrap().clone();
+        assert_eq!(wallets.len(), 1);
+        assert_eq!(wallets[0].address, address);
+        assert_eq!(*app_state.active_wallet_index.lock().unwrap(), Some(0));
+        assert!(*app_state.require_wallet.lock().unwrap());
+        assert_eq!(
+            app_state.youtube_video_id.lock().unwrap().as_deref(),
+            Some("dQw4w9WgXcQ")
+        );
+        // 形式が不正なOBSレイアウトは復元しない
+        assert_eq!(
+            *app_state.obs_layouts.lock().unwrap(),
+            ObsLayoutState::default()
+        );
+
+        // 保存したOBSレイアウトを復元する
+        let mut layouts = ObsLayoutState::default();
+        layouts
+            .save(crate::obs_layout::ObsLayout {
+                name: "game".to_string(),
+                x_percent: 70.0,
+                ..Default::default()
+            })
+            .unwrap();
+        layouts.activate("game").unwrap();
+        database::set_setting(
+            &pool,
+            OBS_LAYOUTS_KEY,
+            &serde_json::to_string(&layouts).unwrap(),
+        )
+        .await?;
+        let app_state = AppState::new();
+        restore_into(&app_state, &pool).await;
+        assert_eq!(*app_state.obs_layouts.lock().unwrap(), layouts);
+
+        // 起動中に変更されて保存待ちの設定は上書きしない
+        database::set_setting(&pool, MAX_CONNECTIONS_KEY, "80").await?;
+        let app_state = AppState::new();
+        save_setting(&app_state, YOUTUBE_VIDEO_ID_KEY, "abcdefghijk".to_string());
+        *app_state.youtube_video_id.lock().unwrap() = Some("abcdefghijk".to_string());
+        let restored = restore_into(&app_state, &pool).await;
+        assert!(!restored.youtube_video_id);
+        assert_eq!(restored.max_connections, Some(80));
+        assert_eq!(
+            app_state.youtube_video_id.lock().unwrap().as_deref(),
+            Some("abcdefghijk")
+        );
+
+        Ok(())
+    }
+}
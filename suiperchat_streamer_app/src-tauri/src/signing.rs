@@ -0,0 +1,294 @@
+//! ブロードキャストメッセージの署名モジュール
+//!
+//! 中間者やトンネル経路での改ざんを視聴者フロントが検出できるよう、
+//! サーバーが送信するJSONメッセージにEd25519の署名を付与します。
+//!
+//! 署名対象は `signature` フィールドを付与する前のJSONテキストのSHA-256ハッシュです。
+//! `signature` は常にJSONオブジェクトの末尾に付与されるため、視聴者フロントは受信したテキストの
+//! 末尾の `,"signature":"..."` を取り除くことで署名対象のテキストを復元できます。
+//! 公開鍵は `GET /info` で配布します。バイナリ（protobuf）モードのフレームは署名されません。
+//!
+//! 鍵ペアはアプリデータディレクトリにPKCS#8形式で保存し、次回起動時も同じ鍵を使用します。
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+/// 署名鍵の保存ファイル名（アプリデータディレクトリ配下）
+pub const SIGNING_KEY_FILE: &str = "message_signing_key.pk8";
+
+/// 署名アルゴリズム名（視聴者フロントへの通知用）
+pub const SIGNATURE_ALGORITHM: &str = "ed25519";
+
+/// 署名を付与するJSONフィールドの接頭辞
+const SIGNATURE_FIELD_PREFIX: &str = ",\"signature\":\"";
+
+/// ## 署名するメッセージの範囲
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningMode {
+    /// 署名しない
+    #[default]
+    Off,
+    /// スーパーチャットなど高優先のメッセージのみ署名する
+    Important,
+    /// 全てのブロードキャストメッセージに署名する
+    All,
+}
+
+/// ## 署名の設定と公開鍵
+///
+/// 視聴者フロントへの公開鍵の配布と、配信者向けの設定表示に使用します。
+#[derive(Debug, Clone, Serialize)]
+pub struct SigningInfo {
+    /// 署名するメッセージの範囲
+    pub mode: SigningMode,
+    /// 署名アルゴリズム ("ed25519")
+    pub algorithm: &'static str,
+    /// Base64エンコードした公開鍵（鍵が未読み込みの場合はNone）
+    pub public_key: Option<String>,
+    /// 鍵の識別子（鍵が未読み込みの場合はNone）
+    pub key_id: Option<String>,
+}
+
+impl SigningInfo {
+    /// ## 署名の範囲と鍵から作成する
+    ///
+    /// ### Arguments
+    /// - `mode`: 署名するメッセージの範囲
+    /// - `signer`: 署名鍵
+    pub fn new(mode: SigningMode, signer: Option<&MessageSigner>) -> Self {
+        Self {
+            mode,
+            algorithm: SIGNATURE_ALGORITHM,
+            public_key: signer.map(|signer| signer.public_key().to_string()),
+            key_id: signer.map(|signer| signer.key_id().to_string()),
+        }
+    }
+}
+
+/// ## メッセージ署名用の鍵ペア
+#[derive(Debug)]
+pub struct MessageSigner {
+    /// Ed25519の鍵ペア
+    key_pair: Ed25519KeyPair,
+    /// Base64エンコードした公開鍵
+    public_key: String,
+    /// 鍵の識別子（公開鍵のSHA-256ハッシュの先頭8バイトの16進表記）
+    key_id: String,
+}
+
+impl MessageSigner {
+    /// ## PKCS#8形式の秘密鍵から作成する
+    ///
+    /// ### Arguments
+    /// - `pkcs8`: PKCS#8形式の秘密鍵
+    ///
+    /// ### Returns
+    /// - `Result<Self, String>`: 鍵ペア、または鍵の形式が不正な場合はエラーメッセージ
+    pub fn from_pkcs8(pkcs8: &[u8]) -> Result<Self, String> {
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8)
+            .map_err(|e| format!("署名鍵の読み込みに失敗しました: {}", e))?;
+        let public_key_bytes = key_pair.public_key().as_ref();
+        let key_id = Sha256::digest(public_key_bytes)[..8]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        Ok(Self {
+            public_key: BASE64.encode(public_key_bytes),
+            key_id,
+            key_pair,
+        })
+    }
+
+    /// ## 保存済みの鍵ペアを読み込む（存在しない場合は生成して保存する）
+    ///
+    /// ### Arguments
+    /// - `path`: 鍵の保存先
+    ///
+    /// ### Returns
+    /// - `Result<Self, String>`: 鍵ペア、またはエラーメッセージ
+    pub fn load_or_generate(path: &Path) -> Result<Self, String> {
+        match std::fs::read(path) {
+            Ok(pkcs8) => Self::from_pkcs8(&pkcs8),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::rotate(path),
+            Err(e) => Err(format!(
+                "署名鍵の読み込みに失敗しました ({}): {}",
+                path.display(),
+                e
+            )),
+        }
+    }
+
+    /// ## 新しい鍵ペアを生成して保存する
+    ///
+    /// 既存の鍵は上書きされます。ローテーション後は視聴者フロントが公開鍵を再取得する必要があります。
+    ///
+    /// ### Arguments
+    /// - `path`: 鍵の保存先
+    ///
+    /// ### Returns
+    /// - `Result<Self, String>`: 生成した鍵ペア、またはエラーメッセージ
+    pub fn rotate(path: &Path) -> Result<Self, String> {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map_err(|e| format!("署名鍵の生成に失敗しました: {}", e))?;
+        let signer = Self::from_pkcs8(pkcs8.as_ref())?;
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("署名鍵の保存先の作成に失敗しました: {}", e))?;
+        }
+        std::fs::write(path, pkcs8.as_ref())
+            .map_err(|e| format!("署名鍵の保存に失敗しました ({}): {}", path.display(), e))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Err(e) = std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)) {
+                eprintln!("署名鍵のパーミッション設定に失敗しました: {}", e);
+            }
+        }
+
+        println!("メッセージ署名鍵を生成しました: key_id={}", signer.key_id);
+        Ok(signer)
+    }
+
+    /// ## Base64エンコードした公開鍵を取得する
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    /// ## 鍵の識別子を取得する
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// ## ペイロードのSHA-256ハッシュに署名する
+    ///
+    /// ### Arguments
+    /// - `payload`: 署名対象のバイト列
+    ///
+    /// ### Returns
+    /// - `String`: Base64エンコードした署名
+    pub fn sign(&self, payload: &[u8]) -> String {
+        BASE64.encode(self.key_pair.sign(&Sha256::digest(payload)))
+    }
+
+    /// ## JSONオブジェクトの末尾に署名を付与する
+    ///
+    /// ### Arguments
+    /// - `json`: 署名するJSONオブジェクトのテキスト
+    ///
+    /// ### Returns
+    /// - `String`: `signature` フィールドを付与したJSONテキスト（空でないオブジェクトでない場合はそのまま）
+    pub fn sign_json(&self, json: &str) -> String {
+        let Some(body) = json.strip_suffix('}').filter(|body| body.trim() != "{") else {
+            return json.to_string();
+        };
+        format!(
+            "{}{}{}\"}}",
+            body,
+            SIGNATURE_FIELD_PREFIX,
+            self.sign(json.as_bytes())
+        )
+    }
+}
+
+/// ## 署名付きJSONから署名対象のテキストと署名を取り出す
+///
+/// ### Arguments
+/// - `signed_json`: `sign_json` で署名を付与したJSONテキスト
+///
+/// ### Returns
+/// - `Option<(String, String)>`: 署名対象のJSONテキストとBase64エンコードした署名（署名がない場合はNone）
+pub fn split_signed_json(signed_json: &str) -> Option<(String, String)> {
+    let body = signed_json.strip_suffix("\"}")?;
+    let index = body.rfind(SIGNATURE_FIELD_PREFIX)?;
+    let signature = &body[index + SIGNATURE_FIELD_PREFIX.len()..];
+    Some((format!("{}}}", &body[..index]), signature.to_string()))
+}
+
+/// ## 署名を検証する
+///
+/// ### Arguments
+/// - `public_key`: Base64エンコードした公開鍵
+/// - `payload`: 署名対象のバイト列
+/// - `signature`: Base64エンコードした署名
+///
+/// ### Returns
+/// - `bool`: 署名が正しい場合は `true`
+pub fn verify_signature(public_key: &str, payload: &[u8], signature: &str) -> bool {
+    let (Ok(public_key), Ok(signature)) = (BASE64.decode(public_key), BASE64.decode(signature))
+    else {
+        return false;
+    };
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(&Sha256::digest(payload), &signature)
+        .is_ok()
+}
+
+/// ## 署名鍵の保存先を取得する
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+///
+/// ### Returns
+/// - `Result<PathBuf, String>`: アプリデータディレクトリ配下の保存先、またはエラーメッセージ
+pub fn key_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app_handle
+        .path()
+        .app_data_dir()
+        .map(|dir| dir.join(SIGNING_KEY_FILE))
+        .map_err(|e| format!("アプリデータディレクトリの取得に失敗しました: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 署名付きJSONから復元したテキストが公開鍵で検証でき、改ざんを検出できることを確認
+    #[test]
+    fn test_sign_and_verify_json() {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let signer = MessageSigner::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let json = r#"{"type":"superchat","message":"こんにちは","amount":1.5}"#;
+
+        let signed = signer.sign_json(json);
+        let parsed: serde_json::Value = serde_json::from_str(&signed).unwrap();
+        assert_eq!(parsed["message"], "こんにちは");
+
+        let (payload, signature) = split_signed_json(&signed).unwrap();
+        assert_eq!(payload, json);
+        assert_eq!(parsed["signature"], signature.as_str());
+        assert!(verify_signature(
+            signer.public_key(),
+            payload.as_bytes(),
+            &signature
+        ));
+
+        // 改ざんされたメッセージや別の鍵では検証に失敗する
+        let tampered = payload.replace("1.5", "15");
+        assert!(!verify_signature(
+            signer.public_key(),
+            tampered.as_bytes(),
+            &signature
+        ));
+        let other_pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let other = MessageSigner::from_pkcs8(other_pkcs8.as_ref()).unwrap();
+        assert!(!verify_signature(
+            other.public_key(),
+            payload.as_bytes(),
+            &signature
+        ));
+
+        // 同じ秘密鍵からは同じ公開鍵と識別子が得られる
+        let reloaded = MessageSigner::from_pkcs8(pkcs8.as_ref()).unwrap();
+        assert_eq!(reloaded.public_key(), signer.public_key());
+        assert_eq!(reloaded.key_id(), signer.key_id());
+    }
+}
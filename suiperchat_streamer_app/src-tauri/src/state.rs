@@ -1,7 +1,31 @@
-use crate::ws_server::tunnel::{TunnelError, TunnelInfo};
+use crate::badges::{BadgeConfig, ViewerProfileCache};
+use crate::coin_registry::{self, CoinInfo};
+use crate::db_models::{Message, ViewerCountPoint};
+use crate::db_vacuum::VacuumState;
+use crate::message_limits::MessageLimits;
+use crate::milestone::MilestoneState;
+use crate::moderation::SuperchatModeration;
+use crate::obs_layout::ObsLayoutState;
+use crate::obs_theme::ObsTheme;
+use crate::settings::PendingSettings;
+use crate::stream_sessions::SessionMap;
+use crate::sui_watcher::SuiWatcherConfig;
+use crate::superchat_alert::SuperchatAlertConfig;
+use crate::translation::{TranslationApiKey, TranslationConfig};
+use crate::types::{MigrationPhase, StartupProgress};
+use crate::wallet_registry::WalletEntry;
+use crate::ws_server::compression::DEFAULT_COMPRESSION_LEVEL;
+use crate::ws_server::human_verification::DEFAULT_POW_DIFFICULTY;
+use crate::ws_server::server_manager::IdleShutdownConfig;
+use crate::ws_server::server_utils::DEFAULT_BIND_HOST;
+use crate::ws_server::tls::TlsConfig;
+use crate::ws_server::tunnel::{TunnelError, TunnelInfo, TunnelKind, TunnelProtocol};
+use crate::ws_server::tx_verification::TxVerificationConfig;
 use actix_web::dev::ServerHandle;
 use sqlx::sqlite::SqlitePool;
+use std::collections::VecDeque;
 use std::net::IpAddr;
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
 use tokio::runtime::Handle as TokioHandle;
 
@@ -23,7 +47,16 @@ pub struct AppState {
     /// WebSocket サーバースレッドで使用される Tokio ランタイムへのハンドル。
     /// サーバー停止時にこのハンドルを使って非同期タスクを spawn する。
     pub runtime_handle: Arc<Mutex<Option<TokioHandle>>>,
-    pub wallet_address: Arc<Mutex<Option<String>>>,
+    /// 登録済みの配信者ウォレット（ラベル付きアドレスのリスト）
+    pub wallets: Arc<Mutex<Vec<WalletEntry>>>,
+    /// アクティブなウォレットの `wallets` 内のインデックス
+    ///
+    /// ウォレットが未登録の場合は `None`
+    pub active_wallet_index: Arc<Mutex<Option<usize>>>,
+    /// ウォレットアドレス未設定時にサーバーの起動を拒否するかどうか
+    ///
+    /// `false`（デフォルト）の場合は警告をログに出力して起動を許可します。
+    pub require_wallet: Arc<Mutex<bool>>,
     /// WebSocketサーバーがリッスンしているホスト名
     pub host: Arc<Mutex<Option<String>>>,
     /// WebSocketサーバーがリッスンしているポート番号
@@ -34,10 +67,23 @@ pub struct AppState {
     ///
     /// データベースに接続済みの場合は `Some(pool)`、未接続の場合は `None`。
     pub db_pool: Arc<Mutex<Option<SqlitePool>>>,
-    /// 現在アクティブな配信セッションのID
+    /// データベースへの再接続処理中かどうかのフラグ
     ///
-    /// 配信中（WebSocketサーバー起動中）は `Some(session_id)`、未配信時は `None`。
-    pub current_session_id: Arc<Mutex<Option<String>>>,
+    /// 再接続中は新着メッセージを `db_pending_messages` に退避する
+    pub db_reconnecting: Arc<Mutex<bool>>,
+    /// 再接続完了後に保存するメッセージの待機キュー
+    pub db_pending_messages: Arc<Mutex<Vec<Message>>>,
+    /// 一時的なエラーで保存に失敗し、バックグラウンドで再保存するメッセージのキュー
+    pub db_retry_queue: Arc<Mutex<VecDeque<Message>>>,
+    /// DBへの保存待ちの設定（1つのタスクが順に書き込む）
+    pub pending_settings: Arc<Mutex<PendingSettings>>,
+    /// 現在アクティブな配信セッションのID（チャンネル名→セッションID）
+    ///
+    /// 配信中（WebSocketサーバー起動中）は少なくとも `PRIMARY_SESSION_CHANNEL` のセッションを含み、
+    /// 未配信時は空。同時配信ではチャンネルごとにセッションを追加する。
+    pub current_session_id: Arc<Mutex<SessionMap>>,
+    /// サーバー起動時に追加で配信セッションを作成するチャンネル
+    pub session_channels: Arc<Mutex<Vec<String>>>,
     /// 外部IPアドレス
     ///
     /// 外部IP取得に成功した場合は `Some(ip)`、失敗または未取得の場合は `None`
@@ -60,6 +106,132 @@ pub struct AppState {
     /// 設定されている場合は `Some(video_id)`、未設定の場合は `None`
     /// アプリ起動ごとにリセットされる一時的な値
     pub youtube_video_id: Arc<Mutex<Option<String>>>,
+    /// グレースフルリスタートの移行フェーズ
+    ///
+    /// 移行処理中でない場合は `MigrationPhase::Idle`
+    pub migration_phase: Arc<Mutex<MigrationPhase>>,
+    /// サーバー起動処理の進捗
+    ///
+    /// `ServerStatus` の `startup_phase` として通知される
+    pub startup_progress: Arc<Mutex<StartupProgress>>,
+    /// 最大接続数超過時に視聴者を誘導する代替URL
+    ///
+    /// 未設定の場合は従来通りエラーメッセージのみを送信して切断する
+    pub overflow_redirect_url: Arc<Mutex<Option<String>>>,
+    /// アプリ内TLS終端の設定
+    ///
+    /// 有効な場合はCloudflaredトンネルを使わず、WebSocketサーバーが wss:// で直接待ち受ける
+    pub tls_config: Arc<Mutex<TlsConfig>>,
+    /// スーパーチャット総額のマイルストーン設定と達成状況
+    pub milestones: Arc<Mutex<MilestoneState>>,
+    /// スーパーチャット受信アラートの設定（大口スーパーチャットの閾値）
+    pub superchat_alert: Arc<Mutex<SuperchatAlertConfig>>,
+    /// スーパーチャットへの自動お礼メッセージのテンプレート（未設定の場合は送信しない）
+    pub auto_thanks_template: Arc<Mutex<Option<String>>>,
+    /// 受信メッセージを転送するWebhookのURL
+    ///
+    /// 未設定の場合は転送しない
+    pub webhook_url: Arc<Mutex<Option<String>>>,
+    /// Webhookに通常チャットも転送するかどうか（`false` の場合はスーパーチャットのみ）
+    pub webhook_include_chat: Arc<Mutex<bool>>,
+    /// NGワード（小文字に正規化済み）
+    ///
+    /// いずれかを含む通常チャットは配信・保存されない
+    pub banned_words: Arc<Mutex<Vec<String>>>,
+    /// NGワードを含むスーパーチャットの扱い
+    pub superchat_moderation: Arc<Mutex<SuperchatModeration>>,
+    /// 受信メッセージの本文・表示名の文字数制限
+    pub message_limits: Arc<Mutex<MessageLimits>>,
+    /// 視聴者に付与する称号バッジの設定
+    pub badge_config: Arc<Mutex<BadgeConfig>>,
+    /// 称号判定用の視聴者プロフィールのキャッシュ
+    pub viewer_profile_cache: Arc<Mutex<ViewerProfileCache>>,
+    /// 保存済みのOBSレイアウトとアクティブなレイアウト
+    pub obs_layouts: Arc<Mutex<ObsLayoutState>>,
+    /// OBS表示の色・フォントサイズのテーマ
+    pub obs_theme: Arc<Mutex<ObsTheme>>,
+    /// cloudflaredの接続プロトコル設定
+    ///
+    /// デフォルトは `TunnelProtocol::Auto`（プロトコル未指定）
+    pub tunnel_protocol: Arc<Mutex<TunnelProtocol>>,
+    /// 省電力モード（視聴者ゼロが続いた場合のトンネル自動停止）の設定
+    pub idle_shutdown: Arc<Mutex<IdleShutdownConfig>>,
+    /// WebSocketメッセージの圧縮レベル（0〜9、0の場合は圧縮しない）
+    ///
+    /// 新しく接続したクライアントから適用されます。
+    pub ws_compression_level: Arc<Mutex<u32>>,
+    /// トンネルを提供するプロバイダ
+    ///
+    /// デフォルトは `TunnelKind::Cloudflared`（Cloudflare Quick Tunnel）
+    pub tunnel_provider: Arc<Mutex<TunnelKind>>,
+    /// ダウンロードするcloudflaredのバージョン
+    ///
+    /// Noneの場合は環境変数 `CLOUDFLARED_VERSION`、未設定なら最新版を使用します。
+    pub cloudflared_version: Arc<Mutex<Option<String>>>,
+    /// 固定したバージョンのリリースファイルの期待するSHA256（16進数）
+    ///
+    /// Noneの場合は環境変数 `CLOUDFLARED_SHA256`、既知のチェックサムの順に参照します。
+    pub cloudflared_sha256: Arc<Mutex<Option<String>>>,
+    /// サーバー起動時にトンネルを使用するかどうか
+    ///
+    /// `false` の場合はトンネルを起動せず、全インターフェースで待ち受けてLAN内にのみ公開する
+    pub use_tunnel: Arc<Mutex<bool>>,
+    /// サーバー起動時にUPnPによるポート開放を試みるかどうか
+    ///
+    /// 成功した場合はトンネルを起動せず、グローバルIPで直接公開する。失敗した場合はトンネルにフォールバックする
+    pub use_upnp: Arc<Mutex<bool>>,
+    /// 配信者が設定したWebSocketサーバーのポート
+    ///
+    /// 未設定の場合はデフォルトの8082を使用する
+    pub configured_ws_port: Arc<Mutex<Option<u16>>>,
+    /// 配信者が設定したOBSサーバーのポート
+    ///
+    /// 未設定の場合はデフォルトの8081を使用する
+    pub configured_obs_port: Arc<Mutex<Option<u16>>>,
+    /// サーバーをバインドするホスト
+    ///
+    /// デフォルトの `127.0.0.1` では配信者のPCからのみ接続できる。`0.0.0.0` を設定するとLANに公開する
+    pub bind_host: Arc<Mutex<String>>,
+    /// バインド失敗時に、ポートを掴んだままの前回インスタンスを終了して解放するかどうか
+    ///
+    /// 他のプロセスを終了するため、明示的に有効化された場合のみ動作する（デフォルトは無効）
+    pub auto_release_ports: Arc<Mutex<bool>>,
+    /// メッセージ送信前に視聴者へ人間検証（proof-of-work）を要求するかどうか
+    ///
+    /// 荒らし発生時のみ有効化する運用を想定している
+    pub require_human_verification: Arc<Mutex<bool>>,
+    /// 人間検証のproof-of-workの難易度（先頭の0ビット数）
+    pub human_verification_difficulty: Arc<Mutex<u8>>,
+    /// オンチェーン着金監視の設定
+    pub sui_watcher: Arc<Mutex<SuiWatcherConfig>>,
+    /// オンチェーン着金監視の停止フラグ
+    ///
+    /// 監視中の場合は `Some(flag)`、停止している場合は `None`
+    pub sui_watcher_stop: Arc<Mutex<Option<Arc<AtomicBool>>>>,
+    /// 視聴者が申告したスーパーチャットのトランザクション検証の設定
+    pub tx_verification: Arc<Mutex<TxVerificationConfig>>,
+    /// スーパーチャットに使用できるコインのレジストリ
+    pub supported_coins: Arc<Mutex<Vec<CoinInfo>>>,
+    /// スーパーチャットを受け付けるコインのシンボル（Noneの場合は対応コインをすべて受け付ける）
+    pub accepted_coins: Arc<Mutex<Option<Vec<String>>>>,
+    /// メッセージ翻訳の設定
+    pub translation: Arc<Mutex<TranslationConfig>>,
+    /// 翻訳APIのキー（メモリ上にのみ保持し、永続化しない）
+    pub translation_api_key: Arc<Mutex<Option<TranslationApiKey>>>,
+    /// メンテナンス予告のキャンセルフラグ
+    ///
+    /// 予告中の場合は `Some(flag)`、予告していない場合は `None`
+    pub maintenance_cancel: Arc<Mutex<Option<Arc<AtomicBool>>>>,
+    /// データベースのVACUUMの実行状況（最終実行時刻・削除行数）
+    pub vacuum_state: Arc<Mutex<VacuumState>>,
+    /// WebSocket接続に要求するアクセストークン
+    ///
+    /// `None` の場合は誰でも接続できる
+    pub access_token: Arc<Mutex<Option<String>>>,
+    /// 配信中の視聴者数の推移（古い順）
+    ///
+    /// 直近 `MAX_VIEWER_COUNT_SAMPLES` 点のみを保持し、セッション終了時にDBへ保存する
+    pub viewer_count_history: Arc<Mutex<Vec<ViewerCountPoint>>>,
 }
 
 impl AppState {
@@ -71,17 +243,65 @@ impl AppState {
         Self {
             server_handle: Arc::new(Mutex::new(None)),
             runtime_handle: Arc::new(Mutex::new(None)),
-            wallet_address: Arc::new(Mutex::new(None)),
+            wallets: Arc::new(Mutex::new(Vec::new())),
+            active_wallet_index: Arc::new(Mutex::new(None)),
+            require_wallet: Arc::new(Mutex::new(false)),
             host: Arc::new(Mutex::new(None)),
             port: Arc::new(Mutex::new(None)),
             obs_port: Arc::new(Mutex::new(None)),
             db_pool: Arc::new(Mutex::new(None)),
-            current_session_id: Arc::new(Mutex::new(None)),
+            db_reconnecting: Arc::new(Mutex::new(false)),
+            db_pending_messages: Arc::new(Mutex::new(Vec::new())),
+            db_retry_queue: Arc::new(Mutex::new(VecDeque::new())),
+            pending_settings: Arc::new(Mutex::new(PendingSettings::default())),
+            current_session_id: Arc::new(Mutex::new(SessionMap::new())),
+            session_channels: Arc::new(Mutex::new(Vec::new())),
             external_ip: Arc::new(Mutex::new(None)),
             global_ip_fetch_failed: Arc::new(Mutex::new(false)),
             cgnat_detected: Arc::new(Mutex::new(false)),
             tunnel_info: Arc::new(Mutex::new(None)),
             youtube_video_id: Arc::new(Mutex::new(None)),
+            migration_phase: Arc::new(Mutex::new(MigrationPhase::Idle)),
+            startup_progress: Arc::new(Mutex::new(StartupProgress::default())),
+            overflow_redirect_url: Arc::new(Mutex::new(None)),
+            tls_config: Arc::new(Mutex::new(TlsConfig::default())),
+            milestones: Arc::new(Mutex::new(MilestoneState::default())),
+            superchat_alert: Arc::new(Mutex::new(SuperchatAlertConfig::default())),
+            auto_thanks_template: Arc::new(Mutex::new(None)),
+            webhook_url: Arc::new(Mutex::new(None)),
+            webhook_include_chat: Arc::new(Mutex::new(false)),
+            banned_words: Arc::new(Mutex::new(Vec::new())),
+            superchat_moderation: Arc::new(Mutex::new(SuperchatModeration::default())),
+            message_limits: Arc::new(Mutex::new(MessageLimits::default())),
+            badge_config: Arc::new(Mutex::new(BadgeConfig::default())),
+            viewer_profile_cache: Arc::new(Mutex::new(ViewerProfileCache::default())),
+            obs_layouts: Arc::new(Mutex::new(ObsLayoutState::default())),
+            obs_theme: Arc::new(Mutex::new(ObsTheme::default())),
+            tunnel_protocol: Arc::new(Mutex::new(TunnelProtocol::default())),
+            idle_shutdown: Arc::new(Mutex::new(IdleShutdownConfig::default())),
+            ws_compression_level: Arc::new(Mutex::new(DEFAULT_COMPRESSION_LEVEL)),
+            tunnel_provider: Arc::new(Mutex::new(TunnelKind::default())),
+            cloudflared_version: Arc::new(Mutex::new(None)),
+            cloudflared_sha256: Arc::new(Mutex::new(None)),
+            use_tunnel: Arc::new(Mutex::new(true)),
+            use_upnp: Arc::new(Mutex::new(false)),
+            configured_ws_port: Arc::new(Mutex::new(None)),
+            configured_obs_port: Arc::new(Mutex::new(None)),
+            bind_host: Arc::new(Mutex::new(DEFAULT_BIND_HOST.to_string())),
+            auto_release_ports: Arc::new(Mutex::new(false)),
+            require_human_verification: Arc::new(Mutex::new(false)),
+            human_verification_difficulty: Arc::new(Mutex::new(DEFAULT_POW_DIFFICULTY)),
+            sui_watcher: Arc::new(Mutex::new(SuiWatcherConfig::default())),
+            sui_watcher_stop: Arc::new(Mutex::new(None)),
+            tx_verification: Arc::new(Mutex::new(TxVerificationConfig::default())),
+            supported_coins: Arc::new(Mutex::new(coin_registry::default_coins())),
+            accepted_coins: Arc::new(Mutex::new(None)),
+            translation: Arc::new(Mutex::new(TranslationConfig::default())),
+            translation_api_key: Arc::new(Mutex::new(None)),
+            maintenance_cancel: Arc::new(Mutex::new(None)),
+            vacuum_state: Arc::new(Mutex::new(VacuumState::default())),
+            access_token: Arc::new(Mutex::new(None)),
+            viewer_count_history: Arc::new(Mutex::new(Vec::new())),
         }
     }
 }
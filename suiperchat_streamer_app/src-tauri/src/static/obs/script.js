@@ -13,11 +13,16 @@ let socket = null;
 let reconnectTimeout = null;
 const reconnectInterval = 5000; // 再接続間隔（ミリ秒）
 const maxMessages = 100; // 画面に表示する最大メッセージ数（増やしました）
-const WS_PORT = 8082; // WebSocketサーバーのポート番号（固定）
+// WebSocketサーバーのポート番号（配信者がポートを変更した場合はURLの ws_port パラメータで指定される）
+const WS_PORT =
+	Number(new URLSearchParams(window.location.search).get("ws_port")) || 8082;
+// WebSocket接続のアクセストークン（配信者がトークンを設定した場合はURLの token パラメータで指定される）
+const ACCESS_TOKEN = new URLSearchParams(window.location.search).get("token");
 
 // メッセージ履歴管理用の変数
 const displayedMessageIds = new Set(); // 表示済みメッセージIDを追跡
 let isLoadingHistory = false; // 履歴読み込み中フラグ
+const milestoneDisplayDuration = 6000; // マイルストーン演出の表示時間（ミリ秒）
 
 // DOMロード時の初期化処理
 document.addEventListener("DOMContentLoaded", () => {
@@ -46,7 +51,9 @@ function initializeWebSocket() {
 	// const wsUrl = `${wsProtocol}//${wsHost}/ws`;
 
 	// 修正後: 正しいWebSocketサーバーのアドレスを直接指定
-	const wsUrl = `ws://127.0.0.1:${WS_PORT}/ws`;
+	const wsUrl = ACCESS_TOKEN
+		? `ws://127.0.0.1:${WS_PORT}/obs-ws?token=${encodeURIComponent(ACCESS_TOKEN)}`
+		: `ws://127.0.0.1:${WS_PORT}/obs-ws`;
 
 	console.log(`Connecting to WebSocket server: ${wsUrl}`);
 
@@ -107,6 +114,18 @@ function setupWebSocketEventHandlers() {
 			} else if (data.type === "HISTORY_DATA") {
 				// 履歴データメッセージを処理
 				handleHistoryData(data);
+			} else if (data.type === "System") {
+				// 自動お礼などのシステムメッセージを表示
+				displaySystemMessage(data);
+			} else if (data.type === "milestone_reached") {
+				// マイルストーン達成の祝福演出を表示
+				displayMilestoneCelebration(data);
+			} else if (data.type === "obs_layout") {
+				// OBSレイアウトの切り替えを反映
+				reloadLayoutStyles();
+			} else if (data.type === "theme_updated") {
+				// OBSテーマの変更を反映
+				reloadStylesheet("styles-css", "styles.css");
 			} else {
 				// その他のメッセージタイプの場合
 				console.log("Unknown message type received:", data);
@@ -483,6 +502,89 @@ function displaySuperchatMessage(data, isHistory = false) {
 	}
 }
 
+/**
+ * 自動お礼などのシステムメッセージを表示する
+ *
+ * @param {Object} data - システムメッセージ（message, timestamp）
+ */
+function displaySystemMessage(data) {
+	if (!data || !data.message) {
+		console.error("Invalid system message received:", data);
+		return;
+	}
+
+	const container = document.getElementById("superchat-container");
+	renderChatMessage(container, {
+		display_name: "SUIperCHAT",
+		message: data.message,
+		timestamp: data.timestamp ? Date.parse(data.timestamp) : Date.now(),
+	});
+}
+
+/**
+ * マイルストーン達成の祝福演出を表示する
+ *
+ * @param {Object} data - マイルストーン達成メッセージ（milestone, total, coin）
+ */
+function displayMilestoneCelebration(data) {
+	if (!data || typeof data.milestone !== "number") {
+		console.error("Invalid milestone data received:", data);
+		return;
+	}
+
+	const coin = data.coin || "SUI";
+	const celebration = document.createElement("div");
+	celebration.className = "milestone-celebration";
+	celebration.innerHTML = `
+        <div class="milestone-title">🎉 ${escapeHtml(String(data.milestone))} ${escapeHtml(coin)} 達成！ 🎉</div>
+        <div class="milestone-total">総額 ${escapeHtml(String(data.total))} ${escapeHtml(coin)}</div>
+    `;
+
+	document.body.appendChild(celebration);
+
+	// アニメーション終了後に要素を削除
+	setTimeout(() => {
+		celebration.remove();
+	}, milestoneDisplayDuration);
+}
+
+/**
+ * アクティブなOBSレイアウトのCSSを読み込み直す
+ *
+ * 新しいCSSの読み込みが完了してから古いCSSを外すため、切り替え時に表示がちらつかない
+ */
+function reloadLayoutStyles() {
+	reloadStylesheet("layout-css", "layout.css");
+}
+
+/**
+ * 指定したスタイルシートを読み込み直す
+ *
+ * 新しいCSSは古いCSSの直後に挿入するため、スタイルシート間の優先順位は変わらない
+ *
+ * @param {string} linkId - 読み込み直すlink要素のID
+ * @param {string} href - スタイルシートのURL
+ */
+function reloadStylesheet(linkId, href) {
+	const current = document.getElementById(linkId);
+	const next = document.createElement("link");
+	next.rel = "stylesheet";
+	next.href = `${href}?t=${Date.now()}`;
+	next.onload = () => {
+		if (current) current.remove();
+		next.id = linkId;
+	};
+	next.onerror = () => {
+		console.error(`Failed to load OBS styles: ${href}`);
+		next.remove();
+	};
+	if (current) {
+		current.after(next);
+	} else {
+		document.head.appendChild(next);
+	}
+}
+
 /**
  * 金額に基づいたCSSクラス名を取得する
  *
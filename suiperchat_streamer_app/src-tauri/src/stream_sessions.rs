@@ -0,0 +1,344 @@
+//! 配信セッションの管理モジュール
+//!
+//! 複数の配信プラットフォームへ同時配信する場合に、チャンネルごとに別々の配信セッションとして
+//! メッセージを記録できるよう、チャンネル名と配信セッションIDの対応を管理します。
+//! 視聴者はWebSocket接続のクエリパラメータ `?channel=xxx` で所属するセッションを指定し、
+//! 指定がない場合や対応するセッションがない場合はサーバー起動時に作成したセッションに属します。
+//! 所属するセッションはメッセージの受信ごとに解決し直すため、接続後に開始したチャンネルのセッションにも
+//! 記録され、チャンネルのセッションが終了した後はサーバー起動時のセッションに戻ります。
+
+use crate::database;
+use crate::state::AppState;
+use crate::ws_server::connection_manager::global;
+use std::collections::HashMap;
+use tauri::Manager;
+use uuid::Uuid;
+
+/// サーバー起動時に作成する配信セッションのチャンネル名
+pub const PRIMARY_SESSION_CHANNEL: &str = "default";
+
+/// 所属する配信セッションを指定するクエリパラメータ名
+pub const SESSION_CHANNEL_PARAM: &str = "channel";
+
+/// チャンネル名の最大文字数
+const MAX_SESSION_CHANNEL_LEN: usize = 32;
+
+/// チャンネル名と配信セッションIDの対応
+pub type SessionMap = HashMap<String, String>;
+
+/// ## チャンネル名を検証して正規化する
+///
+/// 前後の空白を除き、小文字に変換します。
+///
+/// ### Arguments
+/// - `channel`: チャンネル名
+///
+/// ### Returns
+/// - `Result<String, String>`: 正規化したチャンネル名、無効な場合はエラーメッセージ
+pub fn validate_session_channel(channel: &str) -> Result<String, String> {
+    let channel = channel.trim().to_ascii_lowercase();
+    if channel.is_empty() || channel.len() > MAX_SESSION_CHANNEL_LEN {
+        return Err(format!(
+            "チャンネル名は1〜{}文字で指定してください",
+            MAX_SESSION_CHANNEL_LEN
+        ));
+    }
+    if !channel
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "チャンネル名には英数字・ハイフン・アンダースコアのみ使用できます: {}",
+            channel
+        ));
+    }
+    Ok(channel)
+}
+
+/// ## クエリ文字列から所属する配信セッションのチャンネル名を取得する
+///
+/// ### Arguments
+/// - `query`: WebSocket接続リクエストのクエリ文字列
+///
+/// ### Returns
+/// - `Option<String>`: 正規化したチャンネル名（指定がない、または無効な場合はNone）
+pub fn channel_from_query(query: &str) -> Option<String> {
+    url::form_urlencoded::parse(query.as_bytes())
+        .find(|(key, _)| key == SESSION_CHANNEL_PARAM)
+        .and_then(|(_, channel)| validate_session_channel(&channel).ok())
+}
+
+/// ## サーバー起動時に作成した配信セッションのIDを取得する
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+///
+/// ### Returns
+/// - `Option<String>`: 配信セッションID（未配信の場合はNone）
+pub fn primary_session_id(app_state: &AppState) -> Option<String> {
+    app_state
+        .current_session_id
+        .lock()
+        .ok()?
+        .get(PRIMARY_SESSION_CHANNEL)
+        .cloned()
+}
+
+/// ## チャンネルに対応する配信セッションのIDを取得する
+///
+/// 対応するセッションがない場合は、サーバー起動時に作成したセッションを使用します。
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+/// - `channel`: チャンネル名（指定がない場合はNone）
+///
+/// ### Returns
+/// - `Option<String>`: 配信セッションID（未配信の場合はNone）
+pub fn session_id_for_channel(app_state: &AppState, channel: Option<&str>) -> Option<String> {
+    let sessions = app_state.current_session_id.lock().ok()?;
+    resolve_session_id(&sessions, channel)
+}
+
+/// ## チャンネル名と配信セッションIDの対応からセッションIDを解決する
+///
+/// ### Arguments
+/// - `sessions`: チャンネル名と配信セッションIDの対応
+/// - `channel`: チャンネル名（指定がない場合はNone）
+///
+/// ### Returns
+/// - `Option<String>`: チャンネルの配信セッションID（対応するセッションがない場合はサーバー起動時のセッションID）
+fn resolve_session_id(sessions: &SessionMap, channel: Option<&str>) -> Option<String> {
+    channel
+        .and_then(|channel| sessions.get(channel))
+        .or_else(|| sessions.get(PRIMARY_SESSION_CHANNEL))
+        .cloned()
+}
+
+/// ## チャンネルの配信セッションを対応に追加する
+///
+/// ### Arguments
+/// - `sessions`: チャンネル名と配信セッションIDの対応
+/// - `channel`: 正規化済みのチャンネル名
+/// - `session_id`: 配信セッションID
+///
+/// ### Returns
+/// - `Result<(), String>`: 既に開始している場合はエラーメッセージ
+fn insert_channel_session(
+    sessions: &mut SessionMap,
+    channel: &str,
+    session_id: &str,
+) -> Result<(), String> {
+    if sessions.contains_key(channel) {
+        return Err(format!(
+            "チャンネル {} のセッションは既に開始しています",
+            channel
+        ));
+    }
+    sessions.insert(channel.to_string(), session_id.to_string());
+    Ok(())
+}
+
+/// ## チャンネルの配信セッションを対応から取り除く
+///
+/// ### Arguments
+/// - `sessions`: チャンネル名と配信セッションIDの対応
+/// - `channel`: 正規化済みのチャンネル名
+///
+/// ### Returns
+/// - `Result<String, String>`: 取り除いた配信セッションID、サーバー起動時のセッションや開始していない場合はエラーメッセージ
+fn remove_channel_session(sessions: &mut SessionMap, channel: &str) -> Result<String, String> {
+    if channel == PRIMARY_SESSION_CHANNEL {
+        return Err("サーバー起動時のセッションはサーバーの停止時に終了します".to_string());
+    }
+    sessions
+        .remove(channel)
+        .ok_or_else(|| format!("チャンネル {} のセッションは開始していません", channel))
+}
+
+/// ## 配信中のセッションかどうかを判定する
+///
+/// ### Arguments
+/// - `sessions`: チャンネル名と配信セッションIDの対応
+/// - `session_id`: 判定する配信セッションID
+///
+/// ### Returns
+/// - `bool`: いずれかのチャンネルで配信中のセッションの場合は `true`
+pub fn is_active_session(sessions: &SessionMap, session_id: &str) -> bool {
+    sessions.values().any(|active| active == session_id)
+}
+
+/// ## 配信セッションが存在するかどうかを判定する
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+///
+/// ### Returns
+/// - `bool`: 配信中の場合は `true`
+pub fn has_active_session(app_state: &AppState) -> bool {
+    app_state
+        .current_session_id
+        .lock()
+        .is_ok_and(|sessions| !sessions.is_empty())
+}
+
+/// ## チャンネルの配信セッションを開始する
+///
+/// サーバーの起動中のみ開始できます。以降に `?channel=xxx` で接続した視聴者のメッセージは
+/// このセッションに記録されます。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `channel`: チャンネル名
+///
+/// ### Returns
+/// - `Result<String, String>`: 作成した配信セッションID、失敗した場合はエラーメッセージ
+pub async fn start_channel_session(
+    app_handle: &tauri::AppHandle,
+    channel: &str,
+) -> Result<String, String> {
+    let channel = validate_session_channel(channel)?;
+    let app_state = app_handle.state::<AppState>();
+    if primary_session_id(&app_state).is_none() {
+        return Err("サーバーが起動していないため、セッションを開始できません".to_string());
+    }
+    let db_pool = app_state
+        .db_pool
+        .lock()
+        .map_err(|e| format!("データベース接続プールのロックに失敗しました: {}", e))?
+        .clone()
+        .ok_or_else(|| "データベース接続が初期化されていません".to_string())?;
+
+    let session_id = Uuid::new_v4().to_string();
+    {
+        let mut sessions = app_state
+            .current_session_id
+            .lock()
+            .map_err(|e| format!("セッションIDのロックに失敗しました: {}", e))?;
+        insert_channel_session(&mut sessions, &channel, &session_id)?;
+    }
+
+    if let Err(e) = database::create_session(&db_pool, &session_id).await {
+        if let Ok(mut sessions) = app_state.current_session_id.lock() {
+            sessions.remove(&channel);
+        }
+        return Err(format!("セッションの作成に失敗しました: {}", e));
+    }
+    println!(
+        "チャンネル {} の配信セッションを開始しました: {}",
+        channel, session_id
+    );
+    Ok(session_id)
+}
+
+/// ## チャンネルの配信セッションを終了する
+///
+/// サーバー起動時に作成したセッションはサーバーの停止時に終了するため、ここでは終了できません。
+/// 終了したセッションに接続中の視聴者は、サーバー起動時のセッションに移します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `channel`: チャンネル名
+///
+/// ### Returns
+/// - `Result<String, String>`: 終了した配信セッションID、失敗した場合はエラーメッセージ
+pub async fn end_channel_session(
+    app_handle: &tauri::AppHandle,
+    channel: &str,
+) -> Result<String, String> {
+    let channel = validate_session_channel(channel)?;
+    let app_state = app_handle.state::<AppState>();
+    let session_id = {
+        let mut sessions = app_state
+            .current_session_id
+            .lock()
+            .map_err(|e| format!("セッションIDのロックに失敗しました: {}", e))?;
+        remove_channel_session(&mut sessions, &channel)?
+    };
+
+    // 終了したセッションにメッセージが記録され続けないよう、接続中の視聴者をサーバー起動時のセッションに移す
+    let moved = global::get_manager()
+        .reassign_session(&session_id, primary_session_id(&app_state).as_deref());
+    if moved > 0 {
+        println!(
+            "終了したセッションの視聴者{}人をサーバー起動時のセッションに移しました",
+            moved
+        );
+    }
+
+    let db_pool = app_state.db_pool.lock().ok().and_then(|pool| pool.clone());
+    match db_pool {
+        Some(db_pool) => database::end_session(&db_pool, &session_id)
+            .await
+            .map_err(|e| format!("セッションの終了の記録に失敗しました: {}", e))?,
+        None => eprintln!(
+            "データベース接続が初期化されていないため、セッションの終了を記録できません: {}",
+            session_id
+        ),
+    }
+    println!(
+        "チャンネル {} の配信セッションを終了しました: {}",
+        channel, session_id
+    );
+    Ok(session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_from_query() {
+        assert_eq!(
+            channel_from_query("token=abc&channel=YouTube"),
+            Some("youtube".to_string())
+        );
+        assert_eq!(
+            channel_from_query("channel=%20twitch_1%20"),
+            Some("twitch_1".to_string())
+        );
+        assert_eq!(channel_from_query("channel=a%2Fb"), None);
+        assert_eq!(channel_from_query("channel="), None);
+        assert_eq!(channel_from_query("token=abc"), None);
+    }
+
+    /// チャンネルのセッションの開始・終了と、セッションIDの解決を確認
+    #[test]
+    fn test_channel_session_lifecycle() {
+        let mut sessions = SessionMap::new();
+        assert_eq!(resolve_session_id(&sessions, Some("youtube")), None);
+
+        sessions.insert(PRIMARY_SESSION_CHANNEL.to_string(), "primary".to_string());
+        // 対応するセッションがない場合はサーバー起動時のセッションを使用する
+        assert_eq!(
+            resolve_session_id(&sessions, Some("youtube")).as_deref(),
+            Some("primary")
+        );
+        assert_eq!(
+            resolve_session_id(&sessions, None).as_deref(),
+            Some("primary")
+        );
+
+        assert!(insert_channel_session(&mut sessions, "youtube", "yt").is_ok());
+        assert!(insert_channel_session(&mut sessions, "youtube", "yt-2").is_err());
+        assert_eq!(
+            resolve_session_id(&sessions, Some("youtube")).as_deref(),
+            Some("yt")
+        );
+        assert_eq!(
+            resolve_session_id(&sessions, Some("twitch")).as_deref(),
+            Some("primary")
+        );
+
+        assert!(remove_channel_session(&mut sessions, PRIMARY_SESSION_CHANNEL).is_err());
+        assert_eq!(
+            remove_channel_session(&mut sessions, "youtube"),
+            Ok("yt".to_string())
+        );
+        assert!(remove_channel_session(&mut sessions, "youtube").is_err());
+        // 終了後はサーバー起動時のセッションに戻る
+        assert_eq!(
+            resolve_session_id(&sessions, Some("youtube")).as_deref(),
+            Some("primary")
+        );
+    }
+}
@@ -0,0 +1,834 @@
+//! オンチェーン着金の監視モジュール
+//!
+//! Sui RPCを定期的にポーリングし、配信者のウォレットへのコイン転送を検出して
+//! スーパーチャットとしてブロードキャスト・DB保存します。
+//! 視聴者は専用フロントを使わず、ウォレットから直接送金するだけでスーパーチャットできます。
+//!
+//! 送金トランザクションに文字列の入力（メモ）が含まれる場合は、メッセージ本文として使用します。
+//! メモが `{"display_name": "...", "message": "..."}` 形式のJSONの場合は表示名も取り出します。
+//! 対応コインのレジストリに型引数が登録されていないコインの着金は扱いません。
+//! WebSocket経由で申告済みのトランザクションは重複して扱いません。
+
+use crate::auto_thanks;
+use crate::badges;
+use crate::coin_registry::{self, CoinInfo};
+use crate::database;
+use crate::db_health;
+use crate::db_models::{
+    Message as DbMessage, RejectedSuperchat, REJECT_REASON_BANNED_WORD,
+    REJECT_REASON_COIN_NOT_ACCEPTED,
+};
+use crate::language::detect_language;
+use crate::moderation::{contains_banned_word, mask_banned_words, SuperchatModeration};
+use crate::state::AppState;
+use crate::stream_sessions;
+use crate::superchat_alert;
+use crate::types::{ClientMessage, MessageType, SuperchatData, SuperchatMessage, DEFAULT_CHANNEL};
+use crate::wallet_registry;
+use crate::webhook;
+use crate::ws_server::connection_manager::global;
+use crate::ws_server::flow_control::BroadcastPriority;
+use crate::ws_server::protobuf;
+use crate::ws_server::session::Broadcast;
+use chrono::{SubsecRound, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+/// デフォルトのSui RPCエンドポイント（メインネット）
+pub const DEFAULT_SUI_RPC_URL: &str = "https://fullnode.mainnet.sui.io:443";
+
+/// デフォルトのポーリング間隔（秒）
+pub const DEFAULT_POLL_INTERVAL_SECS: u64 = 5;
+
+/// 設定可能なポーリング間隔の範囲（秒）
+pub const POLL_INTERVAL_RANGE_SECS: std::ops::RangeInclusive<u64> = 2..=300;
+
+/// 1回のポーリングで取得するトランザクションの最大件数
+const QUERY_PAGE_SIZE: u64 = 50;
+
+/// RPCリクエストのタイムアウト
+const RPC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// メモが無い場合のスーパーチャット本文
+const DEFAULT_SUPERCHAT_MESSAGE: &str = "";
+
+/// ## 着金監視の設定
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuiWatcherConfig {
+    /// 監視対象のウォレットアドレス（Noneの場合は配信者のウォレットアドレスを使用）
+    pub wallet_address: Option<String>,
+    /// Sui RPCエンドポイント
+    pub rpc_url: String,
+    /// ポーリング間隔（秒）
+    pub poll_interval_secs: u64,
+}
+
+impl Default for SuiWatcherConfig {
+    fn default() -> Self {
+        Self {
+            wallet_address: None,
+            rpc_url: DEFAULT_SUI_RPC_URL.to_string(),
+            poll_interval_secs: DEFAULT_POLL_INTERVAL_SECS,
+        }
+    }
+}
+
+/// ## 着金監視の状態
+#[derive(Debug, Clone, Serialize)]
+pub struct SuiWatcherStatus {
+    /// 監視中かどうか
+    pub running: bool,
+    /// 実際に監視するウォレットアドレス（未設定の場合はNone）
+    pub wallet_address: Option<String>,
+    /// Sui RPCエンドポイント
+    pub rpc_url: String,
+    /// ポーリング間隔（秒）
+    pub poll_interval_secs: u64,
+}
+
+/// ## 検出した着金
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedTransfer {
+    /// トランザクションダイジェスト
+    pub digest: String,
+    /// 送金者のウォレットアドレス
+    pub sender: String,
+    /// 受け取ったコインの型
+    pub coin_type: String,
+    /// 受け取った量（最小単位）
+    pub raw_amount: u128,
+    /// メモから取り出した表示名
+    pub display_name: Option<String>,
+    /// メモから取り出したメッセージ本文
+    pub message: Option<String>,
+    /// トランザクションの実行時刻（Unixミリ秒）
+    pub timestamp_ms: Option<i64>,
+}
+
+/// ## コインのメタデータ
+#[derive(Debug, Clone)]
+pub(crate) struct CoinMetadata {
+    /// 小数点以下の桁数
+    pub(crate) decimals: u32,
+}
+
+/// ## 監視対象のウォレットアドレスを解決する
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+///
+/// ### Returns
+/// - `Option<String>`: 監視設定のアドレス、未設定の場合は配信者のウォレットアドレス
+pub fn resolve_wallet_address(app_handle: &tauri::AppHandle) -> Option<String> {
+    let app_state = app_handle.state::<AppState>();
+    let configured = app_state
+        .sui_watcher
+        .lock()
+        .ok()
+        .and_then(|config| config.wallet_address.clone());
+    configured.or_else(|| wallet_registry::active_wallet_address(&app_state))
+}
+
+/// ## 着金監視の状態を取得する
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+///
+/// ### Returns
+/// - `SuiWatcherStatus`: 現在の監視状態
+pub fn status(app_handle: &tauri::AppHandle) -> SuiWatcherStatus {
+    let app_state = app_handle.state::<AppState>();
+    let config = app_state
+        .sui_watcher
+        .lock()
+        .map(|config| config.clone())
+        .unwrap_or_default();
+    let running = app_state
+        .sui_watcher_stop
+        .lock()
+        .map(|stop| stop.is_some())
+        .unwrap_or(false);
+
+    SuiWatcherStatus {
+        running,
+        wallet_address: resolve_wallet_address(app_handle),
+        rpc_url: config.rpc_url,
+        poll_interval_secs: config.poll_interval_secs,
+    }
+}
+
+/// ## 着金監視を開始する
+///
+/// 既に監視中の場合は何もしません。監視開始以前のトランザクションは対象外です。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+///
+/// ### Returns
+/// - `Result<(), String>`: 監視対象のウォレットアドレスが未設定の場合はエラーメッセージ
+pub fn start(app_handle: tauri::AppHandle) -> Result<(), String> {
+    if resolve_wallet_address(&app_handle).is_none() {
+        return Err("監視対象のウォレットアドレスが設定されていません".to_string());
+    }
+
+    let stop_flag = {
+        let app_state = app_handle.state::<AppState>();
+        let mut stop_guard = app_state
+            .sui_watcher_stop
+            .lock()
+            .map_err(|e| format!("着金監視の状態のロックに失敗しました: {}", e))?;
+        if stop_guard.is_some() {
+            println!("着金監視は既に起動しています");
+            return Ok(());
+        }
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        *stop_guard = Some(Arc::clone(&stop_flag));
+        stop_flag
+    };
+
+    tauri::async_runtime::spawn(run(app_handle, stop_flag));
+    Ok(())
+}
+
+/// ## 着金監視を停止する
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+///
+/// ### Returns
+/// - `bool`: 監視中だった場合は `true`
+pub fn stop(app_handle: &tauri::AppHandle) -> bool {
+    let stop_flag = app_handle
+        .state::<AppState>()
+        .sui_watcher_stop
+        .lock()
+        .ok()
+        .and_then(|mut stop| stop.take());
+
+    match stop_flag {
+        Some(stop_flag) => {
+            stop_flag.store(true, Ordering::SeqCst);
+            println!("着金監視を停止しました");
+            true
+        }
+        None => false,
+    }
+}
+
+/// 停止が要求されるまで着金をポーリングする
+async fn run(app_handle: tauri::AppHandle, stop_flag: Arc<AtomicBool>) {
+    let client = match reqwest::Client::builder().timeout(RPC_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("着金監視のHTTPクライアントの構築に失敗しました: {}", e);
+            stop(&app_handle);
+            return;
+        }
+    };
+
+    let mut watched: Option<(String, String)> = None;
+    let mut cursor: Option<String> = None;
+
+    while !stop_flag.load(Ordering::SeqCst) {
+        let config = app_handle
+            .state::<AppState>()
+            .sui_watcher
+            .lock()
+            .map(|config| config.clone())
+            .unwrap_or_default();
+
+        if let Some(wallet) = resolve_wallet_address(&app_handle) {
+            // 監視対象が変わった場合は、その時点の最新トランザクションから監視し直す
+            let target = (wallet.clone(), config.rpc_url.clone());
+            if watched.as_ref() != Some(&target) {
+                match latest_digest(&client, &config.rpc_url, &wallet).await {
+                    Ok(latest) => {
+                        println!("着金監視を開始しました: {} ({})", wallet, config.rpc_url);
+                        cursor = latest;
+                        watched = Some(target);
+                    }
+                    Err(e) => eprintln!("着金監視の初期化に失敗しました: {}", e),
+                }
+            } else if let Err(e) =
+                poll_transfers(&app_handle, &client, &config.rpc_url, &wallet, &mut cursor).await
+            {
+                eprintln!("着金の確認に失敗しました: {}", e);
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(config.poll_interval_secs)).await;
+    }
+}
+
+/// カーソル以降のトランザクションを取得し、検出した着金をスーパーチャットとして処理する
+///
+/// 処理したトランザクションごとにカーソルを進めるため、途中で失敗しても同じ着金を再度扱いません。
+async fn poll_transfers(
+    app_handle: &tauri::AppHandle,
+    client: &reqwest::Client,
+    rpc_url: &str,
+    wallet: &str,
+    cursor: &mut Option<String>,
+) -> Result<(), String> {
+    loop {
+        let page = query_transactions(client, rpc_url, wallet, cursor.as_deref(), false).await?;
+        let transactions = page["data"].as_array().cloned().unwrap_or_default();
+
+        for transaction in &transactions {
+            if let Some(transfer) = parse_transfer(transaction, wallet) {
+                // 同じシンボルの偽コインを除外するため、型引数全体でレジストリと照合する
+                let coin = coin_registry::find_coin_by_type(
+                    &app_handle.state::<AppState>(),
+                    &transfer.coin_type,
+                );
+                match coin {
+                    Some(coin) => handle_transfer(app_handle, transfer, &coin).await,
+                    None => println!(
+                        "未登録のコインの着金のため扱いません: {} ({})",
+                        transfer.coin_type, transfer.digest
+                    ),
+                }
+            }
+            if let Some(digest) = transaction["digest"].as_str() {
+                *cursor = Some(digest.to_string());
+            }
+        }
+
+        if transactions.is_empty() || !page["hasNextPage"].as_bool().unwrap_or(false) {
+            return Ok(());
+        }
+    }
+}
+
+/// 監視開始時点の最新トランザクションのダイジェストを取得する
+async fn latest_digest(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    wallet: &str,
+) -> Result<Option<String>, String> {
+    let page = query_transactions(client, rpc_url, wallet, None, true).await?;
+    Ok(page["data"][0]["digest"].as_str().map(str::to_string))
+}
+
+/// `suix_queryTransactionBlocks` でウォレット宛てのトランザクションを取得する
+async fn query_transactions(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    wallet: &str,
+    cursor: Option<&str>,
+    descending: bool,
+) -> Result<Value, String> {
+    let limit = if descending { 1 } else { QUERY_PAGE_SIZE };
+    rpc_call(
+        client,
+        rpc_url,
+        "suix_queryTransactionBlocks",
+        json!([
+            {
+                "filter": { "ToAddress": wallet },
+                "options": { "showInput": true, "showBalanceChanges": true }
+            },
+            cursor,
+            limit,
+            descending
+        ]),
+    )
+    .await
+    .map_err(String::from)
+}
+
+/// `suix_getCoinMetadata` でコインの桁数を取得する
+pub(crate) async fn fetch_coin_metadata(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    coin_type: &str,
+) -> Result<CoinMetadata, String> {
+    let result = rpc_call(client, rpc_url, "suix_getCoinMetadata", json!([coin_type])).await?;
+    let decimals = result["decimals"]
+        .as_u64()
+        .and_then(|decimals| u32::try_from(decimals).ok())
+        .ok_or_else(|| format!("コインのメタデータを取得できませんでした: {}", coin_type))?;
+
+    Ok(CoinMetadata { decimals })
+}
+
+/// ## Sui RPCの呼び出しエラー
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum RpcError {
+    /// 通信またはレスポンスの解析に失敗した
+    Transport(String),
+    /// JSON-RPCがエラーを返した
+    Rpc {
+        /// JSON-RPCのエラーコード
+        code: Option<i64>,
+        /// エラーの内容
+        message: String,
+    },
+}
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Transport(message) | Self::Rpc { message, .. } => f.write_str(message),
+        }
+    }
+}
+
+impl From<RpcError> for String {
+    fn from(error: RpcError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Sui JSON-RPCを呼び出して `result` を返す
+pub(crate) async fn rpc_call(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    method: &str,
+    params: Value,
+) -> Result<Value, RpcError> {
+    let response: Value = client
+        .post(rpc_url)
+        .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+        .send()
+        .await
+        .map_err(|e| {
+            RpcError::Transport(format!(
+                "Sui RPCへのリクエストに失敗しました ({}): {}",
+                method, e
+            ))
+        })?
+        .json()
+        .await
+        .map_err(|e| {
+            RpcError::Transport(format!(
+                "Sui RPCのレスポンスの解析に失敗しました ({}): {}",
+                method, e
+            ))
+        })?;
+
+    if let Some(error) = response.get("error") {
+        return Err(RpcError::Rpc {
+            code: error["code"].as_i64(),
+            message: format!("Sui RPCがエラーを返しました ({}): {}", method, error),
+        });
+    }
+    Ok(response["result"].clone())
+}
+
+/// ## トランザクションから監視対象のウォレットへの着金を取り出す
+///
+/// 同じトランザクションで複数のコインを受け取った場合は、最初に見つかった着金のみを扱います。
+///
+/// ### Arguments
+/// - `transaction`: `suix_queryTransactionBlocks` が返したトランザクション
+/// - `wallet`: 監視対象のウォレットアドレス
+///
+/// ### Returns
+/// - `Option<DetectedTransfer>`: 着金（送金者自身への送金や残高が増えていない場合はNone）
+pub fn parse_transfer(transaction: &Value, wallet: &str) -> Option<DetectedTransfer> {
+    let digest = transaction["digest"].as_str()?;
+    let sender = transaction["transaction"]["data"]["sender"].as_str()?;
+    if sender.eq_ignore_ascii_case(wallet) {
+        return None;
+    }
+
+    let (coin_type, raw_amount) = transaction["balanceChanges"]
+        .as_array()?
+        .iter()
+        .filter(|change| {
+            change["owner"]["AddressOwner"]
+                .as_str()
+                .is_some_and(|owner| owner.eq_ignore_ascii_case(wallet))
+        })
+        .find_map(|change| {
+            let amount: i128 = change["amount"].as_str()?.parse().ok()?;
+            let amount = u128::try_from(amount).ok().filter(|amount| *amount > 0)?;
+            Some((change["coinType"].as_str()?.to_string(), amount))
+        })?;
+
+    let (display_name, message) = parse_memo(transaction);
+
+    Some(DetectedTransfer {
+        digest: digest.to_string(),
+        sender: sender.to_string(),
+        coin_type,
+        raw_amount,
+        display_name,
+        message,
+        timestamp_ms: transaction["timestampMs"]
+            .as_str()
+            .and_then(|timestamp| timestamp.parse().ok()),
+    })
+}
+
+/// トランザクションの文字列入力（メモ）から表示名とメッセージ本文を取り出す
+fn parse_memo(transaction: &Value) -> (Option<String>, Option<String>) {
+    let memo = transaction["transaction"]["data"]["transaction"]["inputs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|input| input["type"] == "pure")
+        .filter_map(|input| input["value"].as_str())
+        .find(|value| {
+            !value.trim().is_empty() && !is_address_like(value) && value.parse::<u128>().is_err()
+        });
+    let Some(memo) = memo else {
+        return (None, None);
+    };
+
+    match serde_json::from_str::<Value>(memo) {
+        Ok(Value::Object(fields)) => {
+            let text = |key: &str| {
+                fields
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(str::to_string)
+            };
+            (
+                text("display_name").or_else(|| text("name")),
+                text("message"),
+            )
+        }
+        _ => (None, Some(memo.trim().to_string())),
+    }
+}
+
+/// 文字列がアドレス（0x + 16進数）かどうか判定する
+fn is_address_like(value: &str) -> bool {
+    value
+        .strip_prefix("0x")
+        .is_some_and(|hex| !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// 最小単位の量を通貨単位に変換する
+fn to_coin_amount(raw_amount: u128, decimals: u32) -> f64 {
+    raw_amount as f64 / 10f64.powi(decimals as i32)
+}
+
+/// ウォレットアドレスを表示用に短縮する
+fn short_address(address: &str) -> String {
+    if address.len() <= 12 {
+        return address.to_string();
+    }
+    format!("{}…{}", &address[..6], &address[address.len() - 4..])
+}
+
+/// ## 配信しなかった着金をDBに記録する
+///
+/// 視聴者は送金済みのため、返金などの対応のため後から照合できるよう記録します。
+///
+/// ### Arguments
+/// - `db_pool`: データベース接続プール（未接続の場合はNone）
+/// - `transfer`: 配信しなかった着金
+/// - `coin`: 着金したコインのレジストリ情報
+/// - `session_id`: 記録する配信セッションID
+/// - `reason`: 配信しなかった理由（`REJECT_REASON_*`）
+async fn record_rejected_transfer(
+    db_pool: Option<&sqlx::SqlitePool>,
+    transfer: &DetectedTransfer,
+    coin: &CoinInfo,
+    session_id: Option<String>,
+    reason: &str,
+) {
+    let Some(pool) = db_pool else {
+        return;
+    };
+    let rejected = RejectedSuperchat {
+        id: uuid::Uuid::new_v4().to_string(),
+        session_id,
+        tx_hash: transfer.digest.clone(),
+        wallet_address: transfer.sender.clone(),
+        display_name: transfer
+            .display_name
+            .clone()
+            .unwrap_or_else(|| short_address(&transfer.sender)),
+        amount: to_coin_amount(transfer.raw_amount, u32::from(coin.decimals)),
+        coin: coin.symbol.clone(),
+        content: transfer.message.clone().unwrap_or_default(),
+        reason: reason.to_string(),
+        // チェーン上で検出した着金のため検証済み
+        verified: true,
+        rejected_at: Utc::now().to_rfc3339(),
+    };
+    if let Err(e) = database::record_rejected_superchat(pool, &rejected).await {
+        eprintln!(
+            "配信しなかった着金の記録に失敗しました ({}): {}",
+            transfer.digest, e
+        );
+    }
+}
+
+/// ## 検出した着金をスーパーチャットとしてDBに保存し、ブロードキャストする
+///
+/// WebSocket経由のスーパーチャットと同じく、受け付けるコインの制限・IPアドレスのブロック・NGワードを
+/// 確認してから配信し、Webhookへの転送・OBSのアラート・自動お礼も行います。
+/// 同じトランザクションがWebSocket経由で保存済みの場合は配信しません。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `transfer`: 検出した着金
+/// - `coin`: 着金したコインのレジストリ情報
+async fn handle_transfer(
+    app_handle: &tauri::AppHandle,
+    transfer: DetectedTransfer,
+    coin: &CoinInfo,
+) {
+    let app_state = app_handle.state::<AppState>();
+    let db_pool = app_state
+        .db_pool
+        .lock()
+        .ok()
+        .and_then(|guard| guard.clone());
+    // オンチェーンの着金はチャンネルを特定できないため、サーバー起動時のセッションに記録する
+    let session_id = stream_sessions::primary_session_id(&app_state);
+
+    // 配信者が受け付けるコインを制限している場合、それ以外のコインは配信しない
+    let accepted_coins = app_state
+        .accepted_coins
+        .lock()
+        .ok()
+        .and_then(|coins| coins.clone());
+    if !coin_registry::is_coin_accepted(accepted_coins.as_deref(), &coin.symbol) {
+        println!(
+            "受け付けていないコインの着金のため配信しません: {} ({})",
+            coin.symbol, transfer.digest
+        );
+        record_rejected_transfer(
+            db_pool.as_ref(),
+            &transfer,
+            coin,
+            session_id,
+            REJECT_REASON_COIN_NOT_ACCEPTED,
+        )
+        .await;
+        return;
+    }
+
+    // ブロックしたIPアドレスの視聴者が使用していたウォレットからの着金は配信しない
+    if global::get_manager().is_wallet_blocked(&transfer.sender) {
+        println!(
+            "ブロック中の視聴者のウォレットからの着金のため配信しません: {} ({})",
+            transfer.sender, transfer.digest
+        );
+        return;
+    }
+
+    let mut content = transfer
+        .message
+        .clone()
+        .unwrap_or_else(|| DEFAULT_SUPERCHAT_MESSAGE.to_string());
+    let banned_words = app_state
+        .banned_words
+        .lock()
+        .map(|words| words.clone())
+        .unwrap_or_default();
+    if contains_banned_word(&content, &banned_words) {
+        let action = app_state
+            .superchat_moderation
+            .lock()
+            .map(|action| *action)
+            .unwrap_or_default();
+        match action {
+            SuperchatModeration::Mask => content = mask_banned_words(&content, &banned_words),
+            SuperchatModeration::Block => {
+                println!("NGワードを含むため着金を配信しません: {}", transfer.digest);
+                record_rejected_transfer(
+                    db_pool.as_ref(),
+                    &transfer,
+                    coin,
+                    session_id,
+                    REJECT_REASON_BANNED_WORD,
+                )
+                .await;
+                return;
+            }
+        }
+    }
+
+    let amount = to_coin_amount(transfer.raw_amount, u32::from(coin.decimals));
+    let received_at = Utc::now().trunc_subsecs(6);
+    let timestamp = transfer
+        .timestamp_ms
+        .unwrap_or_else(|| received_at.timestamp_millis());
+
+    let mut superchat_msg = SuperchatMessage {
+        message_type: MessageType::Superchat,
+        id: uuid::Uuid::new_v4().to_string(),
+        display_name: transfer
+            .display_name
+            .clone()
+            .unwrap_or_else(|| short_address(&transfer.sender)),
+        content,
+        superchat: SuperchatData {
+            amount,
+            coin: coin.symbol.clone(),
+            tx_hash: transfer.digest.clone(),
+            wallet_address: transfer.sender.clone(),
+            amount_unit: None,
+        },
+        timestamp: Some(timestamp),
+        donor_streak: None,
+        // チェーン上で検出した着金のため検証済み
+        verified: Some(true),
+        translated_content: None,
+        badges: Vec::new(),
+    };
+    println!(
+        "オンチェーンの着金を検出しました: {} {} from {} ({})",
+        amount, superchat_msg.superchat.coin, transfer.sender, transfer.digest
+    );
+
+    if let Some(pool) = &db_pool {
+        match database::get_donor_streak_for_session(pool, &transfer.sender, session_id.as_deref())
+            .await
+        {
+            Ok(streak) => superchat_msg.donor_streak = Some(streak),
+            Err(e) => eprintln!("ストリークの算出に失敗: {}", e),
+        }
+        superchat_msg.badges = badges::viewer_badges(app_handle, pool, &transfer.sender).await;
+    }
+
+    let db_message = DbMessage {
+        id: superchat_msg.id.clone(),
+        timestamp: Utc
+            .timestamp_millis_opt(timestamp)
+            .single()
+            .unwrap_or(received_at),
+        display_name: superchat_msg.display_name.clone(),
+        content: superchat_msg.content.clone(),
+        amount: Some(amount),
+        coin: Some(superchat_msg.superchat.coin.clone()),
+        tx_hash: Some(transfer.digest),
+        wallet_address: Some(transfer.sender),
+        session_id,
+        // スーパーチャットは全チャンネル向けのため "general" として記録
+        channel: Some(DEFAULT_CHANNEL.to_string()),
+        sequence: None, // 保存時にDB側で採番
+        language: detect_language(&superchat_msg.content),
+        is_edited: false,
+        highlighted: false,
+    };
+    // 保存時にトランザクションハッシュの重複を判定するため、保存してから配信する
+    if !save_superchat(app_handle, db_pool, db_message).await {
+        println!(
+            "保存済みのトランザクションのため配信をスキップしました: {}",
+            superchat_msg.superchat.tx_hash
+        );
+        return;
+    }
+
+    match serde_json::to_string(&superchat_msg) {
+        Ok(json) => {
+            let broadcast =
+                Broadcast::with_protobuf(json, protobuf::encode_superchat(&superchat_msg))
+                    .with_priority(BroadcastPriority::High);
+            global::get_manager().broadcast_frame(broadcast);
+        }
+        Err(e) => eprintln!("メッセージのシリアライズに失敗: {}", e),
+    }
+    webhook::forward_message(app_handle, &ClientMessage::Superchat(superchat_msg.clone()));
+    superchat_alert::notify_superchat_received(app_handle, &superchat_msg);
+    auto_thanks::broadcast_thanks(app_handle, &superchat_msg);
+}
+
+/// ## 着金のスーパーチャットをDBに保存し、保存完了をフロントエンドに通知する
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `db_pool`: データベース接続プール（未接続の場合はNone）
+/// - `db_message`: 保存するメッセージ
+///
+/// ### Returns
+/// - `bool`: 同じトランザクションのメッセージが保存済みの場合はfalse（保存に失敗した場合や再接続後の保存に回した場合はtrue）
+async fn save_superchat(
+    app_handle: &tauri::AppHandle,
+    db_pool: Option<sqlx::SqlitePool>,
+    db_message: DbMessage,
+) -> bool {
+    // DB再接続中は復旧後に保存するためキューに退避
+    let Some(pool) = db_pool.filter(|_| !db_health::is_reconnecting(app_handle)) else {
+        db_health::queue_pending_message(app_handle, db_message);
+        return true;
+    };
+
+    match database::save_message_db(&pool, &db_message).await {
+        Ok(true) => {}
+        Ok(false) => return false,
+        Err(e) => {
+            eprintln!(
+                "メッセージの保存中にエラーが発生しました: ID={}, エラー={}",
+                db_message.id, e
+            );
+            if db_health::is_connection_error(&e) {
+                db_health::queue_pending_message(app_handle, db_message);
+            }
+            return true;
+        }
+    }
+
+    if let Err(e) = database::record_viewer_activity(&pool, &db_message).await {
+        eprintln!("視聴者プロフィールの更新に失敗しました: {}", e);
+    }
+    if let Some(wallet_address) = &db_message.wallet_address {
+        badges::invalidate_viewer_profile(&app_handle.state::<AppState>(), wallet_address);
+    }
+
+    let amount = db_message.amount.unwrap_or_default();
+    let coin = db_message.coin.clone().unwrap_or_default();
+    let serializable_message = crate::types::SerializableMessageForStreamer::from(db_message);
+    if let Err(e) = app_handle.emit("message_saved", &serializable_message) {
+        eprintln!("message_saved イベントの発火に失敗しました: {}", e);
+    }
+    crate::milestone::record_superchat(app_handle, amount, &coin);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SUIのコイン型
+    const SUI_COIN_TYPE: &str = "0x2::sui::SUI";
+
+    /// 監視対象への着金とメモが取り出せることを確認
+    #[test]
+    fn test_parse_transfer_with_memo() {
+        let wallet = format!("0x{}", "ab".repeat(32));
+        let sender = format!("0x{}", "cd".repeat(32));
+        let transaction = json!({
+            "digest": "8Wq3rT6yU1iO4pA7sD0fG2hJ5kL9zX3cV6bN8mQ1wE4r",
+            "timestampMs": "1700000000000",
+            "transaction": { "data": {
+                "sender": sender,
+                "transaction": { "kind": "ProgrammableTransaction", "inputs": [
+                    { "type": "pure", "valueType": "u64", "value": "1500000000" },
+                    { "type": "pure", "valueType": "address", "value": wallet },
+                    { "type": "pure", "valueType": "0x1::string::String",
+                      "value": "{\"display_name\":\"ねこ\",\"message\":\"応援してます\"}" }
+                ]}
+            }},
+            "balanceChanges": [
+                { "owner": { "AddressOwner": sender }, "coinType": SUI_COIN_TYPE, "amount": "-1502000000" },
+                { "owner": { "AddressOwner": wallet }, "coinType": SUI_COIN_TYPE, "amount": "1500000000" }
+            ]
+        });
+
+        let transfer = parse_transfer(&transaction, &wallet).unwrap();
+        assert_eq!(transfer.sender, sender);
+        assert_eq!(transfer.coin_type, SUI_COIN_TYPE);
+        assert_eq!(transfer.display_name.as_deref(), Some("ねこ"));
+        assert_eq!(transfer.message.as_deref(), Some("応援してます"));
+        assert_eq!(transfer.timestamp_ms, Some(1_700_000_000_000));
+        assert_eq!(to_coin_amount(transfer.raw_amount, 9), 1.5);
+
+        // 自分自身からの送金は着金として扱わない
+        assert!(parse_transfer(&transaction, &sender).is_none());
+    }
+}
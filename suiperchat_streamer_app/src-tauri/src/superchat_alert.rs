@@ -0,0 +1,127 @@
+//! スーパーチャットのアラート通知モジュール
+//!
+//! OBSのアラート演出（効果音・アニメーションなど）のため、スーパーチャットを配信した瞬間に
+//! フロントエンドへ `superchat_received` イベントを発行します。
+//! 通貨ごとに設定した閾値以上の金額の場合は、大口のスーパーチャットとして
+//! `big_superchat_received` イベントも発行します。
+
+use crate::state::AppState;
+use crate::types::SuperchatMessage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::{Emitter, Manager};
+
+/// スーパーチャット受信時に発行するイベント名
+pub const SUPERCHAT_RECEIVED_EVENT: &str = "superchat_received";
+
+/// 大口のスーパーチャット受信時に発行するイベント名
+pub const BIG_SUPERCHAT_RECEIVED_EVENT: &str = "big_superchat_received";
+
+/// デフォルトの大口スーパーチャットの閾値（SUI）
+pub const DEFAULT_BIG_SUPERCHAT_THRESHOLD: f64 = 10.0;
+
+/// ## スーパーチャットのアラート設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuperchatAlertConfig {
+    /// 通貨シンボルごとの大口スーパーチャットの閾値（閾値のない通貨は大口として扱わない）
+    pub big_superchat_thresholds: BTreeMap<String, f64>,
+}
+
+impl Default for SuperchatAlertConfig {
+    fn default() -> Self {
+        Self {
+            big_superchat_thresholds: BTreeMap::from([(
+                "SUI".to_string(),
+                DEFAULT_BIG_SUPERCHAT_THRESHOLD,
+            )]),
+        }
+    }
+}
+
+impl SuperchatAlertConfig {
+    /// ## 大口のスーパーチャットか判定する
+    ///
+    /// ### Arguments
+    /// - `amount`: スーパーチャットの金額
+    /// - `coin`: スーパーチャットの通貨シンボル
+    ///
+    /// ### Returns
+    /// - `bool`: 通貨の閾値以上の金額の場合は `true`
+    pub fn is_big_superchat(&self, amount: f64, coin: &str) -> bool {
+        self.big_superchat_thresholds
+            .get(coin)
+            .is_some_and(|threshold| amount >= *threshold)
+    }
+}
+
+/// ## スーパーチャット受信イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct SuperchatReceivedPayload {
+    /// メッセージID
+    pub id: String,
+    /// 表示名
+    pub display_name: String,
+    /// メッセージ本文
+    pub content: String,
+    /// 金額
+    pub amount: f64,
+    /// 通貨シンボル
+    pub coin: String,
+}
+
+/// ## スーパーチャットの受信をフロントエンドに通知する
+///
+/// `superchat_received` イベントを発行し、大口の場合は `big_superchat_received` イベントも発行します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `superchat_msg`: 配信したスーパーチャット
+pub fn notify_superchat_received(app_handle: &tauri::AppHandle, superchat_msg: &SuperchatMessage) {
+    let payload = SuperchatReceivedPayload {
+        id: superchat_msg.id.clone(),
+        display_name: superchat_msg.display_name.clone(),
+        content: superchat_msg.content.clone(),
+        amount: superchat_msg.superchat.amount,
+        coin: superchat_msg.superchat.coin.clone(),
+    };
+    if let Err(e) = app_handle.emit(SUPERCHAT_RECEIVED_EVENT, &payload) {
+        eprintln!(
+            "{} イベントの発火に失敗しました: {}",
+            SUPERCHAT_RECEIVED_EVENT, e
+        );
+    }
+
+    let is_big = app_handle
+        .state::<AppState>()
+        .superchat_alert
+        .lock()
+        .map(|config| config.is_big_superchat(payload.amount, &payload.coin))
+        .unwrap_or(false);
+    if is_big {
+        println!(
+            "大口のスーパーチャットを受信しました: {} {} from {}",
+            payload.amount, payload.coin, payload.display_name
+        );
+        if let Err(e) = app_handle.emit(BIG_SUPERCHAT_RECEIVED_EVENT, &payload) {
+            eprintln!(
+                "{} イベントの発火に失敗しました: {}",
+                BIG_SUPERCHAT_RECEIVED_EVENT, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_big_superchat() {
+        let config = SuperchatAlertConfig::default();
+
+        assert!(config.is_big_superchat(10.0, "SUI"));
+        assert!(!config.is_big_superchat(9.99, "SUI"));
+        // 閾値を設定していない通貨は大口として扱わない
+        assert!(!config.is_big_superchat(1000.0, "USDC"));
+    }
+}
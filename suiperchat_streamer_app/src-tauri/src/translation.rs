@@ -0,0 +1,245 @@
+//! メッセージ翻訳モジュール
+//!
+//! 配信者の設定言語と異なる言語で書かれたメッセージを、外部の翻訳API（DeepL・Google翻訳）で翻訳します。
+//! 翻訳はデフォルトで無効です。APIキーはメモリ上にのみ保持し、ログやフロントエンドへの応答には含めません。
+
+use crate::language::MIN_LANGUAGE_DETECTION_CHARS;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+use std::time::Duration;
+
+/// 翻訳APIリクエストのタイムアウト
+pub const TRANSLATION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// デフォルトの翻訳先言語 (ISO 639-1)
+pub const DEFAULT_TARGET_LANGUAGE: &str = "ja";
+
+/// DeepL API Free のエンドポイント
+const DEEPL_FREE_API_URL: &str = "https://api-free.deepl.com/v2/translate";
+
+/// DeepL API Pro のエンドポイント
+const DEEPL_PRO_API_URL: &str = "https://api.deepl.com/v2/translate";
+
+/// Google Cloud Translation API (v2) のエンドポイント
+const GOOGLE_TRANSLATE_API_URL: &str = "https://translation.googleapis.com/language/translate/v2";
+
+/// ## 翻訳APIの提供元
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranslationProvider {
+    /// DeepL API
+    #[default]
+    Deepl,
+    /// Google Cloud Translation API
+    Google,
+}
+
+/// ## 翻訳の設定
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranslationConfig {
+    /// 翻訳を行うかどうか
+    pub enabled: bool,
+    /// 使用する翻訳API
+    pub provider: TranslationProvider,
+    /// 翻訳先の言語（配信者の設定言語、ISO 639-1）
+    pub target_language: String,
+    /// スーパーチャットのみを翻訳するかどうか
+    pub superchats_only: bool,
+    /// 翻訳対象とするメッセージの最小文字数
+    pub min_chars: usize,
+}
+
+impl Default for TranslationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: TranslationProvider::default(),
+            target_language: DEFAULT_TARGET_LANGUAGE.to_string(),
+            superchats_only: true,
+            min_chars: MIN_LANGUAGE_DETECTION_CHARS,
+        }
+    }
+}
+
+impl TranslationConfig {
+    /// ## メッセージを翻訳対象とするか判定する
+    ///
+    /// ### Arguments
+    /// - `content`: メッセージ本文
+    /// - `detected_language`: 判定されたメッセージの言語（判定できない場合はNone）
+    /// - `is_superchat`: スーパーチャットかどうか
+    ///
+    /// ### Returns
+    /// - `bool`: 翻訳が有効で、メッセージの言語が翻訳先と異なる場合はtrue
+    pub fn should_translate(
+        &self,
+        content: &str,
+        detected_language: Option<&str>,
+        is_superchat: bool,
+    ) -> bool {
+        self.enabled
+            && (is_superchat || !self.superchats_only)
+            && content.trim().chars().count() >= self.min_chars
+            && detected_language
+                .is_some_and(|language| !language.eq_ignore_ascii_case(&self.target_language))
+    }
+}
+
+/// ## 翻訳APIのキー
+///
+/// ログに出力されないよう、`Debug` では値を伏せて表示します。
+#[derive(Clone)]
+pub struct TranslationApiKey(String);
+
+impl TranslationApiKey {
+    /// ## APIキーを作成する
+    ///
+    /// ### Arguments
+    /// - `key`: APIキー
+    ///
+    /// ### Returns
+    /// - `Option<Self>`: 空文字の場合はNone
+    pub fn new(key: &str) -> Option<Self> {
+        let key = key.trim();
+        (!key.is_empty()).then(|| Self(key.to_string()))
+    }
+}
+
+impl fmt::Debug for TranslationApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TranslationApiKey(***)")
+    }
+}
+
+/// ## 1件のメッセージの翻訳リクエスト
+#[derive(Debug, Clone)]
+pub struct TranslationJob {
+    /// 使用する翻訳API
+    provider: TranslationProvider,
+    /// 翻訳APIのキー
+    api_key: TranslationApiKey,
+    /// 翻訳先の言語
+    target_language: String,
+}
+
+impl TranslationJob {
+    /// ## メッセージの翻訳リクエストを準備する
+    ///
+    /// ### Arguments
+    /// - `app_state`: アプリケーション状態
+    /// - `content`: メッセージ本文
+    /// - `detected_language`: 判定されたメッセージの言語
+    /// - `is_superchat`: スーパーチャットかどうか
+    ///
+    /// ### Returns
+    /// - `Option<Self>`: 翻訳対象外、またはAPIキーが未設定の場合はNone
+    pub fn prepare(
+        app_state: &AppState,
+        content: &str,
+        detected_language: Option<&str>,
+        is_superchat: bool,
+    ) -> Option<Self> {
+        let config = app_state.translation.lock().ok()?.clone();
+        if !config.should_translate(content, detected_language, is_superchat) {
+            return None;
+        }
+        let api_key = app_state.translation_api_key.lock().ok()?.clone()?;
+        Some(Self {
+            provider: config.provider,
+            api_key,
+            target_language: config.target_language,
+        })
+    }
+
+    /// ## メッセージを翻訳する
+    ///
+    /// ### Arguments
+    /// - `text`: 翻訳するメッセージ
+    ///
+    /// ### Returns
+    /// - `Result<String, String>`: 翻訳したメッセージ、またはエラーメッセージ
+    pub async fn translate(&self, text: &str) -> Result<String, String> {
+        let client = reqwest::Client::builder()
+            .timeout(TRANSLATION_TIMEOUT)
+            .build()
+            .map_err(|e| format!("HTTPクライアントの作成に失敗しました: {}", e))?;
+        let key = &self.api_key.0;
+
+        let request = match self.provider {
+            TranslationProvider::Deepl => {
+                let url = if key.ends_with(":fx") {
+                    DEEPL_FREE_API_URL
+                } else {
+                    DEEPL_PRO_API_URL
+                };
+                client
+                    .post(url)
+                    .header("Authorization", format!("DeepL-Auth-Key {}", key))
+                    .json(&json!({
+                        "text": [text],
+                        "target_lang": self.target_language.to_ascii_uppercase(),
+                    }))
+            }
+            TranslationProvider::Google => client
+                .post(GOOGLE_TRANSLATE_API_URL)
+                .query(&[("key", key.as_str())])
+                .json(&json!({
+                    "q": text,
+                    "target": self.target_language,
+                    "format": "text",
+                })),
+        };
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("翻訳APIへのリクエストに失敗しました: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "翻訳APIがエラーを返しました: {}",
+                response.status()
+            ));
+        }
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("翻訳APIのレスポンスの解析に失敗しました: {}", e))?;
+
+        let translated = match self.provider {
+            TranslationProvider::Deepl => body["translations"][0]["text"].as_str(),
+            TranslationProvider::Google => {
+                body["data"]["translations"][0]["translatedText"].as_str()
+            }
+        };
+        translated
+            .map(str::to_string)
+            .ok_or_else(|| "翻訳APIのレスポンスに翻訳結果が含まれていません".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_translate() {
+        let config = TranslationConfig {
+            enabled: true,
+            ..TranslationConfig::default()
+        };
+        let content = "Thank you for the great stream!";
+
+        assert!(config.should_translate(content, Some("en"), true));
+        // 翻訳先と同じ言語・判定できない言語は翻訳しない
+        assert!(!config.should_translate(content, Some("ja"), true));
+        assert!(!config.should_translate(content, None, true));
+        // スーパーチャットのみの設定では通常チャットを翻訳しない
+        assert!(!config.should_translate(content, Some("en"), false));
+        // 短いメッセージは翻訳しない
+        assert!(!config.should_translate("gg", Some("en"), true));
+        // デフォルトでは無効
+        assert!(!TranslationConfig::default().should_translate(content, Some("en"), true));
+    }
+}
@@ -6,6 +6,7 @@
 //! 2. 接続管理やセッション処理に使用される共通の型と定数
 //! 3. 過去ログ取得関連の型定義
 
+use crate::coin_registry::{self, CoinInfo};
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
@@ -17,6 +18,40 @@ use std::time::Duration;
 /// WebSocketセッション設定値
 pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
 pub const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+/// ハートビートの応答時刻を接続マネージャーに反映する最小間隔（アイドル判定用）
+pub const HEARTBEAT_REPORT_INTERVAL: Duration = Duration::from_secs(30);
+/// 満員時のリダイレクト案内を送信してから切断するまでの猶予時間
+pub const OVERFLOW_REDIRECT_GRACE: Duration = Duration::from_millis(500);
+
+/// ## デフォルトのチャットチャンネル名
+///
+/// チャンネル未指定のメッセージや、接続直後のクライアントの購読チャンネルとして使用します。
+pub const DEFAULT_CHANNEL: &str = "general";
+
+/// グループ未割り当てのクライアントが所属するデフォルトグループ
+pub const DEFAULT_GROUP: &str = "default";
+
+/// 接続グループ名の最大文字数
+pub const MAX_GROUP_NAME_LENGTH: usize = 32;
+
+/// チャンネル名の最大文字数
+pub const MAX_CHANNEL_NAME_LENGTH: usize = 32;
+
+/// ## チャンネル名を正規化する
+///
+/// 前後の空白を除去し、未指定または空の場合はデフォルトチャンネルを返します。
+///
+/// ### Arguments
+/// - `channel`: クライアントから指定されたチャンネル名
+///
+/// ### Returns
+/// - `String`: 正規化されたチャンネル名
+pub fn normalize_channel(channel: Option<&str>) -> String {
+    match channel.map(str::trim) {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => DEFAULT_CHANNEL.to_string(),
+    }
+}
 
 /// ## グローバル接続カウンター
 ///
@@ -24,6 +59,34 @@ pub const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
 /// アトミック操作で安全に更新されます。
 pub static CONNECTIONS_COUNT: AtomicUsize = AtomicUsize::new(0);
 
+/// ## サーバー容量の情報
+///
+/// 視聴者フロントがWebSocket接続を試みる前に、新規接続を受け入れられるか確認するために使用します。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CapacityInfo {
+    /// 新規接続を受け入れられるかどうか
+    pub accepting: bool,
+    /// 現在の接続数
+    pub current: usize,
+    /// 設定された最大接続数
+    pub max: usize,
+}
+
+/// ## 接続中クライアントの統計
+///
+/// クライアント一覧を含まない軽量な集計結果です。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionStats {
+    /// 現在の接続数
+    pub total_connections: usize,
+    /// 全クライアントの送信メッセージ数の合計
+    pub total_messages_sent: usize,
+    /// 最も古い接続の接続時刻（接続がない場合はNone）
+    pub oldest_connected_at: Option<String>,
+    /// 直近1分間にアクティブだったクライアント数
+    pub active_last_minute: usize,
+}
+
 /// ## 接続情報
 ///
 /// 現在の接続数と最大接続数、接続クライアントの情報を保持します。
@@ -35,6 +98,40 @@ pub struct ConnectionsInfo {
     pub max_connections: usize,
     /// 接続中のクライアント情報のリスト
     pub clients: Vec<crate::ws_server::ClientInfo>,
+    /// モバイル回線と推定された視聴者数
+    pub mobile_clients: usize,
+    /// モバイル視聴者が多く、軽量モードの利用を推奨するかどうか
+    pub lightweight_mode_recommended: bool,
+    /// 接続方式ごとのクライアント数
+    pub connection_methods: ConnectionMethodBreakdown,
+}
+
+/// ## 接続方式ごとのクライアント数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConnectionMethodBreakdown {
+    /// 配信者のPCからの接続数
+    pub local: usize,
+    /// 同一LAN内からの直接接続数
+    pub lan: usize,
+    /// Cloudflaredトンネル経由の接続数
+    pub tunnel: usize,
+}
+
+/// ## ページ単位の接続情報
+///
+/// 大規模配信向けに、接続クライアントの一部のみを保持します。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaginatedConnectionsInfo {
+    /// 現在の接続数（ページ計算用の総数）
+    pub active_connections: usize,
+    /// 設定された最大接続数
+    pub max_connections: usize,
+    /// 取得開始位置
+    pub offset: usize,
+    /// 取得した最大件数
+    pub limit: usize,
+    /// 指定範囲の接続クライアント情報のリスト（接続時刻順）
+    pub clients: Vec<crate::ws_server::ClientInfo>,
 }
 
 /// 接続カウンターを増加させる
@@ -48,6 +145,11 @@ pub fn decrement_connections() -> usize {
     prev_count - 1
 }
 
+/// 接続カウンターを0にリセットする
+pub fn reset_connections() {
+    CONNECTIONS_COUNT.store(0, Ordering::SeqCst);
+}
+
 /// 現在の接続数を取得
 pub fn get_connections_count() -> usize {
     CONNECTIONS_COUNT.load(Ordering::SeqCst)
@@ -84,6 +186,27 @@ pub enum MessageType {
     /// 過去ログデータ
     #[serde(rename = "HISTORY_DATA")]
     HistoryData,
+    /// 人間検証チャレンジへの解答
+    #[serde(rename = "human_verification")]
+    HumanVerification,
+    /// 再接続時の欠損メッセージの再送リクエスト
+    #[serde(rename = "RESUME")]
+    Resume,
+    /// 配信者から特定の視聴者へのシステム通知
+    System,
+}
+
+/// ## チャンネル操作の種類
+///
+/// クライアントから送信されるチャンネル購読操作を定義します。
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Copy)]
+pub enum ChannelAction {
+    /// チャンネルを購読する
+    #[serde(rename = "subscribe_channel")]
+    Subscribe,
+    /// チャンネルの購読を解除する
+    #[serde(rename = "unsubscribe_channel")]
+    Unsubscribe,
 }
 
 /// ## スーパーチャットのデータ構造体
@@ -99,6 +222,39 @@ pub struct SuperchatData {
     pub tx_hash: String,
     /// 送金者のウォレットアドレス
     pub wallet_address: String,
+    /// 送金額の単位 (例: "mist")。未指定の場合は `amount` を表示単位として扱う
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount_unit: Option<String>,
+}
+
+impl SuperchatData {
+    /// ## 表示単位の送金額を取得する
+    ///
+    /// 単位やコインが不明な場合は警告を出力し、受信した金額をそのまま返します。
+    ///
+    /// ### Arguments
+    /// - `coin`: 送金に使用されたコイン（未登録の場合はNone）
+    ///
+    /// ### Returns
+    /// - `f64`: 表示単位の送金額
+    pub fn display_amount(&self, coin: Option<&CoinInfo>) -> f64 {
+        coin_registry::to_display_amount(self.amount, self.amount_unit.as_deref(), coin)
+            .unwrap_or_else(|e| {
+                eprintln!("警告: {}（受信した金額をそのまま使用します）", e);
+                self.amount
+            })
+    }
+
+    /// ## 送金額を表示単位に変換する
+    ///
+    /// 最小単位で送られた金額をコインの小数点以下の桁数で変換し、`amount_unit` を消去します。
+    ///
+    /// ### Arguments
+    /// - `coin`: 送金に使用されたコイン（未登録の場合はNone）
+    pub fn normalize_amount(&mut self, coin: Option<&CoinInfo>) {
+        self.amount = self.display_amount(coin);
+        self.amount_unit = None;
+    }
 }
 
 /// ## ベースメッセージ構造体
@@ -132,6 +288,18 @@ pub struct ChatMessage {
     /// タイムスタンプ (Unixミリ秒, オプション)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timestamp: Option<i64>,
+    /// 投稿先チャンネル (未指定の場合は "general")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    /// サーバー側で判定したメッセージの言語 (ISO 639-1、短いメッセージなど判定できない場合はNone)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detected_language: Option<String>,
+    /// 配信者の設定言語への翻訳 (翻訳は `translation` メッセージで追って送信するため、受信時・配信時ともにNone)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translated_content: Option<String>,
+    /// 送信者の称号バッジ (サーバー側で設定、ウォレットアドレスが不明な場合は空)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub badges: Vec<String>,
 }
 
 /// ## スーパーチャットメッセージ構造体
@@ -154,6 +322,18 @@ pub struct SuperchatMessage {
     /// タイムスタンプ (Unixミリ秒, オプション)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timestamp: Option<i64>,
+    /// 送金者が連続してスーパーチャットした配信日数 (サーバー側で算出)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub donor_streak: Option<u32>,
+    /// オンチェーンで送金を確認できたかどうか (サーバー側で設定、未検証の場合はfalse)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+    /// 配信者の設定言語への翻訳 (翻訳は `translation` メッセージで追って送信するため、受信時・配信時ともにNone)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translated_content: Option<String>,
+    /// 送信者の称号バッジ (サーバー側で設定、ウォレットアドレスが不明な場合は空)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub badges: Vec<String>,
 }
 
 /// ## クライアントメッセージ列挙型
@@ -167,6 +347,36 @@ pub enum ClientMessage {
     Superchat(SuperchatMessage),
     /// 通常のチャットメッセージ
     Chat(ChatMessage),
+    /// チャンネル購読・購読解除リクエスト
+    ChannelSubscription {
+        /// 操作の種類 (subscribe_channel または unsubscribe_channel)
+        #[serde(rename = "type")]
+        action: ChannelAction,
+        /// 対象のチャンネル名
+        channel: String,
+    },
+    /// 人間検証チャレンジへの解答
+    ///
+    /// `GetHistory` も `type` の値を区別しないため、必須フィールドの多いこちらを先に判定する
+    HumanVerification {
+        /// メッセージタイプ (human_verification固定)
+        #[serde(rename = "type")]
+        message_type: MessageType,
+        /// サーバーから受け取ったチャレンジ
+        challenge: String,
+        /// 探索して見つけた値
+        nonce: String,
+    },
+    /// 再接続時の欠損メッセージの再送リクエスト
+    ///
+    /// `GetHistory` も `type` の値を区別しないため、必須フィールドのあるこちらを先に判定する
+    Resume {
+        /// メッセージタイプ (RESUME固定)
+        #[serde(rename = "type")]
+        message_type: MessageType,
+        /// 切断前に最後に受信したブロードキャストのシーケンス番号
+        last_seq: u64,
+    },
     /// 過去ログリクエスト
     GetHistory {
         /// メッセージタイプ (GET_HISTORY固定)
@@ -176,9 +386,59 @@ pub enum ClientMessage {
         limit: Option<i64>,
         /// このタイムスタンプより前のメッセージを取得
         before_timestamp: Option<i64>,
+        /// `before_timestamp` と同一時刻のメッセージのうち、このシーケンス番号より前のものも取得
+        #[serde(default)]
+        before_sequence: Option<i64>,
+        /// 取得対象のチャンネル (指定しない場合は全チャンネル)
+        #[serde(default)]
+        channel: Option<String>,
+        /// 取得対象の言語 (ISO 639-1、指定しない場合は全言語)
+        #[serde(default)]
+        language: Option<String>,
     },
 }
 
+impl ClientMessage {
+    /// ## メッセージIDを取得する
+    ///
+    /// ### Returns
+    /// - `Option<&str>`: チャット・スーパーチャットのID（それ以外のメッセージはNone）
+    pub fn message_id(&self) -> Option<&str> {
+        match self {
+            ClientMessage::Chat(msg) => Some(msg.id.as_str()),
+            ClientMessage::Superchat(msg) => Some(msg.id.as_str()),
+            _ => None,
+        }
+    }
+
+    /// ## メッセージのタイムスタンプ (Unixミリ秒) を取得する
+    ///
+    /// ### Returns
+    /// - `Option<i64>`: チャット・スーパーチャットのタイムスタンプ（それ以外のメッセージはNone）
+    pub fn timestamp(&self) -> Option<i64> {
+        match self {
+            ClientMessage::Chat(msg) => msg.timestamp,
+            ClientMessage::Superchat(msg) => msg.timestamp,
+            _ => None,
+        }
+    }
+
+    /// ## メッセージのタイムスタンプをサーバーの受信時刻で上書きする
+    ///
+    /// クライアントの時計のずれに影響されないよう、サーバーの受信時刻を権威的な時刻として扱います。
+    /// チャット・スーパーチャット以外のメッセージは変更しません。
+    ///
+    /// ### Arguments
+    /// - `received_at`: サーバーの受信時刻 (Unixミリ秒)
+    pub fn set_server_timestamp(&mut self, received_at: i64) {
+        match self {
+            ClientMessage::Chat(msg) => msg.timestamp = Some(received_at),
+            ClientMessage::Superchat(msg) => msg.timestamp = Some(received_at),
+            _ => {}
+        }
+    }
+}
+
 /// ## サーバーレスポンスメッセージ
 ///
 /// エラーやその他のシステムメッセージを送信するための構造体です。
@@ -193,6 +453,42 @@ pub struct ServerResponse {
     pub timestamp: String,
 }
 
+/// ## 切断通知構造体
+///
+/// サーバー停止時など、サーバー側から切断する前に全クライアントへ送信します。
+/// 視聴者側は `reconnect` が `false` の場合に再接続ループを停止します。
+#[derive(Debug, Serialize)]
+pub struct DisconnectNotice {
+    /// メッセージタイプ（常に `MessageType::Disconnected`）
+    #[serde(rename = "type")]
+    pub message_type: MessageType,
+    /// 切断の理由
+    pub reason: String,
+    /// 再接続を試みるべきかどうか
+    pub reconnect: bool,
+    /// タイムスタンプ
+    pub timestamp: String,
+}
+
+impl DisconnectNotice {
+    /// ## 切断通知を作成する
+    ///
+    /// ### Arguments
+    /// - `reason`: 切断の理由
+    /// - `reconnect`: 再接続を試みるべきかどうか
+    ///
+    /// ### Returns
+    /// - `Self`: 現在時刻の切断通知
+    pub fn new(reason: impl Into<String>, reconnect: bool) -> Self {
+        Self {
+            message_type: MessageType::Disconnected,
+            reason: reason.into(),
+            reconnect,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
 /// ## サーバーからのメッセージ列挙型
 ///
 /// WebSocketサーバーからクライアントに送信するメッセージの型を定義します。
@@ -219,6 +515,111 @@ pub enum OutgoingMessage {
         /// エラーメッセージ
         message: String,
     },
+    /// 購読中チャンネルの更新通知
+    #[serde(rename = "channels_updated")]
+    ChannelsUpdated {
+        /// 現在購読中のチャンネル一覧
+        channels: Vec<String>,
+    },
+    /// 接続先移行の通知（グレースフルリスタート時）
+    #[serde(rename = "migrate")]
+    Migrate {
+        /// 新しい接続先のWebSocket URL
+        url: String,
+        /// 旧サーバーが停止するまでの猶予時間（秒）
+        grace_period_secs: u64,
+    },
+    /// 満員時の代替視聴先への誘導
+    #[serde(rename = "redirect")]
+    Redirect {
+        /// 代替URL（録画アーカイブ、別プラットフォームなど）
+        url: String,
+        /// 誘導の理由
+        reason: String,
+    },
+    /// 人間検証（proof-of-work）の要求
+    #[serde(rename = "human_verification_required")]
+    HumanVerificationRequired {
+        /// ハッシュの入力に使うチャレンジ文字列
+        challenge: String,
+        /// 要求する先頭の0ビット数
+        difficulty: u8,
+        /// ハッシュアルゴリズム ("sha256")
+        algorithm: String,
+    },
+    /// アイドル状態による切断の通知（再接続は可能）
+    #[serde(rename = "idle_disconnect")]
+    IdleDisconnect {
+        /// 切断の理由
+        reason: String,
+        /// 最後の活動からの経過秒数
+        idle_secs: u64,
+    },
+    /// 配信済みメッセージの翻訳（翻訳APIの応答後に追って送信）
+    #[serde(rename = "translation")]
+    Translation {
+        /// 翻訳したメッセージのID
+        id: String,
+        /// 配信者の設定言語への翻訳
+        translated_content: String,
+    },
+    /// 人間検証の完了通知
+    #[serde(rename = "human_verified")]
+    HumanVerified,
+    /// 再接続時の欠損メッセージの再送完了通知
+    #[serde(rename = "resume_complete")]
+    ResumeComplete {
+        /// 再送したメッセージ数（フロー制御によりこの通知より後に届く場合がある）
+        replayed: usize,
+        /// 最新のシーケンス番号
+        latest_seq: u64,
+        /// 欠損分を全て再送できたかどうか（falseの場合は GET_HISTORY で補完する）
+        complete: bool,
+    },
+    /// スーパーチャット総額のマイルストーン達成通知
+    #[serde(rename = "milestone_reached")]
+    MilestoneReached {
+        /// 達成したマイルストーン金額
+        milestone: f64,
+        /// 現在の総額
+        total: f64,
+        /// 通貨シンボル
+        coin: String,
+    },
+    /// メンテナンス予告（カウントダウン・キャンセル・開始の通知）
+    #[serde(rename = "maintenance")]
+    Maintenance {
+        /// 予告の段階
+        phase: crate::maintenance::MaintenancePhase,
+        /// 予告メッセージ（キャンセル時は空文字）
+        message: String,
+        /// メンテナンス開始までの残り秒数
+        remaining_secs: u64,
+        /// カウントダウン終了時の動作
+        action: crate::maintenance::MaintenanceAction,
+    },
+    /// OBSレイアウトの切り替え通知（OBS接続のみに送信し、OBS側は `/obs/layout.css` を読み込み直す）
+    #[serde(rename = "obs_layout")]
+    ObsLayout {
+        /// 切り替え後のレイアウト
+        layout: crate::obs_layout::ObsLayout,
+    },
+    /// OBSテーマの変更通知（OBS接続のみに送信し、OBS側は `/obs/styles.css` を読み込み直す）
+    #[serde(rename = "theme_updated")]
+    ThemeUpdated {
+        /// 変更後のテーマ
+        theme: crate::obs_theme::ObsTheme,
+    },
+    /// 配信者によるメッセージの編集通知
+    #[serde(rename = "message_edited")]
+    MessageEdited {
+        /// 編集したメッセージのID
+        id: String,
+        /// 編集後の内容
+        message: String,
+        /// 編集済みかどうか（常にtrue。視聴者フロントで「（編集済み）」を表示する）
+        is_edited: bool,
+    },
 }
 
 /// ## クライアントに送信するメッセージ構造体
@@ -238,6 +639,16 @@ pub struct SerializableMessage {
     pub message: String,
     /// タイムスタンプ (Unixミリ秒)
     pub timestamp: i64,
+    /// 投稿先チャンネル
+    pub channel: String,
+    /// 判定されたメッセージの言語 (ISO 639-1)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// 送信後に編集されたかどうか（視聴者フロントで「（編集済み）」を表示する）
+    pub is_edited: bool,
+    /// 受信順のシーケンス番号（過去ログの続きを取得する際に `before_sequence` として指定する）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<i64>,
     /// スーパーチャットデータ (スーパーチャットの場合のみ)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub superchat: Option<SerializableSuperchatData>,
@@ -293,6 +704,10 @@ impl From<crate::db_models::Message> for SerializableMessage {
             display_name: db_msg.display_name,
             message: db_msg.content,
             timestamp,
+            channel: normalize_channel(db_msg.channel.as_deref()),
+            language: db_msg.language,
+            is_edited: db_msg.is_edited,
+            sequence: db_msg.sequence,
             superchat,
         }
     }
@@ -313,6 +728,10 @@ pub struct SerializableMessageForStreamer {
     pub display_name: String,
     pub content: String, // viewerでは "message" だったが、DBのフィールド名に合わせる
     pub timestamp: i64,  // Unixミリ秒
+    pub channel: String, // 投稿先チャンネル
+    pub language: Option<String>, // 判定されたメッセージの言語 (ISO 639-1)
+    pub is_edited: bool, // 送信後に編集されたかどうか
+    pub highlighted: bool, // 配信者がハイライトしたかどうか
     pub superchat_specific_data: Option<SerializableSuperchatDataForStreamer>, // フィールド名を変更
 }
 
@@ -342,6 +761,10 @@ impl From<crate::db_models::Message> for SerializableMessageForStreamer {
             display_name: db_msg.display_name.clone(),
             content: db_msg.content.clone(),
             timestamp: db_msg.timestamp.timestamp_millis(),
+            channel: normalize_channel(db_msg.channel.as_deref()),
+            language: db_msg.language.clone(),
+            is_edited: db_msg.is_edited,
+            highlighted: db_msg.highlighted,
             superchat_specific_data,
         }
     }
@@ -361,6 +784,10 @@ mod tests {
             display_name: "テストユーザー".to_string(),
             content: "こんにちは、世界！".to_string(),
             timestamp: Some(1679400000000_i64), // 数値タイムスタンプに変更
+            channel: None,
+            detected_language: None,
+            translated_content: None,
+            badges: Vec::new(),
         };
 
         // メッセージをJSONにシリアライズ
@@ -392,6 +819,7 @@ mod tests {
             coin: "SUI".to_string(),
             tx_hash: "0x1234567890abcdef".to_string(),
             wallet_address: "0xabcdef1234567890".to_string(),
+            amount_unit: None,
         };
 
         // テスト用のスーパーチャットメッセージを作成
@@ -402,6 +830,10 @@ mod tests {
             content: "大応援してます！".to_string(),
             superchat: superchat_data,
             timestamp: Some(1679401800000_i64), // 数値タイムスタンプに変更
+            donor_streak: None,
+            verified: None,
+            translated_content: None,
+            badges: Vec::new(),
         };
 
         // メッセージをJSONにシリアライズ
@@ -492,6 +924,75 @@ mod tests {
             _ => panic!("スーパーチャットメッセージが正しくパースされませんでした"),
         }
     }
+
+    /// ## チャンネル関連メッセージのパースをテスト
+    #[test]
+    fn test_channel_messages() {
+        let subscribe_json = r#"{"type": "subscribe_channel", "channel": "game"}"#;
+        match serde_json::from_str::<ClientMessage>(subscribe_json).expect("パースに失敗") {
+            ClientMessage::ChannelSubscription { action, channel } => {
+                assert_eq!(action, ChannelAction::Subscribe);
+                assert_eq!(channel, "game");
+            }
+            _ => panic!("購読リクエストが正しくパースされませんでした"),
+        }
+
+        // チャンネル指定付きの履歴リクエストが購読リクエストと誤認されないこと
+        let history_json = r#"{"type": "GET_HISTORY", "limit": 10, "channel": "game"}"#;
+        match serde_json::from_str::<ClientMessage>(history_json).expect("パースに失敗") {
+            ClientMessage::GetHistory { limit, channel, .. } => {
+                assert_eq!(limit, Some(10));
+                assert_eq!(channel.as_deref(), Some("game"));
+            }
+            _ => panic!("履歴リクエストが正しくパースされませんでした"),
+        }
+
+        // チャンネル未指定のチャットは "general" として扱われること
+        let chat_json = r#"{"type": "chat", "id": "id", "display_name": "A", "message": "hi"}"#;
+        match serde_json::from_str::<ClientMessage>(chat_json).expect("パースに失敗") {
+            ClientMessage::Chat(chat) => {
+                assert_eq!(normalize_channel(chat.channel.as_deref()), DEFAULT_CHANNEL);
+            }
+            _ => panic!("チャットメッセージが正しくパースされませんでした"),
+        }
+    }
+
+    /// ## 並行して完了する起動処理のフェーズ判定をテスト
+    #[test]
+    fn test_startup_phase_progression() {
+        assert_eq!(
+            StartupProgress::default().current_phase(),
+            StartupPhase::Stopped
+        );
+
+        let mut progress = StartupProgress::begin(true);
+        assert_eq!(progress.current_phase(), StartupPhase::Binding);
+
+        // トンネルが先に完了してもIP取得が終わるまではreadyにならない
+        progress.tunnel_settled = true;
+        progress.bound = true;
+        assert_eq!(progress.current_phase(), StartupPhase::FetchingIp);
+        progress.ip_fetched = true;
+        assert_eq!(progress.current_phase(), StartupPhase::CheckingCgnat);
+        progress.cgnat_checked = true;
+        assert_eq!(progress.current_phase(), StartupPhase::Ready);
+
+        // 最初の失敗のみ保持される
+        progress.fail(StartupPhase::StartingTunnel, "tunnel error");
+        progress.fail(StartupPhase::Binding, "bind error");
+        assert_eq!(progress.current_phase(), StartupPhase::Failed);
+        assert_eq!(
+            progress.failure,
+            Some((StartupPhase::StartingTunnel, "tunnel error".to_string()))
+        );
+
+        // トンネル不要の場合はバインドとIP取得後にready
+        let mut progress = StartupProgress::begin(false);
+        progress.bound = true;
+        progress.ip_fetched = true;
+        progress.cgnat_checked = true;
+        assert_eq!(progress.current_phase(), StartupPhase::Ready);
+    }
 }
 
 //=============================================================================
@@ -517,8 +1018,136 @@ pub struct ServerStatus {
     pub cgnat_detected: bool,
     /// Cloudflare HTTPS URL (例: "https://*.trycloudflare.com")
     pub cloudflare_http_url: Option<String>,
-    /// トンネルの状態 ("Stopped", "Starting", "Running", "Failed" など)
+    /// 配信者のPCから接続するWebSocket URL (例: "ws://127.0.0.1:8082/ws")
+    pub local_ws_url: Option<String>,
+    /// 同一LAN内から接続するWebSocket URL（LANから到達できない場合はNone）
+    pub lan_ws_url: Option<String>,
+    /// トンネル経由で接続するWebSocket URL（トンネル未起動の場合はNone）
+    pub tunnel_ws_url: Option<String>,
+    /// UPnPで開放したポートにグローバルIPで接続するWebSocket URL（ポート未開放の場合はNone）
+    pub upnp_ws_url: Option<String>,
+    /// トンネルの状態 ("Stopped", "Starting", "Running", "Unhealthy", "Failed" など)
     pub tunnel_status: String,
-    /// トンネル接続失敗時のエラーメッセージ
+    /// トンネル接続失敗時・HTTPの応答がない場合のエラーメッセージ
     pub tunnel_error: Option<String>,
+    /// 起動フェーズ ("stopped", "binding", "fetching_ip", "checking_cgnat", "starting_tunnel", "ready", "failed")
+    pub startup_phase: String,
+    /// 起動に失敗したフェーズ（失敗していない場合はNone）
+    pub failed_phase: Option<String>,
+    /// 起動失敗時のエラーメッセージ
+    pub startup_error: Option<String>,
+}
+
+/// ## サーバー起動フェーズ
+///
+/// サーバー起動処理（バインド→IP取得→CGNAT判定→トンネル起動→URL確定）の進捗を表します。
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupPhase {
+    /// サーバーは停止中
+    Stopped,
+    /// サーバーのバインドとセッション作成中
+    Binding,
+    /// 外部IPを取得中
+    FetchingIp,
+    /// CGNAT判定中
+    CheckingCgnat,
+    /// Cloudflaredトンネルを起動中
+    StartingTunnel,
+    /// 起動完了（URL確定）
+    Ready,
+    /// 起動に失敗
+    Failed,
+}
+
+impl StartupPhase {
+    /// フロントエンドに通知するフェーズ名を取得する
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StartupPhase::Stopped => "stopped",
+            StartupPhase::Binding => "binding",
+            StartupPhase::FetchingIp => "fetching_ip",
+            StartupPhase::CheckingCgnat => "checking_cgnat",
+            StartupPhase::StartingTunnel => "starting_tunnel",
+            StartupPhase::Ready => "ready",
+            StartupPhase::Failed => "failed",
+        }
+    }
+}
+
+/// ## サーバー起動の進捗
+///
+/// 外部IP取得とトンネル起動はバインドと並行して実行されるため、
+/// 各処理の完了状態を個別に保持し、未完了の最初の処理を現在のフェーズとします。
+#[derive(Clone, Debug, Default)]
+pub struct StartupProgress {
+    /// 起動処理中または起動済みかどうか
+    pub active: bool,
+    /// サーバーのバインドとセッション作成が完了したか
+    pub bound: bool,
+    /// 外部IPの取得が完了したか（失敗した場合も完了とみなす）
+    pub ip_fetched: bool,
+    /// CGNAT判定が完了したか
+    pub cgnat_checked: bool,
+    /// トンネルの起動処理が完了したか（トンネルを使用しない場合は最初から完了）
+    pub tunnel_settled: bool,
+    /// 失敗したフェーズとエラーメッセージ
+    pub failure: Option<(StartupPhase, String)>,
+}
+
+impl StartupProgress {
+    /// ## 起動処理の開始時の進捗を作成する
+    ///
+    /// ### Arguments
+    /// - `tunnel_required`: Cloudflaredトンネルを起動するかどうか
+    pub fn begin(tunnel_required: bool) -> Self {
+        Self {
+            active: true,
+            tunnel_settled: !tunnel_required,
+            ..Self::default()
+        }
+    }
+
+    /// ## 失敗したフェーズを記録する
+    ///
+    /// 最初に発生した失敗のみを保持します。
+    pub fn fail(&mut self, phase: StartupPhase, error: impl Into<String>) {
+        if self.failure.is_none() {
+            self.failure = Some((phase, error.into()));
+        }
+    }
+
+    /// ## 現在のフェーズを取得する
+    pub fn current_phase(&self) -> StartupPhase {
+        if self.failure.is_some() {
+            StartupPhase::Failed
+        } else if !self.active {
+            StartupPhase::Stopped
+        } else if !self.bound {
+            StartupPhase::Binding
+        } else if !self.ip_fetched {
+            StartupPhase::FetchingIp
+        } else if !self.cgnat_checked {
+            StartupPhase::CheckingCgnat
+        } else if !self.tunnel_settled {
+            StartupPhase::StartingTunnel
+        } else {
+            StartupPhase::Ready
+        }
+    }
+}
+
+/// ## サーバー移行フェーズ
+///
+/// グレースフルリスタート中のサーバー移行状態を表します。
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum MigrationPhase {
+    /// 移行処理は行われていない
+    Idle,
+    /// 新しいサーバー（およびトンネル）を起動中
+    StartingNewServer,
+    /// 既存接続に移行を通知し、猶予時間を待機中
+    Migrating,
+    /// 旧サーバーを停止中
+    StoppingOldServer,
 }
@@ -0,0 +1,100 @@
+//! 入力値の形式検証モジュール
+//!
+//! 配信者の設定や視聴者から受信したスーパーチャットに含まれるウォレットアドレス・
+//! トランザクションハッシュの形式を検証します。偽装された不正な値がDBに混入するのを防ぎます。
+
+/// SUIウォレットアドレスの長さ（"0x" + 64文字の16進数）
+const SUI_ADDRESS_LEN: usize = 66;
+
+/// トランザクションハッシュの最大文字数
+const MAX_TX_HASH_LEN: usize = 66;
+
+/// Base58で使用する文字（0, O, I, l を除く英数字）
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// ## SUIウォレットアドレスの形式を検証する
+///
+/// `0x` で始まり、続く64文字が16進数であるアドレスのみを受け付けます。
+///
+/// ### Arguments
+/// - `address`: 検証するウォレットアドレス
+///
+/// ### Returns
+/// - `Result<String, String>`: 前後の空白を除いたアドレス、形式が不正な場合はエラーメッセージ
+pub fn validate_sui_address(address: &str) -> Result<String, String> {
+    let trimmed_address = address.trim();
+
+    if !trimmed_address.starts_with("0x") {
+        return Err("Invalid SUI wallet address: Must start with '0x'.".to_string());
+    }
+    if trimmed_address.len() != SUI_ADDRESS_LEN {
+        return Err(format!(
+            "Invalid SUI wallet address: Expected length {}, got {}.",
+            SUI_ADDRESS_LEN,
+            trimmed_address.len()
+        ));
+    }
+    if !trimmed_address[2..].chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(
+            "Invalid SUI wallet address: Contains non-hexadecimal characters after '0x'."
+                .to_string(),
+        );
+    }
+    Ok(trimmed_address.to_string())
+}
+
+/// ## トランザクションハッシュの形式を検証する
+///
+/// `0x` で始まる16進数に加え、Suiのトランザクションダイジェスト（Base58）も受け付けます。
+/// 実在するトランザクションかどうかはここでは確認しません。
+///
+/// ### Arguments
+/// - `tx_hash`: 検証するトランザクションハッシュ
+///
+/// ### Returns
+/// - `Result<String, String>`: 前後の空白を除いたハッシュ、形式が不正な場合はエラーメッセージ
+pub fn validate_tx_hash(tx_hash: &str) -> Result<String, String> {
+    let trimmed_hash = tx_hash.trim();
+
+    if trimmed_hash.is_empty() || trimmed_hash.len() > MAX_TX_HASH_LEN {
+        return Err(format!(
+            "Invalid transaction hash: Expected 1 to {} characters, got {}.",
+            MAX_TX_HASH_LEN,
+            trimmed_hash.len()
+        ));
+    }
+    let is_valid = match trimmed_hash.strip_prefix("0x") {
+        Some(hex) => !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => trimmed_hash.chars().all(|c| BASE58_ALPHABET.contains(c)),
+    };
+    if !is_valid {
+        return Err(
+            "Invalid transaction hash: Must be a '0x' prefixed hex string or a Base58 digest."
+                .to_string(),
+        );
+    }
+    Ok(trimmed_hash.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_sui_address_and_tx_hash() {
+        let address = format!("0x{}", "ab".repeat(32));
+        assert_eq!(
+            validate_sui_address(&format!(" {} ", address)),
+            Ok(address.clone())
+        );
+        assert!(validate_sui_address("0x1234").is_err());
+        assert!(validate_sui_address(&format!("0x{}", "zz".repeat(32))).is_err());
+
+        assert!(validate_tx_hash("0x1234567890abcdef").is_ok());
+        assert!(validate_tx_hash("8Wq3rT6yU1iO4pA7sD0fG2hJ5kL9zX3cV6bN8mQ1wE4r").is_err());
+        assert!(validate_tx_hash("8Wq3rT6yU1ip4pA7sDSfG2hJ5kL9zX3cV6bN8mQ1wE4r").is_ok());
+        assert!(validate_tx_hash("0x").is_err());
+        assert!(validate_tx_hash("0xzz").is_err());
+        assert!(validate_tx_hash("").is_err());
+    }
+}
@@ -0,0 +1,70 @@
+//! 配信者ウォレットの管理モジュール
+//!
+//! メインウォレットとサブウォレットを配信内容によって使い分けられるよう、
+//! ラベル付きのウォレットアドレスを複数登録し、そのうち1つをアクティブとして扱います。
+//! 視聴者への送金先やトランザクション検証には、アクティブなウォレットのアドレスを使用します。
+
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+
+/// ## 登録済みのウォレット
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletEntry {
+    /// ウォレットのラベル (例: "メイン", "サブ")
+    pub label: String,
+    /// SUIウォレットアドレス
+    pub address: String,
+}
+
+/// ## 登録済みのウォレットからアドレスを検索する
+///
+/// 16進数の大文字・小文字は区別しません。
+///
+/// ### Arguments
+/// - `wallets`: 登録済みのウォレット
+/// - `address`: 検索するウォレットアドレス
+///
+/// ### Returns
+/// - `Option<usize>`: 見つかった場合はウォレットのインデックス
+pub fn find_wallet(wallets: &[WalletEntry], address: &str) -> Option<usize> {
+    wallets
+        .iter()
+        .position(|wallet| wallet.address.eq_ignore_ascii_case(address.trim()))
+}
+
+/// ## アクティブなウォレットのアドレスを取得する
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+///
+/// ### Returns
+/// - `Option<String>`: アクティブなウォレットのアドレス（未登録の場合はNone）
+pub fn active_wallet_address(app_state: &AppState) -> Option<String> {
+    let wallets = app_state.wallets.lock().ok()?;
+    let active_index = (*app_state.active_wallet_index.lock().ok()?)?;
+    wallets
+        .get(active_index)
+        .map(|wallet| wallet.address.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_wallet() {
+        let address = format!("0x{}", "ab".repeat(32));
+        let wallets = vec![WalletEntry {
+            label: "メイン".to_string(),
+            address: address.clone(),
+        }];
+        assert_eq!(
+            find_wallet(&wallets, &address.to_uppercase().replace("0X", "0x")),
+            Some(0)
+        );
+        assert_eq!(
+            find_wallet(&wallets, &format!("0x{}", "cd".repeat(32))),
+            None
+        );
+    }
+}
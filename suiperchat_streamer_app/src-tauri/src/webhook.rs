@@ -0,0 +1,203 @@
+//! 受信メッセージのWebhook転送モジュール
+//!
+//! Discord通知やスプレッドシート連携のため、受信したスーパーチャット（設定により通常チャットも）を
+//! 配信者が設定した外部URLへJSONでPOSTします。
+//! 転送はバックグラウンドで行い、失敗してもブロードキャストやDB保存には影響させません。
+//! 失敗した場合は1回だけリトライし、それでも失敗した場合はログに記録します。
+
+use crate::state::AppState;
+use crate::types::ClientMessage;
+use serde::Serialize;
+use std::time::Duration;
+use tauri::Manager;
+
+/// Webhookへの送信のタイムアウト
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 送信に失敗した場合にリトライするまでの待ち時間
+const WEBHOOK_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// ## Webhookに送信するメッセージ
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebhookPayload {
+    /// メッセージタイプ ("chat" または "superchat")
+    #[serde(rename = "type")]
+    pub message_type: &'static str,
+    /// メッセージID
+    pub id: String,
+    /// 表示名
+    pub display_name: String,
+    /// メッセージ本文
+    pub content: String,
+    /// 金額（通常チャットの場合はNone）
+    pub amount: Option<f64>,
+    /// 通貨シンボル（通常チャットの場合はNone）
+    pub coin: Option<String>,
+    /// トランザクションハッシュ（通常チャットの場合はNone）
+    pub tx_hash: Option<String>,
+}
+
+impl WebhookPayload {
+    /// ## 受信メッセージからWebhookに送信するメッセージを作成する
+    ///
+    /// ### Arguments
+    /// - `client_msg`: 受信メッセージ
+    /// - `include_chat`: 通常チャットも転送するかどうか
+    ///
+    /// ### Returns
+    /// - `Option<Self>`: 転送対象外のメッセージの場合はNone
+    pub fn from_client_message(client_msg: &ClientMessage, include_chat: bool) -> Option<Self> {
+        match client_msg {
+            ClientMessage::Superchat(msg) => Some(Self {
+                message_type: "superchat",
+                id: msg.id.clone(),
+                display_name: msg.display_name.clone(),
+                content: msg.content.clone(),
+                amount: Some(msg.superchat.amount),
+                coin: Some(msg.superchat.coin.clone()),
+                tx_hash: Some(msg.superchat.tx_hash.clone()),
+            }),
+            ClientMessage::Chat(msg) if include_chat => Some(Self {
+                message_type: "chat",
+                id: msg.id.clone(),
+                display_name: msg.display_name.clone(),
+                content: msg.content.clone(),
+                amount: None,
+                coin: None,
+                tx_hash: None,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// ## 受信メッセージをWebhookに転送する
+///
+/// Webhookが設定されていない場合や転送対象外のメッセージの場合は何もしません。
+/// 送信はバックグラウンドで行うため、呼び出し元の処理はブロックしません。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `client_msg`: 受信メッセージ
+pub fn forward_message(app_handle: &tauri::AppHandle, client_msg: &ClientMessage) {
+    let Some(app_state) = app_handle.try_state::<AppState>() else {
+        return;
+    };
+    let Some(url) = app_state
+        .webhook_url
+        .lock()
+        .ok()
+        .and_then(|url| url.clone())
+    else {
+        return;
+    };
+    let include_chat = app_state
+        .webhook_include_chat
+        .lock()
+        .is_ok_and(|include_chat| *include_chat);
+    let Some(payload) = WebhookPayload::from_client_message(client_msg, include_chat) else {
+        return;
+    };
+
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = post_with_retry(&url, &payload).await {
+            eprintln!(
+                "Webhookへのメッセージの転送に失敗しました ({}): {}",
+                payload.id, e
+            );
+        }
+    });
+}
+
+/// ## ログに出力するためにWebhookのURLをマスクする
+///
+/// DiscordなどのWebhookはURLのパスにトークンを含むため、スキームとホスト以外を伏せます。
+///
+/// ### Arguments
+/// - `url`: WebhookのURL
+///
+/// ### Returns
+/// - `String`: マスクしたURL (例: "https://discord.com/[REDACTED]")
+pub fn redact_url(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(parsed) => format!(
+            "{}://{}/[REDACTED]",
+            parsed.scheme(),
+            parsed.host_str().unwrap_or_default()
+        ),
+        Err(_) => "[REDACTED]".to_string(),
+    }
+}
+
+/// Webhookにメッセージを送信し、失敗した場合は1回だけリトライする
+async fn post_with_retry(url: &str, payload: &WebhookPayload) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .map_err(|e| format!("HTTPクライアントの構築に失敗しました: {}", e))?;
+
+    match post(&client, url, payload).await {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            println!("Webhookへの送信に失敗したためリトライします: {}", e);
+            tokio::time::sleep(WEBHOOK_RETRY_DELAY).await;
+            post(&client, url, payload).await
+        }
+    }
+}
+
+/// Webhookにメッセージを1回送信する
+async fn post(client: &reqwest::Client, url: &str, payload: &WebhookPayload) -> Result<(), String> {
+    let response = client
+        .post(url)
+        .json(payload)
+        .send()
+        .await
+        // エラーメッセージにトークンを含むURLが出力されないよう、URLを取り除く
+        .map_err(|e| format!("送信エラー: {}", e.without_url()))?;
+    if !response.status().is_success() {
+        return Err(format!("HTTPステータス {}", response.status()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ChatMessage, MessageType};
+
+    #[test]
+    fn test_webhook_payload_for_chat() {
+        let chat = ClientMessage::Chat(ChatMessage {
+            message_type: MessageType::Chat,
+            id: "chat-1".to_string(),
+            display_name: "viewer".to_string(),
+            content: "こんにちは".to_string(),
+            timestamp: None,
+            channel: None,
+            detected_language: None,
+            translated_content: None,
+            badges: Vec::new(),
+        });
+
+        // 通常チャットは設定した場合のみ転送する
+        assert_eq!(WebhookPayload::from_client_message(&chat, false), None);
+        let payload = WebhookPayload::from_client_message(&chat, true).unwrap();
+        assert_eq!(payload.message_type, "chat");
+        assert_eq!(payload.amount, None);
+    }
+
+    /// WebhookのURLのマスクのテスト
+    #[test]
+    fn test_redact_url() {
+        assert_eq!(
+            redact_url("https://discord.com/api/webhooks/123/secret-token"),
+            "https://discord.com/[REDACTED]"
+        );
+        assert_eq!(
+            redact_url("http://localhost:8080/hook?key=secret"),
+            "http://localhost/[REDACTED]"
+        );
+        assert_eq!(redact_url("not a url"), "[REDACTED]");
+    }
+}
@@ -0,0 +1,121 @@
+//! WebSocket接続のアクセストークン認証モジュール
+//!
+//! メンバー限定配信などで接続できる視聴者を限定するため、WebSocketハンドシェイク時に
+//! クエリパラメータ `?token=xxx` を検査します。
+//! トークンが設定されていない場合は従来通り誰でも接続できます。
+//! 検査はハンドシェイク時のみ行うため、トークンを変更しても接続済みのクライアントには影響しません。
+
+use crate::state::AppState;
+
+/// アクセストークンを指定するクエリパラメータ名
+pub const ACCESS_TOKEN_PARAM: &str = "token";
+
+/// アクセストークンの最大文字数
+pub const MAX_ACCESS_TOKEN_LENGTH: usize = 128;
+
+/// ## アクセストークンを検証する
+///
+/// URLにそのまま含められるよう、英数字と `-` `_` `.` `~` のみを許可します。
+///
+/// ### Arguments
+/// - `token`: 検証するトークン
+///
+/// ### Returns
+/// - `Result<(), String>`: 不正なトークンの場合はエラーメッセージ
+pub fn validate_access_token(token: &str) -> Result<(), String> {
+    if token.is_empty() || token.len() > MAX_ACCESS_TOKEN_LENGTH {
+        return Err(format!(
+            "アクセストークンは1〜{}文字で指定してください",
+            MAX_ACCESS_TOKEN_LENGTH
+        ));
+    }
+    if !token
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~'))
+    {
+        return Err("アクセストークンには英数字と - _ . ~ のみ使用できます".to_string());
+    }
+    Ok(())
+}
+
+/// ## クエリ文字列のトークンが設定値と一致するか判定する
+///
+/// ### Arguments
+/// - `expected`: 設定されたアクセストークン（`None` の場合は認証不要）
+/// - `query`: リクエストのクエリ文字列
+///
+/// ### Returns
+/// - `bool`: 接続を許可する場合は `true`
+pub fn is_authorized(expected: Option<&str>, query: &str) -> bool {
+    let Some(expected) = expected else {
+        return true;
+    };
+    url::form_urlencoded::parse(query.as_bytes())
+        .find(|(key, _)| key == ACCESS_TOKEN_PARAM)
+        .is_some_and(|(_, token)| constant_time_eq(token.as_bytes(), expected.as_bytes()))
+}
+
+/// タイミング攻撃でトークンを推測されないよう、内容によらず同じ時間で比較する
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// ## 現在のアクセストークンを取得する
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+///
+/// ### Returns
+/// - `Option<String>`: 設定されたアクセストークン（未設定、またはロックに失敗した場合は `None`）
+pub fn current_access_token(app_state: &AppState) -> Option<String> {
+    app_state
+        .access_token
+        .lock()
+        .ok()
+        .and_then(|token| token.clone())
+}
+
+/// ## URLにアクセストークンのクエリパラメータを付与する
+///
+/// OBS用ページのURLなど、配信者が共有・設定するURLに使用します。
+///
+/// ### Arguments
+/// - `url`: 元のURL
+/// - `token`: アクセストークン（`None` の場合は付与しない）
+///
+/// ### Returns
+/// - `String`: トークンを付与したURL
+pub fn with_access_token(url: String, token: Option<&str>) -> String {
+    match token {
+        Some(token) => {
+            let separator = if url.contains('?') { '&' } else { '?' };
+            format!("{}{}{}={}", url, separator, ACCESS_TOKEN_PARAM, token)
+        }
+        None => url,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_authorized() {
+        assert!(is_authorized(None, ""));
+        assert!(is_authorized(Some("secret"), "token=secret"));
+        assert!(is_authorized(Some("secret"), "ws_port=8082&token=secret"));
+        assert!(!is_authorized(Some("secret"), ""));
+        assert!(!is_authorized(Some("secret"), "token=secret2"));
+        assert!(!is_authorized(Some("secret"), "token="));
+
+        assert_eq!(
+            with_access_token(
+                "http://127.0.0.1:8081/obs/?ws_port=9000".to_string(),
+                Some("abc")
+            ),
+            "http://127.0.0.1:8081/obs/?ws_port=9000&token=abc"
+        );
+        assert!(validate_access_token("member-only_2024").is_ok());
+        assert!(validate_access_token("a b").is_err());
+    }
+}
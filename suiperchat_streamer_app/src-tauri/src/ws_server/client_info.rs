@@ -4,8 +4,18 @@
 
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
+use std::time::Instant;
 use uuid::Uuid;
 
+/// 配信成功率がこの値を下回るクライアントに警告フラグを立てる
+pub const DELIVERY_WARNING_THRESHOLD: f64 = 0.9;
+
+/// 配信成功率を判定するために必要な最小の送信試行回数
+const DELIVERY_WARNING_MIN_ATTEMPTS: u64 = 10;
+
+/// 記録するUser-Agentの最大文字数（これを超える部分は切り捨てる）
+pub const MAX_USER_AGENT_LEN: usize = 256;
+
 /// ## クライアント接続情報
 ///
 /// 各WebSocket接続のクライアント情報を保持します。
@@ -19,8 +29,35 @@ pub struct ClientInfo {
     pub connected_at: String,
     /// 最後にアクティブだった時刻（ISO8601形式）
     pub last_active: String,
+    /// 最後にハートビートに応答した時刻（ISO8601形式）
+    pub last_heartbeat: String,
     /// 送信したメッセージの数
     pub messages_sent: usize,
+    /// 配信に成功したメッセージの数
+    pub messages_delivered: u64,
+    /// 配信に失敗したメッセージの数
+    pub delivery_failures: u64,
+    /// 配信成功率が閾値を下回っているかどうか（ネットワーク不良の疑い）
+    pub delivery_warning: bool,
+    /// 接続元のネットワーク種別の推定 ("mobile" / "fixed" / "unknown")
+    pub network_type: Option<String>,
+    /// 接続元の国名（GeoIPで取得、プライベートIP・ローカルホストは "Local"、取得前はNone）
+    pub country: Option<String>,
+    /// 接続方式 ("local" / "lan" / "tunnel")
+    pub connection_method: Option<String>,
+    /// 人間検証（proof-of-work）を完了したかどうか
+    pub is_verified_human: bool,
+    /// スーパーチャットの送金に使用したウォレットアドレス（最初のスーパーチャット受信時に紐づけ）
+    pub wallet_address: Option<String>,
+    /// 最後に受信したチャットまたはスーパーチャットの表示名（受信前はNone）
+    pub display_name: Option<String>,
+    /// 所属する配信セッションのID（接続時にクエリパラメータ `channel` から決定）
+    pub session_id: Option<String>,
+    /// 接続時のUser-Agentヘッダー（ブラウザ・デバイスの把握用、ヘッダーがない場合はNone）
+    pub user_agent: Option<String>,
+    /// レート制限の期間内に受け付けたチャットの送信時刻（古い順）
+    #[serde(skip)]
+    pub recent_message_times: Vec<Instant>,
 }
 
 impl ClientInfo {
@@ -37,8 +74,21 @@ impl ClientInfo {
             id: Uuid::new_v4().to_string(),
             ip: addr.ip().to_string(),
             connected_at: now.clone(),
-            last_active: now,
+            last_active: now.clone(),
+            last_heartbeat: now,
             messages_sent: 0,
+            messages_delivered: 0,
+            delivery_failures: 0,
+            delivery_warning: false,
+            network_type: None,
+            country: None,
+            connection_method: None,
+            is_verified_human: false,
+            wallet_address: None,
+            display_name: None,
+            session_id: None,
+            user_agent: None,
+            recent_message_times: Vec::new(),
         }
     }
 
@@ -49,10 +99,83 @@ impl ClientInfo {
         self.last_active = chrono::Utc::now().to_rfc3339();
     }
 
+    /// ## ハートビートの応答時刻を更新
+    ///
+    /// クライアントからpongを受信した時に呼び出します。
+    pub fn update_heartbeat(&mut self) {
+        self.last_heartbeat = chrono::Utc::now().to_rfc3339();
+    }
+
     /// ## メッセージカウンターをインクリメント
     ///
     /// クライアントがメッセージを送信した時に呼び出し、カウンターを増加させます。
     pub fn increment_messages(&mut self) {
         self.messages_sent += 1;
     }
+
+    /// ## 表示名を更新
+    ///
+    /// 前後の空白を除いた表示名を設定します。空の表示名は無視します。
+    ///
+    /// ### Arguments
+    /// - `display_name`: 受信したメッセージの表示名
+    ///
+    /// ### Returns
+    /// - `bool`: 表示名が変更された場合はtrue
+    pub fn set_display_name(&mut self, display_name: &str) -> bool {
+        let display_name = display_name.trim();
+        if display_name.is_empty() || self.display_name.as_deref() == Some(display_name) {
+            return false;
+        }
+        self.display_name = Some(display_name.to_string());
+        true
+    }
+
+    /// ## 配信結果を記録
+    ///
+    /// ブロードキャスト時の送信結果をカウントし、警告フラグを更新します。
+    ///
+    /// ### Arguments
+    /// - `delivered`: 配信に成功した場合はtrue
+    pub fn record_delivery(&mut self, delivered: bool) {
+        if delivered {
+            self.messages_delivered = self.messages_delivered.saturating_add(1);
+        } else {
+            self.delivery_failures = self.delivery_failures.saturating_add(1);
+        }
+        self.update_delivery_warning();
+    }
+
+    /// ## 配信成功率を取得
+    ///
+    /// ### Returns
+    /// - `Option<f64>`: 配信成功率（0.0〜1.0）、送信試行がない場合はNone
+    pub fn delivery_rate(&self) -> Option<f64> {
+        let attempts = self
+            .messages_delivered
+            .saturating_add(self.delivery_failures);
+        if attempts == 0 {
+            None
+        } else {
+            Some(self.messages_delivered as f64 / attempts as f64)
+        }
+    }
+
+    /// ## 配信統計をリセット
+    pub fn reset_delivery_stats(&mut self) {
+        self.messages_delivered = 0;
+        self.delivery_failures = 0;
+        self.delivery_warning = false;
+    }
+
+    /// 送信試行回数が十分な場合のみ、成功率に基づいて警告フラグを更新する
+    fn update_delivery_warning(&mut self) {
+        let attempts = self
+            .messages_delivered
+            .saturating_add(self.delivery_failures);
+        self.delivery_warning = attempts >= DELIVERY_WARNING_MIN_ATTEMPTS
+            && self
+                .delivery_rate()
+                .is_some_and(|rate| rate < DELIVERY_WARNING_THRESHOLD);
+    }
 }
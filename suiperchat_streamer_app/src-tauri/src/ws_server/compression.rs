@@ -0,0 +1,396 @@
+//! WebSocketメッセージ圧縮（permessage-deflate, RFC 7692）モジュール
+//!
+//! 低速回線の視聴者向けに、JSONペイロードをDEFLATEで圧縮して送信します。
+//! actix-web-actors は拡張のネゴシエーションに対応していないため、ハンドシェイク時に
+//! `Sec-WebSocket-Extensions` を処理し、アクターとソケットの間でフレームを書き換えます。
+//! - 送信: テキスト/バイナリフレームのペイロードを圧縮し、RSV1ビットを立てて送信
+//! - 受信: RSV1ビットの立ったメッセージを展開し、非圧縮のフレームとしてアクターに渡す
+//!
+//! 圧縮コンテキストはメッセージごとにリセットする（no_context_takeover）ため、
+//! 接続ごとに圧縮辞書を保持する必要はありません。
+//! クライアントが拡張を提示しない場合は従来通り非圧縮で通信します。
+
+use actix_web::error::PayloadError;
+use actix_web::web::{BufMut, Bytes, BytesMut};
+use flate2::read::DeflateDecoder;
+use flate2::{Compress, Compression, FlushCompress};
+use futures_util::{future, Stream, StreamExt};
+use std::io::{self, Read};
+
+/// デフォルトの圧縮レベル
+pub const DEFAULT_COMPRESSION_LEVEL: u32 = 6;
+
+/// 設定可能な圧縮レベルの上限（0は圧縮無効）
+pub const MAX_COMPRESSION_LEVEL: u32 = 9;
+
+/// 拡張の名前
+const PERMESSAGE_DEFLATE: &str = "permessage-deflate";
+
+/// ネゴシエーション成立時に返す `Sec-WebSocket-Extensions` の値
+const PERMESSAGE_DEFLATE_RESPONSE: &str =
+    "permessage-deflate; server_no_context_takeover; client_no_context_takeover";
+
+/// 展開後のメッセージの最大サイズ（actix-web-actors のフレーム上限と同じ）
+const MAX_MESSAGE_SIZE: usize = 65_536;
+
+/// 圧縮データの末尾から除去・補完するブロック（RFC 7692 7.2.1）
+const DEFLATE_TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+
+/// ## ネゴシエーションが成立したpermessage-deflate拡張
+#[derive(Debug, Clone, Copy)]
+pub struct PerMessageDeflate {
+    /// 送信時の圧縮レベル
+    level: Compression,
+}
+
+impl PerMessageDeflate {
+    /// ## クライアントの拡張の提示から圧縮を有効にするか決定する
+    ///
+    /// 提示のうち、サーバーが対応可能な最初の `permessage-deflate` を受け入れます。
+    /// 圧縮ウィンドウの縮小 (`server_max_window_bits` が15未満) や未知のパラメータを含む提示は受け入れません。
+    ///
+    /// ### Arguments
+    /// - `offers`: リクエストの `Sec-WebSocket-Extensions` ヘッダーの値
+    /// - `level`: 圧縮レベル（0の場合は圧縮しない）
+    ///
+    /// ### Returns
+    /// - `Option<Self>`: 圧縮を有効にする場合は拡張、非圧縮でフォールバックする場合はNone
+    pub fn negotiate<'a>(offers: impl IntoIterator<Item = &'a str>, level: u32) -> Option<Self> {
+        if level == 0 {
+            return None;
+        }
+        offers
+            .into_iter()
+            .flat_map(|value| value.split(','))
+            .any(is_acceptable_offer)
+            .then(|| Self {
+                level: Compression::new(level.min(MAX_COMPRESSION_LEVEL)),
+            })
+    }
+
+    /// ## ハンドシェイク応答の `Sec-WebSocket-Extensions` ヘッダーの値
+    pub fn response_header(&self) -> &'static str {
+        PERMESSAGE_DEFLATE_RESPONSE
+    }
+
+    /// ## クライアントからの受信ストリームを展開済みのストリームに変換する
+    ///
+    /// ### Arguments
+    /// - `stream`: リクエストのペイロードストリーム
+    ///
+    /// ### Returns
+    /// - `impl Stream`: 圧縮メッセージを展開したフレームのストリーム
+    pub fn inflate_stream<S>(&self, stream: S) -> impl Stream<Item = Result<Bytes, PayloadError>>
+    where
+        S: Stream<Item = Result<Bytes, PayloadError>>,
+    {
+        let mut inflater = Inflater::default();
+        stream.map(move |chunk| {
+            chunk.and_then(|bytes| inflater.process(&bytes).map_err(PayloadError::Io))
+        })
+    }
+
+    /// ## クライアントへの送信ストリームを圧縮済みのストリームに変換する
+    ///
+    /// ### Arguments
+    /// - `stream`: WebSocketコンテキストが出力するフレームのストリーム
+    ///
+    /// ### Returns
+    /// - `impl Stream`: メッセージを圧縮したフレームのストリーム
+    pub fn deflate_stream<S, E>(&self, stream: S) -> impl Stream<Item = Result<Bytes, E>>
+    where
+        S: Stream<Item = Result<Bytes, E>>,
+    {
+        let mut deflater = Deflater {
+            buffer: BytesMut::new(),
+            level: self.level,
+        };
+        stream
+            .map(move |chunk| chunk.map(|bytes| deflater.process(&bytes)))
+            .filter(|chunk| future::ready(!matches!(chunk, Ok(bytes) if bytes.is_empty())))
+    }
+}
+
+/// 受け入れ可能な `permessage-deflate` の提示か判定する
+fn is_acceptable_offer(offer: &str) -> bool {
+    let mut params = offer.split(';').map(str::trim);
+    if params.next() != Some(PERMESSAGE_DEFLATE) {
+        return false;
+    }
+    params.all(|param| {
+        let (name, value) = match param.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+            None => (param, None),
+        };
+        match name {
+            "server_no_context_takeover" | "client_no_context_takeover" => value.is_none(),
+            // 応答しないため、クライアントは最大のウィンドウ（15）を使用する
+            "client_max_window_bits" => true,
+            // 圧縮側は常に最大のウィンドウを使用するため、縮小の要求には応じられない
+            "server_max_window_bits" => value == Some("15"),
+            _ => false,
+        }
+    })
+}
+
+/// ## フレームのヘッダー
+#[derive(Debug)]
+struct FrameHeader {
+    fin: bool,
+    rsv1: bool,
+    opcode: u8,
+    mask: Option<[u8; 4]>,
+    header_len: usize,
+    payload_len: usize,
+}
+
+impl FrameHeader {
+    /// バッファの先頭からフレームのヘッダーを読み取る（データが不足している場合はNone）
+    fn parse(buf: &[u8]) -> Option<Self> {
+        let (first, second) = (*buf.first()?, *buf.get(1)?);
+        let (payload_len, mut header_len) = match second & 0x7f {
+            126 => (
+                u16::from_be_bytes(buf.get(2..4)?.try_into().ok()?) as u64,
+                4,
+            ),
+            127 => (u64::from_be_bytes(buf.get(2..10)?.try_into().ok()?), 10),
+            len => (len as u64, 2),
+        };
+        let mask = if second & 0x80 != 0 {
+            let mask: [u8; 4] = buf.get(header_len..header_len + 4)?.try_into().ok()?;
+            header_len += 4;
+            Some(mask)
+        } else {
+            None
+        };
+        Some(Self {
+            fin: first & 0x80 != 0,
+            rsv1: first & 0x40 != 0,
+            opcode: first & 0x0f,
+            mask,
+            header_len,
+            payload_len: usize::try_from(payload_len).unwrap_or(usize::MAX),
+        })
+    }
+
+    /// フレーム全体の長さ
+    fn frame_len(&self) -> usize {
+        self.header_len.saturating_add(self.payload_len)
+    }
+
+    /// データフレーム（テキスト/バイナリ/継続）か
+    fn is_data(&self) -> bool {
+        self.opcode & 0x08 == 0
+    }
+}
+
+/// フレームを書き込む
+///
+/// `masked` の場合はクライアントからのフレームとしてマスクキー0で書き込みます（ペイロードは変化しません）。
+fn write_frame(dst: &mut BytesMut, opcode: u8, rsv1: bool, masked: bool, payload: &[u8]) {
+    let first = 0x80 | if rsv1 { 0x40 } else { 0 } | opcode;
+    let mask_bit = if masked { 0x80 } else { 0 };
+    dst.reserve(payload.len() + 14);
+    dst.put_u8(first);
+    if payload.len() < 126 {
+        dst.put_u8(mask_bit | payload.len() as u8);
+    } else if payload.len() <= u16::MAX as usize {
+        dst.put_u8(mask_bit | 126);
+        dst.put_u16(payload.len() as u16);
+    } else {
+        dst.put_u8(mask_bit | 127);
+        dst.put_u64(payload.len() as u64);
+    }
+    if masked {
+        dst.put_slice(&[0; 4]);
+    }
+    dst.put_slice(payload);
+}
+
+/// ## 送信フレームの圧縮処理
+struct Deflater {
+    /// 未処理のフレームのデータ
+    buffer: BytesMut,
+    /// 圧縮レベル
+    level: Compression,
+}
+
+impl Deflater {
+    /// 受け取ったデータのうち完全なフレームを処理し、送信するデータを返す
+    fn process(&mut self, chunk: &[u8]) -> Bytes {
+        self.buffer.extend_from_slice(chunk);
+        let mut output = BytesMut::new();
+        while let Some(header) =
+            FrameHeader::parse(&self.buffer).filter(|h| self.buffer.len() >= h.frame_len())
+        {
+            let frame = self.buffer.split_to(header.frame_len());
+            let payload = &frame[header.header_len..];
+            // 分割されていないテキスト/バイナリメッセージのみ、圧縮して小さくなる場合に圧縮する
+            let compressed = (header.fin && matches!(header.opcode, OPCODE_TEXT | OPCODE_BINARY))
+                .then(|| deflate_message(payload, self.level))
+                .flatten()
+                .filter(|compressed| compressed.len() < payload.len());
+            match compressed {
+                Some(compressed) => {
+                    write_frame(&mut output, header.opcode, true, false, &compressed)
+                }
+                None => output.extend_from_slice(&frame),
+            }
+        }
+        output.freeze()
+    }
+}
+
+/// メッセージのペイロードを圧縮する（末尾の空ブロックは除去する）
+fn deflate_message(payload: &[u8], level: Compression) -> Option<Vec<u8>> {
+    let mut compressor = Compress::new(level, false);
+    let mut compressed = Vec::with_capacity(payload.len() + 64);
+    loop {
+        let consumed = compressor.total_in() as usize;
+        compressor
+            .compress_vec(&payload[consumed..], &mut compressed, FlushCompress::Sync)
+            .ok()?;
+        if compressor.total_in() as usize == payload.len()
+            && compressed.len() < compressed.capacity()
+        {
+            break;
+        }
+        compressed.reserve(compressed.capacity().max(64));
+    }
+    if compressed.ends_with(&DEFLATE_TAIL) {
+        compressed.truncate(compressed.len() - DEFLATE_TAIL.len());
+    }
+    Some(compressed)
+}
+
+/// ## 受信フレームの展開処理
+#[derive(Default)]
+struct Inflater {
+    /// 未処理のフレームのデータ
+    buffer: BytesMut,
+    /// 分割して受信中の圧縮メッセージ（オペコード, 圧縮データ）
+    fragments: Option<(u8, Vec<u8>)>,
+}
+
+impl Inflater {
+    /// 受け取ったデータのうち完全なフレームを処理し、アクターに渡すデータを返す
+    fn process(&mut self, chunk: &[u8]) -> io::Result<Bytes> {
+        self.buffer.extend_from_slice(chunk);
+        let mut output = BytesMut::new();
+        while let Some(header) = FrameHeader::parse(&self.buffer) {
+            if header.payload_len > MAX_MESSAGE_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "WebSocketフレームのサイズが上限を超えています",
+                ));
+            }
+            if self.buffer.len() < header.frame_len() {
+                break;
+            }
+            let frame = self.buffer.split_to(header.frame_len());
+
+            let is_compressed_start =
+                header.rsv1 && matches!(header.opcode, OPCODE_TEXT | OPCODE_BINARY);
+            let is_compressed_continuation =
+                header.opcode == OPCODE_CONTINUATION && self.fragments.is_some();
+            if !header.is_data() || !(is_compressed_start || is_compressed_continuation) {
+                // 制御フレームと非圧縮のメッセージはそのまま渡す
+                output.extend_from_slice(&frame);
+                continue;
+            }
+
+            let mut payload = frame[header.header_len..].to_vec();
+            if let Some(mask) = header.mask {
+                for (i, byte) in payload.iter_mut().enumerate() {
+                    *byte ^= mask[i % 4];
+                }
+            }
+            let (opcode, mut compressed) = match self.fragments.take() {
+                Some((opcode, mut compressed)) => {
+                    compressed.extend_from_slice(&payload);
+                    (opcode, compressed)
+                }
+                None => (header.opcode, payload),
+            };
+            if compressed.len() > MAX_MESSAGE_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "WebSocketメッセージのサイズが上限を超えています",
+                ));
+            }
+            if !header.fin {
+                self.fragments = Some((opcode, compressed));
+                continue;
+            }
+
+            compressed.extend_from_slice(&DEFLATE_TAIL);
+            let message = inflate_message(&compressed)?;
+            write_frame(&mut output, opcode, false, true, &message);
+        }
+        Ok(output.freeze())
+    }
+}
+
+/// 圧縮されたメッセージを展開する（展開後のサイズが上限を超える場合はエラー）
+fn inflate_message(compressed: &[u8]) -> io::Result<Vec<u8>> {
+    let mut message = Vec::new();
+    DeflateDecoder::new(compressed)
+        .take(MAX_MESSAGE_SIZE as u64 + 1)
+        .read_to_end(&mut message)?;
+    if message.len() > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "展開後のWebSocketメッセージのサイズが上限を超えています",
+        ));
+    }
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permessage_deflate_round_trip() {
+        assert!(
+            PerMessageDeflate::negotiate(["permessage-deflate; client_max_window_bits"], 6)
+                .is_some()
+        );
+        assert!(
+            PerMessageDeflate::negotiate(["permessage-deflate; server_max_window_bits=10"], 6)
+                .is_none()
+        );
+        assert!(PerMessageDeflate::negotiate(["permessage-deflate"], 0).is_none());
+        assert!(PerMessageDeflate::negotiate(["x-webkit-deflate-frame"], 6).is_none());
+
+        // サーバーが圧縮したメッセージを、クライアントが送信したものとして展開できる
+        let message = r#"{"type":"chat","content":"こんにちは"}"#.repeat(20);
+        let mut frame = BytesMut::new();
+        write_frame(&mut frame, OPCODE_TEXT, false, false, message.as_bytes());
+        let mut deflater = Deflater {
+            buffer: BytesMut::new(),
+            level: Compression::new(DEFAULT_COMPRESSION_LEVEL),
+        };
+        let compressed = deflater.process(&frame);
+        let header = FrameHeader::parse(&compressed).unwrap();
+        assert!(header.rsv1);
+        assert!(header.payload_len < message.len());
+
+        let mut client_frame = BytesMut::new();
+        write_frame(
+            &mut client_frame,
+            OPCODE_TEXT,
+            true,
+            true,
+            &compressed[header.header_len..],
+        );
+        let inflated = Inflater::default().process(&client_frame).unwrap();
+        let header = FrameHeader::parse(&inflated).unwrap();
+        assert!(!header.rsv1);
+        assert_eq!(&inflated[header.header_len..], message.as_bytes());
+    }
+}
@@ -3,22 +3,100 @@
 //! WebSocket接続の追加・削除・管理を行います。
 
 use super::client_info::ClientInfo;
+use super::connection_urls::ConnectionMethod;
+use super::flow_control::{BroadcastPriority, FlowControlConfig};
+use super::message_dedup::RecentMessageIds;
+use super::network_type::{self, NetworkType};
+use super::protobuf;
+use super::rate_limit::MessageRateLimit;
+use super::replay_cache::{self, Replay, ReplayCache};
+use super::viewer_count_history;
+use crate::database;
+use crate::signing::{MessageSigner, SigningMode};
+use crate::state::AppState;
+use crate::stream_sessions;
 use crate::types::{
-    decrement_connections, get_connections_count, increment_connections, ConnectionsInfo,
+    decrement_connections, get_connections_count, increment_connections, reset_connections,
+    CapacityInfo, ConnectionMethodBreakdown, ConnectionStats, ConnectionsInfo, MessageType,
+    PaginatedConnectionsInfo, ServerResponse, DEFAULT_CHANNEL, DEFAULT_GROUP,
 };
-use crate::ws_server::session::Broadcast;
+use crate::ws_server::idle_monitor::IdleDisconnectConfig;
+use crate::ws_server::server_manager;
+use crate::ws_server::session::{Broadcast, Disconnect, IdleDisconnect};
+use actix::dev::SendError;
 use actix::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::{Arc, Mutex};
-use tauri::Emitter; // for Addr
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager}; // for Addr
+
+/// 直近アクティブとみなす期間
+const ACTIVE_CLIENT_WINDOW: Duration = Duration::from_secs(60);
 
 /// ## セッションエントリ
 ///
-/// ClientInfo と対応する WebSocket セッションのアドレスを保持する構造体
+/// ClientInfo と対応する WebSocket セッションのアドレス、購読中のチャンネルを保持する構造体
 #[derive(Debug)]
 pub struct SessionEntry {
     pub client_info: ClientInfo,
     pub addr: Addr<crate::ws_server::session::WsSession>,
+    /// 購読中のチャンネル（接続直後は "general" のみ）
+    pub channels: HashSet<String>,
+    /// 所属するグループ
+    pub groups: ClientGroups,
+}
+
+/// ## クライアントの所属グループ
+///
+/// グループ未割り当て（空）のクライアントはデフォルトグループに所属しているものとして扱います。
+/// デフォルトグループは未割り当てとして保持します。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientGroups(HashSet<String>);
+
+impl ClientGroups {
+    /// ## 指定されたグループに所属しているか判定する
+    ///
+    /// ### Arguments
+    /// - `group`: グループ名
+    pub fn contains(&self, group: &str) -> bool {
+        if self.0.is_empty() {
+            group == DEFAULT_GROUP
+        } else {
+            self.0.contains(group)
+        }
+    }
+
+    /// ## 所属するグループの一覧を名前順で取得する
+    ///
+    /// グループ未割り当ての場合はデフォルトグループのみを返します。
+    pub fn names(&self) -> Vec<String> {
+        if self.0.is_empty() {
+            return vec![DEFAULT_GROUP.to_string()];
+        }
+        let mut groups: Vec<String> = self.0.iter().cloned().collect();
+        groups.sort();
+        groups
+    }
+
+    /// ## グループに割り当てる
+    ///
+    /// ### Arguments
+    /// - `group`: グループ名
+    pub fn assign(&mut self, group: &str) {
+        if group != DEFAULT_GROUP {
+            self.0.insert(group.to_string());
+        }
+    }
+
+    /// ## グループの割り当てを解除する
+    ///
+    /// 全てのグループから外れた場合はデフォルトグループに戻ります。
+    ///
+    /// ### Arguments
+    /// - `group`: グループ名
+    pub fn unassign(&mut self, group: &str) {
+        self.0.remove(group);
+    }
 }
 
 /// ## 接続管理
@@ -30,8 +108,35 @@ pub struct ConnectionManager {
     /// 接続中のセッション情報
     /// キーはクライアントID、値はSessionEntry
     connections: Arc<Mutex<HashMap<String, SessionEntry>>>,
+    /// OBSオーバーレイの受信専用セッション（接続数・最大接続数には含めない）
+    /// キーは接続ID、値はWebSocketセッションのアドレス
+    obs_connections: Arc<Mutex<HashMap<String, Addr<crate::ws_server::session::WsSession>>>>,
     /// 最大接続数
     max_connections: Arc<Mutex<usize>>,
+    /// 同一ウォレットに紐づく接続数の上限（0の場合は無制限）
+    per_wallet_limit: Arc<Mutex<usize>>,
+    /// クライアントごとの送信フロー制御の設定
+    flow_control: Arc<Mutex<FlowControlConfig>>,
+    /// クライアントごとのメッセージ受信レート制限の設定
+    message_rate_limit: Arc<Mutex<MessageRateLimit>>,
+    /// 署名するブロードキャストメッセージの範囲
+    signing_mode: Arc<Mutex<SigningMode>>,
+    /// ブロードキャストメッセージの署名鍵（未読み込みの場合はNone）
+    signer: Arc<Mutex<Option<Arc<MessageSigner>>>>,
+    /// 接続を拒否するIPアドレス
+    blocked_ips: Arc<Mutex<HashSet<String>>>,
+    /// ブロック時にそのIPアドレスの接続に紐づいていたウォレットアドレス
+    /// キーはウォレットアドレス、値はブロックしたIPアドレス
+    blocked_wallets: Arc<Mutex<HashMap<String, String>>>,
+    /// アイドル接続の自動切断の設定
+    idle_disconnect: Arc<Mutex<IdleDisconnectConfig>>,
+    /// 再接続時に欠損分を再送するための直近のブロードキャスト
+    replay_cache: Arc<Mutex<ReplayCache>>,
+    /// 重複排除のための直近に処理したメッセージID
+    recent_message_ids: Arc<Mutex<RecentMessageIds>>,
+    /// 検証中・使用済みのスーパーチャットのトランザクションハッシュ
+    /// キーはトランザクションハッシュ、値は予約したメッセージID
+    reserved_tx_hashes: Arc<Mutex<HashMap<String, String>>>,
     /// Tauriアプリケーションハンドル（イベント発行用）
     app_handle: Option<tauri::AppHandle>,
 }
@@ -47,7 +152,19 @@ impl ConnectionManager {
     pub fn new(max_connections: usize) -> Self {
         Self {
             connections: Arc::new(Mutex::new(HashMap::new())),
+            obs_connections: Arc::new(Mutex::new(HashMap::new())),
             max_connections: Arc::new(Mutex::new(max_connections)),
+            per_wallet_limit: Arc::new(Mutex::new(0)),
+            flow_control: Arc::new(Mutex::new(FlowControlConfig::default())),
+            message_rate_limit: Arc::new(Mutex::new(MessageRateLimit::default())),
+            signing_mode: Arc::new(Mutex::new(SigningMode::default())),
+            signer: Arc::new(Mutex::new(None)),
+            blocked_ips: Arc::new(Mutex::new(HashSet::new())),
+            blocked_wallets: Arc::new(Mutex::new(HashMap::new())),
+            idle_disconnect: Arc::new(Mutex::new(IdleDisconnectConfig::default())),
+            replay_cache: Arc::new(Mutex::new(ReplayCache::default())),
+            recent_message_ids: Arc::new(Mutex::new(RecentMessageIds::default())),
+            reserved_tx_hashes: Arc::new(Mutex::new(HashMap::new())),
             app_handle: None,
         }
     }
@@ -80,6 +197,273 @@ impl ConnectionManager {
         *self.max_connections.lock().unwrap()
     }
 
+    /// ## 同一ウォレットの接続数の上限を設定
+    ///
+    /// ### Arguments
+    /// - `limit`: 同一ウォレットに紐づく接続数の上限（0の場合は無制限）
+    pub fn set_per_wallet_limit(&self, limit: usize) {
+        *self.per_wallet_limit.lock().unwrap() = limit;
+    }
+
+    /// ## 同一ウォレットの接続数の上限を取得
+    ///
+    /// ### Returns
+    /// - `usize`: 同一ウォレットに紐づく接続数の上限（0の場合は無制限）
+    pub fn per_wallet_limit(&self) -> usize {
+        *self.per_wallet_limit.lock().unwrap()
+    }
+
+    /// ## サーバー容量の情報を取得
+    ///
+    /// ### Returns
+    /// - `CapacityInfo`: 新規接続を受け入れられるかどうかと、現在・最大の接続数
+    pub fn capacity_info(&self) -> CapacityInfo {
+        let current = get_connections_count();
+        let max = self.get_max_connections();
+        CapacityInfo {
+            accepting: current < max,
+            current,
+            max,
+        }
+    }
+
+    /// ## フロー制御の設定を変更
+    ///
+    /// 各セッションは次の送信時から新しい設定を使用します。
+    ///
+    /// ### Arguments
+    /// - `config`: 新しいフロー制御の設定
+    pub fn set_flow_control_config(&self, config: FlowControlConfig) {
+        *self.flow_control.lock().unwrap() = config;
+    }
+
+    /// ## フロー制御の設定を取得
+    ///
+    /// ### Returns
+    /// - `FlowControlConfig`: 現在のフロー制御の設定
+    pub fn flow_control_config(&self) -> FlowControlConfig {
+        *self.flow_control.lock().unwrap()
+    }
+
+    /// ## メッセージ受信レート制限の設定を変更
+    ///
+    /// ### Arguments
+    /// - `limit`: 新しいレート制限の設定
+    pub fn set_message_rate_limit(&self, limit: MessageRateLimit) {
+        *self.message_rate_limit.lock().unwrap() = limit;
+    }
+
+    /// ## メッセージ受信レート制限の設定を取得
+    ///
+    /// ### Returns
+    /// - `MessageRateLimit`: 現在のレート制限の設定
+    pub fn message_rate_limit(&self) -> MessageRateLimit {
+        *self.message_rate_limit.lock().unwrap()
+    }
+
+    /// ## アイドル接続の自動切断の設定を変更
+    ///
+    /// ### Arguments
+    /// - `config`: 新しいアイドル切断の設定
+    pub fn set_idle_disconnect_config(&self, config: IdleDisconnectConfig) {
+        *self.idle_disconnect.lock().unwrap() = config;
+    }
+
+    /// ## アイドル接続の自動切断の設定を取得
+    ///
+    /// ### Returns
+    /// - `IdleDisconnectConfig`: 現在のアイドル切断の設定
+    pub fn idle_disconnect_config(&self) -> IdleDisconnectConfig {
+        *self.idle_disconnect.lock().unwrap()
+    }
+
+    /// ## アイドル状態のクライアントを切断
+    ///
+    /// アイドル切断の設定に従ってアイドル状態のクライアントに理由を通知して切断し、
+    /// 接続一覧から削除します。IPアドレスはブロックしないため、再接続は可能です。
+    ///
+    /// ### Arguments
+    /// - `now`: 現在時刻
+    ///
+    /// ### Returns
+    /// - `Vec<(ClientInfo, u64)>`: 切断したクライアントと最後の活動からの経過秒数
+    pub fn disconnect_idle_clients(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Vec<(ClientInfo, u64)> {
+        let config = self.idle_disconnect_config();
+        if !config.is_enabled() {
+            return Vec::new();
+        }
+
+        let idle_clients: Vec<(ClientInfo, u64)> = {
+            let connections = self.connections.lock().unwrap();
+            connections
+                .values()
+                .filter_map(|entry| {
+                    let idle_secs = config.idle_secs(&entry.client_info, now)?;
+                    entry.addr.do_send(IdleDisconnect { idle_secs });
+                    Some((entry.client_info.clone(), idle_secs))
+                })
+                .collect()
+        };
+
+        idle_clients
+            .into_iter()
+            .filter(|(client, _)| self.remove_client(&client.id))
+            .collect()
+    }
+
+    /// ## 署名するメッセージの範囲を変更
+    ///
+    /// ### Arguments
+    /// - `mode`: 新しい署名の範囲
+    pub fn set_signing_mode(&self, mode: SigningMode) {
+        *self.signing_mode.lock().unwrap() = mode;
+    }
+
+    /// ## 署名するメッセージの範囲を取得
+    ///
+    /// ### Returns
+    /// - `SigningMode`: 現在の署名の範囲
+    pub fn signing_mode(&self) -> SigningMode {
+        *self.signing_mode.lock().unwrap()
+    }
+
+    /// ## 署名鍵を設定
+    ///
+    /// ### Arguments
+    /// - `signer`: 新しい署名鍵
+    pub fn set_signer(&self, signer: MessageSigner) {
+        *self.signer.lock().unwrap() = Some(Arc::new(signer));
+    }
+
+    /// ## 署名鍵を取得
+    ///
+    /// ### Returns
+    /// - `Option<Arc<MessageSigner>>`: 現在の署名鍵（未読み込みの場合はNone）
+    pub fn signer(&self) -> Option<Arc<MessageSigner>> {
+        self.signer.lock().unwrap().clone()
+    }
+
+    /// 署名の範囲に含まれるメッセージのJSONに署名を付与する
+    fn sign_broadcast(&self, mut message: Broadcast) -> Broadcast {
+        let should_sign = match self.signing_mode() {
+            SigningMode::Off => false,
+            SigningMode::Important => message.priority == BroadcastPriority::High,
+            SigningMode::All => true,
+        };
+        if should_sign {
+            if let Some(signer) = self.signer() {
+                message.json = signer.sign_json(&message.json);
+            }
+        }
+        message
+    }
+
+    /// ## IPアドレスをブロック
+    ///
+    /// 以降の接続を拒否し、既に接続中の同一IPのセッションを全て切断します。
+    /// 切断した接続に紐づいていたウォレットアドレスからのオンチェーンの着金も配信しないよう記録します。
+    /// 転送元IPを取得できないトンネル経由の接続は全てループバックアドレスになるため、
+    /// ループバックアドレスはブロックできません。
+    ///
+    /// ### Arguments
+    /// - `ip`: ブロックするIPアドレス
+    ///
+    /// ### Returns
+    /// - `Result<usize, String>`: 切断したセッション数、ループバックアドレスの場合はエラーメッセージ
+    pub fn block_ip(&self, ip: &str) -> Result<usize, String> {
+        if ip
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|addr| addr.is_loopback())
+        {
+            return Err(format!("ループバックアドレスはブロックできません: {}", ip));
+        }
+        self.blocked_ips.lock().unwrap().insert(ip.to_string());
+
+        let client_ids: Vec<String> = {
+            let connections = self.connections.lock().unwrap();
+            let mut blocked_wallets = self.blocked_wallets.lock().unwrap();
+            connections
+                .iter()
+                .filter(|(_, entry)| entry.client_info.ip == ip)
+                .map(|(client_id, entry)| {
+                    if let Some(wallet_address) = &entry.client_info.wallet_address {
+                        blocked_wallets.insert(wallet_address.clone(), ip.to_string());
+                    }
+                    entry.addr.do_send(Disconnect);
+                    client_id.clone()
+                })
+                .collect()
+        };
+
+        let disconnected = client_ids
+            .iter()
+            .filter(|client_id| self.remove_client(client_id))
+            .count();
+        println!(
+            "IPアドレスをブロックしました: {} (切断: {}件)",
+            ip, disconnected
+        );
+        Ok(disconnected)
+    }
+
+    /// ## IPアドレスのブロックを解除
+    ///
+    /// ### Arguments
+    /// - `ip`: ブロックを解除するIPアドレス
+    ///
+    /// ### Returns
+    /// - `bool`: ブロックされていた場合はtrue
+    pub fn unblock_ip(&self, ip: &str) -> bool {
+        let removed = self.blocked_ips.lock().unwrap().remove(ip);
+        self.blocked_wallets
+            .lock()
+            .unwrap()
+            .retain(|_, blocked_ip| blocked_ip != ip);
+        if removed {
+            println!("IPアドレスのブロックを解除しました: {}", ip);
+        }
+        removed
+    }
+
+    /// ## IPアドレスがブロックされているか確認
+    ///
+    /// ### Arguments
+    /// - `ip`: 確認するIPアドレス
+    ///
+    /// ### Returns
+    /// - `bool`: ブロックされている場合はtrue
+    pub fn is_ip_blocked(&self, ip: &str) -> bool {
+        self.blocked_ips.lock().unwrap().contains(ip)
+    }
+
+    /// ## ウォレットアドレスがIPアドレスのブロックの対象か確認
+    ///
+    /// ### Arguments
+    /// - `wallet_address`: 確認するウォレットアドレス
+    ///
+    /// ### Returns
+    /// - `bool`: ブロックしたIPアドレスの接続に紐づいていた場合はtrue
+    pub fn is_wallet_blocked(&self, wallet_address: &str) -> bool {
+        self.blocked_wallets
+            .lock()
+            .unwrap()
+            .keys()
+            .any(|blocked| blocked.eq_ignore_ascii_case(wallet_address))
+    }
+
+    /// ## ブロック中のIPアドレス一覧を取得
+    ///
+    /// ### Returns
+    /// - `Vec<String>`: ブロック中のIPアドレス（昇順）
+    pub fn blocked_ips(&self) -> Vec<String> {
+        let mut ips: Vec<String> = self.blocked_ips.lock().unwrap().iter().cloned().collect();
+        ips.sort();
+        ips
+    }
+
     /// ## クライアントを追加
     ///
     /// 新しい接続を接続リストに追加します。
@@ -89,12 +473,21 @@ impl ConnectionManager {
     /// - `addr`: WebSocketセッションのアドレス
     ///
     /// ### Returns
-    /// - `bool`: 追加に成功した場合はtrue、最大接続数に達していて追加できなかった場合はfalse
+    /// - `bool`: 追加に成功した場合はtrue、IPアドレスがブロックされているか最大接続数に達していて追加できなかった場合はfalse
     pub fn add_client(
         &self,
         client_info: ClientInfo,
         addr: Addr<crate::ws_server::session::WsSession>,
     ) -> bool {
+        // ブロックリストのチェック
+        if self.is_ip_blocked(&client_info.ip) {
+            println!(
+                "ブロック中のIPアドレスからの接続を拒否します: {}",
+                client_info.ip
+            );
+            return false;
+        }
+
         let max_conn = self.get_max_connections();
         let current_count = get_connections_count();
 
@@ -110,17 +503,30 @@ impl ConnectionManager {
         // 接続カウンターをインクリメント
         increment_connections();
 
+        // 省電力モードのトンネル停止を取り消す（停止中の場合は再開）
+        if let Some(app_handle) = global::get_app_handle() {
+            server_manager::cancel_idle_tunnel_shutdown(&app_handle);
+        }
+
+        // 接続ログを記録
+        Self::log_connection_event(client_info.clone(), None);
+
         // セッションエントリをマップに追加
         let client_id = client_info.id.clone();
         let entry = SessionEntry {
             client_info: client_info.clone(),
             addr,
+            channels: HashSet::from([DEFAULT_CHANNEL.to_string()]),
+            groups: ClientGroups::default(),
         };
         {
             let mut connections = self.connections.lock().unwrap();
             connections.insert(client_id, entry);
         }
 
+        // 視聴者数の推移を記録
+        Self::record_viewer_count();
+
         // イベント発行
         self.emit_connections_updated();
         true // 追加成功
@@ -140,20 +546,95 @@ impl ConnectionManager {
         // --- Lock scope starts ---
         {
             let mut connections = self.connections.lock().unwrap();
-            removed = connections.remove(client_id).is_some();
+            removed = connections.remove(client_id);
         } // --- Lock scope ends ---
 
-        if removed {
+        if let Some(entry) = removed {
+            // 切断を接続ログに記録
+            Self::log_connection_event(entry.client_info, Some(chrono::Utc::now()));
             // 接続カウンターをデクリメント (ロック解放後)
             decrement_connections();
+            // 視聴者数の推移を記録
+            Self::record_viewer_count();
             // イベント発行 (ロック解放後)
             self.emit_connections_updated();
+            // 視聴者がいなくなった場合は省電力モードのトンネル停止を予約
+            if get_connections_count() == 0 {
+                if let Some(app_handle) = global::get_app_handle() {
+                    server_manager::schedule_idle_tunnel_shutdown(&app_handle);
+                }
+            }
             true
         } else {
             false
         }
     }
 
+    /// ## 全ての接続情報をリセット
+    ///
+    /// サーバー停止時に呼び出し、残存しているセッションエントリを全て削除して接続カウンターを0に戻します。
+    /// 停止後に切断処理が行われなかった接続が「幽霊接続」として残り、
+    /// 再起動後の最大接続数の判定を誤らせることを防ぎます。
+    /// 削除したセッションの切断処理が後から実行されても、エントリが無いためカウンターは減少しません。
+    pub fn reset(&self) {
+        let removed = std::mem::take(&mut *self.connections.lock().unwrap());
+        for entry in removed.into_values() {
+            // 切断を接続ログに記録
+            Self::log_connection_event(entry.client_info, Some(chrono::Utc::now()));
+        }
+        self.obs_connections.lock().unwrap().clear();
+        reset_connections();
+        println!("接続マネージャーの接続情報をリセットしました");
+        self.emit_connections_updated();
+    }
+
+    /// ## OBSオーバーレイの接続を追加
+    ///
+    /// OBSオーバーレイはブロードキャストを受信するだけのため、視聴者の接続とは別枠で管理し、
+    /// 接続カウンターや最大接続数の判定には含めません。
+    ///
+    /// ### Arguments
+    /// - `connection_id`: 接続ID
+    /// - `addr`: WebSocketセッションのアドレス
+    pub fn add_obs_client(
+        &self,
+        connection_id: &str,
+        addr: Addr<crate::ws_server::session::WsSession>,
+    ) {
+        let count = {
+            let mut obs_connections = self.obs_connections.lock().unwrap();
+            obs_connections.insert(connection_id.to_string(), addr);
+            obs_connections.len()
+        };
+        println!(
+            "OBSオーバーレイが接続しました: {} (OBS接続数: {})",
+            connection_id, count
+        );
+    }
+
+    /// ## OBSオーバーレイの接続を削除
+    ///
+    /// ### Arguments
+    /// - `connection_id`: 接続ID
+    ///
+    /// ### Returns
+    /// - `bool`: 削除に成功した場合はtrue、指定されたIDの接続が見つからない場合はfalse
+    pub fn remove_obs_client(&self, connection_id: &str) -> bool {
+        self.obs_connections
+            .lock()
+            .unwrap()
+            .remove(connection_id)
+            .is_some()
+    }
+
+    /// ## OBSオーバーレイの接続数を取得
+    ///
+    /// ### Returns
+    /// - `usize`: OBSオーバーレイの接続数
+    pub fn obs_connection_count(&self) -> usize {
+        self.obs_connections.lock().unwrap().len()
+    }
+
     /// ## クライアント情報を取得
     ///
     /// 指定されたIDのクライアント情報を取得します。
@@ -194,6 +675,80 @@ impl ConnectionManager {
         }
     }
 
+    /// ## クライアントにウォレットを紐づけ、同一ウォレットの接続数を制限する
+    ///
+    /// 既にウォレットが紐づいているクライアントは変更しません。
+    /// 同一ウォレットに紐づく接続が上限を超えた場合は、接続時刻の古いものから切断します。
+    ///
+    /// ### Arguments
+    /// - `client_id`: 紐づけるクライアントのID
+    /// - `wallet_address`: スーパーチャットの送金に使用したウォレットアドレス
+    ///
+    /// ### Returns
+    /// - `usize`: 上限超過により切断したセッション数
+    pub fn bind_wallet(&self, client_id: &str, wallet_address: &str) -> usize {
+        let mut newly_bound = false;
+        self.update_client(client_id, |info| {
+            if info.wallet_address.is_none() {
+                info.wallet_address = Some(wallet_address.to_string());
+                newly_bound = true;
+            }
+        });
+        let limit = self.per_wallet_limit();
+        if !newly_bound || limit == 0 {
+            return 0;
+        }
+
+        let client_ids: Vec<String> = {
+            let connections = self.connections.lock().unwrap();
+            let client_ids = Self::excess_wallet_clients(
+                connections.values().map(|entry| &entry.client_info),
+                wallet_address,
+                limit,
+            );
+            for client_id in &client_ids {
+                if let Some(entry) = connections.get(client_id) {
+                    entry.addr.do_send(Disconnect);
+                }
+            }
+            client_ids
+        };
+
+        let disconnected = client_ids
+            .iter()
+            .filter(|client_id| self.remove_client(client_id))
+            .count();
+        if disconnected > 0 {
+            println!(
+                "同一ウォレットの接続数が上限を超えたため、古い接続を切断しました: {} (切断: {}件)",
+                wallet_address, disconnected
+            );
+        }
+        disconnected
+    }
+
+    /// ## 配信セッションに接続中のクライアントを別のセッションに移す
+    ///
+    /// ### Arguments
+    /// - `from`: 終了した配信セッションID
+    /// - `to`: 移動先の配信セッションID（配信中のセッションがない場合はNone）
+    ///
+    /// ### Returns
+    /// - `usize`: 移したクライアント数
+    pub fn reassign_session(&self, from: &str, to: Option<&str>) -> usize {
+        let mut moved = 0;
+        for entry in self.connections.lock().unwrap().values_mut() {
+            if entry.client_info.session_id.as_deref() == Some(from) {
+                entry.client_info.session_id = to.map(str::to_string);
+                moved += 1;
+            }
+        }
+        if moved > 0 {
+            self.emit_connections_updated();
+        }
+        moved
+    }
+
     /// ## 全クライアント情報を取得
     ///
     /// ### Returns
@@ -206,6 +761,73 @@ impl ConnectionManager {
             .collect()
     }
 
+    /// ## クライアント情報をページ単位で取得
+    ///
+    /// 接続時刻順（古い順）に並べたクライアント情報のうち、指定範囲のみを返します。
+    /// 総数は `CONNECTIONS_COUNT` から取得します。
+    ///
+    /// ### Arguments
+    /// - `offset`: 取得開始位置
+    /// - `limit`: 取得する最大件数
+    ///
+    /// ### Returns
+    /// - `(Vec<ClientInfo>, usize)`: 指定範囲のクライアント情報と総接続数
+    pub fn get_connections_paginated(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> (Vec<ClientInfo>, usize) {
+        let mut clients = self.get_all_clients();
+        clients.sort_by(|a, b| {
+            a.connected_at
+                .cmp(&b.connected_at)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+
+        let page = clients.into_iter().skip(offset).take(limit).collect();
+        (page, get_connections_count())
+    }
+
+    /// ## 接続中クライアントの統計を取得
+    ///
+    /// クライアント情報を複製せずに集計するため、`get_connections_info` より軽量です。
+    ///
+    /// ### Returns
+    /// - `ConnectionStats`: 総接続数・総送信メッセージ数・最古の接続時刻・直近アクティブ数
+    pub fn get_connection_stats(&self) -> ConnectionStats {
+        let parse_time = |value: &str| chrono::DateTime::parse_from_rfc3339(value).ok();
+        let active_since = chrono::Utc::now()
+            - chrono::Duration::from_std(ACTIVE_CLIENT_WINDOW).unwrap_or_default();
+
+        let connections = self.connections.lock().unwrap();
+        let clients = connections.values().map(|entry| &entry.client_info);
+
+        let mut total_messages_sent = 0;
+        let mut oldest = None;
+        let mut active_last_minute = 0;
+        for client in clients {
+            total_messages_sent += client.messages_sent;
+            if let Some(connected_at) = parse_time(&client.connected_at) {
+                if oldest
+                    .as_ref()
+                    .map_or(true, |(time, _)| connected_at < *time)
+                {
+                    oldest = Some((connected_at, client.connected_at.clone()));
+                }
+            }
+            if parse_time(&client.last_active).is_some_and(|time| time >= active_since) {
+                active_last_minute += 1;
+            }
+        }
+
+        ConnectionStats {
+            total_connections: connections.len(),
+            total_messages_sent,
+            oldest_connected_at: oldest.map(|(_, connected_at)| connected_at),
+            active_last_minute,
+        }
+    }
+
     /// ## 接続情報を取得
     ///
     /// 現在の接続状況に関する情報を取得します。
@@ -217,17 +839,44 @@ impl ConnectionManager {
         let max_connections = self.get_max_connections();
         let clients = self.get_all_clients();
 
+        let count_network_type = |network_type: NetworkType| {
+            clients
+                .iter()
+                .filter(|client| client.network_type.as_deref() == Some(network_type.as_str()))
+                .count()
+        };
+        let mobile_clients = count_network_type(NetworkType::Mobile);
+        let fixed_clients = count_network_type(NetworkType::Fixed);
+
+        let count_connection_method = |method: ConnectionMethod| {
+            clients
+                .iter()
+                .filter(|client| client.connection_method.as_deref() == Some(method.as_str()))
+                .count()
+        };
+        let connection_methods = ConnectionMethodBreakdown {
+            local: count_connection_method(ConnectionMethod::Local),
+            lan: count_connection_method(ConnectionMethod::Lan),
+            tunnel: count_connection_method(ConnectionMethod::Tunnel),
+        };
+
         ConnectionsInfo {
             active_connections,
             max_connections,
             clients,
+            mobile_clients,
+            lightweight_mode_recommended: network_type::recommend_lightweight_mode(
+                mobile_clients,
+                fixed_clients,
+            ),
+            connection_methods,
         }
     }
 
     /// ## 接続更新イベントを発行
     ///
-    /// 接続状態が変更された際にイベントを発行します。
-    fn emit_connections_updated(&self) {
+    /// 接続状態やクライアント情報が変更された際にイベントを発行します。
+    pub fn emit_connections_updated(&self) {
         if let Some(app_handle) = &self.app_handle {
             // 接続情報を取得
             let info = self.get_connections_info();
@@ -239,37 +888,578 @@ impl ConnectionManager {
         }
     }
 
-    /// ## 全クライアントにメッセージをブロードキャスト
+    /// ## 指定されたクライアントにのみメッセージを送信
     ///
-    /// 受信したメッセージをすべての接続中セッションに送信します。
-    pub fn broadcast(&self, message: &str) {
+    /// ### Arguments
+    /// - `client_id`: 送信先のクライアントのID
+    /// - `message`: 送信するJSONテキスト
+    ///
+    /// ### Returns
+    /// - `bool`: 送信した場合はtrue、指定されたIDのクライアントが見つからない場合はfalse
+    pub fn send_to_client(&self, client_id: &str, message: &str) -> bool {
         let connections = self.connections.lock().unwrap();
-        for entry in connections.values() {
-            // Broadcastメッセージを送信
-            entry.addr.do_send(Broadcast(message.to_string()));
+        match connections.get(client_id) {
+            Some(entry) => {
+                entry.addr.do_send(Broadcast::text(message.to_string()));
+                true
+            }
+            None => false,
         }
     }
-}
-
-/// ## グローバルモジュール
-///
-/// グローバルに共有されるコネクションマネージャーへのアクセスを提供する
-pub mod global {
-    use super::*;
-    use once_cell::sync::OnceCell;
 
-    // デフォルトの最大接続数
-    const DEFAULT_MAX_CONNECTIONS: usize = 100;
+    /// ## 全クライアントにメッセージをブロードキャスト
+    ///
+    /// 受信したメッセージをすべての接続中セッションに送信し、配信結果を記録します。
+    pub fn broadcast(&self, message: &str) {
+        self.broadcast_frame(Broadcast::text(message.to_string()));
+    }
 
-    /// グローバル接続マネージャーのインスタンス
-    static MANAGER: OnceCell<ConnectionManager> = OnceCell::new();
+    /// ## 全クライアントにブロードキャストメッセージを送信
+    ///
+    /// 各セッションはクライアントのエンコーディングに応じてJSONまたはprotobufで送信します。
+    ///
+    /// ### Arguments
+    /// - `message`: 送信するブロードキャストメッセージ
+    pub fn broadcast_frame(&self, message: Broadcast) {
+        let prioritize = self.flow_control_config().drops_low_priority();
+        self.send_sequenced(message, None, |message| {
+            {
+                let mut connections = self.connections.lock().unwrap();
+                for entry in connections.values_mut() {
+                    Self::deliver(entry, message, prioritize);
+                }
+            }
+            self.deliver_to_obs(message);
+        });
+    }
 
-    /// グローバルなTauriアプリケーションハンドル
-    static APP_HANDLE: OnceCell<tauri::AppHandle> = OnceCell::new();
+    /// ## OBSオーバーレイのみにメッセージを送信
+    ///
+    /// レイアウトやテーマの変更通知など、OBS表示だけが使用するメッセージに使用します。
+    /// 視聴者には送信しないため、シーケンス番号の採番や再送キャッシュへの追加は行いません。
+    ///
+    /// ### Arguments
+    /// - `message`: 送信するJSONテキスト
+    pub fn broadcast_to_obs(&self, message: &str) {
+        self.deliver_to_obs(&Broadcast::text(message.to_string()));
+    }
 
-    /// ## 接続マネージャーのインスタンスを取得する
+    /// ## OBSオーバーレイにブロードキャストメッセージを送信
     ///
-    /// まだ初期化されていない場合は新しいインスタンスを作成します。
+    /// 配信画面からメッセージが欠けないよう、メールボックスが満杯の場合も間引かずにキューへ追加します。
+    ///
+    /// ### Arguments
+    /// - `message`: 送信するブロードキャストメッセージ
+    fn deliver_to_obs(&self, message: &Broadcast) {
+        let obs_connections = self.obs_connections.lock().unwrap();
+        for addr in obs_connections.values() {
+            if let Err(SendError::Full(msg)) = addr.try_send(message.clone()) {
+                addr.do_send(msg);
+            }
+        }
+    }
+
+    /// ## ブロードキャストにシーケンス番号と署名を付与して配信し、再送キャッシュに追加する
+    ///
+    /// シーケンス番号はJSONテキストとprotobuf版の両方に付与します
+    /// （MessagePack版は付与後のJSONテキストから変換するため含まれます）。
+    /// 採番順と配信順を一致させるため、`deliver` による各セッションへの配信が終わるまでキャッシュのロックを保持します。
+    /// MessagePackモードのクライアント向けの変換も、送信先ごとではなくここで一度だけ行います。
+    ///
+    /// ### Arguments
+    /// - `message`: 送信するブロードキャストメッセージ
+    /// - `channel`: 配信先のチャンネル（全クライアント宛ての場合はNone）
+    /// - `deliver`: 付与済みのメッセージを各セッションに配信する処理
+    fn send_sequenced<F>(&self, mut message: Broadcast, channel: Option<&str>, deliver: F)
+    where
+        F: FnOnce(&Broadcast),
+    {
+        let session_id = Self::current_session_id();
+        let mut cache = self.replay_cache.lock().unwrap();
+        let seq = cache.next_seq(session_id.as_deref());
+        message.json = replay_cache::with_seq(&message.json, seq);
+        message.protobuf = message.protobuf.map(|bin| protobuf::with_seq(&bin, seq));
+        let message = self.sign_broadcast(message).with_msgpack();
+        deliver(&message);
+        cache.push(seq, channel, message, Instant::now());
+    }
+
+    /// ## 再接続したクライアントに再送するメッセージを取得する
+    ///
+    /// クライアントが購読中のチャンネル宛てのメッセージのみを対象とします。
+    ///
+    /// ### Arguments
+    /// - `client_id`: 再接続したクライアントのID
+    /// - `session_id`: クライアントが接続している配信セッションID
+    /// - `last_seq`: クライアントが最後に受信したシーケンス番号
+    ///
+    /// ### Returns
+    /// - `Option<Replay>`: 再送するメッセージ（クライアントが見つからない場合はNone）
+    pub fn replay_since(
+        &self,
+        client_id: &str,
+        session_id: Option<&str>,
+        last_seq: u64,
+    ) -> Option<Replay> {
+        let channels = self
+            .connections
+            .lock()
+            .unwrap()
+            .get(client_id)?
+            .channels
+            .clone();
+        Some(self.replay_cache.lock().unwrap().since(
+            session_id,
+            last_seq,
+            &channels,
+            Instant::now(),
+        ))
+    }
+
+    /// ## 再送キャッシュを破棄
+    ///
+    /// サーバー停止時に呼び出します。
+    pub fn clear_replay_cache(&self) {
+        self.replay_cache.lock().unwrap().clear();
+    }
+
+    /// ## メッセージが処理済みか確認する
+    ///
+    /// 処理済みとしては記録しないため、検証などの前に再送を早めに除外するために使用します。
+    ///
+    /// ### Arguments
+    /// - `message_id`: 受信したメッセージのID
+    ///
+    /// ### Returns
+    /// - `bool`: 処理済みの場合は `true`
+    pub fn is_message_processed(&self, message_id: &str) -> bool {
+        self.recent_message_ids.lock().unwrap().contains(message_id)
+    }
+
+    /// ## メッセージを処理済みとして記録する
+    ///
+    /// 視聴者の再送による重複配信を防ぐため、直近に処理したメッセージIDと照合します。
+    /// 検証中の切断や拒否でメッセージが失われた後の再送を受け付けるよう、保存・ブロードキャストの直前に呼び出してください。
+    ///
+    /// ### Arguments
+    /// - `message_id`: 受信したメッセージのID
+    ///
+    /// ### Returns
+    /// - `bool`: 初めて処理するメッセージの場合は `true`、処理済みの場合は `false`
+    pub fn mark_message_processed(&self, message_id: &str) -> bool {
+        self.recent_message_ids.lock().unwrap().insert(message_id)
+    }
+
+    /// ## スーパーチャットのトランザクションハッシュを予約する
+    ///
+    /// DBへの保存は検証後に非同期で行われるため、同じトランザクションのスーパーチャットが
+    /// ほぼ同時に届いても1つしか配信しないよう、重複確認の前に予約してください。
+    /// 配信したスーパーチャットの予約はサーバーの稼働中は保持されます。
+    /// 同じメッセージIDの再送は予約済みでも受け付けます（重複配信はメッセージIDで除外されます）。
+    ///
+    /// ### Arguments
+    /// - `tx_hash`: トランザクションハッシュ
+    /// - `message_id`: スーパーチャットのメッセージID
+    ///
+    /// ### Returns
+    /// - `bool`: 予約できた場合は `true`、別のメッセージが予約済みの場合は `false`
+    pub fn reserve_tx_hash(&self, tx_hash: &str, message_id: &str) -> bool {
+        let mut reserved = self.reserved_tx_hashes.lock().unwrap();
+        match reserved.get(tx_hash) {
+            Some(reserved_by) => reserved_by == message_id,
+            None => {
+                reserved.insert(tx_hash.to_string(), message_id.to_string());
+                true
+            }
+        }
+    }
+
+    /// ## トランザクションハッシュの予約を解除する
+    ///
+    /// 重複や検証の結果によりスーパーチャットとして配信しなかった場合に呼び出します。
+    /// 別のメッセージが予約している場合は解除しません。
+    ///
+    /// ### Arguments
+    /// - `tx_hash`: トランザクションハッシュ
+    /// - `message_id`: 予約したスーパーチャットのメッセージID
+    pub fn release_tx_hash(&self, tx_hash: &str, message_id: &str) {
+        let mut reserved = self.reserved_tx_hashes.lock().unwrap();
+        if reserved
+            .get(tx_hash)
+            .is_some_and(|reserved_by| reserved_by == message_id)
+        {
+            reserved.remove(tx_hash);
+        }
+    }
+
+    /// ## 接続・切断を接続ログに非同期で記録する
+    ///
+    /// DB接続プールが未初期化の場合は記録をスキップします。
+    /// 記録に失敗しても接続処理には影響させません。
+    /// 切断時は、視聴者が去った後も確認できるよう切断時点までの送信メッセージ数もあわせて記録します。
+    /// IPアドレスはトンネル経由の場合も転送元の視聴者のもの、セッションはクライアントが所属するものを記録します。
+    ///
+    /// ### Arguments
+    /// - `client_info`: 接続・切断したクライアントの情報
+    /// - `disconnected_at`: 切断時刻（接続の記録の場合はNone）
+    fn log_connection_event(
+        client_info: ClientInfo,
+        disconnected_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) {
+        let Some(db_pool) = global::get_app_handle().and_then(|app_handle| {
+            let app_state = app_handle.try_state::<AppState>()?;
+            let db_pool = app_state.db_pool.lock().ok()?.clone();
+            db_pool
+        }) else {
+            return;
+        };
+        // チャンネルのセッションに所属しないクライアントは、サーバー起動時のセッションに紐づけない
+        let session_id = client_info.session_id.clone();
+
+        tauri::async_runtime::spawn(async move {
+            let result = match disconnected_at {
+                None => {
+                    database::log_connection(
+                        &db_pool,
+                        &client_info.id,
+                        &client_info.ip,
+                        session_id.as_deref(),
+                        &client_info.connected_at,
+                    )
+                    .await
+                }
+                Some(disconnected_at) => {
+                    database::log_disconnection(
+                        &db_pool,
+                        &client_info.id,
+                        &client_info.ip,
+                        session_id.as_deref(),
+                        &client_info.connected_at,
+                        disconnected_at,
+                        client_info.messages_sent as i64,
+                    )
+                    .await
+                }
+            };
+            if let Err(e) = result {
+                eprintln!("接続ログの記録に失敗しました ({}): {}", client_info.id, e);
+            }
+        });
+    }
+
+    /// ## 現在の視聴者数を視聴者数の推移に記録する
+    ///
+    /// 配信セッション中のみ記録します。メモリ上の推移は直近の分しか保持しないため、
+    /// 確定したサンプルはその時点でDBへ保存します。
+    fn record_viewer_count() {
+        let Some(app_handle) = global::get_app_handle() else {
+            return;
+        };
+        let Some(app_state) = app_handle.try_state::<AppState>() else {
+            return;
+        };
+        if !stream_sessions::has_active_session(&app_state) {
+            return;
+        }
+        let completed = match app_state.viewer_count_history.lock() {
+            Ok(mut history) => viewer_count_history::record_sample(
+                &mut history,
+                chrono::Utc::now(),
+                get_connections_count(),
+            ),
+            Err(_) => None,
+        };
+        let Some(sample) = completed else {
+            return;
+        };
+        let Some(session_id) = stream_sessions::primary_session_id(&app_state) else {
+            return;
+        };
+        let Some(db_pool) = app_state.db_pool.lock().ok().and_then(|pool| pool.clone()) else {
+            return;
+        };
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) =
+                database::save_viewer_count_history(&db_pool, &session_id, &[sample]).await
+            {
+                eprintln!("視聴者数の推移の保存中にエラーが発生しました: {}", e);
+            }
+        });
+    }
+
+    /// サーバー起動時に作成した配信セッションのIDを取得する
+    ///
+    /// ブロードキャストのシーケンス番号は全チャンネル共通のため、このセッションを基準に採番します。
+    fn current_session_id() -> Option<String> {
+        let app_handle = global::get_app_handle()?;
+        let app_state = app_handle.try_state::<AppState>()?;
+        stream_sessions::primary_session_id(&app_state)
+    }
+
+    /// ## セッションにメッセージを送信し、配信結果を記録する
+    ///
+    /// Actixのメールボックスの状態から送信の成否を判定します。
+    /// - セッションが終了している場合は失敗として記録します。
+    /// - メールボックスが満杯の場合はクライアントの受信が滞っているとみなして失敗として記録しますが、
+    ///   メッセージ自体は取りこぼさないようにキューへ追加します。
+    ///   ただし優先度制御が有効な場合、低優先のメッセージ（通常チャット）は間引きます。
+    ///
+    /// ### Arguments
+    /// - `entry`: 送信先のセッションエントリ
+    /// - `message`: 送信するメッセージ
+    /// - `prioritize`: 優先度制御が有効かどうか
+    fn deliver(entry: &mut SessionEntry, message: &Broadcast, prioritize: bool) {
+        let delivered = match entry.addr.try_send(message.clone()) {
+            Ok(()) => true,
+            Err(SendError::Full(msg)) => {
+                if !(prioritize && msg.priority == BroadcastPriority::Low) {
+                    entry.addr.do_send(msg);
+                }
+                false
+            }
+            Err(SendError::Closed(_)) => false,
+        };
+        entry.client_info.record_delivery(delivered);
+    }
+
+    /// ## 配信統計をリセット
+    ///
+    /// ### Arguments
+    /// - `client_id`: リセットするクライアントのID（Noneの場合は全クライアント）
+    ///
+    /// ### Returns
+    /// - `usize`: リセットしたクライアントの数
+    pub fn reset_delivery_stats(&self, client_id: Option<&str>) -> usize {
+        let reset_count = {
+            let mut connections = self.connections.lock().unwrap();
+            let mut reset_count = 0;
+            for (id, entry) in connections.iter_mut() {
+                if client_id.map_or(true, |target| target == id) {
+                    entry.client_info.reset_delivery_stats();
+                    reset_count += 1;
+                }
+            }
+            reset_count
+        };
+
+        if reset_count > 0 {
+            self.emit_connections_updated();
+        }
+        reset_count
+    }
+
+    /// ## チャンネル購読者にメッセージをブロードキャスト
+    ///
+    /// 指定されたチャンネルを購読しているセッションにのみメッセージを送信します。
+    ///
+    /// ### Arguments
+    /// - `message`: 送信するメッセージ
+    /// - `channel`: 配信先のチャンネル名
+    pub fn broadcast_to_channel(&self, message: &str, channel: &str) {
+        self.broadcast_frame_to_channel(Broadcast::text(message.to_string()), channel);
+    }
+
+    /// ## チャンネル購読者にブロードキャストメッセージを送信
+    ///
+    /// ### Arguments
+    /// - `message`: 送信するブロードキャストメッセージ
+    /// - `channel`: 配信先のチャンネル名
+    pub fn broadcast_frame_to_channel(&self, message: Broadcast, channel: &str) {
+        let prioritize = self.flow_control_config().drops_low_priority();
+        self.send_sequenced(message, Some(channel), |message| {
+            {
+                let mut connections = self.connections.lock().unwrap();
+                for entry in connections
+                    .values_mut()
+                    .filter(|entry| entry.channels.contains(channel))
+                {
+                    Self::deliver(entry, message, prioritize);
+                }
+            }
+            // OBSオーバーレイはデフォルトチャンネルのみを表示する
+            if channel == DEFAULT_CHANNEL {
+                self.deliver_to_obs(message);
+            }
+        });
+    }
+
+    /// ## チャンネルを購読する
+    ///
+    /// ### Arguments
+    /// - `client_id`: 購読するクライアントのID
+    /// - `channel`: 購読するチャンネル名
+    ///
+    /// ### Returns
+    /// - `Option<Vec<String>>`: 購読後のチャンネル一覧（クライアントが見つからない場合はNone）
+    pub fn subscribe_channel(&self, client_id: &str, channel: &str) -> Option<Vec<String>> {
+        let mut connections = self.connections.lock().unwrap();
+        let entry = connections.get_mut(client_id)?;
+        entry.channels.insert(channel.to_string());
+        Some(Self::sorted_channels(&entry.channels))
+    }
+
+    /// ## チャンネルの購読を解除する
+    ///
+    /// ### Arguments
+    /// - `client_id`: 購読を解除するクライアントのID
+    /// - `channel`: 購読を解除するチャンネル名
+    ///
+    /// ### Returns
+    /// - `Option<Vec<String>>`: 解除後のチャンネル一覧（クライアントが見つからない場合はNone）
+    pub fn unsubscribe_channel(&self, client_id: &str, channel: &str) -> Option<Vec<String>> {
+        let mut connections = self.connections.lock().unwrap();
+        let entry = connections.get_mut(client_id)?;
+        entry.channels.remove(channel);
+        Some(Self::sorted_channels(&entry.channels))
+    }
+
+    /// ## グループ所属者にメッセージをブロードキャスト
+    ///
+    /// グループ単位の配信は再接続時の再送対象外です。
+    ///
+    /// ### Arguments
+    /// - `message`: 送信するJSONテキスト
+    /// - `group`: 配信先のグループ名
+    ///
+    /// ### Returns
+    /// - `usize`: 送信したクライアントの数
+    pub fn broadcast_to_group(&self, message: &str, group: &str) -> usize {
+        let message = self.sign_broadcast(Broadcast::text(message.to_string()));
+        let prioritize = self.flow_control_config().drops_low_priority();
+        let mut connections = self.connections.lock().unwrap();
+        let mut sent = 0;
+        for entry in connections
+            .values_mut()
+            .filter(|entry| entry.groups.contains(group))
+        {
+            Self::deliver(entry, &message, prioritize);
+            sent += 1;
+        }
+        sent
+    }
+
+    /// ## クライアントをグループに割り当てる
+    ///
+    /// グループは割り当て時に動的に作成され、1クライアントが複数のグループに所属できます。
+    ///
+    /// ### Arguments
+    /// - `client_id`: 割り当てるクライアントのID
+    /// - `group`: グループ名
+    ///
+    /// ### Returns
+    /// - `Option<Vec<String>>`: 割り当て後の所属グループ（クライアントが見つからない場合はNone）
+    pub fn assign_client_group(&self, client_id: &str, group: &str) -> Option<Vec<String>> {
+        self.update_client_groups(client_id, |groups| groups.assign(group))
+    }
+
+    /// ## クライアントのグループ割り当てを解除する
+    ///
+    /// 全てのグループから外れたクライアントはデフォルトグループに戻ります。
+    ///
+    /// ### Arguments
+    /// - `client_id`: 解除するクライアントのID
+    /// - `group`: グループ名
+    ///
+    /// ### Returns
+    /// - `Option<Vec<String>>`: 解除後の所属グループ（クライアントが見つからない場合はNone）
+    pub fn unassign_client_group(&self, client_id: &str, group: &str) -> Option<Vec<String>> {
+        self.update_client_groups(client_id, |groups| groups.unassign(group))
+    }
+
+    /// ## クライアントの所属グループを取得する
+    ///
+    /// ### Arguments
+    /// - `client_id`: クライアントのID
+    ///
+    /// ### Returns
+    /// - `Option<Vec<String>>`: 所属グループ（クライアントが見つからない場合はNone）
+    pub fn client_groups(&self, client_id: &str) -> Option<Vec<String>> {
+        let connections = self.connections.lock().unwrap();
+        connections.get(client_id).map(|entry| entry.groups.names())
+    }
+
+    /// ## グループごとの接続数を取得する
+    ///
+    /// ### Returns
+    /// - `BTreeMap<String, usize>`: グループ名をキーにした接続数（所属者のいないグループは含まない）
+    pub fn group_connection_counts(&self) -> BTreeMap<String, usize> {
+        let connections = self.connections.lock().unwrap();
+        let mut counts = BTreeMap::new();
+        for group in connections.values().flat_map(|entry| entry.groups.names()) {
+            *counts.entry(group).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// クライアントの所属グループを更新し、更新後の所属グループを返す
+    fn update_client_groups<F>(&self, client_id: &str, updater: F) -> Option<Vec<String>>
+    where
+        F: FnOnce(&mut ClientGroups),
+    {
+        let mut connections = self.connections.lock().unwrap();
+        let entry = connections.get_mut(client_id)?;
+        updater(&mut entry.groups);
+        Some(entry.groups.names())
+    }
+
+    /// チャンネル集合を名前順のベクターに変換する
+    fn sorted_channels(channels: &HashSet<String>) -> Vec<String> {
+        let mut list: Vec<String> = channels.iter().cloned().collect();
+        list.sort();
+        list
+    }
+
+    /// ## 同一ウォレットの接続数の上限を超えた接続を選ぶ
+    ///
+    /// ### Arguments
+    /// - `clients`: 接続中のクライアント情報
+    /// - `wallet_address`: ウォレットアドレス
+    /// - `limit`: 同一ウォレットに紐づく接続数の上限（0の場合は無制限）
+    ///
+    /// ### Returns
+    /// - `Vec<String>`: 切断する接続のID（接続時刻の古い順）
+    fn excess_wallet_clients<'a>(
+        clients: impl IntoIterator<Item = &'a ClientInfo>,
+        wallet_address: &str,
+        limit: usize,
+    ) -> Vec<String> {
+        if limit == 0 {
+            return Vec::new();
+        }
+        let mut same_wallet: Vec<&ClientInfo> = clients
+            .into_iter()
+            .filter(|info| info.wallet_address.as_deref() == Some(wallet_address))
+            .collect();
+        // RFC3339形式のため、文字列の昇順が接続時刻の古い順になる
+        same_wallet.sort_by(|a, b| a.connected_at.cmp(&b.connected_at));
+        let excess = same_wallet.len().saturating_sub(limit);
+        same_wallet
+            .into_iter()
+            .take(excess)
+            .map(|info| info.id.clone())
+            .collect()
+    }
+}
+
+/// ## グローバルモジュール
+///
+/// グローバルに共有されるコネクションマネージャーへのアクセスを提供する
+pub mod global {
+    use super::*;
+    use once_cell::sync::OnceCell;
+
+    // デフォルトの最大接続数
+    const DEFAULT_MAX_CONNECTIONS: usize = 100;
+
+    /// グローバル接続マネージャーのインスタンス
+    static MANAGER: OnceCell<ConnectionManager> = OnceCell::new();
+
+    /// グローバルなTauriアプリケーションハンドル
+    static APP_HANDLE: OnceCell<tauri::AppHandle> = OnceCell::new();
+
+    /// ## 接続マネージャーのインスタンスを取得する
+    ///
+    /// まだ初期化されていない場合は新しいインスタンスを作成します。
     ///
     /// ### Returns
     /// - `ConnectionManager`: グローバル接続マネージャー
@@ -310,6 +1500,78 @@ pub mod global {
         manager.set_max_connections(max);
     }
 
+    /// ## 同一ウォレットの接続数の上限を設定
+    ///
+    /// ### Arguments
+    /// - `limit`: 同一ウォレットに紐づく接続数の上限（0の場合は無制限）
+    pub fn set_per_wallet_limit(limit: usize) {
+        let manager = get_manager();
+        manager.set_per_wallet_limit(limit);
+    }
+
+    /// ## フロー制御の設定を変更
+    ///
+    /// ### Arguments
+    /// - `config`: 新しいフロー制御の設定
+    pub fn set_flow_control(config: FlowControlConfig) {
+        let manager = get_manager();
+        manager.set_flow_control_config(config);
+    }
+
+    /// ## フロー制御の設定を取得
+    ///
+    /// ### Returns
+    /// - `FlowControlConfig`: 現在のフロー制御の設定
+    pub fn get_flow_control() -> FlowControlConfig {
+        let manager = get_manager();
+        manager.flow_control_config()
+    }
+
+    /// ## メッセージ受信レート制限の設定を変更
+    ///
+    /// ### Arguments
+    /// - `limit`: 新しいレート制限の設定
+    pub fn set_message_rate_limit(limit: MessageRateLimit) {
+        let manager = get_manager();
+        manager.set_message_rate_limit(limit);
+    }
+
+    /// ## メッセージ受信レート制限の設定を取得
+    ///
+    /// ### Returns
+    /// - `MessageRateLimit`: 現在のレート制限の設定
+    pub fn get_message_rate_limit() -> MessageRateLimit {
+        let manager = get_manager();
+        manager.message_rate_limit()
+    }
+
+    /// ## アイドル接続の自動切断の設定を変更
+    ///
+    /// ### Arguments
+    /// - `config`: 新しいアイドル切断の設定
+    pub fn set_idle_disconnect(config: IdleDisconnectConfig) {
+        let manager = get_manager();
+        manager.set_idle_disconnect_config(config);
+    }
+
+    /// ## アイドル接続の自動切断の設定を取得
+    ///
+    /// ### Returns
+    /// - `IdleDisconnectConfig`: 現在のアイドル切断の設定
+    pub fn get_idle_disconnect() -> IdleDisconnectConfig {
+        let manager = get_manager();
+        manager.idle_disconnect_config()
+    }
+
+    /// ## 署名鍵を設定
+    ///
+    /// ### Arguments
+    /// - `signer`: 新しい署名鍵
+    pub fn set_signer(signer: MessageSigner) {
+        let manager = get_manager();
+        manager.set_signer(signer);
+    }
+
     /// ## 接続情報を取得
     ///
     /// ### Returns
@@ -319,6 +1581,90 @@ pub mod global {
         manager.get_connections_info()
     }
 
+    /// ## 接続中クライアントの統計を取得
+    ///
+    /// ### Returns
+    /// - `ConnectionStats`: 接続中クライアントの統計
+    pub fn get_connection_stats() -> ConnectionStats {
+        let manager = get_manager();
+        manager.get_connection_stats()
+    }
+
+    /// ## ページ単位で接続情報を取得
+    ///
+    /// ### Arguments
+    /// - `offset`: 取得開始位置
+    /// - `limit`: 取得する最大件数
+    ///
+    /// ### Returns
+    /// - `PaginatedConnectionsInfo`: 指定範囲の接続情報
+    pub fn get_connections_paginated(offset: usize, limit: usize) -> PaginatedConnectionsInfo {
+        let manager = get_manager();
+        let (clients, total) = manager.get_connections_paginated(offset, limit);
+
+        PaginatedConnectionsInfo {
+            active_connections: total,
+            max_connections: manager.get_max_connections(),
+            offset,
+            limit,
+            clients,
+        }
+    }
+
+    /// ## 配信統計をリセット
+    ///
+    /// ### Arguments
+    /// - `client_id`: リセットするクライアントのID（Noneの場合は全クライアント）
+    ///
+    /// ### Returns
+    /// - `usize`: リセットしたクライアントの数
+    pub fn reset_delivery_stats(client_id: Option<&str>) -> usize {
+        let manager = get_manager();
+        manager.reset_delivery_stats(client_id)
+    }
+
+    /// ## 指定されたIDのクライアントにシステム通知を送信
+    ///
+    /// 視聴者が通常のチャットと区別できるよう、`type: "system"` のサーバーレスポンスとして送信します。
+    ///
+    /// ### Arguments
+    /// - `client_id`: 送信先のクライアントのID
+    /// - `message`: 通知する本文
+    ///
+    /// ### Returns
+    /// - `Result<bool, String>`: 送信した場合はtrue、クライアントが見つからない場合はfalse、シリアライズに失敗した場合はエラーメッセージ
+    pub fn send_system_message(client_id: &str, message: &str) -> Result<bool, String> {
+        let response = ServerResponse {
+            message_type: MessageType::System,
+            message: message.to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+        let json = serde_json::to_string(&response)
+            .map_err(|e| format!("システム通知のシリアライズに失敗しました: {}", e))?;
+        Ok(get_manager().send_to_client(client_id, &json))
+    }
+
+    /// ## グループ所属者にシステム通知を送信
+    ///
+    /// `send_system_message` と同じく `type: "system"` のサーバーレスポンスとして送信します。
+    ///
+    /// ### Arguments
+    /// - `group`: 送信先のグループ名
+    /// - `message`: 通知する本文
+    ///
+    /// ### Returns
+    /// - `Result<usize, String>`: 送信したクライアントの数、シリアライズに失敗した場合はエラーメッセージ
+    pub fn send_group_system_message(group: &str, message: &str) -> Result<usize, String> {
+        let response = ServerResponse {
+            message_type: MessageType::System,
+            message: message.to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+        let json = serde_json::to_string(&response)
+            .map_err(|e| format!("システム通知のシリアライズに失敗しました: {}", e))?;
+        Ok(get_manager().broadcast_to_group(&json, group))
+    }
+
     /// ## 指定されたIDのクライアントを切断
     ///
     /// ### Arguments
@@ -330,4 +1676,295 @@ pub mod global {
         let manager = get_manager();
         manager.remove_client(client_id)
     }
+
+    /// ## IPアドレスをブロックし、接続中のセッションを切断
+    ///
+    /// ### Arguments
+    /// - `ip`: ブロックするIPアドレス
+    ///
+    /// ### Returns
+    /// - `Result<usize, String>`: 切断したセッション数、ブロックできない場合はエラーメッセージ
+    pub fn block_client_ip(ip: &str) -> Result<usize, String> {
+        let manager = get_manager();
+        manager.block_ip(ip)
+    }
+
+    /// ## IPアドレスのブロックを解除
+    ///
+    /// ### Arguments
+    /// - `ip`: ブロックを解除するIPアドレス
+    ///
+    /// ### Returns
+    /// - `bool`: ブロックされていた場合はtrue
+    pub fn unblock_client_ip(ip: &str) -> bool {
+        let manager = get_manager();
+        manager.unblock_ip(ip)
+    }
+
+    /// ## ブロック中のIPアドレス一覧を取得
+    ///
+    /// ### Returns
+    /// - `Vec<String>`: ブロック中のIPアドレス
+    pub fn get_blocked_ips() -> Vec<String> {
+        let manager = get_manager();
+        manager.blocked_ips()
+    }
+
+    /// ## クライアントをグループに割り当て
+    ///
+    /// ### Arguments
+    /// - `client_id`: 割り当てるクライアントのID
+    /// - `group`: グループ名
+    ///
+    /// ### Returns
+    /// - `Option<Vec<String>>`: 割り当て後の所属グループ（クライアントが見つからない場合はNone）
+    pub fn assign_client_group(client_id: &str, group: &str) -> Option<Vec<String>> {
+        let manager = get_manager();
+        manager.assign_client_group(client_id, group)
+    }
+
+    /// ## クライアントのグループ割り当てを解除
+    ///
+    /// ### Arguments
+    /// - `client_id`: 解除するクライアントのID
+    /// - `group`: グループ名
+    ///
+    /// ### Returns
+    /// - `Option<Vec<String>>`: 解除後の所属グループ（クライアントが見つからない場合はNone）
+    pub fn unassign_client_group(client_id: &str, group: &str) -> Option<Vec<String>> {
+        let manager = get_manager();
+        manager.unassign_client_group(client_id, group)
+    }
+
+    /// ## クライアントの所属グループを取得
+    ///
+    /// ### Arguments
+    /// - `client_id`: クライアントのID
+    ///
+    /// ### Returns
+    /// - `Option<Vec<String>>`: 所属グループ（クライアントが見つからない場合はNone）
+    pub fn get_client_groups(client_id: &str) -> Option<Vec<String>> {
+        let manager = get_manager();
+        manager.client_groups(client_id)
+    }
+
+    /// ## グループごとの接続数を取得
+    ///
+    /// ### Returns
+    /// - `BTreeMap<String, usize>`: グループ名をキーにした接続数
+    pub fn get_group_connection_counts() -> BTreeMap<String, usize> {
+        let manager = get_manager();
+        manager.group_connection_counts()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{error::PayloadError, web::Bytes};
+    use actix_web_actors::ws::WebsocketContext;
+
+    /// 接続カウンターはプロセス全体で共有されるため、接続を追加・削除するテストを直列に実行する
+    static CONNECTIONS_COUNT_LOCK: Mutex<()> = Mutex::new(());
+
+    /// テスト用のセッションのアドレスを作成する
+    ///
+    /// リクエストと接続マネージャーを持たないセッションのため、マネージャーには登録されない
+    fn test_session_addr() -> Addr<crate::ws_server::session::WsSession> {
+        let (addr, _stream) = WebsocketContext::create_with_addr(
+            crate::ws_server::session::WsSession::new(),
+            futures::stream::empty::<Result<Bytes, PayloadError>>(),
+        );
+        addr
+    }
+
+    /// テスト用のクライアント情報を作成する
+    fn test_client(id: &str, connected_at: &str) -> ClientInfo {
+        let mut info = ClientInfo::new("203.0.113.1:8080".parse().unwrap());
+        info.id = id.to_string();
+        info.connected_at = connected_at.to_string();
+        info
+    }
+
+    /// 同一ウォレットの接続が上限を超えた場合は接続時刻の古いものから選ぶことを確認
+    #[test]
+    fn test_excess_wallet_clients() {
+        let client = |id: &str, connected_at: &str, wallet_address: Option<&str>| {
+            let mut info = ClientInfo::new("127.0.0.1:8080".parse().unwrap());
+            info.id = id.to_string();
+            info.connected_at = connected_at.to_string();
+            info.wallet_address = wallet_address.map(str::to_string);
+            info
+        };
+        let clients = vec![
+            client("newest", "2024-01-01T00:00:03+00:00", Some("0xaaa")),
+            client("oldest", "2024-01-01T00:00:01+00:00", Some("0xaaa")),
+            client("middle", "2024-01-01T00:00:02+00:00", Some("0xaaa")),
+            client("other", "2024-01-01T00:00:00+00:00", Some("0xbbb")),
+            client("unbound", "2024-01-01T00:00:00+00:00", None),
+        ];
+
+        let excess = |limit| ConnectionManager::excess_wallet_clients(&clients, "0xaaa", limit);
+        assert_eq!(excess(1), vec!["oldest".to_string(), "middle".to_string()]);
+        assert_eq!(excess(2), vec!["oldest".to_string()]);
+        assert!(excess(3).is_empty());
+        // 0の場合は無制限
+        assert!(excess(0).is_empty());
+    }
+
+    /// IPアドレスのブロックのテスト
+    #[test]
+    fn test_block_ip() {
+        let manager = ConnectionManager::new(10);
+        // 転送元IPのないトンネル経由の接続をまとめてブロックしないよう、ループバックは拒否する
+        assert!(manager.block_ip("127.0.0.1").is_err());
+        assert!(manager.block_ip("::1").is_err());
+        assert!(!manager.is_ip_blocked("127.0.0.1"));
+
+        assert_eq!(manager.block_ip("203.0.113.5"), Ok(0));
+        assert!(manager.is_ip_blocked("203.0.113.5"));
+        assert!(!manager.is_ip_blocked("203.0.113.6"));
+        assert_eq!(manager.blocked_ips(), vec!["203.0.113.5".to_string()]);
+
+        assert!(manager.unblock_ip("203.0.113.5"));
+        assert!(!manager.is_ip_blocked("203.0.113.5"));
+    }
+
+    /// クライアントの所属グループの割り当て・解除のテスト
+    #[test]
+    fn test_client_groups() {
+        let mut groups = ClientGroups::default();
+        // 未割り当てのクライアントはデフォルトグループに所属する
+        assert!(groups.contains(DEFAULT_GROUP));
+        assert_eq!(groups.names(), vec![DEFAULT_GROUP.to_string()]);
+
+        // 複数のグループに所属でき、デフォルトグループからは外れる
+        groups.assign("vip");
+        groups.assign("mods");
+        groups.assign(DEFAULT_GROUP);
+        assert!(groups.contains("vip"));
+        assert!(!groups.contains(DEFAULT_GROUP));
+        assert_eq!(groups.names(), vec!["mods".to_string(), "vip".to_string()]);
+
+        // 全てのグループから外れるとデフォルトグループに戻る
+        groups.unassign("vip");
+        groups.unassign("mods");
+        assert_eq!(groups, ClientGroups::default());
+        assert!(groups.contains(DEFAULT_GROUP));
+    }
+
+    /// 同じトランザクションのスーパーチャットが同時に届いた場合に1つだけ予約できることのテスト
+    #[test]
+    fn test_reserve_tx_hash_concurrently() {
+        let manager = Arc::new(ConnectionManager::new(10));
+        let barrier = Arc::new(std::sync::Barrier::new(2));
+        let handles: Vec<_> = ["msg-1", "msg-2"]
+            .into_iter()
+            .map(|message_id| {
+                let manager = Arc::clone(&manager);
+                let barrier = Arc::clone(&barrier);
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    manager.reserve_tx_hash("0xtx", message_id)
+                })
+            })
+            .collect();
+        let reserved: Vec<bool> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(reserved.iter().filter(|reserved| **reserved).count(), 1);
+
+        let owner = if reserved[0] { "msg-1" } else { "msg-2" };
+        let other = if reserved[0] { "msg-2" } else { "msg-1" };
+        // 同じメッセージの再送は受け付ける
+        assert!(manager.reserve_tx_hash("0xtx", owner));
+        // 予約していないメッセージからは解除できない
+        manager.release_tx_hash("0xtx", other);
+        assert!(!manager.reserve_tx_hash("0xtx", other));
+        // 解除後は別のメッセージが予約できる
+        manager.release_tx_hash("0xtx", owner);
+        assert!(manager.reserve_tx_hash("0xtx", other));
+    }
+
+    /// 検証で確認できたウォレットを紐づけると、同一ウォレットの接続数の上限が適用されることのテスト
+    #[actix::test]
+    async fn test_per_wallet_limit_with_verified_wallets() {
+        let _guard = CONNECTIONS_COUNT_LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        reset_connections();
+        let manager = ConnectionManager::new(10);
+        manager.set_per_wallet_limit(1);
+        assert!(manager.add_client(
+            test_client("old", "2024-01-01T00:00:01+00:00"),
+            test_session_addr()
+        ));
+        assert!(manager.add_client(
+            test_client("new", "2024-01-01T00:00:02+00:00"),
+            test_session_addr()
+        ));
+
+        // トランザクション検証で送金者を確認できた順に紐づける
+        assert_eq!(manager.bind_wallet("old", "0xaaa"), 0);
+        // 上限を超えたため、接続時刻の古い接続が切断される
+        assert_eq!(manager.bind_wallet("new", "0xaaa"), 1);
+        assert!(manager.get_client("old").is_none());
+        assert!(manager.get_client("new").is_some());
+        // 紐づけ済みの接続を再度紐づけても切断しない
+        assert_eq!(manager.bind_wallet("new", "0xaaa"), 0);
+
+        manager.reset();
+    }
+
+    /// 接続情報のページ単位取得の範囲・空ページ・最終ページのテスト
+    #[actix::test]
+    async fn test_get_connections_paginated() {
+        let _guard = CONNECTIONS_COUNT_LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        reset_connections();
+        let manager = ConnectionManager::new(10);
+        let page_ids = |offset, limit| {
+            let (page, total) = manager.get_connections_paginated(offset, limit);
+            let ids: Vec<String> = page.into_iter().map(|info| info.id).collect();
+            (ids, total)
+        };
+
+        // 接続がない場合は空ページ
+        assert_eq!(page_ids(0, 2), (Vec::new(), 0));
+
+        // 追加順と無関係に接続時刻の古い順に並ぶ
+        for (id, second) in [("c", 3), ("a", 1), ("e", 5), ("b", 2), ("d", 4)] {
+            let connected_at = format!("2024-01-01T00:00:0{}+00:00", second);
+            assert!(manager.add_client(test_client(id, &connected_at), test_session_addr()));
+        }
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        assert_eq!(page_ids(0, 2), (ids(&["a", "b"]), 5));
+        assert_eq!(page_ids(2, 2), (ids(&["c", "d"]), 5));
+        // 最終ページは残りの件数のみ
+        assert_eq!(page_ids(4, 2), (ids(&["e"]), 5));
+        // 範囲外のオフセットは空ページ（総数は返す）
+        assert_eq!(page_ids(5, 2), (Vec::new(), 5));
+        assert_eq!(page_ids(100, 2), (Vec::new(), 5));
+        // 件数が総数を超える場合は全件
+        assert_eq!(page_ids(0, 100), (ids(&["a", "b", "c", "d", "e"]), 5));
+
+        manager.reset();
+    }
+
+    /// リセットでOBSオーバーレイの接続も削除されることのテスト
+    #[actix::test]
+    async fn test_reset_clears_obs_connections() {
+        let _guard = CONNECTIONS_COUNT_LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let manager = ConnectionManager::new(10);
+        let addr = test_session_addr();
+        manager.add_obs_client("obs-1", addr.clone());
+        manager.add_obs_client("obs-2", addr);
+        assert_eq!(manager.obs_connection_count(), 2);
+
+        manager.reset();
+        assert_eq!(manager.obs_connection_count(), 0);
+        // リセット後は削除済みの接続を再度削除しても何も起きない
+        assert!(!manager.remove_obs_client("obs-1"));
+    }
 }
@@ -0,0 +1,339 @@
+//! 接続先URL生成モジュール
+//!
+//! 視聴者が配信者のWebSocketサーバーへ接続するためのURL（ローカル・LAN・トンネル）を
+//! サーバーの状態から生成します。視聴者フロントは `GET /info` で取得した接続候補を順に試し、
+//! 最も早く接続できたものを使用します（スマート接続）。
+
+use crate::state::AppState;
+use crate::ws_server::access_token;
+use crate::ws_server::network_type;
+use crate::ws_server::server_utils::{detect_lan_ip, is_lan_exposed_host};
+use crate::ws_server::tunnel::TunnelKind;
+use actix_web::HttpRequest;
+use serde::Serialize;
+
+/// ## 視聴者の接続方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionMethod {
+    /// 配信者と同じPCからの接続
+    Local,
+    /// 同一LAN内からの直接接続
+    Lan,
+    /// Cloudflaredトンネル経由の接続
+    Tunnel,
+}
+
+impl ConnectionMethod {
+    /// ## `ClientInfo` に設定する文字列表現を取得する
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::Lan => "lan",
+            Self::Tunnel => "tunnel",
+        }
+    }
+
+    /// ## リクエストから接続方式を判定する
+    ///
+    /// トンネルはループバックアドレスから転送元のヘッダー付きで接続するため
+    /// （cloudflaredは `CF-Connecting-IP`、ngrokは `X-Forwarded-For`）、
+    /// 実行中のトンネルのヘッダーの有無でトンネル経由とローカル接続を区別します。
+    ///
+    /// ### Arguments
+    /// - `req`: WebSocketのアップグレードリクエスト
+    /// - `tunnel`: 実行中のトンネルのプロバイダ（トンネルを実行していない場合はNone）
+    ///
+    /// ### Returns
+    /// - `Option<Self>`: 判定した接続方式（接続元アドレスが取得できない場合はNone）
+    pub fn from_request(req: &HttpRequest, tunnel: Option<TunnelKind>) -> Option<Self> {
+        let peer_ip = req.peer_addr()?.ip();
+        if !peer_ip.is_loopback() {
+            return Some(Self::Lan);
+        }
+
+        if network_type::forwarded_ip(req, tunnel).is_some() {
+            Some(Self::Tunnel)
+        } else {
+            Some(Self::Local)
+        }
+    }
+}
+
+/// ## 視聴者向けの接続候補
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConnectionCandidate {
+    /// 接続方式 ("local" / "lan" / "tunnel")
+    pub method: String,
+    /// WebSocket URL
+    pub url: String,
+}
+
+/// ## 接続方式ごとのWebSocket URL
+///
+/// 利用できない接続方式のURLは `None` になります。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConnectionUrls {
+    /// 配信者のPCから接続するURL (例: "ws://127.0.0.1:8082/ws")
+    pub local_ws_url: Option<String>,
+    /// 同一LAN内から接続するURL（全インターフェースで待ち受けている場合のみ）
+    pub lan_ws_url: Option<String>,
+    /// トンネル経由で接続するURL（トンネル稼働中のみ）
+    pub tunnel_ws_url: Option<String>,
+}
+
+impl ConnectionUrls {
+    /// ## サーバーの状態から接続先URLを生成する
+    ///
+    /// サーバー停止中は全て `None` を返します。
+    /// TLS無効時はサーバーがループバックアドレスのみで待ち受けるため、LAN URLは `None` になります。
+    /// ただしLAN内公開モード（トンネル不使用）では全インターフェースで待ち受けるため、LAN IPのURLを返します。
+    ///
+    /// ### Arguments
+    /// - `app_state`: アプリケーション状態
+    ///
+    /// ### Returns
+    /// - `Self`: 接続方式ごとのURL
+    pub fn collect(app_state: &AppState) -> Self {
+        let is_running = app_state
+            .server_handle
+            .lock()
+            .map(|handle| handle.is_some())
+            .unwrap_or(false);
+        let port = app_state.port.lock().ok().and_then(|port| *port);
+        let (true, Some(port)) = (is_running, port) else {
+            return Self::default();
+        };
+
+        let host = app_state
+            .host
+            .lock()
+            .ok()
+            .and_then(|host| host.clone())
+            .unwrap_or_else(|| "127.0.0.1".to_string());
+        let tls_config = app_state
+            .tls_config
+            .lock()
+            .map(|tls_config| tls_config.clone())
+            .unwrap_or_default();
+
+        let (local_ws_url, lan_ws_url) = if tls_config.enabled {
+            // TLS有効時は全インターフェースで待ち受けるため、LANから直接接続できる
+            let lan_host = tls_config
+                .server_name
+                .clone()
+                .or_else(|| detect_lan_ip().map(|ip| ip.to_string()));
+            (
+                format!("wss://{}:{}/ws", host, port),
+                lan_host.map(|lan_host| format!("wss://{}:{}/ws", lan_host, port)),
+            )
+        } else if is_lan_only(app_state) || is_lan_exposed(app_state) {
+            (
+                format!("ws://{}:{}/ws", host, port),
+                detect_lan_ip().map(|ip| format!("ws://{}:{}/ws", ip, port)),
+            )
+        } else {
+            (format!("ws://{}:{}/ws", host, port), None)
+        };
+
+        let tunnel_ws_url =
+            app_state
+                .tunnel_info
+                .lock()
+                .ok()
+                .and_then(|tunnel_info| match &*tunnel_info {
+                    Some(Ok(tunnel_info)) => Some(tunnel_ws_url(&tunnel_info.url)),
+                    _ => None,
+                });
+
+        Self {
+            local_ws_url: Some(local_ws_url),
+            lan_ws_url,
+            tunnel_ws_url,
+        }
+    }
+
+    /// ## 視聴者に共有する代表のURLを取得する
+    ///
+    /// 外部から到達できる可能性が高い順に、トンネル・LAN・ローカルのURLを選択します。
+    pub fn preferred(&self) -> Option<String> {
+        self.tunnel_ws_url
+            .clone()
+            .or_else(|| self.lan_ws_url.clone())
+            .or_else(|| self.local_ws_url.clone())
+    }
+
+    /// ## 全てのURLにアクセストークンを付与する
+    ///
+    /// 配信者が視聴者に共有するURLに使用します。
+    /// 誰でも取得できる `/info` の接続候補にはトークンを含めないため、`collect` とは分けています。
+    ///
+    /// ### Arguments
+    /// - `token`: アクセストークン（`None` の場合は付与しない）
+    ///
+    /// ### Returns
+    /// - `Self`: トークンを付与したURL
+    pub fn with_access_token(self, token: Option<&str>) -> Self {
+        let with_token =
+            |url: Option<String>| url.map(|url| access_token::with_access_token(url, token));
+        Self {
+            local_ws_url: with_token(self.local_ws_url),
+            lan_ws_url: with_token(self.lan_ws_url),
+            tunnel_ws_url: with_token(self.tunnel_ws_url),
+        }
+    }
+
+    /// ## 視聴者フロントが試行する接続候補を取得する
+    ///
+    /// 通信経路が短い順（ローカル→LAN→トンネル）に並べます。
+    ///
+    /// ### Returns
+    /// - `Vec<ConnectionCandidate>`: 利用可能な接続候補
+    pub fn candidates(&self) -> Vec<ConnectionCandidate> {
+        [
+            (ConnectionMethod::Local, &self.local_ws_url),
+            (ConnectionMethod::Lan, &self.lan_ws_url),
+            (ConnectionMethod::Tunnel, &self.tunnel_ws_url),
+        ]
+        .into_iter()
+        .filter_map(|(method, url)| {
+            url.as_ref().map(|url| ConnectionCandidate {
+                method: method.as_str().to_string(),
+                url: url.clone(),
+            })
+        })
+        .collect()
+    }
+}
+
+/// ## トンネルのHTTPS URLからWebSocket URLを生成する
+///
+/// ### Arguments
+/// - `http_url`: トンネルのHTTPS URL (例: "https://*.trycloudflare.com")
+///
+/// ### Returns
+/// - `String`: WebSocket URL (例: "wss://*.trycloudflare.com/ws")
+pub fn tunnel_ws_url(http_url: &str) -> String {
+    http_url.replace("https://", "wss://") + "/ws"
+}
+
+/// ## トンネルを使用しない場合のWebSocket URLを生成する
+///
+/// TLS有効時は証明書のホスト名を使った wss:// URL、無効時は ws:// URLを返します。
+/// LAN内公開モードでは、LAN内の他の端末から接続できるようLAN IPを使用します。
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+/// - `host`: ホスト名
+/// - `port`: ポート番号
+///
+/// ### Returns
+/// - `String`: WebSocket URL
+pub fn direct_ws_url(app_state: &AppState, host: &str, port: u16) -> String {
+    let tls_config = app_state
+        .tls_config
+        .lock()
+        .map(|tls_config| tls_config.clone())
+        .unwrap_or_default();
+
+    if tls_config.enabled {
+        let server_name = tls_config.server_name.as_deref().unwrap_or(host);
+        format!("wss://{}:{}/ws", server_name, port)
+    } else if is_lan_only(app_state) {
+        let lan_host = detect_lan_ip().map_or_else(|| host.to_string(), |ip| ip.to_string());
+        format!("ws://{}:{}/ws", lan_host, port)
+    } else {
+        format!("ws://{}:{}/ws", host, port)
+    }
+}
+
+/// ## LAN内公開モードかどうかを判定する
+///
+/// トンネルを使用しない設定の場合、サーバーは全インターフェースで待ち受けてLAN内にのみ公開されます。
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+///
+/// ### Returns
+/// - `bool`: トンネルを使用しない設定の場合は `true`
+pub fn is_lan_only(app_state: &AppState) -> bool {
+    app_state
+        .use_tunnel
+        .lock()
+        .map(|use_tunnel| !*use_tunnel)
+        .unwrap_or(false)
+}
+
+/// ## バインドホストの設定によりLANに公開しているかどうかを判定する
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+///
+/// ### Returns
+/// - `bool`: バインドホストに `0.0.0.0` などの全インターフェースが設定されている場合は `true`
+pub fn is_lan_exposed(app_state: &AppState) -> bool {
+    app_state
+        .bind_host
+        .lock()
+        .is_ok_and(|host| is_lan_exposed_host(&host))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    /// 接続方式の判定のテスト
+    #[test]
+    fn test_connection_method_from_request() {
+        let request = |peer: &str, forwarded_for: Option<&str>| {
+            let mut request = TestRequest::default().peer_addr(peer.parse().unwrap());
+            if let Some(value) = forwarded_for {
+                request = request.insert_header(("X-Forwarded-For", value));
+            }
+            request.to_http_request()
+        };
+        let method = |req: &HttpRequest, tunnel| ConnectionMethod::from_request(req, tunnel);
+
+        let forwarded = request("127.0.0.1:50000", Some("203.0.113.5"));
+        assert_eq!(
+            method(&forwarded, Some(TunnelKind::Ngrok)),
+            Some(ConnectionMethod::Tunnel)
+        );
+        // トンネル停止中に同じPCから転送元ヘッダーを付与してもローカル接続として扱う
+        assert_eq!(method(&forwarded, None), Some(ConnectionMethod::Local));
+        assert_eq!(
+            method(&request("127.0.0.1:50000", None), Some(TunnelKind::Ngrok)),
+            Some(ConnectionMethod::Local)
+        );
+        assert_eq!(
+            method(&request("192.168.0.10:50000", Some("203.0.113.5")), None),
+            Some(ConnectionMethod::Lan)
+        );
+    }
+
+    /// 代表のURLと接続候補の選択のテスト
+    #[test]
+    fn test_preferred_and_candidates() {
+        let urls = ConnectionUrls {
+            local_ws_url: Some("ws://127.0.0.1:8082/ws".to_string()),
+            lan_ws_url: None,
+            tunnel_ws_url: Some(tunnel_ws_url("https://example.trycloudflare.com")),
+        };
+        assert_eq!(
+            urls.tunnel_ws_url.as_deref(),
+            Some("wss://example.trycloudflare.com/ws")
+        );
+        // 代表のURLは外部から到達できるトンネルを優先する
+        assert_eq!(urls.preferred(), urls.tunnel_ws_url);
+        // 接続候補は通信経路が短い順に並べ、利用できない方式は含めない
+        let methods: Vec<_> = urls
+            .candidates()
+            .into_iter()
+            .map(|candidate| candidate.method)
+            .collect();
+        assert_eq!(methods, vec!["local", "tunnel"]);
+
+        assert_eq!(ConnectionUrls::default().preferred(), None);
+        assert!(ConnectionUrls::default().candidates().is_empty());
+    }
+}
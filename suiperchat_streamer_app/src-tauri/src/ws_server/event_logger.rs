@@ -0,0 +1,193 @@
+//! サーバーイベントロガーモジュール
+//!
+//! トンネル接続の失敗などを後から調査できるよう、サーバー状態の変化を
+//! アプリデータディレクトリの `logs/server_events.log` にJSON Lines形式で追記します。
+//! ログファイルは日付ごとにローテーションし、前日以前のログは `server_events-YYYY-MM-DD.log` として残します。
+//! 書き込みに失敗してもアプリの動作は止めず、警告を出力するだけにします。
+
+use crate::types::ServerStatus;
+use chrono::{DateTime, Local, NaiveDate};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Manager;
+
+/// ログディレクトリ名（アプリデータディレクトリ配下）
+pub const LOGS_DIR: &str = "logs";
+
+/// サーバーイベントログのファイル名
+pub const SERVER_EVENTS_LOG_FILE: &str = "server_events.log";
+
+/// 保持するローテーション済みログファイルの最大数
+pub const MAX_ROTATED_LOG_FILES: usize = 14;
+
+/// ローテーションと追記が同時に行われないよう、書き込みを直列化するロック
+static WRITE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// ## サーバーイベントログの1行分の記録
+#[derive(Debug, Serialize)]
+struct ServerEventRecord<'a> {
+    /// 記録時刻（RFC3339）
+    timestamp: String,
+    /// サーバーが起動中かどうか
+    is_running: bool,
+    /// トンネルの状態
+    tunnel_status: &'a str,
+    /// トンネルのエラーメッセージ
+    tunnel_error: Option<&'a str>,
+    /// 視聴者に案内するWebSocketのURL
+    ws_url: Option<&'a str>,
+}
+
+/// ## サーバーイベントログのパスを取得する
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+///
+/// ### Returns
+/// - `Result<PathBuf, String>`: ログファイルのパス、アプリデータディレクトリを取得できない場合はエラーメッセージ
+pub fn log_file_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app_handle
+        .path()
+        .app_data_dir()
+        .map(|dir| dir.join(LOGS_DIR).join(SERVER_EVENTS_LOG_FILE))
+        .map_err(|e| format!("アプリデータディレクトリの取得に失敗しました: {}", e))
+}
+
+/// ## サーバー状態をログファイルに記録する
+///
+/// 書き込みに失敗した場合は警告を出力し、処理を続行します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `status`: 通知したサーバー状態
+pub fn log_server_status(app_handle: &tauri::AppHandle, status: &ServerStatus) {
+    let record = ServerEventRecord {
+        timestamp: Local::now().to_rfc3339(),
+        is_running: status.is_running,
+        tunnel_status: &status.tunnel_status,
+        tunnel_error: status.tunnel_error.as_deref(),
+        ws_url: status.ws_url.as_deref(),
+    };
+    let result = log_file_path(app_handle).and_then(|path| append_record(&path, &record));
+    if let Err(e) = result {
+        eprintln!("警告: サーバーイベントログの書き込みに失敗しました: {}", e);
+    }
+}
+
+/// 必要に応じてローテーションしてから、記録を1行追記する
+fn append_record(path: &Path, record: &ServerEventRecord) -> Result<(), String> {
+    let line = serde_json::to_string(record)
+        .map_err(|e| format!("ログのシリアライズに失敗しました: {}", e))?;
+
+    let _guard = WRITE_LOCK
+        .lock()
+        .map_err(|e| format!("ログの書き込みロックに失敗しました: {}", e))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("ログディレクトリの作成に失敗しました: {}", e))?;
+    }
+    rotate_if_needed(path, Local::now().date_naive())?;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("ログファイルを開けませんでした: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("ログファイルへの追記に失敗しました: {}", e))
+}
+
+/// ログファイルの最終更新日が今日より前なら、日付付きのファイル名に変更する
+fn rotate_if_needed(path: &Path, today: NaiveDate) -> Result<(), String> {
+    let Ok(modified) = fs::metadata(path).and_then(|metadata| metadata.modified()) else {
+        // ログファイルがまだ存在しない
+        return Ok(());
+    };
+    let last_date = DateTime::<Local>::from(modified).date_naive();
+    if last_date >= today {
+        return Ok(());
+    }
+
+    let rotated = rotated_path(path, last_date);
+    fs::rename(path, &rotated)
+        .map_err(|e| format!("ログファイルのローテーションに失敗しました: {}", e))?;
+    prune_rotated_logs(path);
+    Ok(())
+}
+
+/// ローテーション後のログファイルのパス（`server_events-YYYY-MM-DD.log`）
+fn rotated_path(path: &Path, date: NaiveDate) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("server_events");
+    path.with_file_name(format!("{}-{}.log", stem, date.format("%Y-%m-%d")))
+}
+
+/// 古いローテーション済みログファイルを削除する（日付の新しいものから `MAX_ROTATED_LOG_FILES` 件を残す）
+fn prune_rotated_logs(path: &Path) {
+    let (Some(dir), Some(stem)) = (path.parent(), path.file_stem().and_then(|s| s.to_str())) else {
+        return;
+    };
+    let prefix = format!("{}-", stem);
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut rotated: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|entry| {
+            entry
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(".log"))
+        })
+        .collect();
+    // ファイル名に日付が含まれるため、名前の降順が新しい順になる
+    rotated.sort_by(|a, b| b.cmp(a));
+    for old in rotated.into_iter().skip(MAX_ROTATED_LOG_FILES) {
+        if let Err(e) = fs::remove_file(&old) {
+            eprintln!(
+                "警告: 古いログファイルの削除に失敗しました ({:?}): {}",
+                old, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_and_rotate() {
+        let dir = std::env::temp_dir().join(format!("suiperchat-log-{}", uuid::Uuid::new_v4()));
+        let path = dir.join(SERVER_EVENTS_LOG_FILE);
+        let record = ServerEventRecord {
+            timestamp: "2024-01-01T00:00:00+09:00".to_string(),
+            is_running: true,
+            tunnel_status: "Failed",
+            tunnel_error: Some("timeout"),
+            ws_url: None,
+        };
+
+        append_record(&path, &record).unwrap();
+        append_record(&path, &record).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), 2);
+        assert!(
+            content.starts_with(r#"{"timestamp":"2024-01-01T00:00:00+09:00","is_running":true"#)
+        );
+
+        // 翌日になるとファイル名に最終更新日を付けて退避し、新しいファイルに書き始める
+        let today = Local::now().date_naive();
+        rotate_if_needed(&path, today.succ_opt().unwrap()).unwrap();
+        assert!(!path.exists());
+        assert!(rotated_path(&path, today).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,332 @@
+//! 送信フロー制御モジュール
+//!
+//! クライアントごとの送信トークンバケットで送信レートに上限を設けます。
+//! トークンが枯渇した場合はメッセージをバッファに溜め、次のトークン補充時にまとめて送信します。
+//! バッファが上限を超えた場合は古い低優先メッセージ（通常チャット）から破棄し、最新のメッセージを優先します。
+//! 優先度制御が有効な場合、滞留時はスーパーチャットを通常チャットより先に送信します。
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// デフォルトの1秒あたりの最大送信メッセージ数
+pub const DEFAULT_MAX_MESSAGES_PER_SECOND: u32 = 20;
+
+/// 設定可能な1秒あたりの最大送信メッセージ数の上限
+pub const MAX_MESSAGES_PER_SECOND_LIMIT: u32 = 1000;
+
+/// バッファに保持する最大メッセージ数（秒間上限の何秒分か）
+const PENDING_BUFFER_SECONDS: u32 = 2;
+
+/// バッファ済みメッセージの送信を試みる間隔
+pub const FLOW_CONTROL_TICK: Duration = Duration::from_millis(100);
+
+/// ## ブロードキャストの配信優先度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BroadcastPriority {
+    /// 高優先（スーパーチャット）: 滞留時も先に送信し、間引かない
+    High,
+    /// 通常（システム通知など）: 配信順に送信し、間引かない
+    #[default]
+    Normal,
+    /// 低優先（通常チャット）: 滞留時に間引かれる
+    Low,
+}
+
+/// ## フロー制御の設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlowControlConfig {
+    /// フロー制御を有効にするかどうか（無効の場合は即時送信、既存の配信動作を変えないためデフォルトは無効）
+    pub enabled: bool,
+    /// クライアントごとの1秒あたりの最大送信メッセージ数
+    pub max_messages_per_second: u32,
+    /// 滞留時にスーパーチャットを優先して送信するかどうか（無効の場合は配信順を維持）
+    #[serde(default = "default_prioritize_superchats")]
+    pub prioritize_superchats: bool,
+}
+
+impl Default for FlowControlConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_messages_per_second: DEFAULT_MAX_MESSAGES_PER_SECOND,
+            prioritize_superchats: default_prioritize_superchats(),
+        }
+    }
+}
+
+fn default_prioritize_superchats() -> bool {
+    true
+}
+
+impl FlowControlConfig {
+    /// ## 送信が滞留した場合に低優先のメッセージを間引くかどうか
+    ///
+    /// フロー制御が無効の場合は優先度制御の設定に関わらず間引きません。
+    pub fn drops_low_priority(&self) -> bool {
+        self.enabled && self.prioritize_superchats
+    }
+
+    /// バッファに保持する最大メッセージ数
+    fn max_pending(&self) -> usize {
+        (self.max_messages_per_second * PENDING_BUFFER_SECONDS) as usize
+    }
+}
+
+/// ## クライアントごとの送信トークンバケット
+///
+/// `T` は送信するメッセージの型です。
+#[derive(Debug)]
+pub struct FlowController<T> {
+    /// 現在のトークン数
+    tokens: f64,
+    /// 最後にトークンを補充した時刻
+    last_refill: Instant,
+    /// 送信待ちのメッセージ（配信優先度付き）
+    pending: VecDeque<(T, BroadcastPriority)>,
+    /// 間引いたメッセージの累計数
+    dropped: u64,
+}
+
+impl<T> FlowController<T> {
+    /// ## 新しいトークンバケットを作成する
+    ///
+    /// バケットは満杯（1秒分のトークン）の状態で開始します。
+    ///
+    /// ### Arguments
+    /// - `config`: フロー制御の設定
+    /// - `now`: 現在時刻
+    pub fn new(config: &FlowControlConfig, now: Instant) -> Self {
+        Self {
+            tokens: config.max_messages_per_second as f64,
+            last_refill: now,
+            pending: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    /// ## メッセージの送信を要求する
+    ///
+    /// トークンがあり送信待ちのメッセージがなければ即時送信対象として返し、
+    /// そうでなければバッファに追加します。
+    ///
+    /// ### Arguments
+    /// - `message`: 送信するメッセージ
+    /// - `priority`: 配信優先度（低優先のメッセージはバッファ溢れ時に間引かれる）
+    /// - `config`: フロー制御の設定
+    /// - `now`: 現在時刻
+    ///
+    /// ### Returns
+    /// - `Vec<T>`: 今すぐ送信するメッセージ（古い順）
+    pub fn offer(
+        &mut self,
+        message: T,
+        priority: BroadcastPriority,
+        config: &FlowControlConfig,
+        now: Instant,
+    ) -> Vec<T> {
+        if !config.enabled {
+            // 無効化された場合はバッファを含めて即時送信
+            let mut ready = self.take_all();
+            ready.push(message);
+            return ready;
+        }
+
+        self.pending.push_back((message, priority));
+        self.trim(config);
+        self.drain(config, now)
+    }
+
+    /// ## トークンを補充し、送信可能な分だけバッファから取り出す
+    ///
+    /// ### Arguments
+    /// - `config`: フロー制御の設定
+    /// - `now`: 現在時刻
+    ///
+    /// ### Returns
+    /// - `Vec<T>`: 今すぐ送信するメッセージ（古い順）
+    pub fn drain(&mut self, config: &FlowControlConfig, now: Instant) -> Vec<T> {
+        if !config.enabled {
+            return self.take_all();
+        }
+
+        let rate = config.max_messages_per_second as f64;
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(rate);
+        self.last_refill = now;
+
+        let mut ready = Vec::new();
+        while self.tokens >= 1.0 {
+            let Some(message) = self.pop_next(config) else {
+                break;
+            };
+            self.tokens -= 1.0;
+            ready.push(message);
+        }
+        ready
+    }
+
+    /// 送信待ちのメッセージがあるかどうか
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// 間引いたメッセージの累計数
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// バッファの全メッセージを取り出す
+    fn take_all(&mut self) -> Vec<T> {
+        self.pending.drain(..).map(|(message, _)| message).collect()
+    }
+
+    /// 次に送信するメッセージを取り出す
+    ///
+    /// 優先度制御が有効な場合は最も古い高優先メッセージを、それ以外は最も古いメッセージを返す
+    fn pop_next(&mut self, config: &FlowControlConfig) -> Option<T> {
+        let index = if config.prioritize_superchats {
+            self.pending
+                .iter()
+                .position(|(_, priority)| *priority == BroadcastPriority::High)
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        self.pending.remove(index).map(|(message, _)| message)
+    }
+
+    /// バッファが上限を超えた場合、古い低優先メッセージから破棄する
+    fn trim(&mut self, config: &FlowControlConfig) {
+        while self.pending.len() > config.max_pending() {
+            let Some(index) = self
+                .pending
+                .iter()
+                .position(|(_, priority)| *priority == BroadcastPriority::Low)
+            else {
+                // 間引けるメッセージがない場合は溜めたまま送信を待つ
+                break;
+            };
+            self.pending.remove(index);
+            self.dropped += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// トークン枯渇時にバッファされ、補充時にまとめて送信・溢れた古いメッセージは間引かれることを確認
+    #[test]
+    fn test_token_bucket_buffers_and_drops_oldest() {
+        let config = FlowControlConfig {
+            enabled: true,
+            max_messages_per_second: 2,
+            prioritize_superchats: false,
+        };
+        let start = Instant::now();
+        let mut flow = FlowController::new(&config, start);
+
+        // 最初の2件はトークンがあるため即時送信
+        assert_eq!(
+            flow.offer(1, BroadcastPriority::Low, &config, start),
+            vec![1]
+        );
+        assert_eq!(
+            flow.offer(2, BroadcastPriority::Low, &config, start),
+            vec![2]
+        );
+
+        // トークン枯渇後はバッファへ（上限は2秒分の4件）
+        for i in 3..=7 {
+            assert!(flow
+                .offer(i, BroadcastPriority::Low, &config, start)
+                .is_empty());
+        }
+        // 間引き不可のメッセージは溢れても破棄されない
+        assert!(flow
+            .offer(100, BroadcastPriority::Normal, &config, start)
+            .is_empty());
+        assert!(flow.has_pending());
+        assert_eq!(flow.dropped(), 2);
+
+        // 1秒後に2件分補充され、古い順にまとめて送信される
+        let later = start + Duration::from_secs(1);
+        assert_eq!(flow.drain(&config, later), vec![5, 6]);
+
+        // 無効化すると残りは即時送信される
+        let disabled = FlowControlConfig {
+            enabled: false,
+            ..config
+        };
+        assert_eq!(
+            flow.offer(8, BroadcastPriority::Low, &disabled, later),
+            vec![7, 100, 8]
+        );
+        assert!(!flow.has_pending());
+    }
+
+    /// 負荷が高く通常チャットが滞留していても、スーパーチャットが先に確実に届くことを確認
+    #[test]
+    fn test_superchat_overtakes_backlog_under_load() {
+        let config = FlowControlConfig {
+            enabled: true,
+            max_messages_per_second: 10,
+            prioritize_superchats: true,
+        };
+        let start = Instant::now();
+        let mut flow = FlowController::new(&config, start);
+
+        // 1秒分のトークンを消費し尽くした後、大量の通常チャットとスーパーチャットが届く
+        let mut sent = Vec::new();
+        for i in 0..100 {
+            sent.extend(flow.offer(
+                format!("chat-{}", i),
+                BroadcastPriority::Low,
+                &config,
+                start,
+            ));
+        }
+        for i in 0..5 {
+            sent.extend(flow.offer(
+                format!("superchat-{}", i),
+                BroadcastPriority::High,
+                &config,
+                start,
+            ));
+        }
+        assert_eq!(sent.len(), 10);
+
+        // 次のトークン補充では、滞留している通常チャットより先にスーパーチャットが配信順に届く
+        let next = flow.drain(&config, start + Duration::from_millis(500));
+        assert_eq!(
+            next,
+            vec![
+                "superchat-0",
+                "superchat-1",
+                "superchat-2",
+                "superchat-3",
+                "superchat-4"
+            ]
+        );
+
+        // スーパーチャットは間引かれず、バッファ上限（20件）を超えた通常チャットのみが間引かれている
+        assert_eq!(flow.dropped(), 100 - 10 - (20 - 5));
+    }
+
+    /// デフォルトではフロー制御が無効で、通常チャットも間引かれないことを確認
+    #[test]
+    fn test_default_does_not_drop_low_priority() {
+        let config = FlowControlConfig::default();
+        assert!(!config.enabled);
+        assert!(!config.drops_low_priority());
+        assert!(FlowControlConfig {
+            enabled: true,
+            ..config
+        }
+        .drops_low_priority());
+    }
+}
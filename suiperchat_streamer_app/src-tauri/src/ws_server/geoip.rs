@@ -0,0 +1,286 @@
+//! 接続元の国・地域の判定モジュール
+//!
+//! どの地域の視聴者が多いかを把握できるよう、接続元IPをGeoIPサービスで調べて国名を取得します。
+//! 接続処理をブロックしないよう問い合わせはバックグラウンドで行い、取得できた時点で
+//! `ClientInfo` に反映します。プライベートIPやローカルホストは問い合わせずに "Local" とします。
+//! 視聴者のIPを外部サービスへ送信するため、問い合わせ先のHTTPSのURLを環境変数で指定した場合のみ有効になります。
+
+use super::connection_manager::ConnectionManager;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// GeoIPの問い合わせ先URLを指定する環境変数（`{ip}` を接続元IPに置き換える）
+///
+/// 未設定の場合は問い合わせを行いません。例: `https://ipwho.is/{ip}?fields=success,country`
+pub const GEOIP_URL_ENV: &str = "GEOIP_LOOKUP_URL";
+
+/// プライベートIP・ローカルホストからの接続に設定する国名
+pub const LOCAL_COUNTRY: &str = "Local";
+
+/// GeoIPの問い合わせのタイムアウト
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// キャッシュする問い合わせ結果の最大件数
+const COUNTRY_CACHE_CAPACITY: usize = 1024;
+
+/// 問い合わせ結果をキャッシュする期間
+const COUNTRY_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 問い合わせ結果のキャッシュ（無料エンドポイントのレート制限を避けるため、同じIPは再度問い合わせない）
+static COUNTRY_CACHE: Lazy<Mutex<CountryCache>> =
+    Lazy::new(|| Mutex::new(CountryCache::new(COUNTRY_CACHE_CAPACITY, COUNTRY_CACHE_TTL)));
+
+/// ## 件数と期間に上限のある国名のキャッシュ
+///
+/// 転送元ヘッダーを偽装した接続でメモリを使い切られないよう、上限に達した場合は
+/// 期限切れの項目、それでも足りない場合は最も古い項目から削除します。
+struct CountryCache {
+    /// IPアドレスごとの国名と取得時刻
+    entries: HashMap<IpAddr, (String, Instant)>,
+    /// 最大件数
+    capacity: usize,
+    /// 有効期間
+    ttl: Duration,
+}
+
+impl CountryCache {
+    /// 空のキャッシュを作成する
+    fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity,
+            ttl,
+        }
+    }
+
+    /// 有効期間内の国名を取得する
+    fn get(&self, ip: &IpAddr, now: Instant) -> Option<String> {
+        self.entries
+            .get(ip)
+            .filter(|(_, fetched_at)| now.duration_since(*fetched_at) < self.ttl)
+            .map(|(country, _)| country.clone())
+    }
+
+    /// 国名を追加する
+    fn insert(&mut self, ip: IpAddr, country: String, now: Instant) {
+        if !self.entries.contains_key(&ip) && self.entries.len() >= self.capacity {
+            let ttl = self.ttl;
+            self.entries
+                .retain(|_, (_, fetched_at)| now.duration_since(*fetched_at) < ttl);
+            if self.entries.len() >= self.capacity {
+                let oldest = self
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, (_, fetched_at))| *fetched_at)
+                    .map(|(ip, _)| *ip);
+                if let Some(oldest) = oldest {
+                    self.entries.remove(&oldest);
+                }
+            }
+        }
+        self.entries.insert(ip, (country, now));
+    }
+}
+
+/// ## GeoIPの問い合わせをスキップするIPアドレスか判定する
+///
+/// ### Arguments
+/// - `ip`: 接続元IPアドレス
+///
+/// ### Returns
+/// - `bool`: ローカルホスト・プライベートIP・リンクローカルアドレスなどの場合は `true`
+pub fn is_local_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                // CGNAT (100.64.0.0/10)
+                || (v4.octets()[0] == 100 && (v4.octets()[1] & 0xc0) == 64)
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_local_ip(IpAddr::V4(v4)),
+            None => {
+                let first_segment = v6.segments()[0];
+                v6.is_loopback()
+                    || v6.is_unspecified()
+                    // ユニークローカルアドレス (fc00::/7)
+                    || (first_segment & 0xfe00) == 0xfc00
+                    // リンクローカルアドレス (fe80::/10)
+                    || (first_segment & 0xffc0) == 0xfe80
+            }
+        },
+    }
+}
+
+/// ## GeoIPの問い合わせ先URLを取得する
+///
+/// 視聴者のIPを平文で送信しないよう、HTTPS以外のURLは使用しません。
+///
+/// ### Arguments
+/// - `ip`: 接続元IPアドレス
+///
+/// ### Returns
+/// - `Option<String>`: 環境変数 `GEOIP_LOOKUP_URL` の `{ip}` を置き換えたURL（未設定・HTTPS以外の場合はNone）
+pub fn lookup_url(ip: IpAddr) -> Option<String> {
+    let url = std::env::var(GEOIP_URL_ENV).ok()?;
+    let url = url.trim();
+    if url.is_empty() || !url.to_ascii_lowercase().starts_with("https://") {
+        return None;
+    }
+    Some(url.replace("{ip}", &ip.to_string()))
+}
+
+/// ## GeoIPの問い合わせが有効か判定する
+///
+/// ### Returns
+/// - `bool`: 環境変数 `GEOIP_LOOKUP_URL` にHTTPSのURLが設定されている場合は `true`
+pub fn is_lookup_enabled() -> bool {
+    lookup_url(IpAddr::from([0, 0, 0, 0])).is_some()
+}
+
+/// GeoIPサービスのレスポンスから国名を取り出す（ip-api.com形式の `status`・ipwho.is形式の `success` が失敗の場合はNone）
+fn parse_country(response: &Value) -> Option<String> {
+    if response
+        .get("status")
+        .and_then(Value::as_str)
+        .is_some_and(|status| status != "success")
+        || response.get("success").and_then(Value::as_bool) == Some(false)
+    {
+        return None;
+    }
+    response
+        .get("country")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|country| !country.is_empty())
+        .map(str::to_string)
+}
+
+/// ## 接続元IPの国名を取得する
+///
+/// ### Arguments
+/// - `ip`: 接続元IPアドレス
+///
+/// ### Returns
+/// - `Result<String, String>`: 国名（ローカルの場合は "Local"）、取得できない場合や問い合わせが無効な場合はエラーメッセージ
+pub async fn lookup_country(ip: IpAddr) -> Result<String, String> {
+    if is_local_ip(ip) {
+        return Ok(LOCAL_COUNTRY.to_string());
+    }
+    if let Some(country) = COUNTRY_CACHE
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(&ip, Instant::now()))
+    {
+        return Ok(country);
+    }
+    let url = lookup_url(ip).ok_or_else(|| "GeoIPの問い合わせは無効です".to_string())?;
+
+    let client = reqwest::Client::builder()
+        .timeout(LOOKUP_TIMEOUT)
+        .build()
+        .map_err(|e| format!("HTTPクライアントの構築に失敗しました: {}", e))?;
+    let response: Value = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("GeoIPの問い合わせに失敗しました: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("GeoIPのレスポンスの解析に失敗しました: {}", e))?;
+    let country = parse_country(&response)
+        .ok_or_else(|| format!("GeoIPのレスポンスに国名がありません: {}", response))?;
+
+    if let Ok(mut cache) = COUNTRY_CACHE.lock() {
+        cache.insert(ip, country.clone(), Instant::now());
+    }
+    Ok(country)
+}
+
+/// ## クライアントの国名をバックグラウンドで取得して反映する
+///
+/// 接続処理をブロックしないよう問い合わせは別タスクで行い、取得できた時点で
+/// `update_client` でクライアント情報に反映します。問い合わせが無効な場合は何もしません。
+///
+/// ### Arguments
+/// - `manager`: 接続マネージャー
+/// - `client_id`: 国名を反映するクライアントのID
+/// - `ip`: 接続元IPアドレス
+pub fn spawn_country_lookup(manager: ConnectionManager, client_id: String, ip: IpAddr) {
+    if is_local_ip(ip) {
+        manager.update_client(&client_id, |info| {
+            info.country = Some(LOCAL_COUNTRY.to_string());
+        });
+        return;
+    }
+    if !is_lookup_enabled() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        match lookup_country(ip).await {
+            Ok(country) => {
+                manager.update_client(&client_id, |info| info.country = Some(country));
+            }
+            Err(e) => eprintln!("接続元の国の取得に失敗しました ({}): {}", ip, e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_ip_and_parse_country() {
+        let is_local = |ip: &str| is_local_ip(ip.parse().unwrap());
+        assert!(is_local("127.0.0.1"));
+        assert!(is_local("192.168.1.10"));
+        assert!(is_local("10.0.0.1"));
+        assert!(is_local("::1"));
+        assert!(is_local("fd00::1"));
+        assert!(is_local("::ffff:172.16.0.1"));
+        assert!(!is_local("8.8.8.8"));
+        assert!(!is_local("2001:4860:4860::8888"));
+
+        let response = serde_json::json!({"status": "success", "country": "Japan"});
+        assert_eq!(parse_country(&response).as_deref(), Some("Japan"));
+        let response = serde_json::json!({"status": "fail", "message": "reserved range"});
+        assert_eq!(parse_country(&response), None);
+        // `status` のない独自エンドポイントのレスポンスも受け付ける
+        let response = serde_json::json!({"country": "Canada"});
+        assert_eq!(parse_country(&response).as_deref(), Some("Canada"));
+        let response = serde_json::json!({"success": false, "message": "Invalid IP address"});
+        assert_eq!(parse_country(&response), None);
+    }
+
+    /// 国名キャッシュの件数・期間の上限のテスト
+    #[test]
+    fn test_country_cache() {
+        let ip = |last: u8| IpAddr::from([203, 0, 113, last]);
+        let now = Instant::now();
+        let mut cache = CountryCache::new(2, Duration::from_secs(60));
+
+        cache.insert(ip(1), "Japan".to_string(), now);
+        cache.insert(ip(2), "Canada".to_string(), now + Duration::from_secs(1));
+        assert_eq!(cache.get(&ip(1), now).as_deref(), Some("Japan"));
+
+        // 上限に達した場合は最も古い項目を削除する
+        cache.insert(ip(3), "France".to_string(), now + Duration::from_secs(2));
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(cache.get(&ip(1), now + Duration::from_secs(2)), None);
+        assert_eq!(
+            cache.get(&ip(3), now + Duration::from_secs(2)).as_deref(),
+            Some("France")
+        );
+
+        // 有効期間を過ぎた項目は返さない
+        assert_eq!(cache.get(&ip(3), now + Duration::from_secs(62)), None);
+    }
+}
@@ -0,0 +1,139 @@
+//! 人間検証（proof-of-work）モジュール
+//!
+//! ボットによる自動投稿を抑止するため、接続クライアントに計算負荷のあるチャレンジを解かせます。
+//! クライアントは `SHA-256(challenge + nonce)` の先頭 `difficulty` ビットが0になる `nonce` を探索して返します。
+//! 難易度を1上げるごとに、クライアントの平均計算量は2倍になります。
+
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// デフォルトの難易度（先頭の0ビット数）
+pub const DEFAULT_POW_DIFFICULTY: u8 = 16;
+
+/// 設定可能な最大の難易度（視聴者の端末で現実的な時間で解ける範囲）
+pub const MAX_POW_DIFFICULTY: u8 = 24;
+
+/// チャレンジで使用するハッシュアルゴリズム名（クライアントへの通知用）
+pub const POW_ALGORITHM: &str = "sha256";
+
+/// 検証完了まで保留できるメッセージの最大数
+pub const MAX_UNVERIFIED_PENDING_MESSAGES: usize = 10;
+
+/// ## 人間検証の設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HumanVerificationConfig {
+    /// メッセージ送信前に人間検証を要求するかどうか
+    pub enabled: bool,
+    /// proof-of-workの難易度（先頭の0ビット数）
+    pub difficulty: u8,
+}
+
+impl Default for HumanVerificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            difficulty: DEFAULT_POW_DIFFICULTY,
+        }
+    }
+}
+
+impl HumanVerificationConfig {
+    /// ## アプリケーション状態から現在の設定を取得する
+    ///
+    /// ### Arguments
+    /// - `app_state`: アプリケーション状態
+    ///
+    /// ### Returns
+    /// - `Self`: 現在の設定（ロックに失敗した項目はデフォルト値）
+    pub fn from_app_state(app_state: &AppState) -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: app_state
+                .require_human_verification
+                .lock()
+                .map_or(defaults.enabled, |enabled| *enabled),
+            difficulty: app_state
+                .human_verification_difficulty
+                .lock()
+                .map_or(defaults.difficulty, |difficulty| *difficulty),
+        }
+    }
+}
+
+/// ## proof-of-workのチャレンジ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PowChallenge {
+    /// クライアントに送信するランダムな文字列
+    pub challenge: String,
+    /// 要求する先頭の0ビット数
+    pub difficulty: u8,
+}
+
+impl PowChallenge {
+    /// ## 新しいチャレンジを作成する
+    ///
+    /// ### Arguments
+    /// - `difficulty`: 要求する先頭の0ビット数
+    pub fn new(difficulty: u8) -> Self {
+        Self {
+            challenge: Uuid::new_v4().simple().to_string(),
+            difficulty: difficulty.min(MAX_POW_DIFFICULTY),
+        }
+    }
+
+    /// ## クライアントの解答を検証する
+    ///
+    /// ### Arguments
+    /// - `challenge`: クライアントが解いたチャレンジ
+    /// - `nonce`: クライアントが見つけた値
+    ///
+    /// ### Returns
+    /// - `bool`: チャレンジが一致し、ハッシュの先頭が指定ビット数以上0の場合は `true`
+    pub fn verify(&self, challenge: &str, nonce: &str) -> bool {
+        if challenge != self.challenge {
+            return false;
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.challenge.as_bytes());
+        hasher.update(nonce.as_bytes());
+        leading_zero_bits(&hasher.finalize()) >= u32::from(self.difficulty)
+    }
+}
+
+/// ハッシュ値の先頭の0ビット数を数える
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// クライアントと同じ方法で探索した解答が受理され、誤った解答が拒否されることを確認
+    #[test]
+    fn test_pow_challenge_verification() {
+        let challenge = PowChallenge::new(8);
+        let nonce = (0u64..)
+            .map(|n| n.to_string())
+            .find(|nonce| challenge.verify(&challenge.challenge, nonce))
+            .unwrap();
+
+        // 別のチャレンジへの解答は受理されない
+        assert!(!challenge.verify("other-challenge", &nonce));
+        // 難易度の上限を超える要求は上限に丸められる
+        assert_eq!(PowChallenge::new(64).difficulty, MAX_POW_DIFFICULTY);
+
+        assert_eq!(leading_zero_bits(&[0x00, 0x0f, 0xff]), 12);
+        assert_eq!(leading_zero_bits(&[0x80]), 0);
+    }
+}
@@ -0,0 +1,232 @@
+//! アイドル接続の検出・自動切断モジュール
+//!
+//! 長時間活動のない接続が接続枠を占有し続けるのを防ぐため、定期的に各クライアントの
+//! 最終アクティブ時刻を確認し、設定した時間を超えた接続をサーバー側から切断します。
+//!
+//! ハートビートのpong応答は生存確認であり、閲覧専用の視聴者も応答し続けます。
+//! そのため対象を「メッセージを送信していない接続」と「pongも含め完全に無応答の接続」から選べます。
+//! アイドル切断はクライアントに理由を通知した上で正常終了のクローズコードで切断し、再接続は制限しません。
+
+use crate::state::AppState;
+use crate::ws_server::client_info::ClientInfo;
+use crate::ws_server::connection_manager::global;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+/// アイドル判定の実行間隔
+pub const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 設定可能なアイドル時間の上限（秒）
+pub const MAX_IDLE_TIMEOUT_SECS: u64 = 24 * 60 * 60;
+
+/// 設定可能なアイドル時間の下限（秒）
+///
+/// アイドル判定の実行間隔（30秒）とハートビートの反映間隔より短いと、
+/// 応答している視聴者まで切断してしまうため、余裕を持たせた値にします。
+pub const MIN_IDLE_TIMEOUT_SECS: u64 = 60;
+
+/// アイドル切断を通知するTauriイベント名（モデレーションによる切断とは別に記録する）
+pub const IDLE_DISCONNECT_EVENT: &str = "client_idle_disconnected";
+
+/// 監視タスクが起動済みかどうか（多重起動防止用）
+static IDLE_MONITOR_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// ## アイドル判定の対象
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdleTarget {
+    /// pong応答も含め、一定時間まったく応答のない接続のみ
+    #[default]
+    Unresponsive,
+    /// 一定時間メッセージを送信していない接続（閲覧専用の視聴者を含む）
+    NoMessages,
+}
+
+/// ## アイドル切断の設定
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdleDisconnectConfig {
+    /// アイドルとみなすまでの時間（秒）。0の場合はアイドル切断を行わない
+    pub timeout_secs: u64,
+    /// アイドル判定の対象
+    pub target: IdleTarget,
+}
+
+impl IdleDisconnectConfig {
+    /// ## アイドル時間を検証する
+    ///
+    /// ### Arguments
+    /// - `timeout_secs`: アイドルとみなすまでの時間（秒、0の場合は無効）
+    ///
+    /// ### Returns
+    /// - `Result<(), String>`: 範囲外の場合はエラーメッセージ
+    pub fn validate_timeout_secs(timeout_secs: u64) -> Result<(), String> {
+        if timeout_secs != 0
+            && !(MIN_IDLE_TIMEOUT_SECS..=MAX_IDLE_TIMEOUT_SECS).contains(&timeout_secs)
+        {
+            return Err(format!(
+                "アイドル時間は{}〜{}秒で指定してください（0の場合は無効）",
+                MIN_IDLE_TIMEOUT_SECS, MAX_IDLE_TIMEOUT_SECS
+            ));
+        }
+        Ok(())
+    }
+
+    /// ## アイドル切断が有効かどうか
+    pub fn is_enabled(&self) -> bool {
+        self.timeout_secs > 0
+    }
+
+    /// ## クライアントがアイドル状態か判定する
+    ///
+    /// ### Arguments
+    /// - `client`: 判定するクライアント
+    /// - `now`: 現在時刻
+    ///
+    /// ### Returns
+    /// - `Option<u64>`: アイドル状態の場合は最後の活動からの経過秒数、それ以外はNone
+    pub fn idle_secs(
+        &self,
+        client: &ClientInfo,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Option<u64> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let last_active = parse_timestamp(&client.last_active)?;
+        let last_seen = match self.target {
+            IdleTarget::NoMessages => last_active,
+            IdleTarget::Unresponsive => parse_timestamp(&client.last_heartbeat)
+                .map_or(last_active, |heartbeat| heartbeat.max(last_active)),
+        };
+
+        let idle_secs = u64::try_from((now - last_seen).num_seconds()).ok()?;
+        (idle_secs >= self.timeout_secs).then_some(idle_secs)
+    }
+}
+
+/// ## アイドル切断イベントのペイロード
+#[derive(Debug, Clone, Serialize)]
+pub struct IdleDisconnectPayload {
+    /// 切断したクライアントのID
+    pub client_id: String,
+    /// 切断したクライアントのIPアドレス
+    pub ip: String,
+    /// 最後の活動からの経過秒数
+    pub idle_secs: u64,
+    /// アイドル判定の対象
+    pub target: IdleTarget,
+}
+
+/// ISO8601形式の時刻を解析する
+fn parse_timestamp(value: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|time| time.with_timezone(&chrono::Utc))
+}
+
+/// タスク終了時（ランタイム停止によるキャンセルを含む）に起動フラグを戻すガード
+struct RunningGuard;
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        IDLE_MONITOR_RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// ## アイドル接続の監視タスクを起動する
+///
+/// WebSocketサーバーが停止するまで `IDLE_CHECK_INTERVAL` ごとにアイドル接続を切断します。
+/// アイドル切断が無効の間は何もしません。既にタスクが起動している場合は何もしません。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+pub fn spawn_idle_monitor(app_handle: tauri::AppHandle) {
+    if IDLE_MONITOR_RUNNING
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        println!("アイドル接続の監視は既に起動しています");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let _guard = RunningGuard;
+
+        loop {
+            tokio::time::sleep(IDLE_CHECK_INTERVAL).await;
+
+            let server_running = app_handle
+                .state::<AppState>()
+                .server_handle
+                .lock()
+                .map(|guard| guard.is_some())
+                .unwrap_or(false);
+            if !server_running {
+                break;
+            }
+
+            let manager = global::get_manager();
+            let config = manager.idle_disconnect_config();
+            for (client, idle_secs) in manager.disconnect_idle_clients(chrono::Utc::now()) {
+                println!(
+                    "[アイドル切断] クライアント {} ({}) を切断しました (最終活動から{}秒, 対象: {:?})",
+                    client.id, client.ip, idle_secs, config.target
+                );
+                let payload = IdleDisconnectPayload {
+                    client_id: client.id,
+                    ip: client.ip,
+                    idle_secs,
+                    target: config.target,
+                };
+                if let Err(e) = app_handle.emit(IDLE_DISCONNECT_EVENT, payload) {
+                    eprintln!("アイドル切断イベントの発行に失敗しました: {}", e);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+
+    /// 判定対象によってpong応答の扱いが変わることを確認
+    #[test]
+    fn test_idle_secs_by_target() {
+        let now = chrono::Utc::now();
+        let mut client = ClientInfo::new("127.0.0.1:12345".parse::<SocketAddr>().unwrap());
+        client.last_active = (now - chrono::Duration::seconds(600)).to_rfc3339();
+        client.last_heartbeat = (now - chrono::Duration::seconds(5)).to_rfc3339();
+
+        let no_messages = IdleDisconnectConfig {
+            timeout_secs: 300,
+            target: IdleTarget::NoMessages,
+        };
+        assert_eq!(no_messages.idle_secs(&client, now), Some(600));
+
+        let unresponsive = IdleDisconnectConfig {
+            timeout_secs: 300,
+            target: IdleTarget::Unresponsive,
+        };
+        assert_eq!(unresponsive.idle_secs(&client, now), None);
+
+        let disabled = IdleDisconnectConfig {
+            timeout_secs: 0,
+            target: IdleTarget::NoMessages,
+        };
+        assert_eq!(disabled.idle_secs(&client, now), None);
+    }
+
+    /// ハートビートの確認間隔より短いアイドル時間を拒否することを確認
+    #[test]
+    fn test_validate_timeout_secs() {
+        assert!(IdleDisconnectConfig::validate_timeout_secs(0).is_ok());
+        assert!(IdleDisconnectConfig::validate_timeout_secs(10).is_err());
+        assert!(IdleDisconnectConfig::validate_timeout_secs(MIN_IDLE_TIMEOUT_SECS).is_ok());
+        assert!(IdleDisconnectConfig::validate_timeout_secs(MAX_IDLE_TIMEOUT_SECS + 1).is_err());
+    }
+}
@@ -1,5 +1,3 @@
-use std::{net::IpAddr, str::FromStr};
-use tauri::AppHandle;
 /**
  * 外部IPアドレス取得ユーティリティ
  *
@@ -8,17 +6,24 @@ use tauri::AppHandle;
  * エンドポイントはカンマ区切りで指定され、JSONレスポンスが期待されます。
  *
  * # 機能
- * - 複数の外部IP取得サービスをフォールバックとして使用
+ * - 複数の外部IP取得サービスへ並列に問い合わせ、最も速く成功した結果を使用
  * - タイムアウト付きの非同期HTTPリクエスト
  * - エラーハンドリングとログ記録
  * - CGNAT (Carrier-grade NAT) 検出機能
  */
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use std::{
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+};
+use tauri::AppHandle;
 use tauri_plugin_http::reqwest; // re-exported reqwest
 use tracing::{debug, error, info, warn};
 
 /// 外部IPアドレスを取得する
 ///
 /// 環境変数 EXTERNAL_IP_ENDPOINTS に設定されたエンドポイントから外部IPアドレスを取得します。
+/// 全エンドポイントに同時に問い合わせ、最初に成功した結果を使用します。
 /// デフォルトでは "https://api.ipify.org?format=json,https://ifconfig.me/all.json" を使用します。
 ///
 /// # 引数
@@ -44,51 +49,18 @@ pub async fn get_external_ip(_app: &AppHandle) -> Result<IpAddr, String> {
 
     debug!("使用する外部IP取得エンドポイント: {}", endpoints);
 
-    // 各エンドポイントに順番にリクエストを送信
-    for url in endpoints.split(',') {
-        info!("外部IP取得を試行中: {}", url);
-
-        match client.get(url).send().await {
-            Ok(response) => {
-                // レスポンスをテキストとして取得してJSONに変換
-                match response.text().await {
-                    Ok(text) => {
-                        match serde_json::from_str::<serde_json::Value>(&text) {
-                            Ok(json_value) => {
-                                // IPアドレスフィールドを探索
-                                if let Some(ip_str) = json_value.get("ip").and_then(|v| v.as_str())
-                                {
-                                    // IPアドレスを解析
-                                    match IpAddr::from_str(ip_str) {
-                                        Ok(ip) => {
-                                            info!("外部IPアドレスの取得に成功: {}", ip);
-                                            return Ok(ip);
-                                        }
-                                        Err(e) => {
-                                            error!("IPアドレスの解析に失敗: {} - {}", ip_str, e);
-                                            continue;
-                                        }
-                                    }
-                                } else {
-                                    error!(
-                                        "JSONレスポンスにIPフィールドがありません: {:?}",
-                                        json_value
-                                    );
-                                }
-                            }
-                            Err(e) => {
-                                error!("JSONのパースに失敗: {} - {}", url, e);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("レスポンステキストの取得に失敗: {} - {}", url, e);
-                    }
-                }
-            }
-            Err(e) => {
-                error!("エンドポイントへのリクエストに失敗: {} - {}", url, e);
-            }
+    // 全エンドポイントに同時にリクエストを送信し、最初に成功した結果を採用する
+    let mut requests: FuturesUnordered<_> = endpoints
+        .split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(|url| fetch_ip_from_endpoint(&client, url))
+        .collect();
+
+    while let Some(result) = requests.next().await {
+        if let Ok(ip) = result {
+            info!("外部IPアドレスの取得に成功: {}", ip);
+            return Ok(ip);
         }
     }
 
@@ -98,10 +70,61 @@ pub async fn get_external_ip(_app: &AppHandle) -> Result<IpAddr, String> {
     Err(error_msg)
 }
 
+/// 1つのエンドポイントから外部IPアドレスを取得する
+///
+/// レスポンスのJSONの `ip` フィールドを外部IPアドレスとして解析します。
+///
+/// # 引数
+/// * `client` - HTTPクライアント
+/// * `url` - 外部IP取得エンドポイントのURL
+///
+/// # 戻り値
+/// * `Result<IpAddr, String>` - 成功した場合は外部IPアドレス、失敗した場合はエラーメッセージ
+async fn fetch_ip_from_endpoint(client: &reqwest::Client, url: &str) -> Result<IpAddr, String> {
+    info!("外部IP取得を試行中: {}", url);
+
+    let response = client.get(url).send().await.map_err(|e| {
+        let error_msg = format!("エンドポイントへのリクエストに失敗: {} - {}", url, e);
+        error!("{}", error_msg);
+        error_msg
+    })?;
+
+    // レスポンスをテキストとして取得してJSONに変換
+    let text = response.text().await.map_err(|e| {
+        let error_msg = format!("レスポンステキストの取得に失敗: {} - {}", url, e);
+        error!("{}", error_msg);
+        error_msg
+    })?;
+    let json_value = serde_json::from_str::<serde_json::Value>(&text).map_err(|e| {
+        let error_msg = format!("JSONのパースに失敗: {} - {}", url, e);
+        error!("{}", error_msg);
+        error_msg
+    })?;
+
+    // IPアドレスフィールドを探索
+    let Some(ip_str) = json_value.get("ip").and_then(|v| v.as_str()) else {
+        let error_msg = format!("JSONレスポンスにIPフィールドがありません: {:?}", json_value);
+        error!("{}", error_msg);
+        return Err(error_msg);
+    };
+
+    // IPアドレスを解析
+    IpAddr::from_str(ip_str).map_err(|e| {
+        let error_msg = format!("IPアドレスの解析に失敗: {} - {}", ip_str, e);
+        error!("{}", error_msg);
+        error_msg
+    })
+}
+
+/// CGNAT判定に使用するSTUNサーバー
+const STUN_SERVER: &str = "stun.l.google.com:19302";
+
 /// CGNAT (Carrier-grade NAT) または二重NATを検出する
 ///
 /// STUNサーバーに問い合わせを行い、取得した外部IPアドレスが渡されたものと一致するか検証します。
 /// 一致しない場合、CGNAT環境と判断されます。
+/// 渡されたアドレスがIPv6の場合は、STUNクライアントをIPv6でバインドしてIPv6の
+/// XOR-MAPPED-ADDRESSを取得し、同じアドレスファミリ同士で比較します。
 ///
 /// # 引数
 /// * `public_ip` - 他の方法で取得した外部IPアドレス
@@ -110,19 +133,40 @@ pub async fn get_external_ip(_app: &AppHandle) -> Result<IpAddr, String> {
 /// * `Result<bool, String>` - 成功した場合、CGNATが検出されたかどうかを示すbool値
 ///   - `Ok(true)` - CGNATが検出された
 ///   - `Ok(false)` - CGNATは検出されなかった
-///   - `Err(message)` - STUNクエリが失敗した場合のエラーメッセージ
+///   - `Err(message)` - STUNクエリが失敗した場合、またはアドレスファミリが異なり判定できない場合のエラーメッセージ
 pub async fn check_cgnat(public_ip: IpAddr) -> Result<bool, String> {
     info!(
         "CGNATの検出を開始します。API経由で取得したIP: {}",
         public_ip
     );
 
-    // Google STUNサーバーにクエリを送信
-    let stun_server = "stun.l.google.com:19302";
+    let stun_ip = match public_ip {
+        // Google STUNサーバーにクエリを送信 (任意のローカルIPv4アドレスにバインド)
+        IpAddr::V4(_) => query_stun_ip("0.0.0.0:0", STUN_SERVER).await?,
+        // STUNサーバーのIPv6アドレスに対して、任意のローカルIPv6アドレスからクエリを送信
+        IpAddr::V6(_) => {
+            let stun_addr = resolve_ipv6_stun_server(STUN_SERVER).await?;
+            query_stun_ip("[::]:0", &stun_addr.to_string()).await?
+        }
+    };
+    info!("STUN経由で取得したIP: {}", stun_ip);
+
+    compare_stun_ip(public_ip, stun_ip)
+}
+
+/// STUNサーバーに問い合わせて、XOR-MAPPED-ADDRESSのIPアドレスを取得する
+///
+/// # 引数
+/// * `bind_addr` - STUNクライアントをバインドするローカルアドレス
+/// * `stun_server` - 問い合わせ先のSTUNサーバー
+///
+/// # 戻り値
+/// * `Result<IpAddr, String>` - 成功した場合はSTUN経由で取得したIPアドレス、失敗した場合はエラーメッセージ
+async fn query_stun_ip(bind_addr: &str, stun_server: &str) -> Result<IpAddr, String> {
     info!("STUNサーバーにクエリを送信します: {}", stun_server);
 
-    // STUNクライアントを作成 (任意のローカルIPにバインド)
-    let mut client = match stun_client::Client::new("0.0.0.0:0", None).await {
+    // STUNクライアントを作成
+    let mut client = match stun_client::Client::new(bind_addr, None).await {
         Ok(client) => client,
         Err(e) => {
             let error_msg = format!("STUNクライアントの作成に失敗しました: {}", e);
@@ -137,19 +181,7 @@ pub async fn check_cgnat(public_ip: IpAddr) -> Result<bool, String> {
             if response.get_class() == stun_client::Class::SuccessResponse {
                 // XOR-MAPPED-ADDRESSを取得
                 match stun_client::Attribute::get_xor_mapped_address(&response) {
-                    Some(stun_addr) => {
-                        let stun_ip = stun_addr.ip();
-                        info!("STUN経由で取得したIP: {}", stun_ip);
-
-                        // IPアドレスが一致するか検証
-                        if stun_ip == public_ip {
-                            info!("CGNAT検出: 両方のIPが一致しています。CGNATは検出されませんでした。");
-                            Ok(false) // CGNATなし
-                        } else {
-                            warn!("CGNAT検出: IPアドレスの不一致。CGNAT環境と判断します。API: {}, STUN: {}", public_ip, stun_ip);
-                            Ok(true) // CGNAT検出
-                        }
-                    }
+                    Some(stun_addr) => Ok(stun_addr.ip()),
                     None => {
                         let error_msg =
                             "STUNレスポンスにXOR-MAPPED-ADDRESSが含まれていません".to_string();
@@ -175,10 +207,68 @@ pub async fn check_cgnat(public_ip: IpAddr) -> Result<bool, String> {
     }
 }
 
+/// STUNサーバーのIPv6アドレスを解決する
+///
+/// # 引数
+/// * `stun_server` - STUNサーバーのホスト名とポート
+///
+/// # 戻り値
+/// * `Result<SocketAddr, String>` - 成功した場合はSTUNサーバーのIPv6ソケットアドレス、失敗した場合はエラーメッセージ
+async fn resolve_ipv6_stun_server(stun_server: &str) -> Result<SocketAddr, String> {
+    let mut addrs = tokio::net::lookup_host(stun_server).await.map_err(|e| {
+        let error_msg = format!(
+            "STUNサーバーの名前解決に失敗しました: {} - {}",
+            stun_server, e
+        );
+        error!("{}", error_msg);
+        error_msg
+    })?;
+
+    addrs.find(SocketAddr::is_ipv6).ok_or_else(|| {
+        let error_msg = format!(
+            "STUNサーバーのIPv6アドレスが見つかりません: {}",
+            stun_server
+        );
+        error!("{}", error_msg);
+        error_msg
+    })
+}
+
+/// API経由で取得したIPとSTUN経由で取得したIPを比較し、CGNATかどうかを判定する
+///
+/// # 引数
+/// * `public_ip` - API経由で取得した外部IPアドレス
+/// * `stun_ip` - STUN経由で取得した外部IPアドレス
+///
+/// # 戻り値
+/// * `Result<bool, String>` - CGNATが検出された場合は `Ok(true)`、アドレスファミリが異なる場合はエラーメッセージ
+fn compare_stun_ip(public_ip: IpAddr, stun_ip: IpAddr) -> Result<bool, String> {
+    if public_ip.is_ipv4() != stun_ip.is_ipv4() {
+        let error_msg = format!(
+            "アドレスファミリが異なるためCGNATを判定できません。API: {}, STUN: {}",
+            public_ip, stun_ip
+        );
+        warn!("{}", error_msg);
+        return Err(error_msg);
+    }
+
+    // IPアドレスが一致するか検証
+    if stun_ip == public_ip {
+        info!("CGNAT検出: 両方のIPが一致しています。CGNATは検出されませんでした。");
+        Ok(false) // CGNATなし
+    } else {
+        warn!(
+            "CGNAT検出: IPアドレスの不一致。CGNAT環境と判断します。API: {}, STUN: {}",
+            public_ip, stun_ip
+        );
+        Ok(true) // CGNAT検出
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::net::{IpAddr, Ipv4Addr};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
     #[test]
     fn test_ip_from_str() {
@@ -223,6 +313,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_compare_stun_ip_address_family() {
+        let v4 = IpAddr::V4(Ipv4Addr::new(203, 0, 113, 1));
+        let v6 = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+        let v6_other = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2));
+
+        assert_eq!(compare_stun_ip(v6, v6), Ok(false));
+        assert_eq!(compare_stun_ip(v6, v6_other), Ok(true));
+        // アドレスファミリが異なる場合は判定不能
+        assert!(compare_stun_ip(v4, v6).is_err());
+        assert!(compare_stun_ip(v6, v4).is_err());
+    }
+
     // テスト用のモックヘルパー関数 - 実際のSTUNクライアントの代わりに使用
     async fn check_cgnat_with_mock(public_ip: IpAddr, stun_ip: IpAddr) -> Result<bool, String> {
         // STUN応答をシミュレート
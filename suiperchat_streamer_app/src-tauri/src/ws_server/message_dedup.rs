@@ -0,0 +1,98 @@
+//! 受信メッセージの重複排除モジュール
+//!
+//! 視聴者がネットワーク再送で同じメッセージを二重送信した場合に、同じコメントが
+//! 重複して保存・表示されないよう、直近に処理したメッセージIDを保持します。
+//! 再接続後の再送にも対応するため、接続ごとではなく接続マネージャー全体で共有します。
+
+use std::collections::{HashSet, VecDeque};
+
+/// 保持する処理済みメッセージIDの最大数
+pub const MAX_RECENT_MESSAGE_IDS: usize = 1000;
+
+/// ## 直近に処理したメッセージID
+///
+/// 上限を超えた場合は最も古く処理したIDから破棄します。
+#[derive(Debug)]
+pub struct RecentMessageIds {
+    /// 保持する最大数
+    capacity: usize,
+    /// 処理したID（古い順）
+    order: VecDeque<String>,
+    /// 重複判定用のIDの集合
+    ids: HashSet<String>,
+}
+
+impl Default for RecentMessageIds {
+    fn default() -> Self {
+        Self::with_capacity(MAX_RECENT_MESSAGE_IDS)
+    }
+}
+
+impl RecentMessageIds {
+    /// ## 保持する最大数を指定して作成する
+    ///
+    /// ### Arguments
+    /// - `capacity`: 保持する最大数
+    ///
+    /// ### Returns
+    /// - `Self`: 空の処理済みメッセージID
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            order: VecDeque::with_capacity(capacity),
+            ids: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// ## メッセージIDが処理済みか確認する
+    ///
+    /// ### Arguments
+    /// - `id`: メッセージID
+    ///
+    /// ### Returns
+    /// - `bool`: 処理済みのIDの場合は `true`
+    pub fn contains(&self, id: &str) -> bool {
+        self.ids.contains(id)
+    }
+
+    /// ## メッセージIDを処理済みとして記録する
+    ///
+    /// ### Arguments
+    /// - `id`: メッセージID
+    ///
+    /// ### Returns
+    /// - `bool`: 初めて処理するIDの場合は `true`、処理済みのIDの場合は `false`
+    pub fn insert(&mut self, id: &str) -> bool {
+        if self.ids.contains(id) {
+            return false;
+        }
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        self.order.push_back(id.to_string());
+        self.ids.insert(id.to_string());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_message_ids() {
+        let mut recent = RecentMessageIds::with_capacity(2);
+        assert!(!recent.contains("a"));
+        assert!(recent.insert("a"));
+        assert!(recent.contains("a"));
+        assert!(!recent.insert("a"));
+        assert!(recent.insert("b"));
+
+        // 上限を超えると最も古いIDから破棄される
+        assert!(recent.insert("c"));
+        assert!(recent.insert("a"));
+        assert!(!recent.insert("c"));
+    }
+}
@@ -4,23 +4,56 @@
 //! クライアント接続管理、セッション処理、メッセージハンドリングなどの機能を含みます。
 
 // サブモジュールの宣言
+pub mod access_token;
 pub mod client_info;
+pub mod compression;
 pub mod connection_manager;
+pub mod connection_urls;
+pub mod event_logger;
+pub mod flow_control;
+pub mod geoip;
+pub mod human_verification;
+pub mod idle_monitor;
 pub mod ip_utils;
+pub mod message_dedup;
+pub mod msgpack;
+pub mod network_type;
+pub mod port_recovery;
+pub mod protobuf;
+pub mod rate_limit;
+pub mod replay_cache;
 pub mod routes;
 pub mod server_manager;
 pub mod server_utils;
 pub mod session;
+pub mod tls;
 pub mod tunnel;
+pub mod tx_verification;
+pub mod upnp;
+pub mod viewer_count_history;
 
 // 型の再エクスポート
 pub use client_info::ClientInfo;
 pub use connection_manager::global::{
-    disconnect_client, get_connections_info, get_manager, set_app_handle, set_max_connections,
+    assign_client_group, block_client_ip, disconnect_client, get_blocked_ips, get_client_groups,
+    get_connection_stats, get_connections_info, get_connections_paginated, get_flow_control,
+    get_group_connection_counts, get_idle_disconnect, get_manager, get_message_rate_limit,
+    reset_delivery_stats, send_group_system_message, send_system_message, set_app_handle,
+    set_flow_control, set_idle_disconnect, set_max_connections, set_message_rate_limit,
+    set_per_wallet_limit, unassign_client_group, unblock_client_ip,
 };
-pub use routes::{obs_index_page, obs_script, obs_styles, status_page, websocket_route};
-pub use server_manager::{start_server, stop_server};
+pub use connection_urls::{ConnectionCandidate, ConnectionUrls};
+pub use flow_control::FlowControlConfig;
+pub use human_verification::HumanVerificationConfig;
+pub use rate_limit::MessageRateLimit;
+pub use routes::{
+    obs_index_page, obs_layout_css, obs_script, obs_styles, obs_websocket_route, server_info,
+    status_page, websocket_route,
+};
+pub use server_manager::{graceful_restart, start_server, stop_server};
 pub use server_utils::{format_socket_addr, resolve_static_file_path};
-pub use session::create_ws_session;
+pub use session::{create_obs_ws_session, create_ws_session};
 // ConnectionsInfoはtypes.rsから再エクスポート
-pub use crate::types::ConnectionsInfo;
+pub use crate::types::{
+    ConnectionMethodBreakdown, ConnectionStats, ConnectionsInfo, PaginatedConnectionsInfo,
+};
@@ -0,0 +1,84 @@
+//! MessagePackによるメッセージ送受信モジュール
+//!
+//! 帯域を節約したいクライアント向けに、WebSocketのサブプロトコル `suiperchat.msgpack` を
+//! 指定して接続した場合はブロードキャストをMessagePackのBinaryフレームとして送信します。
+//! 受信したBinaryフレームはMessagePackとして解釈し、JSONテキストと同様に処理します。
+//! MessagePackのマップはJSONと同じフィールド名を使用します。
+
+use crate::types::ClientMessage;
+use actix_web::web::Bytes;
+
+/// MessagePackモードを選択するWebSocketサブプロトコル名
+pub const MSGPACK_SUBPROTOCOL: &str = "suiperchat.msgpack";
+
+/// ## JSONテキストをMessagePackに変換する
+///
+/// ブロードキャストのJSONテキストを、同じ構造のMessagePackのマップに変換します。
+///
+/// ### Arguments
+/// - `json`: 変換するJSONテキスト
+///
+/// ### Returns
+/// - `Result<Bytes, String>`: MessagePackのバイト列、変換に失敗した場合はエラーメッセージ
+pub fn encode_json(json: &str) -> Result<Bytes, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("JSONの解析に失敗しました: {}", e))?;
+    rmp_serde::to_vec_named(&value)
+        .map(Bytes::from)
+        .map_err(|e| format!("MessagePackへの変換に失敗しました: {}", e))
+}
+
+/// ## MessagePackのバイト列をクライアントメッセージに変換する
+///
+/// ### Arguments
+/// - `bin`: 受信したBinaryフレームのバイト列
+///
+/// ### Returns
+/// - `Result<ClientMessage, String>`: クライアントメッセージ、変換に失敗した場合はエラーメッセージ
+pub fn decode_client_message(bin: &[u8]) -> Result<ClientMessage, String> {
+    rmp_serde::from_slice(bin).map_err(|e| format!("MessagePackの解析に失敗しました: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_msgpack_round_trip() {
+        let chat = serde_json::json!({
+            "type": "chat",
+            "id": "chat-1",
+            "display_name": "viewer",
+            "message": "こんにちは",
+        });
+        let bin = encode_json(&chat.to_string()).unwrap();
+        assert!(bin.len() < chat.to_string().len());
+
+        match decode_client_message(&bin).unwrap() {
+            ClientMessage::Chat(msg) => assert_eq!(msg.content, "こんにちは"),
+            other => panic!("チャットとして解析されませんでした: {:?}", other),
+        }
+        assert!(decode_client_message(b"not msgpack").is_err());
+    }
+
+    /// ブロードキャストのMessagePack版を一度だけ変換して使い回すことのテスト
+    #[test]
+    fn test_broadcast_msgpack_frame() {
+        use crate::ws_server::protobuf::BroadcastEncoding;
+        use crate::ws_server::session::{Broadcast, BroadcastFrame};
+
+        let json = serde_json::json!({"type": "chat", "message": "hi"}).to_string();
+        let broadcast = Broadcast::text(json.clone()).with_msgpack();
+        let encoded = broadcast.msgpack.clone().unwrap();
+        assert_eq!(encoded, encode_json(&json).unwrap());
+
+        match broadcast.clone().into_frame(BroadcastEncoding::MessagePack) {
+            BroadcastFrame::Binary(bin) => assert_eq!(bin, encoded),
+            BroadcastFrame::Text(_) => panic!("MessagePackで送信されませんでした"),
+        }
+        match broadcast.into_frame(BroadcastEncoding::Json) {
+            BroadcastFrame::Text(text) => assert_eq!(text, json),
+            BroadcastFrame::Binary(_) => panic!("JSONテキストで送信されませんでした"),
+        }
+    }
+}
@@ -0,0 +1,393 @@
+//! 接続元ネットワーク種別の推定モジュール
+//!
+//! 接続元IPのASN（自律システム番号）をMaxMindのASNデータベース（GeoLite2-ASN CSV形式）で調べ、
+//! 既知のモバイルキャリアと照合してモバイル回線か固定回線かを推定します。
+//! データベース未読み込み・プライベートIP・未登録のIPなど、判定できない場合は `Unknown` とします。
+
+use crate::ws_server::tunnel::TunnelKind;
+use actix_web::HttpRequest;
+use once_cell::sync::Lazy;
+use std::net::IpAddr;
+use std::sync::RwLock;
+
+/// 既知のモバイルキャリアのASN
+const MOBILE_CARRIER_ASNS: &[u32] = &[
+    9605,   // NTT DOCOMO
+    138384, // Rakuten Mobile
+    21928,  // T-Mobile USA
+    22394,  // Verizon Wireless
+    20057,  // AT&T Mobility
+    12576,  // EE (UK)
+    25135,  // Vodafone UK
+];
+
+/// モバイルキャリアと判定する組織名のキーワード（小文字）
+const MOBILE_ORGANIZATION_KEYWORDS: &[&str] = &["mobile", "wireless", "cellular", "docomo"];
+
+/// モバイル視聴者の割合がこの値以上の場合に軽量モードを推奨する
+pub const LIGHTWEIGHT_MODE_MOBILE_RATIO: f64 = 0.5;
+
+/// 軽量モードの推奨判定に必要な、種別を判定できた最小の視聴者数
+const LIGHTWEIGHT_MODE_MIN_CLASSIFIED: usize = 5;
+
+/// 読み込み済みのASNデータベース
+static ASN_DATABASE: Lazy<RwLock<Option<AsnDatabase>>> = Lazy::new(|| RwLock::new(None));
+
+/// ## 接続元ネットワーク種別
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkType {
+    /// モバイル回線
+    Mobile,
+    /// 固定回線
+    Fixed,
+    /// 判定できない
+    Unknown,
+}
+
+impl NetworkType {
+    /// ## `ClientInfo` に設定する文字列表現を取得する
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Mobile => "mobile",
+            Self::Fixed => "fixed",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// ## IPアドレス範囲とASNの対応
+#[derive(Debug, Clone)]
+struct AsnRecord {
+    /// 範囲の先頭アドレス
+    start: u128,
+    /// 範囲の末尾アドレス
+    end: u128,
+    /// 自律システム番号
+    asn: u32,
+    /// 組織名
+    organization: String,
+}
+
+/// ## ASNデータベース
+///
+/// IPv4とIPv6の範囲をそれぞれ先頭アドレス順に保持し、二分探索で検索します。
+#[derive(Debug, Default)]
+pub struct AsnDatabase {
+    v4: Vec<AsnRecord>,
+    v6: Vec<AsnRecord>,
+}
+
+impl AsnDatabase {
+    /// ## GeoLite2-ASN形式のCSVを読み込む
+    ///
+    /// `network,autonomous_system_number,autonomous_system_organization` の列を持つCSVを
+    /// 読み込みます。IPv4・IPv6どちらのBlocksファイルも追加できます。
+    ///
+    /// ### Arguments
+    /// - `content`: CSVファイルの内容
+    ///
+    /// ### Returns
+    /// - `Result<usize, String>`: 追加したレコード数、またはエラーメッセージ
+    pub fn add_csv(&mut self, content: &str) -> Result<usize, String> {
+        let mut added = 0;
+        for (line_no, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("network,") {
+                continue;
+            }
+
+            let mut fields = line.splitn(3, ',');
+            let (Some(network), Some(asn)) = (fields.next(), fields.next()) else {
+                return Err(format!("{}行目の形式が不正です", line_no + 1));
+            };
+            let organization = fields
+                .next()
+                .unwrap_or("")
+                .trim_matches('"')
+                .replace("\"\"", "\"");
+
+            let (is_v6, start, end) = parse_cidr(network).ok_or_else(|| {
+                format!("{}行目のネットワークが不正です: {}", line_no + 1, network)
+            })?;
+            let asn = asn
+                .parse::<u32>()
+                .map_err(|_| format!("{}行目のASNが不正です: {}", line_no + 1, asn))?;
+
+            let record = AsnRecord {
+                start,
+                end,
+                asn,
+                organization,
+            };
+            if is_v6 {
+                self.v6.push(record);
+            } else {
+                self.v4.push(record);
+            }
+            added += 1;
+        }
+
+        self.v4.sort_by_key(|record| record.start);
+        self.v6.sort_by_key(|record| record.start);
+        Ok(added)
+    }
+
+    /// ## 総レコード数を取得する
+    pub fn len(&self) -> usize {
+        self.v4.len() + self.v6.len()
+    }
+
+    /// ## レコードが空かどうか
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// IPアドレスを含む範囲のレコードを検索する
+    fn lookup(&self, ip: IpAddr) -> Option<&AsnRecord> {
+        let (records, value) = match ip {
+            IpAddr::V4(v4) => (&self.v4, u32::from(v4) as u128),
+            IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+                Some(v4) => (&self.v4, u32::from(v4) as u128),
+                None => (&self.v6, u128::from(v6)),
+            },
+        };
+        let index = records.partition_point(|record| record.start <= value);
+        records
+            .get(index.checked_sub(1)?)
+            .filter(|record| value <= record.end)
+    }
+
+    /// ## IPアドレスのネットワーク種別を推定する
+    ///
+    /// ### Arguments
+    /// - `ip`: 接続元IPアドレス
+    ///
+    /// ### Returns
+    /// - `NetworkType`: 推定したネットワーク種別
+    pub fn classify(&self, ip: IpAddr) -> NetworkType {
+        let Some(record) = self.lookup(ip) else {
+            return NetworkType::Unknown;
+        };
+
+        let organization = record.organization.to_lowercase();
+        if MOBILE_CARRIER_ASNS.contains(&record.asn)
+            || MOBILE_ORGANIZATION_KEYWORDS
+                .iter()
+                .any(|keyword| organization.contains(keyword))
+        {
+            NetworkType::Mobile
+        } else {
+            NetworkType::Fixed
+        }
+    }
+}
+
+/// ## ASNデータベースのCSVファイルを読み込んで有効化する
+///
+/// 既に読み込まれているデータベースは置き換えられます。
+/// 読み込み後に接続したクライアントから推定が行われます。
+///
+/// ### Arguments
+/// - `paths`: GeoLite2-ASNのBlocks CSVファイルのパス（IPv4・IPv6）
+///
+/// ### Returns
+/// - `Result<usize, String>`: 読み込んだレコード数、またはエラーメッセージ
+pub fn load_database(paths: &[String]) -> Result<usize, String> {
+    let mut database = AsnDatabase::default();
+    for path in paths {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("ASNデータベースの読み込みに失敗しました ({}): {}", path, e))?;
+        database
+            .add_csv(&content)
+            .map_err(|e| format!("ASNデータベースの解析に失敗しました ({}): {}", path, e))?;
+    }
+
+    if database.is_empty() {
+        return Err("ASNデータベースにレコードがありません".to_string());
+    }
+
+    let count = database.len();
+    *ASN_DATABASE
+        .write()
+        .map_err(|_| "ASNデータベースのロックに失敗しました".to_string())? = Some(database);
+    println!("ASNデータベースを読み込みました: {} レコード", count);
+    Ok(count)
+}
+
+/// ## 接続元IPのネットワーク種別を推定する
+///
+/// ### Arguments
+/// - `ip`: 接続元IPアドレス
+///
+/// ### Returns
+/// - `NetworkType`: 推定したネットワーク種別（データベース未読み込みの場合は `Unknown`）
+pub fn classify(ip: IpAddr) -> NetworkType {
+    match ASN_DATABASE.read() {
+        Ok(guard) => guard
+            .as_ref()
+            .map_or(NetworkType::Unknown, |database| database.classify(ip)),
+        Err(_) => NetworkType::Unknown,
+    }
+}
+
+/// ## トンネルが転送した接続元IPを取得する
+///
+/// トンネル経由の接続はループバックアドレスから届くため、実行中のトンネルが付与するヘッダーから
+/// 転送元のIPを取得します。cloudflaredはCloudflareが設定する `CF-Connecting-IP`、
+/// ngrokは `X-Forwarded-For` の末尾（ngrokが追記した値）を使用します。
+/// ヘッダーは視聴者も付与できるため、トンネルを実行していない場合やループバック以外からの接続では使用しません。
+///
+/// ### Arguments
+/// - `req`: WebSocketのアップグレードリクエスト
+/// - `tunnel`: 実行中のトンネルのプロバイダ（トンネルを実行していない場合はNone）
+///
+/// ### Returns
+/// - `Option<IpAddr>`: 転送元IPアドレス（トンネル経由でない場合はNone）
+pub fn forwarded_ip(req: &HttpRequest, tunnel: Option<TunnelKind>) -> Option<IpAddr> {
+    if !req.peer_addr()?.ip().is_loopback() {
+        return None;
+    }
+
+    let headers = req.headers();
+    let value = match tunnel? {
+        TunnelKind::Cloudflared => headers.get("CF-Connecting-IP")?.to_str().ok()?,
+        TunnelKind::Ngrok => headers
+            .get_all("X-Forwarded-For")
+            .last()?
+            .to_str()
+            .ok()?
+            .rsplit(',')
+            .next()?,
+    };
+    value.trim().parse().ok()
+}
+
+/// ## ネットワーク種別の推定に使う接続元IPを取得する
+///
+/// トンネル経由の接続はトンネルが転送した接続元IP、それ以外は接続元アドレスを使用します。
+///
+/// ### Arguments
+/// - `req`: WebSocketのアップグレードリクエスト
+/// - `tunnel`: 実行中のトンネルのプロバイダ（トンネルを実行していない場合はNone）
+///
+/// ### Returns
+/// - `Option<IpAddr>`: 接続元IPアドレス（取得できない場合はNone）
+pub fn client_ip(req: &HttpRequest, tunnel: Option<TunnelKind>) -> Option<IpAddr> {
+    forwarded_ip(req, tunnel).or_else(|| req.peer_addr().map(|addr| addr.ip()))
+}
+
+/// ## 軽量モードを推奨するかどうかを判定する
+///
+/// ### Arguments
+/// - `mobile`: モバイル回線と推定された視聴者数
+/// - `fixed`: 固定回線と推定された視聴者数
+///
+/// ### Returns
+/// - `bool`: モバイル視聴者の割合が閾値以上の場合は `true`
+pub fn recommend_lightweight_mode(mobile: usize, fixed: usize) -> bool {
+    let classified = mobile + fixed;
+    classified >= LIGHTWEIGHT_MODE_MIN_CLASSIFIED
+        && mobile as f64 / classified as f64 >= LIGHTWEIGHT_MODE_MOBILE_RATIO
+}
+
+/// CIDR表記をアドレス範囲に変換する
+fn parse_cidr(network: &str) -> Option<(bool, u128, u128)> {
+    let (address, prefix) = network.trim().split_once('/')?;
+    let prefix: u32 = prefix.parse().ok()?;
+    let (is_v6, bits, value) = match address.parse::<IpAddr>().ok()? {
+        IpAddr::V4(v4) => (false, 32, u32::from(v4) as u128),
+        IpAddr::V6(v6) => (true, 128, u128::from(v6)),
+    };
+    if prefix > bits {
+        return None;
+    }
+
+    let host_bits = bits - prefix;
+    let host_mask = if host_bits == 128 {
+        u128::MAX
+    } else {
+        (1u128 << host_bits) - 1
+    };
+    let start = value & !host_mask;
+    Some((is_v6, start, start | host_mask))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_network_type_from_asn_csv() {
+        let csv = "network,autonomous_system_number,autonomous_system_organization\n\
+                   1.66.0.0/15,9605,\"NTT DOCOMO, INC.\"\n\
+                   126.0.0.0/8,17676,\"SoftBank Corp.\"\n\
+                   203.0.113.0/24,64500,\"Example Wireless Networks\"\n\
+                   2001:db8::/32,64501,\"Example Fiber\"\n";
+        let mut database = AsnDatabase::default();
+        assert_eq!(database.add_csv(csv).unwrap(), 4);
+
+        let classify = |ip: &str| database.classify(ip.parse().unwrap());
+        assert_eq!(classify("1.67.255.255"), NetworkType::Mobile);
+        assert_eq!(classify("126.1.2.3"), NetworkType::Fixed);
+        assert_eq!(classify("203.0.113.10"), NetworkType::Mobile);
+        assert_eq!(classify("2001:db8::1"), NetworkType::Fixed);
+        assert_eq!(classify("::ffff:126.1.2.3"), NetworkType::Fixed);
+        assert_eq!(classify("1.68.0.0"), NetworkType::Unknown);
+        assert_eq!(classify("192.168.0.1"), NetworkType::Unknown);
+
+        assert!(recommend_lightweight_mode(3, 2));
+        assert!(!recommend_lightweight_mode(2, 3));
+        assert!(!recommend_lightweight_mode(2, 0));
+    }
+
+    /// トンネル経由の接続元IPの取得のテスト
+    #[test]
+    fn test_client_ip() {
+        use actix_web::test::TestRequest;
+
+        let request = |peer: &str, headers: &[(&str, &str)]| {
+            let mut request = TestRequest::default().peer_addr(peer.parse().unwrap());
+            for header in headers {
+                request = request.append_header(*header);
+            }
+            request.to_http_request()
+        };
+        let ip = |value: &str| Some(value.parse::<IpAddr>().unwrap());
+
+        // ngrokは視聴者が付与した値の後ろに接続元を追記するため、末尾の値を使う
+        let ngrok = request(
+            "127.0.0.1:50000",
+            &[("X-Forwarded-For", "198.51.100.1, 203.0.113.5")],
+        );
+        assert_eq!(
+            client_ip(&ngrok, Some(TunnelKind::Ngrok)),
+            ip("203.0.113.5")
+        );
+        // 他のプロバイダのヘッダーやトンネル停止中のヘッダーは信用しない
+        assert_eq!(
+            client_ip(&ngrok, Some(TunnelKind::Cloudflared)),
+            ip("127.0.0.1")
+        );
+        assert_eq!(client_ip(&ngrok, None), ip("127.0.0.1"));
+
+        let cloudflared = request(
+            "127.0.0.1:50000",
+            &[
+                ("CF-Connecting-IP", "203.0.113.7"),
+                ("X-Forwarded-For", "198.51.100.1"),
+            ],
+        );
+        assert_eq!(
+            client_ip(&cloudflared, Some(TunnelKind::Cloudflared)),
+            ip("203.0.113.7")
+        );
+
+        // ループバック以外からの接続はヘッダーに関わらず接続元アドレスを使う
+        let lan = request("192.168.0.10:50000", &[("CF-Connecting-IP", "203.0.113.7")]);
+        assert_eq!(forwarded_ip(&lan, Some(TunnelKind::Cloudflared)), None);
+        assert_eq!(
+            client_ip(&lan, Some(TunnelKind::Cloudflared)),
+            ip("192.168.0.10")
+        );
+    }
+}
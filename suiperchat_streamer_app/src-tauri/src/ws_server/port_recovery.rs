@@ -0,0 +1,384 @@
+//! 使用中ポートの自動解放モジュール
+//!
+//! 前回のインスタンスが異常終了してポートを掴んだまま残っている場合（ゾンビプロセス）、
+//! サーバーのバインドに失敗します。バインド前にポートの使用状況を確認し、
+//! 使用中の場合は使用しているプロセスを特定します。
+//! 自アプリの前回インスタンスであり、かつ自動解放が有効な場合のみプロセスを終了して解放を待ちます。
+//! 他のアプリケーションが使用しているポートは終了せず、エラーとして通知します。
+//!
+//! 起動中のインスタンスはポートごとのPIDファイルを定期的に更新します。PIDファイルが
+//! 更新されている自アプリのインスタンスは稼働中とみなし、終了しません。
+//! プロセスの特定・終了は外部コマンドの実行を伴うため、ブロッキングスレッドで行います。
+
+use std::io::{self, ErrorKind};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+/// プロセス終了後にポートの解放を確認する間隔
+const RELEASE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// プロセス終了後にポートの解放を待つ最大回数
+const RELEASE_POLL_ATTEMPTS: u32 = 50;
+
+/// 起動中のインスタンスがPIDファイルを更新する間隔
+const PID_FILE_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// PIDファイルの更新がこの期間途絶えたインスタンスは稼働していないとみなす
+const PID_FILE_STALE_AFTER: Duration = Duration::from_secs(30);
+
+/// ## ポートを使用しているプロセス
+#[derive(Debug, Clone)]
+pub struct PortOwner {
+    /// プロセスID
+    pub pid: u32,
+    /// プロセス名
+    pub name: String,
+    /// 実行ファイルのパス（取得できない場合はNone）
+    pub exe: Option<PathBuf>,
+}
+
+impl PortOwner {
+    /// ## 自アプリの別インスタンスかどうかを判定する
+    ///
+    /// 実行ファイルのパスが取得できる場合はパスで、できない場合はプロセス名で照合します。
+    /// 自プロセス自身は対象外です。
+    ///
+    /// ### Returns
+    /// - `bool`: 自アプリの別インスタンスの場合は `true`
+    pub fn is_own_app(&self) -> bool {
+        if self.pid == std::process::id() {
+            return false;
+        }
+        let Ok(current_exe) = std::env::current_exe() else {
+            return false;
+        };
+
+        match &self.exe {
+            Some(exe) => same_path(exe, &current_exe),
+            None => current_exe
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy() == self.name),
+        }
+    }
+}
+
+/// ## バインド前にポートが使用可能か確認し、必要に応じて解放する
+///
+/// ポートが使用中で、使用しているプロセスが自アプリの前回インスタンスの場合、
+/// `auto_release` が有効であればプロセスを終了してポートの解放を待ちます。
+/// 使用しているプロセスを特定できない場合は、実際のバインドの結果に判断を委ねます。
+///
+/// ### Arguments
+/// - `bind_host`: バインドするホスト
+/// - `port`: バインドするポート
+/// - `auto_release`: 自アプリの前回インスタンスを終了してポートを解放するかどうか
+///
+/// ### Returns
+/// - `io::Result<()>`: 使用可能な場合は `Ok(())`、使用中で解放できない場合は `AddrInUse` のエラー
+pub async fn ensure_port_available(
+    bind_host: &str,
+    port: u16,
+    auto_release: bool,
+) -> io::Result<()> {
+    if !is_port_in_use(bind_host, port) {
+        return Ok(());
+    }
+
+    let owner = tokio::task::spawn_blocking(move || find_port_owner(port))
+        .await
+        .ok()
+        .flatten();
+    let Some(owner) = owner else {
+        println!(
+            "ポート{}は使用中ですが、使用しているプロセスを特定できませんでした",
+            port
+        );
+        return Ok(());
+    };
+    println!(
+        "ポート{}を使用しているプロセス: {} (PID {}, {:?})",
+        port, owner.name, owner.pid, owner.exe
+    );
+
+    if !owner.is_own_app() {
+        return Err(io::Error::new(
+            ErrorKind::AddrInUse,
+            format!(
+                "ポート{}は他のアプリケーション ({}, PID {}) が使用中です。ポート番号を変更するか、該当のアプリケーションを終了してください",
+                port, owner.name, owner.pid
+            ),
+        ));
+    }
+
+    if is_live_instance(port, owner.pid) {
+        return Err(io::Error::new(
+            ErrorKind::AddrInUse,
+            format!(
+                "ポート{}は起動中の別のSUIperCHAT (PID {}) が使用中です。ポート番号を変更するか、該当のインスタンスを終了してください",
+                port, owner.pid
+            ),
+        ));
+    }
+
+    if !auto_release {
+        return Err(io::Error::new(
+            ErrorKind::AddrInUse,
+            format!(
+                "ポート{}は前回起動したSUIperCHAT (PID {}) が使用中です。プロセスを終了するか、ポートの自動解放を有効にしてください",
+                port, owner.pid
+            ),
+        ));
+    }
+
+    release_port(bind_host, port, &owner).await
+}
+
+/// ポートにバインドできるか試し、使用中かどうかを判定する
+fn is_port_in_use(bind_host: &str, port: u16) -> bool {
+    matches!(
+        TcpListener::bind((bind_host, port)),
+        Err(e) if e.kind() == ErrorKind::AddrInUse
+    )
+}
+
+/// 自アプリの前回インスタンスを終了し、ポートが解放されるまで待つ
+async fn release_port(bind_host: &str, port: u16, owner: &PortOwner) -> io::Result<()> {
+    println!(
+        "ポート{}を解放するため前回のインスタンスを終了します: {} (PID {}, {:?})",
+        port, owner.name, owner.pid, owner.exe
+    );
+
+    let pid = owner.pid;
+    let killed = tokio::task::spawn_blocking(move || kill_process(pid))
+        .await
+        .unwrap_or(false);
+    if !killed {
+        eprintln!("プロセスの終了に失敗しました: PID {}", owner.pid);
+        return Err(io::Error::new(
+            ErrorKind::AddrInUse,
+            format!(
+                "ポート{}を使用している前回のインスタンス (PID {}) を終了できませんでした",
+                port, owner.pid
+            ),
+        ));
+    }
+
+    for _ in 0..RELEASE_POLL_ATTEMPTS {
+        if !is_port_in_use(bind_host, port) {
+            println!(
+                "前回のインスタンス (PID {}) を終了し、ポート{}を解放しました",
+                owner.pid, port
+            );
+            return Ok(());
+        }
+        tokio::time::sleep(RELEASE_POLL_INTERVAL).await;
+    }
+
+    eprintln!(
+        "前回のインスタンス (PID {}) を終了しましたが、ポート{}が解放されませんでした",
+        owner.pid, port
+    );
+    Err(io::Error::new(
+        ErrorKind::AddrInUse,
+        format!(
+            "前回のインスタンス (PID {}) を終了しましたが、ポート{}が解放されませんでした",
+            owner.pid, port
+        ),
+    ))
+}
+
+/// プロセスを終了し、終了要求を送れたかどうかを返す
+fn kill_process(pid: u32) -> bool {
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::Some(&[Pid::from_u32(pid)]), true);
+    system
+        .process(Pid::from_u32(pid))
+        .is_some_and(|process| process.kill())
+}
+
+/// ## 起動中であることを示すPIDファイルを定期的に更新する
+///
+/// サーバーのランタイム上で実行し、サーバーの停止（ランタイムの終了）とともに更新を終了します。
+/// 更新が途絶えたPIDファイルは、次回起動時に前回のインスタンスが稼働していない証拠として扱います。
+///
+/// ### Arguments
+/// - `ports`: サーバーがバインドしたポート
+pub fn spawn_pid_heartbeat(ports: Vec<u16>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PID_FILE_HEARTBEAT_INTERVAL);
+        loop {
+            interval.tick().await;
+            let record = format_pid_record(std::process::id(), unix_secs(SystemTime::now()));
+            for port in &ports {
+                let path = pid_file_path(*port);
+                if let Err(e) = tokio::fs::write(&path, &record).await {
+                    eprintln!(
+                        "PIDファイルの更新に失敗しました ({}): {}",
+                        path.display(),
+                        e
+                    );
+                }
+            }
+        }
+    });
+}
+
+/// ポートを使用しているプロセスが、PIDファイルを更新している稼働中のインスタンスか判定する
+fn is_live_instance(port: u16, pid: u32) -> bool {
+    std::fs::read_to_string(pid_file_path(port))
+        .map(|record| is_live_record(&record, pid, unix_secs(SystemTime::now())))
+        .unwrap_or(false)
+}
+
+/// ポートごとのPIDファイルのパス
+fn pid_file_path(port: u16) -> PathBuf {
+    std::env::temp_dir().join(format!("suiperchat-port-{}.pid", port))
+}
+
+/// PIDファイルの内容（プロセスIDと更新時刻のUNIX秒）を作成する
+fn format_pid_record(pid: u32, updated_at: u64) -> String {
+    format!("{} {}\n", pid, updated_at)
+}
+
+/// PIDファイルの内容が、指定したプロセスにより最近更新されたものか判定する
+fn is_live_record(record: &str, pid: u32, now: u64) -> bool {
+    let mut fields = record.split_whitespace();
+    let (Some(Ok(recorded_pid)), Some(Ok(updated_at))) = (
+        fields.next().map(str::parse::<u32>),
+        fields.next().map(str::parse::<u64>),
+    ) else {
+        return false;
+    };
+    recorded_pid == pid && now.saturating_sub(updated_at) < PID_FILE_STALE_AFTER.as_secs()
+}
+
+/// 時刻をUNIX秒に変換する
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// ## ポートで待ち受けているプロセスを特定する
+///
+/// ### Arguments
+/// - `port`: 確認するポート
+///
+/// ### Returns
+/// - `Option<PortOwner>`: 待ち受けているプロセス（特定できない場合はNone）
+pub fn find_port_owner(port: u16) -> Option<PortOwner> {
+    let pid = find_listening_pid(port)?;
+
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[Pid::from_u32(pid)]),
+        true,
+        ProcessRefreshKind::nothing().with_exe(UpdateKind::OnlyIfNotSet),
+    );
+    let process = system.process(Pid::from_u32(pid))?;
+
+    Some(PortOwner {
+        pid,
+        name: process.name().to_string_lossy().to_string(),
+        exe: process.exe().map(PathBuf::from),
+    })
+}
+
+/// `netstat` の出力からポートで待ち受けているプロセスIDを取得する（Windows）
+#[cfg(windows)]
+fn find_listening_pid(port: u16) -> Option<u32> {
+    let output = Command::new("netstat")
+        .args(["-ano", "-p", "TCP"])
+        .output()
+        .ok()?;
+    let suffix = format!(":{}", port);
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|columns| {
+            columns.len() >= 5 && columns[1].ends_with(&suffix) && columns[3] == "LISTENING"
+        })
+        .and_then(|columns| columns[4].parse().ok())
+}
+
+/// `lsof` の出力からポートで待ち受けているプロセスIDを取得する（macOS / Linux）
+#[cfg(not(windows))]
+fn find_listening_pid(port: u16) -> Option<u32> {
+    let output = Command::new("lsof")
+        .args(["-nP", "-t", &format!("-iTCP:{}", port), "-sTCP:LISTEN"])
+        .output()
+        .ok()?;
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.trim().parse().ok())
+}
+
+/// シンボリックリンク等を解決した上でパスが同一か判定する
+fn same_path(a: &std::path::Path, b: &std::path::Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// PIDファイルによる稼働中のインスタンスの判定のテスト
+    #[test]
+    fn test_is_live_record() {
+        let now = 1_700_000_000;
+        let record = format_pid_record(1234, now - 5);
+        assert!(is_live_record(&record, 1234, now));
+        // 別のプロセスの記録は稼働中の証拠にならない
+        assert!(!is_live_record(&record, 5678, now));
+        // 更新が途絶えた記録は稼働していないとみなす
+        assert!(!is_live_record(
+            &record,
+            1234,
+            now + PID_FILE_STALE_AFTER.as_secs()
+        ));
+        assert!(!is_live_record("", 1234, now));
+        assert!(!is_live_record("1234", 1234, now));
+    }
+
+    /// 自アプリのインスタンスの判定のテスト
+    #[test]
+    fn test_is_own_app() {
+        let current_exe = std::env::current_exe().unwrap();
+        let owner = |pid: u32, exe: Option<PathBuf>| PortOwner {
+            pid,
+            name: "other".to_string(),
+            exe,
+        };
+
+        // 自プロセス自身は対象外
+        assert!(!owner(std::process::id(), Some(current_exe.clone())).is_own_app());
+        assert!(owner(std::process::id() + 1, Some(current_exe.clone())).is_own_app());
+        assert!(!owner(
+            std::process::id() + 1,
+            Some(PathBuf::from("/usr/bin/other"))
+        )
+        .is_own_app());
+        assert!(!owner(std::process::id() + 1, None).is_own_app());
+    }
+
+    /// 使用されていないポートの確認のテスト
+    #[tokio::test]
+    async fn test_ensure_port_available_for_free_port() {
+        let port = TcpListener::bind(("127.0.0.1", 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        assert!(ensure_port_available("127.0.0.1", port, false)
+            .await
+            .is_ok());
+    }
+}
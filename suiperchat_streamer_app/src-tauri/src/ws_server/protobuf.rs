@@ -0,0 +1,384 @@
+//! Protocol Buffersによるブロードキャストのバイナリ化モジュール
+//!
+//! WebSocketのサブプロトコル `suiperchat.protobuf` を指定して接続したクライアントに対し、
+//! チャット・スーパーチャットのブロードキャストをProtocol Buffersでシリアライズした
+//! Binaryフレームとして送信するための型と変換処理を提供します。
+//! スキーマは `proto/broadcast.proto` と対応しています。
+
+use super::msgpack::MSGPACK_SUBPROTOCOL;
+use crate::types::{ChatMessage, MessageType, SuperchatData, SuperchatMessage};
+use actix_web::web::Bytes;
+use actix_web::HttpRequest;
+use prost::Message as _;
+
+/// バイナリモードを選択するWebSocketサブプロトコル名
+pub const PROTOBUF_SUBPROTOCOL: &str = "suiperchat.protobuf";
+
+/// ## ブロードキャストのエンコーディング
+///
+/// 接続時のサブプロトコルのネゴシエーションで決定されます。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BroadcastEncoding {
+    /// JSONテキストフレーム（デフォルト）
+    #[default]
+    Json,
+    /// Protocol BuffersのBinaryフレーム
+    Protobuf,
+    /// MessagePackのBinaryフレーム
+    MessagePack,
+}
+
+impl BroadcastEncoding {
+    /// ## リクエストのサブプロトコルからエンコーディングを判定する
+    ///
+    /// `Sec-WebSocket-Protocol` ヘッダーで要求されたサブプロトコルのうち、
+    /// 最初に対応しているもの（`suiperchat.protobuf` または `suiperchat.msgpack`）でバイナリモードとします。
+    /// ハンドシェイク応答で選択されるサブプロトコルと一致させるため、先頭のヘッダーのみを参照します。
+    ///
+    /// ### Arguments
+    /// - `req`: WebSocketのアップグレードリクエスト
+    ///
+    /// ### Returns
+    /// - `Self`: 判定したエンコーディング
+    pub fn from_request(req: &HttpRequest) -> Self {
+        req.headers()
+            .get(actix_web::http::header::SEC_WEBSOCKET_PROTOCOL)
+            .and_then(|value| value.to_str().ok())
+            .into_iter()
+            .flat_map(|value| value.split(','))
+            .find_map(|protocol| match protocol.trim() {
+                PROTOBUF_SUBPROTOCOL => Some(Self::Protobuf),
+                MSGPACK_SUBPROTOCOL => Some(Self::MessagePack),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// ## 通常チャットメッセージ（protobuf）
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ChatMessageProto {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub display_name: String,
+    #[prost(string, tag = "3")]
+    pub message: String,
+    #[prost(int64, optional, tag = "4")]
+    pub timestamp: Option<i64>,
+    #[prost(string, optional, tag = "5")]
+    pub channel: Option<String>,
+    #[prost(string, optional, tag = "6")]
+    pub detected_language: Option<String>,
+    #[prost(string, optional, tag = "7")]
+    pub translated_content: Option<String>,
+    #[prost(string, repeated, tag = "8")]
+    pub badges: Vec<String>,
+}
+
+/// ## スーパーチャットの送金情報（protobuf）
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SuperchatDataProto {
+    #[prost(double, tag = "1")]
+    pub amount: f64,
+    #[prost(string, tag = "2")]
+    pub coin: String,
+    #[prost(string, tag = "3")]
+    pub tx_hash: String,
+    #[prost(string, tag = "4")]
+    pub wallet_address: String,
+}
+
+/// ## スーパーチャットメッセージ（protobuf）
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SuperchatMessageProto {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub display_name: String,
+    #[prost(string, tag = "3")]
+    pub message: String,
+    #[prost(message, optional, tag = "4")]
+    pub superchat: Option<SuperchatDataProto>,
+    #[prost(int64, optional, tag = "5")]
+    pub timestamp: Option<i64>,
+    #[prost(uint32, optional, tag = "6")]
+    pub donor_streak: Option<u32>,
+    #[prost(bool, optional, tag = "7")]
+    pub verified: Option<bool>,
+    #[prost(string, optional, tag = "8")]
+    pub translated_content: Option<String>,
+    #[prost(string, repeated, tag = "9")]
+    pub badges: Vec<String>,
+}
+
+/// ## Binaryフレーム1つ分のメッセージ（protobuf）
+///
+/// JSONの `type` フィールドは `payload` のどちらが設定されているかで表現します。
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct BroadcastEnvelope {
+    #[prost(oneof = "broadcast_envelope::Payload", tags = "1, 2")]
+    pub payload: Option<broadcast_envelope::Payload>,
+    #[prost(uint64, optional, tag = "3")]
+    pub seq: Option<u64>,
+}
+
+/// `BroadcastEnvelope` のoneofフィールド定義
+pub mod broadcast_envelope {
+    /// ## ブロードキャストの本体
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Payload {
+        #[prost(message, tag = "1")]
+        Chat(super::ChatMessageProto),
+        #[prost(message, tag = "2")]
+        Superchat(super::SuperchatMessageProto),
+    }
+}
+
+impl From<&ChatMessage> for ChatMessageProto {
+    fn from(msg: &ChatMessage) -> Self {
+        Self {
+            id: msg.id.clone(),
+            display_name: msg.display_name.clone(),
+            message: msg.content.clone(),
+            timestamp: msg.timestamp,
+            channel: msg.channel.clone(),
+            detected_language: msg.detected_language.clone(),
+            translated_content: msg.translated_content.clone(),
+            badges: msg.badges.clone(),
+        }
+    }
+}
+
+impl From<ChatMessageProto> for ChatMessage {
+    fn from(proto: ChatMessageProto) -> Self {
+        Self {
+            message_type: MessageType::Chat,
+            id: proto.id,
+            display_name: proto.display_name,
+            content: proto.message,
+            timestamp: proto.timestamp,
+            channel: proto.channel,
+            detected_language: proto.detected_language,
+            translated_content: proto.translated_content,
+            badges: proto.badges,
+        }
+    }
+}
+
+impl From<&SuperchatMessage> for SuperchatMessageProto {
+    fn from(msg: &SuperchatMessage) -> Self {
+        Self {
+            id: msg.id.clone(),
+            display_name: msg.display_name.clone(),
+            message: msg.content.clone(),
+            superchat: Some(SuperchatDataProto {
+                amount: msg.superchat.amount,
+                coin: msg.superchat.coin.clone(),
+                tx_hash: msg.superchat.tx_hash.clone(),
+                wallet_address: msg.superchat.wallet_address.clone(),
+            }),
+            timestamp: msg.timestamp,
+            donor_streak: msg.donor_streak,
+            verified: msg.verified,
+            translated_content: msg.translated_content.clone(),
+            badges: msg.badges.clone(),
+        }
+    }
+}
+
+impl From<SuperchatMessageProto> for SuperchatMessage {
+    fn from(proto: SuperchatMessageProto) -> Self {
+        let superchat = proto.superchat.unwrap_or_default();
+        Self {
+            message_type: MessageType::Superchat,
+            id: proto.id,
+            display_name: proto.display_name,
+            content: proto.message,
+            superchat: SuperchatData {
+                amount: superchat.amount,
+                coin: superchat.coin,
+                tx_hash: superchat.tx_hash,
+                wallet_address: superchat.wallet_address,
+                amount_unit: None,
+            },
+            timestamp: proto.timestamp,
+            donor_streak: proto.donor_streak,
+            verified: proto.verified,
+            translated_content: proto.translated_content,
+            badges: proto.badges,
+        }
+    }
+}
+
+/// ## チャットメッセージをBinaryフレーム用にエンコードする
+///
+/// ### Arguments
+/// - `msg`: ブロードキャストするチャットメッセージ
+///
+/// ### Returns
+/// - `Bytes`: `BroadcastEnvelope` としてシリアライズしたバイト列
+pub fn encode_chat(msg: &ChatMessage) -> Bytes {
+    encode_envelope(broadcast_envelope::Payload::Chat(msg.into()))
+}
+
+/// ## スーパーチャットメッセージをBinaryフレーム用にエンコードする
+///
+/// ### Arguments
+/// - `msg`: ブロードキャストするスーパーチャットメッセージ
+///
+/// ### Returns
+/// - `Bytes`: `BroadcastEnvelope` としてシリアライズしたバイト列
+pub fn encode_superchat(msg: &SuperchatMessage) -> Bytes {
+    encode_envelope(broadcast_envelope::Payload::Superchat(msg.into()))
+}
+
+fn encode_envelope(payload: broadcast_envelope::Payload) -> Bytes {
+    let envelope = BroadcastEnvelope {
+        payload: Some(payload),
+        seq: None,
+    };
+    Bytes::from(envelope.encode_to_vec())
+}
+
+/// ## エンコード済みのメッセージにシーケンス番号を付与する
+///
+/// Protocol Buffersでは連結したメッセージがマージされるため、
+/// `seq` のみの `BroadcastEnvelope` を末尾に連結して付与します。
+///
+/// ### Arguments
+/// - `bin`: `BroadcastEnvelope` としてシリアライズしたバイト列
+/// - `seq`: シーケンス番号
+///
+/// ### Returns
+/// - `Bytes`: シーケンス番号を付与したバイト列
+pub fn with_seq(bin: &[u8], seq: u64) -> Bytes {
+    let suffix = BroadcastEnvelope {
+        payload: None,
+        seq: Some(seq),
+    };
+    let mut buf = bin.to_vec();
+    buf.extend(suffix.encode_to_vec());
+    Bytes::from(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ws_server::session::{Broadcast, BroadcastFrame};
+
+    fn sample_chat() -> ChatMessage {
+        ChatMessage {
+            message_type: MessageType::Chat,
+            id: "4f1c2b9e-8d7a-4e1f-9c3b-2a6d5e8f0b1c".to_string(),
+            display_name: "viewer".to_string(),
+            content: "こんにちは、今日の配信も楽しみにしていました！".to_string(),
+            timestamp: Some(1_717_000_000_000),
+            channel: Some("general".to_string()),
+            detected_language: Some("ja".to_string()),
+            translated_content: None,
+            badges: Vec::new(),
+        }
+    }
+
+    fn sample_superchat() -> SuperchatMessage {
+        SuperchatMessage {
+            message_type: MessageType::Superchat,
+            id: "9a8b7c6d-5e4f-4a3b-8c2d-1e0f9a8b7c6d".to_string(),
+            display_name: "donor".to_string(),
+            content: "応援しています".to_string(),
+            superchat: SuperchatData {
+                amount: 1.5,
+                coin: "SUI".to_string(),
+                tx_hash: "8Wq3rT6yU1iO4pA7sD0fG2hJ5kL9zX3cV6bN8mQ1wE4r".to_string(),
+                wallet_address: format!("0x{}", "ab".repeat(32)),
+                amount_unit: None,
+            },
+            timestamp: Some(1_717_000_000_123),
+            donor_streak: Some(3),
+            verified: Some(true),
+            translated_content: Some("I support you".to_string()),
+            badges: vec!["ゴールドサポーター".to_string()],
+        }
+    }
+
+    /// 混在した接続でJSON版と同じ情報が欠落なく伝わり、バイナリの方が小さいことを確認
+    #[test]
+    fn test_mixed_encodings_deliver_same_content() {
+        let chat = sample_chat();
+        let superchat = sample_superchat();
+
+        let chat_json = serde_json::to_string(&chat).unwrap();
+        let superchat_json = serde_json::to_string(&superchat).unwrap();
+        let chat_broadcast = Broadcast::with_protobuf(chat_json.clone(), encode_chat(&chat));
+        let superchat_broadcast =
+            Broadcast::with_protobuf(superchat_json.clone(), encode_superchat(&superchat));
+
+        // JSONモードの接続にはJSON版がそのまま届く
+        match chat_broadcast.clone().into_frame(BroadcastEncoding::Json) {
+            BroadcastFrame::Text(text) => assert_eq!(text, chat_json),
+            BroadcastFrame::Binary(_) => panic!("JSONモードにバイナリが送信されました"),
+        }
+
+        // バイナリモードの接続にはprotobuf版が届き、デコード結果がJSON版と一致する
+        let chat_bin = match chat_broadcast.into_frame(BroadcastEncoding::Protobuf) {
+            BroadcastFrame::Binary(bin) => bin,
+            BroadcastFrame::Text(_) => panic!("バイナリモードにテキストが送信されました"),
+        };
+        let superchat_bin = match superchat_broadcast.into_frame(BroadcastEncoding::Protobuf) {
+            BroadcastFrame::Binary(bin) => bin,
+            BroadcastFrame::Text(_) => panic!("バイナリモードにテキストが送信されました"),
+        };
+
+        let decoded_chat = match BroadcastEnvelope::decode(chat_bin.clone()).unwrap().payload {
+            Some(broadcast_envelope::Payload::Chat(proto)) => ChatMessage::from(proto),
+            other => panic!("予期しないペイロード: {:?}", other),
+        };
+        assert_eq!(serde_json::to_string(&decoded_chat).unwrap(), chat_json);
+
+        let decoded_superchat = match BroadcastEnvelope::decode(superchat_bin.clone())
+            .unwrap()
+            .payload
+        {
+            Some(broadcast_envelope::Payload::Superchat(proto)) => SuperchatMessage::from(proto),
+            other => panic!("予期しないペイロード: {:?}", other),
+        };
+        assert_eq!(
+            serde_json::to_string(&decoded_superchat).unwrap(),
+            superchat_json
+        );
+
+        // 帯域削減効果の計測
+        for (label, json_len, bin_len) in [
+            ("chat", chat_json.len(), chat_bin.len()),
+            ("superchat", superchat_json.len(), superchat_bin.len()),
+        ] {
+            println!(
+                "{}: JSON={}バイト, protobuf={}バイト ({:.1}%削減)",
+                label,
+                json_len,
+                bin_len,
+                (1.0 - bin_len as f64 / json_len as f64) * 100.0
+            );
+            assert!(bin_len < json_len);
+        }
+
+        // 付与したシーケンス番号は元のペイロードを変えずにデコードできる
+        let sequenced = BroadcastEnvelope::decode(with_seq(&chat_bin, 42)).unwrap();
+        assert_eq!(sequenced.seq, Some(42));
+        assert_eq!(
+            sequenced.payload,
+            BroadcastEnvelope::decode(chat_bin.clone()).unwrap().payload
+        );
+
+        // テキストのみのブロードキャストはバイナリモードでもテキストで届く
+        match Broadcast::text("{\"type\":\"ERROR\"}".to_string())
+            .into_frame(BroadcastEncoding::Protobuf)
+        {
+            BroadcastFrame::Text(_) => {}
+            BroadcastFrame::Binary(_) => {
+                panic!("テキストのみのメッセージがバイナリで送信されました")
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! メッセージ受信レート制限モジュール
+//!
+//! 短時間に大量のチャットを送信してコメント欄を埋め尽くす行為を防ぐため、
+//! クライアントごとに直近の一定期間内に受け付けるメッセージ数に上限を設けます。
+//! スーパーチャットは送金を伴うため制限の対象外です。
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// デフォルトの期間内に受け付ける最大メッセージ数
+pub const DEFAULT_RATE_LIMIT_MAX_MESSAGES: usize = 5;
+
+/// デフォルトのレート制限の期間（秒）
+pub const DEFAULT_RATE_LIMIT_WINDOW_SECS: u64 = 10;
+
+/// 設定可能なレート制限の期間の上限（秒）
+pub const MAX_RATE_LIMIT_WINDOW_SECS: u64 = 3600;
+
+/// ## メッセージ受信レート制限の設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageRateLimit {
+    /// レート制限を有効にするかどうか
+    pub enabled: bool,
+    /// 期間内に受け付ける最大メッセージ数
+    pub max_messages: usize,
+    /// レート制限の期間（秒）
+    pub window_secs: u64,
+}
+
+impl Default for MessageRateLimit {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_messages: DEFAULT_RATE_LIMIT_MAX_MESSAGES,
+            window_secs: DEFAULT_RATE_LIMIT_WINDOW_SECS,
+        }
+    }
+}
+
+impl MessageRateLimit {
+    /// ## メッセージを受け付けるか判定し、受け付けた場合は送信時刻を記録する
+    ///
+    /// 期間外になった送信時刻は判定前に破棄します。拒否したメッセージの時刻は記録しません。
+    ///
+    /// ### Arguments
+    /// - `recent`: 直近に受け付けたメッセージの送信時刻（古い順）
+    /// - `now`: 現在時刻
+    ///
+    /// ### Returns
+    /// - `bool`: 受け付ける場合は `true`、上限を超えている場合は `false`
+    pub fn check(&self, recent: &mut Vec<Instant>, now: Instant) -> bool {
+        if !self.enabled {
+            return true;
+        }
+
+        let window = Duration::from_secs(self.window_secs);
+        recent.retain(|sent_at| now.duration_since(*sent_at) < window);
+        if recent.len() >= self.max_messages {
+            return false;
+        }
+        recent.push(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 期間内の上限を超えたメッセージが拒否され、期間経過後は再び受け付けられることを確認
+    #[test]
+    fn test_rate_limit_window() {
+        let limit = MessageRateLimit::default();
+        let start = Instant::now();
+        let mut recent = Vec::new();
+
+        for i in 0..DEFAULT_RATE_LIMIT_MAX_MESSAGES {
+            assert!(limit.check(&mut recent, start + Duration::from_secs(i as u64)));
+        }
+        assert!(!limit.check(&mut recent, start + Duration::from_secs(5)));
+        assert_eq!(recent.len(), DEFAULT_RATE_LIMIT_MAX_MESSAGES);
+
+        // 最初のメッセージが期間外になると1件分の枠が空く
+        let later = start + Duration::from_secs(DEFAULT_RATE_LIMIT_WINDOW_SECS);
+        assert!(limit.check(&mut recent, later));
+        assert!(!limit.check(&mut recent, later));
+
+        // 無効な場合は常に受け付ける
+        let disabled = MessageRateLimit {
+            enabled: false,
+            ..limit
+        };
+        assert!(disabled.check(&mut recent, later));
+    }
+}
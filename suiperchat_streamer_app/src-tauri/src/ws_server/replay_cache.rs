@@ -0,0 +1,213 @@
+//! ブロードキャスト再送キャッシュモジュール
+//!
+//! 直近のブロードキャストメッセージをメモリ上のリングバッファに保持し、
+//! 短時間の切断から再接続したクライアントへDBを参照せずに欠損分を再送します。
+//! 各メッセージにはキャッシュ内で採番したシーケンス番号 (`seq`) を付与し、
+//! クライアントが最後に受信したシーケンス番号より新しいものだけを再送します。
+//! シーケンス番号はJSON・MessagePackでは `seq` フィールド、protobufでは `BroadcastEnvelope.seq` で届きます。
+//! 再送分は再接続後に届いたブロードキャストより後に届くことがあるため、受信順はシーケンス番号順とは限りません。
+
+use super::session::Broadcast;
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// キャッシュに保持する期間
+pub const REPLAY_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// キャッシュに保持する最大メッセージ数（メモリ保護のための上限）
+pub const MAX_REPLAY_CACHE_ENTRIES: usize = 500;
+
+/// ## キャッシュ済みのブロードキャスト
+#[derive(Debug, Clone)]
+struct CachedBroadcast {
+    /// シーケンス番号
+    seq: u64,
+    /// 配信先のチャンネル（全クライアント宛ての場合はNone）
+    channel: Option<String>,
+    /// 送信したメッセージ（シーケンス番号・署名付与済み）
+    message: Broadcast,
+    /// キャッシュした時刻
+    cached_at: Instant,
+}
+
+/// ## 再送するメッセージ
+#[derive(Debug, Clone)]
+pub struct Replay {
+    /// 欠損していたメッセージ（古い順）
+    pub messages: Vec<Broadcast>,
+    /// キャッシュ内の最新のシーケンス番号
+    pub latest_seq: u64,
+    /// 欠損分を全て再送できたかどうか（falseの場合は履歴取得での補完が必要）
+    pub complete: bool,
+}
+
+/// ## ブロードキャスト再送キャッシュ
+///
+/// 配信セッションごとに管理し、配信セッションが切り替わった場合は古い内容を破棄します。
+#[derive(Debug, Default)]
+pub struct ReplayCache {
+    /// キャッシュ対象の配信セッションID
+    session_id: Option<String>,
+    /// キャッシュ済みのメッセージ（古い順）
+    entries: VecDeque<CachedBroadcast>,
+    /// 最後に採番したシーケンス番号
+    last_seq: u64,
+}
+
+impl ReplayCache {
+    /// ## 次に採番するシーケンス番号を予約する
+    ///
+    /// 配信セッションが切り替わった場合はキャッシュを破棄します。
+    /// シーケンス番号はキャッシュを破棄しても巻き戻さず、再接続時の取り違えを防ぎます。
+    ///
+    /// ### Arguments
+    /// - `session_id`: 現在の配信セッションID
+    ///
+    /// ### Returns
+    /// - `u64`: 採番したシーケンス番号
+    pub fn next_seq(&mut self, session_id: Option<&str>) -> u64 {
+        if self.session_id.as_deref() != session_id {
+            self.entries.clear();
+            self.session_id = session_id.map(str::to_string);
+        }
+        self.last_seq += 1;
+        self.last_seq
+    }
+
+    /// ## メッセージをキャッシュに追加する
+    ///
+    /// 期限切れのメッセージを破棄し、上限を超えた場合は古いものから破棄します。
+    ///
+    /// ### Arguments
+    /// - `seq`: `next_seq` で採番したシーケンス番号
+    /// - `channel`: 配信先のチャンネル（全クライアント宛ての場合はNone）
+    /// - `message`: 送信したメッセージ
+    /// - `now`: 現在時刻
+    pub fn push(&mut self, seq: u64, channel: Option<&str>, message: Broadcast, now: Instant) {
+        self.prune(now);
+        while self.entries.len() >= MAX_REPLAY_CACHE_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(CachedBroadcast {
+            seq,
+            channel: channel.map(str::to_string),
+            message,
+            cached_at: now,
+        });
+    }
+
+    /// ## 欠損しているメッセージを取得する
+    ///
+    /// ### Arguments
+    /// - `session_id`: クライアントが接続している配信セッションID
+    /// - `last_seq`: クライアントが最後に受信したシーケンス番号
+    /// - `channels`: クライアントが購読中のチャンネル
+    /// - `now`: 現在時刻
+    ///
+    /// ### Returns
+    /// - `Replay`: 再送するメッセージ
+    pub fn since(
+        &mut self,
+        session_id: Option<&str>,
+        last_seq: u64,
+        channels: &HashSet<String>,
+        now: Instant,
+    ) -> Replay {
+        self.prune(now);
+        if self.session_id.as_deref() != session_id {
+            return Replay {
+                messages: Vec::new(),
+                latest_seq: self.last_seq,
+                complete: false,
+            };
+        }
+
+        // 最後に受信した番号の直後からキャッシュに残っていれば欠損なく再送できる
+        let oldest_seq = self
+            .entries
+            .front()
+            .map_or(self.last_seq + 1, |entry| entry.seq);
+        let messages = self
+            .entries
+            .iter()
+            .filter(|entry| entry.seq > last_seq)
+            .filter(|entry| match &entry.channel {
+                Some(channel) => channels.contains(channel),
+                None => true,
+            })
+            .map(|entry| entry.message.clone())
+            .collect();
+
+        Replay {
+            messages,
+            latest_seq: self.last_seq,
+            complete: last_seq + 1 >= oldest_seq && last_seq <= self.last_seq,
+        }
+    }
+
+    /// ## キャッシュを全て破棄する
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.session_id = None;
+    }
+
+    /// 保持期間を過ぎたメッセージを破棄する
+    fn prune(&mut self, now: Instant) {
+        while self
+            .entries
+            .front()
+            .is_some_and(|entry| now.duration_since(entry.cached_at) > REPLAY_CACHE_TTL)
+        {
+            self.entries.pop_front();
+        }
+    }
+}
+
+/// ## JSONメッセージにシーケンス番号を付与する
+///
+/// オブジェクトの末尾に `"seq"` フィールドを追加します。オブジェクト以外はそのまま返します。
+///
+/// ### Arguments
+/// - `json`: JSONテキスト
+/// - `seq`: シーケンス番号
+///
+/// ### Returns
+/// - `String`: シーケンス番号を付与したJSONテキスト
+pub fn with_seq(json: &str, seq: u64) -> String {
+    match json.strip_suffix('}') {
+        Some(body) if body.trim() == "{" => format!("{{\"seq\":{}}}", seq),
+        Some(body) => format!("{},\"seq\":{}}}", body, seq),
+        None => json.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_since_returns_only_missing_messages() {
+        let mut cache = ReplayCache::default();
+        let now = Instant::now();
+        let channels = HashSet::from(["general".to_string()]);
+        for channel in [None, None, None, Some("other")] {
+            let seq = cache.next_seq(Some("session"));
+            let json = with_seq(r#"{"type":"chat"}"#, seq);
+            cache.push(seq, channel, Broadcast::text(json), now);
+        }
+
+        let replay = cache.since(Some("session"), 1, &channels, now);
+        assert_eq!(replay.messages.len(), 2);
+        assert_eq!(replay.latest_seq, 4);
+        assert!(replay.complete);
+
+        // 保持期間を過ぎたメッセージは再送できない
+        let later = now + REPLAY_CACHE_TTL + Duration::from_secs(1);
+        assert!(!cache.since(Some("session"), 1, &channels, later).complete);
+        // 別の配信セッションのメッセージは再送しない
+        assert!(cache
+            .since(Some("next"), 1, &channels, now)
+            .messages
+            .is_empty());
+    }
+}
@@ -2,12 +2,35 @@
 //!
 //! WebSocketおよびOBSのHTTPルートハンドラーを提供します。
 
+use actix_web::http::header;
 use actix_web::{get, Error, HttpRequest, HttpResponse};
 use actix_web_actors::ws;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::access_token;
+use super::compression::{PerMessageDeflate, DEFAULT_COMPRESSION_LEVEL};
+use super::connection_urls::ConnectionUrls;
+use super::msgpack::MSGPACK_SUBPROTOCOL;
+use super::protobuf::PROTOBUF_SUBPROTOCOL;
+use crate::signing::SigningInfo;
+use crate::state::AppState;
+use crate::types::CapacityInfo;
+use tauri::Manager;
+
+/// 容量情報をキャッシュする期間
+const CAPACITY_CACHE_TTL: Duration = Duration::from_secs(1);
+
+/// 直近に算出した容量情報（頻繁なポーリングへの応答用）
+static CAPACITY_CACHE: Lazy<Mutex<Option<(Instant, CapacityInfo)>>> =
+    Lazy::new(|| Mutex::new(None));
 
 /// ## WebSocket ルートハンドラー
 ///
 /// WebSocket 接続リクエストを処理し、`WsSession` アクターを開始します。
+/// アクセストークンが設定されている場合は、クエリパラメータ `token` が一致しない接続を
+/// 401 Unauthorized で拒否します。
 ///
 /// ### Arguments
 /// - `req`: HTTPリクエスト (`HttpRequest`)
@@ -21,11 +44,87 @@ pub async fn websocket_route(
     stream: actix_web::web::Payload,
 ) -> Result<HttpResponse, Error> {
     println!("Received websocket upgrade request");
-    ws::start(
-        crate::ws_server::create_ws_session(req.clone()),
-        &req,
-        stream,
-    )
+    if !is_authorized_request(&req) {
+        return Ok(HttpResponse::Unauthorized().body("Invalid access token"));
+    }
+
+    // クライアントがpermessage-deflateに対応していれば圧縮を有効にする
+    let compression_level = crate::ws_server::connection_manager::global::get_app_handle()
+        .and_then(|app_handle| {
+            let app_state = app_handle.try_state::<AppState>()?;
+            let level = *app_state.ws_compression_level.lock().ok()?;
+            Some(level)
+        })
+        .unwrap_or(DEFAULT_COMPRESSION_LEVEL);
+    let deflate = PerMessageDeflate::negotiate(
+        req.headers()
+            .get_all(header::SEC_WEBSOCKET_EXTENSIONS)
+            .filter_map(|value| value.to_str().ok()),
+        compression_level,
+    );
+
+    // バイナリモードのサブプロトコルを受け入れ、ハンドシェイク応答で返す
+    let mut res = ws::handshake_with_protocols(&req, &[PROTOBUF_SUBPROTOCOL, MSGPACK_SUBPROTOCOL])?;
+    let session = crate::ws_server::create_ws_session(req.clone());
+    match deflate {
+        Some(deflate) => {
+            res.insert_header((header::SEC_WEBSOCKET_EXTENSIONS, deflate.response_header()));
+            let ws_stream = ws::WebsocketContext::create(session, deflate.inflate_stream(stream));
+            Ok(res.streaming(deflate.deflate_stream(ws_stream)))
+        }
+        None => Ok(res.streaming(ws::WebsocketContext::create(session, stream))),
+    }
+}
+
+/// ## OBSオーバーレイ用WebSocketルートハンドラー
+///
+/// OBSオーバーレイ専用のWebSocket接続リクエストを処理し、受信専用の `WsSession` アクターを開始します。
+/// この接続は視聴者の接続数・最大接続数に含めません。
+/// アクセストークンの扱いは視聴者用の `/ws` と同じです。
+///
+/// ### Arguments
+/// - `req`: HTTPリクエスト (`HttpRequest`)
+/// - `stream`: ペイロードストリーム (`actix_web::web::Payload`)
+///
+/// ### Returns
+/// - `Result<HttpResponse, Error>`: WebSocket ハンドシェイク応答 or エラー
+#[get("/obs-ws")]
+pub async fn obs_websocket_route(
+    req: HttpRequest,
+    stream: actix_web::web::Payload,
+) -> Result<HttpResponse, Error> {
+    println!("Received OBS overlay websocket upgrade request");
+    if !is_authorized_request(&req) {
+        return Ok(HttpResponse::Unauthorized().body("Invalid access token"));
+    }
+
+    let session = crate::ws_server::session::create_obs_ws_session(req.clone());
+    ws::start(session, &req, stream)
+}
+
+/// ## WebSocket接続リクエストのアクセストークンを検証する
+///
+/// アクセストークンが設定されている場合、クエリパラメータ `token` が一致しない接続を拒否します。
+///
+/// ### Arguments
+/// - `req`: HTTPリクエスト (`HttpRequest`)
+///
+/// ### Returns
+/// - `bool`: 接続を許可する場合は `true`
+fn is_authorized_request(req: &HttpRequest) -> bool {
+    let access_token =
+        crate::ws_server::connection_manager::global::get_app_handle().and_then(|app_handle| {
+            let app_state = app_handle.try_state::<AppState>()?;
+            access_token::current_access_token(&app_state)
+        });
+    if !access_token::is_authorized(access_token.as_deref(), req.query_string()) {
+        println!(
+            "Rejected websocket upgrade request: invalid access token from {:?}",
+            req.peer_addr()
+        );
+        return false;
+    }
+    true
 }
 
 /// ## OBSステータスページハンドラー
@@ -56,15 +155,31 @@ pub async fn obs_index_page() -> HttpResponse {
 
 /// ## OBSスタイルシートハンドラー
 ///
-/// OBS用のCSSファイルを提供するハンドラー
+/// OBS用のCSSファイルに現在のOBSテーマを埋め込んで提供するハンドラー。
+/// テーマの変更時に読み込み直されるため、キャッシュさせません。
 ///
 /// ### Returns
 /// - `HttpResponse`: CSS形式のスタイルシート
 #[get("/obs/styles.css")]
 pub async fn obs_styles() -> HttpResponse {
+    let theme = crate::ws_server::connection_manager::global::get_app_handle()
+        .and_then(|app_handle| {
+            let app_state = app_handle.try_state::<AppState>()?;
+            let theme = app_state.obs_theme.lock().ok()?;
+            Some(theme.clone())
+        })
+        .unwrap_or_default();
+
+    // 既定のスタイルの後ろに現在のテーマを連結して上書きする
+    let css = format!(
+        "{}\n{}",
+        include_str!("../../src/static/obs/styles.css"),
+        theme.to_css()
+    );
     HttpResponse::Ok()
         .content_type("text/css; charset=utf-8")
-        .body(include_str!("../../src/static/obs/styles.css"))
+        .insert_header(("Cache-Control", "no-store"))
+        .body(css)
 }
 
 /// ## OBSスクリプトハンドラー
@@ -79,3 +194,92 @@ pub async fn obs_script() -> HttpResponse {
         .content_type("application/javascript; charset=utf-8")
         .body(include_str!("../../src/static/obs/script.js"))
 }
+
+/// ## OBSレイアウトハンドラー
+///
+/// アクティブなOBSレイアウトをCSSとして動的に生成するハンドラー。
+/// レイアウトの切り替え時に読み込み直されるため、キャッシュさせません。
+///
+/// ### Returns
+/// - `HttpResponse`: CSS形式のレイアウト
+#[get("/obs/layout.css")]
+pub async fn obs_layout_css() -> HttpResponse {
+    let layout = crate::ws_server::connection_manager::global::get_app_handle()
+        .and_then(|app_handle| {
+            let app_state = app_handle.try_state::<AppState>()?;
+            let layouts = app_state.obs_layouts.lock().ok()?;
+            Some(layouts.active_layout())
+        })
+        .unwrap_or_default();
+
+    HttpResponse::Ok()
+        .content_type("text/css; charset=utf-8")
+        .insert_header(("Cache-Control", "no-store"))
+        .body(layout.to_css())
+}
+
+/// ## 接続情報ハンドラー
+///
+/// 視聴者フロントがスマート接続に使用する接続候補をJSONで返します。
+/// 候補はローカル→LAN→トンネルの順に並び、視聴者フロントは順に試して最初に接続できたものを使用します。
+/// メッセージ署名の検証に使用する公開鍵と、受け付けるコインのシンボル（制限しない場合はnull）も返します。
+///
+/// ### Returns
+/// - `HttpResponse`: JSON形式の接続情報
+#[get("/info")]
+pub async fn server_info() -> HttpResponse {
+    let (connection_urls, accepted_coins) =
+        crate::ws_server::connection_manager::global::get_app_handle()
+            .and_then(|app_handle| {
+                app_handle.try_state::<AppState>().map(|app_state| {
+                    let accepted_coins = app_state
+                        .accepted_coins
+                        .lock()
+                        .ok()
+                        .and_then(|coins| coins.clone());
+                    (ConnectionUrls::collect(&app_state), accepted_coins)
+                })
+            })
+            .unwrap_or_default();
+    let manager = crate::ws_server::connection_manager::global::get_manager();
+    let signing_info = SigningInfo::new(manager.signing_mode(), manager.signer().as_deref());
+
+    HttpResponse::Ok()
+        // 視聴者フロントは別オリジンから取得するため、CORSを許可する
+        .insert_header(("Access-Control-Allow-Origin", "*"))
+        .json(serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "candidates": connection_urls.candidates(),
+            "signing": signing_info,
+            "accepted_coins": accepted_coins,
+        }))
+}
+
+/// ## 容量確認ハンドラー
+///
+/// 視聴者フロントがWebSocket接続を試みる前に、新規接続を受け入れられるかを返します。
+/// 頻繁なポーリングに備え、算出結果を短時間キャッシュします。
+///
+/// ### Returns
+/// - `HttpResponse`: JSON形式の容量情報 (`accepting`・`current`・`max`)
+#[get("/capacity")]
+pub async fn capacity() -> HttpResponse {
+    let info = {
+        let mut cache = CAPACITY_CACHE.lock().unwrap();
+        match *cache {
+            Some((cached_at, info)) if cached_at.elapsed() < CAPACITY_CACHE_TTL => info,
+            _ => {
+                let info =
+                    crate::ws_server::connection_manager::global::get_manager().capacity_info();
+                *cache = Some((Instant::now(), info));
+                info
+            }
+        }
+    };
+
+    HttpResponse::Ok()
+        // 視聴者フロントは別オリジンから取得するため、CORSを許可する
+        .insert_header(("Access-Control-Allow-Origin", "*"))
+        .insert_header(("Cache-Control", "no-store"))
+        .json(info)
+}
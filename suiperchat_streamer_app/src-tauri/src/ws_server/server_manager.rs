@@ -3,38 +3,155 @@
 //! WebSocketサーバーの起動・停止・監視を行うモジュールです。
 
 use crate::database;
+use crate::milestone;
+use crate::signing::{self, MessageSigner};
 use crate::state::AppState;
-use crate::types::ServerStatus;
-use crate::ws_server::connection_manager::global::set_app_handle;
+use crate::stream_sessions;
+use crate::types::{
+    get_connections_count, DisconnectNotice, MigrationPhase, OutgoingMessage, ServerStatus,
+    StartupPhase, StartupProgress,
+};
+use crate::ws_server::access_token;
+use crate::ws_server::connection_manager::global::{get_manager, set_app_handle, set_signer};
+use crate::ws_server::connection_urls::{
+    direct_ws_url, is_lan_only, tunnel_ws_url, ConnectionUrls,
+};
+use crate::ws_server::event_logger;
+use crate::ws_server::port_recovery::ensure_port_available;
 use crate::ws_server::routes::{
-    obs_index_page, obs_script, obs_styles, status_page, websocket_route,
+    capacity, obs_index_page, obs_layout_css, obs_script, obs_styles, obs_websocket_route,
+    server_info, status_page, websocket_route,
 };
-use crate::ws_server::server_utils::{format_socket_addr, resolve_static_file_path};
+use crate::ws_server::server_utils::{
+    format_socket_addr, is_lan_exposed_host, obs_page_url, resolve_static_file_path,
+    DEFAULT_BIND_HOST, DEFAULT_OBS_PORT, DEFAULT_WS_PORT,
+};
+use crate::ws_server::tls;
 use crate::ws_server::tunnel;
+use crate::ws_server::upnp;
 use actix_files as fs;
 use actix_web::{dev::ServerHandle, web, App, HttpRequest, HttpResponse, HttpServer};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use tauri::{Emitter, Manager};
 use tokio::runtime::{Handle as TokioHandle, Runtime};
 use uuid::Uuid;
 
+/// グレースフルリスタート時に旧サーバーを停止するまでの猶予時間（秒）
+const MIGRATION_GRACE_PERIOD_SECS: u64 = 10;
+
+/// 切断通知を送信してからサーバーを停止するまでの待ち時間（ミリ秒）
+const DISCONNECT_NOTICE_FLUSH_MILLIS: u64 = 500;
+
+/// 配信者がサーバーを停止した場合に視聴者へ通知する切断の理由
+pub const STREAM_ENDED_REASON: &str = "配信が終了しました";
+
+/// バインドに失敗した場合に設定ポートの後ろで試行する最大ポート数
+const MAX_PORT_FALLBACKS: u16 = 10;
+
+/// トンネルURLの再生成中かどうか（同時に複数の再生成が走らないようにする）
+static TUNNEL_REGENERATING: AtomicBool = AtomicBool::new(false);
+
+/// 省電力モードのデフォルトの待機時間（秒）
+pub const DEFAULT_IDLE_SHUTDOWN_SECS: u64 = 300;
+
+/// 省電力モードで設定可能な待機時間の下限（秒）
+///
+/// 視聴者の一時的な切断・再接続のたびにトンネルが停止しないよう、余裕を持たせた値にします。
+pub const MIN_IDLE_SHUTDOWN_SECS: u64 = 60;
+
+/// 省電力モードで設定可能な待機時間の上限（秒）
+pub const MAX_IDLE_SHUTDOWN_SECS: u64 = 6 * 60 * 60;
+
+/// 省電力モードにより視聴者ゼロでトンネルを停止しているかどうか
+static TUNNEL_IDLE_STOPPED: AtomicBool = AtomicBool::new(false);
+
+/// 省電力モードのタイマーの世代（接続のたびに進め、待機中のタイマーを無効にする）
+static IDLE_SHUTDOWN_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// ## 省電力モード（視聴者ゼロ時のトンネル自動停止）の設定
+///
+/// トンネルを停止している間は新しい視聴者がトンネルURLから接続できないため、オプトインで有効にします。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdleShutdownConfig {
+    /// 省電力モードが有効かどうか
+    pub enabled: bool,
+    /// 接続数が0になってからトンネルを停止するまでの時間（秒）
+    pub idle_timeout_secs: u64,
+}
+
+impl Default for IdleShutdownConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_timeout_secs: DEFAULT_IDLE_SHUTDOWN_SECS,
+        }
+    }
+}
+
+impl IdleShutdownConfig {
+    /// ## トンネルを停止するまでの待機時間を検証する
+    ///
+    /// ### Arguments
+    /// - `timeout_secs`: 接続数が0になってからトンネルを停止するまでの時間（秒）
+    ///
+    /// ### Returns
+    /// - `Result<(), String>`: 範囲外の場合はエラーメッセージ
+    pub fn validate_timeout_secs(timeout_secs: u64) -> Result<(), String> {
+        if !(MIN_IDLE_SHUTDOWN_SECS..=MAX_IDLE_SHUTDOWN_SECS).contains(&timeout_secs) {
+            return Err(format!(
+                "待機時間は{}〜{}秒で指定してください: {}",
+                MIN_IDLE_SHUTDOWN_SECS, MAX_IDLE_SHUTDOWN_SECS, timeout_secs
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// ## WebSocketサーバーを起動する
 ///
 /// 指定されたホストとポートでWebSocketサーバーを非同期に起動します。
+/// TLSが有効な場合は証明書を読み込み、Cloudflaredトンネルを使わずに wss:// で待ち受けます。
+/// `use_tunnel` が `false` の場合はトンネルを起動せず、全インターフェースで待ち受けてLAN内にのみ公開します。
 ///
 /// ### Arguments
 /// - `app_state`: アプリケーション状態
 /// - `app_handle`: Tauriアプリケーションハンドル
+/// - `use_tunnel`: トンネルを起動するかどうか
 ///
 /// ### Returns
 /// - `Result<(), String>`: 成功時はOk、失敗時はエラーメッセージ
-pub fn start_server(app_state: &AppState, app_handle: tauri::AppHandle) -> Result<(), String> {
+pub fn start_server(
+    app_state: &AppState,
+    app_handle: tauri::AppHandle,
+    use_tunnel: bool,
+) -> Result<(), String> {
     let app_handle_clone = app_handle.clone();
     println!("Attempting to start WebSocket server...");
 
     // 接続マネージャーにアプリケーションハンドルを設定
     set_app_handle(app_handle.clone());
 
+    // メッセージ署名鍵を読み込む（存在しない場合は生成）
+    if get_manager().signer().is_none() {
+        let signer =
+            signing::key_path(&app_handle).and_then(|path| MessageSigner::load_or_generate(&path));
+        match signer {
+            Ok(signer) => {
+                println!(
+                    "メッセージ署名鍵を読み込みました: key_id={}",
+                    signer.key_id()
+                );
+                set_signer(signer);
+            }
+            Err(e) => eprintln!(
+                "メッセージ署名鍵の読み込みに失敗しました（署名なしで起動します）: {}",
+                e
+            ),
+        }
+    }
+
     let server_handle_arc = Arc::clone(&app_state.server_handle);
     let runtime_handle_arc = Arc::clone(&app_state.runtime_handle);
     let host_arc = Arc::clone(&app_state.host);
@@ -54,6 +171,19 @@ pub fn start_server(app_state: &AppState, app_handle: tauri::AppHandle) -> Resul
         }
     }
 
+    // TLSが有効な場合は起動前に証明書を読み込んで検証する
+    let tls_server_config = load_tls_server_config(app_state)?;
+
+    *app_state
+        .use_tunnel
+        .lock()
+        .map_err(|_| "Failed to lock use tunnel mutex".to_string())? = use_tunnel;
+
+    // 起動フェーズの進捗を初期化（TLS有効時・LAN内公開モードではトンネルを起動しない）
+    update_startup_progress(&app_handle, |progress| {
+        *progress = StartupProgress::begin(tls_server_config.is_none() && use_tunnel);
+    });
+
     // サーバーを別スレッドで起動
     std::thread::spawn(move || {
         launch_server_runtime(
@@ -62,6 +192,7 @@ pub fn start_server(app_state: &AppState, app_handle: tauri::AppHandle) -> Resul
             host_arc,
             port_arc,
             obs_port_arc,
+            tls_server_config,
             app_handle_clone,
         );
     });
@@ -72,16 +203,27 @@ pub fn start_server(app_state: &AppState, app_handle: tauri::AppHandle) -> Resul
 /// ## WebSocketサーバーを停止する
 ///
 /// 実行中のWebSocketサーバーを停止します。
+/// 停止前に、接続中の視聴者へ `reason` を含む切断通知を送信します。
 ///
 /// ### Arguments
 /// - `app_state`: アプリケーション状態
 /// - `app_handle`: Tauriアプリケーションハンドル
+/// - `reason`: 視聴者に通知する切断の理由
 ///
 /// ### Returns
 /// - `Result<(), String>`: 成功時はOk、失敗時はエラーメッセージ
-pub fn stop_server(app_state: &AppState, app_handle: tauri::AppHandle) -> Result<(), String> {
+pub fn stop_server(
+    app_state: &AppState,
+    app_handle: tauri::AppHandle,
+    reason: &str,
+) -> Result<(), String> {
     println!("Attempting to stop WebSocket server...");
 
+    // 明示的な停止のため起動フェーズの進捗（失敗情報を含む）をリセット
+    update_startup_progress(&app_handle, |progress| {
+        *progress = StartupProgress::default();
+    });
+
     let server_handles_option: Option<(ServerHandle, ServerHandle)>;
     let runtime_handle_option: Option<TokioHandle>;
 
@@ -110,16 +252,25 @@ pub fn stop_server(app_state: &AppState, app_handle: tauri::AppHandle) -> Result
         tunnel_guard.take()
     };
 
-    // 現在のセッションIDを取得
-    let session_id_option = match app_state.current_session_id.lock() {
-        Ok(session_id_guard) => {
+    // 現在のセッションIDを取り出してクリア（同時配信のチャンネルごとのセッションもあわせて終了する）
+    let (session_id_option, channel_session_ids) = match app_state.current_session_id.lock() {
+        Ok(mut session_id_guard) => {
+            let mut sessions = std::mem::take(&mut *session_id_guard);
+            let session_id = sessions.remove(stream_sessions::PRIMARY_SESSION_CHANNEL);
             // セッションIDが存在する場合はログ出力
-            if let Some(ref session_id) = *session_id_guard {
+            if let Some(ref session_id) = session_id {
                 println!("現在のセッションID: {} - 終了処理を準備します", session_id);
             } else {
                 println!("セッションIDが設定されていません - 終了処理はスキップされます");
             }
-            session_id_guard.clone()
+            let channel_session_ids: Vec<String> = sessions.into_values().collect();
+            if !channel_session_ids.is_empty() {
+                println!(
+                    "チャンネルごとのセッションをクリアします: {:?}",
+                    channel_session_ids
+                );
+            }
+            (session_id, channel_session_ids)
         }
         Err(e) => {
             // ロックエラーの場合はエラーログを出力し、None を返す
@@ -127,7 +278,7 @@ pub fn stop_server(app_state: &AppState, app_handle: tauri::AppHandle) -> Result
                 "セッションID取得のためのロックに失敗しました: {} - セッション終了処理をスキップします",
                 e
             );
-            None
+            (None, Vec::new())
         }
     };
 
@@ -150,19 +301,18 @@ pub fn stop_server(app_state: &AppState, app_handle: tauri::AppHandle) -> Result
         }
     };
 
-    // セッションIDをクリア
-    match app_state.current_session_id.lock() {
-        Ok(mut session_id_guard) => {
-            if session_id_guard.is_some() {
-                println!("セッションID: {:?} をクリアします", *session_id_guard);
-                *session_id_guard = None;
-            }
-        }
-        Err(e) => {
-            eprintln!("セッションIDクリアのためのロックに失敗しました: {}", e);
-            // 処理は継続
-        }
-    }
+    // 視聴者数の推移をクリアし、まだ確定していない最後のサンプルを取り出す
+    // （確定したサンプルは記録時にDBへ保存済みのため、セッション終了時は最後のサンプルのみ保存する）
+    let viewer_count_history: Vec<_> = app_state
+        .viewer_count_history
+        .lock()
+        .map(|mut history| std::mem::take(&mut *history).pop())
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+
+    // 再接続用の再送キャッシュを破棄
+    get_manager().clear_replay_cache();
 
     if let Some((ws_server_handle, obs_server_handle)) = server_handles_option {
         if let Some(runtime_handle) = runtime_handle_option {
@@ -188,6 +338,9 @@ pub fn stop_server(app_state: &AppState, app_handle: tauri::AppHandle) -> Result
                 println!("No active Cloudflared tunnel to stop.");
             }
 
+            // UPnPで開放したポートを閉じる
+            runtime_handle.spawn(upnp::remove_port_forward());
+
             // セッション終了処理
             let has_valid_session_id = session_id_option.is_some();
             let has_valid_db_pool = db_pool_option.is_some();
@@ -204,9 +357,36 @@ pub fn stop_server(app_state: &AppState, app_handle: tauri::AppHandle) -> Result
                     // 非同期でセッション終了処理
                     let session_id_clone = session_id.clone();
                     let db_pool_clone = db_pool.clone();
+                    let app_handle_for_vacuum = app_handle.clone();
                     runtime_handle.spawn(async move {
+                        for channel_session_id in &channel_session_ids {
+                            if let Err(e) =
+                                database::end_session(&db_pool_clone, channel_session_id).await
+                            {
+                                eprintln!(
+                                    "チャンネルのセッション終了処理中にエラーが発生しました ({}): {}",
+                                    channel_session_id, e
+                                );
+                            }
+                        }
                         match database::end_session(&db_pool_clone, &session_id_clone).await {
-                            Ok(_) => println!("セッションが正常に終了しました: {}", session_id_clone),
+                            Ok(_) => {
+                                println!("セッションが正常に終了しました: {}", session_id_clone);
+                                // 後から振り返れるよう視聴者数の推移を保存
+                                if let Err(e) = database::save_viewer_count_history(
+                                    &db_pool_clone,
+                                    &session_id_clone,
+                                    &viewer_count_history,
+                                )
+                                .await
+                                {
+                                    eprintln!("視聴者数の推移の保存中にエラーが発生しました: {}", e);
+                                }
+                                // セッション終了後は配信に影響しないため、必要に応じてVACUUMを実行
+                                tauri::async_runtime::spawn(async move {
+                                    crate::db_vacuum::run_if_idle(&app_handle_for_vacuum).await;
+                                });
+                            }
                             Err(e) => {
                                 let error_msg = format!("セッション終了処理中にエラーが発生しました: {}", e);
                                 eprintln!("エラー: {}", error_msg);
@@ -241,9 +421,22 @@ pub fn stop_server(app_state: &AppState, app_handle: tauri::AppHandle) -> Result
                 }
             }
 
+            // 視聴者が再接続を繰り返さないよう、切断前に停止の理由を通知
+            match serde_json::to_string(&DisconnectNotice::new(reason, false)) {
+                Ok(json) => get_manager().broadcast(&json),
+                Err(e) => eprintln!("切断通知のシリアライズに失敗: {}", e),
+            }
+            // 再起動後に幽霊接続が残らないよう、接続情報をリセット
+            get_manager().reset();
+
             // 両方のサーバーを停止するタスクをspawn
             let app_handle_clone = app_handle.clone();
             runtime_handle.spawn(async move {
+                // 切断通知が送信されるのを待ってから停止
+                tokio::time::sleep(std::time::Duration::from_millis(
+                    DISCONNECT_NOTICE_FLUSH_MILLIS,
+                ))
+                .await;
                 println!("Sending stop signal to WS and OBS servers via Tokio runtime handle...");
                 // 両方の stop を並行して実行
                 let ws_stop = ws_server_handle.stop(true);
@@ -267,6 +460,520 @@ pub fn stop_server(app_state: &AppState, app_handle: tauri::AppHandle) -> Result
     }
 }
 
+/// ## WebSocketサーバーをグレースフルリスタートする
+///
+/// 設定ポート以降の空いているポートで新しいWebSocketサーバーを起動し、既存接続に `type: "migrate"` で
+/// 新しい接続先URLを通知した後、猶予時間を置いて旧サーバーを停止します。
+/// セッションIDとDB接続プールは `AppState` に保持されたものをそのまま引き継ぎます。
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+/// - `app_handle`: Tauriアプリケーションハンドル
+///
+/// ### Returns
+/// - `Result<(), String>`: 移行処理の開始に成功した場合はOk、失敗時はエラーメッセージ
+pub fn graceful_restart(app_state: &AppState, app_handle: tauri::AppHandle) -> Result<(), String> {
+    println!("Attempting graceful restart of WebSocket server...");
+
+    // 移行中でないこと・サーバーが起動中であることを確認し、移行フェーズを開始
+    {
+        let mut phase_guard = app_state
+            .migration_phase
+            .lock()
+            .map_err(|_| "Failed to lock migration phase mutex".to_string())?;
+        if *phase_guard != MigrationPhase::Idle {
+            return Err("Graceful restart is already in progress.".to_string());
+        }
+
+        let handle_guard = app_state
+            .server_handle
+            .lock()
+            .map_err(|_| "Failed to lock server handle mutex for checking".to_string())?;
+        if handle_guard.is_none() {
+            return Err("WebSocket server is not running.".to_string());
+        }
+
+        *phase_guard = MigrationPhase::StartingNewServer;
+    }
+
+    let runtime_handle = match app_state.runtime_handle.lock() {
+        Ok(guard) => guard.clone(),
+        Err(_) => None,
+    };
+    let runtime_handle = match runtime_handle {
+        Some(handle) => handle,
+        None => {
+            set_migration_phase(&app_handle, MigrationPhase::Idle);
+            return Err("No runtime handle available to restart the server.".to_string());
+        }
+    };
+
+    set_migration_phase(&app_handle, MigrationPhase::StartingNewServer);
+
+    runtime_handle.spawn(async move {
+        match run_graceful_restart(&app_handle).await {
+            Ok(_) => println!("Graceful restart completed."),
+            Err(e) => eprintln!("グレースフルリスタートに失敗しました: {}", e),
+        }
+        set_migration_phase(&app_handle, MigrationPhase::Idle);
+    });
+
+    Ok(())
+}
+
+/// ## グレースフルリスタートの本体処理
+///
+/// 新サーバーの起動、AppStateの差し替え、移行通知、旧サーバーの停止を順に行います。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功時はOk、失敗時はエラーメッセージ
+async fn run_graceful_restart(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let app_state = app_handle.state::<AppState>();
+
+    let host = app_state
+        .host
+        .lock()
+        .map_err(|_| "Failed to lock host mutex".to_string())?
+        .clone()
+        .unwrap_or_else(|| "127.0.0.1".to_string());
+
+    let bind_host = app_state
+        .bind_host
+        .lock()
+        .map_err(|_| "Failed to lock bind host mutex".to_string())?
+        .clone();
+
+    // TLSが有効な場合は新サーバーも同じ証明書で起動する
+    let tls_server_config = load_tls_server_config(&app_state)?;
+    let ws_bind_host =
+        if tls_server_config.is_some() || is_lan_only(&app_state) || upnp::is_enabled(&app_state) {
+            "0.0.0.0"
+        } else {
+            bind_host.as_str()
+        };
+
+    // 新しいWebSocketサーバーを設定ポートの範囲内で起動
+    // (旧サーバーが使用中のポートは使用中として飛ばされる。移行中に自アプリのプロセスを
+    // 終了させないよう、ポートの自動解放は行わない)
+    // HttpServer は Send ではないため、クロージャ内で run まで行う
+    let ws_port = app_state
+        .configured_ws_port
+        .lock()
+        .ok()
+        .and_then(|port| *port)
+        .unwrap_or(DEFAULT_WS_PORT);
+    let (new_server_runner, new_port) =
+        bind_with_port_fallback("New WebSocket", ws_bind_host, ws_port, false, |port| {
+            let ws_app_factory = || App::new().configure(configure_ws_app);
+            let new_server = match tls_server_config.clone() {
+                Some(tls_config) => HttpServer::new(ws_app_factory)
+                    .bind_rustls_0_23((ws_bind_host, port), tls_config),
+                None => HttpServer::new(ws_app_factory).bind((ws_bind_host, port)),
+            }?;
+            Ok(new_server.run())
+        })
+        .await?;
+    let new_server_handle = new_server_runner.handle();
+    tokio::spawn(async move {
+        if let Err(e) = new_server_runner.await {
+            eprintln!("New WebSocket server execution error: {}", e);
+        }
+    });
+    println!("New WebSocket server started on port {}", new_port);
+
+    // トンネルが稼働中の場合は新しいポート向けのトンネルを起動
+    let tunnel_running = matches!(
+        &*app_state
+            .tunnel_info
+            .lock()
+            .map_err(|_| "Failed to lock tunnel info mutex".to_string())?,
+        Some(Ok(_))
+    );
+    let new_tunnel = if tunnel_running {
+        match tunnel::start_tunnel(app_handle, new_port).await {
+            Ok(tunnel_info) => Some(tunnel_info),
+            Err(e) => {
+                // 外部視聴者が新サーバーへ到達できないため、移行を中止する
+                new_server_handle.stop(true).await;
+                return Err(format!("Failed to start tunnel for new port: {}", e));
+            }
+        }
+    } else {
+        None
+    };
+
+    // UPnPでポートを開放している場合は新しいポートも開放する（旧ポートのマッピングは削除される）
+    if upnp::active_external_port().is_some() {
+        if let Err(e) = upnp::try_port_forward(new_port).await {
+            new_server_handle.stop(true).await;
+            if let Some(tunnel_info) = new_tunnel {
+                tunnel::stop_tunnel(&tunnel_info).await;
+            }
+            return Err(format!("Failed to forward new port via UPnP: {}", e));
+        }
+    }
+
+    let new_ws_url = match &new_tunnel {
+        Some(tunnel_info) => tunnel_ws_url(&tunnel_info.url),
+        None => upnp::upnp_ws_url(&app_state)
+            .unwrap_or_else(|| direct_ws_url(&app_state, &host, new_port)),
+    };
+    // 既存接続の再接続先にもアクセストークンを付与する
+    let new_ws_url = access_token::with_access_token(
+        new_ws_url,
+        access_token::current_access_token(&app_state).as_deref(),
+    );
+
+    // AppStateのサーバーハンドルを新サーバーのものに差し替え
+    let old_ws_handle = match app_state.server_handle.lock() {
+        Ok(mut handle_guard) => handle_guard
+            .as_mut()
+            .map(|(ws_handle, _)| std::mem::replace(ws_handle, new_server_handle.clone())),
+        Err(_) => None,
+    };
+    let old_ws_handle = match old_ws_handle {
+        Some(handle) => handle,
+        None => {
+            // 移行中にサーバーが停止された場合は新サーバーも停止する
+            new_server_handle.stop(true).await;
+            if let Some(tunnel_info) = new_tunnel {
+                tunnel::stop_tunnel(&tunnel_info).await;
+            }
+            return Err("WebSocket server was stopped during graceful restart.".to_string());
+        }
+    };
+
+    if let Ok(mut port_guard) = app_state.port.lock() {
+        *port_guard = Some(new_port);
+    }
+    let old_tunnel = match new_tunnel {
+        Some(tunnel_info) => match app_state.tunnel_info.lock() {
+            Ok(mut tunnel_guard) => tunnel_guard.replace(Ok(tunnel_info)),
+            Err(_) => None,
+        },
+        None => None,
+    };
+
+    // 既存接続に新しい接続先を通知
+    set_migration_phase(app_handle, MigrationPhase::Migrating);
+    emit_server_status_with_tunnel(app_handle);
+
+    let migrate_message = OutgoingMessage::Migrate {
+        url: new_ws_url.clone(),
+        grace_period_secs: MIGRATION_GRACE_PERIOD_SECS,
+    };
+    match serde_json::to_string(&migrate_message) {
+        Ok(json) => get_manager().broadcast(&json),
+        Err(e) => eprintln!("移行メッセージのシリアライズに失敗: {}", e),
+    }
+    println!(
+        "Migration notice sent: {} (grace period {}s)",
+        new_ws_url, MIGRATION_GRACE_PERIOD_SECS
+    );
+
+    tokio::time::sleep(std::time::Duration::from_secs(MIGRATION_GRACE_PERIOD_SECS)).await;
+
+    // 旧サーバーと旧トンネルを停止
+    set_migration_phase(app_handle, MigrationPhase::StoppingOldServer);
+    old_ws_handle.stop(true).await;
+    println!("Old WebSocket server stopped.");
+
+    if let Some(Ok(old_tunnel_info)) = old_tunnel {
+        tunnel::stop_tunnel(&old_tunnel_info).await;
+        println!("Old Cloudflared tunnel stopped.");
+    }
+
+    Ok(())
+}
+
+/// ## トンネルURLを再生成する
+///
+/// WebSocketサーバーは止めずにトンネルプロセスだけを停止・再起動し、新しいURLを取得します。
+/// 再生成中はトンネルの状態を "Starting" として通知し、完了後に新しいURLを含む状態を通知します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+///
+/// ### Returns
+/// - `Result<String, String>`: 成功時は新しいトンネルURL、失敗時はエラーメッセージ
+pub async fn regenerate_tunnel_url(app_handle: tauri::AppHandle) -> Result<String, String> {
+    if TUNNEL_REGENERATING.swap(true, Ordering::SeqCst) {
+        return Err("Tunnel URL regeneration is already in progress.".to_string());
+    }
+    let result = run_regenerate_tunnel_url(&app_handle).await;
+    TUNNEL_REGENERATING.store(false, Ordering::SeqCst);
+    result
+}
+
+/// ## トンネルURL再生成の本体処理
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+///
+/// ### Returns
+/// - `Result<String, String>`: 成功時は新しいトンネルURL、失敗時はエラーメッセージ
+async fn run_regenerate_tunnel_url(app_handle: &tauri::AppHandle) -> Result<String, String> {
+    let app_state = app_handle.state::<AppState>();
+
+    let is_running = app_state
+        .server_handle
+        .lock()
+        .map_err(|_| "Failed to lock server handle mutex for checking".to_string())?
+        .is_some();
+    if !is_running {
+        return Err("WebSocket server is not running.".to_string());
+    }
+    if *app_state
+        .migration_phase
+        .lock()
+        .map_err(|_| "Failed to lock migration phase mutex".to_string())?
+        != MigrationPhase::Idle
+    {
+        return Err("Cannot regenerate tunnel URL during graceful restart.".to_string());
+    }
+
+    let tls_enabled = app_state
+        .tls_config
+        .lock()
+        .map(|tls_config| tls_config.enabled)
+        .unwrap_or(false);
+    if tls_enabled || is_lan_only(&app_state) || upnp::active_external_port().is_some() {
+        return Err("Tunnel is not used in the current connection mode.".to_string());
+    }
+
+    let ws_port = app_state
+        .port
+        .lock()
+        .map_err(|_| "Failed to lock port mutex".to_string())?
+        .ok_or_else(|| "WebSocket server port is not available.".to_string())?;
+    let runtime_handle = app_state
+        .runtime_handle
+        .lock()
+        .map_err(|_| "Failed to lock runtime handle mutex".to_string())?
+        .clone()
+        .ok_or_else(|| "No runtime handle available to restart the tunnel.".to_string())?;
+
+    // 既存のトンネルを取り出し、再生成中であることを通知
+    let old_tunnel = {
+        let mut tunnel_guard = app_state
+            .tunnel_info
+            .lock()
+            .map_err(|_| "Failed to lock tunnel info mutex".to_string())?;
+        match tunnel_guard.take() {
+            Some(Ok(tunnel_info)) => Some(tunnel_info),
+            Some(Err(_)) => None,
+            None => return Err("Tunnel is not running.".to_string()),
+        }
+    };
+    println!("Regenerating tunnel URL for WebSocket port {}...", ws_port);
+    emit_server_status_with_tunnel(app_handle);
+
+    // トンネルプロセスはサーバーと同じランタイム上で管理する
+    let restart_app_handle = app_handle.clone();
+    let result = runtime_handle
+        .spawn(async move {
+            tunnel::restart_tunnel_only(&restart_app_handle, old_tunnel, ws_port).await
+        })
+        .await
+        .map_err(|e| format!("Tunnel restart task failed: {}", e))?;
+
+    let new_url = match result {
+        Ok(tunnel_info) => {
+            let url = tunnel_info.url.clone();
+            println!("Tunnel URL regenerated: {}", url);
+            if let Ok(mut tunnel_guard) = app_state.tunnel_info.lock() {
+                *tunnel_guard = Some(Ok(tunnel_info));
+            }
+            Ok(url)
+        }
+        Err(e) => {
+            eprintln!("Failed to regenerate tunnel URL: {}", e);
+            let message = format!("Failed to regenerate tunnel URL: {}", e);
+            if let Ok(mut tunnel_guard) = app_state.tunnel_info.lock() {
+                *tunnel_guard = Some(Err(e));
+            }
+            Err(message)
+        }
+    };
+
+    // 新しいURLをフロントエンドに通知
+    emit_server_status_with_tunnel(app_handle);
+    new_url
+}
+
+/// ## 接続数が0になった後のトンネル自動停止を予約する
+///
+/// 省電力モードが有効な場合、待機時間の経過後も接続数が0のままであればトンネルを停止します。
+/// 待機中に新しい接続があった場合は停止しません。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+pub fn schedule_idle_tunnel_shutdown(app_handle: &tauri::AppHandle) {
+    let Some(app_state) = app_handle.try_state::<AppState>() else {
+        return;
+    };
+    let config = app_state
+        .idle_shutdown
+        .lock()
+        .map(|config| *config)
+        .unwrap_or_default();
+    if !config.enabled || get_connections_count() > 0 {
+        return;
+    }
+    let Some(runtime_handle) = app_state
+        .runtime_handle
+        .lock()
+        .ok()
+        .and_then(|handle| handle.clone())
+    else {
+        return;
+    };
+
+    let generation = IDLE_SHUTDOWN_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    println!(
+        "視聴者がいないため、{}秒後にトンネルを停止します（省電力モード）",
+        config.idle_timeout_secs
+    );
+    let app_handle = app_handle.clone();
+    runtime_handle.spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(config.idle_timeout_secs)).await;
+        if IDLE_SHUTDOWN_GENERATION.load(Ordering::SeqCst) != generation
+            || get_connections_count() > 0
+        {
+            return;
+        }
+        stop_tunnel_for_idle(&app_handle).await;
+    });
+}
+
+/// ## 接続があったことを省電力モードに通知する
+///
+/// 待機中のトンネル自動停止を取り消し、省電力モードでトンネルを停止している場合は再開します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+pub fn cancel_idle_tunnel_shutdown(app_handle: &tauri::AppHandle) {
+    IDLE_SHUTDOWN_GENERATION.fetch_add(1, Ordering::SeqCst);
+    if !TUNNEL_IDLE_STOPPED.swap(false, Ordering::SeqCst) {
+        return;
+    }
+
+    let Some(app_state) = app_handle.try_state::<AppState>() else {
+        return;
+    };
+    let ws_port = app_state.port.lock().ok().and_then(|port| *port);
+    let runtime_handle = app_state
+        .runtime_handle
+        .lock()
+        .ok()
+        .and_then(|handle| handle.clone());
+    let (Some(ws_port), Some(runtime_handle)) = (ws_port, runtime_handle) else {
+        return;
+    };
+
+    println!("接続があったため、省電力モードで停止していたトンネルを再開します");
+    let app_handle = app_handle.clone();
+    runtime_handle.spawn(async move {
+        start_tunnel_and_report(&app_handle, ws_port).await;
+    });
+}
+
+/// 接続数が0のまま待機時間が経過したため、稼働中のトンネルを停止する
+async fn stop_tunnel_for_idle(app_handle: &tauri::AppHandle) {
+    let app_state = app_handle.state::<AppState>();
+    let still_enabled = app_state
+        .idle_shutdown
+        .lock()
+        .is_ok_and(|config| config.enabled);
+    let migrating = app_state
+        .migration_phase
+        .lock()
+        .map_or(true, |phase| *phase != MigrationPhase::Idle);
+    if !still_enabled || migrating || TUNNEL_REGENERATING.load(Ordering::SeqCst) {
+        return;
+    }
+
+    let tunnel_info = {
+        let Ok(mut tunnel_guard) = app_state.tunnel_info.lock() else {
+            return;
+        };
+        match tunnel_guard.take() {
+            Some(Ok(tunnel_info)) => tunnel_info,
+            other => {
+                *tunnel_guard = other;
+                return;
+            }
+        }
+    };
+
+    println!(
+        "視聴者がいない状態が続いたため、トンネルを停止しました（省電力モード）: {}",
+        tunnel_info.url
+    );
+    TUNNEL_IDLE_STOPPED.store(true, Ordering::SeqCst);
+    tunnel::stop_tunnel(&tunnel_info).await;
+    emit_server_status_with_tunnel(app_handle);
+}
+
+/// ## 移行フェーズを更新する
+///
+/// AppStateの移行フェーズを更新し、`server_migration_updated` イベントを発行します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `phase`: 新しい移行フェーズ
+fn set_migration_phase(app_handle: &tauri::AppHandle, phase: MigrationPhase) {
+    if let Ok(mut phase_guard) = app_handle.state::<AppState>().migration_phase.lock() {
+        *phase_guard = phase;
+    }
+
+    if let Err(e) = app_handle.emit("server_migration_updated", phase) {
+        eprintln!("Failed to emit server migration event: {}", e);
+    }
+}
+
+/// ## 起動フェーズの進捗を更新する
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `updater`: StartupProgressを更新する関数
+fn update_startup_progress<F>(app_handle: &tauri::AppHandle, updater: F)
+where
+    F: FnOnce(&mut StartupProgress),
+{
+    if let Ok(mut progress_guard) = app_handle.state::<AppState>().startup_progress.lock() {
+        updater(&mut progress_guard);
+    }
+}
+
+/// ## 起動フェーズ情報を取得する
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+///
+/// ### Returns
+/// - `(String, Option<String>, Option<String>)`: 現在のフェーズ、失敗したフェーズ、エラーメッセージ
+fn startup_phase_info(app_state: &AppState) -> (String, Option<String>, Option<String>) {
+    let progress = app_state
+        .startup_progress
+        .lock()
+        .map(|progress| progress.clone())
+        .unwrap_or_default();
+
+    let (failed_phase, startup_error) = match progress.failure.as_ref() {
+        Some((phase, error)) => (Some(phase.as_str().to_string()), Some(error.clone())),
+        None => (None, None),
+    };
+    (
+        progress.current_phase().as_str().to_string(),
+        failed_phase,
+        startup_error,
+    )
+}
+
 /// ## サーバー状態通知イベント発行
 ///
 /// サーバーの状態を通知するイベントを発行します。
@@ -286,6 +993,7 @@ fn emit_server_status(
     let app_state = app_handle.state::<AppState>();
     let cgnat_detected = *app_state.cgnat_detected.lock().unwrap();
     let global_ip_fetch_failed = *app_state.global_ip_fetch_failed.lock().unwrap();
+    let (startup_phase, failed_phase, startup_error) = startup_phase_info(&app_state);
 
     // ServerStatusを構築
     let status = ServerStatus {
@@ -295,14 +1003,24 @@ fn emit_server_status(
         global_ip_fetch_failed,
         cgnat_detected,
         cloudflare_http_url: None,
+        local_ws_url: None,
+        lan_ws_url: None,
+        tunnel_ws_url: None,
+        upnp_ws_url: None,
         tunnel_status: if is_running {
             "Starting".to_string()
         } else {
             "Stopped".to_string()
         },
         tunnel_error: None,
+        startup_phase,
+        failed_phase,
+        startup_error,
     };
 
+    // 調査用にログファイルへ記録
+    event_logger::log_server_status(app_handle, &status);
+
     // イベント発行
     if let Err(e) = app_handle.emit("server_status_updated", status) {
         eprintln!("Failed to emit server status event: {}", e);
@@ -338,6 +1056,7 @@ fn launch_server_runtime(
     host_arc: Arc<Mutex<Option<String>>>,
     port_arc: Arc<Mutex<Option<u16>>>,
     obs_port_arc: Arc<Mutex<Option<u16>>>,
+    tls_server_config: Option<rustls::ServerConfig>,
     app_handle: tauri::AppHandle,
 ) {
     // Tokioランタイムの作成
@@ -345,6 +1064,13 @@ fn launch_server_runtime(
         Ok(rt) => rt,
         Err(e) => {
             eprintln!("Failed to create Tokio runtime: {}", e);
+            update_startup_progress(&app_handle, |progress| {
+                progress.active = false;
+                progress.fail(
+                    StartupPhase::Binding,
+                    format!("Failed to create Tokio runtime: {}", e),
+                );
+            });
             // 起動失敗イベントを発行
             emit_server_status(&app_handle, false, None, None);
             return;
@@ -369,6 +1095,7 @@ fn launch_server_runtime(
             port_arc,
             obs_port_arc,
             runtime_handle_arc,
+            tls_server_config,
             app_handle,
         )
         .await;
@@ -380,9 +1107,11 @@ fn launch_server_runtime(
 /// ## サーバーを実行する
 ///
 /// WebSocketサーバーとOBSサーバーを並列に実行します。
+/// TLS設定が渡された場合、WebSocketサーバーは全インターフェースで wss:// として待ち受けます。
 ///
 /// ### Arguments
 /// - 各種状態保持用のArc<Mutex>
+/// - `tls_server_config`: TLS有効時のrustlsサーバー設定（無効時は `None`）
 /// - `app_handle`: Tauriアプリケーションハンドル
 async fn run_servers(
     server_handle_arc: Arc<Mutex<Option<(ServerHandle, ServerHandle)>>>,
@@ -390,24 +1119,97 @@ async fn run_servers(
     port_arc: Arc<Mutex<Option<u16>>>,
     obs_port_arc: Arc<Mutex<Option<u16>>>,
     runtime_handle_arc: Arc<Mutex<Option<TokioHandle>>>,
+    tls_server_config: Option<rustls::ServerConfig>,
     app_handle: tauri::AppHandle,
 ) {
-    let host = "127.0.0.1";
-    let ws_port = 8082; // WebSocket用ポート（視聴者用）
-    let obs_port = 8081; // OBS用静的ファイル配信ポート
+    // 配信者が設定したバインドホスト・ポート（未設定の場合はデフォルト）を使用
+    let (bind_host, ws_port, obs_port, auto_release_ports, lan_only) = {
+        let app_state = app_handle.state::<AppState>();
+        let bind_host = app_state
+            .bind_host
+            .lock()
+            .map(|host| host.clone())
+            .unwrap_or_else(|_| DEFAULT_BIND_HOST.to_string());
+        let ws_port = app_state
+            .configured_ws_port
+            .lock()
+            .ok()
+            .and_then(|port| *port)
+            .unwrap_or(DEFAULT_WS_PORT); // WebSocket用ポート（視聴者用）
+        let obs_port = app_state
+            .configured_obs_port
+            .lock()
+            .ok()
+            .and_then(|port| *port)
+            .unwrap_or(DEFAULT_OBS_PORT); // OBS用静的ファイル配信ポート
+        let auto_release_ports = app_state
+            .auto_release_ports
+            .lock()
+            .is_ok_and(|enabled| *enabled);
+        (
+            bind_host,
+            ws_port,
+            obs_port,
+            auto_release_ports,
+            is_lan_only(&app_state),
+        )
+    };
+    // 全インターフェースで待ち受ける場合も、配信者のPCからはループバックアドレスで接続する
+    let lan_exposed = is_lan_exposed_host(&bind_host);
+    let host = if lan_exposed {
+        DEFAULT_BIND_HOST
+    } else {
+        bind_host.as_str()
+    };
+    if lan_exposed {
+        let has_access_token = app_handle
+            .state::<AppState>()
+            .access_token
+            .lock()
+            .is_ok_and(|token| token.is_some());
+        eprintln!(
+            "警告: サーバーを {} にバインドするため、LAN内の他の端末から接続できます。",
+            bind_host
+        );
+        if !has_access_token {
+            eprintln!("警告: アクセストークンが設定されていません。LANに公開する場合はアクセストークンの設定を推奨します。");
+        }
+    }
+    let use_upnp = upnp::is_enabled(&app_handle.state::<AppState>());
     let ws_path = "/ws";
+    let tls_enabled = tls_server_config.is_some();
+    // TLS有効時・LAN内公開モード・UPnP使用時は外部から直接接続されるため全インターフェースで待ち受ける
+    let ws_bind_host = if tls_enabled || lan_only || use_upnp {
+        "0.0.0.0"
+    } else {
+        bind_host.as_str()
+    };
+    let ws_scheme = if tls_enabled { "wss" } else { "ws" };
 
     println!(
-        "Starting WebSocket server at ws://{}:{}{}",
-        host, ws_port, ws_path
+        "Starting WebSocket server at {}://{}:{}{}",
+        ws_scheme, ws_bind_host, ws_port, ws_path
+    );
+    println!(
+        "Starting OBS server at http://{}:{}/obs/",
+        bind_host, obs_port
     );
-    println!("Starting OBS server at http://{}:{}/obs/", host, obs_port);
     println!("Note: Client connections MUST include the '/ws' path");
 
+    // 前回のサーバーで予約・停止した省電力モードの状態を引き継がない
+    IDLE_SHUTDOWN_GENERATION.fetch_add(1, Ordering::SeqCst);
+    TUNNEL_IDLE_STOPPED.store(false, Ordering::SeqCst);
+
     // フロントエンドにトンネル起動中のステータスを通知
     let _ = send_current_server_status(app_handle.clone());
     println!("Tunnel startup in progress notification sent to frontend.");
 
+    // データベース接続の定期ヘルスチェックを開始
+    crate::db_health::spawn_health_check(app_handle.clone());
+
+    // アイドル接続の監視を開始
+    crate::ws_server::idle_monitor::spawn_idle_monitor(app_handle.clone());
+
     // 外部IP取得とCGNAT判定処理を非同期で実行
     let app_handle_clone = app_handle.clone();
     tokio::spawn(async move {
@@ -423,6 +1225,12 @@ async fn run_servers(
                     *external_ip_guard = Some(ip);
                 }
 
+                // IP取得完了（CGNAT判定フェーズへ）を通知
+                update_startup_progress(&app_handle_clone, |progress| {
+                    progress.ip_fetched = true;
+                });
+                emit_server_status_with_tunnel(&app_handle_clone);
+
                 // 失敗フラグをfalseに設定
                 {
                     let mut failed_guard = app_state.global_ip_fetch_failed.lock().unwrap();
@@ -467,6 +1275,12 @@ async fn run_servers(
             }
         }
 
+        // IP取得失敗時も含め、IP取得・CGNAT判定フェーズを完了とする
+        update_startup_progress(&app_handle_clone, |progress| {
+            progress.ip_fetched = true;
+            progress.cgnat_checked = true;
+        });
+
         // 新しいクローンを作成
         let app_handle_for_status = app_handle_clone.clone();
         send_current_server_status(app_handle_for_status).unwrap_or_else(|e| {
@@ -474,48 +1288,6 @@ async fn run_servers(
         });
     });
 
-    // Cloudflaredトンネルを必ず起動（WebSocketサーバー起動前）
-    println!(
-        "Starting Cloudflared tunnel for WebSocket port {}...",
-        ws_port
-    );
-    let app_handle_for_tunnel = app_handle.clone();
-
-    // トンネル起動処理を非同期で実行
-    tokio::spawn(async move {
-        match tunnel::start_tunnel(&app_handle_for_tunnel, ws_port).await {
-            Ok(tunnel_info) => {
-                println!(
-                    "Cloudflared tunnel started successfully at: {}",
-                    tunnel_info.url
-                );
-
-                // トンネル情報をAppStateに保存
-                if let Ok(mut tunnel_guard) =
-                    app_handle_for_tunnel.state::<AppState>().tunnel_info.lock()
-                {
-                    *tunnel_guard = Some(Ok(tunnel_info));
-                }
-
-                // サーバー状態変更イベントを発行
-                emit_server_status_with_tunnel(&app_handle_for_tunnel);
-            }
-            Err(e) => {
-                eprintln!("Failed to start Cloudflared tunnel: {}", e);
-
-                // エラー情報をAppStateに保存
-                if let Ok(mut tunnel_guard) =
-                    app_handle_for_tunnel.state::<AppState>().tunnel_info.lock()
-                {
-                    *tunnel_guard = Some(Err(e));
-                }
-
-                // サーバー状態変更イベントを発行
-                emit_server_status_with_tunnel(&app_handle_for_tunnel);
-            }
-        }
-    });
-
     // 静的ファイルの配信パスを解決
     let static_path = resolve_static_file_path();
     let obs_path = static_path.join("obs");
@@ -533,54 +1305,66 @@ async fn run_servers(
     println!("Serving OBS static files from: {}", obs_path_str);
 
     // WebSocketサーバー（視聴者用）を作成
-    let websocket_server_result = HttpServer::new(move || {
-        App::new()
-            // WebSocketエンドポイント
-            .service(websocket_route)
-            // エラーハンドラー
-            .default_service(
-                web::route().to(|| async { HttpResponse::NotFound().body("404 Not Found") }),
-            )
-    })
-    .bind((host, ws_port));
+    let ws_app_factory = || App::new().configure(configure_ws_app);
+    // 設定ポートが使用中の場合は後続のポートへフォールバックしてバインド
+    let websocket_server_result = bind_with_port_fallback(
+        "WebSocket",
+        ws_bind_host,
+        ws_port,
+        auto_release_ports,
+        |port| match tls_server_config.clone() {
+            Some(tls_config) => {
+                HttpServer::new(ws_app_factory).bind_rustls_0_23((ws_bind_host, port), tls_config)
+            }
+            None => HttpServer::new(ws_app_factory).bind((ws_bind_host, port)),
+        },
+    )
+    .await;
 
     // OBS用静的ファイルサーバーを作成
-    let obs_path_clone = obs_path.clone();
-    let obs_server_result = HttpServer::new(move || {
-        App::new()
-            // ステータスページ
-            .service(status_page)
-            // 追加したOBS用ルートハンドラーを登録
-            .service(obs_index_page)
-            .service(obs_styles)
-            .service(obs_script)
-            // OBS用静的ファイル配信
-            .service(
-                fs::Files::new("/obs", obs_path_clone.clone())
-                    .index_file("index.html")
-                    .use_last_modified(true)
-                    .prefer_utf8(true)
-                    .default_handler(web::to(|req: HttpRequest| async move {
-                        let path = req.path().to_string();
-                        if path.ends_with("/") || path == "/obs" {
-                            HttpResponse::Ok()
-                                .content_type("text/html; charset=utf-8")
-                                .body(include_str!("../../src/static/obs/index.html"))
-                        } else {
-                            HttpResponse::NotFound().body("404 - File not found")
-                        }
-                    })),
-            )
-            // エラーハンドラー
-            .default_service(
-                web::route().to(|| async { HttpResponse::NotFound().body("404 Not Found") }),
-            )
-    })
-    .bind((host, obs_port));
+    let obs_bind_host = bind_host.as_str();
+    let obs_server_result =
+        bind_with_port_fallback("OBS", obs_bind_host, obs_port, auto_release_ports, |port| {
+            let obs_path_clone = obs_path.clone();
+            HttpServer::new(move || {
+                App::new()
+                    // ステータスページ
+                    .service(status_page)
+                    // 追加したOBS用ルートハンドラーを登録
+                    .service(obs_index_page)
+                    .service(obs_styles)
+                    .service(obs_script)
+                    .service(obs_layout_css)
+                    // OBS用静的ファイル配信
+                    .service(
+                        fs::Files::new("/obs", obs_path_clone.clone())
+                            .index_file("index.html")
+                            .use_last_modified(true)
+                            .prefer_utf8(true)
+                            .default_handler(web::to(|req: HttpRequest| async move {
+                                let path = req.path().to_string();
+                                if path.ends_with("/") || path == "/obs" {
+                                    HttpResponse::Ok()
+                                        .content_type("text/html; charset=utf-8")
+                                        .body(include_str!("../../src/static/obs/index.html"))
+                                } else {
+                                    HttpResponse::NotFound().body("404 - File not found")
+                                }
+                            })),
+                    )
+                    // エラーハンドラー
+                    .default_service(
+                        web::route()
+                            .to(|| async { HttpResponse::NotFound().body("404 Not Found") }),
+                    )
+            })
+            .bind((obs_bind_host, port))
+        })
+        .await;
 
     // WebSocketサーバーとOBSサーバーのバインド結果を評価
     match (websocket_server_result, obs_server_result) {
-        (Ok(ws_server), Ok(obs_server)) => {
+        (Ok((ws_server, ws_port)), Ok((obs_server, obs_port))) => {
             // 両方のサーバーが正常にバインドされた場合
             println!("Both WebSocket and OBS servers bound successfully.");
 
@@ -593,13 +1377,13 @@ async fn run_servers(
 
             let ws_addr_str = ws_addrs
                 .first()
-                .map(|addr| format_socket_addr(addr, "ws", "/ws"))
-                .unwrap_or_else(|| format!("ws://{}:{}{}", host, ws_port, ws_path));
+                .map(|addr| format_socket_addr(addr, ws_scheme, "/ws", lan_exposed))
+                .unwrap_or_else(|| format!("{}://{}:{}{}", ws_scheme, host, ws_port, ws_path));
 
             let obs_addr_str = obs_addrs
                 .first()
-                .map(|addr| format_socket_addr(addr, "http", "/obs/"))
-                .unwrap_or_else(|| format!("http://{}:{}/obs/", host, obs_port));
+                .map(|addr| format_socket_addr(addr, "http", "/obs/", lan_exposed))
+                .unwrap_or_else(|| obs_page_url(host, obs_port, Some(ws_port)));
 
             println!("Generated WebSocket URL: {}", ws_addr_str);
             println!("Generated OBS URL: {}", obs_addr_str);
@@ -645,6 +1429,12 @@ async fn run_servers(
                 println!("OBS Port '{}' stored in AppState.", obs_port);
             }
 
+            // 外部公開の準備（UPnP・トンネル）は実際に使用したポートで開始する
+            spawn_public_access_setup(&app_handle, ws_port, tls_enabled, lan_only, use_upnp);
+
+            // 起動中であることをPIDファイルで示し、別インスタンスからの終了を防ぐ
+            crate::ws_server::port_recovery::spawn_pid_heartbeat(vec![ws_port, obs_port]);
+
             // 新しいセッションIDを生成してAppStateとDBに保存
             let session_id = Uuid::new_v4().to_string();
             println!("Generated new session ID: {}", session_id);
@@ -663,7 +1453,11 @@ async fn run_servers(
                     .current_session_id
                     .lock()
                     .expect("Failed to lock current_session_id mutex");
-                *session_id_guard = Some(session_id.clone());
+                session_id_guard.clear();
+                session_id_guard.insert(
+                    stream_sessions::PRIMARY_SESSION_CHANNEL.to_string(),
+                    session_id.clone(),
+                );
                 println!("Session ID '{}' stored in AppState.", session_id);
             }
 
@@ -671,16 +1465,44 @@ async fn run_servers(
             if let Some(db_pool) = db_pool_option {
                 match database::create_session(&db_pool, &session_id).await {
                     // tokio::spawn を削除し、直接 await
-                    Ok(_) => println!(
-                        "セッションがデータベースに正常に保存されました: {}",
-                        session_id
-                    ),
+                    Ok(_) => {
+                        println!(
+                            "セッションがデータベースに正常に保存されました: {}",
+                            session_id
+                        );
+                        // 新しいセッションに合わせてマイルストーンの状態を更新
+                        milestone::reset_for_session(&app_handle, &db_pool, &session_id).await;
+
+                        // 同時配信用に設定されたチャンネルのセッションを作成
+                        let session_channels = app_state
+                            .session_channels
+                            .lock()
+                            .map(|channels| channels.clone())
+                            .unwrap_or_default();
+                        for channel in session_channels {
+                            if let Err(e) =
+                                stream_sessions::start_channel_session(&app_handle, &channel).await
+                            {
+                                eprintln!(
+                                    "チャンネル {} のセッションの作成に失敗しました: {}",
+                                    channel, e
+                                );
+                            }
+                        }
+                    }
                     Err(e) => {
                         // セッション作成失敗時はエラーログを出力し、サーバー起動を中止することも検討
                         eprintln!(
                             "セッションのデータベース保存中にエラーが発生しました: {}",
                             e
                         );
+                        update_startup_progress(&app_handle, |progress| {
+                            progress.fail(
+                                StartupPhase::Binding,
+                                format!("Failed to create session: {}", e),
+                            );
+                        });
+                        emit_server_status(&app_handle, false, None, None);
                         // セッション作成に失敗したら、後続の処理に進まない
                         return; // ★★★★★ 早期リターンを追加 ★★★★★
                     }
@@ -689,16 +1511,27 @@ async fn run_servers(
                 eprintln!(
                     "データベース接続プールが初期化されていないため、セッションを保存できません"
                 );
+                update_startup_progress(&app_handle, |progress| {
+                    progress.fail(StartupPhase::Binding, "Database pool is not initialized");
+                });
+                emit_server_status(&app_handle, false, None, None);
                 // DBプールがない場合も、後続の処理に進まない
                 return; // ★★★★★ 早期リターンを追加 ★★★★★
             }
 
-            // サーバー起動成功イベントを発行
+            // バインドとセッション作成の完了を記録し、サーバー起動成功イベントを発行
+            update_startup_progress(&app_handle, |progress| {
+                progress.bound = true;
+            });
             emit_server_status_with_tunnel(&app_handle);
 
             // 両方のサーバーを並行して実行
             println!("Starting both servers concurrently using tokio::try_join!...");
-            if let Err(e) = tokio::try_join!(ws_server_runner, obs_server_runner) {
+            let join_result = tokio::try_join!(ws_server_runner, obs_server_runner);
+            update_startup_progress(&app_handle, |progress| {
+                progress.active = false;
+            });
+            if let Err(e) = join_result {
                 eprintln!("Server execution error in try_join!: {}", e);
                 // エラーが発生した場合も停止イベントを発行
                 emit_server_status(&app_handle, false, None, None);
@@ -712,19 +1545,26 @@ async fn run_servers(
             // どちらかまたは両方のバインドに失敗した場合
             let mut error_msg = String::new();
             if let Err(e) = ws_result {
-                error_msg.push_str(&format!("Failed to bind WebSocket server: {}. ", e));
+                error_msg.push_str(&format!("{}. ", e));
             }
             if let Err(e) = obs_result {
-                error_msg.push_str(&format!("Failed to bind OBS server: {}. ", e));
+                error_msg.push_str(&format!("{}. ", e));
             }
             eprintln!("{}", error_msg.trim());
             eprintln!("Neither server will start.");
+            update_startup_progress(&app_handle, |progress| {
+                progress.active = false;
+                progress.fail(StartupPhase::Binding, error_msg.trim());
+            });
 
             // サーバー起動失敗イベントを発行
             emit_server_status(&app_handle, false, None, None);
         }
     }
 
+    // UPnPで開放したポートが残っている場合は閉じる（停止操作で削除済みの場合は何もしない）
+    upnp::remove_port_forward().await;
+
     // クリーンアップ処理
     cleanup_server_resources(
         server_handle_arc,
@@ -735,6 +1575,57 @@ async fn run_servers(
     );
 }
 
+/// ## TLS設定からrustlsのサーバー設定を読み込む
+///
+/// TLSが無効な場合は `None` を返します。
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+///
+/// ### Returns
+/// - `Result<Option<rustls::ServerConfig>, String>`: TLS有効時はサーバー設定、読み込み失敗時はエラーメッセージ
+fn load_tls_server_config(app_state: &AppState) -> Result<Option<rustls::ServerConfig>, String> {
+    let tls_config = app_state
+        .tls_config
+        .lock()
+        .map_err(|_| "Failed to lock TLS config mutex".to_string())?
+        .clone();
+
+    if !tls_config.enabled {
+        return Ok(None);
+    }
+
+    let (Some(cert_path), Some(key_path)) = (tls_config.cert_path, tls_config.key_path) else {
+        return Err("TLS is enabled but certificate or key path is not set.".to_string());
+    };
+
+    tls::load_server_config(&cert_path, &key_path)
+        .map(Some)
+        .map_err(|e| format!("Failed to load TLS certificate: {}", e))
+}
+
+/// ## WebSocketサーバー（視聴者用）のルートを構成する
+///
+/// 通常起動とグレースフルリスタートで同じルート構成を使用するための共通設定です。
+///
+/// ### Arguments
+/// - `cfg`: サービス設定
+fn configure_ws_app(cfg: &mut web::ServiceConfig) {
+    cfg
+        // WebSocketエンドポイント
+        .service(websocket_route)
+        // OBSオーバーレイ用のWebSocketエンドポイント（接続数に含めない）
+        .service(obs_websocket_route)
+        // 接続候補の情報
+        .service(server_info)
+        // 接続前の容量確認
+        .service(capacity)
+        // エラーハンドラー
+        .default_service(
+            web::route().to(|| async { HttpResponse::NotFound().body("404 Not Found") }),
+        );
+}
+
 /// ## サーバー情報をクリアする
 ///
 /// ホスト、ポート情報をクリアします。
@@ -768,6 +1659,159 @@ fn clear_server_info(app_state: &AppState) {
     }
 }
 
+/// ## ポートをずらしながらサーバーをバインドする
+///
+/// 設定ポートへのバインドに失敗した場合、後続のポートを最大 `MAX_PORT_FALLBACKS` 個まで順に試行します。
+/// 前回のインスタンスがポートを掴んだままの場合は、設定に応じて解放してからバインドします。
+///
+/// ### Arguments
+/// - `label`: ログに表示するサーバー名
+/// - `bind_host`: バインドするホスト
+/// - `port`: 設定されたポート
+/// - `auto_release_ports`: 自アプリの前回インスタンスを終了してポートを解放するかどうか
+/// - `bind`: 指定したポートにサーバーをバインドする関数
+///
+/// ### Returns
+/// - `Result<(T, u16), String>`: バインドしたサーバーと使用したポート、全ポートで失敗した場合は試行したポートと失敗理由を列挙したエラーメッセージ
+async fn bind_with_port_fallback<T>(
+    label: &str,
+    bind_host: &str,
+    port: u16,
+    auto_release_ports: bool,
+    mut bind: impl FnMut(u16) -> std::io::Result<T>,
+) -> Result<(T, u16), String> {
+    let mut failures = Vec::new();
+    for candidate in (0..=MAX_PORT_FALLBACKS).filter_map(|offset| port.checked_add(offset)) {
+        let result = ensure_port_available(bind_host, candidate, auto_release_ports)
+            .await
+            .and_then(|_| bind(candidate));
+        match result {
+            Ok(server) => {
+                if candidate != port {
+                    println!(
+                        "{} server port {} was unavailable. Fell back to port {}.",
+                        label, port, candidate
+                    );
+                }
+                return Ok((server, candidate));
+            }
+            Err(e) => {
+                eprintln!(
+                    "Failed to bind {} server on port {}: {}",
+                    label, candidate, e
+                );
+                failures.push(format!("{} ({})", candidate, e));
+            }
+        }
+    }
+    Err(format!(
+        "Failed to bind {} server on any of the tried ports: {}",
+        label,
+        failures.join(", ")
+    ))
+}
+
+/// ## UPnPでのポート開放、またはCloudflaredトンネルの起動を開始する
+///
+/// TLS有効時とLAN内公開モード（UPnP不使用）では外部公開の準備は行いません。
+/// 処理は非同期で実行し、完了時にサーバー状態変更イベントを発行します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `ws_port`: バインドしたWebSocketサーバーのポート
+/// - `tls_enabled`: TLSが有効かどうか
+/// - `lan_only`: LAN内公開モードかどうか
+/// - `use_upnp`: UPnPでポートを開放するかどうか
+fn spawn_public_access_setup(
+    app_handle: &tauri::AppHandle,
+    ws_port: u16,
+    tls_enabled: bool,
+    lan_only: bool,
+    use_upnp: bool,
+) {
+    if tls_enabled {
+        println!("TLS is enabled. Skipping Cloudflared tunnel startup.");
+        return;
+    }
+    if lan_only && !use_upnp {
+        println!("LAN-only mode is enabled. Skipping tunnel startup.");
+        return;
+    }
+
+    let app_handle_for_tunnel = app_handle.clone();
+    tokio::spawn(async move {
+        if use_upnp {
+            println!(
+                "Trying UPnP port forwarding for WebSocket port {}...",
+                ws_port
+            );
+            match upnp::try_port_forward(ws_port).await {
+                Ok(()) => {
+                    // グローバルIPで直接公開できるため、トンネルは起動しない
+                    update_startup_progress(&app_handle_for_tunnel, |progress| {
+                        progress.tunnel_settled = true;
+                    });
+                    emit_server_status_with_tunnel(&app_handle_for_tunnel);
+                    return;
+                }
+                Err(e) if lan_only => {
+                    eprintln!("UPnP port forwarding failed (LAN-only mode): {}", e);
+                    return;
+                }
+                Err(e) => {
+                    eprintln!("UPnP port forwarding failed. Falling back to tunnel: {}", e);
+                }
+            }
+        }
+        start_tunnel_and_report(&app_handle_for_tunnel, ws_port).await;
+    });
+}
+
+/// ## Cloudflaredトンネルを起動し、結果を通知する
+///
+/// 起動結果をAppStateに保存し、起動フェーズの進捗を更新してからサーバー状態変更イベントを発行します。
+///
+/// ### Arguments
+/// - `app_handle`: Tauriアプリケーションハンドル
+/// - `ws_port`: トンネルの転送先となるWebSocketサーバーのポート
+async fn start_tunnel_and_report(app_handle: &tauri::AppHandle, ws_port: u16) {
+    println!(
+        "Starting Cloudflared tunnel for WebSocket port {}...",
+        ws_port
+    );
+    match tunnel::start_tunnel(app_handle, ws_port).await {
+        Ok(tunnel_info) => {
+            println!(
+                "Cloudflared tunnel started successfully at: {}",
+                tunnel_info.url
+            );
+
+            // トンネル情報をAppStateに保存
+            if let Ok(mut tunnel_guard) = app_handle.state::<AppState>().tunnel_info.lock() {
+                *tunnel_guard = Some(Ok(tunnel_info));
+            }
+            update_startup_progress(app_handle, |progress| {
+                progress.tunnel_settled = true;
+            });
+        }
+        Err(e) => {
+            eprintln!("Failed to start Cloudflared tunnel: {}", e);
+            update_startup_progress(app_handle, |progress| {
+                progress.tunnel_settled = true;
+                progress.fail(StartupPhase::StartingTunnel, e.to_string());
+            });
+
+            // エラー情報をAppStateに保存
+            if let Ok(mut tunnel_guard) = app_handle.state::<AppState>().tunnel_info.lock() {
+                *tunnel_guard = Some(Err(e));
+            }
+        }
+    }
+
+    // サーバー状態変更イベントを発行
+    emit_server_status_with_tunnel(app_handle);
+}
+
 /// ## サーバーリソースをクリーンアップする
 ///
 /// サーバーリソースをクリーンアップします。
@@ -828,20 +1872,49 @@ fn cleanup_server_resources(
 ///
 /// ### Arguments
 /// - `app_handle`: Tauriアプリケーションハンドル
-fn emit_server_status_with_tunnel(app_handle: &tauri::AppHandle) {
+pub(crate) fn emit_server_status_with_tunnel(app_handle: &tauri::AppHandle) {
     let app_state = app_handle.state::<AppState>();
 
     // 必要な情報を取得
     let is_running = app_state.server_handle.lock().unwrap().is_some();
 
+    let tls_enabled = app_state
+        .tls_config
+        .lock()
+        .map(|tls_config| tls_config.enabled)
+        .unwrap_or(false);
+    let lan_only = is_lan_only(&app_state);
+    // 視聴者に共有するURLには、アクセストークン設定時にトークンを付与する
+    let access_token = access_token::current_access_token(&app_state);
+    let upnp_ws_url = upnp::upnp_ws_url(&app_state)
+        .map(|url| access_token::with_access_token(url, access_token.as_deref()));
+    let upnp_forwarded = upnp::active_external_port().is_some();
+
     // Cloudflared Tunnel関連の情報を取得
     let (tunnel_http_url, tunnel_status, tunnel_error) = {
-        if is_running {
+        if is_running && (tls_enabled || lan_only || upnp_forwarded) {
+            // TLS有効時・LAN内公開モード・UPnPでポートを開放した場合はトンネルを使用しない
+            (None, "Disabled".to_string(), None)
+        } else if is_running {
             if let Ok(tunnel_guard) = app_state.tunnel_info.lock() {
                 match &*tunnel_guard {
                     Some(Ok(tunnel_info)) => {
-                        // トンネル接続成功
-                        (Some(tunnel_info.url.clone()), "Running".to_string(), None)
+                        let health = tunnel_info
+                            .health
+                            .lock()
+                            .map(|health| health.clone())
+                            .unwrap_or_default();
+                        if health.is_unhealthy() {
+                            // プロセスは動作しているが、トンネル経由でHTTPの応答がない
+                            (
+                                Some(tunnel_info.url.clone()),
+                                "Unhealthy".to_string(),
+                                health.last_error,
+                            )
+                        } else {
+                            // トンネル接続成功
+                            (Some(tunnel_info.url.clone()), "Running".to_string(), None)
+                        }
                     }
                     Some(Err(e)) => {
                         // トンネル接続失敗
@@ -862,30 +1935,10 @@ fn emit_server_status_with_tunnel(app_handle: &tauri::AppHandle) {
         }
     };
 
-    // WebSocketのURL
-    let ws_url = if is_running {
-        if tunnel_status == "Running" && tunnel_http_url.is_some() {
-            // トンネル接続成功時はCloudflaredのURLを使用
-            let wss_url = tunnel_http_url
-                .as_ref()
-                .unwrap()
-                .replace("https://", "wss://")
-                + "/ws";
-            Some(wss_url)
-        } else {
-            // それ以外の場合はローカルURLを使用
-            let host = app_state
-                .host
-                .lock()
-                .unwrap()
-                .clone()
-                .unwrap_or_else(|| "127.0.0.1".to_string());
-            let port = (*app_state.port.lock().unwrap()).unwrap_or(8082);
-            Some(format!("ws://{}:{}/ws", host, port))
-        }
-    } else {
-        None
-    };
+    // 接続方式ごとのURL（WebSocketのURLはUPnP→トンネル→LAN→ローカルの順に選択）
+    let connection_urls =
+        ConnectionUrls::collect(&app_state).with_access_token(access_token.as_deref());
+    let ws_url = upnp_ws_url.clone().or_else(|| connection_urls.preferred());
 
     // OBSのURL
     let obs_url = if is_running {
@@ -895,9 +1948,13 @@ fn emit_server_status_with_tunnel(app_handle: &tauri::AppHandle) {
             .unwrap()
             .clone()
             .unwrap_or_else(|| "127.0.0.1".to_string());
-        let obs_port = (*app_state.obs_port.lock().unwrap()).unwrap_or(8081);
-        // 必ず/obsパスを含める
-        Some(format!("http://{}:{}/obs/", host, obs_port))
+        let obs_port = (*app_state.obs_port.lock().unwrap()).unwrap_or(DEFAULT_OBS_PORT);
+        let ws_port = *app_state.port.lock().unwrap();
+        // 必ず/obsパスを含める（アクセストークン設定時はOBSページが接続に使用するトークンも付与）
+        Some(access_token::with_access_token(
+            obs_page_url(&host, obs_port, ws_port),
+            access_token.as_deref(),
+        ))
     } else {
         None
     };
@@ -905,6 +1962,7 @@ fn emit_server_status_with_tunnel(app_handle: &tauri::AppHandle) {
     // CGNAT検出とIP取得失敗フラグ
     let cgnat_detected = *app_state.cgnat_detected.lock().unwrap();
     let global_ip_fetch_failed = *app_state.global_ip_fetch_failed.lock().unwrap();
+    let (startup_phase, failed_phase, startup_error) = startup_phase_info(&app_state);
 
     // ServerStatusを構築
     let status = ServerStatus {
@@ -914,12 +1972,42 @@ fn emit_server_status_with_tunnel(app_handle: &tauri::AppHandle) {
         global_ip_fetch_failed,
         cgnat_detected,
         cloudflare_http_url: tunnel_http_url,
+        local_ws_url: connection_urls.local_ws_url,
+        lan_ws_url: connection_urls.lan_ws_url,
+        tunnel_ws_url: connection_urls.tunnel_ws_url,
+        upnp_ws_url,
         tunnel_status,
         tunnel_error,
+        startup_phase,
+        failed_phase,
+        startup_error,
     };
 
+    // 調査用にログファイルへ記録
+    event_logger::log_server_status(app_handle, &status);
+
     // イベント発行
     if let Err(e) = app_handle.emit("server_status_updated", status) {
         eprintln!("Failed to emit server status event: {}", e);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 省電力モードの待機時間の検証のテスト
+    #[test]
+    fn test_idle_shutdown_validate_timeout_secs() {
+        assert!(IdleShutdownConfig::validate_timeout_secs(0).is_err());
+        assert!(IdleShutdownConfig::validate_timeout_secs(MIN_IDLE_SHUTDOWN_SECS - 1).is_err());
+        assert!(IdleShutdownConfig::validate_timeout_secs(MIN_IDLE_SHUTDOWN_SECS).is_ok());
+        assert!(IdleShutdownConfig::validate_timeout_secs(MAX_IDLE_SHUTDOWN_SECS).is_ok());
+        assert!(IdleShutdownConfig::validate_timeout_secs(MAX_IDLE_SHUTDOWN_SECS + 1).is_err());
+        // デフォルトの待機時間は設定可能な範囲に含まれる
+        assert!(IdleShutdownConfig::validate_timeout_secs(
+            IdleShutdownConfig::default().idle_timeout_secs
+        )
+        .is_ok());
+    }
+}
@@ -2,9 +2,21 @@
 //!
 //! サーバー設定のユーティリティ関数を提供します。
 
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
 use std::path::PathBuf;
 
+/// デフォルトのWebSocketサーバー（視聴者用）のポート
+pub const DEFAULT_WS_PORT: u16 = 8082;
+
+/// デフォルトのOBS用静的ファイル配信サーバーのポート
+pub const DEFAULT_OBS_PORT: u16 = 8081;
+
+/// 設定可能な最小のポート番号（ウェルノウンポートは使用しない）
+pub const MIN_CONFIGURABLE_PORT: u16 = 1024;
+
+/// デフォルトのバインドホスト（配信者のPCからのみ接続可能）
+pub const DEFAULT_BIND_HOST: &str = "127.0.0.1";
+
 /// ## 静的ファイルパスを解決する
 ///
 /// 環境に応じて適切な静的ファイルのパスを返します。
@@ -47,19 +59,212 @@ pub fn resolve_static_file_path() -> PathBuf {
 
 /// ## SocketAddr を URL 文字列にフォーマットするヘルパー関数
 ///
-/// `0.0.0.0` を `127.0.0.1` に置換し、指定されたスキーマとパスで完全なURLを生成します。
+/// `0.0.0.0` / `::` を `127.0.0.1` に置換し、指定されたスキーマとパスで完全なURLを生成します。
+/// LAN公開時は他の端末から接続できるよう、`0.0.0.0` / `::` をLAN内のIPアドレスに置換します
+/// （LAN内のIPアドレスを取得できない場合は `127.0.0.1`）。
 ///
 /// ### Arguments
 /// - `addr`: ソケットアドレス
 /// - `schema`: URLスキーマ（例: "ws", "http"）
 /// - `path`: URLパス（例: "/ws", "/obs/"）
+/// - `lan_exposed`: バインドホストに `0.0.0.0` / `::` を設定してLANに公開しているかどうか
 ///
 /// ### Returns
 /// - `String`: フォーマットされたURL
-pub fn format_socket_addr(addr: &SocketAddr, schema: &str, path: &str) -> String {
+pub fn format_socket_addr(
+    addr: &SocketAddr,
+    schema: &str,
+    path: &str,
+    lan_exposed: bool,
+) -> String {
     let ip = match addr.ip() {
-        std::net::IpAddr::V4(ip) if ip.is_unspecified() => "127.0.0.1".to_string(),
+        ip if ip.is_unspecified() && lan_exposed => {
+            detect_lan_ip().map_or_else(|| DEFAULT_BIND_HOST.to_string(), |ip| ip.to_string())
+        }
+        ip if ip.is_unspecified() => DEFAULT_BIND_HOST.to_string(),
+        IpAddr::V6(ip) => format!("[{}]", ip),
         ip => ip.to_string(),
     };
     format!("{}://{}:{}{}", schema, ip, addr.port(), path)
 }
+
+/// ## バインドホストの設定値を検証する
+///
+/// トンネルやヘルスチェックは `127.0.0.1` に接続するため、
+/// ループバックアドレス（`127.0.0.1`）と全インターフェース（`0.0.0.0` / `::`）のみ許可します。
+///
+/// ### Arguments
+/// - `host`: バインドするIPアドレス（例: "127.0.0.1", "0.0.0.0"）
+///
+/// ### Returns
+/// - `Result<IpAddr, String>`: 有効な場合は解析したIPアドレス、無効な場合はエラーメッセージ
+pub fn validate_bind_host(host: &str) -> Result<IpAddr, String> {
+    let ip = host
+        .trim()
+        .parse::<IpAddr>()
+        .map_err(|_| format!("バインドホストにはIPアドレスを指定してください: {}", host))?;
+    if ip != IpAddr::V4(Ipv4Addr::LOCALHOST) && !ip.is_unspecified() {
+        return Err(format!(
+            "バインドホストには 127.0.0.1・0.0.0.0・:: のいずれかを指定してください: {}",
+            host
+        ));
+    }
+    Ok(ip)
+}
+
+/// ## バインドホストがLANに公開する設定かどうかを判定する
+///
+/// ### Arguments
+/// - `host`: バインドホスト
+///
+/// ### Returns
+/// - `bool`: 全インターフェース（`0.0.0.0` / `::`）で待ち受ける場合は `true`
+pub fn is_lan_exposed_host(host: &str) -> bool {
+    host.parse::<IpAddr>().is_ok_and(|ip| ip.is_unspecified())
+}
+
+/// LAN内で使用しているIPアドレスを取得する
+///
+/// UDPソケットを外部アドレスに `connect` し、OSが選択した送信元アドレスを取得します。
+/// `connect` はパケットを送信しないため、オフライン環境でも経路があれば取得できます。
+pub(crate) fn detect_lan_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+}
+
+/// ## サーバーポートの設定値を検証する
+///
+/// ### Arguments
+/// - `ws_port`: WebSocketサーバーのポート
+/// - `obs_port`: OBSサーバーのポート
+///
+/// ### Returns
+/// - `Result<(), String>`: 有効な場合は `Ok(())`、無効な場合はエラーメッセージ
+pub fn validate_server_ports(ws_port: u16, obs_port: u16) -> Result<(), String> {
+    if ws_port == obs_port {
+        return Err(format!(
+            "WebSocketポートとOBSポートに同じ番号は指定できません: {}",
+            ws_port
+        ));
+    }
+    for (label, port) in [("WebSocket", ws_port), ("OBS", obs_port)] {
+        if port < MIN_CONFIGURABLE_PORT {
+            return Err(format!(
+                "{}ポートには{}以上の番号を指定してください: {}",
+                label, MIN_CONFIGURABLE_PORT, port
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// ## OBS用ページのURLを生成する
+///
+/// WebSocketポートがデフォルト以外の場合は、OBSページが接続先を判別できるよう
+/// `ws_port` クエリパラメータを付与します。
+///
+/// ### Arguments
+/// - `host`: ホスト名
+/// - `obs_port`: OBSサーバーのポート
+/// - `ws_port`: WebSocketサーバーのポート
+///
+/// ### Returns
+/// - `String`: OBS用ページのURL
+pub fn obs_page_url(host: &str, obs_port: u16, ws_port: Option<u16>) -> String {
+    match ws_port {
+        Some(ws_port) if ws_port != DEFAULT_WS_PORT => {
+            format!("http://{}:{}/obs/?ws_port={}", host, obs_port, ws_port)
+        }
+        _ => format!("http://{}:{}/obs/", host, obs_port),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// バインドホストの検証のテスト
+    #[test]
+    fn test_validate_bind_host() {
+        assert!(validate_bind_host("127.0.0.1").is_ok());
+        assert!(validate_bind_host(" 0.0.0.0 ").is_ok());
+        assert!(validate_bind_host("::").is_ok());
+        // トンネルの転送先と異なるアドレスは指定できない
+        assert!(validate_bind_host("192.168.1.10").is_err());
+        assert!(validate_bind_host("::1").is_err());
+        assert!(validate_bind_host("localhost").is_err());
+    }
+
+    /// サーバーポートの検証のテスト
+    #[test]
+    fn test_validate_server_ports() {
+        assert!(validate_server_ports(DEFAULT_WS_PORT, DEFAULT_OBS_PORT).is_ok());
+        assert!(validate_server_ports(MIN_CONFIGURABLE_PORT, u16::MAX).is_ok());
+        // 同じポートは指定できない
+        assert!(validate_server_ports(9000, 9000).is_err());
+        // 特権ポートはどちらのサーバーにも指定できない
+        assert!(validate_server_ports(MIN_CONFIGURABLE_PORT - 1, DEFAULT_OBS_PORT).is_err());
+        assert!(validate_server_ports(DEFAULT_WS_PORT, 80).is_err());
+        assert!(validate_server_ports(DEFAULT_WS_PORT, 0).is_err());
+    }
+
+    /// OBS用ページのURL生成のテスト
+    #[test]
+    fn test_obs_page_url() {
+        assert_eq!(
+            obs_page_url("127.0.0.1", DEFAULT_OBS_PORT, None),
+            "http://127.0.0.1:8081/obs/"
+        );
+        // デフォルトのWebSocketポートの場合はクエリパラメータを付与しない
+        assert_eq!(
+            obs_page_url("127.0.0.1", DEFAULT_OBS_PORT, Some(DEFAULT_WS_PORT)),
+            "http://127.0.0.1:8081/obs/"
+        );
+        assert_eq!(
+            obs_page_url("192.168.1.10", 9001, Some(9002)),
+            "http://192.168.1.10:9001/obs/?ws_port=9002"
+        );
+    }
+
+    /// LANに公開するバインドホストの判定のテスト
+    #[test]
+    fn test_is_lan_exposed_host() {
+        assert!(is_lan_exposed_host("0.0.0.0"));
+        assert!(is_lan_exposed_host("::"));
+        assert!(!is_lan_exposed_host("127.0.0.1"));
+        assert!(!is_lan_exposed_host("invalid"));
+    }
+
+    /// 全インターフェースのアドレスをURLにフォーマットするテスト
+    #[test]
+    fn test_format_socket_addr() {
+        let v4: SocketAddr = "0.0.0.0:8082".parse().unwrap();
+        let v6: SocketAddr = "[::]:8082".parse().unwrap();
+        assert_eq!(
+            format_socket_addr(&v4, "ws", "/ws", false),
+            "ws://127.0.0.1:8082/ws"
+        );
+        assert_eq!(
+            format_socket_addr(&v6, "ws", "/ws", false),
+            "ws://127.0.0.1:8082/ws"
+        );
+
+        // LAN公開時はLAN内のIPアドレス（取得できない場合は127.0.0.1）に置換する
+        let lan_host =
+            detect_lan_ip().map_or_else(|| DEFAULT_BIND_HOST.to_string(), |ip| ip.to_string());
+        for addr in [v4, v6] {
+            assert_eq!(
+                format_socket_addr(&addr, "http", "/obs/", true),
+                format!("http://{}:8082/obs/", lan_host)
+            );
+        }
+
+        let loopback_v6: SocketAddr = "[::1]:8082".parse().unwrap();
+        assert_eq!(
+            format_socket_addr(&loopback_v6, "ws", "/ws", false),
+            "ws://[::1]:8082/ws"
+        );
+    }
+}
@@ -2,22 +2,65 @@
 //!
 //! WebSocketセッションのライフサイクル管理と、メッセージの処理を行います。
 
-use super::{client_info::ClientInfo, connection_manager::ConnectionManager};
+use super::client_info::{ClientInfo, MAX_USER_AGENT_LEN};
+use super::connection_manager::ConnectionManager;
+use super::connection_urls::ConnectionMethod;
+use super::flow_control::{
+    BroadcastPriority, FlowControlConfig, FlowController, FLOW_CONTROL_TICK,
+};
+use super::geoip;
+use super::human_verification::{
+    HumanVerificationConfig, PowChallenge, MAX_UNVERIFIED_PENDING_MESSAGES, POW_ALGORITHM,
+};
+use super::msgpack;
+use super::network_type;
+use super::protobuf::{self, BroadcastEncoding};
+use super::tunnel::TunnelKind;
+use super::tx_verification::{
+    self, TxVerificationConfig, TxVerificationResult, UnverifiedAction, VERIFICATION_RPC_TIMEOUT,
+};
+use crate::auto_thanks;
+use crate::badges;
+use crate::coin_registry::{self, CoinInfo};
 use crate::database;
-use crate::db_models::Message as DbMessage;
+use crate::db_health;
+use crate::db_models::{
+    Message as DbMessage, RejectedSuperchat, REJECT_REASON_BANNED_WORD,
+    REJECT_REASON_COIN_NOT_ACCEPTED, REJECT_REASON_OVER_LIMIT,
+};
+use crate::db_retry;
+use crate::language::{detect_language, normalize_language_filter};
+use crate::moderation::{contains_banned_word, mask_banned_words, SuperchatModeration};
 use crate::state::AppState;
+use crate::stream_sessions;
+use crate::sui_watcher;
+use crate::superchat_alert;
+use crate::translation::TranslationJob;
 use crate::types::{
-    ClientMessage, MessageType, ServerResponse, CLIENT_TIMEOUT, HEARTBEAT_INTERVAL,
+    normalize_channel, ChannelAction, ChatMessage, ClientMessage, MessageType, OutgoingMessage,
+    ServerResponse, SuperchatData, SuperchatMessage, CLIENT_TIMEOUT, DEFAULT_CHANNEL,
+    HEARTBEAT_INTERVAL, HEARTBEAT_REPORT_INTERVAL, MAX_CHANNEL_NAME_LENGTH,
+    OVERFLOW_REDIRECT_GRACE,
 };
+use crate::validation;
+use crate::wallet_registry;
+use crate::webhook;
 use actix::prelude::*;
 use actix::Message;
+use actix_web::http::header;
+use actix_web::web::Bytes;
 use actix_web::HttpRequest;
 use actix_web_actors::ws;
-use chrono::Utc;
+use chrono::{DateTime, SubsecRound, Utc};
 use sqlx::sqlite::SqlitePool;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use tauri::{Emitter, Manager};
+use uuid::Uuid;
+
+/// 検証中・使用済みのトランザクションのスーパーチャットを拒否する際のエラーメッセージ
+const TX_HASH_ALREADY_USED_MESSAGE: &str =
+    "このトランザクションは既にスーパーチャットとして使用されています";
 
 /// ## WsSession アクター
 ///
@@ -28,6 +71,8 @@ use tauri::{Emitter, Manager};
 pub struct WsSession {
     /// クライアントからの最後のハートビート受信時刻
     hb: Instant,
+    /// ハートビートの応答時刻を接続マネージャーに最後に反映した時刻
+    hb_reported: Instant,
     /// クライアント情報
     client_info: Option<ClientInfo>,
     /// 接続マネージャー（共有状態）
@@ -38,8 +83,26 @@ pub struct WsSession {
     db_pool: Arc<Mutex<Option<SqlitePool>>>,
     /// 現在のセッションID
     current_session_id: Option<String>,
+    /// 接続時にクエリパラメータで指定された配信セッションのチャンネル名
+    session_channel: Option<String>,
     /// Tauriアプリハンドル（イベント発火用）
     app_handle: Option<tauri::AppHandle>,
+    /// ブロードキャストのエンコーディング（接続時のサブプロトコルで決定）
+    encoding: BroadcastEncoding,
+    /// 送信レート制御用のトークンバケット
+    flow: FlowController<Broadcast>,
+    /// 未解答の人間検証チャレンジ
+    pow_challenge: Option<PowChallenge>,
+    /// 人間検証を完了しているかどうか
+    verified_human: bool,
+    /// 人間検証の完了まで保留しているメッセージ
+    unverified_pending: Vec<ClientMessage>,
+    /// このクライアントがスーパーチャットで使用し、送金者を確認できたウォレットアドレス（通常チャットの称号判定に使用）
+    wallet_address: Option<String>,
+    /// OBSオーバーレイの受信専用セッションかどうか
+    obs_overlay: bool,
+    /// OBSオーバーレイとして登録した接続ID
+    obs_connection_id: Option<String>,
 }
 
 impl Default for WsSession {
@@ -56,12 +119,22 @@ impl WsSession {
     pub fn new() -> Self {
         Self {
             hb: Instant::now(),
+            hb_reported: Instant::now(),
             client_info: None,
             connection_manager: None,
             req: None,
             db_pool: Arc::new(Mutex::new(None)),
             current_session_id: None,
+            session_channel: None,
             app_handle: None,
+            encoding: BroadcastEncoding::Json,
+            flow: FlowController::new(&FlowControlConfig::default(), Instant::now()),
+            pow_challenge: None,
+            verified_human: false,
+            unverified_pending: Vec::new(),
+            wallet_address: None,
+            obs_overlay: false,
+            obs_connection_id: None,
         }
     }
 
@@ -79,10 +152,12 @@ impl WsSession {
     /// ## リクエスト情報を設定する
     ///
     /// クライアント情報取得のためのHTTPリクエストを設定します。
+    /// サブプロトコルの指定からブロードキャストのエンコーディングも決定します。
     ///
     /// ### Arguments
     /// - `request`: HTTPリクエスト
     pub fn with_request(mut self, request: HttpRequest) -> Self {
+        self.encoding = BroadcastEncoding::from_request(&request);
         self.req = Some(request);
         self
     }
@@ -109,6 +184,70 @@ impl WsSession {
         self
     }
 
+    /// ## OBSオーバーレイの受信専用セッションとして設定する
+    ///
+    /// 接続数・最大接続数には含めず、ブロードキャストの受信と履歴の取得のみを許可します。
+    pub fn as_obs_overlay(mut self) -> Self {
+        self.obs_overlay = true;
+        self
+    }
+
+    /// ## 現在のフロー制御設定を取得する
+    ///
+    /// ### Returns
+    /// - `FlowControlConfig`: 接続マネージャーの設定（マネージャーがない場合はデフォルト）
+    fn flow_control_config(&self) -> FlowControlConfig {
+        self.connection_manager
+            .as_ref()
+            .map(|manager| manager.flow_control_config())
+            .unwrap_or_default()
+    }
+
+    /// ## ブロードキャストメッセージをクライアントに送信する
+    ///
+    /// ### Arguments
+    /// - `messages`: 送信するメッセージ（古い順）
+    /// - `ctx`: WebSocketコンテキスト
+    fn send_broadcasts(&self, messages: Vec<Broadcast>, ctx: &mut ws::WebsocketContext<Self>) {
+        for message in messages {
+            match message.into_frame(self.encoding) {
+                BroadcastFrame::Text(text) => ctx.text(text),
+                BroadcastFrame::Binary(bin) => ctx.binary(bin),
+            }
+        }
+    }
+
+    /// ## ブロードキャストメッセージをフロー制御を経て送信する
+    ///
+    /// 送信レートの上限を超えた分はバッファに溜め、`flush_flow_control` で順次送信します。
+    ///
+    /// ### Arguments
+    /// - `message`: 送信するメッセージ
+    /// - `ctx`: WebSocketコンテキスト
+    fn offer_broadcast(&mut self, message: Broadcast, ctx: &mut ws::WebsocketContext<Self>) {
+        let config = self.flow_control_config();
+        let priority = message.priority;
+        let ready = self.flow.offer(message, priority, &config, Instant::now());
+        self.send_broadcasts(ready, ctx);
+    }
+
+    /// ## フロー制御のバッファを定期的に送信する
+    ///
+    /// トークンの補充に合わせて、バッファに溜まったメッセージをまとめて送信します。
+    ///
+    /// ### Arguments
+    /// - `ctx`: WebSocketコンテキスト
+    fn flush_flow_control(&self, ctx: &mut ws::WebsocketContext<Self>) {
+        ctx.run_interval(FLOW_CONTROL_TICK, |act, ctx| {
+            if !act.flow.has_pending() {
+                return;
+            }
+            let config = act.flow_control_config();
+            let ready = act.flow.drain(&config, Instant::now());
+            act.send_broadcasts(ready, ctx);
+        });
+    }
+
     /// ## ハートビートチェック
     ///
     /// 定期的にハートビートを送信し、クライアントの生存を確認します。
@@ -128,6 +267,7 @@ impl WsSession {
                         println!("クライアント削除: {}", client_info.id);
                     }
                 }
+                act.remove_obs_connection();
 
                 ctx.stop();
                 return;
@@ -137,6 +277,16 @@ impl WsSession {
         });
     }
 
+    /// ## OBSオーバーレイの接続を接続マネージャーから削除する
+    fn remove_obs_connection(&mut self) {
+        if let (Some(connection_id), Some(manager)) =
+            (self.obs_connection_id.take(), &self.connection_manager)
+        {
+            manager.remove_obs_client(&connection_id);
+            println!("OBSオーバーレイの接続を削除: {}", connection_id);
+        }
+    }
+
     /// ## エラーレスポンスを作成する
     ///
     /// クライアントに送信するエラーメッセージを作成します。
@@ -210,14 +360,23 @@ impl WsSession {
                 msg.display_name, msg.superchat.amount, msg.superchat.coin
             ),
             ClientMessage::GetHistory { .. } => "履歴取得リクエスト".to_string(),
+            ClientMessage::ChannelSubscription { .. } => "チャンネル購読リクエスト".to_string(),
+            ClientMessage::HumanVerification { .. } => "人間検証の解答".to_string(),
+            ClientMessage::Resume { .. } => "再送リクエスト".to_string(),
         };
         println!("メッセージをデータベースに保存準備中: {}", msg_type);
 
+        // ブロードキャストと同じサーバーの受信時刻を記録（同一時刻の順序はDB側のシーケンス番号で保証）
+        let received_at = client_msg
+            .timestamp()
+            .and_then(DateTime::from_timestamp_millis)
+            .unwrap_or_else(|| Utc::now().trunc_subsecs(3));
+
         // DBに保存するMessageオブジェクトを作成
         let db_message = match client_msg {
             ClientMessage::Chat(chat_msg) => DbMessage {
                 id: chat_msg.id.clone(),
-                timestamp: Utc::now(),
+                timestamp: received_at,
                 display_name: chat_msg.display_name.clone(),
                 content: chat_msg.content.clone(),
                 amount: Some(0.0), // チャットの場合はデフォルト値 0.0 を設定
@@ -225,41 +384,95 @@ impl WsSession {
                 tx_hash: None,
                 wallet_address: None,
                 session_id,
+                channel: Some(normalize_channel(chat_msg.channel.as_deref())),
+                sequence: None, // 保存時にDB側で採番
+                language: chat_msg.detected_language.clone(),
+                is_edited: false,
+                highlighted: false,
             },
             ClientMessage::Superchat(superchat_msg) => DbMessage {
                 id: superchat_msg.id.clone(),
-                timestamp: Utc::now(),
+                timestamp: received_at,
                 display_name: superchat_msg.display_name.clone(),
                 content: superchat_msg.content.clone(),
-                amount: Some(superchat_msg.superchat.amount),
+                // 受信時に変換済みでない金額もここで表示単位にそろえる
+                amount: Some(
+                    superchat_msg.superchat.display_amount(
+                        self.find_supported_coin(&superchat_msg.superchat.coin)
+                            .as_ref(),
+                    ),
+                ),
                 coin: Some(superchat_msg.superchat.coin.clone()),
                 tx_hash: Some(superchat_msg.superchat.tx_hash.clone()),
                 wallet_address: Some(superchat_msg.superchat.wallet_address.clone()),
                 session_id,
+                // スーパーチャットは全チャンネル向けのため "general" として記録
+                channel: Some(DEFAULT_CHANNEL.to_string()),
+                sequence: None, // 保存時にDB側で採番
+                language: detect_language(&superchat_msg.content),
+                is_edited: false,
+                highlighted: false,
             },
-            ClientMessage::GetHistory { .. } => {
-                // 履歴取得リクエストはDBに保存しない
-                println!("履歴取得リクエストはDBに保存しません");
+            ClientMessage::GetHistory { .. }
+            | ClientMessage::ChannelSubscription { .. }
+            | ClientMessage::HumanVerification { .. }
+            | ClientMessage::Resume { .. } => {
+                // 履歴取得・チャンネル購読・再送リクエスト・人間検証の解答はDBに保存しない
+                println!("履歴取得・チャンネル購読リクエストはDBに保存しません");
                 return;
             }
         };
 
+        // DB再接続中は復旧後に保存するためキューに退避
+        if let Some(app_handle) = &self.app_handle {
+            if db_health::is_reconnecting(app_handle) {
+                db_health::queue_pending_message(app_handle, db_message);
+                return;
+            }
+        }
+
         // 非同期タスクでDBに保存
         let db_pool_clone = db_pool.clone();
         let message_id = db_message.id.clone(); // エラー報告用にIDをクローン
         let app_handle_clone = self.app_handle.clone();
+        let app_handle_for_retry = self.app_handle.clone();
         let db_message_clone = db_message.clone();
 
         tokio::spawn(async move {
             match database::save_message_db(&db_pool_clone, &db_message).await {
-                Ok(_) => {
+                Ok(false) => {
+                    println!(
+                        "同じIDのメッセージが保存済みのため、保存をスキップしました: ID={}",
+                        message_id
+                    );
+                }
+                Ok(true) => {
                     println!(
                         "メッセージをデータベースに正常に保存しました: ID={}",
                         message_id
                     );
 
+                    // ウォレットアドレスのある視聴者の累計実績を更新
+                    if let Err(e) =
+                        database::record_viewer_activity(&db_pool_clone, &db_message).await
+                    {
+                        eprintln!("視聴者プロフィールの更新に失敗しました: {}", e);
+                    }
+                    if let (Some(app_handle), Some(wallet_address)) =
+                        (&app_handle_clone, &db_message.wallet_address)
+                    {
+                        badges::invalidate_viewer_profile(
+                            &app_handle.state::<AppState>(),
+                            wallet_address,
+                        );
+                    }
+
                     // フロントエンドに message_saved イベントを発火
                     if let Some(app_handle) = app_handle_clone {
+                        let superchat_amount = db_message_clone
+                            .amount
+                            .zip(db_message_clone.coin.clone())
+                            .filter(|(amount, _)| *amount > 0.0);
                         let serializable_message =
                             crate::types::SerializableMessageForStreamer::from(db_message_clone);
                         if let Err(e) = app_handle.emit("message_saved", &serializable_message) {
@@ -270,46 +483,82 @@ impl WsSession {
                                 message_id
                             );
                         }
+
+                        // スーパーチャットの場合は総額のマイルストーン達成を確認
+                        if let Some((amount, coin)) = superchat_amount {
+                            crate::milestone::record_superchat(&app_handle, amount, &coin);
+                        }
                     } else {
                         println!("アプリハンドルが利用できないため、message_saved イベントを発火できませんでした");
                     }
                 }
-                Err(e) => eprintln!(
-                    "メッセージの保存中にエラーが発生しました: ID={}, エラー={}",
-                    message_id, e
-                ),
+                Err(e) => {
+                    eprintln!(
+                        "メッセージの保存中にエラーが発生しました: ID={}, エラー={}",
+                        message_id, e
+                    );
+                    // 接続断によるエラーの場合は再接続後に保存するためキューに退避し、
+                    // それ以外の一時的なエラーはバックグラウンドで再保存する
+                    match &app_handle_for_retry {
+                        Some(app_handle) if db_health::is_connection_error(&e) => {
+                            db_health::queue_pending_message(app_handle, db_message);
+                        }
+                        Some(app_handle) => db_retry::enqueue(app_handle, db_message),
+                        None => {}
+                    }
+                }
             }
         });
     }
 
     /// ## メッセージをブロードキャストする
     ///
-    /// 受信したメッセージを、接続されているすべてのクライアントに送信します。
-    /// また、メッセージに追加情報（タイムスタンプ）を付与します。
+    /// 受信したメッセージを、接続されているクライアントに送信します。
+    /// 通常チャットは投稿先チャンネルの購読者にのみ、スーパーチャットは全クライアントに送信します。
     ///
     /// ### Arguments
     /// - `client_msg`: ブロードキャストするクライアントメッセージ (`ClientMessage`)
     /// - `ctx`: WebSocketコンテキスト (`&mut ws::WebsocketContext<Self>`)
     fn broadcast_message(&self, client_msg: ClientMessage, ctx: &mut ws::WebsocketContext<Self>) {
+        // 外部サービスへの転送（失敗してもブロードキャストには影響しない）
+        if let Some(app_handle) = &self.app_handle {
+            webhook::forward_message(app_handle, &client_msg);
+        }
+
         match client_msg {
-            ClientMessage::Chat(chat_msg) => {
-                // クライアント情報とマネージャーが設定されている場合、メッセージカウンターを更新
+            ClientMessage::Chat(mut chat_msg) => {
+                // クライアント情報とマネージャーが設定されている場合、メッセージカウンターと表示名を更新
                 if let (Some(client_info), Some(manager)) =
                     (&self.client_info, &self.connection_manager)
                 {
+                    let mut display_name_changed = false;
                     manager.update_client(&client_info.id, |info| {
                         info.update_activity();
                         info.increment_messages();
+                        display_name_changed = info.set_display_name(&chat_msg.display_name);
                     });
+                    if display_name_changed {
+                        manager.emit_connections_updated();
+                    }
                 }
 
+                // チャンネル未指定のメッセージは "general" として配信
+                let channel = normalize_channel(chat_msg.channel.as_deref());
+                chat_msg.channel = Some(channel.clone());
+
+                // 称号はサーバー側で判定するため、クライアントからの値は使用しない
+                chat_msg.badges = self.cached_sender_badges();
+
                 let json_result = serde_json::to_string(&chat_msg);
 
                 match json_result {
                     Ok(json) => {
-                        // 全クライアントにメッセージをブロードキャスト
+                        // チャンネル購読者にメッセージをブロードキャスト
                         if let Some(manager) = &self.connection_manager {
-                            manager.broadcast(&json);
+                            let broadcast =
+                                Broadcast::with_protobuf(json, protobuf::encode_chat(&chat_msg))
+                                    .with_priority(BroadcastPriority::Low);
+                            manager.broadcast_frame_to_channel(broadcast, &channel);
                         }
                     }
                     Err(e) => {
@@ -320,239 +569,1327 @@ impl WsSession {
                     }
                 }
             }
-            ClientMessage::Superchat(superchat_msg) => {
-                // クライアント情報とマネージャーが設定されている場合、メッセージカウンターを更新
+            ClientMessage::Superchat(mut superchat_msg) => {
+                // クライアント情報とマネージャーが設定されている場合、メッセージカウンターと表示名を更新
                 if let (Some(client_info), Some(manager)) =
                     (&self.client_info, &self.connection_manager)
                 {
+                    let mut display_name_changed = false;
                     manager.update_client(&client_info.id, |info| {
                         info.update_activity();
                         info.increment_messages();
+                        display_name_changed = info.set_display_name(&superchat_msg.display_name);
                     });
+                    if display_name_changed {
+                        manager.emit_connections_updated();
+                    }
                 }
 
-                let json_result = serde_json::to_string(&superchat_msg);
+                // ストリークと称号はサーバー側で算出するため、クライアントからの値は使用しない
+                superchat_msg.donor_streak = None;
+                superchat_msg.badges = Vec::new();
 
-                match json_result {
-                    Ok(json) => {
-                        // 全クライアントにメッセージをブロードキャスト
-                        if let Some(manager) = &self.connection_manager {
-                            manager.broadcast(&json);
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("メッセージのシリアライズに失敗: {}", e);
-                        ctx.text(
-                            self.create_error_response(&format!("メッセージ処理エラー: {}", e)),
-                        );
+                let db_pool = self.db_pool.lock().ok().and_then(|guard| guard.clone());
+                let wallet_address = superchat_msg.superchat.wallet_address.clone();
+                // 他人のウォレットを申告して称号を表示させないよう、称号は送金者を確認できた場合のみ付与する
+                let sender_verified = superchat_msg.verified == Some(true);
+
+                match db_pool {
+                    Some(db_pool) if !wallet_address.is_empty() => {
+                        // 送金者のストリークと称号を算出してからブロードキャスト
+                        let session_id = self.current_session_id.clone();
+                        let app_handle = self.app_handle.clone();
+                        let fut = async move {
+                            let streak = database::get_donor_streak_for_session(
+                                &db_pool,
+                                &wallet_address,
+                                session_id.as_deref(),
+                            )
+                            .await;
+                            let badges = match &app_handle {
+                                Some(app_handle) if sender_verified => {
+                                    badges::viewer_badges(app_handle, &db_pool, &wallet_address)
+                                        .await
+                                }
+                                _ => Vec::new(),
+                            };
+                            (streak, badges)
+                        };
+
+                        let fut = actix::fut::wrap_future::<_, Self>(fut);
+                        ctx.spawn(fut.map(move |(result, badges), actor, ctx| {
+                            match result {
+                                Ok(streak) => superchat_msg.donor_streak = Some(streak),
+                                Err(e) => eprintln!("ストリークの算出に失敗: {}", e),
+                            }
+                            superchat_msg.badges = badges;
+                            actor.broadcast_superchat(&superchat_msg, ctx);
+                        }));
                     }
+                    _ => self.broadcast_superchat(&superchat_msg, ctx),
                 }
             }
-            ClientMessage::GetHistory { .. } => {
-                // 履歴取得リクエストはブロードキャストしない
-                println!("履歴取得リクエストはブロードキャストしません");
+            ClientMessage::GetHistory { .. }
+            | ClientMessage::ChannelSubscription { .. }
+            | ClientMessage::HumanVerification { .. }
+            | ClientMessage::Resume { .. } => {
+                // 履歴取得・チャンネル購読・再送リクエスト・人間検証の解答はブロードキャストしない
+                println!("履歴取得・チャンネル購読リクエストはブロードキャストしません");
             }
         }
     }
 
-    /// 履歴取得リクエストを処理する
+    /// ## 送信者の称号をキャッシュから取得する
     ///
-    /// クライアントからの過去ログ取得リクエストを処理し、
-    /// データベースから該当するメッセージを取得して返します。
+    /// 通常チャットはウォレットアドレスを含まないため、同じ接続でスーパーチャットに使用された
+    /// ウォレットアドレスで判定します。毎メッセージでデータベースに問い合わせないよう、
+    /// キャッシュされていない場合は称号なしで配信し、次のメッセージに備えて読み込みます。
     ///
-    /// ### Arguments
-    /// - `limit`: 取得するメッセージの最大数（オプション、デフォルト50）
-    /// - `before_timestamp`: このタイムスタンプより前のメッセージのみを取得（オプション）
-    /// - `ctx`: WebSocketコンテキスト
-    fn handle_get_history(
-        &self,
-        limit: Option<i64>,
-        before_timestamp: Option<i64>,
-        ctx: &mut ws::WebsocketContext<Self>,
-    ) {
-        // セッションIDを確認
-        let session_id = match &self.current_session_id {
-            Some(id) => id.clone(),
-            None => {
-                println!("履歴取得エラー: セッションIDが設定されていません");
-                let error_msg = self.create_error_response(
-                    "セッションIDが設定されていません。履歴を取得できません。",
-                );
-                ctx.text(error_msg);
-                return;
-            }
+    /// ### Returns
+    /// - `Vec<String>`: 送信者の称号（ウォレットアドレスが不明な場合は空）
+    fn cached_sender_badges(&self) -> Vec<String> {
+        let (Some(app_handle), Some(wallet_address)) = (&self.app_handle, &self.wallet_address)
+        else {
+            return Vec::new();
         };
-
-        // DB接続プールを取得
-        let db_pool = {
-            let pool_guard = match self.db_pool.lock() {
-                Ok(guard) => guard,
-                Err(e) => {
-                    println!("履歴取得エラー: DBプールのロックに失敗: {}", e);
-                    let error_msg = self.create_error_response("データベース接続エラー");
-                    ctx.text(error_msg);
-                    return;
-                }
-            };
-
-            match &*pool_guard {
-                Some(pool) => pool.clone(),
-                None => {
-                    println!("履歴取得エラー: DBプールが初期化されていません");
-                    let error_msg =
-                        self.create_error_response("データベース接続が初期化されていません");
-                    ctx.text(error_msg);
-                    return;
-                }
-            }
+        let Some(db_pool) = self.db_pool.lock().ok().and_then(|guard| guard.clone()) else {
+            return Vec::new();
         };
+        badges::cached_viewer_badges(app_handle, &db_pool, wallet_address)
+    }
 
-        // 非同期処理でDBからメッセージを取得
-        let safe_limit = limit.unwrap_or(50);
-        let session_id_clone = session_id.clone();
-        let fut = async move {
-            // DBからメッセージを取得
-            match crate::database::get_messages_by_session_id(
-                &db_pool,
-                &session_id_clone,
-                safe_limit,
-                before_timestamp,
-            )
-            .await
-            {
-                Ok(messages) => {
-                    // さらに古いメッセージがあるかのフラグ
-                    let has_more = messages.len() as i64 > safe_limit;
-
-                    // 実際に返す件数をlimitに制限
-                    let limited_messages = if has_more {
-                        messages[..messages.len() - 1].to_vec()
-                    } else {
-                        messages
-                    };
-
-                    // DB-Modelを送信用のSerializableMessageに変換
-                    let serializable_messages: Vec<crate::types::SerializableMessage> =
-                        limited_messages.into_iter().map(|msg| msg.into()).collect();
-
+    /// ## ハートビートの応答時刻を接続マネージャーに反映する
+    ///
+    /// アイドル判定に使用するため、`HEARTBEAT_REPORT_INTERVAL` ごとに間引いて反映します。
+    fn report_heartbeat(&mut self) {
+        if self.hb.duration_since(self.hb_reported) < HEARTBEAT_REPORT_INTERVAL {
+            return;
+        }
+        self.hb_reported = self.hb;
 
-                    // レスポンスを構築
-                    let history_data = crate::types::OutgoingMessage::HistoryData {
-                        messages: serializable_messages,
-                        has_more,
-                    };
+        if let (Some(manager), Some(client_info)) = (&self.connection_manager, &self.client_info) {
+            manager.update_client(&client_info.id, |info| info.update_heartbeat());
+        }
+    }
 
-                    // JSONに変換
-                    match serde_json::to_string(&history_data) {
-                        Ok(json) => {
-                            Ok(json)
-                        }
-                        Err(e) => {
-                            println!("履歴データのJSONシリアライズに失敗: {}", e);
-                            Err(format!("JSONシリアライズエラー: {}", e))
-                        }
-                    }
-                }
-                Err(e) => {
-                    println!("履歴取得時のデータベースエラー: {}", e);
-                    Err(format!("データベースエラー: {}", e))
-                }
-            }
+    /// ## 通常チャットの受信レート制限を確認する
+    ///
+    /// ### Returns
+    /// - `bool`: 受け付ける場合は `true`、直近の期間内の上限を超えている場合は `false`
+    fn check_message_rate_limit(&mut self) -> bool {
+        let Some(client_info) = &mut self.client_info else {
+            return true;
         };
+        let limit = self
+            .connection_manager
+            .as_ref()
+            .map(|manager| manager.message_rate_limit())
+            .unwrap_or_default();
 
-        // 非同期処理を実行
-        let fut = actix::fut::wrap_future::<_, Self>(fut);
-
-        // 非同期処理の結果を処理
-        ctx.spawn(fut.map(|result, _actor, ctx| match result {
-            Ok(json) => ctx.text(json),
-            Err(e) => {
-                let error_response = _actor.create_error_response(&e);
-                ctx.text(error_response);
-            }
-        }));
+        let allowed = limit.check(&mut client_info.recent_message_times, Instant::now());
+        if !allowed {
+            println!("メッセージ送信レート制限を超過: {}", client_info.id);
+        }
+        allowed
     }
-}
-
-/// ## Actor トレイトの実装
-///
-/// `WsSession` を Actix アクターとして動作させるための実装。
-impl Actor for WsSession {
-    type Context = ws::WebsocketContext<Self>;
 
-    /// ## アクター開始時の処理
+    /// ## NGワードによるモデレーションを行う
     ///
-    /// アクターが開始されたときに呼び出され、ハートビート処理を開始します。
+    /// 通常チャットはNGワードを含む場合に拒否します。スーパーチャットは設定に応じて
+    /// NGワードを伏字にするか、拒否します。
     ///
     /// ### Arguments
-    /// - `ctx`: アクターコンテキスト (`ws::WebsocketContext<Self>`)
-    fn started(&mut self, ctx: &mut Self::Context) {
-        println!("WebSocket Session Started");
+    /// - `client_msg`: モデレーション対象のメッセージ（伏字にする場合は内容を書き換える）
+    ///
+    /// ### Returns
+    /// - `bool`: 配信する場合は `true`、拒否する場合は `false`
+    fn moderate_message(&self, client_msg: &mut ClientMessage) -> bool {
+        let Some(app_state) = self
+            .app_handle
+            .as_ref()
+            .and_then(|app_handle| app_handle.try_state::<AppState>())
+        else {
+            return true;
+        };
+        let banned_words = match app_state.banned_words.lock() {
+            Ok(words) if !words.is_empty() => words.clone(),
+            _ => return true,
+        };
 
-        // AppStateからセッションIDを取得
-        if let Some(app_handle) = super::connection_manager::global::get_app_handle() {
-            if let Some(app_state) = app_handle.try_state::<AppState>() {
-                // セッションIDを取得
-                if let Ok(session_id_guard) = app_state.current_session_id.lock() {
-                    self.current_session_id = session_id_guard.clone();
-                    if let Some(ref session_id) = self.current_session_id {
-                        println!("WebSocket Session: Using session ID: {}", session_id);
-                    } else {
-                        println!("WebSocket Session: No active session ID found");
-                    }
-                } else {
-                    println!("WebSocket Session: Failed to lock current_session_id mutex");
-                }
-            } else {
-                println!("WebSocket Session: AppState not available");
+        match client_msg {
+            ClientMessage::Chat(chat_msg)
+                if contains_banned_word(&chat_msg.content, &banned_words) =>
+            {
+                println!("NGワードを含むチャットを拒否: {}", chat_msg.id);
+                return false;
             }
-        } else {
-            println!("WebSocket Session: app_handle not available");
-        }
-
-        // リクエストからクライアント情報を取得
-        if let Some(req) = &self.req {
-            if let Some(addr) = req.peer_addr() {
-                let client_info = ClientInfo::new(addr);
-                let client_id = client_info.id.clone();
-                println!(
-                    "New client connected: {} from {}",
-                    client_id, client_info.ip
-                );
-
-                // 接続マネージャーに追加
-                if let Some(manager) = &self.connection_manager {
-                    // セッションアドレスを渡して接続登録
-                    if manager.add_client(client_info.clone(), ctx.address()) {
-                        self.client_info = Some(client_info);
-                    } else {
-                        // 最大接続数に達している場合、切断
-                        ctx.text(self.create_error_response(
-                            "Maximum connections reached. Try again later.",
-                        ));
-                        ctx.close(None);
-                        ctx.stop();
-                        return;
+            ClientMessage::Superchat(superchat_msg) => {
+                if !contains_banned_word(&superchat_msg.content, &banned_words) {
+                    return true;
+                }
+                let action = app_state
+                    .superchat_moderation
+                    .lock()
+                    .map(|action| *action)
+                    .unwrap_or_default();
+                match action {
+                    SuperchatModeration::Mask => {
+                        println!(
+                            "NGワードを含むスーパーチャットを伏字化: {}",
+                            superchat_msg.id
+                        );
+                        superchat_msg.content =
+                            mask_banned_words(&superchat_msg.content, &banned_words);
+                    }
+                    SuperchatModeration::Block => {
+                        println!("NGワードを含むスーパーチャットを拒否: {}", superchat_msg.id);
+                        // 送金済みの可能性があるため、破棄せずに後から照合できるよう記録する
+                        self.record_rejected_superchat(superchat_msg, REJECT_REASON_BANNED_WORD);
+                        return false;
                     }
-                } else {
-                    // 接続マネージャーがない場合でもClientInfoは設定
-                    self.client_info = Some(client_info);
                 }
             }
+            _ => {}
         }
-
-        self.hb(ctx);
+        true
     }
 
-    /// ## アクター停止時の処理
+    /// ## 本文・表示名の文字数制限を適用する
     ///
-    /// アクターが停止したときに呼び出されます。
+    /// 拒否したスーパーチャットは、配信しなかった記録として保存します。
+    ///
+    /// ### Arguments
+    /// - `client_msg`: 制限を適用するメッセージ（切り詰める場合は内容を書き換える）
+    ///
+    /// ### Returns
+    /// - `Result<(), String>`: 受け付ける場合は `Ok(())`、拒否する場合は送信者に返すエラーメッセージ
+    fn apply_message_limits(&self, client_msg: &mut ClientMessage) -> Result<(), String> {
+        let limits = self
+            .app_handle
+            .as_ref()
+            .and_then(|app_handle| app_handle.try_state::<AppState>())
+            .and_then(|app_state| app_state.message_limits.lock().ok().map(|limits| *limits))
+            .unwrap_or_default();
+
+        limits.apply(client_msg).inspect_err(|e| {
+            println!("文字数制限を超えたメッセージを拒否: {}", e);
+            // 送金済みの可能性があるため、破棄せずに後から照合できるよう記録する
+            if let ClientMessage::Superchat(superchat_msg) = client_msg {
+                self.record_rejected_superchat(superchat_msg, REJECT_REASON_OVER_LIMIT);
+            }
+        })
+    }
+
+    /// ## 現在の人間検証設定を取得する
+    ///
+    /// ### Returns
+    /// - `HumanVerificationConfig`: アプリケーション状態の設定（取得できない場合はデフォルト）
+    fn human_verification_config(&self) -> HumanVerificationConfig {
+        self.app_handle
+            .as_ref()
+            .and_then(|app_handle| app_handle.try_state::<AppState>())
+            .map_or_else(HumanVerificationConfig::default, |app_state| {
+                HumanVerificationConfig::from_app_state(&app_state)
+            })
+    }
+
+    /// ## 対応コインのレジストリからコインを検索する
+    ///
+    /// ### Arguments
+    /// - `symbol`: スーパーチャットで申告された通貨シンボル
+    ///
+    /// ### Returns
+    /// - `Option<CoinInfo>`: 登録済みのコイン（未登録、またはアプリケーション状態を取得できない場合はNone）
+    fn find_supported_coin(&self, symbol: &str) -> Option<CoinInfo> {
+        let app_state = self.app_handle.as_ref()?.try_state::<AppState>()?;
+        coin_registry::find_coin(&app_state, symbol)
+    }
+
+    /// ## 配信者がスーパーチャットを受け付けているコインか判定する
+    ///
+    /// ### Arguments
+    /// - `symbol`: 通貨シンボル
+    ///
+    /// ### Returns
+    /// - `bool`: 受け付ける場合はtrue（制限がない、またはアプリケーション状態を取得できない場合もtrue）
+    fn is_coin_accepted(&self, symbol: &str) -> bool {
+        let accepted_coins = self
+            .app_handle
+            .as_ref()
+            .and_then(|app_handle| app_handle.try_state::<AppState>())
+            .and_then(|app_state| app_state.accepted_coins.lock().ok()?.clone());
+        coin_registry::is_coin_accepted(accepted_coins.as_deref(), symbol)
+    }
+
+    /// ## メッセージの送信が許可されているか確認する
+    ///
+    /// 人間検証が無効化された場合は、保留中のメッセージを先に送信してから許可します。
+    ///
+    /// ### Arguments
+    /// - `ctx`: WebSocketコンテキスト
+    ///
+    /// ### Returns
+    /// - `bool`: 検証済み、または人間検証が無効な場合は `true`
+    fn human_verification_passed(&mut self, ctx: &mut ws::WebsocketContext<Self>) -> bool {
+        if self.verified_human {
+            return true;
+        }
+        if self.human_verification_config().enabled {
+            return false;
+        }
+        self.flush_unverified_pending(ctx);
+        true
+    }
+
+    /// ## 人間検証のチャレンジを送信する
+    ///
+    /// ### Arguments
+    /// - `difficulty`: 要求する先頭の0ビット数
+    /// - `ctx`: WebSocketコンテキスト
+    fn send_human_verification_challenge(
+        &mut self,
+        difficulty: u8,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        let pow_challenge = PowChallenge::new(difficulty);
+        match serde_json::to_string(&OutgoingMessage::HumanVerificationRequired {
+            challenge: pow_challenge.challenge.clone(),
+            difficulty: pow_challenge.difficulty,
+            algorithm: POW_ALGORITHM.to_string(),
+        }) {
+            Ok(json) => ctx.text(json),
+            Err(e) => eprintln!("人間検証チャレンジのシリアライズに失敗: {}", e),
+        }
+        self.pow_challenge = Some(pow_challenge);
+    }
+
+    /// ## 人間検証の完了までメッセージを保留する
+    ///
+    /// チャレンジ未送信の場合はここで送信します。保留数が上限に達した場合はエラーを返します。
+    /// スーパーチャットは送金済みのため保留の対象外です。
+    ///
+    /// ### Arguments
+    /// - `client_msg`: 保留するクライアントメッセージ
+    /// - `ctx`: WebSocketコンテキスト
+    fn hold_until_verified(
+        &mut self,
+        client_msg: ClientMessage,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        if self.pow_challenge.is_none() {
+            let difficulty = self.human_verification_config().difficulty;
+            self.send_human_verification_challenge(difficulty, ctx);
+        }
+
+        if self.unverified_pending.len() >= MAX_UNVERIFIED_PENDING_MESSAGES {
+            ctx.text(self.create_error_response(
+                "人間検証が完了していないため、これ以上メッセージを送信できません",
+            ));
+            return;
+        }
+        self.unverified_pending.push(client_msg);
+        println!(
+            "人間検証の完了までメッセージを保留: {}件",
+            self.unverified_pending.len()
+        );
+    }
+
+    /// ## 人間検証の解答を処理する
+    ///
+    /// 正しい解答の場合は検証済みとして記録し、保留中のメッセージを送信します。
+    ///
+    /// ### Arguments
+    /// - `challenge`: クライアントが解いたチャレンジ
+    /// - `nonce`: クライアントが見つけた値
+    /// - `ctx`: WebSocketコンテキスト
+    fn handle_human_verification(
+        &mut self,
+        challenge: &str,
+        nonce: &str,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        let Some(pow_challenge) = &self.pow_challenge else {
+            ctx.text(self.create_error_response("有効な人間検証チャレンジがありません"));
+            return;
+        };
+        if !pow_challenge.verify(challenge, nonce) {
+            ctx.text(self.create_error_response("人間検証の解答が正しくありません"));
+            return;
+        }
+
+        self.pow_challenge = None;
+        self.verified_human = true;
+        if let Some(client_info) = &mut self.client_info {
+            client_info.is_verified_human = true;
+            if let Some(manager) = &self.connection_manager {
+                manager.update_client(&client_info.id, |info| info.is_verified_human = true);
+            }
+            println!("人間検証を完了: {}", client_info.id);
+        }
+
+        match serde_json::to_string(&OutgoingMessage::HumanVerified) {
+            Ok(json) => ctx.text(json),
+            Err(e) => eprintln!("人間検証完了通知のシリアライズに失敗: {}", e),
+        }
+        self.flush_unverified_pending(ctx);
+    }
+
+    /// ## 保留中のメッセージを保存・ブロードキャストする
+    ///
+    /// ### Arguments
+    /// - `ctx`: WebSocketコンテキスト
+    fn flush_unverified_pending(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        for client_msg in std::mem::take(&mut self.unverified_pending) {
+            self.deliver_message(client_msg, ctx);
+        }
+    }
+
+    /// ## 受信したクライアントメッセージを処理する
+    ///
+    /// JSONテキスト・MessagePackバイナリのどちらで受信したメッセージも同じ処理に流します。
+    ///
+    /// ### Arguments
+    /// - `client_msg`: 受信したクライアントメッセージ
+    /// - `ctx`: WebSocketコンテキスト
+    fn handle_client_message(
+        &mut self,
+        mut client_msg: ClientMessage,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        // OBSオーバーレイの接続は受信専用のため、履歴の取得以外は受け付けない
+        if self.obs_overlay && !matches!(client_msg, ClientMessage::GetHistory { .. }) {
+            ctx.text(
+                self.create_error_response("OBSオーバーレイの接続からはメッセージを送信できません"),
+            );
+            return;
+        }
+
+        // 接続後に開始・終了したチャンネルのセッションを反映する
+        self.refresh_session_id();
+
+        // 長すぎる本文・表示名は設定に応じて切り詰めるか拒否する
+        if let Err(e) = self.apply_message_limits(&mut client_msg) {
+            ctx.text(self.create_error_response(&e));
+            return;
+        }
+
+        // チャットメッセージの言語を判定（軽量な判定のため同期的に実行）
+        if let ClientMessage::Chat(chat_msg) = &mut client_msg {
+            chat_msg.detected_language = detect_language(&chat_msg.content);
+        }
+
+        // 翻訳はサーバー側で設定するため、NGワードの確認を経ていないクライアントからの値は使用しない
+        match &mut client_msg {
+            ClientMessage::Chat(chat_msg) => chat_msg.translated_content = None,
+            ClientMessage::Superchat(superchat_msg) => superchat_msg.translated_content = None,
+            _ => {}
+        }
+
+        // メッセージタイプごとに処理
+        match client_msg {
+            // 履歴取得リクエスト
+            ClientMessage::GetHistory {
+                message_type: _,
+                limit,
+                before_timestamp,
+                before_sequence,
+                channel,
+                language,
+            } => {
+                self.handle_get_history(
+                    limit,
+                    before_timestamp,
+                    before_sequence,
+                    channel,
+                    language,
+                    ctx,
+                );
+            }
+            // 再接続時の欠損メッセージの再送リクエスト
+            ClientMessage::Resume { last_seq, .. } => {
+                self.handle_resume(last_seq, ctx);
+            }
+            // チャンネル購読・購読解除リクエスト
+            ClientMessage::ChannelSubscription { action, channel } => {
+                self.handle_channel_subscription(action, &channel, ctx);
+            }
+            // 人間検証の解答
+            ClientMessage::HumanVerification {
+                challenge, nonce, ..
+            } => {
+                self.handle_human_verification(&challenge, &nonce, ctx);
+            }
+            // 既存のチャットとスーパーチャットの処理
+            _ => {
+                // 通常チャットの連投を制限（スーパーチャットは対象外）
+                if matches!(client_msg, ClientMessage::Chat(_)) && !self.check_message_rate_limit()
+                {
+                    ctx.text(self.create_error_response("メッセージ送信が速すぎます"));
+                    return;
+                }
+
+                // 不正な形式のウォレットアドレス・トランザクションハッシュのスーパーチャットは保存しない
+                if let ClientMessage::Superchat(superchat_msg) = &mut client_msg {
+                    let superchat = &mut superchat_msg.superchat;
+                    let validated = validation::validate_sui_address(&superchat.wallet_address)
+                        .and_then(|address| {
+                            validation::validate_tx_hash(&superchat.tx_hash)
+                                .map(|tx_hash| (address, tx_hash))
+                        });
+                    match validated {
+                        Ok((address, tx_hash)) => {
+                            superchat.wallet_address = address;
+                            superchat.tx_hash = tx_hash;
+                        }
+                        Err(e) => {
+                            println!("不正な形式のスーパーチャットを拒否: {}", e);
+                            ctx.text(self.create_error_response(
+                                "ウォレットアドレスまたはトランザクションハッシュの形式が不正です",
+                            ));
+                            return;
+                        }
+                    }
+                }
+
+                // 未登録のコインによるスーパーチャットは受け付けない
+                if let ClientMessage::Superchat(superchat_msg) = &mut client_msg {
+                    let coin = &superchat_msg.superchat.coin;
+                    let Some(coin_info) = self.find_supported_coin(coin) else {
+                        ctx.text(
+                            self.create_error_response(&format!(
+                                "対応していないコインです: {}",
+                                coin
+                            )),
+                        );
+                        return;
+                    };
+                    // 配信者が受け付けるコインを制限している場合、それ以外のコインは配信せずに拒否する
+                    // （送金済みの可能性があるため、後から照合できるよう記録する）
+                    if !self.is_coin_accepted(&coin_info.symbol) {
+                        println!("受け付けていないコインのスーパーチャットを拒否: {}", coin);
+                        self.record_rejected_superchat(
+                            superchat_msg,
+                            REJECT_REASON_COIN_NOT_ACCEPTED,
+                        );
+                        ctx.text(self.create_error_response("このコインは現在受け付けていません"));
+                        return;
+                    }
+                    // 最小単位で送られた金額は配信・検証の前に表示単位へ変換する
+                    superchat_msg.superchat.normalize_amount(Some(&coin_info));
+                }
+
+                // NGワードを含むメッセージは配信しない（スーパーチャットは設定により伏字）
+                if !self.moderate_message(&mut client_msg) {
+                    ctx.text(self.create_error_response(
+                        "NGワードが含まれているため、メッセージを送信できませんでした",
+                    ));
+                    return;
+                }
+
+                // 人間検証が必要な場合は完了まで保留（送金済みのスーパーチャットは保留すると
+                // 上限超過や切断で失われるため、保留せずに配信する）
+                let is_superchat = matches!(client_msg, ClientMessage::Superchat(_));
+                if !is_superchat && !self.human_verification_passed(ctx) {
+                    self.hold_until_verified(client_msg, ctx);
+                    return;
+                }
+
+                // メッセージをDBに保存してブロードキャスト
+                self.deliver_message(client_msg, ctx);
+            }
+        }
+    }
+
+    /// ## 配信しなかったスーパーチャットを記録する
+    ///
+    /// 送金済みの可能性があるスーパーチャットを後から照合できるよう、バックグラウンドでDBに記録します。
+    ///
+    /// ### Arguments
+    /// - `superchat_msg`: 配信しなかったスーパーチャット
+    /// - `reason`: 配信しなかった理由（`REJECT_REASON_*`）
+    fn record_rejected_superchat(&self, superchat_msg: &SuperchatMessage, reason: &str) {
+        let Some(db_pool) = self.db_pool.lock().ok().and_then(|guard| guard.clone()) else {
+            return;
+        };
+        let rejected = RejectedSuperchat::from_superchat(
+            superchat_msg,
+            self.current_session_id.clone(),
+            reason,
+        );
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = database::record_rejected_superchat(&db_pool, &rejected).await {
+                eprintln!(
+                    "配信しなかったスーパーチャットの記録に失敗しました ({}): {}",
+                    rejected.tx_hash, e
+                );
+            }
+        });
+    }
+
+    /// ## 実行中のトンネルのプロバイダを取得する
+    ///
+    /// ### Returns
+    /// - `Option<TunnelKind>`: 実行中のトンネルのプロバイダ（トンネルを実行していない場合はNone）
+    fn running_tunnel_kind(&self) -> Option<TunnelKind> {
+        let app_state = self.app_handle.as_ref()?.try_state::<AppState>()?;
+        let tunnel_info = app_state.tunnel_info.lock().ok()?;
+        tunnel_info
+            .as_ref()?
+            .as_ref()
+            .ok()
+            .map(|tunnel| tunnel.kind)
+    }
+
+    /// ## 所属する配信セッションを解決し直す
+    ///
+    /// 接続時に指定したチャンネルのセッションが接続後に開始・終了した場合に、
+    /// メッセージを記録するセッションと接続情報のセッションIDを更新します。
+    fn refresh_session_id(&mut self) {
+        let Some(app_state) = self
+            .app_handle
+            .as_ref()
+            .and_then(|app_handle| app_handle.try_state::<AppState>())
+        else {
+            return;
+        };
+        let session_id =
+            stream_sessions::session_id_for_channel(&app_state, self.session_channel.as_deref());
+        if session_id == self.current_session_id {
+            return;
+        }
+        println!(
+            "所属する配信セッションを更新しました: {:?} -> {:?} (channel: {:?})",
+            self.current_session_id, session_id, self.session_channel
+        );
+        self.current_session_id = session_id.clone();
+        if let Some(client_info) = &mut self.client_info {
+            client_info.session_id = session_id.clone();
+            if let Some(manager) = &self.connection_manager {
+                manager.update_client(&client_info.id, |info| info.session_id = session_id);
+            }
+        }
+    }
+
+    /// ## 配信したメッセージの翻訳を追って送信する
+    ///
+    /// 翻訳が有効で、メッセージの言語が配信者の設定言語と異なる場合は非同期で翻訳し、
+    /// 配信済みのメッセージIDと翻訳文を `type: "translation"` メッセージとして送信します。
+    /// 翻訳を待たずに配信するため、翻訳APIの応答時間はメッセージの表示に影響しません。
+    ///
+    /// ### Arguments
+    /// - `client_msg`: 配信したクライアントメッセージ
+    /// - `ctx`: WebSocketコンテキスト
+    fn send_translation(&self, client_msg: &ClientMessage, ctx: &mut ws::WebsocketContext<Self>) {
+        let (id, content, detected_language, channel) = match client_msg {
+            ClientMessage::Chat(chat_msg) => (
+                chat_msg.id.clone(),
+                chat_msg.content.clone(),
+                chat_msg.detected_language.clone(),
+                Some(normalize_channel(chat_msg.channel.as_deref())),
+            ),
+            // スーパーチャットは全クライアントに配信するため、翻訳も全クライアントに送信する
+            ClientMessage::Superchat(superchat_msg) => (
+                superchat_msg.id.clone(),
+                superchat_msg.content.clone(),
+                detect_language(&superchat_msg.content),
+                None,
+            ),
+            _ => return,
+        };
+        let job = self
+            .app_handle
+            .as_ref()
+            .and_then(|app_handle| app_handle.try_state::<AppState>())
+            .and_then(|app_state| {
+                TranslationJob::prepare(
+                    &app_state,
+                    &content,
+                    detected_language.as_deref(),
+                    channel.is_none(),
+                )
+            });
+        let (Some(job), Some(manager)) = (job, self.connection_manager.clone()) else {
+            return;
+        };
+
+        let fut = async move { job.translate(&content).await };
+        let fut = actix::fut::wrap_future::<_, Self>(fut);
+        ctx.spawn(fut.map(move |result, _actor, _ctx| {
+            let translated_content = match result {
+                Ok(translated_content) => translated_content,
+                Err(e) => {
+                    eprintln!("メッセージの翻訳に失敗しました: {}", e);
+                    return;
+                }
+            };
+            let json = match serde_json::to_string(&OutgoingMessage::Translation {
+                id,
+                translated_content,
+            }) {
+                Ok(json) => json,
+                Err(e) => {
+                    eprintln!("翻訳メッセージのシリアライズに失敗: {}", e);
+                    return;
+                }
+            };
+            let broadcast = Broadcast::text(json).with_priority(BroadcastPriority::Low);
+            match channel {
+                Some(channel) => manager.broadcast_frame_to_channel(broadcast, &channel),
+                None => manager.broadcast_frame(broadcast),
+            }
+        }));
+    }
+
+    /// ## メッセージを保存・ブロードキャストする
+    ///
+    /// トランザクション検証が有効な場合、スーパーチャットはSuiチェーン上で送金を確認してから
+    /// 設定に応じて検証済み・未検証フラグ付き・通常チャットへの降格のいずれかで配信します。
+    /// 保存済みのトランザクションを再度申告したスーパーチャットは配信しません。
+    ///
+    /// ### Arguments
+    /// - `client_msg`: 送信するクライアントメッセージ
+    /// - `ctx`: WebSocketコンテキスト
+    fn deliver_message(&self, mut client_msg: ClientMessage, ctx: &mut ws::WebsocketContext<Self>) {
+        // DBとブロードキャストで時刻をそろえるため、サーバーの受信時刻で上書きする
+        client_msg.set_server_timestamp(Utc::now().timestamp_millis());
+
+        // 再送などで処理済みのメッセージは検証せずに除外する（処理済みとしての記録は配信の直前に行う）
+        if let (Some(message_id), Some(manager)) =
+            (client_msg.message_id(), &self.connection_manager)
+        {
+            if manager.is_message_processed(message_id) {
+                println!(
+                    "処理済みのメッセージのため配信をスキップしました: ID={}",
+                    message_id
+                );
+                return;
+            }
+        }
+
+        let app_state = self
+            .app_handle
+            .as_ref()
+            .and_then(|app_handle| app_handle.try_state::<AppState>());
+        let config = app_state
+            .as_ref()
+            .map_or_else(TxVerificationConfig::default, |app_state| {
+                TxVerificationConfig::from_app_state(app_state)
+            });
+
+        let mut superchat_msg = match client_msg {
+            ClientMessage::Superchat(superchat_msg) => superchat_msg,
+            client_msg => {
+                self.save_and_broadcast(client_msg, ctx);
+                return;
+            }
+        };
+
+        let streamer_wallet = app_state
+            .as_ref()
+            .and_then(|app_state| wallet_registry::active_wallet_address(app_state));
+        let coin = app_state.and_then(|app_state| {
+            coin_registry::find_coin(&app_state, &superchat_msg.superchat.coin)
+        });
+        // 1つの送金を複数のスーパーチャットとして申告できないよう、検証中・保存済みのトランザクションは検証設定にかかわらず拒否する
+        // (保存は配信時に非同期で行われるため、DBを確認する前に予約して同時に届いたものを除外する)
+        if let Some(manager) = &self.connection_manager {
+            if !manager.reserve_tx_hash(&superchat_msg.superchat.tx_hash, &superchat_msg.id) {
+                println!(
+                    "検証中または配信済みのトランザクションのスーパーチャットを拒否: {}",
+                    superchat_msg.superchat.tx_hash
+                );
+                ctx.text(self.create_error_response(TX_HASH_ALREADY_USED_MESSAGE));
+                return;
+            }
+        }
+        let db_pool = self.db_pool.lock().ok().and_then(|guard| guard.clone());
+        let superchat = superchat_msg.superchat.clone();
+        let fut = async move {
+            if let Some(db_pool) = &db_pool {
+                match database::message_exists_with_tx_hash(db_pool, &superchat.tx_hash).await {
+                    Ok(true) => return Err(TX_HASH_ALREADY_USED_MESSAGE),
+                    Ok(false) => {}
+                    Err(e) => {
+                        // 重複を確認できない場合は、二重の配信を避けるため受け付けない
+                        eprintln!("トランザクションの重複確認に失敗しました: {}", e);
+                        return Err(
+                            "トランザクションを確認できませんでした。時間をおいて再送してください",
+                        );
+                    }
+                }
+            }
+            if !config.enabled {
+                return Ok(None);
+            }
+            Ok(Some(
+                verify_superchat_transaction(&superchat, coin.as_ref(), streamer_wallet).await,
+            ))
+        };
+        let fut = actix::fut::wrap_future::<_, Self>(fut);
+        ctx.spawn(fut.map(move |result, actor, ctx| {
+            let result = match result {
+                Ok(Some(result)) => result,
+                Ok(None) => {
+                    // 検証フラグはサーバー側で設定するため、クライアントからの値は使用しない
+                    superchat_msg.verified = None;
+                    actor.save_and_broadcast(ClientMessage::Superchat(superchat_msg), ctx);
+                    return;
+                }
+                Err(error_message) => {
+                    println!(
+                        "トランザクションの重複確認によりスーパーチャットを拒否: {}",
+                        superchat_msg.superchat.tx_hash
+                    );
+                    actor.release_tx_hash(&superchat_msg);
+                    ctx.text(actor.create_error_response(error_message));
+                    return;
+                }
+            };
+            // 他人のウォレットを申告して称号を表示させたり接続を切断させたりしないよう、送金者を確認できた場合のみ紐づける
+            if result == TxVerificationResult::Verified {
+                actor.bind_verified_wallet(&superchat_msg.superchat.wallet_address);
+            }
+            let action = config.action_for(&result);
+            if let TxVerificationResult::Mismatch(reason)
+            | TxVerificationResult::Unavailable(reason) = &result
+            {
+                println!(
+                    "スーパーチャットのトランザクションを検証できませんでした: {} ({}) -> {:?}",
+                    superchat_msg.superchat.tx_hash, reason, action
+                );
+            }
+
+            let client_msg = match action {
+                UnverifiedAction::Accept => {
+                    superchat_msg.verified =
+                        (result == TxVerificationResult::Verified).then_some(true);
+                    ClientMessage::Superchat(superchat_msg)
+                }
+                UnverifiedAction::MarkUnverified => {
+                    superchat_msg.verified = Some(false);
+                    ClientMessage::Superchat(superchat_msg)
+                }
+                UnverifiedAction::Downgrade => {
+                    // スーパーチャットとして使用しなかったため、正しい送金者からの再送を受け付ける
+                    actor.release_tx_hash(&superchat_msg);
+                    ctx.text(actor.create_error_response(
+                        "送金を確認できなかったため、通常のチャットとして送信しました",
+                    ));
+                    ClientMessage::Chat(ChatMessage {
+                        message_type: MessageType::Chat,
+                        id: superchat_msg.id,
+                        display_name: superchat_msg.display_name,
+                        detected_language: detect_language(&superchat_msg.content),
+                        content: superchat_msg.content,
+                        timestamp: superchat_msg.timestamp,
+                        channel: None,
+                        translated_content: superchat_msg.translated_content,
+                        badges: Vec::new(),
+                    })
+                }
+            };
+            actor.save_and_broadcast(client_msg, ctx);
+        }));
+    }
+
+    /// ## メッセージを処理済みとして記録し、保存・ブロードキャストする
+    ///
+    /// 検証中に別の経路で同じメッセージが配信済みの場合は何もしません。
+    ///
+    /// ### Arguments
+    /// - `client_msg`: 送信するクライアントメッセージ
+    /// - `ctx`: WebSocketコンテキスト
+    fn save_and_broadcast(&self, client_msg: ClientMessage, ctx: &mut ws::WebsocketContext<Self>) {
+        if let (Some(message_id), Some(manager)) =
+            (client_msg.message_id(), &self.connection_manager)
+        {
+            if !manager.mark_message_processed(message_id) {
+                println!(
+                    "処理済みのメッセージのため配信をスキップしました: ID={}",
+                    message_id
+                );
+                return;
+            }
+        }
+        self.save_message_to_db(&client_msg);
+        self.send_translation(&client_msg, ctx);
+        self.broadcast_message(client_msg, ctx);
+    }
+
+    /// ## スーパーチャットのトランザクションハッシュの予約を解除する
+    ///
+    /// ### Arguments
+    /// - `superchat_msg`: 配信しなかったスーパーチャット
+    fn release_tx_hash(&self, superchat_msg: &SuperchatMessage) {
+        if let Some(manager) = &self.connection_manager {
+            manager.release_tx_hash(&superchat_msg.superchat.tx_hash, &superchat_msg.id);
+        }
+    }
+
+    /// ## 送金を確認できたウォレットを接続に紐づける
+    ///
+    /// 通常チャットの称号判定と、同一ウォレットからの接続数の制限に使用します。
+    /// 他人のウォレットを申告して称号を表示させないよう、トランザクション検証で送金者を
+    /// 確認できたウォレットのみを紐づけてください。
+    ///
+    /// ### Arguments
+    /// - `wallet_address`: 送金者のウォレットアドレス
+    fn bind_verified_wallet(&mut self, wallet_address: &str) {
+        if wallet_address.is_empty() {
+            return;
+        }
+        self.wallet_address = Some(wallet_address.to_string());
+        if let (Some(client_info), Some(manager)) = (&self.client_info, &self.connection_manager) {
+            manager.bind_wallet(&client_info.id, wallet_address);
+        }
+    }
+
+    /// ## 最大接続数超過のため接続を拒否する
+    ///
+    /// 代替URLが設定されている場合は `type: "redirect"` メッセージで誘導し、
+    /// クライアントが受信する猶予を置いてから切断します。
+    /// 未設定の場合は従来通りエラーメッセージを送信して即座に切断します。
+    ///
+    /// ### Arguments
+    /// - `ctx`: WebSocketコンテキスト (`&mut ws::WebsocketContext<Self>`)
+    fn reject_overflow_connection(&self, ctx: &mut ws::WebsocketContext<Self>) {
+        let redirect_url =
+            super::connection_manager::global::get_app_handle().and_then(|app_handle| {
+                app_handle
+                    .try_state::<AppState>()
+                    .and_then(|app_state| app_state.overflow_redirect_url.lock().ok()?.clone())
+            });
+
+        let redirect_json = redirect_url.and_then(|url| {
+            serde_json::to_string(&OutgoingMessage::Redirect {
+                url,
+                reason: "Maximum connections reached.".to_string(),
+            })
+            .map_err(|e| eprintln!("リダイレクトメッセージのシリアライズに失敗: {}", e))
+            .ok()
+        });
+
+        match redirect_json {
+            Some(json) => {
+                println!("最大接続数に達したため、代替URLへ誘導して切断します");
+                ctx.text(json);
+                ctx.run_later(OVERFLOW_REDIRECT_GRACE, |_act, ctx| {
+                    ctx.close(None);
+                    ctx.stop();
+                });
+            }
+            None => {
+                ctx.text(
+                    self.create_error_response("Maximum connections reached. Try again later."),
+                );
+                ctx.close(None);
+                ctx.stop();
+            }
+        }
+    }
+
+    /// ## スーパーチャットを全クライアントにブロードキャストする
+    ///
+    /// ### Arguments
+    /// - `superchat_msg`: ブロードキャストするスーパーチャットメッセージ
+    /// - `ctx`: WebSocketコンテキスト (`&mut ws::WebsocketContext<Self>`)
+    fn broadcast_superchat(
+        &self,
+        superchat_msg: &SuperchatMessage,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        match serde_json::to_string(superchat_msg) {
+            Ok(json) => {
+                // 全クライアントにメッセージをブロードキャスト
+                if let Some(manager) = &self.connection_manager {
+                    let broadcast =
+                        Broadcast::with_protobuf(json, protobuf::encode_superchat(superchat_msg))
+                            .with_priority(BroadcastPriority::High);
+                    manager.broadcast_frame(broadcast);
+                }
+
+                // OBSのアラート演出用にフロントエンドへ受信を通知し、設定されていればお礼を送る
+                if let Some(app_handle) = super::connection_manager::global::get_app_handle() {
+                    superchat_alert::notify_superchat_received(&app_handle, superchat_msg);
+                    auto_thanks::broadcast_thanks(&app_handle, superchat_msg);
+                }
+            }
+            Err(e) => {
+                eprintln!("メッセージのシリアライズに失敗: {}", e);
+                ctx.text(self.create_error_response(&format!("メッセージ処理エラー: {}", e)));
+            }
+        }
+    }
+
+    /// チャンネル購読・購読解除リクエストを処理する
+    ///
+    /// 接続マネージャーの購読情報を更新し、更新後の購読チャンネル一覧をクライアントに返します。
+    ///
+    /// ### Arguments
+    /// - `action`: 購読または購読解除
+    /// - `channel`: 対象のチャンネル名
+    /// - `ctx`: WebSocketコンテキスト
+    fn handle_channel_subscription(
+        &self,
+        action: ChannelAction,
+        channel: &str,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        let channel = channel.trim();
+        if channel.is_empty() || channel.chars().count() > MAX_CHANNEL_NAME_LENGTH {
+            ctx.text(self.create_error_response(&format!(
+                "チャンネル名は1〜{}文字で指定してください",
+                MAX_CHANNEL_NAME_LENGTH
+            )));
+            return;
+        }
+
+        let (Some(client_info), Some(manager)) = (&self.client_info, &self.connection_manager)
+        else {
+            ctx.text(self.create_error_response("クライアント情報が登録されていません"));
+            return;
+        };
+
+        let channels = match action {
+            ChannelAction::Subscribe => manager.subscribe_channel(&client_info.id, channel),
+            ChannelAction::Unsubscribe => manager.unsubscribe_channel(&client_info.id, channel),
+        };
+
+        match channels {
+            Some(channels) => {
+                println!(
+                    "チャンネル購読を更新: client={}, action={:?}, channels={:?}",
+                    client_info.id, action, channels
+                );
+                match serde_json::to_string(&OutgoingMessage::ChannelsUpdated { channels }) {
+                    Ok(json) => ctx.text(json),
+                    Err(e) => ctx.text(
+                        self.create_error_response(&format!("JSONシリアライズエラー: {}", e)),
+                    ),
+                }
+            }
+            None => {
+                ctx.text(self.create_error_response("クライアント情報が登録されていません"));
+            }
+        }
+    }
+
+    /// 再接続時の再送リクエストを処理する
+    ///
+    /// 再送キャッシュから切断中に配信されたメッセージを取り出し、通常のブロードキャストと同じく
+    /// フロー制御を経て送信します。キャッシュで補えない場合、クライアントは GET_HISTORY で補完します。
+    /// 再接続後に受信したブロードキャストは再送分より先に届くことがあり、再送分と重複することもあるため、
+    /// クライアントは `seq` で並べ替え・重複排除してください。
+    ///
+    /// ### Arguments
+    /// - `last_seq`: クライアントが最後に受信したシーケンス番号
+    /// - `ctx`: WebSocketコンテキスト
+    fn handle_resume(&mut self, last_seq: u64, ctx: &mut ws::WebsocketContext<Self>) {
+        // ブロードキャストのシーケンス番号はサーバー起動時のセッションを基準に採番している
+        let session_id = self
+            .app_handle
+            .as_ref()
+            .and_then(|app_handle| app_handle.try_state::<AppState>())
+            .and_then(|app_state| stream_sessions::primary_session_id(&app_state));
+        let replay = match (&self.connection_manager, &self.client_info) {
+            (Some(manager), Some(client_info)) => {
+                manager.replay_since(&client_info.id, session_id.as_deref(), last_seq)
+            }
+            _ => None,
+        };
+        let Some(replay) = replay else {
+            ctx.text(self.create_error_response("クライアント情報が登録されていません"));
+            return;
+        };
+
+        println!(
+            "再接続クライアントに再送: {}件 (last_seq: {}, complete: {})",
+            replay.messages.len(),
+            last_seq,
+            replay.complete
+        );
+        let replayed = replay.messages.len();
+        let dropped_before = self.flow.dropped();
+        for message in replay.messages {
+            self.offer_broadcast(message, ctx);
+        }
+        // バッファ溢れで間引いた場合は欠損分を全て再送できていない
+        let notice = OutgoingMessage::ResumeComplete {
+            replayed,
+            latest_seq: replay.latest_seq,
+            complete: replay.complete && self.flow.dropped() == dropped_before,
+        };
+        match serde_json::to_string(&notice) {
+            Ok(json) => ctx.text(json),
+            Err(e) => eprintln!("再送完了通知のシリアライズに失敗: {}", e),
+        }
+    }
+
+    /// 履歴取得リクエストを処理する
+    ///
+    /// クライアントからの過去ログ取得リクエストを処理し、
+    /// データベースから該当するメッセージを取得して返します。
+    ///
+    /// ### Arguments
+    /// - `limit`: 取得するメッセージの最大数（オプション、デフォルト50）
+    /// - `before_timestamp`: このタイムスタンプより前のメッセージのみを取得（オプション）
+    /// - `before_sequence`: `before_timestamp` と同一時刻のメッセージのうち、このシーケンス番号より前のものも取得（オプション）
+    /// - `channel`: 取得対象のチャンネル（オプション、指定しない場合は全チャンネル）
+    /// - `language`: 取得対象の言語（ISO 639-1、オプション、指定しない場合は全言語）
+    /// - `ctx`: WebSocketコンテキスト
+    fn handle_get_history(
+        &self,
+        limit: Option<i64>,
+        before_timestamp: Option<i64>,
+        before_sequence: Option<i64>,
+        channel: Option<String>,
+        language: Option<String>,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        // セッションIDを確認
+        let session_id = match &self.current_session_id {
+            Some(id) => id.clone(),
+            None => {
+                println!("履歴取得エラー: セッションIDが設定されていません");
+                let error_msg = self.create_error_response(
+                    "セッションIDが設定されていません。履歴を取得できません。",
+                );
+                ctx.text(error_msg);
+                return;
+            }
+        };
+
+        // DB接続プールを取得
+        let db_pool = {
+            let pool_guard = match self.db_pool.lock() {
+                Ok(guard) => guard,
+                Err(e) => {
+                    println!("履歴取得エラー: DBプールのロックに失敗: {}", e);
+                    let error_msg = self.create_error_response("データベース接続エラー");
+                    ctx.text(error_msg);
+                    return;
+                }
+            };
+
+            match &*pool_guard {
+                Some(pool) => pool.clone(),
+                None => {
+                    println!("履歴取得エラー: DBプールが初期化されていません");
+                    let error_msg =
+                        self.create_error_response("データベース接続が初期化されていません");
+                    ctx.text(error_msg);
+                    return;
+                }
+            }
+        };
+
+        // 非同期処理でDBからメッセージを取得
+        let safe_limit = limit.unwrap_or(50);
+        let session_id_clone = session_id.clone();
+        let language = normalize_language_filter(language.as_deref());
+        let fut = async move {
+            // DBからメッセージを取得
+            match crate::database::get_messages_by_session_id(
+                &db_pool,
+                &session_id_clone,
+                safe_limit,
+                before_timestamp,
+                before_sequence,
+                channel.as_deref(),
+                language.as_deref(),
+            )
+            .await
+            {
+                Ok(messages) => {
+                    // さらに古いメッセージがあるかのフラグ
+                    let has_more = messages.len() as i64 > safe_limit;
+
+                    // 実際に返す件数をlimitに制限
+                    let limited_messages = if has_more {
+                        messages[..messages.len() - 1].to_vec()
+                    } else {
+                        messages
+                    };
+
+                    // DB-Modelを送信用のSerializableMessageに変換
+                    let serializable_messages: Vec<crate::types::SerializableMessage> =
+                        limited_messages.into_iter().map(|msg| msg.into()).collect();
+
+
+                    // レスポンスを構築
+                    let history_data = crate::types::OutgoingMessage::HistoryData {
+                        messages: serializable_messages,
+                        has_more,
+                    };
+
+                    // JSONに変換
+                    match serde_json::to_string(&history_data) {
+                        Ok(json) => {
+                            Ok(json)
+                        }
+                        Err(e) => {
+                            println!("履歴データのJSONシリアライズに失敗: {}", e);
+                            Err(format!("JSONシリアライズエラー: {}", e))
+                        }
+                    }
+                }
+                Err(e) => {
+                    println!("履歴取得時のデータベースエラー: {}", e);
+                    Err(format!("データベースエラー: {}", e))
+                }
+            }
+        };
+
+        // 非同期処理を実行
+        let fut = actix::fut::wrap_future::<_, Self>(fut);
+
+        // 非同期処理の結果を処理
+        ctx.spawn(fut.map(|result, _actor, ctx| match result {
+            Ok(json) => ctx.text(json),
+            Err(e) => {
+                let error_response = _actor.create_error_response(&e);
+                ctx.text(error_response);
+            }
+        }));
+    }
+}
+
+/// ## Actor トレイトの実装
+///
+/// `WsSession` を Actix アクターとして動作させるための実装。
+impl Actor for WsSession {
+    type Context = ws::WebsocketContext<Self>;
+
+    /// ## アクター開始時の処理
+    ///
+    /// アクターが開始されたときに呼び出され、ハートビート処理を開始します。
+    ///
+    /// ### Arguments
+    /// - `ctx`: アクターコンテキスト (`ws::WebsocketContext<Self>`)
+    fn started(&mut self, ctx: &mut Self::Context) {
+        println!("WebSocket Session Started");
+
+        // AppStateからセッションIDを取得（クエリパラメータ `channel` で同時配信のセッションを指定）
+        if let Some(app_handle) = super::connection_manager::global::get_app_handle() {
+            if let Some(app_state) = app_handle.try_state::<AppState>() {
+                let channel = self
+                    .req
+                    .as_ref()
+                    .and_then(|req| stream_sessions::channel_from_query(req.query_string()));
+                self.current_session_id =
+                    stream_sessions::session_id_for_channel(&app_state, channel.as_deref());
+                self.session_channel = channel.clone();
+                if let Some(ref session_id) = self.current_session_id {
+                    println!(
+                        "WebSocket Session: Using session ID: {} (channel: {:?})",
+                        session_id, channel
+                    );
+                } else {
+                    println!("WebSocket Session: No active session ID found");
+                }
+            } else {
+                println!("WebSocket Session: AppState not available");
+            }
+        } else {
+            println!("WebSocket Session: app_handle not available");
+        }
+
+        // OBSオーバーレイは視聴者とは別枠で登録し、接続数・最大接続数に含めない
+        if self.obs_overlay {
+            if let Some(manager) = &self.connection_manager {
+                let connection_id = Uuid::new_v4().to_string();
+                manager.add_obs_client(&connection_id, ctx.address());
+                self.obs_connection_id = Some(connection_id);
+            }
+            self.hb(ctx);
+            // オーバーレイへのブロードキャストもフロー制御を経由するため、バッファの送信を開始する
+            self.flush_flow_control(ctx);
+            return;
+        }
+
+        // リクエストからクライアント情報を取得
+        if let Some(req) = &self.req {
+            if let Some(addr) = req.peer_addr() {
+                let mut client_info = ClientInfo::new(addr);
+                // トンネル経由の接続は転送元のIPで記録し、視聴者ごとにブロックできるようにする
+                let tunnel = self.running_tunnel_kind();
+                let remote_ip = network_type::client_ip(req, tunnel).unwrap_or(addr.ip());
+                client_info.ip = remote_ip.to_string();
+                // 接続元のネットワーク種別（モバイル/固定）を推定
+                let network_type = network_type::classify(remote_ip);
+                client_info.network_type = Some(network_type.as_str().to_string());
+                client_info.connection_method = ConnectionMethod::from_request(req, tunnel)
+                    .map(|method| method.as_str().to_string());
+                client_info.session_id = self.current_session_id.clone();
+                // ASCII以外を含むなど文字列に変換できないヘッダーは記録しない
+                client_info.user_agent = req
+                    .headers()
+                    .get(header::USER_AGENT)
+                    .and_then(|value| value.to_str().ok())
+                    .map(|user_agent| user_agent.chars().take(MAX_USER_AGENT_LEN).collect());
+                let client_id = client_info.id.clone();
+                println!(
+                    "New client connected: {} from {}",
+                    client_id, client_info.ip
+                );
+
+                // 接続マネージャーに追加
+                if let Some(manager) = &self.connection_manager {
+                    // ブロック中のIPアドレスは代替URLへ誘導せずに切断
+                    if manager.is_ip_blocked(&client_info.ip) {
+                        println!("ブロック中のIPアドレスのため切断します: {}", client_info.ip);
+                        ctx.text(self.create_error_response("Connection refused."));
+                        ctx.close(None);
+                        ctx.stop();
+                        return;
+                    }
+                    // セッションアドレスを渡して接続登録
+                    if manager.add_client(client_info.clone(), ctx.address()) {
+                        // 接続元の国をバックグラウンドで取得（トンネル経由の場合は転送元のIPで判定）
+                        geoip::spawn_country_lookup(
+                            manager.clone(),
+                            client_info.id.clone(),
+                            remote_ip,
+                        );
+                        self.client_info = Some(client_info);
+                    } else {
+                        // 最大接続数に達している場合、切断
+                        self.reject_overflow_connection(ctx);
+                        return;
+                    }
+                } else {
+                    // 接続マネージャーがない場合でもClientInfoは設定
+                    self.client_info = Some(client_info);
+                }
+            }
+        }
+
+        // 人間検証が有効な場合は接続時にチャレンジを送信
+        let verification = self.human_verification_config();
+        if verification.enabled {
+            self.send_human_verification_challenge(verification.difficulty, ctx);
+        }
+
+        self.hb(ctx);
+        self.flush_flow_control(ctx);
+    }
+
+    /// ## アクター停止時の処理
+    ///
+    /// アクターが停止したときに呼び出されます。
     ///
     /// ### Arguments
     /// - `_ctx`: アクターコンテキスト (`ws::WebsocketContext<Self>`)
     fn stopped(&mut self, _ctx: &mut Self::Context) {
         println!("WebSocket Session Stopped");
 
+        if self.flow.dropped() > 0 {
+            println!(
+                "フロー制御により間引いたメッセージ数: {}",
+                self.flow.dropped()
+            );
+        }
+
         // クライアント情報がある場合、接続マネージャーから削除
         if let Some(client_info) = &self.client_info {
             if let Some(manager) = &self.connection_manager {
@@ -560,6 +1897,7 @@ impl Actor for WsSession {
                 println!("クライアント削除: {}", client_info.id);
             }
         }
+        self.remove_obs_connection();
     }
 }
 
@@ -573,6 +1911,7 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
             // Pong メッセージ受信: ハートビート時刻を更新
             Ok(ws::Message::Pong(_)) => {
                 self.hb = Instant::now();
+                self.report_heartbeat();
             }
             // Ping メッセージ受信: Pong メッセージを返信
             Ok(ws::Message::Ping(msg)) => {
@@ -583,27 +1922,7 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
             Ok(ws::Message::Text(text)) => {
                 // JSONメッセージのパース
                 match serde_json::from_str::<ClientMessage>(&text) {
-                    Ok(client_msg) => {
-                        // メッセージタイプごとに処理
-                        match client_msg {
-                            // 履歴取得リクエスト
-                            ClientMessage::GetHistory {
-                                message_type: _,
-                                limit,
-                                before_timestamp,
-                            } => {
-                                self.handle_get_history(limit, before_timestamp, ctx);
-                            }
-                            // 既存のチャットとスーパーチャットの処理
-                            _ => {
-                                // メッセージをDBに保存
-                                self.save_message_to_db(&client_msg);
-
-                                // メッセージをブロードキャスト
-                                self.broadcast_message(client_msg, ctx);
-                            }
-                        }
-                    }
+                    Ok(client_msg) => self.handle_client_message(client_msg, ctx),
                     Err(e) => {
                         println!("無効なJSONメッセージを受信: {}", e);
                         let error_response =
@@ -612,11 +1931,20 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
                     }
                 }
             }
-            // バイナリメッセージ受信: 現在は未処理
+            // バイナリメッセージ受信: MessagePackとしてパースしてメッセージ処理
             Ok(ws::Message::Binary(bin)) => {
                 println!("WS Received Binary: {} bytes", bin.len());
-                // 必要に応じてバイナリデータを処理
-                ctx.text(self.create_error_response("バイナリメッセージはサポートされていません"));
+                match msgpack::decode_client_message(&bin) {
+                    Ok(client_msg) => self.handle_client_message(client_msg, ctx),
+                    Err(e) => {
+                        println!("無効なバイナリメッセージを受信: {}", e);
+                        ctx.text(
+                            self.create_error_response(
+                                "バイナリメッセージはサポートされていません",
+                            ),
+                        );
+                    }
+                }
             }
             // Close メッセージ受信 or 接続エラー: アクターを停止
             Ok(ws::Message::Close(reason)) => {
@@ -643,6 +1971,99 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
     }
 }
 
+/// ## スーパーチャットのトランザクションをSuiチェーン上で検証する
+///
+/// `sui_getTransactionBlock` でトランザクションを取得し、申告したウォレットから配信者のウォレットへ
+/// 申告したコインが申告額以上送金されているかを確認します。
+/// RPCエンドポイントは環境変数 `SUI_RPC_URL` で変更でき、タイムアウトは5秒です。
+/// 対応コインのレジストリに登録済みのコインは、コインのメタデータを問い合わせずに照合します。
+/// 未登録のコインは、申告されたコインが型引数全体で指定されている場合のみ照合します。
+///
+/// ### Arguments
+/// - `superchat`: 視聴者が申告したスーパーチャットデータ
+/// - `coin`: 申告されたコインのレジストリ情報（未登録の場合はNone）
+/// - `streamer_wallet`: 配信者のウォレットアドレス
+///
+/// ### Returns
+/// - `TxVerificationResult`: 検証結果
+pub async fn verify_superchat_transaction(
+    superchat: &SuperchatData,
+    coin: Option<&CoinInfo>,
+    streamer_wallet: Option<String>,
+) -> TxVerificationResult {
+    let Some(wallet) = streamer_wallet else {
+        return TxVerificationResult::Unavailable(
+            "配信者のウォレットアドレスが設定されていません".to_string(),
+        );
+    };
+    if superchat.tx_hash.trim().is_empty() {
+        return TxVerificationResult::Mismatch("tx_hashが指定されていません".to_string());
+    }
+
+    let client = match reqwest::Client::builder()
+        .timeout(VERIFICATION_RPC_TIMEOUT)
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => return TxVerificationResult::Unavailable(e.to_string()),
+    };
+    let rpc_url = tx_verification::rpc_url();
+    let transaction = match sui_watcher::rpc_call(
+        &client,
+        &rpc_url,
+        "sui_getTransactionBlock",
+        serde_json::json!([
+            superchat.tx_hash.trim(),
+            { "showInput": true, "showEffects": true, "showBalanceChanges": true }
+        ]),
+    )
+    .await
+    {
+        Ok(transaction) => transaction,
+        // 存在しないダイジェストは偽のtx_hashとして扱う
+        Err(sui_watcher::RpcError::Rpc { code, message })
+            if tx_verification::is_transaction_not_found(code) =>
+        {
+            return TxVerificationResult::Mismatch(message)
+        }
+        Err(e) => return TxVerificationResult::Unavailable(e.to_string()),
+    };
+
+    if let Some(coin) = coin {
+        return tx_verification::check_transaction(
+            &transaction,
+            &superchat.wallet_address,
+            &wallet,
+            &coin.type_arg,
+            superchat.amount,
+            u32::from(coin.decimals),
+        );
+    }
+
+    // 同じシンボルの偽コインと区別できないため、型引数全体で申告されたコインの着金のみを探す
+    let coin_type = tx_verification::received_coin_types(&transaction, &wallet)
+        .into_iter()
+        .find(|coin_type| coin_registry::is_same_coin_type(coin_type, &superchat.coin));
+    let Some(coin_type) = coin_type else {
+        return TxVerificationResult::Mismatch(format!(
+            "配信者のウォレットへの {} の送金が含まれていません",
+            superchat.coin
+        ));
+    };
+    let metadata = match sui_watcher::fetch_coin_metadata(&client, &rpc_url, &coin_type).await {
+        Ok(metadata) => metadata,
+        Err(e) => return TxVerificationResult::Unavailable(e),
+    };
+    tx_verification::check_transaction(
+        &transaction,
+        &superchat.wallet_address,
+        &wallet,
+        &coin_type,
+        superchat.amount,
+        metadata.decimals,
+    )
+}
+
 /// ## WebSocket ルートハンドラー用の拡張関数
 ///
 /// WebSocket ハンドラーでWsSessionを接続マネージャと共に作成します。
@@ -671,18 +2092,179 @@ pub fn create_ws_session(req: HttpRequest) -> WsSession {
     session
 }
 
+/// ## OBSオーバーレイ用のWsSessionを作成する
+///
+/// 接続数・最大接続数に含めない受信専用のセッションを作成します。
+///
+/// ### Arguments
+/// - `req`: HTTPリクエスト
+///
+/// ### Returns
+/// - `WsSession`: OBSオーバーレイ用のWsSessionインスタンス
+pub fn create_obs_ws_session(req: HttpRequest) -> WsSession {
+    create_ws_session(req).as_obs_overlay()
+}
+
 /// ## ブロードキャスト用メッセージ
 ///
-/// 他セッションにテキストを送信するためのActixメッセージ
-#[derive(Message)]
+/// 他セッションにメッセージを送信するためのActixメッセージ。
+/// JSONテキストに加え、バイナリモードのクライアント向けのprotobuf版を持つことができます。
+#[derive(Message, Debug, Clone)]
+#[rtype(result = "()")]
+pub struct Broadcast {
+    /// JSONテキスト
+    pub json: String,
+    /// Protocol Buffersでシリアライズしたバイト列（チャット・スーパーチャットのみ）
+    pub protobuf: Option<Bytes>,
+    /// MessagePackに変換したバイト列（送信先ごとに変換しないよう、配信前に一度だけ変換する）
+    pub msgpack: Option<Bytes>,
+    /// 配信優先度（スーパーチャットは高優先、通常チャットは低優先）
+    pub priority: BroadcastPriority,
+}
+
+/// ## 送信するWebSocketフレーム
+#[derive(Debug)]
+pub enum BroadcastFrame {
+    /// テキストフレーム
+    Text(String),
+    /// Binaryフレーム
+    Binary(Bytes),
+}
+
+impl Broadcast {
+    /// ## JSONテキストのみのブロードキャストを作成する
+    ///
+    /// ### Arguments
+    /// - `json`: 送信するJSONテキスト
+    pub fn text(json: String) -> Self {
+        Self {
+            json,
+            protobuf: None,
+            msgpack: None,
+            priority: BroadcastPriority::Normal,
+        }
+    }
+
+    /// ## protobuf版を含むブロードキャストを作成する
+    ///
+    /// ### Arguments
+    /// - `json`: JSONモードのクライアントに送信するJSONテキスト
+    /// - `protobuf`: バイナリモードのクライアントに送信するバイト列
+    pub fn with_protobuf(json: String, protobuf: Bytes) -> Self {
+        Self {
+            json,
+            protobuf: Some(protobuf),
+            msgpack: None,
+            priority: BroadcastPriority::Normal,
+        }
+    }
+
+    /// ## MessagePack版を付与する
+    ///
+    /// JSONテキストを確定した後（シーケンス番号の付与・署名の後）に呼び出します。
+    /// 変換できない場合はMessagePackモードのクライアントにもJSONテキストを送信します。
+    pub fn with_msgpack(mut self) -> Self {
+        self.msgpack = match msgpack::encode_json(&self.json) {
+            Ok(bin) => Some(bin),
+            Err(e) => {
+                eprintln!("{}", e);
+                None
+            }
+        };
+        self
+    }
+
+    /// ## 配信優先度を設定する
+    ///
+    /// ### Arguments
+    /// - `priority`: 配信優先度
+    pub fn with_priority(mut self, priority: BroadcastPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// ## クライアントのエンコーディングに応じて送信フレームを選択する
+    ///
+    /// バイナリモードでもprotobuf版がないメッセージはJSONテキストで送信します。
+    /// MessagePackモードでは変換済みのMessagePack版を送信し、未変換の場合はここでJSONテキストを変換します。
+    /// 変換できない場合はJSONテキストで送信します。
+    ///
+    /// ### Arguments
+    /// - `encoding`: 送信先クライアントのエンコーディング
+    ///
+    /// ### Returns
+    /// - `BroadcastFrame`: 送信するフレーム
+    pub fn into_frame(self, encoding: BroadcastEncoding) -> BroadcastFrame {
+        match (encoding, self.protobuf) {
+            (BroadcastEncoding::Protobuf, Some(bin)) => BroadcastFrame::Binary(bin),
+            (BroadcastEncoding::MessagePack, _) => match self
+                .msgpack
+                .map_or_else(|| msgpack::encode_json(&self.json), Ok)
+            {
+                Ok(bin) => BroadcastFrame::Binary(bin),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    BroadcastFrame::Text(self.json)
+                }
+            },
+            _ => BroadcastFrame::Text(self.json),
+        }
+    }
+}
+
+/// ## セッション切断要求
+///
+/// IPアドレスのブロック等により、サーバー側からセッションを切断するためのActixメッセージ。
+#[derive(Message, Debug, Clone, Copy)]
+#[rtype(result = "()")]
+pub struct Disconnect;
+
+impl Handler<Disconnect> for WsSession {
+    type Result = ();
+
+    /// 切断要求を受け取り、WebSocket接続を閉じてアクターを停止します
+    fn handle(&mut self, _msg: Disconnect, ctx: &mut Self::Context) {
+        ctx.close(None);
+        ctx.stop();
+    }
+}
+
+/// ## アイドル切断要求
+///
+/// 一定時間活動のないセッションを、理由を通知した上で切断するためのActixメッセージ。
+#[derive(Message, Debug, Clone, Copy)]
 #[rtype(result = "()")]
-pub struct Broadcast(pub String);
+pub struct IdleDisconnect {
+    /// 最後の活動からの経過秒数
+    pub idle_secs: u64,
+}
+
+impl Handler<IdleDisconnect> for WsSession {
+    type Result = ();
+
+    /// アイドル切断の理由を通知し、正常終了のクローズコードで接続を閉じてアクターを停止します
+    fn handle(&mut self, msg: IdleDisconnect, ctx: &mut Self::Context) {
+        let notice = OutgoingMessage::IdleDisconnect {
+            reason: "一定時間操作がなかったため切断しました。再接続できます".to_string(),
+            idle_secs: msg.idle_secs,
+        };
+        match serde_json::to_string(&notice) {
+            Ok(json) => ctx.text(json),
+            Err(e) => eprintln!("アイドル切断通知のシリアライズに失敗: {}", e),
+        }
+        ctx.close(Some(ws::CloseReason {
+            code: ws::CloseCode::Normal,
+            description: Some("idle timeout".to_string()),
+        }));
+        ctx.stop();
+    }
+}
 
 impl Handler<Broadcast> for WsSession {
     type Result = ();
 
-    /// ブロードキャストメッセージを受け取り、WebSocketテキストとして送信します
+    /// ブロードキャストメッセージを受け取り、フロー制御を経てクライアントのエンコーディングに応じたフレームで送信します
     fn handle(&mut self, msg: Broadcast, ctx: &mut Self::Context) {
-        ctx.text(msg.0);
+        self.offer_broadcast(msg, ctx);
     }
 }
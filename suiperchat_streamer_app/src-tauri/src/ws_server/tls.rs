@@ -0,0 +1,189 @@
+//! TLS設定モジュール
+//!
+//! Cloudflaredトンネルを使わずに wss:// を直接提供するための、
+//! 証明書・秘密鍵の読み込みと証明書情報の確認機能を提供します。
+
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::ServerConfig;
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+use thiserror::Error;
+use x509_parser::extensions::GeneralName;
+
+/// 証明書の有効期限が近いと判断する残り日数
+const EXPIRY_WARNING_DAYS: i64 = 14;
+
+/// ## TLS設定
+///
+/// アプリ内でTLS終端を行うための証明書・秘密鍵の設定を保持します。
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TlsConfig {
+    /// TLSが有効かどうか
+    pub enabled: bool,
+    /// 証明書（PEM形式、フルチェーン）のパス
+    pub cert_path: Option<String>,
+    /// 秘密鍵（PEM形式）のパス
+    pub key_path: Option<String>,
+    /// 証明書に含まれるホスト名（wss:// URLの生成に使用）
+    pub server_name: Option<String>,
+}
+
+/// ## 証明書情報
+///
+/// フロントエンドに返す証明書の概要と有効期限情報です。
+#[derive(Debug, Clone, Serialize)]
+pub struct CertificateInfo {
+    /// 証明書のサブジェクト
+    pub subject: String,
+    /// 証明書の発行者
+    pub issuer: String,
+    /// 証明書に含まれるDNS名
+    pub dns_names: Vec<String>,
+    /// 有効期間の開始日時（RFC 3339形式）
+    pub not_before: String,
+    /// 有効期間の終了日時（RFC 3339形式）
+    pub not_after: String,
+    /// 有効期限までの残り日数（期限切れの場合は負の値）
+    pub days_remaining: i64,
+    /// 期限切れかどうか
+    pub is_expired: bool,
+    /// 有効期限が近い（更新が推奨される）かどうか
+    pub expires_soon: bool,
+}
+
+/// ## TLS関連のエラー
+#[derive(Debug, Error)]
+pub enum TlsError {
+    /// ファイルの読み込みに失敗
+    #[error("Failed to read {0}: {1}")]
+    ReadFailed(String, std::io::Error),
+
+    /// 証明書が見つからなかった
+    #[error("No certificate found in {0}")]
+    CertificateNotFound(String),
+
+    /// 秘密鍵が見つからなかった
+    #[error("No private key found in {0}")]
+    PrivateKeyNotFound(String),
+
+    /// 証明書の解析に失敗
+    #[error("Failed to parse certificate: {0}")]
+    ParseFailed(String),
+
+    /// rustlsの設定作成に失敗（証明書と秘密鍵の不一致など）
+    #[error("Invalid TLS configuration: {0}")]
+    InvalidConfig(#[from] rustls::Error),
+}
+
+/// ## PEMファイルから証明書チェーンを読み込む
+///
+/// ### Arguments
+/// - `cert_path`: 証明書ファイルのパス
+///
+/// ### Returns
+/// - `Result<Vec<CertificateDer<'static>>, TlsError>`: 証明書チェーン
+fn load_certificates(cert_path: &str) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let file = File::open(Path::new(cert_path))
+        .map_err(|e| TlsError::ReadFailed(cert_path.to_string(), e))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| TlsError::ReadFailed(cert_path.to_string(), e))?;
+
+    if certs.is_empty() {
+        return Err(TlsError::CertificateNotFound(cert_path.to_string()));
+    }
+    Ok(certs)
+}
+
+/// ## PEMファイルから秘密鍵を読み込む
+///
+/// PKCS#1 / PKCS#8 / SEC1 形式の鍵に対応します。
+///
+/// ### Arguments
+/// - `key_path`: 秘密鍵ファイルのパス
+///
+/// ### Returns
+/// - `Result<PrivateKeyDer<'static>, TlsError>`: 秘密鍵
+fn load_private_key(key_path: &str) -> Result<PrivateKeyDer<'static>, TlsError> {
+    let file = File::open(Path::new(key_path))
+        .map_err(|e| TlsError::ReadFailed(key_path.to_string(), e))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| TlsError::ReadFailed(key_path.to_string(), e))?
+        .ok_or_else(|| TlsError::PrivateKeyNotFound(key_path.to_string()))
+}
+
+/// ## rustlsのサーバー設定を作成する
+///
+/// 証明書と秘密鍵を読み込み、actix-webのバインドに使用する設定を作成します。
+///
+/// ### Arguments
+/// - `cert_path`: 証明書ファイルのパス
+/// - `key_path`: 秘密鍵ファイルのパス
+///
+/// ### Returns
+/// - `Result<ServerConfig, TlsError>`: rustlsのサーバー設定
+pub fn load_server_config(cert_path: &str, key_path: &str) -> Result<ServerConfig, TlsError> {
+    let certs = load_certificates(cert_path)?;
+    let key = load_private_key(key_path)?;
+
+    let config =
+        ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)?;
+
+    Ok(config)
+}
+
+/// ## 証明書の情報を取得する
+///
+/// 証明書チェーンの先頭（サーバー証明書）を解析し、有効期限などの情報を返します。
+/// Let's Encrypt の証明書の更新時期の確認に使用します。
+///
+/// ### Arguments
+/// - `cert_path`: 証明書ファイルのパス
+///
+/// ### Returns
+/// - `Result<CertificateInfo, TlsError>`: 証明書情報
+pub fn inspect_certificate(cert_path: &str) -> Result<CertificateInfo, TlsError> {
+    let certs = load_certificates(cert_path)?;
+    let (_, cert) = x509_parser::parse_x509_certificate(certs[0].as_ref())
+        .map_err(|e| TlsError::ParseFailed(e.to_string()))?;
+
+    let dns_names = match cert.subject_alternative_name() {
+        Ok(Some(san)) => san
+            .value
+            .general_names
+            .iter()
+            .filter_map(|name| match name {
+                GeneralName::DNSName(dns) => Some(dns.to_string()),
+                _ => None,
+            })
+            .collect(),
+        Ok(None) => Vec::new(),
+        Err(e) => return Err(TlsError::ParseFailed(e.to_string())),
+    };
+
+    let validity = cert.validity();
+    let not_before = chrono::DateTime::from_timestamp(validity.not_before.timestamp(), 0)
+        .ok_or_else(|| TlsError::ParseFailed("invalid notBefore".to_string()))?;
+    let not_after = chrono::DateTime::from_timestamp(validity.not_after.timestamp(), 0)
+        .ok_or_else(|| TlsError::ParseFailed("invalid notAfter".to_string()))?;
+    let now = chrono::Utc::now();
+    let days_remaining = (not_after - now).num_days();
+    let is_expired = not_after <= now;
+
+    Ok(CertificateInfo {
+        subject: cert.subject().to_string(),
+        issuer: cert.issuer().to_string(),
+        dns_names,
+        not_before: not_before.to_rfc3339(),
+        not_after: not_after.to_rfc3339(),
+        days_remaining,
+        is_expired,
+        expires_soon: !is_expired && days_remaining < EXPIRY_WARNING_DAYS,
+    })
+}
@@ -1,19 +1,28 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
+use std::sync::{Arc, Mutex, atomic::{AtomicBool, AtomicU32, Ordering}};
 use std::process::Stdio;
-use tauri::AppHandle;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
 use thiserror::Error;
 use tokio::time::{timeout, Duration, sleep, interval};
-use tokio::process::{Child, Command as TokioCommand};
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, ChildStderr, ChildStdout, Command as TokioCommand};
+use tokio::io::{AsyncBufReadExt, BufReader, Lines};
 use tracing::{error, info, warn, debug};
 use crate::cloudflared_manager::{CloudflaredManager, CloudflaredManagerError};
 
 /// Cloudflaredが出力するURLを検出するための正規表現
-static URL_REGEX: Lazy<Regex> =
+static CLOUDFLARED_URL_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"https?://[a-z0-9-]+\.trycloudflare\.com").unwrap());
 
+/// ngrokが出力するURLを検出するための正規表現
+static NGROK_URL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"https://[a-z0-9-]+\.ngrok-free\.app").unwrap());
+
+/// ngrokの認証トークンを指定する環境変数
+pub const NGROK_AUTHTOKEN_ENV: &str = "NGROK_AUTHTOKEN";
+
 /// タイムアウト時間（秒）
 const TUNNEL_START_TIMEOUT_SECS: u64 = 30;
 /// 健全性チェックの間隔（秒）
@@ -22,19 +31,33 @@ const HEALTH_CHECK_INTERVAL_SECS: u64 = 5;
 const MAX_RESTART_ATTEMPTS: u32 = 3;
 /// 再起動待機時間（秒）
 const RESTART_DELAY_SECS: u64 = 2;
+/// HTTPレベルの健全性チェックの間隔（秒）
+const HTTP_HEALTH_CHECK_INTERVAL_SECS: u64 = 30;
+/// HTTPレベルの健全性チェックのタイムアウト（秒）
+const HTTP_HEALTH_CHECK_TIMEOUT_SECS: u64 = 10;
+/// トンネルを異常と判定するまでに許容するHTTPチェックの連続失敗回数
+const MAX_CONSECUTIVE_HTTP_FAILURES: u32 = 3;
+
+/// トンネルプロセスの標準出力の行リーダー
+type StdoutLines = Lines<BufReader<ChildStdout>>;
+/// トンネルプロセスの標準エラー出力の行リーダー
+type StderrLines = Lines<BufReader<ChildStderr>>;
 
 /**
  * トンネル情報を保持する構造体
  *
- * cloudflaredプロセスと生成されたトンネルURLを管理します。
+ * トンネルプロセスと生成されたトンネルURLを管理します。
  */
 #[derive(Debug, Clone)]
 pub struct TunnelInfo {
-    /// cloudflaredプロセスへの参照（Option<Child>型）
+    /// トンネルを提供するプロバイダ
+    pub kind: TunnelKind,
+
+    /// トンネルプロセスへの参照（Option<Child>型）
     /// Option型でラップすることでtake()メソッドを使用可能に
     pub process: Arc<Mutex<Option<Child>>>,
 
-    /// 生成されたCloudflare Tunnelの一時URL
+    /// 生成されたトンネルの一時URL
     /// 例: https://xxxx-xxxx-xxxx-xxxx.trycloudflare.com
     pub url: String,
 
@@ -43,6 +66,39 @@ pub struct TunnelInfo {
 
     /// プロセス管理情報
     pub process_manager: Arc<Mutex<ProcessManager>>,
+
+    /// HTTPレベルの健全性チェックの結果
+    pub health: Arc<Mutex<TunnelHealth>>,
+
+    /// HTTPチェックの失敗によりトンネルを再起動した連続回数
+    /// （トンネル経由の応答を確認できた時点でリセットし、URLの再生成時は新しいトンネルに引き継ぐ）
+    pub http_health_restarts: Arc<AtomicU32>,
+}
+
+/**
+ * HTTPレベルの健全性チェックの結果
+ *
+ * 原因を切り分けられるよう、ローカルのWebSocketサーバーの応答とトンネル経由の応答を分けて記録します。
+ */
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TunnelHealth {
+    /// ローカルのWebSocketサーバーが応答したかどうか（未確認の場合はNone）
+    pub local_reachable: Option<bool>,
+    /// トンネル経由で応答があったかどうか（未確認の場合はNone）
+    pub tunnel_reachable: Option<bool>,
+    /// トンネル経由のチェックが連続で失敗した回数
+    pub consecutive_failures: u32,
+    /// 最後にチェックした時刻（ISO8601形式）
+    pub last_checked_at: Option<String>,
+    /// 最後に失敗したチェックのエラーメッセージ
+    pub last_error: Option<String>,
+}
+
+impl TunnelHealth {
+    /// トンネル経由のチェックが許容回数を超えて連続で失敗しているかどうか
+    pub fn is_unhealthy(&self) -> bool {
+        self.consecutive_failures >= MAX_CONSECUTIVE_HTTP_FAILURES
+    }
 }
 
 /**
@@ -54,12 +110,105 @@ pub struct ProcessManager {
     pub app_handle: AppHandle,
     /// WebSocketポート
     pub ws_port: u16,
+    /// トンネルを提供するプロバイダ（再起動時に使用）
+    pub kind: TunnelKind,
     /// 再起動試行回数
     pub restart_attempts: u32,
     /// プロセスが実行中かどうか
     pub is_running: bool,
 }
 
+/**
+ * トンネルを提供するプロバイダの種類
+ *
+ * Cloudflare Quick Tunnelが不安定な環境向けに、代替としてngrokを選べるようにします。
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TunnelKind {
+    /// Cloudflare Quick Tunnel（cloudflared）
+    #[default]
+    Cloudflared,
+    /// ngrok（環境変数 `NGROK_AUTHTOKEN` の認証トークンを使用）
+    Ngrok,
+}
+
+impl TunnelKind {
+    /// 文字列からプロバイダの種類を解決する
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "cloudflared" => Some(TunnelKind::Cloudflared),
+            "ngrok" => Some(TunnelKind::Ngrok),
+            _ => None,
+        }
+    }
+
+    /// AppStateに保存されたプロバイダ設定を取得する
+    fn current(app: &AppHandle) -> Self {
+        app.state::<crate::state::AppState>()
+            .tunnel_provider
+            .lock()
+            .map(|kind| *kind)
+            .unwrap_or_default()
+    }
+
+    /// プロバイダの実装を取得する
+    pub fn provider(self) -> &'static dyn TunnelProvider {
+        match self {
+            TunnelKind::Cloudflared => &CloudflaredProvider,
+            TunnelKind::Ngrok => &NgrokProvider,
+        }
+    }
+}
+
+/**
+ * cloudflaredの接続プロトコル設定
+ *
+ * Issue #45 の経緯からデフォルトはプロトコル未指定（Cloudflare任せ）とし、
+ * UDPがブロックされた環境向けに明示的な指定を選べるようにします。
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TunnelProtocol {
+    /// プロトコルを指定しない（Cloudflareのデフォルト動作）
+    #[default]
+    Auto,
+    /// QUICを無効化してHTTP/2で接続する
+    Http2,
+    /// QUICで接続する
+    Quic,
+}
+
+impl TunnelProtocol {
+    /// 文字列からプロトコル設定を解決する
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "auto" => Some(TunnelProtocol::Auto),
+            "http2" => Some(TunnelProtocol::Http2),
+            "quic" => Some(TunnelProtocol::Quic),
+            _ => None,
+        }
+    }
+
+    /// `--protocol` に渡す値（未指定の場合は `None`）
+    fn as_arg(self) -> Option<&'static str> {
+        match self {
+            TunnelProtocol::Auto => None,
+            TunnelProtocol::Http2 => Some("http2"),
+            TunnelProtocol::Quic => Some("quic"),
+        }
+    }
+
+    /// AppStateに保存されたプロトコル設定を取得する
+    fn current(app: &AppHandle) -> Self {
+        app.state::<crate::state::AppState>()
+            .tunnel_protocol
+            .lock()
+            .map(|protocol| *protocol)
+            .unwrap_or_default()
+    }
+}
+
 /**
  * トンネル処理に関するエラー
  */
@@ -70,27 +219,214 @@ pub enum TunnelError {
     ManagerError(#[from] CloudflaredManagerError),
 
     /// プロセスの起動に失敗
-    #[error("Failed to spawn cloudflared process: {0}")]
+    #[error("Failed to spawn tunnel process: {0}")]
     SpawnFailed(#[from] std::io::Error),
 
     /// 標準入出力の操作中にエラー発生
-    #[error("Failed to read cloudflared stdout")]
+    #[error("Failed to read tunnel process stdout")]
     StdioError,
 
     /// トンネルURLが見つからなかった
-    #[error("Cloudflare URL not found in output within timeout")]
+    #[error("Tunnel URL not found in output within timeout")]
     UrlNotFound,
 
     /// タイムアウト発生
-    #[error("Timed out waiting for cloudflared URL")]
+    #[error("Timed out waiting for tunnel URL")]
     Timeout,
+
+    /// ngrokの認証トークンが設定されていない
+    #[error("NGROK_AUTHTOKEN environment variable is not set")]
+    MissingAuthToken,
+}
+
+/**
+ * トンネルを提供するプロバイダの共通インターフェース
+ *
+ * プロバイダごとに異なるのは起動コマンドと出力からのURL抽出のみで、
+ * プロセスの起動・URL待ち・健全性監視・停止は共通の実装を使用します。
+ */
+pub trait TunnelProvider: Send + Sync {
+    /// プロバイダの種類
+    fn kind(&self) -> TunnelKind;
+
+    /// トンネルプロセスの起動コマンドを構築する
+    fn command<'a>(
+        &'a self,
+        app: &'a AppHandle,
+        ws_port: u16,
+    ) -> BoxFuture<'a, Result<TokioCommand, TunnelError>>;
+
+    /// トンネルプロセスの出力行から公開URLを抽出する
+    fn url(&self, line: &str) -> Option<String>;
+
+    /// トンネルを起動し、公開URLが出力されるまで待つ
+    fn start<'a>(
+        &'a self,
+        app: &'a AppHandle,
+        ws_port: u16,
+    ) -> BoxFuture<'a, Result<TunnelInfo, TunnelError>> {
+        Box::pin(start_with_provider(self, app, ws_port))
+    }
+
+    /// トンネルプロセスと健全性監視を停止する
+    fn stop<'a>(&'a self, tunnel_info: &'a TunnelInfo) -> BoxFuture<'a, ()> {
+        Box::pin(stop_process(tunnel_info))
+    }
+}
+
+/// Cloudflare Quick Tunnel（cloudflared）のプロバイダ
+///
+/// 動的にダウンロードしたcloudflaredバイナリを使用し、
+/// 一時的なURL（https://*.trycloudflare.com）を取得します。
+pub struct CloudflaredProvider;
+
+impl CloudflaredProvider {
+    /**
+     * cloudflaredコマンドの引数を構築する
+     */
+    fn build_args(ws_port: u16, protocol: TunnelProtocol) -> Vec<String> {
+        let mut args = vec![
+            "tunnel".to_string(),
+            "--url".to_string(),
+            format!("http://127.0.0.1:{}", ws_port),
+            "--no-autoupdate".to_string(),
+        ];
+
+        // デフォルトではプロトコル設定を削除してCloudflareのデフォルト動作に任せる
+        // Issue #45の修正: macOSでWebSocket接続が失敗する問題を解決
+        // UDPがブロックされた環境向けに、明示的に選択された場合のみ指定する
+        if let Some(protocol) = protocol.as_arg() {
+            args.push("--protocol".to_string());
+            args.push(protocol.to_string());
+        }
+
+        // WebSocket接続改善のための設定
+        args.push("--compression-quality".to_string());
+        args.push("0".to_string()); // 圧縮を無効化してWebSocketを安定化
+
+        // macOS固有の設定
+        #[cfg(target_os = "macos")]
+        {
+            args.push("--http-host-header".to_string());
+            args.push("localhost".to_string());
+            args.push("--origin-server-name".to_string());
+            args.push("localhost".to_string());
+        }
+
+        // 環境変数から追加引数を取得して追加
+        if let Ok(extra_args_str) = std::env::var("CLOUDFLARED_EXTRA_ARGS") {
+            if !extra_args_str.is_empty() {
+                args.extend(extra_args_str.split_whitespace().map(String::from));
+            }
+        }
+
+        // 環境変数からログレベルを取得して追加（存在する場合）
+        let log_level = std::env::var("CLOUDFLARED_LOG_LEVEL").unwrap_or_else(|_| "info".to_string());
+        if !log_level.is_empty() {
+            args.push("--loglevel".to_string());
+            args.push(log_level);
+        }
+
+        args
+    }
+}
+
+impl TunnelProvider for CloudflaredProvider {
+    fn kind(&self) -> TunnelKind {
+        TunnelKind::Cloudflared
+    }
+
+    fn command<'a>(
+        &'a self,
+        app: &'a AppHandle,
+        ws_port: u16,
+    ) -> BoxFuture<'a, Result<TokioCommand, TunnelError>> {
+        Box::pin(async move {
+            // cloudflaredマネージャーを初期化
+            let manager = CloudflaredManager::new(app.clone())?;
+
+            // cloudflaredバイナリを確保（存在しない場合はダウンロード）
+            let binary_path = manager.ensure_cloudflared().await?;
+            info!("Using cloudflared binary at: {:?}", binary_path);
+
+            // cloudflaredコマンドの引数を構築
+            let args = Self::build_args(ws_port, TunnelProtocol::current(app));
+            info!("Full command: {} {}", binary_path.display(), args.join(" "));
+
+            // SIGPIPE対策のための環境変数設定
+            #[cfg(unix)]
+            {
+                std::env::set_var("RUST_BACKTRACE", "1");
+                // SIGPIPEエラーを防ぐための環境変数
+                std::env::set_var("CLOUDFLARED_NO_CHUNKED_ENCODING", "true");
+            }
+
+            let mut command = TokioCommand::new(&binary_path);
+            command.args(&args);
+            Ok(command)
+        })
+    }
+
+    fn url(&self, line: &str) -> Option<String> {
+        CLOUDFLARED_URL_REGEX
+            .find(line)
+            .map(|mat| mat.as_str().to_string())
+    }
+}
+
+/// ngrokのプロバイダ
+///
+/// PATH上の `ngrok` コマンド（環境変数 `NGROK_PATH` で変更可能）を使用し、
+/// 環境変数 `NGROK_AUTHTOKEN` の認証トークンで一時的なURL（https://*.ngrok-free.app）を取得します。
+pub struct NgrokProvider;
+
+impl TunnelProvider for NgrokProvider {
+    fn kind(&self) -> TunnelKind {
+        TunnelKind::Ngrok
+    }
+
+    fn command<'a>(
+        &'a self,
+        _app: &'a AppHandle,
+        ws_port: u16,
+    ) -> BoxFuture<'a, Result<TokioCommand, TunnelError>> {
+        Box::pin(async move {
+            let authtoken = std::env::var(NGROK_AUTHTOKEN_ENV)
+                .ok()
+                .filter(|token| !token.trim().is_empty())
+                .ok_or(TunnelError::MissingAuthToken)?;
+            let binary_path = std::env::var("NGROK_PATH").unwrap_or_else(|_| "ngrok".to_string());
+
+            // URLを標準出力から抽出できるよう、ログを標準出力に出力させる
+            let args = vec![
+                "http".to_string(),
+                format!("127.0.0.1:{}", ws_port),
+                "--log".to_string(),
+                "stdout".to_string(),
+                "--log-format".to_string(),
+                "logfmt".to_string(),
+            ];
+            info!("Full command: {} {}", binary_path, args.join(" "));
+
+            let mut command = TokioCommand::new(&binary_path);
+            command.args(&args).env(NGROK_AUTHTOKEN_ENV, authtoken);
+            Ok(command)
+        })
+    }
+
+    fn url(&self, line: &str) -> Option<String> {
+        NGROK_URL_REGEX
+            .find(line)
+            .map(|mat| mat.as_str().to_string())
+    }
 }
 
 impl ProcessManager {
-    pub fn new(app_handle: AppHandle, ws_port: u16) -> Self {
+    pub fn new(app_handle: AppHandle, ws_port: u16, kind: TunnelKind) -> Self {
         Self {
             app_handle,
             ws_port,
+            kind,
             restart_attempts: 0,
             is_running: false,
         }
@@ -117,18 +453,28 @@ impl TunnelInfo {
     /**
      * 新しいTunnelInfoインスタンスを作成
      *
-     * @param {Child} process - cloudflaredプロセス
-     * @param {String} url - Cloudflare Tunnelの一時URL
+     * @param {TunnelKind} kind - トンネルを提供するプロバイダ
+     * @param {Child} process - トンネルプロセス
+     * @param {String} url - トンネルの一時URL
      * @param {AppHandle} app_handle - Tauriアプリハンドル
      * @param {u16} ws_port - WebSocketポート
      * @returns {TunnelInfo} 作成されたTunnelInfoインスタンス
      */
-    pub fn new(process: Child, url: String, app_handle: AppHandle, ws_port: u16) -> Self {
+    pub fn new(
+        kind: TunnelKind,
+        process: Child,
+        url: String,
+        app_handle: AppHandle,
+        ws_port: u16,
+    ) -> Self {
         Self {
+            kind,
             process: Arc::new(Mutex::new(Some(process))),
             url,
             should_stop: Arc::new(AtomicBool::new(false)),
-            process_manager: Arc::new(Mutex::new(ProcessManager::new(app_handle, ws_port))),
+            process_manager: Arc::new(Mutex::new(ProcessManager::new(app_handle, ws_port, kind))),
+            health: Arc::new(Mutex::new(TunnelHealth::default())),
+            http_health_restarts: Arc::new(AtomicU32::new(0)),
         }
     }
 
@@ -136,6 +482,9 @@ impl TunnelInfo {
      * プロセスの健全性を監視し、必要に応じて再起動する
      */
     pub async fn start_health_monitor(&self) {
+        // プロセスが生きていてもURLが応答しない場合に備え、HTTPレベルの確認も並行して行う
+        self.start_http_health_monitor();
+
         let process_arc = Arc::clone(&self.process);
         let should_stop = Arc::clone(&self.should_stop);
         let process_manager = Arc::clone(&self.process_manager);
@@ -155,7 +504,7 @@ impl TunnelInfo {
                     if let Some(ref mut child) = process_guard.as_mut() {
                         match child.try_wait() {
                             Ok(Some(status)) => {
-                                error!("Cloudflared process exited with status: {:?}", status);
+                                error!("Tunnel process exited with status: {:?}", status);
                                 if let Some(code) = status.code() {
                                     error!("Exit code: {}", code);
                                 } else {
@@ -164,16 +513,16 @@ impl TunnelInfo {
                                 true // 再起動が必要
                             }
                             Ok(None) => {
-                                debug!("Cloudflared process is still running");
+                                debug!("Tunnel process is still running");
                                 false // 再起動不要
                             }
                             Err(e) => {
-                                error!("Error checking cloudflared process status: {}", e);
+                                error!("Error checking tunnel process status: {}", e);
                                 true // エラーの場合も再起動を試行
                             }
                         }
                     } else {
-                        warn!("Cloudflared process handle is None");
+                        warn!("Tunnel process handle is None");
                         true // プロセスハンドルがない場合は再起動
                     }
                 };
@@ -186,15 +535,15 @@ impl TunnelInfo {
                     };
                     
                     if can_restart {
-                        info!("Attempting to restart cloudflared process...");
+                        info!("Attempting to restart tunnel process...");
                         
                         // 少し待ってから再起動
                         sleep(Duration::from_secs(RESTART_DELAY_SECS)).await;
                         
                         if let Err(e) = Self::restart_process(&process_arc, &process_manager).await {
-                            error!("Failed to restart cloudflared process: {}", e);
+                            error!("Failed to restart tunnel process: {}", e);
                         } else {
-                            info!("Cloudflared process restarted successfully");
+                            info!("Tunnel process restarted successfully");
                         }
                     } else {
                         error!("Maximum restart attempts ({}) reached, giving up", MAX_RESTART_ATTEMPTS);
@@ -207,6 +556,128 @@ impl TunnelInfo {
         });
     }
     
+    /**
+     * トンネルURLとローカルのWebSocketサーバーにHTTP HEADリクエストを送り、応答を監視する
+     *
+     * トンネル経由のチェックが連続で失敗した場合は状態を "Unhealthy" としてフロントに通知し、
+     * ローカルのサーバーが応答している（トンネル側の問題と判断できる）場合はトンネルURLを再生成します。
+     */
+    fn start_http_health_monitor(&self) {
+        let url = self.url.clone();
+        let should_stop = Arc::clone(&self.should_stop);
+        let health = Arc::clone(&self.health);
+        let http_health_restarts = Arc::clone(&self.http_health_restarts);
+        let (app_handle, ws_port) = {
+            let manager = self.process_manager.lock().unwrap();
+            (manager.app_handle.clone(), manager.ws_port)
+        };
+
+        tokio::spawn(async move {
+            let client = match reqwest::Client::builder()
+                .timeout(Duration::from_secs(HTTP_HEALTH_CHECK_TIMEOUT_SECS))
+                .build()
+            {
+                Ok(client) => client,
+                Err(e) => {
+                    error!("Failed to build HTTP client for tunnel health check: {}", e);
+                    return;
+                }
+            };
+            let local_url = format!("http://127.0.0.1:{}/", ws_port);
+            let mut interval = interval(Duration::from_secs(HTTP_HEALTH_CHECK_INTERVAL_SECS));
+            // 起動直後はURLが反映されていない場合があるため、初回のチェックは1周期後に行う
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+                if should_stop.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                let (local_result, tunnel_result) = tokio::join!(
+                    check_http(&client, &local_url, false),
+                    check_http(&client, &url, true)
+                );
+                if let Err(e) = &local_result {
+                    warn!("Local WebSocket server health check failed: {}", e);
+                }
+
+                let (was_unhealthy, is_unhealthy, failures) = {
+                    let mut health = health.lock().unwrap();
+                    let was_unhealthy = health.is_unhealthy();
+                    health.local_reachable = Some(local_result.is_ok());
+                    health.tunnel_reachable = Some(tunnel_result.is_ok());
+                    health.last_checked_at = Some(chrono::Utc::now().to_rfc3339());
+                    match &tunnel_result {
+                        Ok(()) => health.consecutive_failures = 0,
+                        Err(e) => {
+                            health.consecutive_failures += 1;
+                            health.last_error = Some(e.clone());
+                        }
+                    }
+                    (
+                        was_unhealthy,
+                        health.is_unhealthy(),
+                        health.consecutive_failures,
+                    )
+                };
+
+                match tunnel_result {
+                    Ok(()) => {
+                        debug!("Tunnel HTTP health check succeeded: {}", url);
+                        http_health_restarts.store(0, Ordering::SeqCst);
+                        if was_unhealthy {
+                            info!("Tunnel recovered: {}", url);
+                            crate::ws_server::server_manager::emit_server_status_with_tunnel(
+                                &app_handle,
+                            );
+                        }
+                        continue;
+                    }
+                    Err(e) => warn!(
+                        "Tunnel HTTP health check failed ({}/{}): {}",
+                        failures, MAX_CONSECUTIVE_HTTP_FAILURES, e
+                    ),
+                }
+                if !is_unhealthy {
+                    continue;
+                }
+                if !was_unhealthy {
+                    error!("Tunnel is unhealthy: {}", url);
+                    crate::ws_server::server_manager::emit_server_status_with_tunnel(&app_handle);
+                }
+
+                // ローカルのサーバーも応答しない場合はトンネルを再起動しても回復しない
+                if local_result.is_err() {
+                    error!("Local WebSocket server is not responding, skipping tunnel restart");
+                    continue;
+                }
+                if http_health_restarts.fetch_add(1, Ordering::SeqCst) >= MAX_RESTART_ATTEMPTS {
+                    error!(
+                        "Maximum tunnel restart attempts ({}) by HTTP health check reached, giving up",
+                        MAX_RESTART_ATTEMPTS
+                    );
+                    continue;
+                }
+
+                // 再生成で現在のトンネルは停止されるため、このチェックは終了する
+                info!("Restarting unhealthy tunnel: {}", url);
+                let restart_app_handle = app_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) =
+                        crate::ws_server::server_manager::regenerate_tunnel_url(restart_app_handle)
+                            .await
+                    {
+                        error!("Failed to restart unhealthy tunnel: {}", e);
+                    }
+                });
+                break;
+            }
+
+            info!("Tunnel HTTP health monitor stopped");
+        });
+    }
+
     /**
      * プロセスを再起動する
      */
@@ -214,78 +685,34 @@ impl TunnelInfo {
         process_arc: &Arc<Mutex<Option<Child>>>,
         process_manager: &Arc<Mutex<ProcessManager>>
     ) -> Result<(), TunnelError> {
-        let (app_handle, ws_port) = {
+        let (app_handle, ws_port, kind) = {
             let manager = process_manager.lock().unwrap();
-            (manager.app_handle.clone(), manager.ws_port)
+            (manager.app_handle.clone(), manager.ws_port, manager.kind)
         };
         
-        // cloudflaredマネージャーを初期化
-        let manager = CloudflaredManager::new(app_handle)?;
-        let binary_path = manager.ensure_cloudflared().await?;
+        // 起動時と同じプロバイダでコマンドを構築
+        let mut command = kind.provider().command(&app_handle, ws_port).await?;
         
-        // コマンド引数を構築
-        let args = Self::build_cloudflared_args(ws_port);
-        
-        info!("Restarting cloudflared with args: {:?}", args.join(" "));
+        info!("Restarting {:?} tunnel process", kind);
         
         // 新しいプロセスを起動
-        let mut child = TokioCommand::new(&binary_path)
-            .args(&args)
+        let mut child = command
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
             .map_err(|e| {
-                error!("Failed to spawn cloudflared process during restart: {}", e);
+                error!("Failed to spawn tunnel process during restart: {}", e);
                 e
             })?;
         
-        info!("New cloudflared process spawned with PID: {:?}", child.id());
+        info!("New tunnel process spawned with PID: {:?}", child.id());
         
         // SIGPIPEを防ぐため、再起動時も即座にバックグラウンドログ読み取りを開始
         if let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) {
-            use tokio::io::{AsyncBufReadExt, BufReader};
-            
-            let mut stdout_reader = BufReader::new(stdout).lines();
-            let mut stderr_reader = BufReader::new(stderr).lines();
-            
-            tokio::spawn(async move {
-                info!("Starting background log reading for restarted process...");
-                loop {
-                    tokio::select! {
-                        line = stdout_reader.next_line() => {
-                            match line {
-                                Ok(Some(line_str)) => {
-                                    debug!("cloudflared stdout (restart): {}", line_str);
-                                }
-                                Ok(None) => {
-                                    debug!("cloudflared stdout stream ended (restart)");
-                                    break;
-                                }
-                                Err(e) => {
-                                    debug!("Error reading cloudflared stdout (restart): {}", e);
-                                    break;
-                                }
-                            }
-                        }
-                        line = stderr_reader.next_line() => {
-                            match line {
-                                Ok(Some(line_str)) => {
-                                    debug!("cloudflared stderr (restart): {}", line_str);
-                                }
-                                Ok(None) => {
-                                    debug!("cloudflared stderr stream ended (restart)");
-                                    break;
-                                }
-                                Err(e) => {
-                                    debug!("Error reading cloudflared stderr (restart): {}", e);
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                }
-                info!("Background log reading for restarted process completed");
-            });
+            spawn_log_reader(
+                BufReader::new(stdout).lines(),
+                BufReader::new(stderr).lines(),
+            );
         }
         
         // 古いプロセスを置き換え
@@ -301,59 +728,86 @@ impl TunnelInfo {
         
         Ok(())
     }
+}
     
-    /**
-     * cloudflaredコマンドの引数を構築する
-     */
-    fn build_cloudflared_args(ws_port: u16) -> Vec<String> {
-        let mut args = vec![
-            "tunnel".to_string(),
-            "--url".to_string(),
-            format!("http://127.0.0.1:{}", ws_port),
-            "--no-autoupdate".to_string(),
-        ];
-        
-        // プロトコル設定を削除してCloudflareのデフォルト動作に任せる
-        // Issue #45の修正: macOSでWebSocket接続が失敗する問題を解決
-        // args.push("--protocol".to_string());
-        // args.push("auto".to_string());
-        
-        // WebSocket接続改善のための設定
-        args.push("--compression-quality".to_string());
-        args.push("0".to_string()); // 圧縮を無効化してWebSocketを安定化
-        
-        // macOS固有の設定
-        #[cfg(target_os = "macos")]
-        {
-            args.push("--http-host-header".to_string());
-            args.push("localhost".to_string());
-            args.push("--origin-server-name".to_string());
-            args.push("localhost".to_string());
-        }
+/**
+ * HTTP HEADリクエストを送り、応答があるか確認する
+ *
+ * トンネル経由の場合、オリジンに到達できないとCloudflare・ngrokが5xxを返すため、
+ * 5xxの応答も失敗として扱います。ローカルのサーバーは応答があれば成功とします。
+ *
+ * # Arguments
+ * * `client` - HTTPクライアント
+ * * `url` - 確認するURL
+ * * `via_tunnel` - トンネル経由の確認かどうか
+ *
+ * # Returns
+ * * `Result<(), String>` - 応答があった場合はOk、タイムアウト・エラーの場合はエラーメッセージ
+ */
+async fn check_http(client: &reqwest::Client, url: &str, via_tunnel: bool) -> Result<(), String> {
+    let response = client
+        .head(url)
+        .send()
+        .await
+        .map_err(|e| format!("HEAD {} failed: {}", url, e))?;
+    if via_tunnel && response.status().is_server_error() {
+        return Err(format!("HEAD {} returned {}", url, response.status()));
+    }
+    Ok(())
+}
         
-        // 環境変数から追加引数を取得して追加
-        if let Ok(extra_args_str) = std::env::var("CLOUDFLARED_EXTRA_ARGS") {
-            if !extra_args_str.is_empty() {
-                args.extend(extra_args_str.split_whitespace().map(String::from));
+/**
+ * トンネルプロセスの出力をバックグラウンドで読み続ける
+ *
+ * 出力を読み捨てないとパイプが詰まり、SIGPIPEでプロセスが終了するため、
+ * URL抽出後や再起動後も読み取りを継続します。
+ */
+fn spawn_log_reader(mut stdout_reader: StdoutLines, mut stderr_reader: StderrLines) {
+    tokio::spawn(async move {
+        info!("Starting background log reading to prevent SIGPIPE...");
+        loop {
+            tokio::select! {
+                line = stdout_reader.next_line() => {
+                    match line {
+                        Ok(Some(line_str)) => {
+                            debug!("tunnel stdout (bg): {}", line_str);
+                        }
+                        Ok(None) => {
+                            debug!("tunnel stdout stream ended (bg)");
+                            break;
+                        }
+                        Err(e) => {
+                            debug!("Error reading tunnel stdout (bg): {}", e);
+                            break;
+                        }
+                    }
+                }
+                line = stderr_reader.next_line() => {
+                    match line {
+                        Ok(Some(line_str)) => {
+                            debug!("tunnel stderr (bg): {}", line_str);
+                        }
+                        Ok(None) => {
+                            debug!("tunnel stderr stream ended (bg)");
+                            break;
+                        }
+                        Err(e) => {
+                            debug!("Error reading tunnel stderr (bg): {}", e);
+                            break;
+                        }
+                    }
+                }
             }
         }
-        
-        // 環境変数からログレベルを取得して追加（存在する場合）
-        let log_level = std::env::var("CLOUDFLARED_LOG_LEVEL").unwrap_or_else(|_| "info".to_string());
-        if !log_level.is_empty() {
-            args.push("--loglevel".to_string());
-            args.push(log_level);
-        }
-        
-        args
-    }
+        info!("Background log reading task completed");
+    });
 }
 
 /// トンネルを起動し、WebSocketサーバーをインターネットに公開する
 ///
-/// Cloudflare Quick Tunnelを使用して、ローカルで実行されているWebSocketサーバーを
-/// インターネットに安全に公開します。動的にダウンロードしたcloudflaredバイナリを使用し、
-/// 生成された一時的なURL（https://*.trycloudflare.com）を取得します。
+/// `AppState` で選択されたプロバイダ（デフォルトはCloudflare Quick Tunnel）を使用して、
+/// ローカルで実行されているWebSocketサーバーをインターネットに公開し、
+/// 生成された一時的なURLを取得します。
 ///
 /// # Arguments
 /// * `app` - Tauriアプリハンドル
@@ -362,37 +816,59 @@ impl TunnelInfo {
 /// # Returns
 /// * `Result<TunnelInfo, TunnelError>` - 成功時はTunnelInfo、失敗時はエラー
 pub async fn start_tunnel(app: &AppHandle, ws_port: u16) -> Result<TunnelInfo, TunnelError> {
-    info!("Starting Cloudflare Tunnel for WebSocket port {}", ws_port);
-
-    // cloudflaredマネージャーを初期化
-    let manager = CloudflaredManager::new(app.clone())?;
-    
-    // cloudflaredバイナリを確保（存在しない場合はダウンロード）
-    let binary_path = manager.ensure_cloudflared().await?;
-    info!("Using cloudflared binary at: {:?}", binary_path);
-
-    // cloudflaredコマンドの引数を構築
-    let args = TunnelInfo::build_cloudflared_args(ws_port);
-
-    info!(
-        "Attempting to start cloudflared with args: {:?}",
-        args.join(" ")
-    );
-    info!("Full command: {} {}", binary_path.display(), args.join(" "));
+    TunnelKind::current(app)
+        .provider()
+        .start(app, ws_port)
+        .await
+}
     
-    // SIGPIPE対策のための環境変数設定
-    #[cfg(unix)]
-    {
-        std::env::set_var("RUST_BACKTRACE", "1");
-        // SIGPIPEエラーを防ぐための環境変数
-        std::env::set_var("CLOUDFLARED_NO_CHUNKED_ENCODING", "true");
+/// WebSocketサーバーを止めずに、トンネルプロセスだけを再起動して新しいURLを取得する
+///
+/// 健全性監視による自動再起動（`ProcessManager` の再起動試行回数）とは独立した、
+/// ユーザー操作による再起動です。新しいトンネルは再起動試行回数0から監視を開始します。
+/// ただしHTTPチェックの失敗による再起動の連続回数は引き継ぎ、再起動を繰り返し続けないようにします。
+/// サーバーの停止後に起動したトンネルは、連続回数0から監視を開始します。
+/// プロバイダは現在の設定から選択するため、プロバイダを切り替えてから再生成することもできます。
+///
+/// # Arguments
+/// * `app` - Tauriアプリハンドル
+/// * `old_tunnel` - 停止する既存のトンネル（起動に失敗していた場合は `None`）
+/// * `ws_port` - WebSocketサーバーのポート番号
+///
+/// # Returns
+/// * `Result<TunnelInfo, TunnelError>` - 成功時は新しいTunnelInfo、失敗時はエラー
+pub async fn restart_tunnel_only(
+    app: &AppHandle,
+    old_tunnel: Option<TunnelInfo>,
+    ws_port: u16,
+) -> Result<TunnelInfo, TunnelError> {
+    // HTTPチェックによる再起動の回数は、再生成後のトンネルに引き継いで上限を判定する
+    let mut http_health_restarts = 0;
+    if let Some(old_tunnel) = old_tunnel {
+        info!("Stopping tunnel for URL regeneration: {}", old_tunnel.url);
+        http_health_restarts = old_tunnel.http_health_restarts.load(Ordering::SeqCst);
+        stop_tunnel(&old_tunnel).await;
     }
+    let tunnel = start_tunnel(app, ws_port).await?;
+    tunnel
+        .http_health_restarts
+        .store(http_health_restarts, Ordering::SeqCst);
+    Ok(tunnel)
+}
 
-    // tokioプロセスを使用してcloudflaredを起動
+/// プロバイダのコマンドでトンネルプロセスを起動し、出力から公開URLを抽出する
+async fn start_with_provider<P: TunnelProvider + ?Sized>(
+    provider: &P,
+    app: &AppHandle,
+    ws_port: u16,
+) -> Result<TunnelInfo, TunnelError> {
+    let kind = provider.kind();
+    info!("Starting {:?} tunnel for WebSocket port {}", kind, ws_port);
+
+    // tokioプロセスを使用してトンネルを起動
     // SIGPIPEエラーを回避するための設定を追加
-    let mut command = TokioCommand::new(&binary_path);
+    let mut command = provider.command(app, ws_port).await?;
     command
-        .args(&args)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .kill_on_drop(true); // プロセスがドロップされたときに終了させる
@@ -403,17 +879,16 @@ pub async fn start_tunnel(app: &AppHandle, ws_port: u16) -> Result<TunnelInfo, T
         // macOSでSIGPIPEを無視する設定
         command.stdin(Stdio::null()); // 標準入力を閉じる
     }
-    
-    let mut child = command
-        .spawn()
-        .map_err(|e| {
-            error!("Failed to spawn cloudflared process: {}", e);
-            error!("Binary path: {}", binary_path.display());
-            error!("Args: {:?}", args);
-            e
-        })?;
 
-    info!("Cloudflared process spawned successfully with PID: {:?}", child.id());
+    let mut child = command.spawn().map_err(|e| {
+        error!("Failed to spawn {:?} tunnel process: {}", kind, e);
+        e
+    })?;
+
+    info!(
+        "Tunnel process spawned successfully with PID: {:?}",
+        child.id()
+    );
 
     // 標準出力と標準エラー出力を非同期で読み取り
     let stdout = child.stdout.take().ok_or(TunnelError::StdioError)?;
@@ -428,74 +903,30 @@ pub async fn start_tunnel(app: &AppHandle, ws_port: u16) -> Result<TunnelInfo, T
     // URL抽出ロジック（タイムアウト付き）
     // SIGPIPEを防ぐため、URL抽出後もログ読み取りを継続
     let url_extraction = async {
-        info!("Starting URL extraction from cloudflared output...");
-        let found_url = None;
+        info!("Starting URL extraction from tunnel output...");
         
         loop {
             tokio::select! {
                 line = stdout_reader.next_line() => {
                     match line {
                         Ok(Some(line_str)) => {
-                            info!("cloudflared stdout: {}", line_str);
+                            info!("tunnel stdout: {}", line_str);
                             
                             // 標準出力からTunnelのURLを検索
-                            if found_url.is_none() {
-                                if let Some(mat) = URL_REGEX.find(&line_str) {
-                                    let url = mat.as_str().to_string();
-                                    info!("Cloudflare Tunnel URL found: {}", url);
-                                    
-                                    // URLが見つかったらバックグラウンドで継続読み取り開始
-                                    let mut stdout_reader_bg = stdout_reader;
-                                    let mut stderr_reader_bg = stderr_reader;
-                                    tokio::spawn(async move {
-                                        info!("Starting background log reading to prevent SIGPIPE...");
-                                        loop {
-                                            tokio::select! {
-                                                line = stdout_reader_bg.next_line() => {
-                                                    match line {
-                                                        Ok(Some(line_str)) => {
-                                                            debug!("cloudflared stdout (bg): {}", line_str);
-                                                        }
-                                                        Ok(None) => {
-                                                            debug!("cloudflared stdout stream ended (bg)");
-                                                            break;
-                                                        }
-                                                        Err(e) => {
-                                                            debug!("Error reading cloudflared stdout (bg): {}", e);
-                                                            break;
-                                                        }
-                                                    }
-                                                }
-                                                line = stderr_reader_bg.next_line() => {
-                                                    match line {
-                                                        Ok(Some(line_str)) => {
-                                                            debug!("cloudflared stderr (bg): {}", line_str);
-                                                        }
-                                                        Ok(None) => {
-                                                            debug!("cloudflared stderr stream ended (bg)");
-                                                            break;
-                                                        }
-                                                        Err(e) => {
-                                                            debug!("Error reading cloudflared stderr (bg): {}", e);
-                                                            break;
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        info!("Background log reading task completed");
-                                    });
-                                    
-                                    return Ok(url);
-                                }
+                            if let Some(url) = provider.url(&line_str) {
+                                info!("Tunnel URL found: {}", url);
+
+                                // URLが見つかったらバックグラウンドで継続読み取り開始
+                                spawn_log_reader(stdout_reader, stderr_reader);
+                                return Ok(url);
                             }
                         }
                         Ok(None) => {
-                            warn!("cloudflared stdout stream ended");
+                            warn!("tunnel stdout stream ended");
                             break;
                         }
                         Err(e) => {
-                            error!("Error reading cloudflared stdout: {}", e);
+                            error!("Error reading tunnel stdout: {}", e);
                             return Err(TunnelError::StdioError);
                         }
                     }
@@ -503,66 +934,23 @@ pub async fn start_tunnel(app: &AppHandle, ws_port: u16) -> Result<TunnelInfo, T
                 line = stderr_reader.next_line() => {
                     match line {
                         Ok(Some(line_str)) => {
-                            warn!("cloudflared stderr: {}", line_str);
+                            warn!("tunnel stderr: {}", line_str);
                             
                             // 標準エラー出力からもURLを検索
-                            if found_url.is_none() {
-                                if let Some(mat) = URL_REGEX.find(&line_str) {
-                                    let url = mat.as_str().to_string();
-                                    info!("Cloudflare Tunnel URL found in stderr: {}", url);
-                                    
-                                    // URLが見つかったらバックグラウンドで継続読み取り開始
-                                    let mut stdout_reader_bg = stdout_reader;
-                                    let mut stderr_reader_bg = stderr_reader;
-                                    tokio::spawn(async move {
-                                        info!("Starting background log reading to prevent SIGPIPE...");
-                                        loop {
-                                            tokio::select! {
-                                                line = stdout_reader_bg.next_line() => {
-                                                    match line {
-                                                        Ok(Some(line_str)) => {
-                                                            debug!("cloudflared stdout (bg): {}", line_str);
-                                                        }
-                                                        Ok(None) => {
-                                                            debug!("cloudflared stdout stream ended (bg)");
-                                                            break;
-                                                        }
-                                                        Err(e) => {
-                                                            debug!("Error reading cloudflared stdout (bg): {}", e);
-                                                            break;
-                                                        }
-                                                    }
-                                                }
-                                                line = stderr_reader_bg.next_line() => {
-                                                    match line {
-                                                        Ok(Some(line_str)) => {
-                                                            debug!("cloudflared stderr (bg): {}", line_str);
-                                                        }
-                                                        Ok(None) => {
-                                                            debug!("cloudflared stderr stream ended (bg)");
-                                                            break;
-                                                        }
-                                                        Err(e) => {
-                                                            debug!("Error reading cloudflared stderr (bg): {}", e);
-                                                            break;
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        info!("Background log reading task completed");
-                                    });
-                                    
-                                    return Ok(url);
-                                }
+                            if let Some(url) = provider.url(&line_str) {
+                                info!("Tunnel URL found in stderr: {}", url);
+
+                                // URLが見つかったらバックグラウンドで継続読み取り開始
+                                spawn_log_reader(stdout_reader, stderr_reader);
+                                return Ok(url);
                             }
                         }
                         Ok(None) => {
-                            warn!("cloudflared stderr stream ended");
+                            warn!("tunnel stderr stream ended");
                             break;
                         }
                         Err(e) => {
-                            error!("Error reading cloudflared stderr: {}", e);
+                            error!("Error reading tunnel stderr: {}", e);
                             return Err(TunnelError::StdioError);
                         }
                     }
@@ -570,11 +958,7 @@ pub async fn start_tunnel(app: &AppHandle, ws_port: u16) -> Result<TunnelInfo, T
             }
         }
         
-        if let Some(url) = found_url {
-            Ok(url)
-        } else {
-            Err(TunnelError::UrlNotFound)
-        }
+        Err(TunnelError::UrlNotFound)
     };
 
     // タイムアウト付きでURL抽出処理を実行
@@ -586,12 +970,19 @@ pub async fn start_tunnel(app: &AppHandle, ws_port: u16) -> Result<TunnelInfo, T
     {
         Ok(Ok(url)) => {
             // 成功: URLとプロセスハンドルを含むTunnelInfoを返す
-            info!("Cloudflare tunnel established with URL: {}", url);
+            info!("{:?} tunnel established with URL: {}", kind, url);
             let tunnel_info = TunnelInfo {
+                kind,
                 process: child_arc,
                 url: url.clone(),
                 should_stop: Arc::new(AtomicBool::new(false)),
-                process_manager: Arc::new(Mutex::new(ProcessManager::new(app.clone(), ws_port))),
+                process_manager: Arc::new(Mutex::new(ProcessManager::new(
+                    app.clone(),
+                    ws_port,
+                    kind,
+                ))),
+                health: Arc::new(Mutex::new(TunnelHealth::default())),
+                http_health_restarts: Arc::new(AtomicU32::new(0)),
             };
             
             // プロセスの健全性監視を開始
@@ -609,11 +1000,11 @@ pub async fn start_tunnel(app: &AppHandle, ws_port: u16) -> Result<TunnelInfo, T
             if let Some(mut child) = child_to_kill {
                 if let Err(kill_err) = child.kill().await {
                     error!(
-                        "Failed to kill cloudflared process after URL extraction error: {}",
+                        "Failed to kill tunnel process after URL extraction error: {}",
                         kill_err
                     );
                 } else {
-                    info!("Killed cloudflared process after URL extraction error");
+                    info!("Killed tunnel process after URL extraction error");
                 }
             }
             Err(e)
@@ -621,7 +1012,7 @@ pub async fn start_tunnel(app: &AppHandle, ws_port: u16) -> Result<TunnelInfo, T
         Err(_) => {
             // タイムアウト: プロセスは起動しているので終了処理
             error!(
-                "Timed out waiting for cloudflared URL (timeout: {}s)",
+                "Timed out waiting for tunnel URL (timeout: {}s)",
                 TUNNEL_START_TIMEOUT_SECS
             );
             
@@ -634,18 +1025,18 @@ pub async fn start_tunnel(app: &AppHandle, ws_port: u16) -> Result<TunnelInfo, T
                 // プロセスの状態を確認
                 match child.try_wait() {
                     Ok(Some(status)) => {
-                        error!("Cloudflared process already exited with status: {:?}", status);
+                        error!("Tunnel process already exited with status: {:?}", status);
                     }
                     Ok(None) => {
-                        info!("Cloudflared process is still running, sending kill signal...");
+                        info!("Tunnel process is still running, sending kill signal...");
                         if let Err(kill_err) = child.kill().await {
-                            error!("Failed to kill timed out cloudflared process: {}", kill_err);
+                            error!("Failed to kill timed out tunnel process: {}", kill_err);
                         } else {
-                            info!("Killed cloudflared process due to timeout");
+                            info!("Killed tunnel process due to timeout");
                         }
                     }
                     Err(e) => {
-                        error!("Error checking cloudflared process status before kill: {}", e);
+                        error!("Error checking tunnel process status before kill: {}", e);
                         // 強制終了を試行
                         let _ = child.kill().await;
                     }
@@ -659,13 +1050,17 @@ pub async fn start_tunnel(app: &AppHandle, ws_port: u16) -> Result<TunnelInfo, T
 /**
  * トンネルを停止する
  *
- * 作成されたCloudflare Quick Tunnelを停止します。
- * TunnelInfo内のプロセスハンドルを使用してcloudflaredプロセスを終了させます。
+ * 起動時のプロバイダを使用してトンネルプロセスを終了させます。
  *
  * # Arguments
  * * `tunnel_info` - 停止するトンネルの情報
  */
 pub async fn stop_tunnel(tunnel_info: &TunnelInfo) {
+    tunnel_info.kind.provider().stop(tunnel_info).await;
+}
+
+/// 健全性監視を停止し、TunnelInfo内のプロセスハンドルを使用してトンネルプロセスを終了させる
+async fn stop_process(tunnel_info: &TunnelInfo) {
     // 健全性監視を停止
     tunnel_info.should_stop.store(true, Ordering::Relaxed);
     info!("Health monitor stop signal sent");
@@ -675,17 +1070,17 @@ pub async fn stop_tunnel(tunnel_info: &TunnelInfo) {
 
     if let Some(mut child) = maybe_child {
         info!(
-            "Stopping cloudflared tunnel process for URL: {}",
-            tunnel_info.url
+            "Stopping {:?} tunnel process for URL: {}",
+            tunnel_info.kind, tunnel_info.url
         );
         
         // プロセスの状態を確認してから終了処理
         match child.try_wait() {
             Ok(Some(status)) => {
-                info!("Cloudflared process already exited with status: {:?}", status);
+                info!("Tunnel process already exited with status: {:?}", status);
             }
             Ok(None) => {
-                info!("Sending termination signal to cloudflared process...");
+                info!("Sending termination signal to tunnel process...");
                 match child.kill().await {
                     Ok(_) => {
                         info!("Termination signal sent successfully");
@@ -693,23 +1088,26 @@ pub async fn stop_tunnel(tunnel_info: &TunnelInfo) {
                         // プロセスの終了を待つ（タイムアウト付き）
                         match timeout(Duration::from_secs(5), child.wait()).await {
                             Ok(Ok(status)) => {
-                                info!("Cloudflared process exited gracefully with status: {:?}", status);
+                                info!("Tunnel process exited gracefully with status: {:?}", status);
                             }
                             Ok(Err(e)) => {
-                                error!("Error waiting for cloudflared process to exit: {}", e);
+                                error!("Error waiting for tunnel process to exit: {}", e);
                             }
                             Err(_) => {
-                                warn!("Timeout waiting for cloudflared process to exit, process may still be running");
+                                warn!("Timeout waiting for tunnel process to exit, process may still be running");
                             }
                         }
                     }
                     Err(e) => {
-                        error!("Failed to send termination signal to cloudflared process: {}", e);
+                        error!("Failed to send termination signal to tunnel process: {}", e);
                     }
                 }
             }
             Err(e) => {
-                error!("Error checking cloudflared process status before termination: {}", e);
+                error!(
+                    "Error checking tunnel process status before termination: {}",
+                    e
+                );
                 // エラーが発生しても終了を試行
                 let _ = child.kill().await;
             }
@@ -720,3 +1118,21 @@ pub async fn stop_tunnel(tunnel_info: &TunnelInfo) {
     
     info!("Tunnel stop process completed");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// HTTPチェックの連続失敗による異常判定のテスト
+    #[test]
+    fn test_tunnel_health_is_unhealthy() {
+        let mut health = TunnelHealth::default();
+        assert!(!health.is_unhealthy());
+
+        health.consecutive_failures = MAX_CONSECUTIVE_HTTP_FAILURES - 1;
+        assert!(!health.is_unhealthy());
+
+        health.consecutive_failures = MAX_CONSECUTIVE_HTTP_FAILURES;
+        assert!(health.is_unhealthy());
+    }
+}
@@ -0,0 +1,281 @@
+//! スーパーチャットのトランザクション検証モジュール
+//!
+//! 視聴者が申告した `tx_hash` のトランザクションをSui RPCで取得し、
+//! 配信者のウォレットへ申告した金額以上が送金されているかを確認します。
+//! 検証に失敗した場合・検証できなかった場合の扱いは設定で選択できます。
+
+use crate::coin_registry;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Sui RPCエンドポイントを指定する環境変数
+pub const SUI_RPC_URL_ENV: &str = "SUI_RPC_URL";
+
+/// 検証時のRPCリクエストのタイムアウト
+pub const VERIFICATION_RPC_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 申告額と着金額の比較で許容する誤差（浮動小数点の丸め分）
+const AMOUNT_TOLERANCE: f64 = 1e-9;
+
+/// JSON-RPCの不正なパラメータを表すエラーコード
+///
+/// Sui RPCは存在しない・形式が不正なダイジェストに対してこのコードを返します。
+pub const JSON_RPC_INVALID_PARAMS: i64 = -32602;
+
+/// ## 検証結果に応じたスーパーチャットの扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnverifiedAction {
+    /// そのままスーパーチャットとして配信する
+    Accept,
+    /// "未検証"フラグ付きのスーパーチャットとして配信する
+    #[default]
+    MarkUnverified,
+    /// 通常チャットに降格して配信する
+    Downgrade,
+}
+
+/// ## トランザクション検証の設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxVerificationConfig {
+    /// スーパーチャットのトランザクションを検証するかどうか
+    pub enabled: bool,
+    /// 送金先・金額が一致しなかった場合の扱い
+    pub on_mismatch: UnverifiedAction,
+    /// RPCのタイムアウトなどで検証できなかった場合の扱い
+    pub on_unavailable: UnverifiedAction,
+}
+
+impl Default for TxVerificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            on_mismatch: UnverifiedAction::Downgrade,
+            on_unavailable: UnverifiedAction::MarkUnverified,
+        }
+    }
+}
+
+impl TxVerificationConfig {
+    /// ## アプリケーション状態から現在の設定を取得する
+    ///
+    /// ### Arguments
+    /// - `app_state`: アプリケーション状態
+    ///
+    /// ### Returns
+    /// - `Self`: 現在の設定（ロックに失敗した場合はデフォルト値）
+    pub fn from_app_state(app_state: &AppState) -> Self {
+        app_state
+            .tx_verification
+            .lock()
+            .map(|config| *config)
+            .unwrap_or_default()
+    }
+
+    /// ## 検証結果に応じた扱いを取得する
+    ///
+    /// ### Arguments
+    /// - `result`: 検証結果
+    ///
+    /// ### Returns
+    /// - `UnverifiedAction`: スーパーチャットの扱い
+    pub fn action_for(&self, result: &TxVerificationResult) -> UnverifiedAction {
+        match result {
+            TxVerificationResult::Verified => UnverifiedAction::Accept,
+            TxVerificationResult::Mismatch(_) => self.on_mismatch,
+            TxVerificationResult::Unavailable(_) => self.on_unavailable,
+        }
+    }
+}
+
+/// ## トランザクションの検証結果
+#[derive(Debug, Clone, PartialEq)]
+pub enum TxVerificationResult {
+    /// 配信者のウォレットへ申告額以上が送金されている
+    Verified,
+    /// 送金先・金額・コインが申告と一致しない（理由を保持）
+    Mismatch(String),
+    /// RPCエラーなどで検証できなかった（理由を保持）
+    Unavailable(String),
+}
+
+/// ## 検証に使用するSui RPCエンドポイントを取得する
+///
+/// ### Returns
+/// - `String`: 環境変数 `SUI_RPC_URL` の値（未設定の場合はメインネットのフルノード）
+pub fn rpc_url() -> String {
+    std::env::var(SUI_RPC_URL_ENV)
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+        .unwrap_or_else(|| crate::sui_watcher::DEFAULT_SUI_RPC_URL.to_string())
+}
+
+/// ## Sui RPCのエラーコードが存在しないトランザクションを示すか判定する
+///
+/// ### Arguments
+/// - `code`: JSON-RPCのエラーコード
+///
+/// ### Returns
+/// - `bool`: 存在しない、または形式が不正なダイジェストの場合はtrue
+pub fn is_transaction_not_found(code: Option<i64>) -> bool {
+    code == Some(JSON_RPC_INVALID_PARAMS)
+}
+
+/// ## トランザクションの送金者・送金先・金額を申告と照合する
+///
+/// ### Arguments
+/// - `transaction`: `sui_getTransactionBlock` が返したトランザクション
+/// - `sender`: 申告された送金者のウォレットアドレス
+/// - `wallet`: 配信者のウォレットアドレス
+/// - `coin_type`: 申告されたコインの型 (例: "0x2::sui::SUI")
+/// - `claimed_amount`: 申告された送金額（通貨単位）
+/// - `decimals`: コインの小数点以下の桁数
+///
+/// ### Returns
+/// - `TxVerificationResult`: 照合結果
+pub fn check_transaction(
+    transaction: &Value,
+    sender: &str,
+    wallet: &str,
+    coin_type: &str,
+    claimed_amount: f64,
+    decimals: u32,
+) -> TxVerificationResult {
+    if transaction["effects"]["status"]["status"].as_str() != Some("success") {
+        return TxVerificationResult::Mismatch("トランザクションが成功していません".to_string());
+    }
+
+    // 他人の送金を自分のスーパーチャットとして申告できないよう送金者を照合する
+    let is_sender = transaction["transaction"]["data"]["sender"]
+        .as_str()
+        .is_some_and(|actual| actual.eq_ignore_ascii_case(sender));
+    if !is_sender {
+        return TxVerificationResult::Mismatch(
+            "トランザクションの送金者が申告されたウォレットと一致しません".to_string(),
+        );
+    }
+
+    let received: i128 = transaction["balanceChanges"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|change| {
+            change["owner"]["AddressOwner"]
+                .as_str()
+                .is_some_and(|owner| owner.eq_ignore_ascii_case(wallet))
+                && change["coinType"]
+                    .as_str()
+                    .is_some_and(|actual| coin_registry::is_same_coin_type(actual, coin_type))
+        })
+        .filter_map(|change| change["amount"].as_str()?.parse::<i128>().ok())
+        .sum();
+    if received <= 0 {
+        return TxVerificationResult::Mismatch(
+            "配信者のウォレットへの送金が含まれていません".to_string(),
+        );
+    }
+
+    let received_amount = received as f64 / 10f64.powi(decimals as i32);
+    if received_amount + AMOUNT_TOLERANCE < claimed_amount {
+        return TxVerificationResult::Mismatch(format!(
+            "申告額 {} に対して着金額が {} です",
+            claimed_amount, received_amount
+        ));
+    }
+    TxVerificationResult::Verified
+}
+
+/// ## トランザクションで配信者のウォレットが受け取ったコインの型を取得する
+///
+/// ### Arguments
+/// - `transaction`: `sui_getTransactionBlock` が返したトランザクション
+/// - `wallet`: 配信者のウォレットアドレス
+///
+/// ### Returns
+/// - `Vec<String>`: 残高が増えたコインの型
+pub fn received_coin_types(transaction: &Value, wallet: &str) -> Vec<String> {
+    let mut coin_types: Vec<String> = transaction["balanceChanges"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|change| {
+            change["owner"]["AddressOwner"]
+                .as_str()
+                .is_some_and(|owner| owner.eq_ignore_ascii_case(wallet))
+                && change["amount"]
+                    .as_str()
+                    .and_then(|amount| amount.parse::<i128>().ok())
+                    .is_some_and(|amount| amount > 0)
+        })
+        .filter_map(|change| change["coinType"].as_str().map(str::to_string))
+        .collect();
+    coin_types.sort();
+    coin_types.dedup();
+    coin_types
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_check_transaction_compares_amount_and_recipient() {
+        let wallet = "0xabc";
+        let sender = "0xdef";
+        let transaction = json!({
+            "transaction": { "data": { "sender": sender } },
+            "effects": { "status": { "status": "success" } },
+            "balanceChanges": [
+                { "owner": { "AddressOwner": "0xdef" }, "coinType": "0x2::sui::SUI", "amount": "-1500000000" },
+                { "owner": { "AddressOwner": wallet }, "coinType": "0x2::sui::SUI", "amount": "1500000000" }
+            ]
+        });
+
+        let check = |coin_type, amount| {
+            check_transaction(&transaction, sender, wallet, coin_type, amount, 9)
+        };
+        assert_eq!(check("0x2::sui::SUI", 1.5), TxVerificationResult::Verified);
+        assert!(matches!(
+            check("0x2::sui::SUI", 2.0),
+            TxVerificationResult::Mismatch(_)
+        ));
+        assert!(matches!(
+            check("0x2::usdc::USDC", 1.5),
+            TxVerificationResult::Mismatch(_)
+        ));
+
+        // 他人の送金を申告した場合は一致しない
+        assert!(matches!(
+            check_transaction(&transaction, "0x123", wallet, "0x2::sui::SUI", 1.5, 9),
+            TxVerificationResult::Mismatch(_)
+        ));
+    }
+
+    #[test]
+    fn test_received_coin_types_and_not_found() {
+        let wallet = "0xabc";
+        let change = |coin_type: &str, amount: &str| {
+            let owner = json!({ "AddressOwner": wallet });
+            json!({ "owner": owner, "coinType": coin_type, "amount": amount })
+        };
+        let transaction = json!({
+            "balanceChanges": [
+                change("0x2::sui::SUI", "100"),
+                change("0xaaa::usdc::USDC", "5"),
+                change("0x2::sui::SUI", "200"),
+                change("0xbbb::fake::FAKE", "-1")
+            ]
+        });
+        assert_eq!(
+            received_coin_types(&transaction, wallet),
+            vec!["0x2::sui::SUI".to_string(), "0xaaa::usdc::USDC".to_string()]
+        );
+
+        assert!(is_transaction_not_found(Some(JSON_RPC_INVALID_PARAMS)));
+        assert!(!is_transaction_not_found(Some(-32603)));
+        assert!(!is_transaction_not_found(None));
+    }
+}
@@ -0,0 +1,488 @@
+//! UPnPによる自動ポートフォワーディングモジュール
+//!
+//! Cloudflareトンネルを使わずにグローバルIPで直接接続できるよう、
+//! ルーター（UPnP IGDデバイス）にWebSocketポートの外部マッピングを登録します。
+//! SSDPでLAN内のIGDデバイスを探し、WANIPConnection（またはWANPPPConnection）サービスに
+//! SOAPで `AddPortMapping` を要求します。登録したマッピングはサーバー停止時に削除します。
+//! アプリが異常終了してもマッピングが残り続けないよう、有効期限付きで登録して定期的に更新します。
+
+use crate::state::AppState;
+use once_cell::sync::Lazy;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+/// SSDPのマルチキャストアドレス
+const SSDP_MULTICAST_ADDR: &str = "239.255.255.250:1900";
+
+/// IGDデバイスの応答を待つ時間
+const SSDP_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// デバイス記述の取得・SOAP要求のタイムアウト
+const UPNP_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// ポートマッピングに使用できるサービスタイプ（優先順）
+const WAN_SERVICE_TYPES: [&str; 3] = [
+    "urn:schemas-upnp-org:service:WANIPConnection:2",
+    "urn:schemas-upnp-org:service:WANIPConnection:1",
+    "urn:schemas-upnp-org:service:WANPPPConnection:1",
+];
+
+/// ルーターの管理画面に表示されるマッピングの説明
+const PORT_MAPPING_DESCRIPTION: &str = "SUIperCHAT WebSocket";
+
+/// ポートマッピングの有効期限（秒）
+const PORT_MAPPING_LEASE_SECS: u64 = 3600;
+
+/// ポートマッピングを更新する間隔（有効期限が切れる前に更新する）
+const PORT_MAPPING_RENEW_INTERVAL: Duration = Duration::from_secs(PORT_MAPPING_LEASE_SECS / 2);
+
+/// ## UPnP IGDデバイスのポートマッピング用サービス
+#[derive(Debug, Clone, PartialEq)]
+struct WanService {
+    /// サービスタイプ
+    service_type: String,
+    /// SOAP要求の送信先URL
+    control_url: String,
+}
+
+/// ## 登録済みのポートマッピング
+#[derive(Debug, Clone)]
+struct PortMapping {
+    /// マッピングを登録したサービス
+    service: WanService,
+    /// 外部ポート（内部ポートと同じ番号）
+    port: u16,
+    /// 転送先のこのPCのLAN内アドレス
+    local_ip: Ipv4Addr,
+    /// ルーターが報告した外部IPアドレス
+    external_ip: Ipv4Addr,
+}
+
+/// 現在登録しているポートマッピング（登録していない場合はNone）
+static ACTIVE_MAPPING: Lazy<Mutex<Option<PortMapping>>> = Lazy::new(|| Mutex::new(None));
+
+/// ## UPnPによるポート開放を試みる設定か判定する
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+///
+/// ### Returns
+/// - `bool`: UPnPを使用する設定の場合は `true`
+pub fn is_enabled(app_state: &AppState) -> bool {
+    app_state.use_upnp.lock().is_ok_and(|enabled| *enabled)
+}
+
+/// ## WebSocketポートの外部マッピングを登録する
+///
+/// LAN内のIGDデバイスを探し、外部ポート `internal_port` をこのPCの同じポートに転送するよう登録します。
+/// 既に別のポートのマッピングを登録している場合は、新しいマッピングの登録後に削除します。
+/// ルーターの外部IPアドレスがグローバルIPでない場合（CGNAT配下など）は外部から接続できないため、
+/// 登録したマッピングを削除してエラーを返します。
+///
+/// ### Arguments
+/// - `internal_port`: WebSocketサーバーのポート
+///
+/// ### Returns
+/// - `Result<(), String>`: 成功時は `Ok(())`、UPnP非対応のルーターなどで登録できない場合はエラーメッセージ
+pub async fn try_port_forward(internal_port: u16) -> Result<(), String> {
+    let (service, local_ip) = discover_wan_service().await?;
+    add_mapping(&service, internal_port, local_ip).await?;
+
+    let external_ip = match get_external_ip(&service).await {
+        Ok(external_ip) => external_ip,
+        Err(e) => {
+            delete_mapping(&PortMapping {
+                service,
+                port: internal_port,
+                local_ip,
+                external_ip: Ipv4Addr::UNSPECIFIED,
+            })
+            .await;
+            return Err(e);
+        }
+    };
+    println!(
+        "UPnPでポートマッピングを登録しました: {}:{} -> {}:{}",
+        external_ip, internal_port, local_ip, internal_port
+    );
+
+    let mapping = PortMapping {
+        service,
+        port: internal_port,
+        local_ip,
+        external_ip,
+    };
+    let previous = ACTIVE_MAPPING
+        .lock()
+        .map_err(|e| format!("ポートマッピング情報のロックに失敗しました: {}", e))?
+        .replace(mapping.clone());
+    if let Some(previous) = previous.filter(|previous| previous.port != internal_port) {
+        delete_mapping(&previous).await;
+    }
+    spawn_lease_renewal(mapping);
+    Ok(())
+}
+
+/// ## 登録したポートマッピングを削除する
+///
+/// マッピングを登録していない場合は何もしません。削除に失敗した場合は警告を出力します。
+pub async fn remove_port_forward() {
+    let mapping = ACTIVE_MAPPING
+        .lock()
+        .ok()
+        .and_then(|mut mapping| mapping.take());
+    if let Some(mapping) = mapping {
+        delete_mapping(&mapping).await;
+    }
+}
+
+/// ## 登録中のマッピングの外部ポートを取得する
+///
+/// ### Returns
+/// - `Option<u16>`: 外部ポート（登録していない場合はNone）
+pub fn active_external_port() -> Option<u16> {
+    ACTIVE_MAPPING
+        .lock()
+        .ok()
+        .and_then(|mapping| mapping.as_ref().map(|mapping| mapping.port))
+}
+
+/// ## UPnP経由で接続するWebSocket URLを生成する
+///
+/// IGDのポートマッピングはIPv4のみのため、STUNで取得した外部IPがIPv4でない場合や
+/// 取得できていない場合は、ルーターが報告した外部IPを使用します。
+///
+/// ### Arguments
+/// - `app_state`: アプリケーション状態
+///
+/// ### Returns
+/// - `Option<String>`: `ws://<external_ip>:<port>/ws`（マッピング未登録の場合はNone）
+pub fn upnp_ws_url(app_state: &AppState) -> Option<String> {
+    let (port, router_external_ip) = ACTIVE_MAPPING
+        .lock()
+        .ok()?
+        .as_ref()
+        .map(|mapping| (mapping.port, mapping.external_ip))?;
+    let external_ip = match app_state.external_ip.lock().ok().and_then(|ip| *ip) {
+        Some(IpAddr::V4(ip)) => ip,
+        _ => router_external_ip,
+    };
+    Some(format!("ws://{}:{}/ws", external_ip, port))
+}
+
+/// マッピングを有効期限付きで登録する（同じマッピングの再登録で有効期限を更新する）
+async fn add_mapping(service: &WanService, port: u16, local_ip: Ipv4Addr) -> Result<(), String> {
+    let port = port.to_string();
+    soap_request(
+        service,
+        "AddPortMapping",
+        &[
+            ("NewRemoteHost", ""),
+            ("NewExternalPort", &port),
+            ("NewProtocol", "TCP"),
+            ("NewInternalPort", &port),
+            ("NewInternalClient", &local_ip.to_string()),
+            ("NewEnabled", "1"),
+            ("NewPortMappingDescription", PORT_MAPPING_DESCRIPTION),
+            ("NewLeaseDuration", &PORT_MAPPING_LEASE_SECS.to_string()),
+        ],
+    )
+    .await
+    .map(|_| ())
+}
+
+/// ルーターの外部IPアドレスを取得する（グローバルIPでない場合はエラー）
+async fn get_external_ip(service: &WanService) -> Result<Ipv4Addr, String> {
+    let response = soap_request(service, "GetExternalIPAddress", &[]).await?;
+    let external_ip: Ipv4Addr = xml_element(&response, "NewExternalIPAddress")
+        .and_then(|ip| ip.parse().ok())
+        .ok_or_else(|| "ルーターの外部IPアドレスを取得できませんでした".to_string())?;
+    if super::geoip::is_local_ip(IpAddr::V4(external_ip)) {
+        return Err(format!(
+            "ルーターの外部IPアドレスがグローバルIPではありません: {}",
+            external_ip
+        ));
+    }
+    Ok(external_ip)
+}
+
+/// 有効期限が切れる前にマッピングを定期的に更新する（マッピングが削除・変更された時点で終了する）
+fn spawn_lease_renewal(mapping: PortMapping) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(PORT_MAPPING_RENEW_INTERVAL).await;
+            let is_active = ACTIVE_MAPPING.lock().is_ok_and(|active| {
+                active.as_ref().is_some_and(|active| {
+                    active.port == mapping.port && active.service == mapping.service
+                })
+            });
+            if !is_active {
+                break;
+            }
+            match add_mapping(&mapping.service, mapping.port, mapping.local_ip).await {
+                Ok(()) => println!("UPnPのポートマッピングを更新しました: {}", mapping.port),
+                Err(e) => eprintln!(
+                    "警告: UPnPのポートマッピングの更新に失敗しました ({}): {}",
+                    mapping.port, e
+                ),
+            }
+        }
+    });
+}
+
+/// マッピングを削除する
+async fn delete_mapping(mapping: &PortMapping) {
+    let port = mapping.port.to_string();
+    let result = soap_request(
+        &mapping.service,
+        "DeletePortMapping",
+        &[
+            ("NewRemoteHost", ""),
+            ("NewExternalPort", &port),
+            ("NewProtocol", "TCP"),
+        ],
+    )
+    .await;
+    match result {
+        Ok(_) => println!("UPnPのポートマッピングを削除しました: {}", mapping.port),
+        Err(e) => eprintln!(
+            "警告: UPnPのポートマッピングの削除に失敗しました ({}): {}",
+            mapping.port, e
+        ),
+    }
+}
+
+/// SSDPでIGDデバイスを探し、ポートマッピング用のサービスとこのPCのLAN内アドレスを取得する
+async fn discover_wan_service() -> Result<(WanService, Ipv4Addr), String> {
+    let socket = UdpSocket::bind("0.0.0.0:0")
+        .await
+        .map_err(|e| format!("SSDP用ソケットの作成に失敗しました: {}", e))?;
+    let request = format!(
+        "M-SEARCH * HTTP/1.1\r\n\
+         HOST: {}\r\n\
+         MAN: \"ssdp:discover\"\r\n\
+         MX: 2\r\n\
+         ST: urn:schemas-upnp-org:device:InternetGatewayDevice:1\r\n\r\n",
+        SSDP_MULTICAST_ADDR
+    );
+    socket
+        .send_to(request.as_bytes(), SSDP_MULTICAST_ADDR)
+        .await
+        .map_err(|e| format!("SSDP探索要求の送信に失敗しました: {}", e))?;
+
+    let client = reqwest::Client::builder()
+        .timeout(UPNP_REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("HTTPクライアントの作成に失敗しました: {}", e))?;
+    let deadline = tokio::time::Instant::now() + SSDP_DISCOVERY_TIMEOUT;
+    let mut buf = [0u8; 2048];
+    loop {
+        let (len, from) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf))
+            .await
+            .map_err(|_| "UPnP対応のルーターが見つかりませんでした".to_string())?
+            .map_err(|e| format!("SSDP応答の受信に失敗しました: {}", e))?;
+        let Some(location) = parse_location(&String::from_utf8_lossy(&buf[..len])) else {
+            continue;
+        };
+        // 他の機器が応答したデバイス記述へ誘導されないよう、応答元と同じアドレスの場合のみ使用する
+        if !is_location_on_host(&location, from.ip()) {
+            eprintln!(
+                "SSDP応答元と異なるアドレスのデバイス記述を無視します ({}): {}",
+                from, location
+            );
+            continue;
+        }
+
+        let description = match fetch_text(&client, &location).await {
+            Ok(description) => description,
+            Err(e) => {
+                eprintln!("IGDデバイス記述の取得に失敗しました ({}): {}", location, e);
+                continue;
+            }
+        };
+        if let Some(service) = find_wan_service(&description, &location) {
+            let local_ip = local_ipv4_towards(from)?;
+            return Ok((service, local_ip));
+        }
+    }
+}
+
+/// デバイス記述を取得する
+async fn fetch_text(client: &reqwest::Client, url: &str) -> Result<String, String> {
+    client
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?
+        .text()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// IGDデバイスとの通信に使用される、このPCのIPv4アドレスを取得する
+fn local_ipv4_towards(gateway: SocketAddr) -> Result<Ipv4Addr, String> {
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0")
+        .and_then(|socket| socket.connect(gateway).map(|_| socket))
+        .map_err(|e| format!("LAN内アドレスの取得に失敗しました: {}", e))?;
+    match socket.local_addr().map(|addr| addr.ip()) {
+        Ok(IpAddr::V4(ip)) => Ok(ip),
+        Ok(ip) => Err(format!("LAN内アドレスがIPv4ではありません: {}", ip)),
+        Err(e) => Err(format!("LAN内アドレスの取得に失敗しました: {}", e)),
+    }
+}
+
+/// IGDのサービスにSOAP要求を送信する
+async fn soap_request(
+    service: &WanService,
+    action: &str,
+    args: &[(&str, &str)],
+) -> Result<String, String> {
+    let arguments: String = args
+        .iter()
+        .map(|(name, value)| format!("<{0}>{1}</{0}>", name, value))
+        .collect();
+    let body = format!(
+        "<?xml version=\"1.0\"?>\
+         <s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+         s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">\
+         <s:Body><u:{action} xmlns:u=\"{service_type}\">{arguments}</u:{action}></s:Body>\
+         </s:Envelope>",
+        action = action,
+        service_type = service.service_type,
+        arguments = arguments
+    );
+
+    let response = reqwest::Client::new()
+        .post(&service.control_url)
+        .timeout(UPNP_REQUEST_TIMEOUT)
+        .header("Content-Type", "text/xml; charset=\"utf-8\"")
+        .header(
+            "SOAPAction",
+            format!("\"{}#{}\"", service.service_type, action),
+        )
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| format!("{} の送信に失敗しました: {}", action, e))?;
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        let reason = xml_element(&text, "errorDescription").unwrap_or_else(|| status.to_string());
+        return Err(format!("ルーターが {} を拒否しました: {}", action, reason));
+    }
+    Ok(text)
+}
+
+/// SSDP応答から `LOCATION` ヘッダーの値を取り出す
+fn parse_location(response: &str) -> Option<String> {
+    response.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("location")
+            .then(|| value.trim().to_string())
+    })
+}
+
+/// `LOCATION` がSSDPの応答元（ルーター）のアドレスを指しているか判定する
+fn is_location_on_host(location: &str, responder: IpAddr) -> bool {
+    let Ok(url) = url::Url::parse(location) else {
+        return false;
+    };
+    let host_ip = match url.host() {
+        Some(url::Host::Ipv4(ip)) => IpAddr::V4(ip),
+        Some(url::Host::Ipv6(ip)) => IpAddr::V6(ip),
+        _ => return false,
+    };
+    matches!(url.scheme(), "http" | "https") && host_ip == responder
+}
+
+/// デバイス記述からポートマッピング用のサービスを探す
+fn find_wan_service(description: &str, location: &str) -> Option<WanService> {
+    let base = xml_element(description, "URLBase").unwrap_or_else(|| location.to_string());
+    let base = url::Url::parse(&base).ok()?;
+
+    let services: Vec<(String, String)> = description
+        .split("<service>")
+        .skip(1)
+        .filter_map(|block| {
+            let block = block.split("</service>").next()?;
+            Some((
+                xml_element(block, "serviceType")?,
+                xml_element(block, "controlURL")?,
+            ))
+        })
+        .collect();
+    WAN_SERVICE_TYPES.iter().find_map(|wanted| {
+        let (service_type, control_url) = services
+            .iter()
+            .find(|(service_type, _)| service_type == wanted)?;
+        Some(WanService {
+            service_type: service_type.clone(),
+            control_url: base.join(control_url).ok()?.to_string(),
+        })
+    })
+}
+
+/// XMLから最初に現れる要素のテキストを取り出す（名前空間の接頭辞は考慮しない）
+fn xml_element(xml: &str, name: &str) -> Option<String> {
+    let start_tag = format!("<{}>", name);
+    let end_tag = format!("</{}>", name);
+    let start = xml.find(&start_tag)? + start_tag.len();
+    let end = start + xml[start..].find(&end_tag)?;
+    Some(xml[start..end].trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_wan_service() {
+        let response = "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=120\r\n\
+                        Location: http://192.168.1.1:5000/rootDesc.xml\r\n\r\n";
+        let location = parse_location(response).unwrap();
+        assert_eq!(location, "http://192.168.1.1:5000/rootDesc.xml");
+
+        let description = r#"<root><device><serviceList>
+            <service><serviceType>urn:schemas-upnp-org:service:Layer3Forwarding:1</serviceType>
+            <controlURL>/ctl/L3F</controlURL></service>
+            <service><serviceType>urn:schemas-upnp-org:service:WANIPConnection:1</serviceType>
+            <controlURL>/ctl/IPConn</controlURL></service>
+            </serviceList></device></root>"#;
+        assert_eq!(
+            find_wan_service(description, &location),
+            Some(WanService {
+                service_type: "urn:schemas-upnp-org:service:WANIPConnection:1".to_string(),
+                control_url: "http://192.168.1.1:5000/ctl/IPConn".to_string(),
+            })
+        );
+        assert!(find_wan_service("<root></root>", &location).is_none());
+    }
+
+    /// SSDP応答の `LOCATION` の検証のテスト
+    #[test]
+    fn test_is_location_on_host() {
+        let gateway: IpAddr = "192.168.1.1".parse().unwrap();
+        assert!(is_location_on_host(
+            "http://192.168.1.1:5000/rootDesc.xml",
+            gateway
+        ));
+        // 応答元と異なるアドレス・ホスト名・HTTP以外のスキームは使用しない
+        assert!(!is_location_on_host(
+            "http://192.168.1.50:5000/rootDesc.xml",
+            gateway
+        ));
+        assert!(!is_location_on_host(
+            "http://router.local/rootDesc.xml",
+            gateway
+        ));
+        assert!(!is_location_on_host(
+            "file://192.168.1.1/rootDesc.xml",
+            gateway
+        ));
+        assert!(!is_location_on_host("not a url", gateway));
+    }
+}
@@ -0,0 +1,95 @@
+//! 視聴者数の推移の記録モジュール
+//!
+//! 配信のどのタイミングで盛り上がったかを後から分析できるよう、
+//! 接続数が変化したときに視聴者数をサンプリングして時系列として保持します。
+//! メモリの肥大化を防ぐため、一定間隔ごとに1点へまとめ、直近の点のみを保持します。
+//! 長時間の配信でも推移を失わないよう、確定したサンプルはその都度DBへ保存します。
+
+use crate::db_models::ViewerCountPoint;
+use chrono::{DateTime, Duration, Utc};
+
+/// サンプリング間隔（秒）
+pub const SAMPLE_INTERVAL_SECS: i64 = 10;
+
+/// 保持するサンプルの最大数（1時間分を10秒間隔で保持）
+pub const MAX_VIEWER_COUNT_SAMPLES: usize = 360;
+
+/// ## 視聴者数のサンプルを記録する
+///
+/// 直前のサンプルからサンプリング間隔が経過していない場合は、ピークを取りこぼさないよう
+/// 直前のサンプルの視聴者数を期間中の最大値に更新します。
+/// 最大数を超えた場合は最も古いサンプルから破棄します。
+///
+/// ### Arguments
+/// - `history`: 視聴者数の時系列（古い順）
+/// - `at`: サンプリング時刻
+/// - `viewer_count`: 視聴者数
+///
+/// ### Returns
+/// - `Option<ViewerCountPoint>`: 新しいサンプルを追加した場合は、それ以上更新されない直前のサンプル
+pub fn record_sample(
+    history: &mut Vec<ViewerCountPoint>,
+    at: DateTime<Utc>,
+    viewer_count: usize,
+) -> Option<ViewerCountPoint> {
+    let completed = match history.last_mut() {
+        Some((sampled_at, count)) if at - *sampled_at < Duration::seconds(SAMPLE_INTERVAL_SECS) => {
+            *count = (*count).max(viewer_count);
+            return None;
+        }
+        Some(last) => Some(*last),
+        None => None,
+    };
+
+    history.push((at, viewer_count));
+    if history.len() > MAX_VIEWER_COUNT_SAMPLES {
+        let excess = history.len() - MAX_VIEWER_COUNT_SAMPLES;
+        history.drain(..excess);
+    }
+    completed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_sample() {
+        let start = Utc::now();
+        let mut history = Vec::new();
+        assert_eq!(record_sample(&mut history, start, 1), None);
+        assert_eq!(
+            record_sample(&mut history, start + Duration::seconds(3), 5),
+            None
+        );
+        assert_eq!(
+            record_sample(&mut history, start + Duration::seconds(6), 2),
+            None
+        );
+        // サンプリング間隔内の変化は最大値にまとめられる
+        assert_eq!(history, vec![(start, 5)]);
+        // 次のサンプルを追加すると直前のサンプルが確定する
+        assert_eq!(
+            record_sample(
+                &mut history,
+                start + Duration::seconds(SAMPLE_INTERVAL_SECS),
+                3
+            ),
+            Some((start, 5))
+        );
+
+        for i in 2..=MAX_VIEWER_COUNT_SAMPLES as i64 {
+            record_sample(
+                &mut history,
+                start + Duration::seconds(SAMPLE_INTERVAL_SECS * i),
+                3,
+            );
+        }
+        // 上限を超えると最も古いサンプルから破棄される
+        assert_eq!(history.len(), MAX_VIEWER_COUNT_SAMPLES);
+        assert_eq!(
+            history[0].0,
+            start + Duration::seconds(SAMPLE_INTERVAL_SECS)
+        );
+    }
+}
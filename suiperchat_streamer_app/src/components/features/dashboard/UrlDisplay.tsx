@@ -22,6 +22,7 @@ interface StreamerInfo {
 	obs_url: string;
 	wallet_address: string;
 	youtube_video_id?: string | null;
+	accepted_coins?: string[] | null;
 }
 
 /**
@@ -32,10 +33,16 @@ interface ServerStatusPayload {
 	obs_url?: string | null;
 	ws_url?: string | null;
 	cloudflare_http_url?: string | null;
+	local_ws_url?: string | null;
+	lan_ws_url?: string | null;
+	tunnel_ws_url?: string | null;
 	tunnel_status: string;
 	tunnel_error?: string | null;
 	global_ip_fetch_failed?: boolean;
 	cgnat_detected?: boolean;
+	startup_phase?: string;
+	failed_phase?: string | null;
+	startup_error?: string | null;
 }
 
 // --- 定数 ---
@@ -175,6 +182,7 @@ export default function UrlDisplay() {
 			obs_url: streamerInfoObsUrl,
 			wallet_address,
 			youtube_video_id,
+			accepted_coins,
 		} = streamerInfo;
 		try {
 			const encodedWalletAddress = encodeURIComponent(wallet_address);
@@ -207,6 +215,10 @@ export default function UrlDisplay() {
 			if (youtube_video_id) {
 				viewer_url += `&videoId=${encodeURIComponent(youtube_video_id)}`;
 			}
+			// 受け付けるコインを制限している場合は、視聴者が送金前に選べるコインを絞り込む
+			if (accepted_coins) {
+				viewer_url += `&coins=${encodeURIComponent(accepted_coins.join(","))}`;
+			}
 		} catch (encodeError) {
 			console.error("Failed to encode URL parameters:", encodeError);
 			setError("Failed to encode URL parameters.");
@@ -53,4 +53,6 @@ export interface SessionInfo {
 	started_at: string;
 	/** セッション終了日時（ISO 8601形式の文字列、終了していない場合はnull） */
 	ended_at: string | null;
+	/** 配信時間（秒、終了していない場合や時刻を解釈できない場合はnull） */
+	duration_seconds: number | null;
 }
@@ -313,6 +313,9 @@ function CommentItem({ comment }: CommentItemProps) {
 					</div>
 					<div className="font-medium text-white text-xs md:text-sm mt-0.5 leading-tight whitespace-pre-wrap break-words break-all w-full">
 						{comment.message}
+						{comment.is_edited && (
+							<span className="ml-1 text-white/70 text-xs">(edited)</span>
+						)}
 					</div>
 				</>
 			) : (
@@ -325,6 +328,11 @@ function CommentItem({ comment }: CommentItemProps) {
 						<span className="text-xs md:text-sm whitespace-pre-wrap break-words break-all overflow-hidden">
 							{comment.message}
 						</span>
+						{comment.is_edited && (
+							<span className="ml-1 text-muted-foreground text-xs">
+								(edited)
+							</span>
+						)}
 					</div>
 				</div>
 			)}
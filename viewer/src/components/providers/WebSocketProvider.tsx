@@ -49,6 +49,7 @@ const INITIAL_STATE: WebSocketState = {
 	hasMoreHistory: true,
 	historyError: null,
 	oldestMessageTimestamp: null,
+	oldestMessageSequence: null,
 };
 
 /**
@@ -110,9 +111,11 @@ export function WebSocketProvider({
 				// メッセージをタイムスタンプでソート
 				newMessages.sort((a, b) => a.timestamp - b.timestamp);
 
-				// 最も古いメッセージのタイムスタンプを更新
+				// 最も古いメッセージのタイムスタンプとシーケンス番号を更新
+				// (同一時刻のメッセージを取りこぼさないよう、次の取得位置の指定に両方を使用する)
 				const oldestMessage = newMessages[0];
 				const oldestTimestamp = oldestMessage ? oldestMessage.timestamp : null;
+				const oldestSequence = oldestMessage?.sequence ?? null;
 
 				return {
 					...prev,
@@ -121,6 +124,7 @@ export function WebSocketProvider({
 					hasMoreHistory: hasMore,
 					historyError: null,
 					oldestMessageTimestamp: oldestTimestamp,
+					oldestMessageSequence: oldestSequence,
 				};
 			});
 		},
@@ -226,6 +230,7 @@ export function WebSocketProvider({
 				...prev,
 				messages: [],
 				oldestMessageTimestamp: null,
+				oldestMessageSequence: null,
 				hasMoreHistory: true,
 			}));
 
@@ -363,6 +368,7 @@ export function WebSocketProvider({
 						type: MessageType.GET_HISTORY,
 						limit: Math.min(Math.max(1, limit), 200), // 1～200の範囲に制限
 						before_timestamp: state.oldestMessageTimestamp,
+						before_sequence: state.oldestMessageSequence,
 					};
 
 					console.debug("過去ログリクエスト送信:", request);
@@ -385,6 +391,7 @@ export function WebSocketProvider({
 		standardMessageHandler,
 		state.isLoadingHistory,
 		state.oldestMessageTimestamp,
+		state.oldestMessageSequence,
 	]);
 
 	// コンテキスト値
@@ -40,6 +40,7 @@ import {
 	DEFAULT_GAS_BUDGET,
 	PACKAGE_ID,
 	PAYMENT_CONFIG_ID,
+	type CoinInfo,
 	SUPPORTED_COINS,
 } from "@/lib/constants"; // 定数をインポート
 import { fromContractValue, toContractValue } from "@/lib/utils"; // ユーティリティをインポート
@@ -123,6 +124,12 @@ interface SuperchatFormProps {
 	 */
 	initial_recipient_address?: string;
 
+	/**
+	 * 配信者が受け付けるコイン
+	 * (受け付けないコインでの送金は配信されないため、選択肢から除外する)
+	 */
+	accepted_coins?: CoinInfo[];
+
 	/**
 	 * コンパクトモードを有効にするかどうか
 	 * (レイアウト調整用)
@@ -155,6 +162,7 @@ interface SuperchatFormProps {
 export function SuperchatForm({
 	on_send_success,
 	initial_recipient_address = "",
+	accepted_coins = SUPPORTED_COINS,
 	compact_mode = false,
 	integrated_ui = false,
 	on_tip_mode_change,
@@ -201,6 +209,17 @@ export function SuperchatForm({
 		}
 	}, [initial_recipient_address, form]);
 
+	// 選択中のコインを受け付けていない場合は、受け付けるコインに切り替える
+	useEffect(() => {
+		const selected = form.getValues("coinTypeArg");
+		if (
+			accepted_coins.length > 0 &&
+			!accepted_coins.some((coin) => coin.typeArg === selected)
+		) {
+			form.setValue("coinTypeArg", accepted_coins[0].typeArg);
+		}
+	}, [accepted_coins, form]);
+
 	// ユーザー名が変更された場合にフォームを更新
 	useEffect(() => {
 		if (username) {
@@ -301,13 +320,13 @@ export function SuperchatForm({
 
 			// 選択されたコインタイプの取得
 			const selectedCoinType = values.coinTypeArg;
-			const selectedCoin = SUPPORTED_COINS.find(
+			const selectedCoin = accepted_coins.find(
 				(c) => c.typeArg === selectedCoinType,
 			);
 
 			if (!selectedCoin) {
 				toast.error("Invalid Coin Type", {
-					description: "Please select a valid coin type.",
+					description: "Please select a coin accepted by the streamer.",
 				});
 				return;
 			}
@@ -688,7 +707,7 @@ export function SuperchatForm({
 													<SelectValue placeholder="Coin" />
 												</SelectTrigger>
 												<SelectContent>
-													{SUPPORTED_COINS.map((coin) => (
+													{accepted_coins.map((coin) => (
 														<SelectItem key={coin.typeArg} value={coin.typeArg}>
 															{coin.symbol}
 														</SelectItem>
@@ -11,8 +11,9 @@
  */
 "use client";
 
+import { getAcceptedCoins } from "@/lib/utils";
 import { useSearchParams } from "next/navigation";
-import { Suspense, useEffect } from "react";
+import { Suspense, useEffect, useMemo } from "react";
 import { useState } from "react";
 import { SuperchatForm } from "./superchat-form";
 
@@ -92,18 +93,27 @@ export function Superchat({
  */
 function UrlParamReader({
 	onStreamerAddressChange,
+	onAcceptedCoinsChange,
 }: {
 	onStreamerAddressChange: (address: string) => void;
+	onAcceptedCoinsChange: (coins: string | null) => void;
 }) {
 	// URLパラメータから配信者のウォレットアドレスを取得
 	const search_params = useSearchParams();
 	const streamer_address = search_params.get("streamerAddress") || "";
+	// URLパラメータから配信者が受け付けるコインを取得（制限しない場合はnull）
+	const accepted_coins = search_params.get("coins");
 
 	// アドレスが変更されたら親コンポーネントに通知
 	useEffect(() => {
 		onStreamerAddressChange(streamer_address);
 	}, [streamer_address, onStreamerAddressChange]);
 
+	// 受け付けるコインが変更されたら親コンポーネントに通知
+	useEffect(() => {
+		onAcceptedCoinsChange(accepted_coins);
+	}, [accepted_coins, onAcceptedCoinsChange]);
+
 	return null; // このコンポーネントはUIをレンダリングしない
 }
 
@@ -122,6 +132,13 @@ function SuperchatContent({
 	const [has_tip, set_has_tip] = useState<boolean>(false);
 	// URLから取得した配信者アドレス
 	const [streamer_address, set_streamer_address] = useState<string>("");
+	// URLから取得した受け付けるコイン（制限しない場合はnull）
+	const [accepted_coins, set_accepted_coins] = useState<string | null>(null);
+	// 送金に選択できるコイン
+	const selectable_coins = useMemo(
+		() => getAcceptedCoins(accepted_coins),
+		[accepted_coins],
+	);
 
 	// Tipモード変更を親コンポーネントに通知
 	useEffect(() => {
@@ -145,7 +162,10 @@ function SuperchatContent({
 		>
 			{/* URLパラメータを読み取るコンポーネント */}
 			<Suspense fallback={null}>
-				<UrlParamReader onStreamerAddressChange={set_streamer_address} />
+				<UrlParamReader
+					onStreamerAddressChange={set_streamer_address}
+					onAcceptedCoinsChange={set_accepted_coins}
+				/>
 			</Suspense>
 
 			<SuperchatForm
@@ -153,6 +173,7 @@ function SuperchatContent({
 				initial_recipient_address={
 					initial_recipient_address || streamer_address
 				}
+				accepted_coins={selectable_coins}
 				compact_mode={true}
 				integrated_ui={true}
 				on_tip_mode_change={handle_tip_mode_change}
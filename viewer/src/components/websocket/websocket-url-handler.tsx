@@ -48,13 +48,18 @@ export default function WebSocketUrlHandler() {
 				console.log(`Streamer Wallet Address: ${streamer_address}`);
 				console.log(`Connection status: ${state.status}`);
 
-				let final_url = ws_url;
-				if (!ws_url.endsWith("/ws")) {
-					final_url = ws_url.endsWith("/") ? `${ws_url}ws` : `${ws_url}/ws`;
-					console.log(`URLにパスが含まれていないため、/wsを追加: ${final_url}`);
+				// アクセストークン (?token=...) などのクエリ文字列を保持したままパスを補完する
+				const parsed_url = new URL(ws_url);
+				if (!parsed_url.pathname.endsWith("/ws")) {
+					parsed_url.pathname = parsed_url.pathname.endsWith("/")
+						? `${parsed_url.pathname}ws`
+						: `${parsed_url.pathname}/ws`;
+					console.log(
+						`URLにパスが含まれていないため、/wsを追加: ${parsed_url.toString()}`,
+					);
 				}
 
-				actions.connect(final_url);
+				actions.connect(parsed_url.toString());
 			} catch (error) {
 				console.error("Failed to decode WebSocket URL:", error);
 				console.error("Original encoded URL:", ws_url_encoded);
@@ -10,9 +10,11 @@
 import {
 	type ChatMessage,
 	ConnectionStatus,
+	type MessageEditedMessage,
 	MessageType,
 	type SuperchatData,
 	type SuperchatMessage,
+	type SystemMessage,
 	type WebSocketState,
 } from "@/lib/types/websocket";
 import {
@@ -21,6 +23,7 @@ import {
 	type SetStateAction,
 	useCallback,
 } from "react";
+import { toast } from "sonner";
 
 /**
  * WebSocketメッセージハンドラーオプション
@@ -64,6 +67,7 @@ export function useWebSocketMessageHandler({
 								display_name: data.display_name,
 								message: data.message,
 								timestamp: data.timestamp,
+								is_edited: data.is_edited,
 							};
 
 							// メッセージリストに追加
@@ -89,6 +93,7 @@ export function useWebSocketMessageHandler({
 								display_name: data.display_name,
 								message: data.message,
 								timestamp: data.timestamp,
+								is_edited: data.is_edited,
 								superchat: {
 									amount: data.superchat.amount,
 									coin: data.superchat.coin,
@@ -113,6 +118,33 @@ export function useWebSocketMessageHandler({
 						}
 						break;
 
+					case MessageType.MESSAGE_EDITED:
+						{
+							// 配信者が編集したメッセージの内容を差し替える
+							const edited = data as MessageEditedMessage;
+							setState((prev) => ({
+								...prev,
+								messages: prev.messages.map((msg) =>
+									msg.id === edited.id
+										? {
+												...msg,
+												message: edited.message,
+												is_edited: edited.is_edited,
+											}
+										: msg,
+								),
+							}));
+						}
+						break;
+
+					case MessageType.SYSTEM:
+						{
+							// 配信者からのお知らせはチャットと区別して通知する
+							const system = data as SystemMessage;
+							toast.info(system.message);
+						}
+						break;
+
 					case MessageType.PONG:
 						// PONGメッセージ受信時の処理
 						console.debug("PONG received");
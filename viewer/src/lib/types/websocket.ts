@@ -25,6 +25,10 @@ export enum MessageType {
 	GET_HISTORY = "GET_HISTORY",
 	/** 過去のメッセージデータ */
 	HISTORY_DATA = "HISTORY_DATA",
+	/** 配信者によるメッセージの編集通知 */
+	MESSAGE_EDITED = "message_edited",
+	/** 配信者からのシステム通知 */
+	SYSTEM = "system",
 }
 
 /**
@@ -53,6 +57,8 @@ export interface BaseMessage {
 	id: string;
 	/** メッセージのタイムスタンプ */
 	timestamp: number;
+	/** 受信順のシーケンス番号（過去ログから取得したメッセージのみ） */
+	sequence?: number;
 }
 
 /**
@@ -66,6 +72,8 @@ export interface ChatMessage extends BaseMessage {
 	display_name: string;
 	/** メッセージ内容 */
 	message: string;
+	/** 送信後に配信者が編集したかどうか */
+	is_edited?: boolean;
 }
 
 /**
@@ -79,10 +87,40 @@ export interface SuperchatMessage extends BaseMessage {
 	display_name: string;
 	/** メッセージ内容 */
 	message: string;
+	/** 送信後に配信者が編集したかどうか */
+	is_edited?: boolean;
 	/** スーパーチャットデータ */
 	superchat: SuperchatData;
 }
 
+/**
+ * メッセージ編集通知インターフェース
+ * 配信者が編集したメッセージの編集後の内容
+ */
+export interface MessageEditedMessage {
+	/** メッセージの種類（編集通知） */
+	type: MessageType.MESSAGE_EDITED;
+	/** 編集したメッセージのID */
+	id: string;
+	/** 編集後のメッセージ内容 */
+	message: string;
+	/** 編集済みかどうか */
+	is_edited: boolean;
+}
+
+/**
+ * システム通知インターフェース
+ * 配信者から視聴者へのお知らせ（例: 「まもなく配信終了します」）の構造
+ */
+export interface SystemMessage {
+	/** メッセージの種類（システム通知） */
+	type: MessageType.SYSTEM;
+	/** 通知の本文 */
+	message: string;
+	/** タイムスタンプ（ISO 8601形式） */
+	timestamp: string;
+}
+
 /**
  * エラーメッセージインターフェース
  * エラーに関する情報を含むメッセージの構造
@@ -148,6 +186,8 @@ export interface WebSocketState {
 	hasMoreHistory: boolean;
 	/** 最も古いメッセージのタイムスタンプ */
 	oldestMessageTimestamp: number | null;
+	/** 最も古いメッセージのシーケンス番号（過去ログから取得したメッセージの場合のみ） */
+	oldestMessageSequence: number | null;
 	/** 過去ログ取得時のエラー */
 	historyError: string | null;
 }
@@ -192,6 +232,8 @@ export interface GetHistoryPayload {
 	limit?: number;
 	/** このタイムスタンプより前のメッセージを取得 */
 	before_timestamp?: number;
+	/** before_timestampと同一時刻のメッセージのうち、このシーケンス番号より前のものも取得 */
+	before_sequence?: number;
 }
 
 /**
@@ -6,7 +6,7 @@
  * @module utils
  */
 
-import { SUPPORTED_COINS } from "./constants";
+import { type CoinInfo, SUPPORTED_COINS } from "./constants";
 
 type ClassValue =
 	| string
@@ -95,6 +95,24 @@ export function sleep(ms: number): Promise<void> {
 	return new Promise((resolve) => setTimeout(resolve, ms));
 }
 
+/**
+ * 配信者が受け付けるコインに絞り込んだ対応コインの一覧を取得する
+ *
+ * @param {string | null} coins - 受け付けるコインのシンボルのカンマ区切り（URLパラメータ `coins`）
+ * @returns {CoinInfo[]} 受け付けるコインの一覧（制限がない場合はすべての対応コイン）
+ */
+export function getAcceptedCoins(coins: string | null): CoinInfo[] {
+	if (coins === null) return SUPPORTED_COINS;
+
+	const symbols = coins
+		.split(",")
+		.map((symbol) => symbol.trim().toUpperCase())
+		.filter((symbol) => symbol !== "");
+	return SUPPORTED_COINS.filter((coin) =>
+		symbols.includes(coin.symbol.toUpperCase()),
+	);
+}
+
 /**
  * 通貨の数値をコントラクトに渡すためのbigint型に変換する
  *